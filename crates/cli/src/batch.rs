@@ -0,0 +1,213 @@
+//! Batch fetch: snapshot many URLs into per-URL files plus a manifest.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Instant;
+
+use browsy_core::fetch::{fnv1a, RetryPolicy, Session, SessionConfig};
+use serde::{Deserialize, Serialize};
+
+use crate::format_dom;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// One manifest row, keyed by URL in `manifest.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Output file name relative to the output directory (absent on failure).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// `"ok"` or `"error"`.
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub els: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_type: Option<String>,
+    pub duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+pub struct BatchOptions {
+    pub input: PathBuf,
+    pub out_dir: PathBuf,
+    pub json: bool,
+    pub concurrency: usize,
+    pub resume: bool,
+    pub viewport: (f32, f32),
+    pub fetch_css: bool,
+    pub allow_private_network: bool,
+    pub retry: RetryPolicy,
+}
+
+impl BatchOptions {
+    fn session_config(&self) -> SessionConfig {
+        SessionConfig {
+            viewport_width: self.viewport.0,
+            viewport_height: self.viewport.1,
+            fetch_css: self.fetch_css,
+            allow_private_network: self.allow_private_network,
+            retry: self.retry.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Read URLs one per line, skipping blanks, `#` comments and duplicates.
+fn read_urls(path: &Path) -> Result<Vec<String>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let mut seen = std::collections::HashSet::new();
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter(|l| seen.insert(l.to_string()))
+        .map(str::to_string)
+        .collect())
+}
+
+fn load_manifest(path: &Path) -> Result<BTreeMap<String, ManifestEntry>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => serde_json::from_str(&text).map_err(|e| format!("{}: {e}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(format!("{}: {e}", path.display())),
+    }
+}
+
+/// Write the manifest to a partial file and rename it into place, so a run
+/// interrupted mid-write leaves the previous manifest for `--resume`.
+fn write_manifest(path: &Path, manifest: &BTreeMap<String, ManifestEntry>) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(manifest).map_err(|e| e.to_string())?;
+    let partial = path.with_extension("json.partial");
+    std::fs::write(&partial, json).map_err(|e| format!("{}: {e}", partial.display()))?;
+    std::fs::rename(&partial, path).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Output file name for a URL: sanitized host plus a hash of the full URL.
+fn output_file_name(url: &str, json: bool) -> String {
+    let host = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest)
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default()
+        .rsplit('@')
+        .next()
+        .unwrap_or_default()
+        .split(':')
+        .next()
+        .unwrap_or_default();
+    let host: String = host
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect();
+    let host = if host.is_empty() { "page" } else { host.as_str() };
+    let ext = if json { "json" } else { "txt" };
+    format!("{host}-{:016x}.{ext}", fnv1a(url))
+}
+
+fn snapshot(session: &mut Session, url: &str, opts: &BatchOptions) -> ManifestEntry {
+    let started = Instant::now();
+    let result = session.goto(url).map_err(|e| e.to_string()).and_then(|dom| {
+        let file = output_file_name(url, opts.json);
+        let text = format_dom(&dom, opts.json, false, None);
+        std::fs::write(opts.out_dir.join(&file), text).map_err(|e| format!("{file}: {e}"))?;
+        Ok((file, dom))
+    });
+    let duration_ms = started.elapsed().as_millis() as u64;
+    match result {
+        Ok((file, dom)) => ManifestEntry {
+            file: Some(file),
+            status: "ok".to_string(),
+            els: Some(dom.els.len()),
+            page_type: Some(format!("{:?}", dom.page_type)),
+            duration_ms,
+            error: None,
+        },
+        Err(e) => ManifestEntry {
+            file: None,
+            status: "error".to_string(),
+            els: None,
+            page_type: None,
+            duration_ms,
+            error: Some(e),
+        },
+    }
+}
+
+/// Run a batch. Per-URL failures are recorded in the manifest; only setup
+/// errors (unreadable input, unwritable output directory) are returned.
+pub fn run(opts: BatchOptions) -> Result<(), String> {
+    let urls = read_urls(&opts.input)?;
+    std::fs::create_dir_all(&opts.out_dir).map_err(|e| format!("{}: {e}", opts.out_dir.display()))?;
+    let manifest_path = opts.out_dir.join(MANIFEST_FILE);
+    let mut manifest = if opts.resume {
+        load_manifest(&manifest_path)?
+    } else {
+        BTreeMap::new()
+    };
+
+    let pending: Vec<String> = urls.into_iter().filter(|u| !manifest.contains_key(u)).collect();
+    let total = pending.len();
+    if opts.resume {
+        eprintln!("resuming: {} already in manifest, {total} to fetch", manifest.len());
+    }
+
+    let opts = Arc::new(opts);
+    let pending = Arc::new(pending);
+    let next = Arc::new(AtomicUsize::new(0));
+    let (tx, rx) = mpsc::channel::<(String, ManifestEntry)>();
+
+    let workers: Vec<_> = (0..opts.concurrency.clamp(1, total.max(1)))
+        .map(|_| {
+            let (opts, pending, next, tx) = (opts.clone(), pending.clone(), next.clone(), tx.clone());
+            std::thread::spawn(move || {
+                let mut session = match Session::with_config(opts.session_config()) {
+                    Ok(s) => s,
+                    Err(e) => {
+                        eprintln!("Error: {e}");
+                        return;
+                    }
+                };
+                loop {
+                    let i = next.fetch_add(1, Ordering::SeqCst);
+                    let Some(url) = pending.get(i) else { break };
+                    let entry = snapshot(&mut session, url, &opts);
+                    if tx.send((url.clone(), entry)).is_err() {
+                        break;
+                    }
+                }
+            })
+        })
+        .collect();
+    drop(tx);
+
+    // Record results as they arrive so an interrupted run can be resumed.
+    let (mut ok, mut failed) = (0, 0);
+    for (done, (url, entry)) in rx.into_iter().enumerate() {
+        match entry.error {
+            Some(ref e) => {
+                failed += 1;
+                eprintln!("[{}/{total}] error {url}: {e}", done + 1);
+            }
+            None => {
+                ok += 1;
+                eprintln!("[{}/{total}] ok {url} ({} ms)", done + 1, entry.duration_ms);
+            }
+        }
+        manifest.insert(url, entry);
+        write_manifest(&manifest_path, &manifest)?;
+    }
+    for worker in workers {
+        let _ = worker.join();
+    }
+    write_manifest(&manifest_path, &manifest)?;
+
+    eprintln!(
+        "done: {ok} ok, {failed} failed, manifest at {}",
+        manifest_path.display()
+    );
+    Ok(())
+}
@@ -3,11 +3,29 @@
 use browsy_core::{fetch, output};
 use clap::{Parser, Subcommand};
 
+mod batch;
+mod repl;
+mod run;
+mod watch;
+
+/// Help for every command's `--retries`, naming what `RetryPolicy::default()`
+/// retries on.
+const RETRIES_HELP: &str = "Retry transient failures (connection errors, timeouts, 429, 5xx, and \
+    403s or challenge pages) up to N times with exponential backoff";
+
 #[derive(Parser)]
 #[command(name = "browsy", about = "Zero-render browser engine for AI agents")]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Log filter for stderr, e.g. "info" or "browsy_core=debug"
+    #[arg(long, global = true, default_value = "warn")]
+    log_level: String,
+
+    /// Log line format
+    #[arg(long, global = true, default_value = "text", value_parser = ["text", "json"])]
+    log_format: String,
 }
 
 #[derive(Subcommand)]
@@ -25,6 +43,11 @@ enum Commands {
         #[arg(long)]
         json_meta: bool,
 
+        /// Output an ASCII map of the page layout instead, with a legend of
+        /// the element IDs on it
+        #[arg(long, conflicts_with_all = ["json", "json_meta", "count"])]
+        map: bool,
+
         /// Viewport size as WxH (default: 1920x1080)
         #[arg(long, default_value = "1920x1080")]
         viewport: String,
@@ -37,13 +60,55 @@ enum Commands {
         #[arg(long)]
         visible_only: bool,
 
+        /// Why an element has to be hidden for --visible-only to leave it out:
+        /// display, visibility, aria_hidden, hidden_attr or offscreen_sr_only
+        /// (default: aria_hidden)
+        #[arg(long, value_name = "REASONS", value_delimiter = ',', requires = "visible_only")]
+        drop_hidden: Vec<output::HiddenReason>,
+
         /// Only include above-fold elements
         #[arg(long)]
         above_fold: bool,
 
+        /// Only include the main content, leaving out the header, navigation,
+        /// sidebars and footer
+        #[arg(long)]
+        main_content: bool,
+
         /// Allow fetching private/LAN addresses (e.g., localhost)
         #[arg(long)]
         allow_private_network: bool,
+
+        /// Click "accept" or "reject" on cookie consent banners automatically
+        #[arg(long, value_name = "CHOICE")]
+        auto_consent: Option<fetch::ConsentChoice>,
+
+        /// Record every HTTP exchange into this fixture directory
+        #[arg(long, value_name = "DIR", conflicts_with = "replay")]
+        record: Option<String>,
+
+        /// Serve every request from a fixture directory made with --record
+        #[arg(long, value_name = "DIR")]
+        replay: Option<String>,
+
+        #[arg(long, value_name = "N", help = RETRIES_HELP)]
+        retries: Option<usize>,
+
+        /// Include each element's source line and column in the output
+        #[arg(long)]
+        src_pos: bool,
+
+        /// Cut element text longer than N characters short in the compact
+        /// output (0 shows all of it)
+        #[arg(long, value_name = "N", default_value_t = output::DEFAULT_TEXT_LIMIT)]
+        text_limit: usize,
+
+        /// Per-site overrides (user agent, CSS, viewport, headers) from a browsy.toml file
+        #[arg(long, value_name = "PATH")]
+        site_config: Option<std::path::PathBuf>,
+
+        #[command(flatten)]
+        filter: ElementFilter,
     },
     /// Parse a local HTML string and output the Spatial DOM
     Parse {
@@ -57,6 +122,226 @@ enum Commands {
         /// Viewport size as WxH (default: 1920x1080)
         #[arg(long, default_value = "1920x1080")]
         viewport: String,
+
+        /// Include each element's source line and column in the output
+        #[arg(long)]
+        src_pos: bool,
+
+        /// Print the HTML around an element's start tag instead of the DOM
+        #[arg(long, value_name = "ID")]
+        explain: Option<u32>,
+
+        /// Cut element text longer than N characters short in the compact
+        /// output (0 shows all of it)
+        #[arg(long, value_name = "N", default_value_t = output::DEFAULT_TEXT_LIMIT)]
+        text_limit: usize,
+
+        #[command(flatten)]
+        filter: ElementFilter,
+    },
+    /// Compare two saved Spatial DOM JSON files (exit 1 if they differ)
+    Diff {
+        /// The older Spatial DOM JSON file
+        old: String,
+
+        /// The newer Spatial DOM JSON file
+        new: String,
+
+        /// Output the delta as JSON instead of compact format
+        #[arg(long)]
+        json: bool,
+
+        /// Match elements without comparing bounds (ignore layout shifts)
+        #[arg(long)]
+        ignore_bounds: bool,
+    },
+    /// Poll a URL and print timestamped deltas when it changes
+    Watch {
+        /// The URL to watch
+        url: String,
+
+        /// Seconds between polls
+        #[arg(long, default_value = "300")]
+        interval: u64,
+
+        /// Shell command to run on change, with the compact delta on stdin
+        #[arg(long)]
+        notify_cmd: Option<String>,
+
+        /// Stop after this many polls
+        #[arg(long)]
+        max_iterations: Option<u64>,
+
+        /// Directory for the last snapshot, so restarts resume from it
+        #[arg(long)]
+        state_dir: Option<String>,
+
+        /// Match elements without comparing bounds (ignore layout shifts)
+        #[arg(long)]
+        ignore_bounds: bool,
+
+        /// Viewport size as WxH (default: 1920x1080)
+        #[arg(long, default_value = "1920x1080")]
+        viewport: String,
+
+        /// Skip fetching external CSS stylesheets
+        #[arg(long)]
+        no_css: bool,
+
+        /// Allow fetching private/LAN addresses (e.g., localhost)
+        #[arg(long)]
+        allow_private_network: bool,
+    },
+    /// Fetch many URLs into per-URL output files plus a manifest.json
+    Batch {
+        /// File with one URL per line (blank lines and # comments are skipped)
+        #[arg(long)]
+        input: String,
+
+        /// Directory for output files and manifest.json
+        #[arg(long)]
+        out_dir: String,
+
+        /// Output format: compact or json
+        #[arg(long, default_value = "compact", value_parser = ["compact", "json"])]
+        format: String,
+
+        /// Number of worker threads, each with its own session
+        #[arg(long, default_value = "4")]
+        concurrency: usize,
+
+        /// Skip URLs already recorded in an existing manifest.json
+        #[arg(long)]
+        resume: bool,
+
+        /// Viewport size as WxH (default: 1920x1080)
+        #[arg(long, default_value = "1920x1080")]
+        viewport: String,
+
+        /// Skip fetching external CSS stylesheets
+        #[arg(long)]
+        no_css: bool,
+
+        /// Allow fetching private/LAN addresses (e.g., localhost)
+        #[arg(long)]
+        allow_private_network: bool,
+
+        #[arg(long, value_name = "N", help = RETRIES_HELP)]
+        retries: Option<usize>,
+    },
+    /// Crawl a site breadth first, writing one JSON line per page visited
+    Crawl {
+        /// URL to start from
+        url: String,
+
+        /// Most pages to visit
+        #[arg(long, default_value_t = fetch::CrawlOpts::default().max_pages)]
+        max_pages: usize,
+
+        /// Most links to follow from the start page
+        #[arg(long, default_value_t = fetch::CrawlOpts::default().max_depth)]
+        max_depth: usize,
+
+        /// Follow links to other hosts too
+        #[arg(long)]
+        all_hosts: bool,
+
+        /// Only follow links whose URL contains this (repeatable)
+        #[arg(long = "include", value_name = "PATTERN")]
+        include_patterns: Vec<String>,
+
+        /// Never follow links whose URL contains this (repeatable)
+        #[arg(long = "exclude", value_name = "PATTERN")]
+        exclude_patterns: Vec<String>,
+
+        /// Visit pages robots.txt disallows
+        #[arg(long)]
+        ignore_robots: bool,
+
+        /// Wait at least this long between requests to one host
+        #[arg(long, value_name = "MS", default_value = "0")]
+        delay_ms: u64,
+
+        /// Skip fetching external CSS stylesheets
+        #[arg(long)]
+        no_css: bool,
+
+        /// Allow fetching private/LAN addresses (e.g., localhost)
+        #[arg(long)]
+        allow_private_network: bool,
+
+        #[arg(long, value_name = "N", help = RETRIES_HELP)]
+        retries: Option<usize>,
+    },
+    /// Drive a browsing session interactively, one command per line
+    Repl {
+        /// URL to open before reading commands
+        #[arg(long)]
+        url: Option<String>,
+
+        /// Replay newline-separated commands from a file instead of stdin
+        #[arg(long)]
+        script: Option<String>,
+
+        /// Viewport size as WxH (default: 1920x1080)
+        #[arg(long, default_value = "1920x1080")]
+        viewport: String,
+
+        /// Skip fetching external CSS stylesheets
+        #[arg(long)]
+        no_css: bool,
+
+        /// Allow fetching private/LAN addresses (e.g., localhost)
+        #[arg(long)]
+        allow_private_network: bool,
+
+        /// Click "accept" or "reject" on cookie consent banners automatically
+        #[arg(long, value_name = "CHOICE")]
+        auto_consent: Option<fetch::ConsentChoice>,
+
+        /// Record every HTTP exchange into this fixture directory
+        #[arg(long, value_name = "DIR", conflicts_with = "replay")]
+        record: Option<String>,
+
+        /// Serve every request from a fixture directory made with --record
+        #[arg(long, value_name = "DIR")]
+        replay: Option<String>,
+
+        #[arg(long, value_name = "N", help = RETRIES_HELP)]
+        retries: Option<usize>,
+    },
+    /// Run a YAML or JSON flow file of steps (goto, click, type, select,
+    /// check, assert, extract, snapshot); exit 1 if a step fails
+    Run {
+        /// The flow file; `.yaml` and `.yml` are read as YAML, anything else as JSON
+        flow: std::path::PathBuf,
+
+        /// Save a snapshot of the page into this directory when a step fails
+        #[arg(long, value_name = "DIR")]
+        snapshot_on_failure: Option<std::path::PathBuf>,
+
+        /// Viewport size as WxH (default: 1920x1080)
+        #[arg(long, default_value = "1920x1080")]
+        viewport: String,
+
+        /// Skip fetching external CSS stylesheets
+        #[arg(long)]
+        no_css: bool,
+
+        /// Allow fetching private/LAN addresses (e.g., localhost)
+        #[arg(long)]
+        allow_private_network: bool,
+
+        /// Record every HTTP exchange into this fixture directory
+        #[arg(long, value_name = "DIR", conflicts_with = "replay")]
+        record: Option<String>,
+
+        /// Serve every request from a fixture directory made with --record
+        #[arg(long, value_name = "DIR")]
+        replay: Option<String>,
+
+        #[arg(long, value_name = "N", help = RETRIES_HELP)]
+        retries: Option<usize>,
     },
     /// Start the REST API + A2A server
     #[cfg(feature = "serve")]
@@ -68,9 +353,133 @@ enum Commands {
         /// Allow fetching private/LAN addresses
         #[arg(long)]
         allow_private_network: bool,
+
+        /// Require an API key, read (comma-separated) from this environment variable
+        #[arg(long, value_name = "VAR")]
+        api_keys_env: Option<String>,
+
+        /// Require an API key, read (one per line) from this file
+        #[arg(long, value_name = "PATH")]
+        api_keys_file: Option<std::path::PathBuf>,
+
+        /// Requests allowed per API key per minute
+        #[arg(long, value_name = "N")]
+        rate_limit: Option<u32>,
+
+        /// Origin allowed to call the API from a browser (repeatable; "*" for any).
+        /// Defaults to any origin
+        #[arg(long = "cors-origin", value_name = "ORIGIN")]
+        cors_origins: Vec<String>,
+
+        /// Only let sessions navigate to this domain and its subdomains (repeatable)
+        #[arg(long = "allow-domain", value_name = "DOMAIN")]
+        allowed_domains: Vec<String>,
+
+        /// Never let sessions navigate to this domain or its subdomains (repeatable)
+        #[arg(long = "block-domain", value_name = "DOMAIN")]
+        blocked_domains: Vec<String>,
+
+        /// Directory for snapshots saved with POST /api/snapshot
+        /// (default: browsy-snapshots in the system temp directory)
+        #[arg(long, value_name = "DIR")]
+        snapshot_dir: Option<std::path::PathBuf>,
+
+        /// Per-site overrides (user agent, CSS, viewport, headers) from a browsy.toml file
+        #[arg(long, value_name = "PATH")]
+        site_config: Option<std::path::PathBuf>,
+
+        /// Share this domain's cookies (with subdomains) across all sessions (repeatable)
+        #[arg(long = "share-cookies", value_name = "DOMAIN")]
+        shared_cookie_domains: Vec<String>,
+
+        /// Hold submissions of forms whose button text has these words until
+        /// confirmed with POST /api/confirm (repeatable). With only
+        /// --confirm-outside: "delete", "pay", "confirm purchase", "place order"
+        #[arg(long = "confirm-submit", value_name = "WORDS")]
+        confirm_submit: Vec<String>,
+
+        /// Hold clicks and submissions that leave this domain and its subdomains
+        /// until confirmed (repeatable)
+        #[arg(long = "confirm-outside", value_name = "DOMAIN")]
+        confirm_outside: Vec<String>,
+
+        /// Print the OpenAPI description of the REST API and exit
+        #[arg(long)]
+        print_openapi: bool,
     },
 }
 
+/// Flags shared by `fetch` and `parse` that narrow the output to matching
+/// elements. Given together, an element must match all of them.
+#[derive(clap::Args)]
+struct ElementFilter {
+    /// Only output elements whose text contains TEXT, ignoring case and extra whitespace
+    #[arg(long, value_name = "TEXT")]
+    find_text: Option<String>,
+
+    /// Only output elements with this ARIA role, e.g. link, or any of several: button,link
+    #[arg(long, value_name = "ROLE")]
+    find_role: Option<String>,
+
+    /// Only output the elements with these IDs, e.g. 1,2,3
+    #[arg(long, value_name = "IDS", value_delimiter = ',')]
+    ids: Vec<u32>,
+
+    /// Print only the number of matching elements
+    #[arg(long)]
+    count: bool,
+}
+
+impl ElementFilter {
+    fn is_active(&self) -> bool {
+        self.find_text.is_some() || self.find_role.is_some() || !self.ids.is_empty()
+    }
+
+    /// Keep only the elements of `dom` that match every given filter.
+    fn apply(&self, mut dom: output::SpatialDom) -> output::SpatialDom {
+        if !self.is_active() {
+            return dom;
+        }
+        let mut keep: Option<std::collections::HashSet<u32>> = None;
+        let mut narrow = |ids: Vec<u32>| {
+            let ids: std::collections::HashSet<u32> = ids.into_iter().collect();
+            keep = Some(match keep.take() {
+                Some(kept) => &kept & &ids,
+                None => ids,
+            });
+        };
+        if let Some(ref text) = self.find_text {
+            narrow(dom.find_by_text(text).iter().map(|e| e.id).collect());
+        }
+        if let Some(ref role) = self.find_role {
+            narrow(dom.find_by_role(role).iter().map(|e| e.id).collect());
+        }
+        if !self.ids.is_empty() {
+            narrow(self.ids.clone());
+        }
+        let keep = keep.unwrap_or_default();
+        dom.els.retain(|e| keep.contains(&e.id));
+        dom.rebuild_index();
+        dom
+    }
+
+    /// Print `dom` narrowed to the matching elements, or just how many there
+    /// are. Exits with status 2, printing nothing else, when filters match
+    /// nothing, so scripts can branch on it.
+    fn print(&self, dom: output::SpatialDom, as_json: bool, as_json_meta: bool, domain_memory: Option<fetch::DomainMemory>) {
+        let dom = self.apply(dom);
+        if self.count {
+            println!("{}", dom.els.len());
+        }
+        if self.is_active() && dom.els.is_empty() {
+            std::process::exit(2);
+        }
+        if !self.count {
+            print_dom(&dom, as_json, as_json_meta, domain_memory);
+        }
+    }
+}
+
 fn parse_viewport(s: &str) -> (f32, f32) {
     let parts: Vec<&str> = s.split('x').collect();
     if parts.len() == 2 {
@@ -82,19 +491,105 @@ fn parse_viewport(s: &str) -> (f32, f32) {
     }
 }
 
+/// A `--text-limit` value, where 0 means no limit.
+fn limit(text_limit: usize) -> Option<usize> {
+    (text_limit > 0).then_some(text_limit)
+}
+
+/// The default retry policy, allowing `retries` retries when given.
+fn retry_policy(retries: Option<usize>) -> fetch::RetryPolicy {
+    let mut policy = fetch::RetryPolicy::default();
+    if let Some(n) = retries {
+        policy.max_attempts = n + 1;
+    }
+    policy
+}
+
+/// The exit status for a failed fetch, by class of error, so scripts can
+/// tell a bad URL from a site that is down without parsing the message. 2 is
+/// left for usage errors and filters that match nothing.
+fn exit_code(e: &fetch::FetchError) -> i32 {
+    use fetch::ErrorCode;
+    match e.code() {
+        ErrorCode::InvalidUrl | ErrorCode::BlockedUrl => 3,
+        ErrorCode::Network | ErrorCode::Timeout | ErrorCode::TooManyRedirects => 4,
+        ErrorCode::HttpStatus | ErrorCode::ResponseTooLarge | ErrorCode::BinaryContent => 5,
+        ErrorCode::ActionElementNotFound
+        | ErrorCode::ActionWrongElementType
+        | ErrorCode::ActionElementUnavailable
+        | ErrorCode::ActionFailed
+        | ErrorCode::ConfirmationRequired
+        | ErrorCode::NoPageLoaded => 6,
+        _ => 1,
+    }
+}
+
+/// The action policy for `--confirm-submit` and `--confirm-outside`, if
+/// either was given.
+#[cfg(feature = "serve")]
+fn action_policy(submit_patterns: Vec<String>, allowed_domains: Vec<String>) -> Option<fetch::ActionPolicy> {
+    if submit_patterns.is_empty() && allowed_domains.is_empty() {
+        return None;
+    }
+    let mut policy = fetch::ActionPolicy { allowed_domains, ..Default::default() };
+    if !submit_patterns.is_empty() {
+        policy.submit_patterns = submit_patterns;
+    }
+    Some(policy)
+}
+
+fn recording_mode(record: Option<String>, replay: Option<String>) -> Option<fetch::RecordingMode> {
+    match (record, replay) {
+        (Some(dir), _) => Some(fetch::RecordingMode::Record(dir.into())),
+        (None, Some(dir)) => Some(fetch::RecordingMode::Replay(dir.into())),
+        (None, None) => None,
+    }
+}
+
+/// Log to stderr, with each closed span's duration so slow pages can be
+/// broken down into fetch, CSS, parse, style, layout and spatial phases.
+fn init_logging(level: &str, format: &str) -> Result<(), String> {
+    let filter = tracing_subscriber::EnvFilter::try_new(level).map_err(|e| e.to_string())?;
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+        .with_writer(std::io::stderr);
+    let result = if format == "json" {
+        builder.json().try_init()
+    } else {
+        builder.try_init()
+    };
+    result.map_err(|e| e.to_string())
+}
+
 fn main() {
     let cli = Cli::parse();
+    if let Err(e) = init_logging(&cli.log_level, &cli.log_format) {
+        eprintln!("Error: invalid --log-level: {}", e);
+        std::process::exit(2);
+    }
 
     match cli.command {
         Commands::Fetch {
             url,
             json,
             json_meta,
+            map,
             viewport,
             no_css,
             visible_only,
+            drop_hidden,
             above_fold,
+            main_content,
             allow_private_network,
+            auto_consent,
+            record,
+            replay,
+            retries,
+            src_pos,
+            text_limit,
+            site_config,
+            filter,
         } => {
             let (vw, vh) = parse_viewport(&viewport);
             let config = fetch::SessionConfig {
@@ -102,6 +597,12 @@ fn main() {
                 viewport_height: vh,
                 fetch_css: !no_css,
                 allow_private_network,
+                auto_dismiss_cookie_banners: auto_consent,
+                recording: recording_mode(record, replay),
+                retry: retry_policy(retries),
+                track_source_positions: src_pos,
+                text_limit: limit(text_limit),
+                site_overrides_path: site_config,
                 ..Default::default()
             };
 
@@ -114,13 +615,22 @@ fn main() {
             };
             match session.goto(&url) {
                 Ok(dom) => {
-                    let scoped = apply_scope(dom, visible_only, above_fold);
-                    let domain_memory = session.domain_memory_for_current();
-                    print_dom(&scoped, json, json_meta, domain_memory);
+                    if let Some(stats) = session.last_nav_stats() {
+                        eprintln!("{}", stats.summary());
+                    }
+                    let drop = visible_only.then_some(drop_hidden.as_slice());
+                    let scoped = output::apply_scope(&dom, drop, above_fold, main_content);
+                    if map {
+                        let dom = filter.apply(scoped);
+                        println!("{}", output::to_ascii_map(&dom, output::MAP_COLS, output::MAP_ROWS));
+                    } else {
+                        let domain_memory = session.domain_memory_for_current();
+                        filter.print(scoped, json, json_meta, domain_memory);
+                    }
                 }
                 Err(e) => {
-                    eprintln!("Error: {}", e);
-                    std::process::exit(1);
+                    eprintln!("Error: {} ({})", e, e.code());
+                    std::process::exit(exit_code(&e));
                 }
             }
         }
@@ -128,6 +638,10 @@ fn main() {
             file,
             json,
             viewport,
+            src_pos,
+            explain,
+            text_limit,
+            filter,
         } => {
             let (vw, vh) = parse_viewport(&viewport);
             let html = if file == "-" {
@@ -139,16 +653,286 @@ fn main() {
                 std::fs::read_to_string(&file).expect("Failed to read file")
             };
 
-            let dom = browsy_core::parse(&html, vw, vh);
-            print_dom(&dom, json, false, None);
+            if let Some(id) = explain {
+                let dom = browsy_core::parse_with_positions(&html, vw, vh);
+                match explain_element(&dom, &html, id) {
+                    Ok(fragment) => print!("{}", fragment),
+                    Err(e) => {
+                        eprintln!("Error: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+                return;
+            }
+
+            let mut dom = if src_pos {
+                browsy_core::parse_with_positions(&html, vw, vh)
+            } else {
+                browsy_core::parse(&html, vw, vh)
+            };
+            dom.text_limit = limit(text_limit);
+            filter.print(dom, json, false, None);
+        }
+        Commands::Diff {
+            old,
+            new,
+            json,
+            ignore_bounds,
+        } => {
+            let load = |path: &str| {
+                std::fs::read_to_string(path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|s| output::SpatialDom::from_json(&s).map_err(|e| e.to_string()))
+                    .unwrap_or_else(|e| {
+                        eprintln!("Error: {}: {}", path, e);
+                        std::process::exit(2);
+                    })
+            };
+            let (old_dom, new_dom) = (load(&old), load(&new));
+            let options = output::DiffOptions { ignore_bounds };
+            let delta = output::diff_with_options(&old_dom, &new_dom, &options);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&delta).unwrap());
+            } else {
+                let compact = output::delta_to_compact_string(&delta);
+                if !compact.is_empty() {
+                    println!("{}", compact);
+                }
+            }
+            let identical = delta.changed.is_empty() && delta.removed.is_empty();
+            std::process::exit(if identical { 0 } else { 1 });
+        }
+        Commands::Watch {
+            url,
+            interval,
+            notify_cmd,
+            max_iterations,
+            state_dir,
+            ignore_bounds,
+            viewport,
+            no_css,
+            allow_private_network,
+        } => {
+            let (vw, vh) = parse_viewport(&viewport);
+            let config = fetch::SessionConfig {
+                viewport_width: vw,
+                viewport_height: vh,
+                fetch_css: !no_css,
+                allow_private_network,
+                ..Default::default()
+            };
+            let session = match fetch::Session::with_config(config) {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            let watch_config = fetch::WatchConfig {
+                interval: std::time::Duration::from_secs(interval),
+                max_iterations,
+                state_dir: state_dir.map(Into::into),
+                diff: output::DiffOptions { ignore_bounds },
+            };
+            if let Err(e) = watch::run(session, &url, watch_config, notify_cmd) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Batch {
+            input,
+            out_dir,
+            format,
+            concurrency,
+            resume,
+            viewport,
+            no_css,
+            allow_private_network,
+            retries,
+        } => {
+            let opts = batch::BatchOptions {
+                input: input.into(),
+                out_dir: out_dir.into(),
+                json: format == "json",
+                concurrency,
+                resume,
+                viewport: parse_viewport(&viewport),
+                fetch_css: !no_css,
+                allow_private_network,
+                retry: retry_policy(retries),
+            };
+            if let Err(e) = batch::run(opts) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Crawl {
+            url,
+            max_pages,
+            max_depth,
+            all_hosts,
+            include_patterns,
+            exclude_patterns,
+            ignore_robots,
+            delay_ms,
+            no_css,
+            allow_private_network,
+            retries,
+        } => {
+            let config = fetch::SessionConfig {
+                fetch_css: !no_css,
+                allow_private_network,
+                per_host_delay_ms: delay_ms,
+                retry: retry_policy(retries),
+                ..Default::default()
+            };
+            let opts = fetch::CrawlOpts {
+                max_pages,
+                max_depth,
+                same_host_only: !all_hosts,
+                include_patterns,
+                exclude_patterns,
+                respect_robots: !ignore_robots,
+            };
+            let result = fetch::Session::with_config(config).and_then(|mut session| session.crawl(&url, opts));
+            match result {
+                Ok(entries) => {
+                    for entry in entries {
+                        println!("{}", serde_json::to_string(&entry).unwrap());
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error: {} ({})", e, e.code());
+                    std::process::exit(exit_code(&e));
+                }
+            }
+        }
+        Commands::Repl {
+            url,
+            script,
+            viewport,
+            no_css,
+            allow_private_network,
+            auto_consent,
+            record,
+            replay,
+            retries,
+        } => {
+            let (vw, vh) = parse_viewport(&viewport);
+            let config = fetch::SessionConfig {
+                viewport_width: vw,
+                viewport_height: vh,
+                fetch_css: !no_css,
+                allow_private_network,
+                auto_dismiss_cookie_banners: auto_consent,
+                recording: recording_mode(record, replay),
+                retry: retry_policy(retries),
+                ..Default::default()
+            };
+            let session = match fetch::Session::with_config(config) {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            if let Err(e) = repl::run(session, url, script) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Run {
+            flow,
+            snapshot_on_failure,
+            viewport,
+            no_css,
+            allow_private_network,
+            record,
+            replay,
+            retries,
+        } => {
+            let flow = run::load(&flow).unwrap_or_else(|e| {
+                eprintln!("Error: {}", e);
+                std::process::exit(2);
+            });
+            let (vw, vh) = parse_viewport(&viewport);
+            let config = fetch::SessionConfig {
+                viewport_width: vw,
+                viewport_height: vh,
+                fetch_css: !no_css,
+                allow_private_network,
+                recording: recording_mode(record, replay),
+                retry: retry_policy(retries),
+                ..Default::default()
+            };
+            let mut session = match fetch::Session::with_config(config) {
+                Ok(s) => s,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            if !run::run(&mut session, &flow, snapshot_on_failure.as_deref()) {
+                std::process::exit(1);
+            }
         }
         #[cfg(feature = "serve")]
-        Commands::Serve { port, allow_private_network } => {
-            let config = browsy_server::ServerConfig {
+        Commands::Serve {
+            port,
+            allow_private_network,
+            api_keys_env,
+            api_keys_file,
+            rate_limit,
+            cors_origins,
+            allowed_domains,
+            blocked_domains,
+            snapshot_dir,
+            site_config,
+            shared_cookie_domains,
+            confirm_submit,
+            confirm_outside,
+            print_openapi,
+        } => {
+            if print_openapi {
+                println!("{}", serde_json::to_string_pretty(&browsy_server::openapi()).unwrap());
+                return;
+            }
+            let api_keys = match load_api_keys(api_keys_env.as_deref(), api_keys_file.as_deref()) {
+                Ok(keys) => keys,
+                Err(e) => {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(2);
+                }
+            };
+            if rate_limit.is_some() && api_keys.is_empty() {
+                eprintln!("Error: --rate-limit applies per API key; pass --api-keys-env or --api-keys-file");
+                std::process::exit(2);
+            }
+            // Sessions read the file as they're created; report mistakes now
+            if let Some(ref path) = site_config {
+                if let Err(e) = fetch::SiteOverrides::load(path) {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(2);
+                }
+            }
+            let mut config = browsy_server::ServerConfig {
                 port,
                 allow_private_network,
+                api_keys,
+                rate_limit_per_minute: rate_limit,
+                allowed_domains: (!allowed_domains.is_empty()).then_some(allowed_domains),
+                blocked_domains,
+                site_overrides_path: site_config,
+                shared_cookie_domains,
+                action_policy: action_policy(confirm_submit, confirm_outside),
                 ..Default::default()
             };
+            if !cors_origins.is_empty() {
+                config.cors_origins = cors_origins;
+            }
+            if let Some(dir) = snapshot_dir {
+                config.snapshot_dir = dir;
+            }
             let state = std::sync::Arc::new(browsy_server::AppState::new(config));
             let app = browsy_server::build_router(state);
             tokio::runtime::Builder::new_multi_thread()
@@ -166,40 +950,100 @@ fn main() {
     }
 }
 
-fn apply_scope(mut dom: output::SpatialDom, visible_only: bool, above_fold: bool) -> output::SpatialDom {
-    if visible_only {
-        dom.els = dom.els.into_iter().filter(|e| e.hidden != Some(true)).collect();
-        dom.rebuild_index();
+/// API keys from an environment variable (comma-separated) and/or a file
+/// (one per line, `#` comments allowed). Naming a source that holds no keys
+/// is an error, so a typo can't silently disable authentication.
+#[cfg(feature = "serve")]
+fn load_api_keys(env: Option<&str>, file: Option<&std::path::Path>) -> Result<Vec<String>, String> {
+    let mut keys = Vec::new();
+    if let Some(var) = env {
+        let value = std::env::var(var).map_err(|_| format!("environment variable {var} is not set"))?;
+        let found: Vec<String> = value.split(',').map(str::trim).filter(|k| !k.is_empty()).map(String::from).collect();
+        if found.is_empty() {
+            return Err(format!("environment variable {var} holds no API keys"));
+        }
+        keys.extend(found);
     }
-    if above_fold {
-        dom = dom.filter_above_fold();
+    if let Some(path) = file {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let found: Vec<String> = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(String::from)
+            .collect();
+        if found.is_empty() {
+            return Err(format!("{} holds no API keys", path.display()));
+        }
+        keys.extend(found);
     }
-    dom
+    Ok(keys)
+}
+
+/// Lines of source shown on each side of the explained element's start tag.
+const EXPLAIN_CONTEXT_LINES: usize = 3;
+
+/// The element as JSON, then the source around its start tag with a caret
+/// under the `<`.
+fn explain_element(dom: &output::SpatialDom, html: &str, id: u32) -> Result<String, String> {
+    use std::fmt::Write;
+
+    let el = dom.get(id).ok_or_else(|| format!("no element with ID {}", id))?;
+    let [line, column] = el.src.ok_or_else(|| {
+        format!("element {} has no start tag in the source; the parser inserted it", id)
+    })?;
+    let (line, column) = (line as usize, column as usize);
+
+    let lines: Vec<&str> = html.lines().collect();
+    let first = line.saturating_sub(EXPLAIN_CONTEXT_LINES).max(1);
+    let last = (line + EXPLAIN_CONTEXT_LINES).min(lines.len());
+    let width = last.to_string().len();
+
+    let mut out = String::new();
+    let _ = writeln!(out, "{}", serde_json::to_string(el).unwrap());
+    let _ = writeln!(out, "--> line {}, column {}", line, column);
+    for n in first..=last {
+        let marker = if n == line { '>' } else { ' ' };
+        let _ = writeln!(out, "{} {:>width$} | {}", marker, n, lines[n - 1], width = width);
+        if n == line {
+            let _ = writeln!(out, "  {:>width$} | {}^", "", " ".repeat(column - 1), width = width);
+        }
+    }
+    Ok(out)
 }
 
 fn print_dom(dom: &output::SpatialDom, as_json: bool, as_json_meta: bool, domain_memory: Option<fetch::DomainMemory>) {
+    print!("{}", format_dom(dom, as_json, as_json_meta, domain_memory));
+}
+
+/// Render a page the way `fetch` prints it: JSON, JSON with metadata, or the
+/// compact header plus element lines.
+fn format_dom(dom: &output::SpatialDom, as_json: bool, as_json_meta: bool, domain_memory: Option<fetch::DomainMemory>) -> String {
+    use std::fmt::Write;
+
+    let mut out = String::new();
     if as_json_meta {
         let wrapped = serde_json::json!({
             "dom": dom,
             "domain_memory": domain_memory,
         });
-        println!("{}", serde_json::to_string_pretty(&wrapped).unwrap());
+        let _ = writeln!(out, "{}", serde_json::to_string_pretty(&wrapped).unwrap());
     } else if as_json {
-        println!("{}", serde_json::to_string_pretty(dom).unwrap());
+        let _ = writeln!(out, "{}", serde_json::to_string_pretty(dom).unwrap());
     } else {
         if let Some(ref blocked) = dom.blocked {
-            println!("WARNING: blocked ({})", blocked.reason);
+            let _ = writeln!(out, "WARNING: blocked ({})", blocked.reason);
             if !blocked.signals.is_empty() {
-                println!("signals: {}", blocked.signals.join(", "));
+                let _ = writeln!(out, "signals: {}", blocked.signals.join(", "));
             }
             if !blocked.recommendations.is_empty() {
-                println!("recommendations:");
+                let _ = writeln!(out, "recommendations:");
                 for rec in &blocked.recommendations {
-                    println!("  - {}", rec);
+                    let _ = writeln!(out, "  - {}", rec);
                 }
             }
             if blocked.require_human {
-                println!("requires_human: true");
+                let _ = writeln!(out, "requires_human: true");
             }
             let next_step = if blocked.require_human {
                 "ask_human_to_solve"
@@ -208,19 +1052,14 @@ fn print_dom(dom: &output::SpatialDom, as_json: bool, as_json_meta: bool, domain
             } else {
                 "retry_with_guidance"
             };
-            println!("next_step: {}", next_step);
+            let _ = writeln!(out, "next_step: {}", next_step);
         }
         if dom.page_type == output::PageType::Captcha {
-            println!("WARNING: captcha detected");
-        }
-        if !dom.title.is_empty() {
-            println!("title: {}", dom.title);
-        }
-        if !dom.url.is_empty() {
-            println!("url: {}", dom.url);
+            let _ = writeln!(out, "WARNING: captcha detected");
         }
         if let Some(ref memory) = domain_memory {
-            println!(
+            let _ = writeln!(
+                out,
                 "domain_memory: ok={} blocked={} error={} last_outcome={} last_reason={} last_seen_unix={}",
                 memory.ok_count,
                 memory.blocked_count,
@@ -230,9 +1069,9 @@ fn print_dom(dom: &output::SpatialDom, as_json: bool, as_json_meta: bool, domain
                 memory.last_seen_unix
             );
         }
-        println!("vp: {}x{}", dom.vp[0] as i32, dom.vp[1] as i32);
-        println!("els: {}", dom.els.len());
-        println!("---");
-        println!("{}", output::to_compact_string(dom));
+        let _ = writeln!(out, "vp: {}x{}", dom.vp[0] as i32, dom.vp[1] as i32);
+        out.push_str(&output::format_header(dom));
+        let _ = writeln!(out, "{}", output::to_compact_string(dom));
     }
+    out
 }
@@ -0,0 +1,223 @@
+//! Interactive REPL for driving a `Session` by hand.
+
+use std::io::{BufRead, IsTerminal, Write};
+
+use browsy_core::fetch::Session;
+use browsy_core::output;
+
+use crate::print_dom;
+
+const HELP: &str = "\
+commands:
+  goto <url>              navigate to a URL
+  click <id>              click an element
+  type <id> <text>        type text into an input
+  find <text>             find elements containing text
+  page [--json] [--above-fold]
+                          print the current page with form state
+  tables                  print extracted tables as JSON
+  info                    print page type, suggested actions, alerts and pagination
+  back                    go back in history
+  delta                   print changes since the previous page
+  snapshot [dir]          save the page, form state and recent actions for
+                          debugging (default dir: current directory)
+  help                    show this help
+  quit                    exit the REPL";
+
+/// A single parsed REPL command.
+#[derive(Debug)]
+enum Command {
+    Goto(String),
+    Click(u32),
+    Type(u32, String),
+    Find(String),
+    Page { json: bool, above_fold: bool },
+    Tables,
+    Info,
+    Back,
+    Delta,
+    Snapshot(String),
+    Help,
+    Quit,
+}
+
+fn parse_id(arg: &str) -> Result<u32, String> {
+    arg.parse().map_err(|_| format!("invalid element id: {arg:?}"))
+}
+
+/// Parse one input line. Blank lines and `#` comments yield `None`.
+fn parse_command(line: &str) -> Result<Option<Command>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let (name, rest) = match line.split_once(char::is_whitespace) {
+        Some((name, rest)) => (name, rest.trim()),
+        None => (line, ""),
+    };
+    let cmd = match name {
+        "goto" if !rest.is_empty() => Command::Goto(rest.to_string()),
+        "goto" => return Err("usage: goto <url>".into()),
+        "click" => Command::Click(parse_id(rest)?),
+        "type" => {
+            let (id, text) = rest.split_once(char::is_whitespace).ok_or("usage: type <id> <text>")?;
+            Command::Type(parse_id(id)?, text.trim_start().to_string())
+        }
+        "find" if !rest.is_empty() => Command::Find(rest.to_string()),
+        "find" => return Err("usage: find <text>".into()),
+        "page" => {
+            let mut json = false;
+            let mut above_fold = false;
+            for flag in rest.split_whitespace() {
+                match flag {
+                    "--json" => json = true,
+                    "--above-fold" => above_fold = true,
+                    other => return Err(format!("unknown page flag: {other}")),
+                }
+            }
+            Command::Page { json, above_fold }
+        }
+        "tables" => Command::Tables,
+        "info" => Command::Info,
+        "back" => Command::Back,
+        "delta" => Command::Delta,
+        "snapshot" | ":snapshot" if rest.is_empty() => Command::Snapshot(".".to_string()),
+        "snapshot" | ":snapshot" => Command::Snapshot(rest.to_string()),
+        "help" | "?" => Command::Help,
+        "quit" | "exit" => Command::Quit,
+        other => return Err(format!("unknown command: {other} (try `help`)")),
+    };
+    Ok(Some(cmd))
+}
+
+fn print_json(value: serde_json::Value) {
+    println!("{}", serde_json::to_string_pretty(&value).unwrap_or_default());
+}
+
+/// Run one command. Returns `Ok(false)` when the REPL should stop.
+fn execute(session: &mut Session, cmd: Command) -> Result<bool, String> {
+    match cmd {
+        Command::Goto(url) => {
+            let dom = session.goto(&url).map_err(|e| e.to_string())?;
+            print_dom(&dom, false, false, session.domain_memory_for_current());
+        }
+        Command::Click(id) => {
+            let dom = session.click(id).map_err(|e| e.to_string())?;
+            print_dom(&dom, false, false, None);
+        }
+        Command::Type(id, text) => {
+            session.type_text(id, &text).map_err(|e| e.to_string())?;
+            println!("Typed {} into element {id}", session.typed_echo(id, &text));
+            if let Some(warning) = session.suggestion_warning(id, &text) {
+                println!("Warning: {warning}");
+            }
+        }
+        Command::Find(text) => {
+            let found: Vec<_> = session.find_by_text(&text).into_iter().cloned().collect();
+            print_json(serde_json::json!(found));
+        }
+        Command::Page { json, above_fold } => {
+            let dom = session.dom().ok_or("No page loaded")?;
+            let scoped = output::apply_scope(&dom, None, above_fold, false);
+            print_dom(&scoped, json, false, None);
+        }
+        Command::Tables => {
+            let dom = session.dom_ref().ok_or("No page loaded")?;
+            print_json(serde_json::json!(dom.tables()));
+        }
+        Command::Info => {
+            let dom = session.dom_ref().ok_or("No page loaded")?;
+            let alerts: Vec<_> = dom
+                .alerts()
+                .iter()
+                .map(|a| serde_json::json!({ "id": a.id, "type": a.alert_type, "text": a.text }))
+                .collect();
+            print_json(serde_json::json!({
+                "title": dom.title,
+                "url": dom.url,
+                "page_type": format!("{:?}", dom.page_type),
+                "suggested_actions": dom.suggested_actions,
+                "alerts": alerts,
+                "pagination": dom.pagination(),
+            }));
+        }
+        Command::Back => {
+            let dom = session.back().map_err(|e| e.to_string())?;
+            print_dom(&dom, false, false, None);
+        }
+        Command::Delta => {
+            let delta = session.delta().ok_or("No previous page to diff against")?;
+            println!("{}", output::delta_to_compact_string(&delta));
+        }
+        Command::Snapshot(dir) => {
+            let path = session
+                .save_snapshot(std::path::Path::new(&dir))
+                .map_err(|e| e.to_string())?;
+            println!("Saved snapshot to {}", path.display());
+        }
+        Command::Help => println!("{HELP}"),
+        Command::Quit => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// Read commands from `input` until EOF or `quit`. Errors are reported and
+/// the loop continues. With `echo`, each command is printed before its output
+/// so replayed scripts read like a transcript.
+fn run_lines(session: &mut Session, input: impl BufRead, prompt: bool, echo: bool) {
+    let mut lines = input.lines();
+    loop {
+        if prompt {
+            print!("browsy> ");
+            let _ = std::io::stdout().flush();
+        }
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            Some(Err(e)) => {
+                eprintln!("Error: {e}");
+                break;
+            }
+            None => break,
+        };
+        let cmd = match parse_command(&line) {
+            Ok(Some(cmd)) => cmd,
+            Ok(None) => continue,
+            Err(e) => {
+                eprintln!("Error: {e}");
+                continue;
+            }
+        };
+        if echo {
+            println!("> {}", line.trim());
+        }
+        match execute(session, cmd) {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => eprintln!("Error: {e}"),
+        }
+    }
+}
+
+/// Entry point for `browsy repl`.
+pub fn run(mut session: Session, url: Option<String>, script: Option<String>) -> Result<(), String> {
+    if let Some(url) = url {
+        if let Err(e) = execute(&mut session, Command::Goto(url)) {
+            eprintln!("Error: {e}");
+        }
+    }
+    match script {
+        Some(path) => {
+            let file = std::fs::File::open(&path).map_err(|e| format!("{path}: {e}"))?;
+            run_lines(&mut session, std::io::BufReader::new(file), false, true);
+        }
+        None => {
+            let stdin = std::io::stdin();
+            let interactive = stdin.is_terminal();
+            if interactive {
+                eprintln!("browsy repl — type `help` for commands, `quit` to exit");
+            }
+            run_lines(&mut session, stdin.lock(), interactive, false);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,51 @@
+//! `browsy run`: run a flow file of scripted steps and report each one, for CI.
+
+use std::path::Path;
+
+use browsy_core::fetch::{Flow, Session, StepError};
+
+/// Read a flow from YAML (`.yaml` or `.yml`) or JSON.
+pub fn load(path: &Path) -> Result<Flow, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    let yaml = matches!(path.extension().and_then(|ext| ext.to_str()), Some("yaml" | "yml"));
+    let flow = if yaml {
+        // Steps are written as `goto: <url>` maps, not YAML tags like `!goto <url>`
+        let steps = serde_yaml::Deserializer::from_str(&text);
+        serde_yaml::with::singleton_map_recursive::deserialize(steps).map_err(|e| e.to_string())
+    } else {
+        Flow::from_json(&text).map_err(|e| e.to_string())
+    };
+    flow.map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Run `flow`, printing `ok N <step>` as each step passes and, for the one
+/// that fails, `not ok N <step>` with what went wrong. Returns whether every
+/// step passed.
+pub fn run(session: &mut Session, flow: &Flow, snapshot_on_failure: Option<&Path>) -> bool {
+    if let Some(ref name) = flow.name {
+        println!("# {name}");
+    }
+    let total = flow.steps.len();
+    match flow.run(session, snapshot_on_failure, |i, step| println!("ok {i} {step}")) {
+        Ok(()) => {
+            println!("{total} of {total} steps passed");
+            true
+        }
+        Err(failure) => {
+            println!("not ok {} {}", failure.index, failure.step);
+            match failure.error {
+                StepError::Assertion { check, expected, actual } => {
+                    println!("  {check}");
+                    println!("  - expected: {expected}");
+                    println!("  + actual:   {actual}");
+                }
+                error => println!("  error: {error}"),
+            }
+            if let Some(path) = failure.snapshot {
+                println!("  snapshot: {}", path.display());
+            }
+            println!("{} of {total} steps passed", failure.index - 1);
+            false
+        }
+    }
+}
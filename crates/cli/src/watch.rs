@@ -0,0 +1,75 @@
+//! `browsy watch`: print timestamped deltas and run a notify command on change.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use browsy_core::fetch::{Session, WatchConfig, WatchEvent, Watcher};
+use browsy_core::output;
+
+/// Current UTC time as `YYYY-MM-DDTHH:MM:SSZ`.
+fn timestamp() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    let (days, rem) = ((secs / 86_400) as i64, secs % 86_400);
+    // Civil-from-days (Howard Hinnant's algorithm).
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3_600,
+        rem / 60 % 60,
+        rem % 60
+    )
+}
+
+/// Run `cmd` through the shell with `delta` on stdin.
+fn notify(cmd: &str, url: &str, delta: &str) -> Result<(), String> {
+    let mut child = if cfg!(windows) {
+        Command::new("cmd").args(["/C", cmd]).stdin(Stdio::piped()).env("BROWSY_URL", url).spawn()
+    } else {
+        Command::new("sh").args(["-c", cmd]).stdin(Stdio::piped()).env("BROWSY_URL", url).spawn()
+    }
+    .map_err(|e| e.to_string())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(delta.as_bytes());
+    }
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("exited with {status}"))
+    }
+}
+
+/// Entry point for `browsy watch`.
+pub fn run(mut session: Session, url: &str, config: WatchConfig, notify_cmd: Option<String>) -> Result<(), String> {
+    let mut watcher = Watcher::new(url, config);
+    if watcher.previous().is_some() {
+        eprintln!("resuming from saved snapshot");
+    }
+    watcher
+        .run(&mut session, |event| match event {
+            WatchEvent::Baseline { els } => println!("[{}] baseline: {els} elements", timestamp()),
+            WatchEvent::Unchanged => eprintln!("[{}] unchanged", timestamp()),
+            WatchEvent::Changed(delta) => {
+                let compact = output::delta_to_compact_string(delta);
+                println!("[{}] changed\n{compact}", timestamp());
+                if let Some(ref cmd) = notify_cmd {
+                    if let Err(e) = notify(cmd, url, &compact) {
+                        eprintln!("notify command failed: {e}");
+                    }
+                }
+            }
+            WatchEvent::Failed { error, retry_in } => {
+                eprintln!("[{}] error: {error} (retrying in {}s)", timestamp(), retry_in.as_secs())
+            }
+        })
+        .map_err(|e| e.to_string())
+}
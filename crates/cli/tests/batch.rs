@@ -0,0 +1,71 @@
+mod common;
+
+use std::process::Command;
+
+const PAGE: &str = r#"<html><head><title>Batch Fixture</title></head>
+<body><h1>Snapshot me</h1><a href="/next">Next</a></body></html>"#;
+
+fn batch(input: &std::path::Path, out_dir: &std::path::Path, extra: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_browsy"))
+        .args(["batch", "--no-css", "--allow-private-network", "--concurrency", "2"])
+        .arg("--input")
+        .arg(input)
+        .arg("--out-dir")
+        .arg(out_dir)
+        .args(extra)
+        .output()
+        .unwrap()
+}
+
+fn manifest(out_dir: &std::path::Path) -> serde_json::Map<String, serde_json::Value> {
+    let text = std::fs::read_to_string(out_dir.join("manifest.json")).unwrap();
+    serde_json::from_str(&text).unwrap()
+}
+
+#[test]
+fn batch_writes_files_manifest_and_resumes() {
+    let base = common::serve_fixture(PAGE);
+    let dir = std::env::temp_dir().join(format!("browsy-batch-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("urls.txt");
+    let out_dir = dir.join("snaps");
+
+    let ok_a = format!("{base}a");
+    let ok_b = format!("{base}b?x=1");
+    let bad = "http://127.0.0.1:1/unreachable".to_string();
+    std::fs::write(&input, format!("# fixtures\n{ok_a}\n\n{ok_b}\n{bad}\n{ok_a}\n")).unwrap();
+
+    let out = batch(&input, &out_dir, &["--format", "json"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(out.stdout.is_empty(), "progress belongs on stderr");
+
+    let entries = manifest(&out_dir);
+    assert_eq!(entries.len(), 3);
+    for url in [&ok_a, &ok_b] {
+        let entry = &entries[url.as_str()];
+        assert_eq!(entry["status"], "ok");
+        assert!(entry["els"].as_u64().unwrap() > 0);
+        let file = entry["file"].as_str().unwrap();
+        assert!(file.starts_with("127.0.0.1-") && file.ends_with(".json"), "{file}");
+        let dom: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(out_dir.join(file)).unwrap()).unwrap();
+        assert_eq!(dom["title"], "Batch Fixture");
+    }
+    assert_ne!(entries[ok_a.as_str()]["file"], entries[ok_b.as_str()]["file"]);
+    assert_eq!(entries[bad.as_str()]["status"], "error");
+    assert!(entries[bad.as_str()]["error"].is_string());
+
+    // Resume only fetches URLs missing from the manifest.
+    let ok_c = format!("{base}c");
+    std::fs::write(&input, format!("{ok_a}\n{ok_b}\n{bad}\n{ok_c}\n")).unwrap();
+    let out = batch(&input, &out_dir, &["--format", "json", "--resume"]);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(out.status.success(), "{stderr}");
+    assert!(stderr.contains("[1/1] ok"), "{stderr}");
+    let entries = manifest(&out_dir);
+    assert_eq!(entries.len(), 4);
+    assert_eq!(entries[ok_c.as_str()]["status"], "ok");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
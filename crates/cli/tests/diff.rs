@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+use std::process::Command;
+
+fn write_dom(name: &str, html: &str, url: &str) -> PathBuf {
+    let mut dom = browsy_core::parse(html, 1920.0, 1080.0);
+    dom.url = url.to_string();
+    let path = std::env::temp_dir().join(format!("browsy-diff-{}-{name}.json", std::process::id()));
+    std::fs::write(&path, serde_json::to_string(&dom).unwrap()).unwrap();
+    path
+}
+
+fn diff(old: &PathBuf, new: &PathBuf, extra: &[&str]) -> (i32, String) {
+    let out = Command::new(env!("CARGO_BIN_EXE_browsy"))
+        .arg("diff")
+        .arg(old)
+        .arg(new)
+        .args(extra)
+        .output()
+        .unwrap();
+    (out.status.code().unwrap(), String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+#[test]
+fn diff_exit_status_and_ignore_bounds() {
+    let base = write_dom("base", "<html><body><h1>Prices</h1><a href=\"/buy\">$10</a></body></html>", "https://a.example/");
+    let moved = write_dom(
+        "moved",
+        "<html><body><div style=\"height: 60px\"></div><h1>Prices</h1><a href=\"/buy\">$10</a></body></html>",
+        "https://b.example/",
+    );
+    let changed = write_dom("changed", "<html><body><h1>Prices</h1><a href=\"/buy\">$12</a></body></html>", "https://a.example/");
+
+    assert_eq!(diff(&base, &base, &[]).0, 0, "identical files");
+
+    let (code, stdout) = diff(&base, &changed, &[]);
+    assert_eq!(code, 1);
+    assert!(stdout.contains("+") && stdout.contains("$12"), "{stdout}");
+
+    let (code, stdout) = diff(&base, &changed, &["--json"]);
+    assert_eq!(code, 1);
+    let delta: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    assert_eq!(delta["changed"][0]["text"], "$12");
+
+    assert_eq!(diff(&base, &moved, &[]).0, 1, "layout shift counts by default");
+    assert_eq!(diff(&base, &moved, &["--ignore-bounds"]).0, 0, "layout shift ignored");
+
+    let missing = std::env::temp_dir().join("browsy-diff-missing.json");
+    assert_eq!(diff(&base, &missing, &[]).0, 2);
+
+    for path in [base, moved, changed] {
+        let _ = std::fs::remove_file(path);
+    }
+}
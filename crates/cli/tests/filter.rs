@@ -0,0 +1,60 @@
+use std::process::Command;
+
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/pricing.html");
+
+fn parse(extra: &[&str]) -> (i32, String) {
+    let out = Command::new(env!("CARGO_BIN_EXE_browsy"))
+        .arg("parse")
+        .arg(FIXTURE)
+        .args(extra)
+        .output()
+        .unwrap();
+    (out.status.code().unwrap(), String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// Element lines of compact output, without the header.
+fn element_lines(stdout: &str) -> Vec<&str> {
+    stdout.lines().filter(|l| l.starts_with('[')).collect()
+}
+
+#[test]
+fn filters_narrow_compact_and_json_output() {
+    let (code, stdout) = parse(&["--find-role", "link"]);
+    assert_eq!(code, 0);
+    let links = element_lines(&stdout);
+    assert_eq!(links.len(), 3, "{stdout}");
+    assert!(links.iter().all(|l| l.contains(":a ")), "{stdout}");
+    assert!(stdout.contains("els: 3"), "{stdout}");
+
+    // Filters combine: links whose text mentions pricing
+    let (code, stdout) = parse(&["--find-role", "link", "--find-text", "Pricing"]);
+    assert_eq!(code, 0);
+    assert_eq!(element_lines(&stdout), vec![links[1]]);
+
+    let (code, stdout) = parse(&["--find-text", "price", "--json"]);
+    assert_eq!(code, 0);
+    let dom: serde_json::Value = serde_json::from_str(&stdout).unwrap();
+    let texts: Vec<&str> = dom["els"].as_array().unwrap().iter().map(|e| e["text"].as_str().unwrap()).collect();
+    assert_eq!(texts, ["Starter: price $10 per seat", "Business: price $25 per seat"]);
+
+    let ids: Vec<String> = dom["els"].as_array().unwrap().iter().map(|e| e["id"].to_string()).collect();
+    let (code, stdout) = parse(&["--ids", &ids.join(",")]);
+    assert_eq!(code, 0);
+    assert_eq!(element_lines(&stdout).len(), 2, "{stdout}");
+}
+
+#[test]
+fn count_and_no_match_exit_status() {
+    assert_eq!(parse(&["--find-role", "link", "--count"]), (0, "3\n".to_string()));
+    assert_eq!(parse(&["--find-role", "button", "--count"]), (0, "1\n".to_string()));
+
+    assert_eq!(parse(&["--find-text", "Enterprise"]), (2, String::new()));
+    assert_eq!(parse(&["--find-text", "Enterprise", "--json"]), (2, String::new()));
+    assert_eq!(parse(&["--ids", "999"]), (2, String::new()));
+    assert_eq!(parse(&["--find-text", "Enterprise", "--count"]), (2, "0\n".to_string()));
+
+    let (code, stdout) = parse(&["--count"]);
+    assert_eq!(code, 0);
+    let all = parse(&[]).1;
+    assert_eq!(stdout.trim(), element_lines(&all).len().to_string());
+}
@@ -0,0 +1,39 @@
+mod common;
+
+use std::process::Command;
+
+const PAGE: &str = r#"<html><head><title>Repl Fixture</title></head>
+<body>
+    <h1>Welcome</h1>
+    <a href="/signup">Signup today</a>
+    <table><tr><th>Plan</th></tr><tr><td>Starter</td></tr></table>
+</body></html>"#;
+
+#[test]
+fn repl_script_replays_commands_and_survives_errors() {
+    let url = common::serve_fixture(PAGE);
+    let script = std::env::temp_dir().join(format!("browsy-repl-{}.txt", std::process::id()));
+    std::fs::write(
+        &script,
+        format!("# reproduce\nhelp\ngoto {url}\nfind Signup\ntables\nclick 999\nbogus\nquit\nhelp\n"),
+    )
+    .unwrap();
+
+    let out = Command::new(env!("CARGO_BIN_EXE_browsy"))
+        .args(["repl", "--no-css", "--allow-private-network", "--script"])
+        .arg(&script)
+        .output()
+        .unwrap();
+    let _ = std::fs::remove_file(&script);
+
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(out.status.success(), "repl should exit cleanly: {stderr}");
+    assert!(stdout.contains("goto <url>"), "help output: {stdout}");
+    assert!(stdout.contains("title: Repl Fixture"), "goto output: {stdout}");
+    assert!(stdout.contains("Signup today"), "find output: {stdout}");
+    assert!(stdout.contains("Starter"), "tables output: {stdout}");
+    assert!(stderr.contains("Element 999 not found"), "click error: {stderr}");
+    assert!(stderr.contains("unknown command: bogus"), "parse error: {stderr}");
+    assert_eq!(stdout.matches("> help").count(), 1, "commands after quit must not run");
+}
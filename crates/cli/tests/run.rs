@@ -0,0 +1,111 @@
+mod common;
+
+use std::path::Path;
+use std::process::Command;
+
+const PAGE: &str = r#"<html><head><title>Run Fixture</title></head>
+<body>
+    <form action="/" method="get">
+        <label for="email">Email</label>
+        <input type="email" id="email" name="email">
+        <label for="password">Password</label>
+        <input type="password" id="password" name="password">
+        <input type="checkbox" id="remember" name="remember">
+        <label for="remember">Remember me</label>
+        <button type="submit">Sign in</button>
+    </form>
+    <table><tr><th>Plan</th></tr><tr><td>Starter</td></tr></table>
+</body></html>"#;
+
+fn run(flow: &Path, extra: &[&str]) -> (i32, String) {
+    let out = Command::new(env!("CARGO_BIN_EXE_browsy"))
+        .args(["run", "--no-css", "--allow-private-network"])
+        .args(extra)
+        .arg(flow)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&out.stdout).into_owned();
+    (out.status.code().unwrap_or(-1), stdout + &String::from_utf8_lossy(&out.stderr))
+}
+
+#[test]
+fn run_passes_a_yaml_flow_and_replays_it() {
+    let url = common::serve_fixture(PAGE);
+    let dir = std::env::temp_dir().join(format!("browsy-run-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let flow = dir.join("flow.yaml");
+    std::fs::write(
+        &flow,
+        format!(
+            "name: sign in
+steps:
+  - goto: {url}
+  - type: {{ label: Email, text: ada@example.com }}
+  - check: {{ label: Remember me }}
+  - click: {{ text: Sign in, role: button }}
+  - assert: {{ page_type: Login, title_contains: Run Fixture }}
+  - extract: {{ tables: {} }}
+",
+            dir.join("tables.json").display()
+        ),
+    )
+    .unwrap();
+
+    let fixtures = dir.join("fixtures");
+    let (code, out) = run(&flow, &["--record", fixtures.to_str().unwrap()]);
+    assert_eq!(code, 0, "{out}");
+    assert!(out.starts_with("# sign in\nok 1 goto "), "{out}");
+    assert!(out.contains("ok 2 type into label=\"Email\"\n"), "{out}");
+    assert!(!out.contains("ada@example.com"), "typed text stays out of the log: {out}");
+    assert!(out.contains("ok 4 click text=\"Sign in\" role=button\n"), "{out}");
+    assert!(out.contains("\n6 of 6 steps passed\n"), "{out}");
+    let tables = std::fs::read_to_string(dir.join("tables.json")).unwrap();
+    assert!(tables.contains("Starter"), "{tables}");
+
+    let (code, out) = run(&flow, &["--replay", fixtures.to_str().unwrap()]);
+    assert_eq!(code, 0, "{out}");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn run_fails_with_the_step_and_what_the_page_had() {
+    let url = common::serve_fixture(PAGE);
+    let dir = std::env::temp_dir().join(format!("browsy-run-fail-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let flow = dir.join("flow.json");
+    std::fs::write(
+        &flow,
+        format!(
+            r#"{{ "steps": [
+                {{ "goto": "{url}" }},
+                {{ "assert": {{ "page_type": "Dashboard" }} }},
+                {{ "click": {{ "text": "Sign in" }} }}
+            ] }}"#
+        ),
+    )
+    .unwrap();
+
+    let snapshots = dir.join("snapshots");
+    let (code, out) = run(&flow, &["--snapshot-on-failure", snapshots.to_str().unwrap()]);
+    assert_eq!(code, 1, "{out}");
+    assert!(out.contains("not ok 2 assert page_type=Dashboard\n"), "{out}");
+    assert!(out.contains("  - expected: Dashboard\n  + actual:   Login\n"), "{out}");
+    assert!(out.contains("  snapshot: "), "{out}");
+    assert!(!out.contains("ok 3"), "steps after the failure don't run: {out}");
+    assert!(out.contains("1 of 3 steps passed"), "{out}");
+    assert_eq!(std::fs::read_dir(&snapshots).unwrap().count(), 1);
+
+    std::fs::write(&flow, r#"{ "steps": [{ "wait": 5 }] }"#).unwrap();
+    let (code, out) = run(&flow, &[]);
+    assert_eq!(code, 2, "{out}");
+    assert!(out.contains("unknown variant `wait`"), "{out}");
+
+    let yaml = dir.join("typo.yaml");
+    std::fs::write(&yaml, "steps:\n  - type: { lable: Email, text: ada@example.com }\n").unwrap();
+    let (code, out) = run(&yaml, &[]);
+    assert_eq!(code, 2, "{out}");
+    assert!(out.contains("step 1 (type) gives nothing to match"), "{out}");
+    let _ = std::fs::remove_dir_all(&dir);
+}
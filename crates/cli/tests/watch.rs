@@ -0,0 +1,49 @@
+mod common;
+
+use std::process::Command;
+
+const PAGE: &str = r#"<html><head><title>Status</title></head>
+<body><h1>All systems operational</h1></body></html>"#;
+
+fn watch(url: &str, state_dir: &std::path::Path) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_browsy"))
+        .args(["watch", url, "--no-css", "--allow-private-network"])
+        .args(["--interval", "0", "--max-iterations", "2"])
+        .arg("--state-dir")
+        .arg(state_dir)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn watch_polls_and_resumes_from_state_dir() {
+    let url = common::serve_fixture(PAGE);
+    let state_dir = std::env::temp_dir().join(format!("browsy-watch-cli-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&state_dir);
+
+    let out = watch(&url, &state_dir);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(out.status.success(), "{stderr}");
+    assert!(stdout.contains("baseline"), "{stdout}");
+    assert!(stderr.contains("unchanged"), "{stderr}");
+    assert!(state_dir.join("last.json").exists());
+
+    let out = watch(&url, &state_dir);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("resuming from saved snapshot"), "{stderr}");
+    assert!(!stdout.contains("baseline"), "{stdout}");
+    assert!(!stdout.contains("changed"), "{stdout}");
+
+    let _ = std::fs::remove_dir_all(&state_dir);
+}
+
+#[test]
+fn watch_rejects_invalid_url() {
+    let out = Command::new(env!("CARGO_BIN_EXE_browsy"))
+        .args(["watch", "not a url", "--max-iterations", "1"])
+        .output()
+        .unwrap();
+    assert!(!out.status.success());
+}
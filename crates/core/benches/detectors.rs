@@ -0,0 +1,47 @@
+//! Page type and suggested action detection on a large page, on rayon's
+//! pool versus one detector after another.
+//!
+//! Run with:
+//!   cargo bench -p browsy-core --bench detectors
+
+use browsy_core::output::{self, OutputOptions};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// A page of about 10,000 elements: a search box, then rows of product
+/// cards with a heading, a description, a link, a field and a button.
+fn synthetic_page() -> String {
+    let mut html = String::from(
+        r#"<html><head><title>Catalog</title></head><body>
+        <input type="search" name="q" placeholder="Search products"><button>Search</button><main>"#,
+    );
+    for i in 0..1_250 {
+        html.push_str(&format!(
+            r#"<section><h2>Product {i}</h2>
+            <p>{}</p>
+            <a href="/item/{i}">View product {i}</a>
+            <label for="q{i}">Quantity</label><input id="q{i}" name="q{i}" value="1">
+            <button>Add to cart</button></section>"#,
+            "A sturdy everyday item, shipped within two days. ".repeat(4)
+        ));
+    }
+    html.push_str(r#"</main><a href="/page/2" rel="next">Next</a></body></html>"#);
+    html
+}
+
+fn bench_detectors(c: &mut Criterion) {
+    let dom_tree = browsy_core::dom::parse_html(&synthetic_page());
+    let styled = browsy_core::css::compute_styles_with_viewport(&dom_tree, 1920.0, 1080.0);
+    let laid_out = browsy_core::layout::compute_layout(&styled, 1920.0, 1080.0);
+
+    let mut group = c.benchmark_group("detectors");
+    for (name, parallel_min_elements) in [("sequential", usize::MAX), ("parallel", 0)] {
+        let options = OutputOptions { parallel_min_elements, ..Default::default() };
+        group.bench_function(name, |b| {
+            b.iter(|| output::generate_spatial_dom_with_options(black_box(&laid_out), 1920.0, 1080.0, &options))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_detectors);
+criterion_main!(benches);
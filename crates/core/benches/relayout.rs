@@ -0,0 +1,61 @@
+//! Re-rendering a large page after a dropdown toggle, laying out only the
+//! toggled menu versus the whole page.
+//!
+//! Run with:
+//!   cargo bench -p browsy-core --bench relayout
+
+use browsy_core::fetch::{Session, SessionConfig};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// A content-heavy page of about 8,000 elements with an account menu in its
+/// header.
+fn page_with_menu() -> String {
+    let mut html = String::from(
+        r#"<html><head><title>Menu</title></head><body>
+        <header style="display: flex; gap: 16px">
+            <a href="/">Home</a>
+            <button onclick="toggle('account')">Account</button>
+            <ul id="account" style="display: none">
+                <li><a href="/profile">Profile</a></li>
+                <li><a href="/settings">Settings</a></li>
+                <li><a href="/logout">Sign out</a></li>
+            </ul>
+        </header><main>"#,
+    );
+    for i in 0..1_000 {
+        html.push_str(&format!(
+            r#"<article style="display: flex; flex-wrap: wrap; width: 80%">
+            <h2>Story {i}</h2><p>{}</p><a href="/story/{i}">Read more</a>
+            <ul><li>Tag one</li><li>Tag two</li></ul></article>"#,
+            "Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(6)
+        ));
+    }
+    html.push_str("</main></body></html>");
+    html
+}
+
+fn session(incremental_relayout: bool, html: &str) -> (Session, u32) {
+    let mut session = Session::with_config(SessionConfig {
+        incremental_relayout,
+        ..SessionConfig::default()
+    })
+    .unwrap();
+    session.load_html_offline(html, "http://localhost/menu").unwrap();
+    let account = session.find_by_text_exact("Account")[0].id;
+    (session, account)
+}
+
+fn bench_toggle(c: &mut Criterion) {
+    let html = page_with_menu();
+    let mut group = c.benchmark_group("toggle");
+    group.sample_size(20);
+    for (name, incremental) in [("full", false), ("incremental", true)] {
+        let (mut session, account) = session(incremental, &html);
+        // Each click opens or closes the menu again
+        group.bench_function(name, |b| b.iter(|| black_box(session.click(account).unwrap())));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_toggle);
+criterion_main!(benches);
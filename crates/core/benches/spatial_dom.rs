@@ -0,0 +1,89 @@
+//! Formatting a large page through scoped views versus the cloning filters.
+//!
+//! Run with:
+//!   cargo bench -p browsy-core --bench spatial_dom
+
+use browsy_core::output::{self, Scope, SpatialDom, DEFAULT_HIDDEN_DROP};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Counts bytes allocated, so the report can show what the views save.
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// A page of about 10,000 elements: repeating cards with a heading, a long
+/// paragraph, a link, a field, a closed menu and aria-hidden decoration.
+fn synthetic_page() -> String {
+    let mut html = String::from("<html><head><title>Synthetic</title></head><body><main>");
+    for i in 0..1_250 {
+        html.push_str(&format!(
+            r#"<section><h2>Card {i}</h2>
+            <p>{}</p>
+            <a href="/item/{i}">Open item {i}</a>
+            <label for="q{i}">Quantity</label><input id="q{i}" name="q{i}" value="1">
+            <ul style="display:none"><li><a href="/more/{i}">More</a></li></ul>
+            <span aria-hidden="true">*</span></section>"#,
+            "Lorem ipsum dolor sit amet, consectetur adipiscing elit. ".repeat(8)
+        ));
+    }
+    html.push_str("</main></body></html>");
+    html
+}
+
+fn cloned(dom: &SpatialDom, scope: Scope) -> String {
+    let scoped = match scope {
+        Scope::Visible => dom.strip_hidden(DEFAULT_HIDDEN_DROP),
+        Scope::AboveFold => dom.filter_above_fold(),
+        Scope::Main => dom.filter_main_content(),
+        _ => dom.clone(),
+    };
+    output::to_compact_string(&scoped)
+}
+
+fn viewed(dom: &SpatialDom, scope: Scope) -> String {
+    dom.view(scope).to_compact_string()
+}
+
+fn allocated_by(f: impl FnOnce() -> String) -> usize {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    black_box(f());
+    ALLOCATED.load(Ordering::Relaxed) - before
+}
+
+fn bench_scopes(c: &mut Criterion) {
+    let dom = browsy_core::parse(&synthetic_page(), 1920.0, 1080.0);
+    println!("synthetic page: {} elements", dom.els.len());
+    for scope in [Scope::Visible, Scope::AboveFold, Scope::Main] {
+        println!(
+            "{scope:?}: cloned {} KiB allocated, view {} KiB allocated",
+            allocated_by(|| cloned(&dom, scope)) / 1024,
+            allocated_by(|| viewed(&dom, scope)) / 1024,
+        );
+    }
+
+    let mut group = c.benchmark_group("scope");
+    for scope in [Scope::Visible, Scope::AboveFold, Scope::Main] {
+        group.bench_function(format!("{scope:?}/cloned"), |b| b.iter(|| cloned(black_box(&dom), scope)));
+        group.bench_function(format!("{scope:?}/view"), |b| b.iter(|| viewed(black_box(&dom), scope)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_scopes);
+criterion_main!(benches);
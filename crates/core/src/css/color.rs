@@ -0,0 +1,140 @@
+//! CSS colors, kept for telling what text means (red errors, grey hints)
+//! rather than for drawing. Hex, `rgb()`, `hsl()` and the common named
+//! colors are understood; system colors and `color-mix()` are not.
+
+/// An sRGB color with its alpha, from 0 (transparent) to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: f32,
+}
+
+/// Named colors, as `0xRRGGBB`.
+const NAMED: &[(&str, u32)] = &[
+    ("black", 0x000000), ("white", 0xffffff), ("red", 0xff0000), ("green", 0x008000),
+    ("blue", 0x0000ff), ("yellow", 0xffff00), ("orange", 0xffa500), ("gray", 0x808080),
+    ("grey", 0x808080), ("silver", 0xc0c0c0), ("maroon", 0x800000), ("darkred", 0x8b0000),
+    ("crimson", 0xdc143c), ("firebrick", 0xb22222), ("indianred", 0xcd5c5c), ("tomato", 0xff6347),
+    ("orangered", 0xff4500), ("salmon", 0xfa8072), ("lightcoral", 0xf08080), ("brown", 0xa52a2a),
+    ("pink", 0xffc0cb), ("darkorange", 0xff8c00), ("gold", 0xffd700), ("goldenrod", 0xdaa520),
+    ("darkgreen", 0x006400), ("forestgreen", 0x228b22), ("seagreen", 0x2e8b57), ("limegreen", 0x32cd32),
+    ("lime", 0x00ff00), ("olive", 0x808000), ("teal", 0x008080), ("navy", 0x000080),
+    ("purple", 0x800080), ("fuchsia", 0xff00ff), ("magenta", 0xff00ff), ("aqua", 0x00ffff),
+    ("cyan", 0x00ffff), ("darkgray", 0xa9a9a9), ("darkgrey", 0xa9a9a9), ("dimgray", 0x696969),
+    ("dimgrey", 0x696969), ("lightgray", 0xd3d3d3), ("lightgrey", 0xd3d3d3), ("gainsboro", 0xdcdcdc),
+    ("whitesmoke", 0xf5f5f5), ("slategray", 0x708090), ("slategrey", 0x708090),
+    ("lightslategray", 0x778899), ("darkslategray", 0x2f4f4f),
+];
+
+impl Color {
+    /// Parse a CSS color value. `None` for anything not understood,
+    /// including `inherit` and `currentcolor`.
+    pub fn parse(value: &str) -> Option<Color> {
+        let value = value.trim().to_ascii_lowercase();
+        if let Some(hex) = value.strip_prefix('#') {
+            return from_hex(hex);
+        }
+        if let Some((name, args)) = value.strip_suffix(')').and_then(|v| v.split_once('(')) {
+            let args: Vec<&str> =
+                args.split(|c: char| c == ',' || c == '/' || c.is_whitespace()).filter(|a| !a.is_empty()).collect();
+            return match name.trim() {
+                "rgb" | "rgba" => from_rgb(&args),
+                "hsl" | "hsla" => from_hsl(&args),
+                _ => None,
+            };
+        }
+        if value == "transparent" {
+            return Some(Color { r: 0, g: 0, b: 0, a: 0.0 });
+        }
+        NAMED.iter().find(|(name, _)| *name == value).map(|(_, rgb)| rgb_u32(*rgb))
+    }
+
+    /// Hue in degrees, then saturation and lightness from 0 to 1.
+    pub fn to_hsl(self) -> (f32, f32, f32) {
+        let [r, g, b] = [self.r, self.g, self.b].map(|c| c as f32 / 255.0);
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let lightness = (max + min) / 2.0;
+        let delta = max - min;
+        if delta == 0.0 {
+            return (0.0, 0.0, lightness);
+        }
+        let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+        let hue = if max == r {
+            60.0 * ((g - b) / delta).rem_euclid(6.0)
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        (hue, saturation.min(1.0), lightness)
+    }
+}
+
+fn rgb_u32(rgb: u32) -> Color {
+    Color { r: (rgb >> 16) as u8, g: (rgb >> 8) as u8, b: rgb as u8, a: 1.0 }
+}
+
+/// `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`, without the `#`.
+fn from_hex(hex: &str) -> Option<Color> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digits: Vec<u8> = match hex.len() {
+        3 | 4 => hex.chars().map(|c| u8::from_str_radix(&c.to_string().repeat(2), 16).ok()).collect::<Option<_>>()?,
+        6 | 8 => (0..hex.len()).step_by(2).map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok()).collect::<Option<_>>()?,
+        _ => return None,
+    };
+    let a = digits.get(3).map_or(1.0, |a| *a as f32 / 255.0);
+    Some(Color { r: digits[0], g: digits[1], b: digits[2], a })
+}
+
+/// A channel from 0 to 255, or a percentage of it.
+fn channel(value: &str) -> Option<u8> {
+    let v = match value.strip_suffix('%') {
+        Some(pct) => pct.parse::<f32>().ok()? * 2.55,
+        None => value.parse::<f32>().ok()?,
+    };
+    Some(v.clamp(0.0, 255.0).round() as u8)
+}
+
+/// An alpha from 0 to 1, or a percentage; opaque when left out.
+fn alpha(value: Option<&&str>) -> Option<f32> {
+    let Some(value) = value else { return Some(1.0) };
+    let a = match value.strip_suffix('%') {
+        Some(pct) => pct.parse::<f32>().ok()? / 100.0,
+        None => value.parse::<f32>().ok()?,
+    };
+    Some(a.clamp(0.0, 1.0))
+}
+
+fn from_rgb(args: &[&str]) -> Option<Color> {
+    if !(3..=4).contains(&args.len()) {
+        return None;
+    }
+    Some(Color { r: channel(args[0])?, g: channel(args[1])?, b: channel(args[2])?, a: alpha(args.get(3))? })
+}
+
+fn from_hsl(args: &[&str]) -> Option<Color> {
+    if !(3..=4).contains(&args.len()) {
+        return None;
+    }
+    let hue = args[0].trim_end_matches("deg").parse::<f32>().ok()?.rem_euclid(360.0);
+    let percent = |v: &str| v.trim_end_matches('%').parse::<f32>().ok().map(|p| (p / 100.0).clamp(0.0, 1.0));
+    let (saturation, lightness) = (percent(args[1])?, percent(args[2])?);
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let x = chroma * (1.0 - ((hue / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let (r, g, b) = match hue as u32 / 60 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    let [r, g, b] = [r, g, b].map(|c| ((c + m) * 255.0).round().clamp(0.0, 255.0) as u8);
+    Some(Color { r, g, b, a: alpha(args.get(3))? })
+}
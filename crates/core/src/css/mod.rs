@@ -1,12 +1,15 @@
+pub mod color;
 pub mod properties;
 pub mod selector;
 
 use crate::dom::{DomNode, NodeType};
-use properties::{parse_dimension, parse_inline_style_with_vars};
-use selector::{parse_stylesheet, matches_element, CssRule, SelectorIndex};
+use color::Color;
+use properties::{collect_custom_props, parse_dimension, parse_inline_style_with_vars};
+use selector::{parse_stylesheet, matches_element, Ancestor, CssRule, SelectorIndex};
 
 /// Computed layout styles for a single element.
-/// Only the ~40 properties that affect bounding box computation.
+/// Only the ~40 properties that affect bounding box computation, plus the
+/// colors that say what text means.
 #[derive(Debug, Clone)]
 pub struct LayoutStyle {
     pub display: Display,
@@ -42,6 +45,13 @@ pub struct LayoutStyle {
     pub grid_column: Option<GridPlacement>,
     pub grid_row: Option<GridPlacement>,
     pub overflow: Overflow,
+    /// `clip` or `clip-path` cuts the whole box away, as screen-reader-only
+    /// classes do.
+    pub clip_hidden: bool,
+    /// Text color, inherited like in a browser.
+    pub color: Option<Color>,
+    /// Not inherited: a child over a red box has no background of its own.
+    pub background_color: Option<Color>,
 }
 
 impl Default for LayoutStyle {
@@ -80,6 +90,9 @@ impl Default for LayoutStyle {
             grid_column: None,
             grid_row: None,
             overflow: Overflow::Visible,
+            clip_hidden: false,
+            color: None,
+            background_color: None,
         }
     }
 }
@@ -104,7 +117,7 @@ impl Edges {
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub enum Position { Static, Relative, Absolute, Fixed }
+pub enum Position { Static, Relative, Absolute, Fixed, Sticky }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum FlexDirection { Row, RowReverse, Column, ColumnReverse }
@@ -139,6 +152,57 @@ pub struct StyledNode {
     pub node_type: NodeType,
     pub style: LayoutStyle,
     pub children: Vec<StyledNode>,
+    /// Line and column of the element's start tag, if positions were tracked.
+    pub src: Option<[u32; 2]>,
+}
+
+// --- Viewport meta ---
+
+/// Width mobile browsers lay out pages at when they have no viewport meta tag.
+pub const LEGACY_LAYOUT_WIDTH: f32 = 980.0;
+
+/// Layout width for a page shown on a screen `device_width` pixels wide.
+///
+/// Narrow (mobile) screens honor `<meta name="viewport">`: `width=device-width`
+/// lays out at the device width, `width=N` at N pixels, and pages without the
+/// tag at [`LEGACY_LAYOUT_WIDTH`]. Screens at least that wide use their own width.
+pub fn layout_viewport_width(dom: &DomNode, device_width: f32) -> f32 {
+    if device_width >= LEGACY_LAYOUT_WIDTH {
+        return device_width;
+    }
+    let Some(content) = find_viewport_meta(dom) else {
+        return LEGACY_LAYOUT_WIDTH;
+    };
+
+    let mut width = None;
+    let mut scale = None;
+    for (key, value) in content
+        .split([',', ';'])
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(k, v)| (k.trim().to_ascii_lowercase(), v.trim()))
+    {
+        match key.as_str() {
+            "width" if value.eq_ignore_ascii_case("device-width") => width = Some(device_width),
+            "width" => width = value.parse::<f32>().ok().filter(|w| *w >= 1.0).map(|w| w.min(10_000.0)),
+            "initial-scale" => scale = value.parse::<f32>().ok().filter(|s| *s > 0.0),
+            _ => {}
+        }
+    }
+    // `initial-scale` alone implies a device-width layout at that zoom
+    width
+        .or_else(|| scale.map(|s| device_width / s))
+        .unwrap_or(LEGACY_LAYOUT_WIDTH)
+}
+
+fn find_viewport_meta(node: &DomNode) -> Option<&str> {
+    if node.tag == "meta"
+        && node
+            .get_attr("name")
+            .is_some_and(|n| n.eq_ignore_ascii_case("viewport"))
+    {
+        return node.get_attr("content");
+    }
+    node.children.iter().find_map(find_viewport_meta)
 }
 
 // --- Style computation ---
@@ -150,11 +214,7 @@ pub fn compute_styles(dom: &DomNode) -> StyledNode {
 
 /// Apply styles with viewport dimensions for @media query evaluation.
 pub fn compute_styles_with_viewport(dom: &DomNode, viewport_width: f32, viewport_height: f32) -> StyledNode {
-    let css_text = extract_style_tags(dom);
-    let rules = parse_stylesheet(&css_text, viewport_width, viewport_height);
-    let index = SelectorIndex::build(&rules);
-    let custom_props = std::collections::HashMap::new();
-    style_node(dom, &rules, &index, &[], None, &custom_props)
+    Stylesheet::new(dom, "", viewport_width, viewport_height).style(dom)
 }
 
 /// Apply styles including external CSS (fetched from <link> tags).
@@ -169,123 +229,204 @@ pub fn compute_styles_with_external_and_viewport(
     viewport_width: f32,
     viewport_height: f32,
 ) -> StyledNode {
-    let mut css_text = external_css.to_string();
-    css_text.push('\n');
-    css_text.push_str(&extract_style_tags(dom));
-    let rules = parse_stylesheet(&css_text, viewport_width, viewport_height);
-    let index = SelectorIndex::build(&rules);
-    let custom_props = std::collections::HashMap::new();
-    style_node(dom, &rules, &index, &[], None, &custom_props)
+    Stylesheet::new(dom, external_css, viewport_width, viewport_height).style(dom)
 }
 
-fn extract_style_tags(node: &DomNode) -> String {
-    let mut css = String::new();
-    if node.tag == "style" {
-        for child in &node.children {
-            if child.node_type == NodeType::Text {
-                css.push_str(&child.text);
-                css.push('\n');
-            }
-        }
+/// The parsed rules of a page: its `<style>` tags after `external_css`, with
+/// @media queries evaluated for one viewport. Kept to restyle parts of the
+/// page later without parsing them again.
+pub struct Stylesheet {
+    rules: Vec<CssRule>,
+    index: SelectorIndex,
+    viewport: (f32, f32),
+}
+
+impl Stylesheet {
+    pub fn new(dom: &DomNode, external_css: &str, viewport_width: f32, viewport_height: f32) -> Self {
+        let css_text = if external_css.is_empty() {
+            extract_style_tags(dom)
+        } else {
+            format!("{external_css}\n{}", extract_style_tags(dom))
+        };
+        let rules = parse_stylesheet(&css_text, viewport_width, viewport_height);
+        let index = SelectorIndex::build(&rules);
+        Self { rules, index, viewport: (viewport_width, viewport_height) }
     }
-    for child in &node.children {
-        css.push_str(&extract_style_tags(child));
+
+    /// Style the whole tree.
+    pub fn style(&self, dom: &DomNode) -> StyledNode {
+        let custom_props = std::collections::HashMap::new();
+        self.style_node(dom, &[], None, &custom_props)
     }
-    css
-}
 
-fn style_node(
-    node: &DomNode,
-    rules: &[CssRule],
-    index: &SelectorIndex,
-    ancestors: &[(String, Vec<String>, Option<String>)],
-    parent_style: Option<&LayoutStyle>,
-    inherited_props: &std::collections::HashMap<String, String>,
-) -> StyledNode {
-    let mut style = default_style_for_tag(&node.tag);
-    // Inherit custom properties from parent (they cascade)
-    let mut custom_props = inherited_props.clone();
-
-    // Inherit from parent
-    if let Some(parent) = parent_style {
-        let has_ua_font_size = matches!(
-            node.tag.as_str(),
-            "h1" | "h2" | "h3" | "h4" | "h5" | "h6"
-        );
-        if !has_ua_font_size {
-            style.font_size = parent.font_size;
+    /// Style the node at `path` (child indices from the root) and its
+    /// descendants again from `dom`, replacing them in `styled`, a styling
+    /// of a tree the same shape as `dom`. Selectors only look at ancestors,
+    /// so nothing outside the subtree can change. Returns false, changing
+    /// nothing, when there is no node at `path`.
+    pub fn restyle(&self, dom: &DomNode, styled: &mut StyledNode, path: &[usize]) -> bool {
+        let mut ancestors = Vec::new();
+        let mut parent_style = None;
+        let mut custom_props = std::collections::HashMap::new();
+        let mut node = dom;
+        let mut target = styled;
+        for &i in path {
+            let (style, props) = self.cascade(node, &ancestors, parent_style.as_ref(), &custom_props);
+            if node.node_type == NodeType::Element {
+                ancestors.push(ancestor(node));
+            }
+            let (Some(child), Some(styled_child)) = (node.children.get(i), target.children.get_mut(i)) else {
+                return false;
+            };
+            parent_style = Some(style);
+            custom_props = props;
+            node = child;
+            target = styled_child;
         }
-        style.line_height = parent.line_height;
+        *target = self.style_node(node, &ancestors, parent_style.as_ref(), &custom_props);
+        true
     }
 
-    // Apply matching stylesheet rules
-    if node.node_type == NodeType::Element {
-        let classes = get_classes(node);
-        let id = node.get_attr("id");
+    fn style_node<'a>(
+        &self,
+        node: &'a DomNode,
+        ancestors: &[Ancestor<'a>],
+        parent_style: Option<&LayoutStyle>,
+        inherited_props: &std::collections::HashMap<String, String>,
+    ) -> StyledNode {
+        let (style, custom_props) = self.cascade(node, ancestors, parent_style, inherited_props);
+
+        // Build ancestry for children
+        let mut child_ancestors = ancestors.to_vec();
+        if node.node_type == NodeType::Element {
+            child_ancestors.push(ancestor(node));
+        }
 
-        let candidates = index.candidates_for(&node.tag, &classes, id);
-        let mut matched: Vec<(&CssRule, u32)> = candidates
+        let children = node
+            .children
             .iter()
-            .filter_map(|&idx| rules.get(idx))
-            .filter(|rule| {
-                rule.selectors.iter().any(|sel| {
-                    matches_element(sel, &node.tag, &classes, id, &node.attributes, ancestors)
-                })
-            })
-            .map(|rule| (rule, rule.specificity))
+            .map(|c| self.style_node(c, &child_ancestors, Some(&style), &custom_props))
             .collect();
 
-        matched.sort_by_key(|(_, spec)| *spec);
+        StyledNode {
+            tag: node.tag.clone(),
+            attributes: node.attributes.clone(),
+            text: node.text.clone(),
+            node_type: node.node_type.clone(),
+            style,
+            children,
+            src: node.src,
+        }
+    }
+
+    /// The computed style of one node, and the custom properties its
+    /// children inherit.
+    fn cascade(
+        &self,
+        node: &DomNode,
+        ancestors: &[Ancestor<'_>],
+        parent_style: Option<&LayoutStyle>,
+        inherited_props: &std::collections::HashMap<String, String>,
+    ) -> (LayoutStyle, std::collections::HashMap<String, String>) {
+        let viewport = self.viewport;
+        let mut style = default_style_for_tag(&node.tag);
+        // Inherit custom properties from parent (they cascade)
+        let mut custom_props = inherited_props.clone();
+
+        // Inherit from parent
+        if let Some(parent) = parent_style {
+            let has_ua_font_size = matches!(
+                node.tag.as_str(),
+                "h1" | "h2" | "h3" | "h4" | "h5" | "h6"
+            );
+            if !has_ua_font_size {
+                style.font_size = parent.font_size;
+            }
+            style.line_height = parent.line_height;
+            style.color = parent.color;
+        }
+
+        // Collect matching stylesheet rules
+        let mut matched: Vec<(&CssRule, u32)> = Vec::new();
+        if node.node_type == NodeType::Element {
+            let classes = get_classes(node);
+            let id = node.get_attr("id");
+
+            let candidates = self.index.candidates_for(&node.tag, &classes, id);
+            matched = candidates
+                .iter()
+                .filter_map(|&idx| self.rules.get(idx))
+                .filter(|rule| {
+                    rule.selectors.iter().any(|sel| {
+                        matches_element(sel, &node.tag, &classes, id, &node.attributes, ancestors)
+                    })
+                })
+                .map(|rule| (rule, rule.specificity))
+                .collect();
+
+            matched.sort_by_key(|(_, spec)| *spec);
+        }
+        let inline = node.get_attr("style");
 
+        // Custom properties first, so every var() on this element sees the winning values
         for (rule, _) in &matched {
-            parse_inline_style_with_vars(&rule.declarations, &mut style, Some(&mut custom_props));
+            collect_custom_props(&rule.declarations, &mut custom_props);
+        }
+        if let Some(inline) = inline {
+            collect_custom_props(inline, &mut custom_props);
         }
-    }
 
-    // Inline style (highest priority)
-    if let Some(inline) = node.get_attr("style") {
-        parse_inline_style_with_vars(inline, &mut style, Some(&mut custom_props));
-    }
+        for (rule, _) in &matched {
+            parse_inline_style_with_vars(&rule.declarations, &mut style, &custom_props, viewport);
+        }
 
-    // HTML attributes
-    if node.attributes.contains_key("hidden") {
-        style.display = Display::None;
-    }
-    if let Some(w) = node.get_attr("width") {
-        if let Some(dim) = parse_dimension(w) {
-            style.width = dim;
+        // Inline style (highest priority)
+        if let Some(inline) = inline {
+            parse_inline_style_with_vars(inline, &mut style, &custom_props, viewport);
         }
-    }
-    if let Some(h) = node.get_attr("height") {
-        if let Some(dim) = parse_dimension(h) {
-            style.height = dim;
+
+        // HTML attributes
+        if node.attributes.contains_key("hidden") {
+            style.display = Display::None;
+        }
+        if let Some(w) = node.get_attr("width") {
+            if let Some(dim) = parse_dimension(w) {
+                style.width = dim;
+            }
+        }
+        if let Some(h) = node.get_attr("height") {
+            if let Some(dim) = parse_dimension(h) {
+                style.height = dim;
+            }
         }
+
+        (style, custom_props)
     }
+}
 
-    // Build ancestry for children
-    let mut child_ancestors = ancestors.to_vec();
-    if node.node_type == NodeType::Element {
-        child_ancestors.push((
-            node.tag.clone(),
-            get_classes(node),
-            node.get_attr("id").map(|s| s.to_string()),
-        ));
+fn ancestor(node: &DomNode) -> Ancestor<'_> {
+    Ancestor {
+        tag: &node.tag,
+        classes: get_classes(node),
+        id: node.get_attr("id"),
+        attrs: &node.attributes,
     }
+}
 
-    let children = node
-        .children
-        .iter()
-        .map(|c| style_node(c, rules, index, &child_ancestors, Some(&style), &custom_props))
-        .collect();
-
-    StyledNode {
-        tag: node.tag.clone(),
-        attributes: node.attributes.clone(),
-        text: node.text.clone(),
-        node_type: node.node_type.clone(),
-        style,
-        children,
+fn extract_style_tags(node: &DomNode) -> String {
+    let mut css = String::new();
+    if node.tag == "style" {
+        for child in &node.children {
+            if child.node_type == NodeType::Text {
+                css.push_str(&child.text);
+                css.push('\n');
+            }
+        }
     }
+    for child in &node.children {
+        css.push_str(&extract_style_tags(child));
+    }
+    css
 }
 
 fn get_classes(node: &DomNode) -> Vec<String> {
@@ -347,7 +488,7 @@ fn default_style_for_tag(tag: &str) -> LayoutStyle {
             style.height = Dimension::Px(21.0);
         }
         "img" => { style.display = Display::InlineBlock; }
-        "head" | "meta" | "link" | "title" | "script" | "style" | "noscript" => {
+        "head" | "meta" | "link" | "title" | "script" | "style" | "template" => {
             style.display = Display::None;
         }
         "body" => {
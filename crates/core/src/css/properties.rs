@@ -4,98 +4,150 @@ use super::*;
 
 /// Root font size for rem calculations (browser default).
 pub(crate) const ROOT_FONT_SIZE: f32 = 16.0;
+/// Viewport assumed when none is given.
+const DEFAULT_VIEWPORT: (f32, f32) = (1920.0, 1080.0);
+
+/// What relative lengths resolve against: the font size for `em` and the
+/// viewport for `vw`, `vh`, `vmin` and `vmax`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Units {
+    pub em: f32,
+    pub viewport: (f32, f32),
+}
 
-/// Parse declarations with CSS variable resolution.
-pub(crate) fn parse_inline_style_with_vars(
+impl Units {
+    /// Root font size and the default viewport.
+    const ROOT: Units = Units { em: ROOT_FONT_SIZE, viewport: DEFAULT_VIEWPORT };
+}
+
+/// Maximum `var()` substitution depth; deeper (or cyclic) references are invalid.
+const MAX_VAR_DEPTH: usize = 16;
+
+/// Split a declaration block into `(property, value)` pairs. Property names are
+/// lowercased except custom properties, whose names are case-sensitive.
+fn declarations(style_str: &str) -> impl Iterator<Item = (String, &str)> {
+    style_str.split(';').filter_map(|declaration| {
+        let (property, value) = declaration.trim().split_once(':')?;
+        let property = property.trim();
+        let property = if property.starts_with("--") {
+            property.to_string()
+        } else {
+            property.to_lowercase()
+        };
+        Some((property, value.trim().trim_end_matches("!important").trim()))
+    })
+}
+
+/// Record custom property declarations (`--name: value`) from a declaration block.
+/// Run over every rule matching an element before applying any of them, so a
+/// variable defined by a more specific rule is visible to all `var()` uses.
+pub(crate) fn collect_custom_props(
     style_str: &str,
-    style: &mut LayoutStyle,
-    mut custom_props: Option<&mut std::collections::HashMap<String, String>>,
+    custom_props: &mut std::collections::HashMap<String, String>,
 ) {
-    for declaration in style_str.split(';') {
-        let declaration = declaration.trim();
-        if declaration.is_empty() {
-            continue;
+    for (property, value) in declarations(style_str) {
+        if property.starts_with("--") {
+            custom_props.insert(property, value.to_string());
         }
-        let mut parts = declaration.splitn(2, ':');
-        let property = match parts.next() {
-            Some(p) => p.trim().to_lowercase(),
-            None => continue,
-        };
-        let raw_value = match parts.next() {
-            Some(v) => v.trim(),
-            None => continue,
-        };
-        let value = raw_value.trim_end_matches("!important").trim();
+    }
+}
 
-        // Store custom property declarations (--var-name: value)
+/// Parse declarations, substituting `var()` references from `custom_props`.
+/// Declarations whose variables cannot be resolved are skipped.
+pub(crate) fn parse_inline_style_with_vars(
+    style_str: &str,
+    style: &mut LayoutStyle,
+    custom_props: &std::collections::HashMap<String, String>,
+    viewport: (f32, f32),
+) {
+    for (property, value) in declarations(style_str) {
         if property.starts_with("--") {
-            if let Some(props) = custom_props.as_deref_mut() {
-                props.insert(property.clone(), value.to_string());
-            }
             continue;
         }
-
-        // Resolve var() references in the value
-        let resolved = if let Some(props) = &custom_props {
-            resolve_vars(value, props)
-        } else {
-            value.to_string()
-        };
-
-        apply_property(&property, &resolved, style);
+        if let Some(resolved) = resolve_vars(value, custom_props, 0) {
+            apply_property(&property, &resolved, style, viewport);
+        }
     }
 }
 
 /// Resolve `var(--name)` and `var(--name, fallback)` references in a CSS value.
-fn resolve_vars(value: &str, custom_props: &std::collections::HashMap<String, String>) -> String {
+/// Returns `None` when a variable is undefined and has no fallback.
+fn resolve_vars(
+    value: &str,
+    custom_props: &std::collections::HashMap<String, String>,
+    depth: usize,
+) -> Option<String> {
     if !value.contains("var(") {
-        return value.to_string();
+        return Some(value.to_string());
+    }
+    if depth >= MAX_VAR_DEPTH {
+        return None;
     }
 
     let mut result = String::with_capacity(value.len());
-    let mut chars = value.chars().peekable();
-
-    while let Some(c) = chars.next() {
-        if c == 'v' {
-            // Check for "var("
-            let rest: String = chars.clone().take(3).collect();
-            if rest == "ar(" {
-                // Consume "ar("
-                chars.next(); chars.next(); chars.next();
-                // Read var content until matching ')'
-                let mut depth = 1;
-                let mut var_content = String::new();
-                while let Some(vc) = chars.next() {
-                    if vc == '(' { depth += 1; }
-                    if vc == ')' {
-                        depth -= 1;
-                        if depth == 0 { break; }
+    let mut rest = value;
+    while let Some(start) = rest.find("var(") {
+        result.push_str(&rest[..start]);
+        // Read var content until matching ')'
+        let inner = &rest[start + 4..];
+        let mut depth_parens = 1;
+        let mut end = inner.len();
+        for (i, c) in inner.char_indices() {
+            match c {
+                '(' => depth_parens += 1,
+                ')' => {
+                    depth_parens -= 1;
+                    if depth_parens == 0 {
+                        end = i;
+                        break;
                     }
-                    var_content.push(vc);
-                }
-                // Parse: var(--name) or var(--name, fallback)
-                let (var_name, fallback) = match var_content.split_once(',') {
-                    Some((name, fb)) => (name.trim(), Some(fb.trim())),
-                    None => (var_content.trim(), None),
-                };
-                // Look up the variable
-                if let Some(val) = custom_props.get(var_name) {
-                    // Recursively resolve (variables can reference other variables)
-                    result.push_str(&resolve_vars(val, custom_props));
-                } else if let Some(fb) = fallback {
-                    result.push_str(&resolve_vars(fb, custom_props));
                 }
-                // If neither found, the property is invalid — leave empty
-                continue;
+                _ => {}
             }
         }
-        result.push(c);
+        let var_content = &inner[..end];
+        rest = inner.get(end + 1..).unwrap_or_default();
+
+        // Parse: var(--name) or var(--name, fallback)
+        let (var_name, fallback) = match var_content.split_once(',') {
+            Some((name, fb)) => (name.trim(), Some(fb.trim())),
+            None => (var_content.trim(), None),
+        };
+        // Variables can reference other variables, and fallbacks can nest var()
+        let substituted = match custom_props.get(var_name) {
+            Some(val) => resolve_vars(val, custom_props, depth + 1),
+            None => None,
+        };
+        match substituted.or_else(|| resolve_vars(fallback?, custom_props, depth + 1)) {
+            Some(resolved) => result.push_str(&resolved),
+            None => return None,
+        }
     }
+    result.push_str(rest);
 
-    result
+    Some(result)
 }
 
-fn apply_property(property: &str, value: &str, style: &mut LayoutStyle) {
+/// Whether a `clip` or `clip-path` value leaves nothing of the box: an
+/// empty `rect()`, or an `inset()` of half the box or more.
+fn clips_everything(value: &str) -> bool {
+    let args = |name: &str| -> Option<Vec<f32>> {
+        let inner = value.trim().strip_prefix(name)?.trim_start().strip_prefix('(')?.strip_suffix(')')?;
+        inner
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
+            .map(|part| part.trim_end_matches("px").trim_end_matches('%').parse().ok())
+            .collect()
+    };
+    if let Some(rect) = args("rect") {
+        // rect(top, right, bottom, left)
+        return matches!(rect[..], [top, right, bottom, left] if bottom <= top || right <= left);
+    }
+    args("inset").is_some_and(|inset| value.contains('%') && inset.first().is_some_and(|v| *v >= 50.0))
+}
+
+fn apply_property(property: &str, value: &str, style: &mut LayoutStyle, viewport: (f32, f32)) {
+    let units = Units { em: style.font_size, viewport };
     match property {
         "display" => {
             style.display = match value {
@@ -119,98 +171,99 @@ fn apply_property(property: &str, value: &str, style: &mut LayoutStyle) {
                 "relative" => Position::Relative,
                 "absolute" => Position::Absolute,
                 "fixed" => Position::Fixed,
+                "sticky" | "-webkit-sticky" => Position::Sticky,
                 _ => Position::Static,
             };
         }
         "width" => {
-            if let Some(dim) = parse_dimension_with_context(value, style.font_size) {
+            if let Some(dim) = parse_dimension_with_context(value, units) {
                 style.width = dim;
             }
         }
         "height" => {
-            if let Some(dim) = parse_dimension_with_context(value, style.font_size) {
+            if let Some(dim) = parse_dimension_with_context(value, units) {
                 style.height = dim;
             }
         }
         "min-width" => {
-            if let Some(dim) = parse_dimension_with_context(value, style.font_size) {
+            if let Some(dim) = parse_dimension_with_context(value, units) {
                 style.min_width = dim;
             }
         }
         "min-height" => {
-            if let Some(dim) = parse_dimension_with_context(value, style.font_size) {
+            if let Some(dim) = parse_dimension_with_context(value, units) {
                 style.min_height = dim;
             }
         }
         "max-width" => {
-            if let Some(dim) = parse_dimension_with_context(value, style.font_size) {
+            if let Some(dim) = parse_dimension_with_context(value, units) {
                 style.max_width = dim;
             }
         }
         "max-height" => {
-            if let Some(dim) = parse_dimension_with_context(value, style.font_size) {
+            if let Some(dim) = parse_dimension_with_context(value, units) {
                 style.max_height = dim;
             }
         }
-        "margin" => style.margin = parse_edges_with_context(value, style.font_size),
+        "margin" => style.margin = parse_edges_with_context(value, units),
         "margin-top" => {
-            if let Some(v) = parse_length(value, style.font_size) {
+            if let Some(v) = parse_length(value, units) {
                 style.margin.top = v;
             }
         }
         "margin-right" => {
-            if let Some(v) = parse_length(value, style.font_size) {
+            if let Some(v) = parse_length(value, units) {
                 style.margin.right = v;
             }
         }
         "margin-bottom" => {
-            if let Some(v) = parse_length(value, style.font_size) {
+            if let Some(v) = parse_length(value, units) {
                 style.margin.bottom = v;
             }
         }
         "margin-left" => {
-            if let Some(v) = parse_length(value, style.font_size) {
+            if let Some(v) = parse_length(value, units) {
                 style.margin.left = v;
             }
         }
-        "padding" => style.padding = parse_edges_with_context(value, style.font_size),
+        "padding" => style.padding = parse_edges_with_context(value, units),
         "padding-top" => {
-            if let Some(v) = parse_length(value, style.font_size) {
+            if let Some(v) = parse_length(value, units) {
                 style.padding.top = v;
             }
         }
         "padding-right" => {
-            if let Some(v) = parse_length(value, style.font_size) {
+            if let Some(v) = parse_length(value, units) {
                 style.padding.right = v;
             }
         }
         "padding-bottom" => {
-            if let Some(v) = parse_length(value, style.font_size) {
+            if let Some(v) = parse_length(value, units) {
                 style.padding.bottom = v;
             }
         }
         "padding-left" => {
-            if let Some(v) = parse_length(value, style.font_size) {
+            if let Some(v) = parse_length(value, units) {
                 style.padding.left = v;
             }
         }
         "top" => {
-            if let Some(dim) = parse_dimension_with_context(value, style.font_size) {
+            if let Some(dim) = parse_dimension_with_context(value, units) {
                 style.top = dim;
             }
         }
         "right" => {
-            if let Some(dim) = parse_dimension_with_context(value, style.font_size) {
+            if let Some(dim) = parse_dimension_with_context(value, units) {
                 style.right = dim;
             }
         }
         "bottom" => {
-            if let Some(dim) = parse_dimension_with_context(value, style.font_size) {
+            if let Some(dim) = parse_dimension_with_context(value, units) {
                 style.bottom = dim;
             }
         }
         "left" => {
-            if let Some(dim) = parse_dimension_with_context(value, style.font_size) {
+            if let Some(dim) = parse_dimension_with_context(value, units) {
                 style.left = dim;
             }
         }
@@ -240,7 +293,7 @@ fn apply_property(property: &str, value: &str, style: &mut LayoutStyle) {
             }
         }
         "flex-basis" => {
-            if let Some(dim) = parse_dimension_with_context(value, style.font_size) {
+            if let Some(dim) = parse_dimension_with_context(value, units) {
                 style.flex_basis = dim;
             }
         }
@@ -285,20 +338,20 @@ fn apply_property(property: &str, value: &str, style: &mut LayoutStyle) {
             };
         }
         "gap" => {
-            if let Some(v) = parse_length(value, style.font_size) {
+            if let Some(v) = parse_length(value, units) {
                 style.gap = v;
             }
         }
         "font-size" => {
             // For font-size, em is relative to the inherited (parent) font-size
-            if let Some(v) = parse_length(value, style.font_size) {
+            if let Some(v) = parse_length(value, units) {
                 style.font_size = v;
             }
         }
         "line-height" => {
             if let Ok(v) = value.parse::<f32>() {
                 style.line_height = v;
-            } else if let Some(v) = parse_length(value, style.font_size) {
+            } else if let Some(v) = parse_length(value, units) {
                 style.line_height = v / style.font_size;
             }
         }
@@ -310,6 +363,25 @@ fn apply_property(property: &str, value: &str, style: &mut LayoutStyle) {
                 _ => Overflow::Visible,
             };
         }
+        "clip" | "clip-path" => {
+            style.clip_hidden = clips_everything(value);
+        }
+        "color" => {
+            if let Some(color) = Color::parse(value) {
+                style.color = Some(color);
+            }
+        }
+        "background-color" => {
+            if let Some(color) = Color::parse(value) {
+                style.background_color = Some(color);
+            }
+        }
+        "background" => {
+            // The color can sit anywhere in the shorthand, next to images and positions
+            if let Some(color) = Color::parse(value).or_else(|| value.split_whitespace().find_map(Color::parse)) {
+                style.background_color = Some(color);
+            }
+        }
         "box-sizing" => {
             style.box_sizing = match value {
                 "border-box" => BoxSizing::BorderBox,
@@ -323,22 +395,22 @@ fn apply_property(property: &str, value: &str, style: &mut LayoutStyle) {
         }
         "border-width" => style.border_width = parse_edges(value),
         "border-top-width" => {
-            if let Some(v) = parse_length(value, style.font_size) {
+            if let Some(v) = parse_length(value, units) {
                 style.border_width.top = v;
             }
         }
         "border-right-width" => {
-            if let Some(v) = parse_length(value, style.font_size) {
+            if let Some(v) = parse_length(value, units) {
                 style.border_width.right = v;
             }
         }
         "border-bottom-width" => {
-            if let Some(v) = parse_length(value, style.font_size) {
+            if let Some(v) = parse_length(value, units) {
                 style.border_width.bottom = v;
             }
         }
         "border-left-width" => {
-            if let Some(v) = parse_length(value, style.font_size) {
+            if let Some(v) = parse_length(value, units) {
                 style.border_width.left = v;
             }
         }
@@ -352,8 +424,8 @@ fn apply_property(property: &str, value: &str, style: &mut LayoutStyle) {
 
 // --- Value parsers ---
 
-/// Parse a CSS length value (px, em, rem) to pixels.
-fn parse_length(value: &str, em_base: f32) -> Option<f32> {
+/// Parse a CSS length value (px, em, rem, vw, vh, vmin, vmax) to pixels.
+fn parse_length(value: &str, units: Units) -> Option<f32> {
     let value = value.trim();
     if value == "0" {
         return Some(0.0);
@@ -367,7 +439,7 @@ fn parse_length(value: &str, em_base: f32) -> Option<f32> {
             .trim()
             .parse::<f32>()
             .ok()
-            .map(|v| v * em_base);
+            .map(|v| v * units.em);
     }
     if value.ends_with("rem") {
         return value
@@ -377,32 +449,51 @@ fn parse_length(value: &str, em_base: f32) -> Option<f32> {
             .ok()
             .map(|v| v * ROOT_FONT_SIZE);
     }
-    None
+    let (unit, per_percent) = viewport_unit(value, units.viewport)?;
+    value
+        .trim_end_matches(unit)
+        .trim()
+        .parse::<f32>()
+        .ok()
+        .map(|v| v * per_percent)
+}
+
+/// The viewport unit `value` ends with and the pixels one unit is worth.
+fn viewport_unit(value: &str, (width, height): (f32, f32)) -> Option<(&'static str, f32)> {
+    let per_percent = |px: f32| px / 100.0;
+    [
+        ("vmin", per_percent(width.min(height))),
+        ("vmax", per_percent(width.max(height))),
+        ("vw", per_percent(width)),
+        ("vh", per_percent(height)),
+    ]
+    .into_iter()
+    .find(|(unit, _)| value.ends_with(unit))
 }
 
 /// Parse a length without font-size context (uses root 16px for em).
 pub(crate) fn parse_length_default(value: &str) -> Option<f32> {
-    parse_length(value, ROOT_FONT_SIZE)
+    parse_length(value, Units::ROOT)
 }
 
 /// Parse a dimension value (px, %, em, rem, auto).
 pub(crate) fn parse_dimension(value: &str) -> Option<Dimension> {
-    parse_dimension_with_context(value, ROOT_FONT_SIZE)
+    parse_dimension_with_context(value, Units::ROOT)
 }
 
-fn parse_dimension_with_context(value: &str, em_base: f32) -> Option<Dimension> {
+fn parse_dimension_with_context(value: &str, units: Units) -> Option<Dimension> {
     let value = value.trim();
     if value == "auto" {
         return Some(Dimension::Auto);
     }
     if value.starts_with("calc(") {
-        return parse_calc(value, em_base);
+        return parse_calc(value, units);
     }
     if value.ends_with('%') {
         let num = value.trim_end_matches('%').trim().parse::<f32>().ok()?;
         return Some(Dimension::Percent(num / 100.0));
     }
-    if let Some(px) = parse_length(value, em_base) {
+    if let Some(px) = parse_length(value, units) {
         return Some(Dimension::Px(px));
     }
     value.parse::<f32>().ok().map(Dimension::Px)
@@ -410,13 +501,13 @@ fn parse_dimension_with_context(value: &str, em_base: f32) -> Option<Dimension>
 
 /// Parse 1-4 edge values (margin, padding, border-width shorthand).
 pub(crate) fn parse_edges(value: &str) -> Edges {
-    parse_edges_with_context(value, ROOT_FONT_SIZE)
+    parse_edges_with_context(value, Units::ROOT)
 }
 
-fn parse_edges_with_context(value: &str, em_base: f32) -> Edges {
+fn parse_edges_with_context(value: &str, units: Units) -> Edges {
     let parts: Vec<f32> = value
         .split_whitespace()
-        .filter_map(|v| parse_length(v, em_base))
+        .filter_map(|v| parse_length(v, units))
         .collect();
 
     match parts.len() {
@@ -578,17 +669,20 @@ fn expand_repeats(value: &str) -> String {
 /// Parse a CSS calc() expression into a Dimension.
 /// Handles: calc(100% - 200px), calc(50% + 2em), calc(100px * 2), etc.
 /// Returns Px if pure pixels, Percent if pure percent, Calc(pct, px) if mixed.
-fn parse_calc(value: &str, em_base: f32) -> Option<Dimension> {
+/// Expressions the evaluator can't handle fall back to their dominant term.
+fn parse_calc(value: &str, units: Units) -> Option<Dimension> {
     let inner = value.trim()
         .strip_prefix("calc(")?
         .strip_suffix(')')?
         .trim();
 
-    let tokens = tokenize_calc(inner, em_base)?;
-
     // Evaluate with operator precedence: first pass for * and /, second for + and -
-    let resolved = eval_mul_div(tokens)?;
-    let (px_total, pct_total) = eval_add_sub(&resolved)?;
+    let evaluated = tokenize_calc(inner, units)
+        .and_then(eval_mul_div)
+        .and_then(|resolved| eval_add_sub(&resolved));
+    let Some((px_total, pct_total)) = evaluated else {
+        return dominant_calc_term(inner, units);
+    };
 
     if pct_total == 0.0 {
         Some(Dimension::Px(px_total))
@@ -599,6 +693,28 @@ fn parse_calc(value: &str, em_base: f32) -> Option<Dimension> {
     }
 }
 
+/// Approximate an unsupported calc() (unknown units, nested functions,
+/// length × length) by a single term: the first percentage, since it tracks
+/// the container, or else the largest length.
+fn dominant_calc_term(inner: &str, units: Units) -> Option<Dimension> {
+    let mut largest: Option<f32> = None;
+    let terms = inner
+        .split(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | ','))
+        .filter(|t| !t.is_empty());
+    for term in terms {
+        if let Some(pct) = term.strip_suffix('%').and_then(|n| n.parse::<f32>().ok()) {
+            return Some(Dimension::Percent(pct / 100.0));
+        }
+        if let Some(px) = parse_length(term, units) {
+            match largest {
+                Some(l) if l.abs() >= px.abs() => {}
+                _ => largest = Some(px),
+            }
+        }
+    }
+    largest.map(Dimension::Px)
+}
+
 #[derive(Debug, Clone)]
 enum CalcToken {
     /// (px_value, pct_value) — one will be zero
@@ -608,7 +724,7 @@ enum CalcToken {
     Op(char),
 }
 
-fn tokenize_calc(input: &str, em_base: f32) -> Option<Vec<CalcToken>> {
+fn tokenize_calc(input: &str, units: Units) -> Option<Vec<CalcToken>> {
     let mut tokens = Vec::new();
     let mut chars = input.chars().peekable();
 
@@ -651,15 +767,22 @@ fn tokenize_calc(input: &str, em_base: f32) -> Option<Vec<CalcToken>> {
             match unit.as_str() {
                 "px" => tokens.push(CalcToken::Value(num, 0.0)),
                 "%" => tokens.push(CalcToken::Value(0.0, num / 100.0)),
-                "em" => tokens.push(CalcToken::Value(num * em_base, 0.0)),
+                "em" => tokens.push(CalcToken::Value(num * units.em, 0.0)),
                 "rem" => tokens.push(CalcToken::Value(num * ROOT_FONT_SIZE, 0.0)),
-                "vw" | "vh" => tokens.push(CalcToken::Value(0.0, num / 100.0)), // approximate as %
                 "" => tokens.push(CalcToken::Number(num)),
-                _ => return None,
+                _ => {
+                    let (_, per_unit) = viewport_unit(&unit, units.viewport)?;
+                    tokens.push(CalcToken::Value(num * per_unit, 0.0));
+                }
             }
             continue;
         }
 
+        // Nested functions like min() or var() leftovers aren't evaluated
+        if c.is_ascii_alphabetic() {
+            return None;
+        }
+
         // Skip unexpected characters
         chars.next();
     }
@@ -1,6 +1,6 @@
 /// Minimal CSS selector matching engine.
-/// Supports: tag, .class, #id, combinators (descendant, child),
-/// comma-separated selectors, and specificity ordering.
+/// Supports: tag, .class, #id, [attr] selectors, :not(), combinators
+/// (descendant, child), comma-separated selectors, and specificity ordering.
 
 use std::collections::HashMap;
 
@@ -36,8 +36,20 @@ pub enum SelectorPart {
     Child,
     /// Universal selector (*)
     Universal,
-    /// Pseudo-class (stripped, ignored for layout)
+    /// Pseudo-class. Structural ones are ignored for layout; user-action
+    /// ones like `:hover` never match a static render.
     PseudoClass(String),
+    /// `:not(...)` with a list of compound selectors (no combinators)
+    Not(Vec<Selector>),
+}
+
+/// An element on the path from the document root to the element being matched.
+#[derive(Debug, Clone)]
+pub struct Ancestor<'a> {
+    pub tag: &'a str,
+    pub classes: Vec<String>,
+    pub id: Option<&'a str>,
+    pub attrs: &'a HashMap<String, String>,
 }
 
 /// Attribute match operator.
@@ -100,7 +112,7 @@ pub fn parse_stylesheet(css: &str, viewport_width: f32, viewport_height: f32) ->
                     }
                 } else if !selector_str.is_empty() && !selector_str.starts_with('@') {
                     // Parse comma-separated selectors
-                    for sel_str in selector_str.split(',') {
+                    for sel_str in split_selector_list(&selector_str) {
                         let sel_str = sel_str.trim();
                         if sel_str.is_empty() {
                             continue;
@@ -307,29 +319,30 @@ fn parse_selector(input: &str) -> Option<Selector> {
             ':' => {
                 flush_tag(&mut current, &mut parts, &mut specificity);
                 chars.next();
-                // Skip :: for pseudo-elements
+                // Pseudo-elements style generated boxes, never the element itself
                 if chars.peek() == Some(&':') {
-                    chars.next();
+                    return None;
                 }
-                let pseudo = read_ident(&mut chars);
-                // Skip function arguments like :not(...)
-                if chars.peek() == Some(&'(') {
+                let pseudo = read_ident(&mut chars).to_lowercase();
+                if matches!(pseudo.as_str(), "before" | "after" | "first-line" | "first-letter") {
+                    return None;
+                }
+                let args = if chars.peek() == Some(&'(') {
                     chars.next();
-                    let mut depth = 1;
-                    while let Some(&c) = chars.peek() {
-                        chars.next();
-                        if c == '(' {
-                            depth += 1;
-                        } else if c == ')' {
-                            depth -= 1;
-                            if depth == 0 {
-                                break;
-                            }
-                        }
-                    }
+                    Some(read_parenthesized(&mut chars))
+                } else {
+                    None
+                };
+                if pseudo == "not" {
+                    // An unsupported argument invalidates the whole selector, as in browsers
+                    let inner = parse_not_arguments(args.as_deref().unwrap_or_default())?;
+                    // :not() takes the specificity of its most specific argument
+                    specificity += inner.iter().map(|sel| sel.specificity).max().unwrap_or(0);
+                    parts.push(SelectorPart::Not(inner));
+                } else {
+                    parts.push(SelectorPart::PseudoClass(pseudo));
+                    specificity += 10;
                 }
-                parts.push(SelectorPart::PseudoClass(pseudo));
-                specificity += 10;
             }
             '>' => {
                 flush_tag(&mut current, &mut parts, &mut specificity);
@@ -373,6 +386,66 @@ fn parse_selector(input: &str) -> Option<Selector> {
     }
 }
 
+/// Parse the argument list of `:not()`: comma-separated compound selectors.
+fn parse_not_arguments(args: &str) -> Option<Vec<Selector>> {
+    let mut selectors = Vec::new();
+    for arg in split_selector_list(args) {
+        let selector = parse_selector(arg)?;
+        if selector
+            .parts
+            .iter()
+            .any(|p| matches!(p, SelectorPart::Descendant | SelectorPart::Child))
+        {
+            return None;
+        }
+        selectors.push(selector);
+    }
+    Some(selectors)
+}
+
+/// Split a selector list on top-level commas, leaving commas inside
+/// `:not(...)` arguments and attribute values alone.
+fn split_selector_list(input: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0i32;
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in input.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '(' | '[') => depth += 1,
+            (None, ')' | ']') => depth -= 1,
+            (None, ',') if depth <= 0 => {
+                items.push(&input[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&input[start..]);
+    items
+}
+
+/// Read up to the matching `)`, after the opening `(` has been consumed.
+fn read_parenthesized(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut inner = String::new();
+    let mut depth = 1;
+    for c in chars.by_ref() {
+        if c == '(' {
+            depth += 1;
+        } else if c == ')' {
+            depth -= 1;
+            if depth == 0 {
+                break;
+            }
+        }
+        inner.push(c);
+    }
+    inner
+}
+
 fn flush_tag(current: &mut String, parts: &mut Vec<SelectorPart>, specificity: &mut u32) {
     let tag = current.trim().to_string();
     if !tag.is_empty() {
@@ -431,27 +504,21 @@ fn skip_whitespace(chars: &mut std::iter::Peekable<std::str::Chars>) {
 }
 
 /// Check if a selector matches an element, given its ancestry.
-/// `ancestors` is the list of (tag, classes, id) from root to parent.
+/// `ancestors` is the list of elements from the root to the parent.
 pub fn matches_element(
     selector: &Selector,
     tag: &str,
     classes: &[String],
     id: Option<&str>,
-    attrs: &std::collections::HashMap<String, String>,
-    ancestors: &[(String, Vec<String>, Option<String>)],
+    attrs: &HashMap<String, String>,
+    ancestors: &[Ancestor<'_>],
 ) -> bool {
-    // Walk the selector parts in reverse (right to left)
-    // The rightmost parts must match the current element
-    let parts = &selector.parts;
-    if parts.is_empty() {
-        return false;
-    }
-
-    // Split into segments separated by combinators
+    // Split into compound segments, each paired with the combinator that
+    // follows it (`None` for the rightmost segment)
     let mut segments: Vec<(Vec<&SelectorPart>, Option<&SelectorPart>)> = Vec::new();
     let mut current_segment: Vec<&SelectorPart> = Vec::new();
 
-    for part in parts {
+    for part in &selector.parts {
         match part {
             SelectorPart::Descendant | SelectorPart::Child => {
                 if !current_segment.is_empty() {
@@ -468,63 +535,41 @@ pub fn matches_element(
         segments.push((current_segment, None));
     }
 
-    if segments.is_empty() {
+    // The rightmost segment must match the element itself
+    let Some((last_segment, _)) = segments.last() else {
         return false;
-    }
-
-    // Last segment must match the current element
-    let last_segment = &segments.last().unwrap().0;
+    };
     if !segment_matches(last_segment, tag, classes, id, attrs) {
         return false;
     }
 
-    // No more segments to check
-    if segments.len() == 1 {
-        return true;
+    match segments.len() {
+        1 => true,
+        n => ancestors_match(&segments, n - 2, ancestors),
     }
+}
 
-    // Walk ancestors for remaining segments (right to left)
-    let mut seg_idx = segments.len() - 2; // start from second-to-last
-    let mut anc_idx = ancestors.len();
-
-    loop {
-        let (segment, _combinator) = &segments[seg_idx];
-        let combinator_of_next = &segments[seg_idx + 1].1;
-
-        // Find an ancestor that matches this segment
-        let is_child = matches!(combinator_of_next, Some(SelectorPart::Child));
-
-        let mut found = false;
-        while anc_idx > 0 {
-            anc_idx -= 1;
-            let (anc_tag, anc_classes, anc_id) = &ancestors[anc_idx];
-            let anc_attrs = std::collections::HashMap::new(); // ancestors don't carry full attrs in this impl
-
-            if segment_matches(
-                segment,
-                anc_tag,
-                anc_classes,
-                anc_id.as_deref(),
-                &anc_attrs,
-            ) {
-                found = true;
-                break;
-            }
-
-            if is_child {
-                // Child combinator: must be direct parent
-                return false;
-            }
-        }
-
-        if !found {
-            return false;
-        }
+/// Match `segments[..=seg_idx]` right to left against `ancestors`, whose last
+/// entry is the parent of the element matched by `segments[seg_idx + 1]`.
+/// Descendant combinators backtrack to farther ancestors when a nearer match
+/// fails further left.
+fn ancestors_match(
+    segments: &[(Vec<&SelectorPart>, Option<&SelectorPart>)],
+    seg_idx: usize,
+    ancestors: &[Ancestor<'_>],
+) -> bool {
+    let (segment, combinator) = &segments[seg_idx];
+    let matches_at = |i: usize| {
+        let anc = &ancestors[i];
+        segment_matches(segment, anc.tag, &anc.classes, anc.id, anc.attrs)
+            && (seg_idx == 0 || ancestors_match(segments, seg_idx - 1, &ancestors[..i]))
+    };
 
-        if seg_idx == 0 {
-            return true;
-        }
-        seg_idx -= 1;
+    if matches!(combinator, Some(SelectorPart::Child)) {
+        // Child combinator: must be the direct parent
+        !ancestors.is_empty() && matches_at(ancestors.len() - 1)
+    } else {
+        (0..ancestors.len()).rev().any(matches_at)
     }
 }
 
@@ -533,12 +578,12 @@ fn segment_matches(
     tag: &str,
     classes: &[String],
     id: Option<&str>,
-    attrs: &std::collections::HashMap<String, String>,
+    attrs: &HashMap<String, String>,
 ) -> bool {
     for part in segment {
         match part {
             SelectorPart::Tag(t) => {
-                if t != &tag.to_lowercase() {
+                if !t.eq_ignore_ascii_case(tag) {
                     return false;
                 }
             }
@@ -600,8 +645,29 @@ fn segment_matches(
                     }
                 }
             }
+            SelectorPart::Not(inner) => {
+                let excluded = inner.iter().any(|sel| {
+                    let parts: Vec<&SelectorPart> = sel.parts.iter().collect();
+                    segment_matches(&parts, tag, classes, id, attrs)
+                });
+                if excluded {
+                    return false;
+                }
+            }
+            SelectorPart::PseudoClass(pseudo) => {
+                // Nothing is hovered, focused or visited in a static render
+                if matches!(
+                    pseudo.as_str(),
+                    "hover" | "active" | "focus" | "focus-within" | "focus-visible" | "visited" | "target"
+                ) {
+                    return false;
+                }
+                // :root is the document element, where custom properties usually live
+                if pseudo == "root" && !tag.eq_ignore_ascii_case("html") {
+                    return false;
+                }
+            }
             SelectorPart::Universal => {} // matches everything
-            SelectorPart::PseudoClass(_) => {} // ignored for layout
             SelectorPart::Descendant | SelectorPart::Child => {} // handled elsewhere
         }
     }
@@ -628,13 +694,14 @@ enum RightmostKind {
 }
 
 fn extract_rightmost_simple(selector: &Selector) -> RightmostKind {
+    // Only the rightmost compound selector constrains the element itself
     for part in selector.parts.iter().rev() {
         match part {
-            SelectorPart::Descendant | SelectorPart::Child => continue,
+            SelectorPart::Descendant | SelectorPart::Child => break,
             SelectorPart::Tag(t) => return RightmostKind::Tag(t.clone()),
             SelectorPart::Class(c) => return RightmostKind::Class(c.clone()),
             SelectorPart::Id(i) => return RightmostKind::Id(i.clone()),
-            _ => return RightmostKind::Universal,
+            _ => continue,
         }
     }
     RightmostKind::Universal
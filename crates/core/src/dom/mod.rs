@@ -1,9 +1,14 @@
+mod source;
+
 use html5ever::parse_document;
 use html5ever::tendril::TendrilSink;
 use html5ever::tree_builder::TreeBuilderOpts;
 use html5ever::ParseOpts;
 use markup5ever_rcdom::{Handle, NodeData, RcDom};
 use std::collections::HashMap;
+use std::rc::Rc;
+
+use source::{PositionSink, Positions};
 
 /// A node in our DOM tree. Minimal — only what layout needs.
 #[derive(Debug, Clone)]
@@ -13,6 +18,9 @@ pub struct DomNode {
     pub text: String,
     pub children: Vec<DomNode>,
     pub node_type: NodeType,
+    /// Line and column of the element's start tag in the source, when
+    /// parsed with [`parse_html_with_positions`].
+    pub src: Option<[u32; 2]>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -30,6 +38,7 @@ impl DomNode {
             text: String::new(),
             children: Vec::new(),
             node_type: NodeType::Element,
+            src: None,
         }
     }
 
@@ -40,6 +49,7 @@ impl DomNode {
             text: text.to_string(),
             children: Vec::new(),
             node_type: NodeType::Text,
+            src: None,
         }
     }
 
@@ -50,6 +60,7 @@ impl DomNode {
             text: String::new(),
             children: Vec::new(),
             node_type: NodeType::Document,
+            src: None,
         }
     }
 
@@ -86,41 +97,80 @@ impl DomNode {
 
 /// Parse an HTML string into a DomNode tree.
 pub fn parse_html(html: &str) -> DomNode {
-    let opts = ParseOpts {
+    let dom = parse_document(RcDom::default(), parse_opts())
+        .from_utf8()
+        .read_from(&mut html.as_bytes())
+        .expect("failed to parse HTML");
+
+    convert_node(&dom.document, None)
+}
+
+/// Parse an HTML string into a DomNode tree, recording where each element's
+/// start tag is in `html`.
+pub fn parse_html_with_positions(html: &str) -> DomNode {
+    let (dom, positions) = parse_document(PositionSink::new(html), parse_opts())
+        .from_utf8()
+        .read_from(&mut html.as_bytes())
+        .expect("failed to parse HTML");
+
+    convert_node(&dom.document, Some(&positions))
+}
+
+fn parse_opts() -> ParseOpts {
+    ParseOpts {
         tree_builder: TreeBuilderOpts {
             drop_doctype: true,
+            // browsy runs no scripts, so <noscript> holds markup it should see,
+            // exactly as in a browser with JavaScript turned off
+            scripting_enabled: false,
             ..Default::default()
         },
         ..Default::default()
-    };
-
-    let dom = parse_document(RcDom::default(), opts)
-        .from_utf8()
-        .read_from(&mut html.as_bytes())
-        .expect("failed to parse HTML");
+    }
+}
 
-    convert_node(&dom.document)
+fn convert_node(handle: &Handle, positions: Option<&Positions>) -> DomNode {
+    let mut node = convert_node_data(handle, positions);
+    if let Some(positions) = positions {
+        node.src = positions.get(&Rc::as_ptr(handle)).copied();
+    }
+    node
 }
 
-fn convert_node(handle: &Handle) -> DomNode {
+fn convert_node_data(handle: &Handle, positions: Option<&Positions>) -> DomNode {
     match &handle.data {
         NodeData::Document => {
             let mut doc = DomNode::new_document();
             for child in handle.children.borrow().iter() {
-                doc.children.push(convert_node(child));
+                doc.children.push(convert_node(child, positions));
             }
             doc
         }
         NodeData::Element { name, attrs, .. } => {
             let tag = name.local.to_string();
 
-            // Skip script content
+            // <template> content is inert until a script clones it; html5ever
+            // keeps it aside in `template_contents`, which is never walked
+            if tag == "template" {
+                let mut node = DomNode::new_element(&tag);
+                for attr in attrs.borrow().iter() {
+                    node.attributes
+                        .insert(attr.name.local.to_string(), attr.value.to_string());
+                }
+                return node;
+            }
+
+            // Skip script content, except JSON-LD which is kept as the element's
+            // own `text` so it never shows up in visible text
             if tag == "script" || tag == "path" {
                 let mut node = DomNode::new_element(&tag);
                 for attr in attrs.borrow().iter() {
                     node.attributes
                         .insert(attr.name.local.to_string(), attr.value.to_string());
                 }
+                if node.get_attr("type").is_some_and(|t| t.trim().eq_ignore_ascii_case("application/ld+json")) {
+                    collect_raw_text(handle, &mut node.text);
+                }
                 return node;
             }
 
@@ -146,7 +196,7 @@ fn convert_node(handle: &Handle) -> DomNode {
                     .insert(attr.name.local.to_string(), attr.value.to_string());
             }
             for child in handle.children.borrow().iter() {
-                let child_node = convert_node(child);
+                let child_node = convert_node(child, positions);
                 // Skip empty text nodes
                 if child_node.node_type == NodeType::Text && child_node.text.trim().is_empty() {
                     continue;
@@ -0,0 +1,230 @@
+//! Source positions of parsed elements.
+//!
+//! html5ever only tells a tree sink which line the tokenizer is on, once a
+//! whole start tag has been read. [`PositionSink`] wraps `RcDom` and, as each
+//! element is created, looks for its `<tag` in the source: the first one
+//! after the previous element's that begins by the end of the current line.
+//! Elements the parser inserts on its own (a missing `<html>`, an implied
+//! `<tbody>`) have no start tag and get no position.
+
+use std::borrow::Cow;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use html5ever::tendril::StrTendril;
+use html5ever::tree_builder::{ElementFlags, NodeOrText, QuirksMode, TreeSink};
+use html5ever::{Attribute, QualName};
+use markup5ever_rcdom::{Handle, Node, RcDom};
+
+/// Elements whose content is raw text, so a `<tag` inside them is not markup.
+const RAW_TEXT_TAGS: &[&str] = &[
+    "script", "style", "textarea", "title", "xmp", "iframe", "noembed", "noframes",
+];
+
+/// Line and column (1-based, column in characters) of each element's start
+/// tag, keyed by node address.
+pub(crate) type Positions = HashMap<*const Node, [u32; 2]>;
+
+pub(crate) struct PositionSink<'a> {
+    dom: RcDom,
+    source: &'a str,
+    /// `source` with ASCII letters lowercased, so byte offsets line up.
+    lower: String,
+    /// Byte offset where each line starts.
+    line_starts: Vec<usize>,
+    line: Cell<u64>,
+    /// Byte offset just past the last start tag found.
+    cursor: Cell<usize>,
+    positions: RefCell<Positions>,
+}
+
+impl<'a> PositionSink<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            dom: RcDom::default(),
+            source,
+            lower: source.to_ascii_lowercase(),
+            line_starts,
+            line: Cell::new(1),
+            cursor: Cell::new(0),
+            positions: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Where the start tag of a `tag` element created on the current line is.
+    fn locate(&self, tag: &str) -> Option<[u32; 2]> {
+        let line = self.line.get() as usize;
+        let line_end = self.line_starts.get(line).copied().unwrap_or(self.source.len());
+        let start = find_start_tag(&self.lower, self.cursor.get(), line_end, tag)?;
+
+        let mut next = start + 1 + tag.len();
+        if RAW_TEXT_TAGS.contains(&tag) {
+            if let Some(close) = self.lower[next..].find(&format!("</{tag}")) {
+                next += close;
+            }
+        }
+        self.cursor.set(next);
+
+        let line_index = self.line_starts.partition_point(|&s| s <= start) - 1;
+        let column = self.source[self.line_starts[line_index]..start].chars().count() + 1;
+        Some([line_index as u32 + 1, column as u32])
+    }
+}
+
+/// Offset of the first `<tag` in `lower` from `from` that begins before
+/// `end`, skipping comments.
+fn find_start_tag(lower: &str, mut from: usize, end: usize, tag: &str) -> Option<usize> {
+    let bytes = lower.as_bytes();
+    while from < end {
+        let lt = from + lower[from..end].find('<')?;
+        let rest = &lower[lt + 1..];
+        if let Some(comment) = rest.strip_prefix("!--") {
+            from = lt + 4 + comment.find("-->")? + 3;
+            continue;
+        }
+        let boundary = match bytes.get(lt + 1 + tag.len()) {
+            None => true,
+            Some(b) => b.is_ascii_whitespace() || matches!(b, b'>' | b'/'),
+        };
+        if rest.starts_with(tag) && boundary {
+            return Some(lt);
+        }
+        from = lt + 1;
+    }
+    None
+}
+
+impl TreeSink for PositionSink<'_> {
+    type Handle = Handle;
+    type Output = (RcDom, Positions);
+    type ElemName<'b>
+        = <RcDom as TreeSink>::ElemName<'b>
+    where
+        Self: 'b;
+
+    fn finish(self) -> Self::Output {
+        (self.dom, self.positions.into_inner())
+    }
+
+    fn parse_error(&self, msg: Cow<'static, str>) {
+        self.dom.parse_error(msg)
+    }
+
+    fn get_document(&self) -> Handle {
+        self.dom.get_document()
+    }
+
+    fn elem_name<'b>(&'b self, target: &'b Handle) -> Self::ElemName<'b> {
+        self.dom.elem_name(target)
+    }
+
+    fn create_element(&self, name: QualName, attrs: Vec<Attribute>, flags: ElementFlags) -> Handle {
+        let position = self.locate(&name.local.to_ascii_lowercase());
+        let handle = self.dom.create_element(name, attrs, flags);
+        if let Some(position) = position {
+            self.positions.borrow_mut().insert(Rc::as_ptr(&handle), position);
+        }
+        handle
+    }
+
+    fn create_comment(&self, text: StrTendril) -> Handle {
+        self.dom.create_comment(text)
+    }
+
+    fn create_pi(&self, target: StrTendril, data: StrTendril) -> Handle {
+        self.dom.create_pi(target, data)
+    }
+
+    fn append(&self, parent: &Handle, child: NodeOrText<Handle>) {
+        self.dom.append(parent, child)
+    }
+
+    fn append_based_on_parent_node(
+        &self,
+        element: &Handle,
+        prev_element: &Handle,
+        child: NodeOrText<Handle>,
+    ) {
+        self.dom.append_based_on_parent_node(element, prev_element, child)
+    }
+
+    fn append_doctype_to_document(
+        &self,
+        name: StrTendril,
+        public_id: StrTendril,
+        system_id: StrTendril,
+    ) {
+        self.dom.append_doctype_to_document(name, public_id, system_id)
+    }
+
+    fn mark_script_already_started(&self, node: &Handle) {
+        self.dom.mark_script_already_started(node)
+    }
+
+    fn pop(&self, node: &Handle) {
+        self.dom.pop(node)
+    }
+
+    fn get_template_contents(&self, target: &Handle) -> Handle {
+        self.dom.get_template_contents(target)
+    }
+
+    fn same_node(&self, x: &Handle, y: &Handle) -> bool {
+        self.dom.same_node(x, y)
+    }
+
+    fn set_quirks_mode(&self, mode: QuirksMode) {
+        self.dom.set_quirks_mode(mode)
+    }
+
+    fn append_before_sibling(&self, sibling: &Handle, new_node: NodeOrText<Handle>) {
+        self.dom.append_before_sibling(sibling, new_node)
+    }
+
+    fn add_attrs_if_missing(&self, target: &Handle, attrs: Vec<Attribute>) {
+        self.dom.add_attrs_if_missing(target, attrs)
+    }
+
+    fn associate_with_form(
+        &self,
+        target: &Handle,
+        form: &Handle,
+        nodes: (&Handle, Option<&Handle>),
+    ) {
+        self.dom.associate_with_form(target, form, nodes)
+    }
+
+    fn remove_from_parent(&self, target: &Handle) {
+        self.dom.remove_from_parent(target)
+    }
+
+    fn reparent_children(&self, node: &Handle, new_parent: &Handle) {
+        self.dom.reparent_children(node, new_parent)
+    }
+
+    fn is_mathml_annotation_xml_integration_point(&self, handle: &Handle) -> bool {
+        self.dom.is_mathml_annotation_xml_integration_point(handle)
+    }
+
+    fn set_current_line(&self, line_number: u64) {
+        self.line.set(line_number);
+    }
+
+    fn allow_declarative_shadow_roots(&self, intended_parent: &Handle) -> bool {
+        self.dom.allow_declarative_shadow_roots(intended_parent)
+    }
+
+    fn attach_declarative_shadow(
+        &self,
+        location: &Handle,
+        template: &Handle,
+        attrs: &[Attribute],
+    ) -> bool {
+        self.dom.attach_declarative_shadow(location, template, attrs)
+    }
+}
+
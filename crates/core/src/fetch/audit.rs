@@ -0,0 +1,108 @@
+//! A per-session record of what an agent did, for compliance and debugging.
+//!
+//! Every public `Session` action appends an [`AuditEvent`]. Events carry a
+//! sequence number that keeps increasing for the life of the session, so a
+//! client can poll for what's new with [`AuditLog::since`] even after the
+//! oldest events have been evicted.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use super::RetryCondition;
+
+/// One entry in a session's audit log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    /// Position in the session's log, starting at 1. Never reused.
+    pub seq: u64,
+    /// When the event happened, in milliseconds since the Unix epoch.
+    pub ts_ms: u64,
+    #[serde(flatten)]
+    pub action: AuditAction,
+}
+
+/// What happened. Serialized with a `type` tag: `{"type": "click", "id": 4, ...}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AuditAction {
+    /// A page was requested: `goto`, a followed link, or `back`.
+    Navigate { url: String },
+    /// HTML was loaded without fetching it.
+    LoadHtml { url: String },
+    /// An element was clicked.
+    Click {
+        id: u32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        text: Option<String>,
+    },
+    /// Text was typed into a field. Sensitive fields show `•••• (N chars)`
+    /// when `redact_sensitive` is on.
+    Type { id: u32, value: String },
+    Check { id: u32 },
+    Uncheck { id: u32 },
+    Select { id: u32, value: String },
+    /// Enter was pressed in an input, submitting its form.
+    PressEnter { id: u32 },
+    /// A form was submitted. Field values are left out.
+    Submit { url: String, method: String, fields: Vec<String> },
+    Back,
+    Search { query: String, engine: String },
+    /// `login` filled in one page of a login form: the username, the
+    /// password or both. `step` counts from 1.
+    LoginStep { step: u32, fields: Vec<String> },
+    /// A request failed in a way the retry policy covers and was sent again
+    /// after `delay_ms`. `attempt` counts from 1.
+    Retry { url: String, attempt: u32, reason: RetryCondition, delay_ms: u64 },
+    /// The loaded page shows a CAPTCHA.
+    Captcha { url: String, captcha_type: String },
+    /// The loaded page looks like an anti-bot block or rate limit.
+    Blocked { url: String, reason: String },
+    /// A navigation or submission failed.
+    Error { message: String },
+    /// `action_policy` held an action until it's confirmed.
+    Held { description: String },
+    /// A held action was confirmed and taken.
+    Confirmed { description: String },
+}
+
+/// A bounded, append-only list of audit events. When full, the oldest
+/// event is dropped to make room.
+#[derive(Debug, Clone)]
+pub struct AuditLog {
+    events: VecDeque<AuditEvent>,
+    limit: usize,
+    next_seq: u64,
+}
+
+impl AuditLog {
+    /// A log keeping at most `limit` events. A limit of 0 records nothing.
+    pub fn new(limit: usize) -> Self {
+        Self { events: VecDeque::new(), limit, next_seq: 1 }
+    }
+
+    pub(crate) fn record(&mut self, action: AuditAction) {
+        if self.limit == 0 {
+            return;
+        }
+        if self.events.len() == self.limit {
+            self.events.pop_front();
+        }
+        let ts_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        self.events.push_back(AuditEvent { seq: self.next_seq, ts_ms, action });
+        self.next_seq += 1;
+    }
+
+    /// Every event still held, oldest first.
+    pub fn events(&self) -> Vec<AuditEvent> {
+        self.events.iter().cloned().collect()
+    }
+
+    /// Events with a sequence number greater than `seq`, oldest first.
+    pub fn since(&self, seq: u64) -> Vec<AuditEvent> {
+        self.events.iter().filter(|e| e.seq > seq).cloned().collect()
+    }
+}
+
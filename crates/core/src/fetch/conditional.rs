@@ -0,0 +1,101 @@
+//! Pages remembered for conditional requests.
+//!
+//! When a page comes with an `ETag` or `Last-Modified` header, the session
+//! keeps it here. Revisiting the URL sends `If-None-Match` /
+//! `If-Modified-Since`, and a `304 Not Modified` answer reuses the stored
+//! page instead of parsing and laying it out again.
+
+use std::collections::{HashMap, VecDeque};
+
+use url::Url;
+
+use super::HttpResponse;
+use crate::output::SpatialDom;
+
+/// Pages kept per session; the least recently used is dropped first.
+const CAPACITY: usize = 32;
+
+#[derive(Debug, Clone)]
+pub(crate) struct CachedPage {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    pub html: String,
+    /// External stylesheets the page was laid out with.
+    pub css: String,
+    pub dom: SpatialDom,
+}
+
+impl CachedPage {
+    /// A page worth keeping: `response` carries at least one validator.
+    pub fn from_response(response: &HttpResponse, css: &str, dom: &SpatialDom) -> Option<Self> {
+        let etag = response.header("etag").map(str::to_string);
+        let last_modified = response.header("last-modified").map(str::to_string);
+        if etag.is_none() && last_modified.is_none() {
+            return None;
+        }
+        Some(Self {
+            etag,
+            last_modified,
+            html: response.body.clone(),
+            css: css.to_string(),
+            dom: dom.clone(),
+        })
+    }
+
+    /// Request headers asking the server whether the page changed.
+    pub fn conditional_headers(&self) -> Vec<(String, String)> {
+        let mut headers = Vec::new();
+        if let Some(etag) = &self.etag {
+            headers.push(("if-none-match".to_string(), etag.clone()));
+        }
+        if let Some(date) = &self.last_modified {
+            headers.push(("if-modified-since".to_string(), date.clone()));
+        }
+        headers
+    }
+}
+
+/// A small LRU of [`CachedPage`]s keyed by URL without the fragment.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct PageCache {
+    pages: HashMap<String, CachedPage>,
+    /// Keys from least to most recently used.
+    order: VecDeque<String>,
+}
+
+impl PageCache {
+    pub fn get(&mut self, url: &Url) -> Option<&CachedPage> {
+        let key = cache_key(url);
+        if !self.pages.contains_key(&key) {
+            return None;
+        }
+        self.touch(&key);
+        self.pages.get(&key)
+    }
+
+    pub fn insert(&mut self, url: &Url, page: CachedPage) {
+        let key = cache_key(url);
+        if self.pages.insert(key.clone(), page).is_some() {
+            self.touch(&key);
+            return;
+        }
+        self.order.push_back(key);
+        if self.order.len() > CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.pages.remove(&oldest);
+            }
+        }
+    }
+
+    fn touch(&mut self, key: &str) {
+        if let Some(pos) = self.order.iter().position(|k| k == key) {
+            let key = self.order.remove(pos).unwrap();
+            self.order.push_back(key);
+        }
+    }
+}
+
+fn cache_key(url: &Url) -> String {
+    url[..url::Position::AfterQuery].to_string()
+}
+
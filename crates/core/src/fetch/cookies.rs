@@ -0,0 +1,168 @@
+//! The session's cookie jar.
+//!
+//! Works like `reqwest::cookie::Jar`, which can't be copied: `Session::fork`
+//! needs a jar holding the same cookies that the original no longer shares.
+//! A jar can also hand the cookies of some domains to another jar shared by
+//! several sessions, so one login serves all of them.
+
+use std::sync::{Arc, RwLock};
+
+use cookie_store::CookieStore;
+use reqwest::header::HeaderValue;
+use url::Url;
+
+use super::FetchError;
+
+/// Cookies for a [`Session`](super::Session), given to
+/// [`Session::with_cookie_jar`](super::Session::with_cookie_jar).
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    store: RwLock<CookieStore>,
+    shared: Option<Shared>,
+}
+
+/// Where cookies of hosts under `domains` live instead of the jar's own store.
+#[derive(Debug, Clone)]
+struct Shared {
+    jar: Arc<CookieJar>,
+    /// Lowercase, without a leading dot.
+    domains: Vec<String>,
+}
+
+impl CookieJar {
+    /// A jar that keeps the cookies set by and sent to hosts under `domains`
+    /// (subdomains included) in `shared`, and every other cookie to itself.
+    pub fn sharing(shared: Arc<CookieJar>, domains: &[String]) -> Self {
+        let domains = domains
+            .iter()
+            .map(|d| d.trim_start_matches('.').to_ascii_lowercase())
+            .filter(|d| !d.is_empty())
+            .collect();
+        Self {
+            store: RwLock::default(),
+            shared: Some(Shared { jar: shared, domains }),
+        }
+    }
+
+    /// The store holding cookies for `host`.
+    fn store_for(&self, host: &str) -> &RwLock<CookieStore> {
+        match self.shared_jar(host) {
+            Some(jar) => jar.store_for(host),
+            None => &self.store,
+        }
+    }
+
+    /// The shared jar, if `host` is under one of its domains.
+    fn shared_jar(&self, host: &str) -> Option<&CookieJar> {
+        let shared = self.shared.as_ref()?;
+        let host = host.to_ascii_lowercase();
+        let under = |d: &String| host == *d || host.strip_suffix(d.as_str()).is_some_and(|rest| rest.ends_with('.'));
+        shared.domains.iter().any(under).then_some(&*shared.jar)
+    }
+
+    fn store_for_url(&self, url: &Url) -> &RwLock<CookieStore> {
+        self.store_for(url.host_str().unwrap_or_default())
+    }
+
+    /// `name=value` for each cookie to send to `url`. A cookie for a parent
+    /// domain can be in either store, whichever side of the shared domains
+    /// set it, so both are read.
+    fn request_values(&self, url: &Url) -> Vec<String> {
+        let store = self.store.read().unwrap();
+        let mut values: Vec<String> =
+            store.get_request_values(url).map(|(name, value)| format!("{name}={value}")).collect();
+        if let Some(shared) = &self.shared {
+            values.extend(shared.jar.request_values(url));
+        }
+        values
+    }
+
+    /// Store a cookie as if `url` had answered with `Set-Cookie: cookie`.
+    pub(crate) fn add_cookie_str(&self, cookie: &str, url: &Url) {
+        let cookies = cookie::Cookie::parse(cookie).ok().map(|c| c.into_owned());
+        self.store_for_url(url).write().unwrap().store_response_cookies(cookies.into_iter(), url);
+    }
+
+    /// A new jar with a copy of this one's own cookies. Cookies kept in a
+    /// shared jar stay there, shared with the copy too.
+    pub(crate) fn fork(&self) -> Self {
+        Self {
+            store: RwLock::new(self.store.read().unwrap().clone()),
+            shared: self.shared.clone(),
+        }
+    }
+
+    /// The unexpired cookies, session cookies included, with those of the
+    /// shared domains.
+    pub(crate) fn unexpired(&self) -> Vec<cookie_store::Cookie<'static>> {
+        let mut cookies: Vec<_> = self.store.read().unwrap().iter_unexpired().cloned().collect();
+        if let Some(shared) = &self.shared {
+            let shared_cookies = shared.jar.unexpired().into_iter();
+            cookies.extend(shared_cookies.filter(|c| self.shared_jar(&cookie_host(c)).is_some()));
+        }
+        cookies
+    }
+
+    /// Hold exactly `cookies` of its own, leaving out any that have expired
+    /// since. Cookies of the shared domains are added to the shared jar,
+    /// which keeps the rest of its cookies.
+    pub(crate) fn replace(&self, cookies: Vec<cookie_store::Cookie<'static>>) {
+        let (shared, own): (Vec<_>, Vec<_>) = cookies
+            .into_iter()
+            .partition(|c| self.shared_jar(&cookie_host(c)).is_some());
+        *self.store.write().unwrap() = store_of(own);
+        self.add(shared);
+    }
+
+    /// Add `cookies`, replacing any with the same domain, path and name.
+    fn add(&self, cookies: Vec<cookie_store::Cookie<'static>>) {
+        for cookie in cookies {
+            let mut store = self.store_for(&cookie_host(&cookie)).write().unwrap();
+            let kept = store.iter_unexpired().cloned().chain(std::iter::once(cookie));
+            *store = store_of(kept.collect());
+        }
+    }
+
+    /// The unexpired cookies as JSON, in cookie_store's format: the same as
+    /// the `cookies` of a saved [`SessionState`](super::SessionState).
+    pub fn export_json(&self) -> String {
+        serde_json::to_string_pretty(&self.unexpired()).unwrap_or_default()
+    }
+
+    /// Add the cookies of an [`export_json`](Self::export_json) and return
+    /// how many there were.
+    pub fn import_json(&self, json: &str) -> Result<usize, FetchError> {
+        let cookies: Vec<cookie_store::Cookie<'static>> =
+            serde_json::from_str(json).map_err(|e| FetchError::ActionError(format!("invalid cookies: {e}")))?;
+        let count = cookies.len();
+        self.add(cookies);
+        Ok(count)
+    }
+}
+
+fn store_of(cookies: Vec<cookie_store::Cookie<'static>>) -> CookieStore {
+    CookieStore::from_cookies(cookies.into_iter().map(Ok::<_, std::convert::Infallible>), false).unwrap_or_default()
+}
+
+/// The host or domain a stored cookie belongs to.
+fn cookie_host(cookie: &cookie_store::Cookie<'static>) -> String {
+    cookie.domain.as_cow().map(|d| d.into_owned()).unwrap_or_default()
+}
+
+impl reqwest::cookie::CookieStore for CookieJar {
+    fn set_cookies(&self, cookie_headers: &mut dyn Iterator<Item = &HeaderValue>, url: &Url) {
+        let cookies = cookie_headers.filter_map(|header| {
+            let header = header.to_str().ok()?;
+            cookie::Cookie::parse(header).ok().map(|c| c.into_owned())
+        });
+        self.store_for_url(url).write().unwrap().store_response_cookies(cookies, url);
+    }
+
+    fn cookies(&self, url: &Url) -> Option<HeaderValue> {
+        let header = self.request_values(url).join("; ");
+        if header.is_empty() {
+            return None;
+        }
+        HeaderValue::from_str(&header).ok()
+    }
+}
@@ -0,0 +1,179 @@
+//! A small breadth-first crawler that keeps one inventory line per page.
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::output::{PageType, SpatialDom};
+
+/// Limits and filters for [`Session::crawl`](super::Session::crawl).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CrawlOpts {
+    /// Most pages to visit, counting the start page and pages that failed.
+    pub max_pages: usize,
+    /// Most links to follow from the start page; 0 visits only the start page.
+    pub max_depth: usize,
+    /// Only follow links to the start page's host.
+    pub same_host_only: bool,
+    /// When not empty, only follow links whose URL contains one of these.
+    pub include_patterns: Vec<String>,
+    /// Never follow links whose URL contains one of these.
+    pub exclude_patterns: Vec<String>,
+    /// Skip pages the site's robots.txt disallows for the session's User-Agent.
+    pub respect_robots: bool,
+}
+
+impl Default for CrawlOpts {
+    fn default() -> Self {
+        Self {
+            max_pages: 50,
+            max_depth: 3,
+            same_host_only: true,
+            include_patterns: Vec::new(),
+            exclude_patterns: Vec::new(),
+            respect_robots: true,
+        }
+    }
+}
+
+/// One page a crawl visited.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrawlEntry {
+    /// Where the page ended up, after redirects, normalized.
+    pub url: String,
+    /// Links followed from the start page to reach it.
+    pub depth: usize,
+    pub title: String,
+    pub page_type: PageType,
+    pub element_count: usize,
+    /// The page's http(s) links, normalized, in page order without repeats.
+    pub outlinks: Vec<String>,
+    /// Why the page couldn't be loaded; the other fields are empty then.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl CrawlEntry {
+    pub(crate) fn from_dom(url: &Url, depth: usize, dom: &SpatialDom) -> Self {
+        let mut outlinks: Vec<String> = Vec::new();
+        for href in dom.els.iter().filter_map(|e| e.href.as_deref()) {
+            let Ok(link) = Url::parse(href) else { continue };
+            if !matches!(link.scheme(), "http" | "https") {
+                continue;
+            }
+            let link = normalize_url(&link).to_string();
+            if !outlinks.contains(&link) {
+                outlinks.push(link);
+            }
+        }
+        Self {
+            url: url.to_string(),
+            depth,
+            title: dom.title.clone(),
+            page_type: dom.page_type.clone(),
+            element_count: dom.els.len(),
+            outlinks,
+            error: None,
+        }
+    }
+
+    pub(crate) fn failed(url: &Url, depth: usize, error: String) -> Self {
+        Self {
+            url: url.to_string(),
+            depth,
+            title: String::new(),
+            page_type: PageType::default(),
+            element_count: 0,
+            outlinks: Vec::new(),
+            error: Some(error),
+        }
+    }
+}
+
+/// `url` without its fragment and with its query parameters sorted, so the
+/// ways of writing one page's address compare equal.
+pub fn normalize_url(url: &Url) -> Url {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    let mut pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    if pairs.is_empty() {
+        url.set_query(None);
+    } else {
+        pairs.sort();
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+    }
+    url
+}
+
+/// A robots.txt group: the user agents it names, and its rules as
+/// (allow, pattern).
+type RobotsGroup = (Vec<String>, Vec<(bool, String)>);
+
+/// Whether robots.txt lets `user_agent` fetch `path` (with its query). The
+/// group naming the agent applies, or else the `*` group; within it the
+/// longest matching rule wins, and `Allow` wins a tie.
+pub(crate) fn robots_allows(robots: &str, user_agent: &str, path: &str) -> bool {
+    let agent = user_agent.to_ascii_lowercase();
+    let mut groups: Vec<RobotsGroup> = Vec::new();
+    let mut naming_agents = false;
+    for line in robots.lines() {
+        let line = line.split('#').next().unwrap_or_default();
+        let Some((key, value)) = line.split_once(':') else { continue };
+        let (key, value) = (key.trim().to_ascii_lowercase(), value.trim());
+        match key.as_str() {
+            "user-agent" => {
+                if !naming_agents {
+                    groups.push((Vec::new(), Vec::new()));
+                }
+                naming_agents = true;
+                if let Some((agents, _)) = groups.last_mut() {
+                    agents.push(value.to_ascii_lowercase());
+                }
+            }
+            "allow" | "disallow" => {
+                naming_agents = false;
+                if let (Some((_, rules)), false) = (groups.last_mut(), value.is_empty()) {
+                    rules.push((key == "allow", value.to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+    let named = groups
+        .iter()
+        .find(|(agents, _)| agents.iter().any(|a| a != "*" && agent.contains(a.as_str())));
+    let Some((_, rules)) = named.or_else(|| groups.iter().find(|(agents, _)| agents.iter().any(|a| a == "*"))) else {
+        return true;
+    };
+    rules
+        .iter()
+        .filter(|(_, pattern)| robots_pattern_matches(pattern, path))
+        .max_by_key(|(allow, pattern)| (pattern.len(), *allow))
+        .is_none_or(|(allow, _)| *allow)
+}
+
+/// A robots.txt path pattern: a prefix, where `*` matches any run of
+/// characters and a final `$` anchors the end.
+fn robots_pattern_matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(p) => (p, true),
+        None => (pattern, false),
+    };
+    let mut parts = pattern.split('*');
+    let Some(rest) = parts.next().and_then(|first| path.strip_prefix(first)) else {
+        return false;
+    };
+    let mut rest = rest;
+    let parts: Vec<&str> = parts.collect();
+    for (i, part) in parts.iter().enumerate() {
+        // An anchored last part has to end the path
+        if anchored && i == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
@@ -0,0 +1,153 @@
+//! Where a site lists its pages: RSS and Atom feeds linked from a page,
+//! sitemaps named in robots.txt, and the sitemaps themselves.
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::dom::DomNode;
+
+/// Most sitemaps [`Session::fetch_sitemap`](super::Session::fetch_sitemap)
+/// reads in one call, counting the index and the sitemaps it lists.
+pub const MAX_SITEMAPS: usize = 20;
+
+/// What [`Session::discover_feeds`](super::Session::discover_feeds) found
+/// for the current page's site.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DiscoveredFeeds {
+    /// Sitemap URLs: those robots.txt names, then `/sitemap.xml` if it exists.
+    pub sitemaps: Vec<String>,
+    /// Feeds the page links to with `<link rel="alternate">`.
+    pub feeds: Vec<FeedLink>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeedLink {
+    pub href: String,
+    pub kind: FeedKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedKind {
+    Rss,
+    Atom,
+}
+
+/// A page or sitemap listed in a sitemap.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SitemapEntry {
+    pub loc: String,
+    /// When the page last changed, as the sitemap wrote it (W3C datetime).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lastmod: Option<String>,
+}
+
+/// A parsed sitemap file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Sitemap {
+    /// A `<urlset>` of pages.
+    Urls(Vec<SitemapEntry>),
+    /// A `<sitemapindex>` of more sitemaps.
+    Index(Vec<SitemapEntry>),
+}
+
+/// Parse a sitemap: a `<urlset>` or a `<sitemapindex>`, in any namespace.
+pub fn parse_sitemap(xml: &str) -> Result<Sitemap, String> {
+    let mut reader = Reader::from_str(xml);
+    reader.config_mut().trim_text(true);
+    let mut root: Option<String> = None;
+    let mut path: Vec<String> = Vec::new();
+    let mut entry: Option<SitemapEntry> = None;
+    let mut entries = Vec::new();
+    loop {
+        let text = match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let name = String::from_utf8_lossy(e.local_name().as_ref()).into_owned();
+                root.get_or_insert_with(|| name.clone());
+                if path.len() == 1 && matches!(name.as_str(), "url" | "sitemap") {
+                    entry = Some(SitemapEntry::default());
+                }
+                path.push(name);
+                continue;
+            }
+            Ok(Event::Empty(e)) => {
+                root.get_or_insert_with(|| String::from_utf8_lossy(e.local_name().as_ref()).into_owned());
+                continue;
+            }
+            Ok(Event::End(_)) => {
+                if path.pop().is_some() && path.len() == 1 {
+                    entries.extend(entry.take().filter(|e| !e.loc.is_empty()));
+                }
+                continue;
+            }
+            Ok(Event::Text(t)) => t.unescape().map_err(|e| e.to_string())?.into_owned(),
+            Ok(Event::CData(c)) => String::from_utf8_lossy(&c.into_inner()).into_owned(),
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(format!("invalid XML at byte {}: {e}", reader.error_position())),
+            Ok(_) => continue,
+        };
+        // Only the entry's own fields: extensions such as <image:loc> nest deeper
+        let (Some(entry), [_, _, field]) = (entry.as_mut(), path.as_slice()) else {
+            continue;
+        };
+        match field.as_str() {
+            "loc" => entry.loc.push_str(text.trim()),
+            "lastmod" => entry.lastmod.get_or_insert_with(String::new).push_str(text.trim()),
+            _ => {}
+        }
+    }
+    match root.as_deref() {
+        Some("urlset") => Ok(Sitemap::Urls(entries)),
+        Some("sitemapindex") => Ok(Sitemap::Index(entries)),
+        Some(other) => Err(format!("not a sitemap: the root element is <{other}>")),
+        None => Err("not a sitemap: no root element".to_string()),
+    }
+}
+
+/// The `Sitemap:` lines of a robots.txt file.
+pub(crate) fn robots_sitemaps(robots: &str) -> Vec<String> {
+    robots
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(key, _)| key.trim().eq_ignore_ascii_case("sitemap"))
+        .map(|(_, value)| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+/// RSS and Atom feeds a page links to, resolved against `base`.
+pub(crate) fn feed_links(dom: &DomNode, base: &Url) -> Vec<FeedLink> {
+    let mut feeds: Vec<FeedLink> = Vec::new();
+    collect_feed_links(dom, base, &mut feeds);
+    feeds
+}
+
+fn collect_feed_links(node: &DomNode, base: &Url, feeds: &mut Vec<FeedLink>) {
+    if node.tag == "link" {
+        let alternate = node
+            .get_attr("rel")
+            .is_some_and(|rel| rel.split_whitespace().any(|r| r.eq_ignore_ascii_case("alternate")));
+        let kind = match node.get_attr("type").map(|t| t.trim().to_ascii_lowercase()).as_deref() {
+            Some("application/rss+xml") => Some(FeedKind::Rss),
+            Some("application/atom+xml") => Some(FeedKind::Atom),
+            _ => None,
+        };
+        let href = node.get_attr("href").and_then(|href| base.join(href.trim()).ok());
+        if let (true, Some(kind), Some(href)) = (alternate, kind, href) {
+            if !feeds.iter().any(|f| f.href == href.as_str()) {
+                feeds.push(FeedLink {
+                    href: href.to_string(),
+                    kind,
+                    title: node.get_attr("title").map(str::trim).filter(|t| !t.is_empty()).map(str::to_string),
+                });
+            }
+        }
+    }
+    for child in &node.children {
+        collect_feed_links(child, base, feeds);
+    }
+}
@@ -0,0 +1,429 @@
+//! Scripted flows: steps like "goto", "type", "click" and "assert" run in
+//! order against one `Session`, so a check such as "sign in and land on the
+//! dashboard" can run in CI from a YAML or JSON file (`browsy run`).
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use super::{FetchError, Session};
+use crate::output::{PageType, SpatialDom, SpatialElement};
+
+/// Steps run in order against one session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Flow {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(deserialize_with = "checked_steps")]
+    pub steps: Vec<Step>,
+}
+
+/// Steps, refusing any whose target or assert gives nothing to match: field
+/// names can't be checked for typos where a target is flattened into its
+/// step, so `type: { lable: Email, text: x }` would otherwise match anything.
+fn checked_steps<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Step>, D::Error> {
+    let steps = Vec::<Step>::deserialize(deserializer)?;
+    for (i, step) in steps.iter().enumerate() {
+        let empty = match step {
+            Step::Click(target) => Some(("click", *target == Target::default())),
+            Step::Type { field, .. } => Some(("type", *field == FieldTarget::default())),
+            Step::Select { field, .. } => Some(("select", *field == FieldTarget::default())),
+            Step::Check(field) => Some(("check", *field == FieldTarget::default())),
+            Step::Assert(assertion) => Some((
+                "assert",
+                *assertion == Assertion::default() || assertion.element_exists == Some(Target::default()),
+            )),
+            _ => None,
+        };
+        if let Some((kind, true)) = empty {
+            return Err(serde::de::Error::custom(format!("step {} ({kind}) gives nothing to match", i + 1)));
+        }
+    }
+    Ok(steps)
+}
+
+/// One step of a flow, written as a map with a single key:
+/// `goto: https://example.com/login` or `click: { text: Sign in }`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    /// Navigate to a URL.
+    Goto(String),
+    /// Click the element the target picks.
+    Click(Target),
+    /// Type `text` into a field.
+    Type {
+        #[serde(flatten)]
+        field: FieldTarget,
+        text: String,
+    },
+    /// Choose `value` in a select.
+    Select {
+        #[serde(flatten)]
+        field: FieldTarget,
+        value: String,
+    },
+    /// Check a checkbox or radio button.
+    Check(FieldTarget),
+    /// Stop the flow unless the current page passes every check given.
+    Assert(Assertion),
+    /// Write the current page's tables or links to JSON files.
+    Extract(Extraction),
+    /// Save a snapshot of the page into a new folder under this directory.
+    Snapshot(PathBuf),
+}
+
+/// Picks an element on the current page. Every field given has to match.
+/// Of several matches, elements whose whole text matches come first, then
+/// the most prominent; an exact label beats one that only contains it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Target {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u32>,
+    /// Text the element contains, ignoring case and extra whitespace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// ARIA role, such as `button` or `link`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    /// The element's label, ignoring case.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// The `name` attribute.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// Picks a form field by ID, label or `name` attribute, as `Target` does.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FieldTarget {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// Checks on the current page. Every one given has to pass.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Assertion {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_type: Option<PageType>,
+    /// Part of the title, matched exactly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title_contains: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub element_exists: Option<Target>,
+    /// Text some element contains, ignoring case and extra whitespace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text_contains: Option<String>,
+}
+
+/// Files to write the current page's data to, as pretty-printed JSON.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Extraction {
+    /// For `SpatialDom::tables`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tables: Option<PathBuf>,
+    /// For every link's `id`, `text` and `href`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub links: Option<PathBuf>,
+}
+
+/// Why a step failed.
+#[derive(Debug)]
+pub enum StepError {
+    /// The session action failed, or there's no page yet.
+    Fetch(FetchError),
+    /// No element on the page matches the target, shown as written.
+    NotFound(String),
+    /// A check failed: its name, what the flow expected and what the page had.
+    Assertion { check: &'static str, expected: String, actual: String },
+    /// An extract file couldn't be written.
+    Io(String),
+}
+
+impl fmt::Display for StepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StepError::Fetch(e) => write!(f, "{e}"),
+            StepError::NotFound(target) => write!(f, "no element matches {target}"),
+            StepError::Assertion { check, expected, actual } => {
+                write!(f, "{check}: expected {expected}, got {actual}")
+            }
+            StepError::Io(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for StepError {}
+
+impl From<FetchError> for StepError {
+    fn from(e: FetchError) -> Self {
+        StepError::Fetch(e)
+    }
+}
+
+/// Where a flow stopped and why.
+#[derive(Debug)]
+pub struct StepFailure {
+    /// From 1.
+    pub index: usize,
+    pub step: Step,
+    pub error: StepError,
+    /// The snapshot saved for the failure, when the run asked for one.
+    pub snapshot: Option<PathBuf>,
+}
+
+impl Flow {
+    pub fn from_json(json: &str) -> Result<Flow, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Run the steps in order, calling `passed` with each one's index, from
+    /// 1, as it succeeds, and stop at the first failure. With
+    /// `snapshot_on_failure`, the page at that point is saved there too.
+    pub fn run(
+        &self,
+        session: &mut Session,
+        snapshot_on_failure: Option<&Path>,
+        mut passed: impl FnMut(usize, &Step),
+    ) -> Result<(), Box<StepFailure>> {
+        for (i, step) in self.steps.iter().enumerate() {
+            if let Err(error) = step.run(session) {
+                let snapshot = snapshot_on_failure.and_then(|dir| session.save_snapshot(dir).ok());
+                return Err(Box::new(StepFailure { index: i + 1, step: step.clone(), error, snapshot }));
+            }
+            passed(i + 1, step);
+        }
+        Ok(())
+    }
+}
+
+impl Step {
+    /// Run this step against the session's current page.
+    pub fn run(&self, session: &mut Session) -> Result<(), StepError> {
+        match self {
+            Step::Goto(url) => {
+                session.goto(url)?;
+            }
+            Step::Click(target) => {
+                let id = find(session, target)?;
+                session.click(id)?;
+            }
+            Step::Type { field, text } => {
+                let id = find(session, &field.target())?;
+                session.type_text(id, text)?;
+            }
+            Step::Select { field, value } => {
+                let id = find(session, &field.target())?;
+                session.select(id, value)?;
+            }
+            Step::Check(field) => {
+                let id = find(session, &field.target())?;
+                session.check(id)?;
+            }
+            Step::Assert(assertion) => assertion.check(page(session)?)?,
+            Step::Extract(extraction) => extraction.write(page(session)?)?,
+            Step::Snapshot(dir) => {
+                session.save_snapshot(dir)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn page(session: &Session) -> Result<&SpatialDom, StepError> {
+    session.dom_ref().ok_or(StepError::Fetch(FetchError::NoPageLoaded))
+}
+
+fn find(session: &Session, target: &Target) -> Result<u32, StepError> {
+    target.resolve(page(session)?).ok_or_else(|| StepError::NotFound(target.to_string()))
+}
+
+impl Target {
+    /// The ID of the element this target picks on `dom`, if any. A target
+    /// that gives nothing to match picks nothing.
+    pub fn resolve(&self, dom: &SpatialDom) -> Option<u32> {
+        if *self == Target::default() {
+            return None;
+        }
+        let candidates: Vec<&SpatialElement> = match &self.text {
+            Some(text) => dom.find_by_text(text),
+            None => dom.els.iter().collect(),
+        };
+        let pick = |exact_label: bool| candidates.iter().find(|el| self.matches(el, exact_label)).map(|el| el.id);
+        pick(true).or_else(|| pick(false))
+    }
+
+    /// Whether `el` matches everything but the text, which `resolve` has
+    /// already matched.
+    fn matches(&self, el: &SpatialElement, exact_label: bool) -> bool {
+        let label_matches = |wanted: &String| {
+            let wanted = wanted.trim().to_lowercase();
+            el.label.as_deref().map(|label| label.trim().to_lowercase()).is_some_and(|label| {
+                if exact_label {
+                    label == wanted
+                } else {
+                    label.contains(&wanted)
+                }
+            })
+        };
+        self.id.is_none_or(|id| el.id == id)
+            && self.role.as_deref().is_none_or(|role| el.role.as_deref() == Some(role))
+            && self.name.as_deref().is_none_or(|name| el.name.as_deref() == Some(name))
+            && self.label.as_ref().is_none_or(label_matches)
+    }
+}
+
+impl FieldTarget {
+    fn target(&self) -> Target {
+        Target { id: self.id, label: self.label.clone(), name: self.name.clone(), ..Target::default() }
+    }
+}
+
+impl Assertion {
+    /// Pass, or the first check that fails on `dom`.
+    pub fn check(&self, dom: &SpatialDom) -> Result<(), StepError> {
+        if let Some(ref expected) = self.page_type {
+            if dom.page_type != *expected {
+                return Err(StepError::Assertion {
+                    check: "page_type",
+                    expected: format!("{expected:?}"),
+                    actual: format!("{:?}", dom.page_type),
+                });
+            }
+        }
+        if let Some(ref expected) = self.title_contains {
+            if !dom.title.contains(expected.as_str()) {
+                return Err(StepError::Assertion {
+                    check: "title_contains",
+                    expected: format!("{expected:?}"),
+                    actual: format!("{:?}", dom.title),
+                });
+            }
+        }
+        if let Some(ref target) = self.element_exists {
+            if target.resolve(dom).is_none() {
+                return Err(StepError::Assertion {
+                    check: "element_exists",
+                    expected: target.to_string(),
+                    actual: "no such element".to_string(),
+                });
+            }
+        }
+        if let Some(ref text) = self.text_contains {
+            if dom.find_by_text(text).is_empty() {
+                return Err(StepError::Assertion {
+                    check: "text_contains",
+                    expected: format!("{text:?}"),
+                    actual: "no element with that text".to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Extraction {
+    /// Write the files asked for, creating their directories.
+    pub fn write(&self, dom: &SpatialDom) -> Result<(), StepError> {
+        if let Some(ref path) = self.tables {
+            write_json(path, &serde_json::to_value(dom.tables()).unwrap_or_default())?;
+        }
+        if let Some(ref path) = self.links {
+            let links: Vec<serde_json::Value> = dom
+                .els
+                .iter()
+                .filter(|el| el.tag == "a")
+                .filter_map(|el| Some(serde_json::json!({ "id": el.id, "text": el.text, "href": el.href.as_ref()? })))
+                .collect();
+            write_json(path, &serde_json::Value::Array(links))?;
+        }
+        Ok(())
+    }
+}
+
+fn write_json(path: &Path, value: &serde_json::Value) -> Result<(), StepError> {
+    let io_error = |e: std::io::Error| StepError::Io(format!("{}: {e}", path.display()));
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).map_err(io_error)?;
+    }
+    let json = serde_json::to_string_pretty(value).unwrap_or_default();
+    std::fs::write(path, json + "\n").map_err(io_error)
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(id) = self.id {
+            parts.push(format!("id={id}"));
+        }
+        if let Some(ref text) = self.text {
+            parts.push(format!("text={text:?}"));
+        }
+        if let Some(ref role) = self.role {
+            parts.push(format!("role={role}"));
+        }
+        if let Some(ref label) = self.label {
+            parts.push(format!("label={label:?}"));
+        }
+        if let Some(ref name) = self.name {
+            parts.push(format!("name={name:?}"));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+impl fmt::Display for FieldTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.target())
+    }
+}
+
+impl fmt::Display for Assertion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(ref page_type) = self.page_type {
+            parts.push(format!("page_type={page_type:?}"));
+        }
+        if let Some(ref title) = self.title_contains {
+            parts.push(format!("title_contains={title:?}"));
+        }
+        if let Some(ref target) = self.element_exists {
+            parts.push(format!("element_exists=({target})"));
+        }
+        if let Some(ref text) = self.text_contains {
+            parts.push(format!("text_contains={text:?}"));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// How reports show a step. Typed text is left out, as it may be a password.
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::Goto(url) => write!(f, "goto {url}"),
+            Step::Click(target) => write!(f, "click {target}"),
+            Step::Type { field, .. } => write!(f, "type into {field}"),
+            Step::Select { field, value } => write!(f, "select {value:?} in {field}"),
+            Step::Check(field) => write!(f, "check {field}"),
+            Step::Assert(assertion) => write!(f, "assert {assertion}"),
+            Step::Extract(extraction) => {
+                let files = [("tables", &extraction.tables), ("links", &extraction.links)];
+                let parts: Vec<String> = files
+                    .iter()
+                    .filter_map(|(what, path)| Some(format!("{what} to {}", path.as_ref()?.display())))
+                    .collect();
+                write!(f, "extract {}", parts.join(", "))
+            }
+            Step::Snapshot(dir) => write!(f, "snapshot to {}", dir.display()),
+        }
+    }
+}
@@ -1,9 +1,26 @@
 //! HTTP fetching, session management, and agent actions.
 //! Gated behind the "fetch" feature flag.
 
+mod audit;
+mod conditional;
+mod cookies;
+mod crawl;
+mod feeds;
+mod flow;
+mod paginate;
+mod policy;
+mod preview;
+pub mod redact;
+mod recording;
+mod retry;
 mod session;
+mod site;
+mod snapshot;
+mod state;
+mod watch;
 
 pub use session::{
+    ConsentChoice,
     DomainMemory,
     Session,
     SessionConfig,
@@ -11,17 +28,36 @@ pub use session::{
     SearchResult,
     SearchPage,
     InputPurpose,
+    LoginOutcome,
+    LoginStatus,
+    NavStats,
+    Timings,
     extract_search_results_from,
     extract_google_results_from,
 };
+pub use audit::{AuditAction, AuditEvent, AuditLog};
+pub use cookies::CookieJar;
+pub use crawl::{normalize_url, CrawlEntry, CrawlOpts};
+pub use feeds::{parse_sitemap, DiscoveredFeeds, FeedKind, FeedLink, Sitemap, SitemapEntry, MAX_SITEMAPS};
+pub use flow::{Assertion, Extraction, FieldTarget, Flow, Step, StepError, StepFailure, Target};
+pub use policy::ActionPolicy;
+pub use preview::{ActionPreview, PreviewField};
+pub use paginate::{Extract, PaginatedResult, StopReason, DEFAULT_MAX_PAGES};
+pub use recording::{default_ignored_params, fnv1a, Exchange, RecordingMode};
+pub use retry::{Attempt, RetryCondition, RetryPolicy};
+pub use site::{SiteOverride, SiteOverrides};
+pub use state::SessionState;
+pub use watch::{backoff_delay, WatchConfig, WatchEvent, Watcher};
 
 use crate::output::SpatialDom;
 use reqwest::blocking::Client;
 use reqwest::redirect::Policy;
-use reqwest::header::{RETRY_AFTER, USER_AGENT};
-use serde::Serialize;
+use reqwest::header::USER_AGENT;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::io::Read;
 use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 use url::Url;
@@ -35,53 +71,53 @@ pub fn fetch(url: &str, config: &FetchConfig) -> Result<SpatialDom, FetchError>
 
     let allow_private = config.allow_private_network;
     let allow_non_http = config.allow_non_http;
-    let max_redirects = config.max_redirects;
     let client = Client::builder()
         .user_agent(&config.user_agent)
         .timeout(std::time::Duration::from_secs(config.timeout_secs))
-        .redirect(Policy::custom(move |attempt| {
-            if attempt.previous().len() >= max_redirects {
-                return attempt.stop();
-            }
-            if !is_url_allowed(attempt.url(), allow_private, allow_non_http) {
-                return attempt.stop();
-            }
-            attempt.follow()
-        }))
+        .redirect(redirect_policy(
+            config.max_redirects,
+            move |url| is_url_allowed(url, allow_private, allow_non_http),
+            Arc::default(),
+        ))
         .build()
         .map_err(|e| FetchError::Network(e.to_string()))?;
 
-    let html = fetch_html_with_retry(&client, &parsed_url, config)?;
+    let send: &SendFn<'_> = &|request| send_http(&client, request);
+    let html = fetch_with_retry(send, &parsed_url, &[], config, &mut Vec::new())?.body;
 
     let dom_tree = crate::dom::parse_html(&html);
-    let external_css = if config.fetch_css {
+    let (external_css, _) = if config.fetch_css {
+        let allowed = |url: &Url| {
+            is_url_allowed(url, config.allow_private_network, config.allow_non_http)
+                && matching_pattern(url, &config.blocked_patterns).is_none()
+        };
         fetch_external_css(
             &dom_tree,
             &parsed_url,
-            &client,
-            &config.blocked_patterns,
+            send,
+            &allowed,
             config.max_css_bytes_total,
             config.max_css_bytes_per_file,
-            config.allow_private_network,
-            config.allow_non_http,
         )
     } else {
-        String::new()
+        (String::new(), CssStats::default())
     };
 
+    let layout_width = crate::css::layout_viewport_width(&dom_tree, config.viewport_width);
     let styled = if external_css.is_empty() {
-        crate::css::compute_styles_with_viewport(&dom_tree, config.viewport_width, config.viewport_height)
+        crate::css::compute_styles_with_viewport(&dom_tree, layout_width, config.viewport_height)
     } else {
-        crate::css::compute_styles_with_external_and_viewport(&dom_tree, &external_css, config.viewport_width, config.viewport_height)
+        crate::css::compute_styles_with_external_and_viewport(&dom_tree, &external_css, layout_width, config.viewport_height)
     };
 
     let laid_out =
-        crate::layout::compute_layout(&styled, config.viewport_width, config.viewport_height);
+        crate::layout::compute_layout(&styled, layout_width, config.viewport_height);
     let mut spatial = crate::output::generate_spatial_dom(
         &laid_out,
         config.viewport_width,
         config.viewport_height,
     );
+    spatial.set_layout_width(layout_width);
     spatial.url = url.to_string();
     crate::output::resolve_urls(&mut spatial, url);
     Ok(spatial)
@@ -100,9 +136,8 @@ pub struct FetchConfig {
     pub allow_private_network: bool,
     pub allow_non_http: bool,
     pub blocked_patterns: Vec<String>,
-    pub retry_attempts: usize,
-    pub retry_delay_ms: u64,
-    pub retry_on_blocked: bool,
+    /// How the fetch retries transient failures, as `SessionConfig::retry`.
+    pub retry: RetryPolicy,
     pub retry_user_agents: Vec<String>,
 }
 
@@ -121,9 +156,7 @@ impl Default for FetchConfig {
             allow_private_network: false,
             allow_non_http: false,
             blocked_patterns: default_blocked_patterns(),
-            retry_attempts: 2,
-            retry_delay_ms: 250,
-            retry_on_blocked: true,
+            retry: RetryPolicy::default(),
             retry_user_agents: default_retry_user_agents(),
         }
     }
@@ -134,9 +167,32 @@ pub enum FetchError {
     InvalidUrl(String),
     BlockedUrl(String),
     Network(String),
+    /// The request or the response body took longer than `timeout_secs`.
+    Timeout(String),
     HttpError(u16),
+    /// An action failed for a reason without a variant of its own.
     ActionError(String),
+    /// No element has this ID on the current page. `known_ids` is the lowest
+    /// and highest ID the page does have.
+    ElementNotFound { id: u32, known_ids: Option<(u32, u32)> },
+    /// The element can't take the action: `expected` is what it would have
+    /// to be (`text input`, `select`, `checkbox or radio`).
+    WrongElementType { id: u32, tag: String, expected: String },
+    /// The element is `disabled`, or `readonly` when typing, so the page
+    /// would ignore the action. For a disabled submit button,
+    /// `empty_required` lists its form's required fields that have no value.
+    ElementUnavailable { id: u32, tag: String, state: String, empty_required: Vec<u32> },
+    NoPageLoaded,
     ResponseTooLarge(u64, usize),
+    /// A redirect chain went back to a URL it had already visited, or ran
+    /// past `max_redirects`. `chain` starts with the requested URL.
+    TooManyRedirects { chain: Vec<String> },
+    /// `SessionConfig::action_policy` holds the action until
+    /// `Session::confirm(token)` takes it. `description` says what it does.
+    ConfirmationRequired { token: String, description: String },
+    /// The URL answered with content that isn't a page, such as an image,
+    /// an archive, or a PDF without the `pdf` feature.
+    BinaryContent { url: String, content_type: String, bytes: u64 },
 }
 
 impl std::fmt::Display for FetchError {
@@ -145,17 +201,330 @@ impl std::fmt::Display for FetchError {
             FetchError::InvalidUrl(e) => write!(f, "Invalid URL: {}", e),
             FetchError::BlockedUrl(u) => write!(f, "Blocked URL: {}", u),
             FetchError::Network(e) => write!(f, "Network error: {}", e),
+            FetchError::Timeout(e) => write!(f, "Timed out: {}", e),
             FetchError::HttpError(code) => write!(f, "HTTP error: {}", code),
             FetchError::ActionError(e) => write!(f, "Action error: {}", e),
+            FetchError::ElementNotFound { id, .. } => write!(f, "Action error: Element {} not found", id),
+            FetchError::WrongElementType { id, tag, expected } => {
+                write!(f, "Action error: Element {} ({}) is not a {}", id, tag, expected)
+            }
+            FetchError::ElementUnavailable { id, tag, state, empty_required } => {
+                write!(f, "Action error: Element {} ({}) is {}", id, tag, state)?;
+                if !empty_required.is_empty() {
+                    let ids: Vec<String> = empty_required.iter().map(|id| id.to_string()).collect();
+                    write!(f, "; required fields still empty: {}", ids.join(", "))?;
+                }
+                Ok(())
+            }
+            FetchError::NoPageLoaded => write!(f, "Action error: No page loaded"),
             FetchError::ResponseTooLarge(found, max) => write!(f, "Response too large: {} bytes (max {})", found, max),
+            FetchError::TooManyRedirects { chain } => {
+                let looped = chain.last().is_some_and(|last| chain[..chain.len() - 1].contains(last));
+                let what = if looped { "Redirect loop" } else { "Too many redirects" };
+                write!(f, "{}: {}", what, chain.join(" -> "))
+            }
+            FetchError::ConfirmationRequired { token, description } => {
+                write!(f, "Confirmation required: {} (confirm with token {})", description, token)
+            }
+            FetchError::BinaryContent { url, content_type, bytes } => {
+                write!(f, "Not a page: {} is {} ({} bytes)", url, content_type, bytes)
+            }
         }
     }
 }
 
 impl std::error::Error for FetchError {}
 
+impl FetchError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            FetchError::InvalidUrl(_) => ErrorCode::InvalidUrl,
+            FetchError::BlockedUrl(_) => ErrorCode::BlockedUrl,
+            FetchError::Network(_) => ErrorCode::Network,
+            FetchError::Timeout(_) => ErrorCode::Timeout,
+            FetchError::HttpError(_) => ErrorCode::HttpStatus,
+            FetchError::ActionError(_) => ErrorCode::ActionFailed,
+            FetchError::ElementNotFound { .. } => ErrorCode::ActionElementNotFound,
+            FetchError::WrongElementType { .. } => ErrorCode::ActionWrongElementType,
+            FetchError::ElementUnavailable { .. } => ErrorCode::ActionElementUnavailable,
+            FetchError::NoPageLoaded => ErrorCode::NoPageLoaded,
+            FetchError::ResponseTooLarge(_, _) => ErrorCode::ResponseTooLarge,
+            FetchError::TooManyRedirects { .. } => ErrorCode::TooManyRedirects,
+            FetchError::ConfirmationRequired { .. } => ErrorCode::ConfirmationRequired,
+            FetchError::BinaryContent { .. } => ErrorCode::BinaryContent,
+        }
+    }
+
+    /// The error's particulars as JSON, for clients that act on them rather
+    /// than on the message. `None` when the message says it all.
+    pub fn details(&self) -> Option<serde_json::Value> {
+        use serde_json::json;
+        match self {
+            FetchError::BlockedUrl(url) => Some(json!({ "url": url })),
+            FetchError::HttpError(status) => Some(json!({ "status": status })),
+            FetchError::ElementNotFound { id, known_ids } => Some(match known_ids {
+                Some((first, last)) => json!({ "id": id, "known_ids_range": [first, last] }),
+                None => json!({ "id": id }),
+            }),
+            FetchError::WrongElementType { id, tag, expected } => {
+                Some(json!({ "id": id, "tag": tag, "expected": expected }))
+            }
+            FetchError::ElementUnavailable { id, tag, state, empty_required } => {
+                Some(json!({ "id": id, "tag": tag, "state": state, "empty_required": empty_required }))
+            }
+            FetchError::ResponseTooLarge(size, max) => Some(json!({ "size": size, "max": max })),
+            FetchError::TooManyRedirects { chain } => Some(json!({ "redirect_chain": chain })),
+            FetchError::ConfirmationRequired { token, description } => {
+                Some(json!({ "token": token, "description": description }))
+            }
+            FetchError::BinaryContent { url, content_type, bytes } => {
+                Some(json!({ "url": url, "content_type": content_type, "bytes": bytes }))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// What kind of error a client got, so it can branch without matching on
+/// messages. Written in snake_case (`no_page_loaded`). The codes after
+/// `NoPageLoaded` come from the servers rather than from a `FetchError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    InvalidUrl,
+    BlockedUrl,
+    Network,
+    Timeout,
+    HttpStatus,
+    ResponseTooLarge,
+    TooManyRedirects,
+    /// The URL isn't a page; `details` has its content type and size.
+    BinaryContent,
+    ActionElementNotFound,
+    ActionWrongElementType,
+    ActionElementUnavailable,
+    ActionFailed,
+    /// The action is held until it's confirmed; `details` has the token.
+    ConfirmationRequired,
+    NoPageLoaded,
+    /// The request named a session that has expired or never existed.
+    SessionExpired,
+    /// The server has as many sessions open as it allows.
+    SessionLimit,
+    /// The request's body or parameters are malformed.
+    InvalidRequest,
+    Unauthorized,
+    RateLimited,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::InvalidUrl => "invalid_url",
+            ErrorCode::BlockedUrl => "blocked_url",
+            ErrorCode::Network => "network",
+            ErrorCode::Timeout => "timeout",
+            ErrorCode::HttpStatus => "http_status",
+            ErrorCode::ResponseTooLarge => "response_too_large",
+            ErrorCode::TooManyRedirects => "too_many_redirects",
+            ErrorCode::BinaryContent => "binary_content",
+            ErrorCode::ActionElementNotFound => "action_element_not_found",
+            ErrorCode::ActionWrongElementType => "action_wrong_element_type",
+            ErrorCode::ActionElementUnavailable => "action_element_unavailable",
+            ErrorCode::ActionFailed => "action_failed",
+            ErrorCode::ConfirmationRequired => "confirmation_required",
+            ErrorCode::NoPageLoaded => "no_page_loaded",
+            ErrorCode::SessionExpired => "session_expired",
+            ErrorCode::SessionLimit => "session_limit",
+            ErrorCode::InvalidRequest => "invalid_request",
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::RateLimited => "rate_limited",
+            ErrorCode::Internal => "internal",
+        }
+    }
+}
+
+impl std::fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 // --- Shared helpers used by both fetch() and Session ---
 
+/// Response headers kept on an [`HttpResponse`], and in recorded fixtures
+/// except for `set-cookie`.
+const KEPT_HEADERS: &[&str] = &["content-type", "etag", "last-modified", "location", "retry-after", "set-cookie"];
+
+/// A request issued by the fetch layer: a GET, or a POST with a form body.
+pub(crate) struct HttpRequest<'a> {
+    pub method: &'static str,
+    pub url: &'a Url,
+    /// URL-encoded form body (POST only).
+    pub form: &'a [(String, String)],
+    pub user_agent: Option<&'a str>,
+    /// Extra request headers, such as `If-None-Match`.
+    pub headers: &'a [(String, String)],
+    /// Bodies larger than this fail with `ResponseTooLarge`.
+    pub max_bytes: usize,
+}
+
+/// A response with its body read. The body is only read for 2xx statuses.
+pub(crate) struct HttpResponse {
+    pub status: u16,
+    /// Final URL after redirects.
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    /// The body of a response that isn't text, such as a PDF, as sent;
+    /// `body` is empty then. Not kept in recorded fixtures.
+    pub binary: Option<Vec<u8>>,
+}
+
+impl HttpResponse {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Bytes in the body, text or not.
+    pub fn body_len(&self) -> usize {
+        self.binary.as_ref().map_or(self.body.len(), Vec::len)
+    }
+}
+
+/// The redirect chain of a client's latest request, written by the client's
+/// [`redirect_policy`]: the requested URL, then every URL it was redirected
+/// to. Empty when the request wasn't redirected.
+#[derive(Debug, Default)]
+pub(crate) struct RedirectLog(Mutex<Vec<String>>);
+
+impl RedirectLog {
+    /// Forget the chain, before a new request is sent.
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+
+    pub fn take(&self) -> Vec<String> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+/// Ends a redirect chain from inside the policy; `send_http` turns it into
+/// `FetchError::TooManyRedirects`.
+#[derive(Debug)]
+struct RedirectChainError(Vec<String>);
+
+impl std::fmt::Display for RedirectChainError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "redirect chain: {}", self.0.join(" -> "))
+    }
+}
+
+impl std::error::Error for RedirectChainError {}
+
+/// Follows up to `max_redirects` redirects, stopping at (and returning) one
+/// that leads where `may_follow` says not to go. A chain that comes back to
+/// a URL it already visited fails straight away instead of running out the
+/// limit. Each followed redirect is written to `log`.
+pub(crate) fn redirect_policy(
+    max_redirects: usize,
+    may_follow: impl Fn(&Url) -> bool + Send + Sync + 'static,
+    log: Arc<RedirectLog>,
+) -> Policy {
+    Policy::custom(move |attempt| {
+        let chain: Vec<String> = attempt
+            .previous()
+            .iter()
+            .chain([attempt.url()])
+            .map(|url| url.to_string())
+            .collect();
+        if attempt.previous().contains(attempt.url()) || attempt.previous().len() > max_redirects {
+            *log.0.lock().unwrap() = chain.clone();
+            return attempt.error(RedirectChainError(chain));
+        }
+        if !may_follow(attempt.url()) {
+            return attempt.stop();
+        }
+        *log.0.lock().unwrap() = chain;
+        attempt.follow()
+    })
+}
+
+/// How the fetch helpers issue requests: over the network, or through a
+/// session's recorder.
+pub(crate) type SendFn<'a> = dyn Fn(&HttpRequest<'_>) -> Result<HttpResponse, FetchError> + 'a;
+
+/// Send a request over the network.
+pub(crate) fn send_http(client: &Client, request: &HttpRequest<'_>) -> Result<HttpResponse, FetchError> {
+    let mut builder = match request.method {
+        "POST" => client.post(request.url.as_str()).form(request.form),
+        "HEAD" => client.head(request.url.as_str()),
+        _ => client.get(request.url.as_str()),
+    };
+    if let Some(ua) = request.user_agent {
+        builder = builder.header(USER_AGENT, ua);
+    }
+    for (name, value) in request.headers {
+        builder = builder.header(name.as_str(), value);
+    }
+    let response = builder.send().map_err(network_error)?;
+
+    let status = response.status();
+    let url = response.url().to_string();
+    let headers: Vec<(String, String)> = KEPT_HEADERS
+        .iter()
+        .flat_map(|name| {
+            response
+                .headers()
+                .get_all(*name)
+                .iter()
+                .filter_map(|v| v.to_str().ok())
+                .map(|v| (name.to_string(), v.to_string()))
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    let bytes = if status.is_success() {
+        read_response_limited(response, request.max_bytes)?
+    } else {
+        Vec::new()
+    };
+    let content_type = headers.iter().find(|(name, _)| name == "content-type").map(|(_, v)| v.as_str());
+    let (body, binary) = if is_text(content_type, &bytes) {
+        (String::from_utf8_lossy(&bytes).to_string(), None)
+    } else {
+        (String::new(), Some(bytes))
+    };
+    Ok(HttpResponse {
+        status: status.as_u16(),
+        url,
+        headers,
+        body,
+        binary,
+    })
+}
+
+/// Whether a body is text browsy reads as a page, stylesheet or feed: its
+/// `Content-Type` is text, HTML, XML or JSON, or missing, and it isn't a
+/// PDF served under another type.
+fn is_text(content_type: Option<&str>, body: &[u8]) -> bool {
+    if body.starts_with(b"%PDF-") {
+        return false;
+    }
+    let Some(content_type) = content_type else { return true };
+    let mime = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    mime.is_empty()
+        || mime.starts_with("text/")
+        || ["html", "xml", "json", "javascript"].iter().any(|kind| mime.contains(kind))
+}
+
 fn default_blocked_patterns() -> Vec<String> {
     [
         "doubleclick.net", "googlesyndication.com", "googleadservices.com",
@@ -187,46 +556,61 @@ fn default_retry_user_agents() -> Vec<String> {
     .collect()
 }
 
+/// What fetching a page's external stylesheets took.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct CssStats {
+    /// Stylesheets fetched successfully.
+    pub fetched: u32,
+    /// Stylesheets not requested because the URL policy blocked them.
+    pub blocked: u32,
+    pub bytes: u64,
+}
+
 fn fetch_external_css(
     dom: &crate::dom::DomNode,
     base_url: &Url,
-    client: &Client,
-    blocked: &[String],
+    send: &SendFn<'_>,
+    allowed: &dyn Fn(&Url) -> bool,
     max_total_bytes: usize,
     max_per_file_bytes: usize,
-    allow_private: bool,
-    allow_non_http: bool,
-) -> String {
+) -> (String, CssStats) {
     let mut css = String::new();
+    let mut stats = CssStats::default();
     let mut remaining = max_total_bytes;
     collect_link_hrefs(dom, &mut |href| {
         if remaining == 0 {
             return;
         }
         if let Ok(css_url) = base_url.join(href) {
-            if !is_url_allowed(&css_url, allow_private, allow_non_http) {
+            if !allowed(&css_url) {
+                stats.blocked += 1;
                 return;
             }
-            let url_str = css_url.as_str();
-            if blocked.iter().any(|p| url_str.contains(p.as_str())) {
+            let limit = remaining.min(max_per_file_bytes);
+            if limit == 0 {
                 return;
             }
-            if let Ok(resp) = client.get(url_str).send() {
-                if resp.status().is_success() {
-                    let limit = remaining.min(max_per_file_bytes);
-                    if limit == 0 {
-                        return;
-                    }
-                    if let Ok(text) = read_response_text_limited(resp, limit) {
-                        remaining = remaining.saturating_sub(text.len());
-                        css.push_str(&text);
-                        css.push('\n');
-                    }
+            let request = HttpRequest {
+                method: "GET",
+                url: &css_url,
+                form: &[],
+                user_agent: None,
+                headers: &[],
+                max_bytes: limit,
+            };
+            let _span = tracing::debug_span!("css_fetch", url = %css_url).entered();
+            if let Ok(resp) = send(&request) {
+                if resp.is_success() {
+                    remaining = remaining.saturating_sub(resp.body.len());
+                    stats.fetched += 1;
+                    stats.bytes += resp.body.len() as u64;
+                    css.push_str(&resp.body);
+                    css.push('\n');
                 }
             }
         }
     });
-    css
+    (css, stats)
 }
 
 fn collect_link_hrefs(node: &crate::dom::DomNode, callback: &mut dyn FnMut(&str)) {
@@ -270,77 +654,99 @@ pub(crate) struct FormButton {
     pub text: Option<String>,
 }
 
+/// Every `<form>` on the page in document order, with its controls: those
+/// inside it, and those elsewhere whose `form` attribute names its id.
 pub(crate) fn extract_forms(node: &crate::dom::DomNode) -> Vec<FormInfo> {
     let mut forms = Vec::new();
-    collect_forms(node, &mut forms);
+    let mut ids = HashMap::new();
+    collect_forms(node, &mut forms, &mut ids);
+    collect_form_fields(node, None, &mut 0, &ids, &mut forms);
     forms
 }
 
-fn collect_forms(node: &crate::dom::DomNode, forms: &mut Vec<FormInfo>) {
+fn collect_forms(node: &crate::dom::DomNode, forms: &mut Vec<FormInfo>, ids: &mut HashMap<String, usize>) {
     if node.tag == "form" {
+        if let Some(id) = node.get_attr("id") {
+            ids.entry(id.to_string()).or_insert(forms.len());
+        }
         let action = node.get_attr("action").map(|s| s.to_string());
         let method = node.get_attr("method").map(|s| s.to_string());
-        let mut fields = Vec::new();
-        let mut buttons = Vec::new();
-        collect_form_fields(node, &mut fields, &mut buttons);
-        forms.push(FormInfo { action, method, fields, buttons });
+        forms.push(FormInfo { action, method, fields: Vec::new(), buttons: Vec::new() });
     }
     for child in &node.children {
-        collect_forms(child, forms);
+        collect_forms(child, forms, ids);
     }
 }
 
-fn collect_form_fields(node: &crate::dom::DomNode, fields: &mut Vec<FormField>, buttons: &mut Vec<FormButton>) {
-    match node.tag.as_str() {
-        "input" => {
-            let field_type = node.get_attr("type").unwrap_or("text").to_string();
-            if field_type == "submit" || field_type == "button" || field_type == "image" {
+/// Add each control to its form: the one its `form` attribute names, else
+/// `form`, the enclosing one. `seen` counts forms as `collect_forms` does.
+fn collect_form_fields(
+    node: &crate::dom::DomNode,
+    mut form: Option<usize>,
+    seen: &mut usize,
+    ids: &HashMap<String, usize>,
+    forms: &mut Vec<FormInfo>,
+) {
+    if node.tag == "form" {
+        form = Some(*seen);
+        *seen += 1;
+    }
+    let owner = node
+        .get_attr("form")
+        .and_then(|id| ids.get(id).copied())
+        .or(form);
+    if let Some(FormInfo { fields, buttons, .. }) = owner.and_then(|i| forms.get_mut(i)) {
+        match node.tag.as_str() {
+            "input" => {
+                let field_type = node.get_attr("type").unwrap_or("text").to_string();
+                if field_type == "submit" || field_type == "button" || field_type == "image" {
+                    buttons.push(FormButton {
+                        name: node.get_attr("name").map(|s| s.to_string()),
+                        value: node.get_attr("value").map(|s| s.to_string()),
+                        formaction: node.get_attr("formaction").map(|s| s.to_string()),
+                        text: node.get_attr("value").map(|s| s.to_string()),
+                    });
+                } else {
+                    let checked = (field_type == "checkbox" || field_type == "radio")
+                        && node.attributes.contains_key("checked");
+                    fields.push(FormField {
+                        name: node.get_attr("name").map(|s| s.to_string()),
+                        value: node.get_attr("value").map(|s| s.to_string()),
+                        field_type,
+                        checked,
+                    });
+                }
+            }
+            "button" => {
                 buttons.push(FormButton {
                     name: node.get_attr("name").map(|s| s.to_string()),
                     value: node.get_attr("value").map(|s| s.to_string()),
                     formaction: node.get_attr("formaction").map(|s| s.to_string()),
-                    text: node.get_attr("value").map(|s| s.to_string()),
+                    text: Some(node.text_content()),
                 });
-            } else {
-                let checked = (field_type == "checkbox" || field_type == "radio")
-                    && node.attributes.contains_key("checked");
+            }
+            "textarea" => {
                 fields.push(FormField {
                     name: node.get_attr("name").map(|s| s.to_string()),
-                    value: node.get_attr("value").map(|s| s.to_string()),
-                    field_type,
-                    checked,
+                    value: Some(node.text_content()),
+                    field_type: "textarea".to_string(),
+                    checked: false,
                 });
             }
+            "select" => {
+                let selected_value = find_selected_option(node);
+                fields.push(FormField {
+                    name: node.get_attr("name").map(|s| s.to_string()),
+                    value: selected_value,
+                    field_type: "select".to_string(),
+                    checked: false,
+                });
+            }
+            _ => {}
         }
-        "button" => {
-            buttons.push(FormButton {
-                name: node.get_attr("name").map(|s| s.to_string()),
-                value: node.get_attr("value").map(|s| s.to_string()),
-                formaction: node.get_attr("formaction").map(|s| s.to_string()),
-                text: Some(node.text_content()),
-            });
-        }
-        "textarea" => {
-            fields.push(FormField {
-                name: node.get_attr("name").map(|s| s.to_string()),
-                value: Some(node.text_content()),
-                field_type: "textarea".to_string(),
-                checked: false,
-            });
-        }
-        "select" => {
-            let selected_value = find_selected_option(node);
-            fields.push(FormField {
-                name: node.get_attr("name").map(|s| s.to_string()),
-                value: selected_value,
-                field_type: "select".to_string(),
-                checked: false,
-            });
-        }
-        _ => {}
     }
     for child in &node.children {
-        collect_form_fields(child, fields, buttons);
+        collect_form_fields(child, form, seen, ids, forms);
     }
 }
 
@@ -371,24 +777,62 @@ pub(crate) fn find_form_index_for_button(
     0
 }
 
+/// The value a `<select>` submits without user input, as a browser picks it:
+/// the last option marked `selected` (the attribute's value doesn't matter),
+/// or else the first option that isn't disabled.
 fn find_selected_option(node: &crate::dom::DomNode) -> Option<String> {
+    let mut options = Vec::new();
+    collect_options(node, false, &mut options);
+    options
+        .iter()
+        .rev()
+        .find(|(option, _)| option.attributes.contains_key("selected"))
+        .or_else(|| options.iter().find(|(_, disabled)| !disabled))
+        .map(|(option, _)| option_value(option))
+}
+
+/// The `<option>`s of a select in order, those inside an `<optgroup>`
+/// included, each with whether it or its group is disabled.
+fn collect_options<'a>(
+    node: &'a crate::dom::DomNode,
+    group_disabled: bool,
+    options: &mut Vec<(&'a crate::dom::DomNode, bool)>,
+) {
     for child in &node.children {
-        if child.tag == "option" && child.attributes.contains_key("selected") {
-            return child.get_attr("value").map(|s| s.to_string());
+        match child.tag.as_str() {
+            "option" => options.push((child, group_disabled || child.attributes.contains_key("disabled"))),
+            "optgroup" => collect_options(child, group_disabled || child.attributes.contains_key("disabled"), options),
+            _ => collect_options(child, group_disabled, options),
         }
     }
-    for child in &node.children {
-        if child.tag == "option" {
-            return child.get_attr("value").map(|s| s.to_string());
+}
+
+/// An option's `value`, or its text with whitespace collapsed when it has none.
+fn option_value(option: &crate::dom::DomNode) -> String {
+    match option.get_attr("value") {
+        Some(value) => value.to_string(),
+        None => option.text_content().split_whitespace().collect::<Vec<_>>().join(" "),
+    }
+}
+
+/// A `Timeout` when reqwest gave up waiting, a `Network` error otherwise.
+fn network_error(e: reqwest::Error) -> FetchError {
+    let mut source = std::error::Error::source(&e);
+    while let Some(cause) = source {
+        if let Some(RedirectChainError(chain)) = cause.downcast_ref::<RedirectChainError>() {
+            return FetchError::TooManyRedirects { chain: chain.clone() };
         }
+        source = cause.source();
+    }
+    if e.is_timeout() {
+        FetchError::Timeout(e.to_string())
+    } else {
+        FetchError::Network(e.to_string())
     }
-    None
 }
 
-pub(crate) fn read_response_text_limited(
-    response: reqwest::blocking::Response,
-    max_bytes: usize,
-) -> Result<String, FetchError> {
+/// The body of `response`, failing with `ResponseTooLarge` past `max_bytes`.
+fn read_response_limited(response: reqwest::blocking::Response, max_bytes: usize) -> Result<Vec<u8>, FetchError> {
     if let Some(len) = response.content_length() {
         if len > max_bytes as u64 {
             return Err(FetchError::ResponseTooLarge(len, max_bytes));
@@ -402,97 +846,129 @@ pub(crate) fn read_response_text_limited(
     if buf.len() > max_bytes {
         return Err(FetchError::ResponseTooLarge(buf.len() as u64, max_bytes));
     }
-    Ok(String::from_utf8_lossy(&buf).to_string())
+    Ok(buf)
 }
 
-pub(crate) fn fetch_html_with_retry(
-    client: &Client,
+/// GET `url` with `headers` under `policy`, noting each try in `attempts`.
+/// A page that is still blocked after the last attempt is returned as is, and
+/// so is `304 Not Modified`; other non-2xx statuses are errors.
+pub(crate) fn fetch_with_retry(
+    send: &SendFn<'_>,
     url: &Url,
+    headers: &[(String, String)],
     config: &FetchConfig,
-) -> Result<String, FetchError> {
-    let mut attempts = 0usize;
-    let max_attempts = config.retry_attempts + 1;
-
-    while attempts < max_attempts {
-        let ua = select_retry_user_agent(config, attempts);
-        let response = client
-            .get(url.as_str())
-            .header(USER_AGENT, ua)
-            .send()
-            .map_err(|e| FetchError::Network(e.to_string()))?;
-
-        let status = response.status();
-        let retry_after = response.headers().get(RETRY_AFTER).and_then(parse_retry_after);
-        if !status.is_success() {
-            if should_retry_status(status, attempts, config) {
-                attempts += 1;
-                let delay = retry_delay_ms(config, attempts - 1, retry_after);
-                thread::sleep(Duration::from_millis(delay));
-                continue;
-            }
-            return Err(FetchError::HttpError(status.as_u16()));
-        }
-
-        let html = read_response_text_limited(response, config.max_response_bytes)?;
-        if config.retry_on_blocked && is_blocked_html(status, &html) && attempts + 1 < max_attempts {
-            attempts += 1;
-            let delay = retry_delay_ms(config, attempts - 1, None);
-            thread::sleep(Duration::from_millis(delay));
-            continue;
-        }
-        return Ok(html);
+    attempts: &mut Vec<Attempt>,
+) -> Result<HttpResponse, FetchError> {
+    let response = retry::run(
+        &config.retry,
+        true,
+        attempts,
+        |n| {
+            let ua = select_retry_user_agent(config, n);
+            send(&HttpRequest {
+                method: "GET",
+                url,
+                form: &[],
+                user_agent: Some(&ua),
+                headers,
+                max_bytes: config.max_response_bytes,
+            })
+        },
+        |ms| thread::sleep(Duration::from_millis(ms)),
+    )?;
+    if !response.is_success() && response.status != 304 {
+        return Err(FetchError::HttpError(response.status));
     }
-
-    Err(FetchError::Network("Retry attempts exhausted".to_string()))
+    Ok(response)
 }
 
+/// The user agent for try `attempt`: the configured one first, then the
+/// retry user agents in turn, the last one repeating.
 fn select_retry_user_agent(config: &FetchConfig, attempt: usize) -> String {
-    if config.retry_user_agents.is_empty() {
-        return config.user_agent.clone();
-    }
-    let idx = attempt.min(config.retry_user_agents.len() - 1);
-    config.retry_user_agents[idx].clone()
+    retry_user_agent(&config.user_agent, &config.retry_user_agents, attempt).to_string()
 }
 
-fn should_retry_status(status: reqwest::StatusCode, attempt: usize, config: &FetchConfig) -> bool {
-    if attempt + 1 >= config.retry_attempts + 1 {
-        return false;
+pub(crate) fn retry_user_agent<'a>(user_agent: &'a str, retry_user_agents: &'a [String], attempt: usize) -> &'a str {
+    match (attempt.checked_sub(1), retry_user_agents.last()) {
+        (Some(retry), Some(last)) => retry_user_agents.get(retry).unwrap_or(last),
+        _ => user_agent,
     }
-    matches!(status.as_u16(), 403 | 408 | 429 | 500 | 502 | 503 | 504)
 }
 
-fn retry_delay_ms(config: &FetchConfig, attempt: usize, retry_after_secs: Option<u64>) -> u64 {
-    let base = config.retry_delay_ms.max(50);
-    let exp = 1u64 << attempt.min(6);
-    let mut delay = base.saturating_mul(exp);
-    delay = delay.min(30_000);
-    if let Some(secs) = retry_after_secs {
-        delay = delay.max(secs.saturating_mul(1000));
-    }
-    delay
+/// Query parameters stripped from search result URLs: they only record where
+/// a click came from. A trailing `*` matches any parameter with that prefix.
+pub fn default_tracking_params() -> Vec<String> {
+    ["utm_*", "gclid", "fbclid", "ved", "usg"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
 }
 
-fn parse_retry_after(value: &reqwest::header::HeaderValue) -> Option<u64> {
-    let s = value.to_str().ok()?;
-    s.trim().parse::<u64>().ok()
+/// The URL a search result links to: unwrapped from the engine's redirect
+/// (DuckDuckGo's `/l/?uddg=`, Google's `/url?q=`) and without the `tracking`
+/// query parameters. A link that doesn't resolve to a URL comes back as is.
+pub fn normalize_result_url(href: &str, tracking: &[String]) -> String {
+    let target = unwrap_result_redirect(href).unwrap_or_else(|| href.to_string());
+    let Ok(mut url) = Url::parse(&target) else {
+        return target;
+    };
+    // Filter the raw pairs, so the ones kept stay encoded as they were
+    let kept = url
+        .query()
+        .unwrap_or("")
+        .split('&')
+        .filter(|pair| {
+            let name = url::form_urlencoded::parse(pair.as_bytes()).next().map(|(name, _)| name);
+            name.is_some_and(|name| !recording::is_ignored(&name, tracking))
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    url.set_query((!kept.is_empty()).then_some(kept.as_str()));
+    url.to_string()
 }
 
-fn is_blocked_html(status: reqwest::StatusCode, body: &str) -> bool {
-    if matches!(status.as_u16(), 403 | 429) {
-        return true;
+/// The percent-decoded target of a search engine's redirect link.
+fn unwrap_result_redirect(href: &str) -> Option<String> {
+    // Resolves `//duckduckgo.com/l/?...` and `/url?...` alike
+    let url = Url::parse("https://redirect.invalid/").ok()?.join(href).ok()?;
+    let params: &[&str] = match url.path() {
+        "/l" | "/l/" => &["uddg"],
+        "/url" => &["q", "url"],
+        _ => return None,
+    };
+    params.iter().find_map(|param| {
+        url.query_pairs()
+            .find(|(name, value)| name == param && value.starts_with("http"))
+            .map(|(_, value)| value.into_owned())
+    })
+}
+
+/// What a URL is fetched for. `Session::check_url_allowed` applies a
+/// different policy to each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlKind {
+    /// A page: `goto`, a followed link, a search or a redirect.
+    Navigation,
+    /// A form submission.
+    Form,
+    /// An external stylesheet of a page.
+    Stylesheet,
+    /// A file fetched for its content rather than shown as a page.
+    Download,
+}
+
+impl UrlKind {
+    /// Whether a URL of this kind is fetched without the agent going there,
+    /// so matching `blocked_patterns` stops it rather than only warning.
+    pub fn is_subresource(self) -> bool {
+        matches!(self, UrlKind::Stylesheet | UrlKind::Download)
     }
-    let lower = body.to_lowercase();
-    let hints = [
-        "captcha",
-        "access denied",
-        "verify you are a human",
-        "unusual traffic",
-        "bot detection",
-        "cloudflare",
-        "perimeterx",
-        "datadome",
-    ];
-    hints.iter().any(|h| lower.contains(h))
+}
+
+/// The first of `patterns` that `url` contains.
+pub(crate) fn matching_pattern<'a>(url: &Url, patterns: &'a [String]) -> Option<&'a str> {
+    patterns.iter().map(String::as_str).find(|p| url.as_str().contains(p))
 }
 
 pub(crate) fn is_url_allowed(url: &Url, allow_private: bool, allow_non_http: bool) -> bool {
@@ -515,6 +991,35 @@ pub(crate) fn is_url_allowed(url: &Url, allow_private: bool, allow_non_http: boo
     }
 }
 
+/// Whether `url`'s host passes a session's domain lists: not under any of
+/// `blocked`, and under one of `allowed` when that is given. A domain covers
+/// itself and its subdomains (`example.com` covers `app.example.com` but not
+/// `notexample.com`); `*.example.com` covers only the subdomains.
+pub(crate) fn is_domain_allowed(url: &Url, allowed: Option<&[String]>, blocked: &[String]) -> bool {
+    let Some(host) = url.host_str() else {
+        return allowed.is_none();
+    };
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    if blocked.iter().any(|domain| host_in_domain(&host, domain)) {
+        return false;
+    }
+    allowed.is_none_or(|list| list.iter().any(|domain| host_in_domain(&host, domain)))
+}
+
+fn host_in_domain(host: &str, domain: &str) -> bool {
+    let domain = domain.trim().trim_end_matches('.').to_ascii_lowercase();
+    let is_subdomain = |parent: &str| {
+        !parent.is_empty() && host.strip_suffix(parent).is_some_and(|rest| rest.len() > 1 && rest.ends_with('.'))
+    };
+    match domain.strip_prefix("*.") {
+        Some(parent) => is_subdomain(parent),
+        None => {
+            let domain = domain.trim_start_matches('.');
+            !domain.is_empty() && (host == domain || is_subdomain(domain))
+        }
+    }
+}
+
 fn is_local_hostname(host: &str) -> bool {
     let h = host.to_lowercase();
     h == "localhost" || h.ends_with(".localhost") || h.ends_with(".local")
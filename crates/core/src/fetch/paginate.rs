@@ -0,0 +1,100 @@
+//! Collecting a table, list or set of links that is split across pages.
+
+use serde::{Deserialize, Serialize};
+
+use crate::output::SpatialDom;
+
+/// Pages read when a caller doesn't say how many.
+pub const DEFAULT_MAX_PAGES: usize = 10;
+
+/// What [`Session::paginate_collect`](super::Session::paginate_collect)
+/// takes from each page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Extract {
+    /// Rows of the page's tables, under the first headers found.
+    Tables,
+    /// The text of each visible list item.
+    Lists,
+    /// The text and URL of each visible link, except the pager's own.
+    Links,
+}
+
+impl std::str::FromStr for Extract {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "tables" => Ok(Extract::Tables),
+            "lists" => Ok(Extract::Lists),
+            "links" => Ok(Extract::Links),
+            other => Err(format!("unknown extract '{other}' (expected tables, lists or links)")),
+        }
+    }
+}
+
+/// Why [`Session::paginate_collect`](super::Session::paginate_collect)
+/// stopped following next links.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    /// The last page had no next link.
+    Exhausted,
+    /// `max_pages` pages were read.
+    MaxPages,
+    /// The next link led to a page already read.
+    LoopDetected,
+    /// Loading the next page failed; see [`PaginatedResult::error`].
+    Error,
+}
+
+/// Rows gathered across pages, with the page each came from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaginatedResult {
+    /// Column names, starting with `page_index`.
+    pub headers: Vec<String>,
+    /// One row per table row, list item or link; the first cell is the
+    /// index into `visited` of the page it came from.
+    pub rows: Vec<Vec<String>>,
+    /// URLs of the pages read, in order, starting with the current page.
+    pub visited: Vec<String>,
+    pub stop_reason: StopReason,
+    /// The error that stopped collection, when `stop_reason` is `error`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The column names `extract` gives a page, without `page_index`. Empty for
+/// tables without header cells.
+pub(crate) fn columns(dom: &SpatialDom, extract: Extract) -> Vec<String> {
+    match extract {
+        Extract::Tables => dom.tables().into_iter().map(|t| t.headers).find(|h| !h.is_empty()).unwrap_or_default(),
+        Extract::Lists => vec!["text".to_string()],
+        Extract::Links => vec!["text".to_string(), "href".to_string()],
+    }
+}
+
+/// The rows `extract` takes from one page.
+pub(crate) fn rows(dom: &SpatialDom, extract: Extract) -> Vec<Vec<String>> {
+    let visible = dom.els.iter().filter(|e| e.hidden != Some(true));
+    match extract {
+        Extract::Tables => dom.tables().into_iter().flat_map(|t| t.rows).collect(),
+        Extract::Lists => visible
+            .filter(|e| e.tag == "li")
+            .filter_map(|e| e.text.as_deref().map(str::trim).filter(|t| !t.is_empty()))
+            .map(|text| vec![text.to_string()])
+            .collect(),
+        Extract::Links => {
+            let pager: Vec<String> = dom
+                .pagination()
+                .map(|p| p.next.into_iter().chain(p.prev).chain(p.pages.into_iter().map(|(_, href)| href)).collect())
+                .unwrap_or_default();
+            visible
+                .filter(|e| e.role.as_deref() == Some("link"))
+                .filter_map(|e| Some((e.text.as_deref().unwrap_or("").trim(), e.href.as_deref()?)))
+                .filter(|(_, href)| !pager.iter().any(|p| p == href))
+                .map(|(text, href)| vec![text.to_string(), href.to_string()])
+                .collect()
+        }
+    }
+}
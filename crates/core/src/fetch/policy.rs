@@ -0,0 +1,109 @@
+//! Actions a session holds until they're confirmed: submitting a form whose
+//! button says something like "Delete" or "Pay", or a click or submission
+//! that would leave the domains an operator expects the agent to stay on.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+/// Which clicks and form submissions need confirmation, given to
+/// `SessionConfig::action_policy`. A matching action fails with
+/// `FetchError::ConfirmationRequired` until `Session::confirm` takes it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActionPolicy {
+    /// Words or phrases that make submitting a form need confirmation when
+    /// its submit button's text contains them, matched case-insensitively on
+    /// word boundaries: `pay` matches "Pay now" but not "Display".
+    pub submit_patterns: Vec<String>,
+    /// Domains (with subdomains) clicks and form submissions may go to
+    /// without confirmation. Empty doesn't check where they go.
+    pub allowed_domains: Vec<String>,
+    /// Seconds a held action can be confirmed before it's dropped.
+    pub confirm_timeout_secs: u64,
+}
+
+impl Default for ActionPolicy {
+    fn default() -> Self {
+        Self {
+            submit_patterns: ["delete", "pay", "confirm purchase", "place order"].map(String::from).to_vec(),
+            allowed_domains: Vec::new(),
+            confirm_timeout_secs: 300,
+        }
+    }
+}
+
+impl ActionPolicy {
+    /// The pattern a submit button's `text` matches, if any.
+    pub fn matching_pattern(&self, text: &str) -> Option<&str> {
+        let text = text.to_lowercase();
+        self.submit_patterns
+            .iter()
+            .map(|p| p.trim())
+            .find(|p| !p.is_empty() && contains_words(&text, &p.to_lowercase()))
+    }
+
+    /// Whether going to `url` leaves the allowed domains.
+    pub fn leaves_allowed_domains(&self, url: &Url) -> bool {
+        !self.allowed_domains.is_empty() && !super::is_domain_allowed(url, Some(&self.allowed_domains), &[])
+    }
+}
+
+/// Whether `needle` occurs in `text` with no letter or digit right before
+/// or after it.
+fn contains_words(text: &str, needle: &str) -> bool {
+    let is_word = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+    text.match_indices(needle).any(|(start, _)| {
+        !is_word(text[..start].chars().next_back()) && !is_word(text[start + needle.len()..].chars().next())
+    })
+}
+
+/// What a held action does once confirmed.
+#[derive(Debug, Clone)]
+pub(crate) enum Held {
+    Navigate(Url),
+    /// Send a form exactly as it was when the action was held.
+    Submit { url: Url, method: String, fields: Vec<(String, String)> },
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct HeldAction {
+    pub action: Held,
+    pub description: String,
+    expires: Instant,
+}
+
+/// Actions waiting for `Session::confirm`, by token.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct HeldActions {
+    actions: HashMap<String, HeldAction>,
+}
+
+impl HeldActions {
+    /// Hold `action` for `timeout` and return the token that confirms it.
+    pub fn hold(&mut self, action: Held, description: String, timeout: Duration) -> String {
+        self.actions.retain(|_, held| held.expires > Instant::now());
+        let token = new_token();
+        let expires = Instant::now() + timeout;
+        self.actions.insert(token.clone(), HeldAction { action, description, expires });
+        token
+    }
+
+    /// Remove and return the action `token` holds, unless it has expired.
+    pub fn take(&mut self, token: &str) -> Option<HeldAction> {
+        self.actions.remove(token).filter(|held| held.expires > Instant::now())
+    }
+
+    pub fn clear(&mut self) {
+        self.actions.clear();
+    }
+}
+
+/// 128 bits from the operating system's random source, as hex.
+fn new_token() -> String {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).expect("the operating system's random source is available");
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
@@ -0,0 +1,39 @@
+//! What a click or form submission would do, worked out without sending a
+//! request or changing the session, so an agent's action can be checked
+//! before it's taken.
+
+use serde::Serialize;
+
+use crate::js::JsAction;
+
+/// The effect [`Session::preview_click`](super::Session::preview_click) or
+/// [`preview_submit`](super::Session::preview_submit) found.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ActionPreview {
+    /// Load `url`: a link, a JS behavior that navigates, or an input outside
+    /// any form sending its value as a query parameter.
+    Navigate { url: String },
+    /// Change the page in place, as a JS behavior does, without a request.
+    Behavior { action: JsAction },
+    /// Send a form to `url`, its action resolved against the page. GET
+    /// submissions carry `fields` in the query string, POSTs in the body.
+    Submit {
+        /// `GET` or `POST`.
+        method: String,
+        url: String,
+        /// In the order they're sent. Values of sensitive fields are redacted
+        /// unless `SessionConfig::redact_sensitive` is off.
+        fields: Vec<PreviewField>,
+    },
+    /// Nothing: an in-page anchor, a `javascript:` or `mailto:` link, or an
+    /// element with no behavior.
+    Nothing,
+}
+
+/// One name/value pair a form would send.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PreviewField {
+    pub name: String,
+    pub value: String,
+}
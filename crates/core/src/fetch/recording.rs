@@ -0,0 +1,270 @@
+//! Record-and-replay of HTTP exchanges, for hermetic tests and offline demos.
+//!
+//! In record mode every request a session makes is sent live and written to a
+//! fixture directory, one JSON file per exchange. In replay mode requests are
+//! answered from those files and nothing touches the network.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::{redact, FetchError, HttpRequest, HttpResponse};
+
+/// Where a session's HTTP traffic comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordingMode {
+    /// Fetch live and append every exchange to this fixture directory.
+    Record(PathBuf),
+    /// Answer every request from this fixture directory; unmatched requests fail.
+    Replay(PathBuf),
+}
+
+/// Query and form parameters ignored when matching requests to fixtures.
+/// A trailing `*` matches any parameter with that prefix.
+pub fn default_ignored_params() -> Vec<String> {
+    ["utm_*", "_", "cb", "cachebuster", "timestamp", "ts", "nonce", "fbclid", "gclid"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// One recorded request and its response, stored as a JSON file.
+///
+/// Form bodies are stored as a hash only, leaving out the values of fields
+/// whose names look like credentials, and `Set-Cookie` headers are left out,
+/// so neither passwords typed during a recording nor the session cookies
+/// they earn end up in the fixtures.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exchange {
+    pub method: String,
+    pub url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_hash: Option<String>,
+    pub status: u16,
+    /// Final URL after redirects.
+    pub final_url: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<(String, String)>,
+    #[serde(default)]
+    pub body: String,
+}
+
+enum State {
+    Record {
+        dir: PathBuf,
+        next: Mutex<usize>,
+    },
+    Replay {
+        dir: PathBuf,
+        exchanges: HashMap<String, Vec<Exchange>>,
+        /// How many times each key has been served; repeats walk through the
+        /// recorded responses in order and then stick to the last one.
+        served: Mutex<HashMap<String, usize>>,
+    },
+}
+
+pub(crate) struct Recorder {
+    state: State,
+    ignored: Vec<String>,
+}
+
+impl Recorder {
+    pub fn new(mode: &RecordingMode, ignored: Vec<String>) -> Result<Self, FetchError> {
+        let state = match mode {
+            RecordingMode::Record(dir) => {
+                std::fs::create_dir_all(dir).map_err(|e| fixture_error(dir, e))?;
+                let next = fixture_files(dir)?.len();
+                State::Record {
+                    dir: dir.clone(),
+                    next: Mutex::new(next),
+                }
+            }
+            RecordingMode::Replay(dir) => {
+                let mut exchanges: HashMap<String, Vec<Exchange>> = HashMap::new();
+                for path in fixture_files(dir)? {
+                    let json = std::fs::read_to_string(&path).map_err(|e| fixture_error(&path, e))?;
+                    let exchange: Exchange = serde_json::from_str(&json)
+                        .map_err(|e| FetchError::ActionError(format!("{}: {e}", path.display())))?;
+                    let key = match Url::parse(&exchange.url) {
+                        Ok(url) => request_key(&exchange.method, &url, exchange.body_hash.as_deref(), &ignored),
+                        Err(_) => continue,
+                    };
+                    exchanges.entry(key).or_default().push(exchange);
+                }
+                State::Replay {
+                    dir: dir.clone(),
+                    exchanges,
+                    served: Mutex::new(HashMap::new()),
+                }
+            }
+        };
+        Ok(Self { state, ignored })
+    }
+
+    pub fn is_replay(&self) -> bool {
+        matches!(self.state, State::Replay { .. })
+    }
+
+    /// Answer a request from the fixtures.
+    pub fn replay(&self, request: &HttpRequest<'_>) -> Result<HttpResponse, FetchError> {
+        let State::Replay { dir, exchanges, served } = &self.state else {
+            return Err(FetchError::ActionError("Session is not replaying".to_string()));
+        };
+        let body_hash = body_hash(request.form, &self.ignored);
+        let key = request_key(request.method, request.url, body_hash.as_deref(), &self.ignored);
+        let recorded = exchanges.get(&key).ok_or_else(|| {
+            FetchError::ActionError(format!(
+                "No recorded response for {} {} in {}",
+                request.method,
+                request.url,
+                dir.display()
+            ))
+        })?;
+        let mut served = served.lock().unwrap();
+        let count = served.entry(key).or_insert(0);
+        let exchange = &recorded[(*count).min(recorded.len() - 1)];
+        *count += 1;
+
+        if exchange.body.len() > request.max_bytes {
+            return Err(FetchError::ResponseTooLarge(exchange.body.len() as u64, request.max_bytes));
+        }
+        Ok(HttpResponse {
+            status: exchange.status,
+            url: exchange.final_url.clone(),
+            headers: exchange.headers.clone(),
+            body: exchange.body.clone(),
+            binary: None,
+        })
+    }
+
+    /// Append a live exchange to the fixture directory.
+    pub fn record(&self, request: &HttpRequest<'_>, response: &HttpResponse) -> Result<(), FetchError> {
+        let State::Record { dir, next } = &self.state else {
+            return Ok(());
+        };
+        let exchange = Exchange {
+            method: request.method.to_string(),
+            url: request.url.to_string(),
+            body_hash: body_hash(request.form, &self.ignored),
+            status: response.status,
+            final_url: response.url.clone(),
+            headers: response
+                .headers
+                .iter()
+                .filter(|(name, _)| !name.eq_ignore_ascii_case("set-cookie"))
+                .cloned()
+                .collect(),
+            body: response.body.clone(),
+        };
+        let json = serde_json::to_string_pretty(&exchange)
+            .map_err(|e| FetchError::ActionError(e.to_string()))?;
+
+        let mut next = next.lock().unwrap();
+        let path = dir.join(fixture_name(*next, request));
+        std::fs::write(&path, json).map_err(|e| fixture_error(&path, e))?;
+        *next += 1;
+        Ok(())
+    }
+}
+
+fn fixture_error(path: &Path, e: std::io::Error) -> FetchError {
+    FetchError::ActionError(format!("{}: {e}", path.display()))
+}
+
+/// The `*.json` files in a fixture directory, in recording order.
+fn fixture_files(dir: &Path) -> Result<Vec<PathBuf>, FetchError> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map_err(|e| fixture_error(dir, e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// `0003-post-example.com-login.json`: sequence number plus a readable slug.
+fn fixture_name(seq: usize, request: &HttpRequest<'_>) -> String {
+    let target = format!(
+        "{}{}",
+        request.url.host_str().unwrap_or_default(),
+        request.url.path()
+    );
+    let slug: String = target
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' { c } else { '-' })
+        .take(80)
+        .collect();
+    format!(
+        "{seq:04}-{}-{}.json",
+        request.method.to_lowercase(),
+        slug.trim_end_matches('-')
+    )
+}
+
+pub(crate) fn is_ignored(name: &str, ignored: &[String]) -> bool {
+    ignored.iter().any(|pattern| match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name == pattern,
+    })
+}
+
+/// The URL without its fragment and ignored query parameters, with the
+/// remaining parameters sorted so their order doesn't matter.
+fn normalize_url(url: &Url, ignored: &[String]) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    let mut pairs: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(name, _)| !is_ignored(name, ignored))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    pairs.sort();
+    if pairs.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(&pairs);
+    }
+    url.to_string()
+}
+
+/// Hash of a form body, ignoring volatile fields and field order. Sensitive
+/// fields count by name only: the hash is unsalted and short enough that a
+/// weak password could be recovered from it.
+fn body_hash(form: &[(String, String)], ignored: &[String]) -> Option<String> {
+    if form.is_empty() {
+        return None;
+    }
+    let mut pairs: Vec<(&str, &str)> = form
+        .iter()
+        .filter(|(name, _)| !is_ignored(name, ignored))
+        .map(|(name, value)| {
+            let value = if redact::is_sensitive_name(name) { "" } else { value.as_str() };
+            (name.as_str(), value)
+        })
+        .collect();
+    pairs.sort();
+    let encoded = url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(pairs)
+        .finish();
+    Some(format!("{:016x}", fnv1a(&encoded)))
+}
+
+fn request_key(method: &str, url: &Url, body_hash: Option<&str>, ignored: &[String]) -> String {
+    format!(
+        "{} {} {}",
+        method.to_uppercase(),
+        normalize_url(url, ignored),
+        body_hash.unwrap_or("-")
+    )
+}
+
+/// 64-bit FNV-1a of `s`; stable across runs and toolchains, unlike
+/// `DefaultHasher`, so it can name files and fixtures.
+pub fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
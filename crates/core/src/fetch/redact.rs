@@ -0,0 +1,44 @@
+//! Keeping typed secrets out of agent transcripts and logs.
+//!
+//! A field is sensitive when it's a password input, or when its name, label
+//! or placeholder looks like it holds a credential. Values typed into such
+//! fields are echoed as `•••• (N chars)` instead of verbatim.
+
+use crate::output::SpatialElement;
+
+/// Name, label and placeholder fragments that mark a credential field.
+const SENSITIVE_PATTERNS: &[&str] = &[
+    "password", "passwd", "passcode", "pwd", "token", "secret", "api_key", "apikey", "api key",
+];
+
+/// Whether values typed into `el` should be redacted.
+pub fn is_sensitive(el: &SpatialElement) -> bool {
+    if el.input_type.as_deref() == Some("password") {
+        return true;
+    }
+    [el.name.as_deref(), el.label.as_deref(), el.ph.as_deref()]
+        .into_iter()
+        .flatten()
+        .any(is_sensitive_name)
+}
+
+/// Whether a form field called `name` looks like it holds a credential, for
+/// callers that only see the submitted name/value pairs.
+pub fn is_sensitive_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    SENSITIVE_PATTERNS.iter().any(|p| name.contains(p))
+}
+
+/// The placeholder shown instead of a sensitive value.
+pub fn redacted(value: &str) -> String {
+    format!("•••• ({} chars)", value.chars().count())
+}
+
+/// Replace every occurrence of `secret` in `text` with its placeholder.
+pub fn scrub(text: &str, secret: &str) -> String {
+    if secret.is_empty() {
+        return text.to_string();
+    }
+    text.replace(secret, &redacted(secret))
+}
+
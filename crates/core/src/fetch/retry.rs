@@ -0,0 +1,179 @@
+//! Retrying requests that fail for transient reasons.
+//!
+//! A [`RetryPolicy`] says how many times to try and which failures are worth
+//! another attempt. [`run`] applies it to one request and notes each attempt,
+//! so sessions can report what happened through `last_attempts()` and the
+//! audit log.
+
+use serde::{Deserialize, Serialize};
+
+use super::{FetchError, HttpResponse};
+
+/// Longest backoff between attempts, unless the server asks for more with
+/// `Retry-After`.
+const MAX_DELAY_MS: u64 = 30_000;
+
+/// A kind of failure that a [`RetryPolicy`] may retry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryCondition {
+    /// The connection failed before any response arrived.
+    Connection,
+    /// The request timed out, or the server answered 408.
+    Timeout,
+    /// 429 Too Many Requests. `Retry-After` is honored when present.
+    RateLimited,
+    /// Any 5xx response.
+    ServerError,
+    /// 403, or a page that looks like an anti-bot challenge.
+    Blocked,
+}
+
+impl RetryCondition {
+    pub const ALL: [RetryCondition; 5] = [
+        RetryCondition::Connection,
+        RetryCondition::Timeout,
+        RetryCondition::RateLimited,
+        RetryCondition::ServerError,
+        RetryCondition::Blocked,
+    ];
+
+    /// The condition a failed request matches, if any.
+    fn of_error(error: &FetchError) -> Option<Self> {
+        match error {
+            FetchError::Network(_) => Some(RetryCondition::Connection),
+            FetchError::Timeout(_) => Some(RetryCondition::Timeout),
+            _ => None,
+        }
+    }
+
+    /// The condition a response matches, if any. Successful responses only
+    /// match `Blocked`, when the body looks like a challenge page.
+    fn of_response(response: &HttpResponse) -> Option<Self> {
+        match response.status {
+            408 => Some(RetryCondition::Timeout),
+            429 => Some(RetryCondition::RateLimited),
+            403 => Some(RetryCondition::Blocked),
+            500..=599 => Some(RetryCondition::ServerError),
+            _ if response.is_success() && looks_blocked(&response.body) => Some(RetryCondition::Blocked),
+            _ => None,
+        }
+    }
+}
+
+/// How a session retries navigations and form submissions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Total attempts, the first one included. 1 disables retrying.
+    pub max_attempts: usize,
+    /// Wait before the first retry; each later retry waits twice as long.
+    pub base_delay_ms: u64,
+    /// Failures worth another attempt.
+    pub retry_on: Vec<RetryCondition>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay_ms: 250,
+            retry_on: RetryCondition::ALL.to_vec(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that never retries.
+    pub fn none() -> Self {
+        Self { max_attempts: 1, ..Self::default() }
+    }
+
+    /// The wait before retry number `retry` (0 for the first retry).
+    /// `Retry-After` wins when it asks for longer.
+    pub fn delay_ms(&self, retry: usize, retry_after_secs: Option<u64>) -> u64 {
+        let backoff = self
+            .base_delay_ms
+            .saturating_mul(1u64 << retry.min(16))
+            .min(MAX_DELAY_MS);
+        match retry_after_secs {
+            Some(secs) => backoff.max(secs.saturating_mul(1000)),
+            None => backoff,
+        }
+    }
+}
+
+/// One try at a request, as reported by `Session::last_attempts()`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attempt {
+    /// The response status, or `None` when no response arrived.
+    pub status: Option<u16>,
+    /// Why the attempt was retried; `None` for the last one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retried_for: Option<RetryCondition>,
+    /// How long the session waited before the next attempt; 0 for the last one.
+    pub delay_ms: u64,
+}
+
+/// Call `send` with the attempt number (from 0) until it gives a result the
+/// policy won't retry, or attempts run out. Non-idempotent requests are only
+/// retried when no response arrived at all. `sleep` waits the given
+/// milliseconds between attempts; each attempt is appended to `attempts`.
+pub(crate) fn run<S, W>(
+    policy: &RetryPolicy,
+    idempotent: bool,
+    attempts: &mut Vec<Attempt>,
+    mut send: S,
+    mut sleep: W,
+) -> Result<HttpResponse, FetchError>
+where
+    S: FnMut(usize) -> Result<HttpResponse, FetchError>,
+    W: FnMut(u64),
+{
+    let max_attempts = policy.max_attempts.max(1);
+    let mut n = 0;
+    loop {
+        let result = send(n);
+        let (status, condition) = match &result {
+            Ok(response) => (Some(response.status), RetryCondition::of_response(response)),
+            Err(e) => (None, RetryCondition::of_error(e)),
+        };
+        let retry = condition.is_some_and(|c| policy.retry_on.contains(&c))
+            && (idempotent || status.is_none())
+            && n + 1 < max_attempts;
+        if !retry {
+            attempts.push(Attempt { status, retried_for: None, delay_ms: 0 });
+            return result;
+        }
+        let retry_after = result
+            .as_ref()
+            .ok()
+            .and_then(|r| r.header("retry-after"))
+            .and_then(parse_retry_after);
+        let delay_ms = policy.delay_ms(n, retry_after);
+        tracing::debug!(attempt = n + 1, ?condition, delay_ms, "retrying request");
+        attempts.push(Attempt { status, retried_for: condition, delay_ms });
+        sleep(delay_ms);
+        n += 1;
+    }
+}
+
+fn parse_retry_after(value: &str) -> Option<u64> {
+    value.trim().parse::<u64>().ok()
+}
+
+fn looks_blocked(body: &str) -> bool {
+    let lower = body.to_lowercase();
+    [
+        "captcha",
+        "access denied",
+        "verify you are a human",
+        "unusual traffic",
+        "bot detection",
+        "cloudflare",
+        "perimeterx",
+        "datadome",
+    ]
+    .iter()
+    .any(|hint| lower.contains(hint))
+}
+
@@ -1,44 +1,147 @@
 //! Browsing session with cookie persistence, navigation, and agent actions.
 
 use super::{
+    CssStats,
     FetchError,
     FetchConfig,
+    HttpRequest,
+    HttpResponse,
     fetch_external_css,
     extract_forms,
     find_form_index_for_button,
     is_url_allowed,
-    read_response_text_limited,
-    fetch_html_with_retry,
+    is_domain_allowed,
+    matching_pattern,
+    fetch_with_retry,
+    send_http,
+    normalize_result_url,
+    redirect_policy,
+    RedirectLog,
+    UrlKind,
 };
-use crate::output::{CaptchaInfo, PageType, SpatialDom, SpatialElement, SuggestedAction};
+use super::audit::{AuditAction, AuditEvent, AuditLog};
+use super::conditional::{CachedPage, PageCache};
+use super::cookies::CookieJar;
+use super::crawl::{self, CrawlEntry, CrawlOpts};
+use super::feeds::{self, DiscoveredFeeds, Sitemap, SitemapEntry, MAX_SITEMAPS};
+use super::paginate::{self, Extract, PaginatedResult, StopReason};
+use super::policy::{ActionPolicy, Held, HeldActions};
+use super::preview::{ActionPreview, PreviewField};
+use super::recording::{Recorder, RecordingMode};
+use super::site::{self, SiteOverrides};
+use super::snapshot::{self, FieldValue, FormState, Snapshot};
+use super::state::SessionState;
+use super::retry::{self, Attempt, RetryPolicy};
+use crate::output::{CaptchaInfo, DroppedNote, PageType, SpatialDom, SpatialElement, SuggestedAction};
 use reqwest::blocking::Client;
-use reqwest::redirect::Policy;
-use reqwest::header::{RETRY_AFTER, USER_AGENT};
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT_LANGUAGE};
 use serde::{Serialize, Deserialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use url::Url;
 
+/// Run `$body` inside a tracing span named `$name`, adding its wall-clock
+/// milliseconds to `$slot`.
+macro_rules! phase {
+    ($slot:expr, $name:literal, $body:expr) => {{
+        let _span = tracing::debug_span!($name).entered();
+        let started = Instant::now();
+        let value = $body;
+        $slot += elapsed_ms(started);
+        value
+    }};
+}
+
 /// Configuration for a browsy session.
+#[derive(Clone)]
 pub struct SessionConfig {
     pub viewport_width: f32,
     pub viewport_height: f32,
     pub user_agent: String,
     pub timeout_secs: u64,
     pub fetch_css: bool,
+    /// URL substrings of trackers and ad networks. Stylesheets matching one
+    /// aren't fetched; a page matching one still loads, with a warning.
     pub blocked_patterns: Vec<String>,
     pub max_response_bytes: usize,
     pub max_css_bytes_total: usize,
     pub max_css_bytes_per_file: usize,
+    /// Most redirects followed for one request. A chain that runs past it,
+    /// or comes back to a URL it already visited, fails with
+    /// `FetchError::TooManyRedirects`.
     pub max_redirects: usize,
     pub allow_private_network: bool,
     pub allow_non_http: bool,
-    pub retry_attempts: usize,
-    pub retry_delay_ms: u64,
-    pub retry_on_blocked: bool,
+    /// How `goto`, `search` and form submissions retry transient failures.
+    pub retry: RetryPolicy,
+    /// User agents for the second and later tries of a request, in turn;
+    /// the first try uses `user_agent`.
     pub retry_user_agents: Vec<String>,
+    /// Populate `SpatialElement::selector` with a CSS selector for each element.
+    pub emit_selectors: bool,
+    /// Type into, select in, check and click elements even when they are
+    /// disabled or read-only, instead of failing with `ElementUnavailable`.
+    /// For testing how a site handles it.
+    pub force_actions: bool,
+    /// Most characters of an element's text the compact format shows; `None`
+    /// shows all of it. `Session::element_text` returns the whole text.
+    pub text_limit: Option<usize>,
+    /// Keep element IDs stable when the same page is rendered again (after a
+    /// toggle, a form submit that stays on the page, or a viewport change).
+    pub stable_ids: bool,
+    /// When a click's JS behavior only changes the style, class or `hidden`
+    /// attribute of elements with an `id`, restyle and lay out just those
+    /// elements again instead of the whole page. The result is the same
+    /// either way.
+    pub incremental_relayout: bool,
+    /// Record every HTTP exchange to a fixture directory, or replay from one.
+    pub recording: Option<RecordingMode>,
+    /// Query and form parameters ignored when matching requests to fixtures.
+    pub recording_ignore_params: Vec<String>,
+    /// Hide values typed into password and credential fields: `dom()` shows
+    /// `•••• (N chars)` instead, as does `typed_echo()`.
+    pub redact_sensitive: bool,
+    /// Click the accept or reject button of a detected cookie banner as soon
+    /// as a page loads. What was clicked is listed in `SpatialDom::auto_actions`.
+    pub auto_dismiss_cookie_banners: Option<ConsentChoice>,
+    /// Sent as the `Accept-Language` header (`de-DE,de;q=0.9`). Pages whose
+    /// language differs are flagged by `Session::language_mismatch`.
+    pub accept_language: Option<String>,
+    /// Only navigate to these domains (and their subdomains; `*.example.com`
+    /// for subdomains only). `None` allows every domain.
+    pub allowed_domains: Option<Vec<String>>,
+    /// Never navigate to these domains or their subdomains. Checked for
+    /// `goto`, followed links, form submissions and redirects.
+    pub blocked_domains: Vec<String>,
+    /// Clicks and form submissions to hold until `Session::confirm` takes
+    /// them. `None` takes every action straight away.
+    pub action_policy: Option<ActionPolicy>,
+    /// Most events kept in the audit log; older ones are dropped first.
+    /// 0 turns the log off.
+    pub audit_log_limit: usize,
+    /// Make `goto` revalidate pages it has seen before with `If-None-Match` /
+    /// `If-Modified-Since`, as `goto_if_modified` does.
+    pub use_conditional_requests: bool,
+    /// Save a snapshot into this directory whenever `goto` or `click` fails,
+    /// or lands on a page classified as blocked or a CAPTCHA.
+    pub snapshot_on_error: Option<PathBuf>,
+    /// Populate `SpatialElement::src` with the line and column of each
+    /// element's start tag in the page HTML.
+    pub track_source_positions: bool,
+    /// Query parameters stripped from search result URLs (see
+    /// [`normalize_result_url`]).
+    pub search_tracking_params: Vec<String>,
+    /// Headers sent with every page request and form submission.
+    pub extra_headers: Vec<(String, String)>,
+    /// Least time between two page requests to the same host. 0 doesn't wait.
+    pub per_host_delay_ms: u64,
+    /// A `browsy.toml` file of per-site overrides (see [`SiteOverrides`]).
+    /// Read when the session is created; `goto` applies the override
+    /// matching each host it loads.
+    pub site_overrides_path: Option<PathBuf>,
 }
 
 impl Default for SessionConfig {
@@ -57,10 +160,49 @@ impl Default for SessionConfig {
             max_redirects: fetch.max_redirects,
             allow_private_network: fetch.allow_private_network,
             allow_non_http: fetch.allow_non_http,
-            retry_attempts: fetch.retry_attempts,
-            retry_delay_ms: fetch.retry_delay_ms,
-            retry_on_blocked: fetch.retry_on_blocked,
+            retry: fetch.retry,
             retry_user_agents: fetch.retry_user_agents,
+            emit_selectors: false,
+            force_actions: false,
+            text_limit: Some(crate::output::DEFAULT_TEXT_LIMIT),
+            stable_ids: true,
+            incremental_relayout: true,
+            recording: None,
+            recording_ignore_params: super::default_ignored_params(),
+            redact_sensitive: true,
+            auto_dismiss_cookie_banners: None,
+            accept_language: None,
+            allowed_domains: None,
+            blocked_domains: Vec::new(),
+            action_policy: None,
+            audit_log_limit: 1000,
+            use_conditional_requests: false,
+            snapshot_on_error: None,
+            track_source_positions: false,
+            search_tracking_params: super::default_tracking_params(),
+            extra_headers: Vec::new(),
+            per_host_delay_ms: 0,
+            site_overrides_path: None,
+        }
+    }
+}
+
+/// Which button to press on a cookie consent banner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConsentChoice {
+    Accept,
+    Reject,
+}
+
+impl std::str::FromStr for ConsentChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "accept" => Ok(ConsentChoice::Accept),
+            "reject" => Ok(ConsentChoice::Reject),
+            other => Err(format!("unknown consent choice '{other}' (expected accept or reject)")),
         }
     }
 }
@@ -74,6 +216,72 @@ pub enum InputPurpose {
     VerificationCode,
     Search,
     Phone,
+    Address,
+    CreditCardNumber,
+}
+
+impl InputPurpose {
+    /// The name used for this purpose in `SpatialElement::purpose`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InputPurpose::Password => "password",
+            InputPurpose::Email => "email",
+            InputPurpose::Username => "username",
+            InputPurpose::VerificationCode => "verification_code",
+            InputPurpose::Search => "search",
+            InputPurpose::Phone => "phone",
+            InputPurpose::Address => "address",
+            InputPurpose::CreditCardNumber => "credit_card_number",
+        }
+    }
+}
+
+/// The URL policy behind [`Session::check_url_allowed`].
+fn check_url(config: &SessionConfig, url: &Url, kind: UrlKind) -> Result<(), FetchError> {
+    let blocked = || Err(FetchError::BlockedUrl(url.to_string()));
+    if !is_url_allowed(url, config.allow_private_network, config.allow_non_http) {
+        return blocked();
+    }
+    // Stylesheets often come from a CDN outside the allowed domains
+    let allowed_domains = match kind {
+        UrlKind::Stylesheet => None,
+        _ => config.allowed_domains.as_deref(),
+    };
+    if !is_domain_allowed(url, allowed_domains, &config.blocked_domains) {
+        return blocked();
+    }
+    if kind.is_subresource() && matching_pattern(url, &config.blocked_patterns).is_some() {
+        return blocked();
+    }
+    Ok(())
+}
+
+/// The HTTP client for a session. Redirects stop short of any URL the
+/// session may not navigate to; `Session::send` turns that into an error.
+fn build_client(
+    config: &SessionConfig,
+    cookie_store: Arc<CookieJar>,
+    redirect_log: Arc<RedirectLog>,
+) -> Result<Client, FetchError> {
+    let policy = config.clone();
+    let mut headers = HeaderMap::new();
+    if let Some(ref accept_language) = config.accept_language {
+        let value = HeaderValue::from_str(accept_language)
+            .map_err(|_| FetchError::Network(format!("invalid Accept-Language: {accept_language}")))?;
+        headers.insert(ACCEPT_LANGUAGE, value);
+    }
+    Client::builder()
+        .user_agent(&config.user_agent)
+        .default_headers(headers)
+        .timeout(std::time::Duration::from_secs(config.timeout_secs))
+        .redirect(redirect_policy(
+            config.max_redirects,
+            move |url| check_url(&policy, url, UrlKind::Navigation).is_ok(),
+            redirect_log,
+        ))
+        .cookie_provider(cookie_store)
+        .build()
+        .map_err(|e| FetchError::Network(e.to_string()))
 }
 
 /// A browsing session with cookie persistence and page state.
@@ -88,7 +296,52 @@ pub struct Session {
     checked_ids: HashSet<u32>,
     unchecked_ids: HashSet<u32>,
     current_html: Option<String>,
+    /// The parsed tree of the current page, kept with `current_html` so
+    /// clicks and `behaviors` don't parse it again. After a click's JS
+    /// behavior it is the tree that behavior changed.
+    current_tree: Option<crate::dom::DomNode>,
+    /// External stylesheets fetched for the current page, kept so it can be
+    /// laid out again without the network.
+    current_css: String,
+    /// What the current page was styled and laid out with, for
+    /// `incremental_relayout`.
+    current_layout: Option<PageLayout>,
     domain_memory: HashMap<String, DomainMemory>,
+    cookie_jar: Arc<CookieJar>,
+    redirect_log: Arc<RedirectLog>,
+    /// Shared with forks, so they record into one fixture sequence.
+    recorder: Option<Arc<Recorder>>,
+    last_timings: Option<Timings>,
+    /// Stylesheets fetched for the last page loaded.
+    last_css_stats: CssStats,
+    /// The last [`NAV_STATS_KEPT`] navigations, oldest first.
+    nav_stats: Vec<NavStats>,
+    /// Set while a cookie banner is being dismissed, so the page that click
+    /// loads isn't dismissed again.
+    auto_dismissing: bool,
+    /// Set while a `goto` or `click` watched by `snapshot_on_error` runs, so
+    /// the actions it triggers don't take snapshots of their own.
+    watching_for_failure: bool,
+    audit: AuditLog,
+    last_attempts: Vec<Attempt>,
+    last_redirects: Vec<String>,
+    page_cache: PageCache,
+    site_overrides: Arc<SiteOverrides>,
+    /// The suffix of the site override in effect, and the settings it
+    /// replaced.
+    site: Option<(String, SessionConfig)>,
+    /// When each host was last sent a page request, for `per_host_delay_ms`.
+    host_requests: HashMap<String, Instant>,
+    /// robots.txt of each origin asked about, `None` where there is none.
+    robots: HashMap<String, Option<String>>,
+    /// Actions `action_policy` held, waiting for `confirm`.
+    held: HeldActions,
+    /// Set while `confirmed` runs, so the policy lets its actions through.
+    preconfirmed: bool,
+    /// Notes left with `annotate`, by element ID on the current page.
+    annotations: HashMap<u32, String>,
+    /// Notes the last page change dropped, for `delta`.
+    dropped_notes: Vec<DroppedNote>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -102,6 +355,209 @@ pub struct DomainMemory {
     pub last_seen_unix: u64,
 }
 
+/// Milliseconds spent in each phase of the last page load.
+///
+/// Phases that didn't run (no network for `load_html`, no stylesheets) are 0.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Timings {
+    pub fetch: f64,
+    pub css_fetch: f64,
+    pub parse: f64,
+    pub style: f64,
+    pub layout: f64,
+    pub spatial: f64,
+    pub total: f64,
+    /// Whether a re-render after a click laid out just the elements the
+    /// click restyled (see `SessionConfig::incremental_relayout`).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub incremental: bool,
+}
+
+/// How many navigations [`Session::nav_stats`] keeps.
+const NAV_STATS_KEPT: usize = 50;
+
+/// What one navigation, a `goto` or a form submission, cost: what was
+/// downloaded, what `blocked_patterns` kept out, how long each phase took and
+/// how many elements the page came out with.
+///
+/// Byte counts are of the decoded response bodies.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NavStats {
+    pub url: String,
+    pub page_bytes: u64,
+    /// External stylesheets fetched.
+    pub css_fetched: u32,
+    /// External stylesheets not requested because they matched a blocked
+    /// pattern.
+    pub css_blocked: u32,
+    pub css_bytes: u64,
+    pub elements: usize,
+    pub timings: Timings,
+}
+
+impl NavStats {
+    /// Bytes of the page and its stylesheets.
+    pub fn total_bytes(&self) -> u64 {
+        self.page_bytes + self.css_bytes
+    }
+
+    /// One line: `fetched 1 page + 6 css (3 blocked), 412KB, 1.8s, 231 els`.
+    pub fn summary(&self) -> String {
+        let total = self.total_bytes();
+        let size = if total < 1024 {
+            format!("{total}B")
+        } else if total < 1024 * 1024 {
+            format!("{}KB", (total + 512) / 1024)
+        } else {
+            format!("{:.1}MB", total as f64 / (1024.0 * 1024.0))
+        };
+        let time = if self.timings.total < 1000.0 {
+            format!("{}ms", self.timings.total.round())
+        } else {
+            format!("{:.1}s", self.timings.total / 1000.0)
+        };
+        format!(
+            "fetched 1 page + {} css ({} blocked), {size}, {time}, {} els",
+            self.css_fetched, self.css_blocked, self.elements
+        )
+    }
+}
+
+/// How a login attempt ended, judged from the page after submitting.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum LoginStatus {
+    Success,
+    /// The site rejected the credentials; `reason` is its error message.
+    Failed { reason: String },
+    TwoFactorRequired,
+    CaptchaRequired,
+}
+
+impl LoginStatus {
+    /// Classify the page a login form submitted to.
+    pub fn from_page(dom: &SpatialDom) -> Self {
+        if dom.page_type == PageType::Captcha || dom.captcha.is_some() {
+            return LoginStatus::CaptchaRequired;
+        }
+        if dom.page_type == PageType::TwoFactorAuth {
+            return LoginStatus::TwoFactorRequired;
+        }
+        let visible = |e: &&SpatialElement| e.hidden != Some(true);
+        let password_shown = dom.els.iter().filter(visible).any(|e| {
+            e.tag == "input" && e.input_type.as_deref() == Some("password")
+        });
+        let alert = dom.els.iter().filter(visible).find(|e| {
+            matches!(e.alert_type.as_deref(), Some("error") | Some("alert"))
+                && e.text.as_deref().is_some_and(|t| !t.trim().is_empty())
+        });
+        match alert {
+            Some(a) if password_shown || a.alert_type.as_deref() == Some("error") => LoginStatus::Failed {
+                reason: a.text.as_deref().unwrap_or_default().trim().to_string(),
+            },
+            _ if password_shown => LoginStatus::Failed {
+                reason: "Login form is still shown".to_string(),
+            },
+            _ => LoginStatus::Success,
+        }
+    }
+}
+
+impl std::fmt::Display for LoginStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LoginStatus::Success => write!(f, "success"),
+            LoginStatus::Failed { reason } => write!(f, "failed ({reason})"),
+            LoginStatus::TwoFactorRequired => write!(f, "two_factor_required"),
+            LoginStatus::CaptchaRequired => write!(f, "captcha_required"),
+        }
+    }
+}
+
+/// The page after a login attempt and what it says about the attempt.
+///
+/// Derefs to the page, so it can be used wherever a `SpatialDom` was.
+#[derive(Debug, Clone)]
+pub struct LoginOutcome {
+    pub dom: SpatialDom,
+    pub status: LoginStatus,
+    /// How many forms were submitted: 2 when the site asked for the email
+    /// and the password on separate pages.
+    pub steps: u32,
+}
+
+impl LoginOutcome {
+    pub fn dom(&self) -> &SpatialDom {
+        &self.dom
+    }
+
+    pub fn into_dom(self) -> SpatialDom {
+        self.dom
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.status == LoginStatus::Success
+    }
+}
+
+impl std::ops::Deref for LoginOutcome {
+    type Target = SpatialDom;
+
+    fn deref(&self) -> &SpatialDom {
+        &self.dom
+    }
+}
+
+/// Most pages `Session::login` fills in before giving up on reaching a
+/// password field.
+const MAX_LOGIN_STEPS: u32 = 3;
+
+/// The fields one page of a login takes.
+struct LoginStep {
+    username_id: Option<u32>,
+    password_id: Option<u32>,
+    submit_id: u32,
+}
+
+impl LoginStep {
+    /// The page's login form: username and password together, the password
+    /// alone (its username was given on an earlier page or is filled in
+    /// already), or, until it has been sent, the username alone.
+    fn find(dom: &SpatialDom, username_sent: bool) -> Option<Self> {
+        let editable = |id: u32| {
+            dom.get(id)
+                .is_some_and(|e| e.disabled != Some(true) && e.readonly != Some(true))
+        };
+        let login = dom.suggested_actions.iter().find_map(|a| match a {
+            SuggestedAction::Login { username_id, password_id, submit_id, .. } => {
+                Some((*username_id, *password_id, *submit_id))
+            }
+            _ => None,
+        });
+        if let Some((username_id, password_id, submit_id)) = login {
+            // Step two often shows the username again, read-only
+            let username_id = Some(username_id).filter(|&id| editable(id) && !username_sent);
+            return Some(LoginStep { username_id, password_id: Some(password_id), submit_id });
+        }
+
+        let visible = |e: &&SpatialElement| e.hidden != Some(true) && e.tag == "input";
+        if let Some(password) = dom.els.iter().filter(visible).find(|e| e.input_type.as_deref() == Some("password")) {
+            let submit_id = crate::output::find_nearest_submit_button(dom, password.id)?;
+            return Some(LoginStep { username_id: None, password_id: Some(password.id), submit_id });
+        }
+        if username_sent {
+            return None;
+        }
+        let username = dom.els.iter().filter(visible).find(|e| {
+            let purpose = e.purpose.as_deref();
+            editable(e.id)
+                && (purpose == Some(InputPurpose::Username.as_str()) || purpose == Some(InputPurpose::Email.as_str()))
+        })?;
+        let submit_id = crate::output::find_nearest_submit_button(dom, username.id)?;
+        Some(LoginStep { username_id: Some(username.id), password_id: None, submit_id })
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 enum DomainOutcome {
     Ok,
@@ -109,31 +565,129 @@ enum DomainOutcome {
     Error,
 }
 
+/// What clicking an element does, in the order `click` checks.
+enum Click {
+    /// Go to this URL, resolved against the current page.
+    Navigate(String),
+    /// Show the page again with this JS behavior applied.
+    Behavior(crate::js::JsAction),
+    /// Submit the element's form.
+    Submit,
+    /// Nothing; the page stays as it is.
+    Stay,
+}
+
+/// The styles and taffy tree a page was rendered with, kept so elements a
+/// JS behavior restyles can be laid out again on their own.
+struct PageLayout {
+    stylesheet: crate::css::Stylesheet,
+    styled: crate::css::StyledNode,
+    tree: crate::layout::LayoutTree,
+    /// Device viewport the page was rendered for.
+    viewport: (f32, f32),
+    layout_width: f32,
+    /// The path of child indices to each element with an `id`, or `None`
+    /// for an `id` more than one element has.
+    ids: HashMap<String, Option<Vec<usize>>>,
+}
+
+impl PageLayout {
+    /// The path to the one element with `id`.
+    fn path_to(&self, id: &str) -> Option<&[usize]> {
+        self.ids.get(id)?.as_deref()
+    }
+}
+
+fn element_ids(node: &crate::dom::DomNode, path: &mut Vec<usize>, ids: &mut HashMap<String, Option<Vec<usize>>>) {
+    if let Some(id) = node.get_attr("id").filter(|_| node.node_type == crate::dom::NodeType::Element) {
+        ids.entry(id.to_string())
+            .and_modify(|found| *found = None)
+            .or_insert_with(|| Some(path.clone()));
+    }
+    for (i, child) in node.children.iter().enumerate() {
+        path.push(i);
+        element_ids(child, path, ids);
+        path.pop();
+    }
+}
+
+/// Where a form goes, its method, and the name/value pairs it sends.
+type FormSubmission = (Url, String, Vec<(String, String)>);
+
+fn node_at<'a>(node: &'a crate::dom::DomNode, path: &[usize]) -> Option<&'a crate::dom::DomNode> {
+    path.iter().try_fold(node, |node, &i| node.children.get(i))
+}
+
+/// The attributes that only change how an element is styled.
+const STYLING_ATTRIBUTES: [&str; 3] = ["style", "class", "hidden"];
+
+/// `node`'s attributes other than its styling ones, sorted.
+fn unstyled_attributes(node: &crate::dom::DomNode) -> Vec<(&String, &String)> {
+    let mut attrs: Vec<(&String, &String)> = node
+        .attributes
+        .iter()
+        .filter(|(name, _)| !STYLING_ATTRIBUTES.contains(&name.as_str()))
+        .collect();
+    attrs.sort();
+    attrs
+}
+
+/// Whether `after` differs from `before` in nothing but the attributes that
+/// only change how it is styled. Descendants aren't compared; `apply_action`
+/// never changes those of the elements it targets.
+fn only_restyled(before: &crate::dom::DomNode, after: &crate::dom::DomNode) -> bool {
+    before.tag == after.tag
+        && before.node_type == after.node_type
+        && before.children.len() == after.children.len()
+        && unstyled_attributes(before) == unstyled_attributes(after)
+}
+
 impl Session {
     pub fn new() -> Result<Self, FetchError> {
         Self::with_config(SessionConfig::default())
     }
 
     pub fn with_config(config: SessionConfig) -> Result<Self, FetchError> {
-        let cookie_store = Arc::new(reqwest::cookie::Jar::default());
-        let allow_private = config.allow_private_network;
-        let allow_non_http = config.allow_non_http;
-        let max_redirects = config.max_redirects;
-        let client = Client::builder()
-            .user_agent(&config.user_agent)
-            .timeout(std::time::Duration::from_secs(config.timeout_secs))
-            .redirect(Policy::custom(move |attempt| {
-                if attempt.previous().len() >= max_redirects {
-                    return attempt.stop();
-                }
-                if !is_url_allowed(attempt.url(), allow_private, allow_non_http) {
-                    return attempt.stop();
-                }
-                attempt.follow()
-            }))
-            .cookie_provider(cookie_store)
-            .build()
-            .map_err(|e| FetchError::Network(e.to_string()))?;
+        Self::with_cookie_jar(config, Arc::new(CookieJar::default()))
+    }
+
+    /// A session keeping its cookies in `cookie_jar`. Sessions given the
+    /// same jar share their cookies, as `new_tab` does; give each a
+    /// [`CookieJar::sharing`] one to share only some domains' cookies.
+    pub fn with_cookie_jar(config: SessionConfig, cookie_jar: Arc<CookieJar>) -> Result<Self, FetchError> {
+        let recorder = match config.recording {
+            Some(ref mode) => Some(Arc::new(Recorder::new(mode, config.recording_ignore_params.clone())?)),
+            None => None,
+        };
+        let site_overrides = match config.site_overrides_path {
+            Some(ref path) => Arc::new(SiteOverrides::load(path)?),
+            None => Arc::default(),
+        };
+        Self::build(config, cookie_jar, recorder, site_overrides)
+    }
+
+    /// A new session with the same settings and the same cookie jar, but no
+    /// page or history of its own, like another tab of one browser: cookies
+    /// either one receives are sent by both. A recording session's tabs
+    /// record into the same fixtures.
+    pub fn new_tab(&self) -> Result<Session, FetchError> {
+        // Settings as they were before a site override changed them
+        let config = match self.site {
+            Some((_, ref base)) => base.clone(),
+            None => self.config.clone(),
+        };
+        Self::build(config, self.cookie_jar.clone(), self.recorder.clone(), self.site_overrides.clone())
+    }
+
+    fn build(
+        config: SessionConfig,
+        cookie_store: Arc<CookieJar>,
+        recorder: Option<Arc<Recorder>>,
+        site_overrides: Arc<SiteOverrides>,
+    ) -> Result<Self, FetchError> {
+        let redirect_log = Arc::new(RedirectLog::default());
+        let client = build_client(&config, cookie_store.clone(), redirect_log.clone())?;
+        let audit = AuditLog::new(config.audit_log_limit);
 
         Ok(Self {
             client,
@@ -146,109 +700,883 @@ impl Session {
             checked_ids: HashSet::new(),
             unchecked_ids: HashSet::new(),
             current_html: None,
+            current_tree: None,
+            current_css: String::new(),
+            current_layout: None,
             domain_memory: HashMap::new(),
+            cookie_jar: cookie_store,
+            redirect_log,
+            recorder,
+            last_timings: None,
+            last_css_stats: CssStats::default(),
+            nav_stats: Vec::new(),
+            auto_dismissing: false,
+            watching_for_failure: false,
+            audit,
+            last_attempts: Vec::new(),
+            last_redirects: Vec::new(),
+            page_cache: PageCache::default(),
+            site_overrides,
+            site: None,
+            host_requests: HashMap::new(),
+            robots: HashMap::new(),
+            held: HeldActions::default(),
+            preconfirmed: false,
+            annotations: HashMap::new(),
+            dropped_notes: Vec::new(),
         })
     }
 
-    /// Navigate to a URL and return the Spatial DOM.
+    /// A copy of the session that goes its own way from here: it starts with
+    /// the same cookies, pages, history, form values and settings, but has
+    /// its own cookie jar and HTTP client, so nothing either one does shows
+    /// up in the other.
+    ///
+    /// Rate-limit state is per fork too. Retries back off within a single
+    /// call, and the domain outcomes and last attempts are copied, then kept
+    /// separately. A recording session's forks record into the same fixtures.
+    pub fn fork(&self) -> Result<Session, FetchError> {
+        let cookie_jar = Arc::new(self.cookie_jar.fork());
+        let redirect_log = Arc::new(RedirectLog::default());
+        let client = build_client(&self.config, cookie_jar.clone(), redirect_log.clone())?;
+        Ok(Self {
+            client,
+            config: self.config.clone(),
+            current_url: self.current_url.clone(),
+            current_dom: self.current_dom.clone(),
+            previous_dom: self.previous_dom.clone(),
+            history: self.history.clone(),
+            form_values: self.form_values.clone(),
+            checked_ids: self.checked_ids.clone(),
+            unchecked_ids: self.unchecked_ids.clone(),
+            current_html: self.current_html.clone(),
+            current_tree: self.current_tree.clone(),
+            current_css: self.current_css.clone(),
+            // The fork's first re-render lays the whole page out and keeps its own
+            current_layout: None,
+            domain_memory: self.domain_memory.clone(),
+            cookie_jar,
+            redirect_log,
+            recorder: self.recorder.clone(),
+            last_timings: self.last_timings.clone(),
+            last_css_stats: self.last_css_stats,
+            nav_stats: self.nav_stats.clone(),
+            auto_dismissing: false,
+            watching_for_failure: false,
+            audit: self.audit.clone(),
+            last_attempts: self.last_attempts.clone(),
+            last_redirects: self.last_redirects.clone(),
+            page_cache: self.page_cache.clone(),
+            site_overrides: self.site_overrides.clone(),
+            site: self.site.clone(),
+            host_requests: self.host_requests.clone(),
+            robots: self.robots.clone(),
+            // A confirmation token confirms an action in one session only
+            held: HeldActions::default(),
+            preconfirmed: false,
+            annotations: self.annotations.clone(),
+            dropped_notes: self.dropped_notes.clone(),
+        })
+    }
+
+    /// Navigate to a URL and return the Spatial DOM. With
+    /// `SessionConfig::use_conditional_requests`, behaves like `goto_if_modified`.
     pub fn goto(&mut self, url: &str) -> Result<SpatialDom, FetchError> {
+        self.goto_with(url, self.config.use_conditional_requests, false)
+    }
+
+    /// Navigate to a URL, revalidating a page fetched earlier with its
+    /// `ETag` / `Last-Modified`. If the server answers `304 Not Modified`,
+    /// the earlier page is returned with `not_modified` set.
+    pub fn goto_if_modified(&mut self, url: &str) -> Result<SpatialDom, FetchError> {
+        self.goto_with(url, true, false)
+    }
+
+    /// Open the PDF at `url` as the current page: its text in reading order,
+    /// headings told apart by font size, and each element with the `page`
+    /// it's on. `goto` does the same for any URL that answers with a PDF;
+    /// this fails, keeping the current page, when the URL answers with
+    /// anything else.
+    #[cfg(feature = "pdf")]
+    pub fn open_pdf(&mut self, url: &str) -> Result<SpatialDom, FetchError> {
+        self.goto_with(url, false, true)
+    }
+
+    fn goto_with(&mut self, url: &str, conditional: bool, pdf_only: bool) -> Result<SpatialDom, FetchError> {
+        self.audit.record(AuditAction::Navigate { url: url.to_string() });
+        self.snapshot_failures(|session| {
+            let result = session.navigate(url, conditional, pdf_only);
+            if let Err(ref e) = result {
+                session.audit.record(AuditAction::Error { message: e.to_string() });
+            }
+            result
+        })
+    }
+
+    fn navigate(&mut self, url: &str, conditional: bool, pdf_only: bool) -> Result<SpatialDom, FetchError> {
+        let _span = tracing::info_span!("goto", %url).entered();
+        let started = Instant::now();
         let parsed_url = Url::parse(url).map_err(|e| FetchError::InvalidUrl(e.to_string()))?;
-        if !is_url_allowed(&parsed_url, self.config.allow_private_network, self.config.allow_non_http) {
-            return Err(FetchError::BlockedUrl(parsed_url.to_string()));
-        }
+        self.check_url_allowed(&parsed_url, UrlKind::Navigation)?;
+        self.select_site(&parsed_url);
 
-        let html = match self.fetch_html_with_retry(&parsed_url) {
-            Ok(h) => h,
+        let validators = if conditional {
+            self.page_cache.get(&parsed_url).map(CachedPage::conditional_headers)
+        } else {
+            None
+        };
+        let mut fetch_ms = 0.0;
+        let fetched = phase!(
+            fetch_ms,
+            "fetch",
+            self.fetch_with_retry(&parsed_url, validators.as_deref().unwrap_or_default())
+        );
+        let response = match fetched {
+            Ok(r) => r,
             Err(e) => {
                 self.record_domain_error(&parsed_url, &e);
                 return Err(e);
             }
         };
 
-        let dom = self.load_html(&html, url)?;
+        if response.status == 304 {
+            let dom = self.restore_cached(&parsed_url, fetch_ms, started)?;
+            self.history.push(url.to_string());
+            let dom = self.warn_blocked_pattern(&parsed_url, dom);
+            self.current_url = Some(parsed_url);
+            return Ok(dom);
+        }
+
+        if pdf_only && response.binary.is_none() {
+            let content_type = response.header("content-type").unwrap_or("text/html");
+            return Err(FetchError::ActionError(format!("{url} is not a PDF but {content_type}")));
+        }
+        let dom = self.load_response(&response, url)?;
+        if conditional && response.binary.is_none() {
+            if let Some(page) = CachedPage::from_response(&response, &self.current_css, &dom) {
+                self.page_cache.insert(&parsed_url, page);
+            }
+        }
+        self.add_fetch_timing(fetch_ms, started);
+        self.record_nav_stats(response.body_len());
         self.history.push(url.to_string());
-        self.current_url = Some(parsed_url);
+        self.current_url = Some(parsed_url.clone());
         if let Some(url) = self.current_url.clone() {
             self.update_domain_from_dom(&url, &dom);
         }
 
+        let dom = self.auto_dismiss(dom);
+        Ok(self.warn_blocked_pattern(&parsed_url, dom))
+    }
+
+    /// Make the cached copy of `url` the current page after a `304`.
+    fn restore_cached(&mut self, url: &Url, fetch_ms: f64, started: Instant) -> Result<SpatialDom, FetchError> {
+        let page = self
+            .page_cache
+            .get(url)
+            .cloned()
+            // Only cached pages are revalidated, so a 304 without one is the server's doing
+            .ok_or(FetchError::HttpError(304))?;
+        self.carry_annotations(&page.dom);
+        self.previous_dom = self.current_dom.take();
+        self.current_dom = Some(page.dom.clone());
+        self.current_tree = Some(self.parse_dom_tree(&page.html));
+        // The first re-render lays the page out in full
+        self.current_layout = None;
+        self.current_html = Some(page.html);
+        self.current_css = page.css;
+        self.form_values.clear();
+        self.checked_ids.clear();
+        self.unchecked_ids.clear();
+        self.last_timings = Some(Timings { fetch: fetch_ms, total: elapsed_ms(started), ..Timings::default() });
+        let mut dom = page.dom;
+        dom.not_modified = true;
         Ok(dom)
     }
 
-    /// Load HTML content directly (without fetching).
+    /// Load HTML content directly (without fetching). `url` is the page's
+    /// address, and the base its relative links resolve against.
     pub fn load_html(&mut self, html: &str, url: &str) -> Result<SpatialDom, FetchError> {
-        let result = self.parse_html_only(html, url)?;
+        self.audit.record(AuditAction::LoadHtml { url: url.to_string() });
+        if let Ok(parsed) = Url::parse(url) {
+            self.select_site(&parsed);
+        }
+        let dom = self.load_html_with(html, url, self.config.fetch_css)?;
+        self.current_url = Url::parse(url).ok();
+        Ok(self.auto_dismiss(dom))
+    }
+
+    /// Load HTML content without touching the network: external stylesheets
+    /// are not fetched even when `fetch_css` is enabled.
+    pub fn load_html_offline(&mut self, html: &str, url: &str) -> Result<SpatialDom, FetchError> {
+        self.audit.record(AuditAction::LoadHtml { url: url.to_string() });
+        if let Ok(parsed) = Url::parse(url) {
+            self.select_site(&parsed);
+        }
+        let dom = self.load_html_with(html, url, false)?;
+        self.current_url = Url::parse(url).ok();
+        Ok(self.auto_dismiss(dom))
+    }
+
+    /// Change the viewport used for later page loads and re-renders.
+    /// The current page is not re-rendered; see `resize` for that.
+    pub fn set_viewport(&mut self, width: f32, height: f32) {
+        self.update_config(|config| {
+            config.viewport_width = width;
+            config.viewport_height = height;
+        });
+    }
+
+    /// Viewport width and height pages are laid out at.
+    pub fn viewport(&self) -> (f32, f32) {
+        (self.config.viewport_width, self.config.viewport_height)
+    }
+
+    /// Change the viewport and lay the current page out again at the new
+    /// size, from the HTML and stylesheets already loaded: nothing is
+    /// fetched, and typed values and checked state are kept. Returns the
+    /// page as `dom()` would, or `None` when no page is loaded.
+    ///
+    /// A page changed by a click's JS behavior is laid out, and its
+    /// behaviors detected, from the HTML it was loaded with.
+    pub fn resize(&mut self, width: f32, height: f32) -> Option<SpatialDom> {
+        self.set_viewport(width, height);
+        let html = self.current_html.clone()?;
+        let url = self.current_dom.as_ref()?.url.clone();
+        let _span = tracing::info_span!("resize", %url, width, height).entered();
+        let started = Instant::now();
+        let mut timings = Timings::default();
+        let dom_tree = phase!(timings.parse, "parse", self.parse_dom_tree(&html));
+        let (mut spatial, page) = self.render_tree(&dom_tree, &self.current_css, &url, &mut timings);
+        crate::output::resolve_urls(&mut spatial, &url);
+        self.stabilize_ids(&mut spatial, true);
+        self.current_tree = Some(dom_tree);
+        self.current_layout = Some(page);
+        timings.total = elapsed_ms(started);
+        self.last_timings = Some(timings);
+
+        // Form state is keyed by element ID, which only survives with stable IDs
+        if !self.config.stable_ids {
+            self.form_values.clear();
+            self.checked_ids.clear();
+            self.unchecked_ids.clear();
+        }
+        self.carry_annotations(&spatial);
+        self.current_dom = Some(spatial);
+        self.dom()
+    }
+
+    /// Change whether later page loads fetch external stylesheets.
+    pub fn set_fetch_css(&mut self, fetch_css: bool) {
+        self.update_config(|config| config.fetch_css = fetch_css);
+    }
+
+    /// Whether page loads fetch external stylesheets.
+    pub fn fetch_css(&self) -> bool {
+        self.config.fetch_css
+    }
+
+    /// Change whether later page loads give each element a CSS selector
+    /// hint. See `SessionConfig::emit_selectors`.
+    pub fn set_emit_selectors(&mut self, emit_selectors: bool) {
+        self.update_config(|config| config.emit_selectors = emit_selectors);
+    }
+
+    /// Whether page loads give elements selector hints.
+    pub fn emit_selectors(&self) -> bool {
+        self.config.emit_selectors
+    }
+
+    /// Change the `User-Agent` sent with later requests. Cookies are kept.
+    pub fn set_user_agent(&mut self, user_agent: &str) -> Result<(), FetchError> {
+        if user_agent == self.config.user_agent {
+            return Ok(());
+        }
+        let previous = std::mem::replace(&mut self.config.user_agent, user_agent.to_string());
+        match build_client(&self.config, self.cookie_jar.clone(), self.redirect_log.clone()) {
+            Ok(client) => {
+                self.client = client;
+                if let Some((_, base)) = self.site.as_mut() {
+                    base.user_agent = user_agent.to_string();
+                }
+                Ok(())
+            }
+            Err(e) => {
+                self.config.user_agent = previous;
+                Err(e)
+            }
+        }
+    }
+
+    /// The `User-Agent` sent with requests.
+    pub fn user_agent(&self) -> &str {
+        &self.config.user_agent
+    }
+
+    /// Change whether later page loads dismiss cookie banners, and how.
+    pub fn set_auto_dismiss_cookie_banners(&mut self, choice: Option<ConsentChoice>) {
+        self.update_config(|config| config.auto_dismiss_cookie_banners = choice);
+    }
+
+    /// Which button later page loads press on cookie banners, if any.
+    pub fn auto_dismiss_cookie_banners(&self) -> Option<ConsentChoice> {
+        self.config.auto_dismiss_cookie_banners
+    }
+
+    /// The settings in effect for the current page: the session's, with the
+    /// matching site override (see [`site_override`](Self::site_override))
+    /// applied on top.
+    pub fn effective_config(&self) -> &SessionConfig {
+        &self.config
+    }
+
+    /// The domain suffix of the site override applied to the current page.
+    pub fn site_override(&self) -> Option<&str> {
+        self.site.as_ref().map(|(suffix, _)| suffix.as_str())
+    }
+
+    /// Apply the site override matching `url`'s host, putting back the
+    /// settings the previous site's override replaced.
+    fn select_site(&mut self, url: &Url) {
+        let matched = url
+            .host_str()
+            .and_then(|host| self.site_overrides.for_host(host))
+            .map(|(suffix, site)| (suffix.to_string(), site.clone()));
+        if matched.as_ref().map(|(suffix, _)| suffix.as_str()) == self.site_override() {
+            return;
+        }
+        if let Some((_, base)) = self.site.take() {
+            site::restore(&mut self.config, &base);
+        }
+        if let Some((suffix, site)) = matched {
+            tracing::debug!(site = %suffix, "applying site override");
+            let base = self.config.clone();
+            site.apply(&mut self.config);
+            self.site = Some((suffix, base));
+        }
+    }
+
+    /// Change a setting a site override may also set. The change holds for
+    /// the current page and for every site that doesn't override it.
+    fn update_config(&mut self, change: impl Fn(&mut SessionConfig)) {
+        change(&mut self.config);
+        if let Some((_, base)) = self.site.as_mut() {
+            change(base);
+        }
+    }
+
+    /// Wait until `per_host_delay_ms` has passed since the last page request
+    /// to `url`'s host.
+    fn pace(&mut self, url: &Url) {
+        let Some(host) = url.host_str() else { return };
+        let delay = Duration::from_millis(self.config.per_host_delay_ms);
+        if let Some(last) = self.host_requests.get(host) {
+            if let Some(wait) = delay.checked_sub(last.elapsed()) {
+                thread::sleep(wait);
+            }
+        }
+        self.host_requests.insert(host.to_string(), Instant::now());
+    }
+
+    /// Replace the domain lists checked before every navigation and redirect.
+    /// See `SessionConfig::allowed_domains` and `blocked_domains`.
+    pub fn set_domain_lists(&mut self, allowed: Option<Vec<String>>, blocked: Vec<String>) -> Result<(), FetchError> {
+        self.config.allowed_domains = allowed;
+        self.config.blocked_domains = blocked;
+        // The redirect policy holds its own copy of the lists
+        self.client = build_client(&self.config, self.cookie_jar.clone(), self.redirect_log.clone())?;
+        Ok(())
+    }
+
+    /// Domains navigation is limited to, if any.
+    pub fn allowed_domains(&self) -> Option<&[String]> {
+        self.config.allowed_domains.as_deref()
+    }
+
+    /// Domains navigation never goes to.
+    pub fn blocked_domains(&self) -> &[String] {
+        &self.config.blocked_domains
+    }
+
+    /// Time spent in each phase of the last page load, if any.
+    pub fn timings(&self) -> Option<&Timings> {
+        self.last_timings.as_ref()
+    }
+
+    /// What the last `goto` or form submission cost, if any. Pages loaded
+    /// from HTML and `304` revalidations don't count.
+    pub fn last_nav_stats(&self) -> Option<&NavStats> {
+        self.nav_stats.last()
+    }
+
+    /// The costs of the last navigations, oldest first. Up to 50 are kept.
+    pub fn nav_stats(&self) -> &[NavStats] {
+        &self.nav_stats
+    }
+
+    /// Whether the current page's language differs from the configured
+    /// `accept_language`. Always false when none is configured.
+    pub fn language_mismatch(&self) -> bool {
+        match (&self.current_dom, &self.config.accept_language) {
+            (Some(dom), Some(accept_language)) => dom.language_mismatch(accept_language),
+            _ => false,
+        }
+    }
+
+    /// Everything done in this session, oldest first, up to
+    /// `SessionConfig::audit_log_limit` events.
+    pub fn audit_log(&self) -> Vec<AuditEvent> {
+        self.audit.events()
+    }
+
+    /// Audit events newer than sequence number `seq`.
+    pub fn audit_log_since(&self, seq: u64) -> Vec<AuditEvent> {
+        self.audit.since(seq)
+    }
+
+    /// Save the current page, form state and recent audit events into a new
+    /// timestamped folder under `dir`, and return the folder's path. Values
+    /// typed into sensitive fields are redacted as in `dom()`.
+    pub fn save_snapshot(&self, dir: &Path) -> Result<PathBuf, FetchError> {
+        self.write_snapshot(dir, None)
+    }
+
+    fn write_snapshot(&self, dir: &Path, reason: Option<&str>) -> Result<PathBuf, FetchError> {
+        let audit = self.audit.events();
+        let skip = audit.len().saturating_sub(snapshot::AUDIT_EVENTS);
+        // The page's own URL when there is no base to navigate from
+        let url = self.url().or_else(|| self.current_dom.as_ref().map(|d| d.url.as_str()).filter(|u| !u.is_empty()));
+        Snapshot {
+            url,
+            reason,
+            html: self.current_html.as_deref(),
+            dom: self.dom(),
+            form_state: self.form_state(),
+            audit: audit.into_iter().skip(skip).collect(),
+        }
+        .write(dir)
+    }
+
+    /// Typed and selected values with the redaction rules of `dom()` applied.
+    fn form_state(&self) -> FormState {
+        let mut values: Vec<FieldValue> = self
+            .form_values
+            .iter()
+            .map(|(&id, value)| {
+                let el = self.element(id);
+                let sensitive = el.is_some_and(super::redact::is_sensitive);
+                FieldValue {
+                    id,
+                    name: el.and_then(|e| e.name.clone()),
+                    value: if self.config.redact_sensitive && sensitive {
+                        super::redact::redacted(value)
+                    } else {
+                        value.clone()
+                    },
+                }
+            })
+            .collect();
+        values.sort_by_key(|v| v.id);
+        let mut checked: Vec<u32> = self.checked_ids.iter().copied().collect();
+        checked.sort_unstable();
+        let mut unchecked: Vec<u32> = self.unchecked_ids.iter().copied().collect();
+        unchecked.sort_unstable();
+        FormState { values, checked, unchecked }
+    }
+
+    /// Run `action`, saving a snapshot to `snapshot_on_error` if it fails or
+    /// ends on a blocked or CAPTCHA page.
+    fn snapshot_failures(
+        &mut self,
+        action: impl FnOnce(&mut Self) -> Result<SpatialDom, FetchError>,
+    ) -> Result<SpatialDom, FetchError> {
+        let Some(dir) = self.config.snapshot_on_error.clone() else {
+            return action(self);
+        };
+        if self.watching_for_failure {
+            return action(self);
+        }
+        self.watching_for_failure = true;
+        let result = action(self);
+        self.watching_for_failure = false;
+
+        let reason = match &result {
+            // Held for confirmation, not failed
+            Err(FetchError::ConfirmationRequired { .. }) => None,
+            Err(e) => Some(e.to_string()),
+            Ok(dom) => match (&dom.blocked, &dom.page_type) {
+                (Some(blocked), _) => Some(format!("Blocked: {}", blocked.reason)),
+                (None, PageType::Blocked) => Some("Blocked".to_string()),
+                (None, PageType::Captcha) => Some("CAPTCHA".to_string()),
+                _ => None,
+            },
+        };
+        if let Some(reason) = reason {
+            match self.write_snapshot(&dir, Some(&reason)) {
+                Ok(path) => tracing::info!(path = %path.display(), %reason, "saved snapshot"),
+                Err(e) => tracing::warn!(error = %e, "could not save snapshot"),
+            }
+        }
+        result
+    }
+
+    /// Every try made by the last page fetch or form submission, including
+    /// the delay waited after each failed one.
+    pub fn last_attempts(&self) -> &[Attempt] {
+        &self.last_attempts
+    }
+
+    /// The redirects behind the last page fetch or form submission: the
+    /// requested URL, then each URL it was redirected to. Empty when there
+    /// were none. When the chain fails with `TooManyRedirects`, this is the
+    /// error's chain.
+    pub fn last_redirects(&self) -> &[String] {
+        &self.last_redirects
+    }
+
+    fn load_html_with(&mut self, html: &str, url: &str, fetch_css: bool) -> Result<SpatialDom, FetchError> {
+        let _span = tracing::info_span!("load_html", %url).entered();
+        let started = Instant::now();
+        let mut timings = Timings::default();
+        let dom_tree = phase!(timings.parse, "parse", self.parse_dom_tree(html));
+        let (mut result, page, css, css_stats) = self.render_parsed(&dom_tree, url, fetch_css, &mut timings)?;
+        let rerender = self.current_dom.as_ref().is_some_and(|dom| same_page(&dom.url, url));
+        self.stabilize_ids(&mut result, rerender);
+        if !rerender {
+            self.audit_detections(&result);
+        }
+        self.carry_annotations(&result);
         self.previous_dom = self.current_dom.take();
         self.current_dom = Some(result.clone());
         self.current_html = Some(html.to_string());
+        self.current_tree = Some(dom_tree);
+        self.current_layout = Some(page);
+        self.current_css = css;
+        self.last_css_stats = css_stats;
         self.form_values.clear();
         self.checked_ids.clear();
         self.unchecked_ids.clear();
+        timings.total = elapsed_ms(started);
+        self.last_timings = Some(timings);
 
         Ok(result)
     }
 
-    fn parse_html_only(&self, html: &str, url: &str) -> Result<SpatialDom, FetchError> {
-        let dom_tree = crate::dom::parse_html(html);
+    /// Click the configured button of a detected cookie banner on `dom`, the
+    /// page just loaded. Returns the page after the click, or `dom` itself
+    /// when there is nothing to do. Runs at most once per navigation.
+    fn auto_dismiss(&mut self, dom: SpatialDom) -> SpatialDom {
+        let Some(choice) = self.config.auto_dismiss_cookie_banners else {
+            return dom;
+        };
+        if self.auto_dismissing {
+            return dom;
+        }
+        let target = dom.suggested_actions.iter().find_map(|a| match (a, choice) {
+            (SuggestedAction::CookieConsent { accept_id, .. }, ConsentChoice::Accept) => Some(*accept_id),
+            (SuggestedAction::CookieConsent { reject_id, .. }, ConsentChoice::Reject) => *reject_id,
+            _ => None,
+        });
+        // A hidden button means the banner is already out of the way
+        let Some(id) = target.filter(|id| dom.get(*id).is_some_and(|e| e.hidden != Some(true))) else {
+            return dom;
+        };
+        let verb = match choice {
+            ConsentChoice::Accept => "accept",
+            ConsentChoice::Reject => "reject",
+        };
+        let label = dom.get(id).and_then(|e| e.text.clone()).unwrap_or_default();
+
+        self.auto_dismissing = true;
+        let clicked = self.click(id);
+        self.auto_dismissing = false;
 
-        let external_css = if self.config.fetch_css {
-            if let Ok(base_url) = Url::parse(url) {
-                fetch_external_css(
-                    &dom_tree,
+        let (mut result, note) = match clicked {
+            Ok(after) => (after, format!("Clicked {label:?} (element {id}) to {verb} cookies")),
+            Err(e) => (dom, format!("Could not {verb} cookies via element {id}: {e}")),
+        };
+        tracing::debug!(%note, "cookie banner");
+        if let Some(current) = self.current_dom.as_mut() {
+            current.auto_actions.push(note.clone());
+        }
+        result.auto_actions.push(note);
+        result
+    }
+
+    /// Fold the network fetch into the timings of the page load that followed it.
+    fn add_fetch_timing(&mut self, fetch_ms: f64, started: Instant) {
+        if let Some(timings) = self.last_timings.as_mut() {
+            timings.fetch = fetch_ms;
+            timings.total = elapsed_ms(started);
+            tracing::info!(total_ms = timings.total, fetch_ms, "page loaded");
+        }
+    }
+
+    /// Note what the navigation that just loaded the current page cost.
+    /// `page_bytes` is the size of its HTML.
+    fn record_nav_stats(&mut self, page_bytes: usize) {
+        let Some(dom) = self.current_dom.as_ref() else { return };
+        let stats = NavStats {
+            url: dom.url.clone(),
+            page_bytes: page_bytes as u64,
+            css_fetched: self.last_css_stats.fetched,
+            css_blocked: self.last_css_stats.blocked,
+            css_bytes: self.last_css_stats.bytes,
+            elements: dom.els.len(),
+            timings: self.last_timings.clone().unwrap_or_default(),
+        };
+        if self.nav_stats.len() == NAV_STATS_KEPT {
+            self.nav_stats.remove(0);
+        }
+        self.nav_stats.push(stats);
+    }
+
+    fn parse_dom_tree(&self, html: &str) -> crate::dom::DomNode {
+        if self.config.track_source_positions {
+            crate::dom::parse_html_with_positions(html)
+        } else {
+            crate::dom::parse_html(html)
+        }
+    }
+
+    /// Parse and render `html`. Also returns the external stylesheets fetched
+    /// for it and what fetching them took.
+    fn parse_html_only(
+        &self,
+        html: &str,
+        url: &str,
+        fetch_css: bool,
+    ) -> Result<(SpatialDom, Timings, String, CssStats), FetchError> {
+        let mut timings = Timings::default();
+        let dom_tree = phase!(timings.parse, "parse", self.parse_dom_tree(html));
+        let (spatial, _, external_css, css_stats) = self.render_parsed(&dom_tree, url, fetch_css, &mut timings)?;
+        Ok((spatial, timings, external_css, css_stats))
+    }
+
+    /// Render an already parsed page. Also returns the external stylesheets
+    /// fetched for it and what fetching them took.
+    fn render_parsed(
+        &self,
+        dom_tree: &crate::dom::DomNode,
+        url: &str,
+        fetch_css: bool,
+        timings: &mut Timings,
+    ) -> Result<(SpatialDom, PageLayout, String, CssStats), FetchError> {
+        let (external_css, css_stats) = match Url::parse(url) {
+            Ok(base_url) if fetch_css => {
+                let started = Instant::now();
+                let fetched = fetch_external_css(
+                    dom_tree,
                     &base_url,
-                    &self.client,
-                    &self.config.blocked_patterns,
+                    &|request| self.send(request),
+                    &|css_url| self.check_url_allowed(css_url, UrlKind::Stylesheet).is_ok(),
                     self.config.max_css_bytes_total,
                     self.config.max_css_bytes_per_file,
-                    self.config.allow_private_network,
-                    self.config.allow_non_http,
-                )
-            } else {
-                String::new()
+                );
+                timings.css_fetch = elapsed_ms(started);
+                fetched
             }
-        } else {
-            String::new()
+            _ => (String::new(), CssStats::default()),
         };
 
-        let styled = if external_css.is_empty() {
-            crate::css::compute_styles_with_viewport(&dom_tree, self.config.viewport_width, self.config.viewport_height)
-        } else {
-            crate::css::compute_styles_with_external_and_viewport(&dom_tree, &external_css, self.config.viewport_width, self.config.viewport_height)
+        let (mut spatial, page) = self.render_tree(dom_tree, &external_css, url, timings);
+        crate::output::resolve_urls(&mut spatial, url);
+        Ok((spatial, page, external_css, css_stats))
+    }
+
+    /// Style, lay out and emit the Spatial DOM for a parsed tree. Also
+    /// returns what it was styled and laid out with.
+    fn render_tree(
+        &self,
+        dom_tree: &crate::dom::DomNode,
+        external_css: &str,
+        url: &str,
+        timings: &mut Timings,
+    ) -> (SpatialDom, PageLayout) {
+        let viewport = (self.config.viewport_width, self.config.viewport_height);
+        let layout_width = crate::css::layout_viewport_width(dom_tree, viewport.0);
+        let (stylesheet, styled) = phase!(timings.style, "style", {
+            let stylesheet = crate::css::Stylesheet::new(dom_tree, external_css, layout_width, viewport.1);
+            let styled = stylesheet.style(dom_tree);
+            (stylesheet, styled)
+        });
+
+        let (tree, laid_out) = phase!(timings.layout, "layout", {
+            let mut tree = crate::layout::LayoutTree::new(&styled, layout_width, viewport.1);
+            let laid_out = tree.layout(&styled);
+            (tree, laid_out)
+        });
+        let spatial = self.emit(&laid_out, layout_width, url, timings);
+
+        let mut ids = HashMap::new();
+        element_ids(dom_tree, &mut Vec::new(), &mut ids);
+        let page = PageLayout { stylesheet, styled, tree, viewport, layout_width, ids };
+        (spatial, page)
+    }
+
+    /// Lay the current page out again after `action` changed its tree into
+    /// `modified`, restyling only the elements the action changed. `None`
+    /// when it changed more than their styling, or the current page wasn't
+    /// laid out at this viewport; the page needs a full render then.
+    fn relayout(
+        &mut self,
+        modified: &crate::dom::DomNode,
+        action: &crate::js::JsAction,
+        url: &str,
+        timings: &mut Timings,
+    ) -> Option<SpatialDom> {
+        let before = self.current_tree.as_ref()?;
+        let page = self.current_layout.as_mut()?;
+        if page.viewport != (self.config.viewport_width, self.config.viewport_height) {
+            return None;
+        }
+        let targets = action.target_ids();
+        if targets.is_empty() {
+            return None;
+        }
+        let mut paths = Vec::new();
+        for id in targets {
+            let path = page.path_to(id)?;
+            if !only_restyled(node_at(before, path)?, node_at(modified, path)?) {
+                return None;
+            }
+            paths.push(path.to_vec());
+        }
+
+        let PageLayout { stylesheet, styled, tree, layout_width, .. } = page;
+        let layout_width = *layout_width;
+        let restyled = phase!(timings.style, "style", {
+            paths.iter().all(|path| stylesheet.restyle(modified, styled, path))
+        });
+        let laid_out = phase!(timings.layout, "layout", {
+            let rebuilt = restyled && paths.iter().all(|path| tree.rebuild(styled, path));
+            rebuilt.then(|| tree.layout(styled))
+        });
+        let Some(laid_out) = laid_out else {
+            // Partly updated, so no longer matches the page
+            self.current_layout = None;
+            return None;
         };
+        Some(self.emit(&laid_out, layout_width, url, timings))
+    }
 
-        let laid_out = crate::layout::compute_layout(
-            &styled,
-            self.config.viewport_width,
-            self.config.viewport_height,
-        );
-        let mut spatial = crate::output::generate_spatial_dom(
-            &laid_out,
+    /// The Spatial DOM of a laid out page.
+    fn emit(
+        &self,
+        laid_out: &crate::layout::LayoutNode,
+        layout_width: f32,
+        url: &str,
+        timings: &mut Timings,
+    ) -> SpatialDom {
+        let mut spatial = phase!(timings.spatial, "spatial", crate::output::generate_spatial_dom_with_options(
+            laid_out,
             self.config.viewport_width,
             self.config.viewport_height,
-        );
+            &self.output_options(),
+        ));
+        spatial.set_layout_width(layout_width);
         spatial.url = url.to_string();
-        crate::output::resolve_urls(&mut spatial, url);
-        Ok(spatial)
+        spatial
     }
 
-    /// Load from a pre-parsed DOM tree (used after JS actions modify the DOM).
-    fn load_html_from_dom(&mut self, dom_tree: crate::dom::DomNode, url: &str) -> Result<SpatialDom, FetchError> {
-        let styled = crate::css::compute_styles_with_viewport(&dom_tree, self.config.viewport_width, self.config.viewport_height);
-        let laid_out = crate::layout::compute_layout(
-            &styled,
-            self.config.viewport_width,
-            self.config.viewport_height,
-        );
-        let mut spatial = crate::output::generate_spatial_dom(
-            &laid_out,
-            self.config.viewport_width,
-            self.config.viewport_height,
-        );
-        spatial.url = url.to_string();
+    fn output_options(&self) -> crate::output::OutputOptions {
+        crate::output::OutputOptions {
+            emit_selectors: self.config.emit_selectors,
+            text_limit: self.config.text_limit,
+            ..Default::default()
+        }
+    }
+
+    /// Load a fetched body as the current page: HTML, or with the `pdf`
+    /// feature a PDF's text. Other content that isn't text fails with
+    /// `BinaryContent`.
+    fn load_response(&mut self, response: &HttpResponse, url: &str) -> Result<SpatialDom, FetchError> {
+        let Some(bytes) = &response.binary else {
+            return self.load_html_with(&response.body, url, self.config.fetch_css);
+        };
+        let content_type = response
+            .header("content-type")
+            .and_then(|value| value.split(';').next())
+            .map(str::trim)
+            .unwrap_or("application/octet-stream");
+        #[cfg(feature = "pdf")]
+        if crate::pdf::is_pdf(Some(content_type), bytes) {
+            return self.load_pdf(bytes, url);
+        }
+        Err(FetchError::BinaryContent {
+            url: url.to_string(),
+            content_type: content_type.to_string(),
+            bytes: bytes.len() as u64,
+        })
+    }
+
+    /// Make the text of the PDF in `bytes` the current page. It has no HTML,
+    /// so it can't be clicked through, resized or re-rendered.
+    #[cfg(feature = "pdf")]
+    fn load_pdf(&mut self, bytes: &[u8], url: &str) -> Result<SpatialDom, FetchError> {
+        let _span = tracing::info_span!("load_pdf", %url).entered();
+        let started = Instant::now();
+        let mut dom = crate::pdf::parse_pdf(bytes, url, self.config.viewport_width, self.config.viewport_height)
+            .map_err(|e| {
+                tracing::warn!(error = %e, "unreadable PDF");
+                FetchError::BinaryContent {
+                    url: url.to_string(),
+                    content_type: "application/pdf".to_string(),
+                    bytes: bytes.len() as u64,
+                }
+            })?;
+        let rerender = self.current_dom.as_ref().is_some_and(|current| same_page(&current.url, url));
+        self.stabilize_ids(&mut dom, rerender);
+        self.carry_annotations(&dom);
+        self.previous_dom = self.current_dom.take();
+        self.current_dom = Some(dom.clone());
+        self.current_html = None;
+        self.current_tree = None;
+        self.current_css.clear();
+        self.current_layout = None;
+        self.form_values.clear();
+        self.checked_ids.clear();
+        self.unchecked_ids.clear();
+        self.last_css_stats = CssStats::default();
+        self.last_timings = Some(Timings { total: elapsed_ms(started), ..Timings::default() });
+        Ok(dom)
+    }
+
+    /// Show the page again from `dom_tree`, the current page's tree after
+    /// `action` changed it. Only the elements it restyled are laid out again
+    /// when `incremental_relayout` allows.
+    fn load_html_from_dom(
+        &mut self,
+        dom_tree: crate::dom::DomNode,
+        action: &crate::js::JsAction,
+        url: &str,
+    ) -> Result<SpatialDom, FetchError> {
+        let _span = tracing::info_span!("rerender", %url).entered();
+        let started = Instant::now();
+        let mut timings = Timings::default();
+        let relaid = if self.config.incremental_relayout {
+            self.relayout(&dom_tree, action, url, &mut timings)
+        } else {
+            None
+        };
+        timings.incremental = relaid.is_some();
+        let mut spatial = match relaid {
+            Some(spatial) => spatial,
+            None => {
+                let (spatial, page) = self.render_tree(&dom_tree, &self.current_css, url, &mut timings);
+                self.current_layout = Some(page);
+                spatial
+            }
+        };
+        self.stabilize_ids(&mut spatial, true);
+        timings.total = elapsed_ms(started);
+        self.last_timings = Some(timings);
 
         let result = spatial.clone();
+        self.carry_annotations(&spatial);
         self.previous_dom = self.current_dom.take();
         self.current_dom = Some(spatial);
+        self.current_tree = Some(dom_tree);
         self.form_values.clear();
         self.checked_ids.clear();
         self.unchecked_ids.clear();
@@ -256,6 +1584,16 @@ impl Session {
         Ok(result)
     }
 
+    /// Reuse IDs from the current DOM when `dom` is a re-render of the same page.
+    fn stabilize_ids(&self, dom: &mut SpatialDom, same_page: bool) {
+        if !same_page || !self.config.stable_ids {
+            return;
+        }
+        if let Some(current) = &self.current_dom {
+            crate::output::stabilize_ids(dom, current);
+        }
+    }
+
     /// Return the current DOM with form state (typed values, checked state) overlaid.
     pub fn dom(&self) -> Option<SpatialDom> {
         let dom = self.current_dom.as_ref()?;
@@ -264,7 +1602,10 @@ impl Session {
         // Overlay typed form values
         for el in &mut result.els {
             if let Some(val) = self.form_values.get(&el.id) {
-                el.val = Some(val.clone());
+                let redact = self.config.redact_sensitive && super::redact::is_sensitive(el);
+                let shown = |v: &str| if redact { super::redact::redacted(v) } else { v.to_string() };
+                el.default_val = el.val.take().map(|v| shown(&v));
+                el.val = Some(shown(val));
             }
             // Overlay checked/unchecked state
             if self.checked_ids.contains(&el.id) {
@@ -272,6 +1613,7 @@ impl Session {
             } else if self.unchecked_ids.contains(&el.id) {
                 el.checked = Some(false);
             }
+            el.note = self.annotations.get(&el.id).cloned();
         }
 
         Some(result)
@@ -282,20 +1624,93 @@ impl Session {
         self.current_dom.as_ref()
     }
 
+    /// What changed between the previous page and the current one, with
+    /// the notes the change dropped.
     pub fn delta(&self) -> Option<crate::output::DeltaDom> {
         match (&self.previous_dom, &self.current_dom) {
-            (Some(old), Some(new)) => Some(crate::output::diff(old, new)),
+            (Some(old), Some(new)) => {
+                let mut delta = crate::output::diff(old, new);
+                delta.dropped_notes = self.dropped_notes.clone();
+                Some(delta)
+            }
             _ => None,
         }
     }
 
+    /// Leave a note on an element, such as "checked, out of stock", shown
+    /// with it in `dom` and the compact format. The note follows the element
+    /// when the page is rendered again, and is dropped, and reported in
+    /// `delta`, when the element disappears or another page loads. An empty
+    /// note removes the element's note.
+    pub fn annotate(&mut self, id: u32, note: String) -> Result<(), FetchError> {
+        self.require_element(id)?;
+        let note = note.trim();
+        if note.is_empty() {
+            self.annotations.remove(&id);
+        } else {
+            self.annotations.insert(id, note.to_string());
+        }
+        Ok(())
+    }
+
+    /// The notes on the current page's elements, by element ID.
+    pub fn annotations(&self) -> Vec<(u32, String)> {
+        let mut notes: Vec<_> = self.annotations.iter().map(|(&id, note)| (id, note.clone())).collect();
+        notes.sort_unstable_by_key(|&(id, _)| id);
+        notes
+    }
+
+    /// Move notes to the elements of `dom`, which is about to replace the
+    /// current page, that match the elements they were left on. Notes that
+    /// don't find their element, all of them when `dom` is another page,
+    /// are dropped.
+    fn carry_annotations(&mut self, dom: &SpatialDom) {
+        self.dropped_notes.clear();
+        if self.annotations.is_empty() {
+            return;
+        }
+        let mut notes = std::mem::take(&mut self.annotations);
+        if let Some(current) = self.current_dom.as_ref().filter(|current| same_page(&current.url, &dom.url)) {
+            let matched = crate::output::matching_ids(dom, current);
+            for (el, previous_id) in dom.els.iter().zip(matched) {
+                if let Some(note) = previous_id.and_then(|id| notes.remove(&id)) {
+                    self.annotations.insert(el.id, note);
+                }
+            }
+        }
+        self.dropped_notes = notes.into_iter().map(|(id, note)| DroppedNote { id, note }).collect();
+        self.dropped_notes.sort_unstable_by_key(|dropped| dropped.id);
+    }
+
+    /// `dom` without the elements the previous page on the same host also
+    /// had, such as a shared header, nav and footer. Unchanged when there is
+    /// no previous page. See [`crate::output::suppress_boilerplate`].
+    pub fn without_boilerplate(&self, dom: SpatialDom) -> SpatialDom {
+        match &self.previous_dom {
+            Some(previous) => crate::output::suppress_boilerplate(dom, previous),
+            None => dom,
+        }
+    }
+
+    /// `dom` with the part of the servers' `scope` parameter that needs the
+    /// session applied: `new_content` compares against the previous page
+    /// (see [`Self::without_boilerplate`]). [`Scope::from_param`] does the rest.
+    ///
+    /// [`Scope::from_param`]: crate::output::Scope::from_param
+    pub fn scoped(&self, dom: SpatialDom, scope: Option<&str>) -> SpatialDom {
+        if scope == Some("new_content") {
+            self.without_boilerplate(dom)
+        } else {
+            dom
+        }
+    }
+
+    /// The JS behaviors of the current page, as it stands after any click
+    /// that changed it.
     pub fn behaviors(&self) -> Vec<crate::js::JsBehavior> {
-        self.current_html
+        self.current_dom
             .as_ref()
-            .map(|html| {
-                let dom_tree = crate::dom::parse_html(html);
-                crate::js::detect_behaviors(&dom_tree)
-            })
+            .map(|dom| dom.behaviors().to_vec())
             .unwrap_or_default()
     }
 
@@ -303,6 +1718,25 @@ impl Session {
         self.current_dom.as_ref().and_then(|dom| dom.get(id))
     }
 
+    /// The element with this ID, or `ElementNotFound` with the range of IDs
+    /// the page has, so the caller can tell a stale ID from a typo.
+    pub fn require_element(&self, id: u32) -> Result<&SpatialElement, FetchError> {
+        if self.current_dom.is_none() {
+            return Err(FetchError::NoPageLoaded);
+        }
+        self.element(id).ok_or_else(|| {
+            let ids = self.current_dom.iter().flat_map(|dom| dom.els.iter().map(|el| el.id));
+            let known_ids = ids.clone().min().zip(ids.max());
+            FetchError::ElementNotFound { id, known_ids }
+        })
+    }
+
+    /// The whole text of an element on the current page, however much of it
+    /// the compact format showed.
+    pub fn element_text(&self, id: u32) -> Option<String> {
+        self.element(id)?.text.clone()
+    }
+
     /// Return an owned copy of an element by ID (for FFI consumers).
     pub fn element_owned(&self, id: u32) -> Option<SpatialElement> {
         self.element(id).cloned()
@@ -311,24 +1745,21 @@ impl Session {
     pub fn find_by_text(&self, text: &str) -> Vec<&SpatialElement> {
         self.current_dom
             .as_ref()
-            .map(|dom| {
-                dom.els
-                    .iter()
-                    .filter(|e| e.text.as_deref().map(|t| t.contains(text)).unwrap_or(false))
-                    .collect()
-            })
+            .map(|dom| dom.find_by_text(text))
+            .unwrap_or_default()
+    }
+
+    pub fn find_by_text_exact(&self, text: &str) -> Vec<&SpatialElement> {
+        self.current_dom
+            .as_ref()
+            .map(|dom| dom.find_by_text_exact(text))
             .unwrap_or_default()
     }
 
     pub fn find_by_role(&self, role: &str) -> Vec<&SpatialElement> {
         self.current_dom
             .as_ref()
-            .map(|dom| {
-                dom.els
-                    .iter()
-                    .filter(|e| e.role.as_deref() == Some(role))
-                    .collect()
-            })
+            .map(|dom| dom.find_by_role(role))
             .unwrap_or_default()
     }
 
@@ -341,17 +1772,45 @@ impl Session {
 
     /// Click an element. Links navigate, buttons submit forms, JS behaviors are simulated.
     pub fn click(&mut self, id: u32) -> Result<SpatialDom, FetchError> {
-        let (tag, href, is_submit) = {
-            let el = self.element(id).ok_or_else(|| {
-                FetchError::ActionError(format!("Element {} not found", id))
-            })?;
-            let is_submit = el.tag == "button"
-                || (el.tag == "input" && el.input_type.as_deref() == Some("submit"));
-            (el.tag.clone(), el.href.clone(), is_submit)
+        self.snapshot_failures(|session| session.click_element(id))
+    }
+
+    fn click_element(&mut self, id: u32) -> Result<SpatialDom, FetchError> {
+        self.require_enabled(id, false)?;
+        let text = {
+            let el = self.require_element(id)?;
+            el.text.clone().or_else(|| el.label.clone())
         };
+        self.audit.record(AuditAction::Click { id, text });
+
+        match self.click_kind(id)? {
+            Click::Navigate(target) => {
+                self.hold_navigation(&target)?;
+                self.goto(&target)
+            }
+            Click::Behavior(action) => {
+                let dom_tree = self.current_tree.as_ref().ok_or(FetchError::NoPageLoaded)?;
+                let modified = crate::js::apply_action(dom_tree, &action);
+                // The page may have been loaded from raw HTML, so keep its own URL
+                let html_url = self.current_dom.as_ref()
+                    .map(|dom| dom.url.clone())
+                    .unwrap_or_default();
+                self.load_html_from_dom(modified, &action, &html_url)
+            }
+            Click::Submit => self.submit_form(id),
+            Click::Stay => self.current_dom
+                .as_ref()
+                .cloned()
+                .ok_or(FetchError::NoPageLoaded),
+        }
+    }
 
-        if tag == "a" {
-            if let Some(href) = href {
+    /// What clicking element `id` does: links navigate, then JS behaviors
+    /// apply, then submit buttons submit.
+    fn click_kind(&self, id: u32) -> Result<Click, FetchError> {
+        let el = self.require_element(id)?;
+        if el.tag == "a" {
+            if let Some(href) = &el.href {
                 let trimmed = href.trim();
                 let lower = trimmed.to_lowercase();
                 if lower.starts_with('#')
@@ -360,79 +1819,355 @@ impl Session {
                     || lower.starts_with("tel:")
                     || lower.starts_with("data:")
                 {
-                    return self.current_dom
-                        .as_ref()
-                        .cloned()
-                        .ok_or_else(|| FetchError::ActionError("No page loaded".to_string()));
+                    return Ok(Click::Stay);
                 }
-                let target = self.resolve_url(trimmed);
-                return self.goto(&target);
+                return Ok(Click::Navigate(self.resolve_url(trimmed)));
             }
         }
 
         // Check JS behaviors before form submit
-        if let Some(html) = &self.current_html {
-            let dom_tree = crate::dom::parse_html(html);
-            let behaviors = crate::js::detect_behaviors(&dom_tree);
-            if let Some(behavior) = behaviors.iter().find(|b| b.trigger_id == id) {
-                match &behavior.action {
-                    crate::js::JsAction::Navigate { url } => {
-                        let target = self.resolve_url(url);
-                        return self.goto(&target);
-                    }
-                    action => {
-                        let modified = crate::js::apply_action(&dom_tree, action);
-                        let html_url = self.current_url.as_ref()
-                            .map(|u| u.to_string())
-                            .unwrap_or_default();
-                        return self.load_html_from_dom(modified, &html_url);
-                    }
-                }
-            }
+        match self.behavior_of(id) {
+            Some(crate::js::JsAction::Navigate { url }) => return Ok(Click::Navigate(self.resolve_url(&url))),
+            Some(action) => return Ok(Click::Behavior(action)),
+            None => {}
+        }
+
+        Ok(if is_submit(el) { Click::Submit } else { Click::Stay })
+    }
+
+    /// The JS behavior element `id` triggers, if it has one.
+    fn behavior_of(&self, id: u32) -> Option<crate::js::JsAction> {
+        self.current_dom
+            .as_ref()?
+            .behaviors()
+            .iter()
+            .find(|b| b.trigger_id == id)
+            .map(|b| b.action.clone())
+    }
+
+    /// What `click(id)` would do, without sending a request or changing the
+    /// session. Fails as the click would on a missing or disabled element,
+    /// or a target the session may not go to.
+    pub fn preview_click(&self, id: u32) -> Result<ActionPreview, FetchError> {
+        self.require_enabled(id, false)?;
+        match self.click_kind(id)? {
+            Click::Navigate(url) => self.preview_navigation(&url),
+            Click::Behavior(action) => Ok(ActionPreview::Behavior { action }),
+            Click::Submit => self.preview_form(id),
+            Click::Stay => Ok(ActionPreview::Nothing),
+        }
+    }
+
+    /// What submitting with element `id` would send, without sending it: a
+    /// submit button's form, or for an input what `press_enter(id)` does.
+    pub fn preview_submit(&self, id: u32) -> Result<ActionPreview, FetchError> {
+        let el = self.require_element(id)?;
+        self.require_enabled(id, false)?;
+        if is_submit(el) {
+            return self.preview_form(id);
+        }
+        if el.tag != "input" {
+            return Err(FetchError::WrongElementType {
+                id,
+                tag: el.tag.to_string(),
+                expected: "submit button or input".to_string(),
+            });
+        }
+        if el.form.is_some() {
+            return self.preview_form(id);
+        }
+        let target = self.enter_query_url(el)?;
+        self.preview_navigation(target.as_str())
+    }
+
+    fn preview_navigation(&self, url: &str) -> Result<ActionPreview, FetchError> {
+        let parsed = Url::parse(url).map_err(|e| FetchError::InvalidUrl(e.to_string()))?;
+        self.check_url_allowed(&parsed, UrlKind::Navigation)?;
+        Ok(ActionPreview::Navigate { url: parsed.to_string() })
+    }
+
+    fn preview_form(&self, button_id: u32) -> Result<ActionPreview, FetchError> {
+        let (url, method, form_data) = self.form_submission(button_id)?;
+        self.check_url_allowed(&url, UrlKind::Form)?;
+        let sensitive: HashSet<&str> = match self.current_dom.as_ref() {
+            Some(dom) if self.config.redact_sensitive => dom
+                .els
+                .iter()
+                .filter(|el| super::redact::is_sensitive(el))
+                .filter_map(|el| el.name.as_deref())
+                .collect(),
+            _ => HashSet::new(),
+        };
+        let fields = form_data
+            .into_iter()
+            .map(|(name, value)| {
+                let value = if sensitive.contains(name.as_str()) { super::redact::redacted(&value) } else { value };
+                PreviewField { name, value }
+            })
+            .collect();
+        Ok(ActionPreview::Submit { method: method.to_uppercase(), url: url.to_string(), fields })
+    }
+
+    /// Take the action `token` holds, as `action_policy` described it when
+    /// it was held: a form is sent with the values it had then. Fails with
+    /// `ActionError` once the token has expired or been used.
+    pub fn confirm(&mut self, token: &str) -> Result<SpatialDom, FetchError> {
+        let held = self.held.take(token).ok_or_else(|| {
+            FetchError::ActionError(format!("No action is waiting for confirmation token {token}; it may have expired"))
+        })?;
+        self.audit.record(AuditAction::Confirmed { description: held.description });
+        self.confirmed(|session| match held.action {
+            Held::Navigate(url) => session.goto(url.as_str()),
+            Held::Submit { url, method, fields } => session.send_form(url, method, fields),
+        })
+    }
+
+    /// Run `action` with `action_policy` letting every click and submission
+    /// through, for an action the user has already approved.
+    pub fn confirmed<T>(&mut self, action: impl FnOnce(&mut Self) -> Result<T, FetchError>) -> Result<T, FetchError> {
+        let outer = std::mem::replace(&mut self.preconfirmed, true);
+        let result = action(self);
+        self.preconfirmed = outer;
+        result
+    }
+
+    /// Hold a click that would go to `target` outside the policy's domains.
+    fn hold_navigation(&mut self, target: &str) -> Result<(), FetchError> {
+        let Some(policy) = self.policy() else { return Ok(()) };
+        // Targets the session may not go to at all fail when it tries
+        let Ok(url) = Url::parse(target) else { return Ok(()) };
+        if !policy.leaves_allowed_domains(&url) || self.check_url_allowed(&url, UrlKind::Navigation).is_err() {
+            return Ok(());
+        }
+        let description = format!("Navigate to {url}, outside the allowed domains");
+        Err(self.hold(Held::Navigate(url), description))
+    }
+
+    /// Hold submitting a form with button `button_id`, or by pressing Enter
+    /// in it, when its submit button's text matches the policy or the form
+    /// goes outside its domains.
+    fn hold_submission(
+        &mut self,
+        button_id: u32,
+        url: &Url,
+        method: &str,
+        fields: &[(String, String)],
+    ) -> Result<(), FetchError> {
+        let Some(policy) = self.policy() else { return Ok(()) };
+        if self.check_url_allowed(url, UrlKind::Form).is_err() {
+            return Ok(());
+        }
+        let label = self.submit_label(button_id);
+        let pattern = label.as_deref().and_then(|label| policy.matching_pattern(label));
+        let reason = match pattern {
+            Some(pattern) => format!("the button matches \"{pattern}\""),
+            None if policy.leaves_allowed_domains(url) => "it goes outside the allowed domains".to_string(),
+            None => return Ok(()),
+        };
+        let button = label.map(|label| format!(" \"{label}\"")).unwrap_or_default();
+        let description = format!("Submit{button} ({} {url}) because {reason}", method.to_uppercase());
+        let action = Held::Submit { url: url.clone(), method: method.to_string(), fields: fields.to_vec() };
+        Err(self.hold(action, description))
+    }
+
+    /// The policy, unless it's off or `confirmed` is running.
+    fn policy(&self) -> Option<ActionPolicy> {
+        self.config.action_policy.clone().filter(|_| !self.preconfirmed)
+    }
+
+    fn hold(&mut self, action: Held, description: String) -> FetchError {
+        let timeout = self.config.action_policy.as_ref().map_or(0, |p| p.confirm_timeout_secs);
+        let token = self.held.hold(action, description.clone(), Duration::from_secs(timeout));
+        self.audit.record(AuditAction::Held { description: description.clone() });
+        FetchError::ConfirmationRequired { token, description }
+    }
+
+    /// The text of the button that submits with element `id`: the element
+    /// itself when it is a submit button, else its form's first one.
+    fn submit_label(&self, id: u32) -> Option<String> {
+        let dom = self.current_dom.as_ref()?;
+        let el = dom.els.iter().find(|e| e.id == id)?;
+        let button = if is_submit(el) {
+            el
+        } else {
+            dom.els.iter().find(|e| is_submit(e) && el.form.is_some() && e.form == el.form)?
+        };
+        button.text.clone().or_else(|| button.label.clone()).or_else(|| button.val.clone())
+    }
+
+    /// Press Enter in input `id`: submit its form with the current values,
+    /// as a browser does even when the form has no submit button. An input
+    /// outside any form sends its value to the current URL as a query
+    /// parameter instead, which is how most such search boxes work.
+    pub fn press_enter(&mut self, id: u32) -> Result<SpatialDom, FetchError> {
+        self.snapshot_failures(|session| session.press_enter_in(id))
+    }
+
+    fn press_enter_in(&mut self, id: u32) -> Result<SpatialDom, FetchError> {
+        let el = self.require_element(id)?.clone();
+        if el.tag != "input" {
+            return Err(FetchError::WrongElementType {
+                id,
+                tag: el.tag.to_string(),
+                expected: "input".to_string(),
+            });
         }
+        self.require_enabled(id, false)?;
+        self.audit.record(AuditAction::PressEnter { id });
 
-        if is_submit {
+        if el.form.is_some() {
             return self.submit_form(id);
         }
+        let target = self.enter_query_url(&el)?;
+        self.goto(target.as_str())
+    }
 
-        self.current_dom
-            .as_ref()
-            .cloned()
-            .ok_or_else(|| FetchError::ActionError("No page loaded".to_string()))
+    /// The current URL with the value of `el`, an input outside any form,
+    /// as a query parameter in place of any other with its name.
+    fn enter_query_url(&self, el: &SpatialElement) -> Result<Url, FetchError> {
+        let name = el.name.as_deref().ok_or_else(|| {
+            FetchError::ActionError(format!("Input {} is outside any form and has no name to send", el.id))
+        })?;
+        let value = self.form_values.get(&el.id).or(el.val.as_ref()).cloned().unwrap_or_default();
+        let mut target = self.current_url.clone().ok_or(FetchError::NoPageLoaded)?;
+        let others: Vec<(String, String)> = target
+            .query_pairs()
+            .filter(|(n, _)| *n != name)
+            .map(|(n, v)| (n.into_owned(), v.into_owned()))
+            .collect();
+        target.set_fragment(None);
+        target.query_pairs_mut().clear().extend_pairs(others).append_pair(name, &value);
+        Ok(target)
     }
 
     pub fn type_text(&mut self, id: u32, text: &str) -> Result<(), FetchError> {
-        let el = self.element(id).ok_or_else(|| {
-            FetchError::ActionError(format!("Element {} not found", id))
-        })?;
+        let el = self.require_element(id)?;
         if el.tag != "input" && el.tag != "textarea" {
-            return Err(FetchError::ActionError(format!(
-                "Element {} ({}) is not a text input", id, el.tag
-            )));
+            return Err(FetchError::WrongElementType {
+                id,
+                tag: el.tag.to_string(),
+                expected: "text input".to_string(),
+            });
         }
+        self.require_enabled(id, true)?;
+        let value = if self.config.redact_sensitive && super::redact::is_sensitive(el) {
+            super::redact::redacted(text)
+        } else {
+            text.to_string()
+        };
         self.form_values.insert(id, text.to_string());
+        self.audit.record(AuditAction::Type { id, value });
         Ok(())
     }
 
+    /// How to echo `text` after typing it into element `id`: quoted, or
+    /// redacted when the field is sensitive and `redact_sensitive` is on.
+    pub fn typed_echo(&self, id: u32, text: &str) -> String {
+        let sensitive = self.element(id).is_some_and(super::redact::is_sensitive);
+        if self.config.redact_sensitive && sensitive {
+            super::redact::redacted(text)
+        } else {
+            format!("{:?}", text)
+        }
+    }
+
+    /// A warning when `text` isn't one of the values element `id` suggests
+    /// from its datalist. The page accepts it anyway, but the site may not.
+    pub fn suggestion_warning(&self, id: u32, text: &str) -> Option<String> {
+        let suggestions = self.element(id)?.suggestions.as_ref()?;
+        if suggestions.iter().any(|s| s == text) {
+            return None;
+        }
+        Some(format!(
+            "{} is not one of the {} values element {} suggests",
+            self.typed_echo(id, text),
+            suggestions.len(),
+            id
+        ))
+    }
+
     fn require_checkable(&self, id: u32) -> Result<&SpatialElement, FetchError> {
-        let el = self.element(id).ok_or_else(|| {
-            FetchError::ActionError(format!("Element {} not found", id))
-        })?;
+        let el = self.require_element(id)?;
         let is_checkable = el.input_type.as_deref() == Some("checkbox")
             || el.input_type.as_deref() == Some("radio");
         if !is_checkable {
-            return Err(FetchError::ActionError(format!(
-                "Element {} is not a checkbox or radio", id
-            )));
+            return Err(FetchError::WrongElementType {
+                id,
+                tag: el.tag.to_string(),
+                expected: "checkbox or radio".to_string(),
+            });
         }
+        self.require_enabled(id, false)?;
         Ok(el)
     }
 
+    /// Fail with `ElementUnavailable` when the element is disabled, or
+    /// read-only and being typed into, since the page would ignore the
+    /// action. `force_actions` skips the check.
+    fn require_enabled(&self, id: u32, typing: bool) -> Result<(), FetchError> {
+        let el = self.require_element(id)?;
+        let state = if el.disabled == Some(true) {
+            "disabled"
+        } else if typing && el.readonly == Some(true) {
+            "readonly"
+        } else {
+            return Ok(());
+        };
+        if self.config.force_actions {
+            return Ok(());
+        }
+        // A disabled submit button is usually waiting on its form's required fields
+        let empty_required = if state == "disabled" && is_submit(el) {
+            self.empty_required_fields(id)
+        } else {
+            Vec::new()
+        };
+        Err(FetchError::ElementUnavailable {
+            id,
+            tag: el.tag.to_string(),
+            state: state.to_string(),
+            empty_required,
+        })
+    }
+
+    /// IDs of the required fields submitted by `submit_id` that have no
+    /// value yet, counting typed values and checked state. All the page's
+    /// required fields when the button isn't paired with a form.
+    fn empty_required_fields(&self, submit_id: u32) -> Vec<u32> {
+        let Some(dom) = self.current_dom.as_ref() else {
+            return Vec::new();
+        };
+        let forms = dom.forms();
+        let fields: Vec<&SpatialElement> = match forms.iter().find(|form| form.submit_id == Some(submit_id)) {
+            Some(form) => form.fields.iter().filter_map(|field| dom.get(field.id)).collect(),
+            None => dom.els.iter().collect(),
+        };
+        fields
+            .into_iter()
+            .filter(|el| el.required == Some(true) && el.hidden != Some(true) && !self.has_value(el))
+            .map(|el| el.id)
+            .collect()
+    }
+
+    /// Whether a field has a value: typed or selected, or checked for a
+    /// checkbox or radio.
+    fn has_value(&self, el: &SpatialElement) -> bool {
+        if matches!(el.input_type.as_deref(), Some("checkbox") | Some("radio")) {
+            return self.checked_ids.contains(&el.id)
+                || (el.checked == Some(true) && !self.unchecked_ids.contains(&el.id));
+        }
+        self.form_values
+            .get(&el.id)
+            .or(el.val.as_ref())
+            .is_some_and(|value| !value.trim().is_empty())
+    }
+
     /// Check a checkbox or radio button.
     pub fn check(&mut self, id: u32) -> Result<(), FetchError> {
         self.require_checkable(id)?;
         self.checked_ids.insert(id);
         self.unchecked_ids.remove(&id);
+        self.audit.record(AuditAction::Check { id });
         Ok(())
     }
 
@@ -441,6 +2176,7 @@ impl Session {
         self.require_checkable(id)?;
         self.unchecked_ids.insert(id);
         self.checked_ids.remove(&id);
+        self.audit.record(AuditAction::Uncheck { id });
         Ok(())
     }
 
@@ -457,23 +2193,27 @@ impl Session {
         if currently_checked {
             self.unchecked_ids.insert(id);
             self.checked_ids.remove(&id);
+            self.audit.record(AuditAction::Uncheck { id });
         } else {
             self.checked_ids.insert(id);
             self.unchecked_ids.remove(&id);
+            self.audit.record(AuditAction::Check { id });
         }
         Ok(())
     }
 
     pub fn select(&mut self, id: u32, value: &str) -> Result<(), FetchError> {
-        let el = self.element(id).ok_or_else(|| {
-            FetchError::ActionError(format!("Element {} not found", id))
-        })?;
+        let el = self.require_element(id)?;
         if el.tag != "select" {
-            return Err(FetchError::ActionError(format!(
-                "Element {} ({}) is not a select", id, el.tag
-            )));
+            return Err(FetchError::WrongElementType {
+                id,
+                tag: el.tag.to_string(),
+                expected: "select".to_string(),
+            });
         }
+        self.require_enabled(id, false)?;
         self.form_values.insert(id, value.to_string());
+        self.audit.record(AuditAction::Select { id, value: value.to_string() });
         Ok(())
     }
 
@@ -483,6 +2223,7 @@ impl Session {
         }
         self.history.pop();
         let prev = self.history.last().unwrap().clone();
+        self.audit.record(AuditAction::Back);
         self.goto(&prev)
     }
 
@@ -490,6 +2231,98 @@ impl Session {
         self.current_url.as_ref().map(|u| u.as_str())
     }
 
+    /// Number of entries in the navigation history.
+    pub fn history_len(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Number of cookies the session would send to the current URL.
+    pub fn cookie_count(&self) -> usize {
+        use reqwest::cookie::CookieStore;
+
+        let Some(url) = self.current_url.as_ref() else {
+            return 0;
+        };
+        CookieStore::cookies(&*self.cookie_jar, url)
+            .and_then(|header| header.to_str().ok().map(|h| h.split("; ").filter(|c| !c.is_empty()).count()))
+            .unwrap_or(0)
+    }
+
+    /// The session's unexpired cookies as JSON: see [`CookieJar::export_json`].
+    pub fn export_cookies(&self) -> String {
+        self.cookie_jar.export_json()
+    }
+
+    /// Add cookies exported by [`export_cookies`](Self::export_cookies) or
+    /// a saved state, replacing any with the same domain, path and name.
+    /// Returns how many were given.
+    pub fn import_cookies(&self, json: &str) -> Result<usize, FetchError> {
+        self.cookie_jar.import_json(json)
+    }
+
+    /// The current URL, history and cookies, for saving with
+    /// [`SessionState::save`] and restoring in a later process.
+    pub fn state(&self) -> SessionState {
+        SessionState {
+            url: self.url().map(str::to_string),
+            history: self.history.clone(),
+            cookies: self.cookie_jar.unexpired(),
+        }
+    }
+
+    /// Take up a saved state: its cookies replace the session's, and its URL
+    /// and history become the session's. No page is loaded until `reload`
+    /// or another navigation, so `dom()` is `None` until then.
+    pub fn restore_state(&mut self, state: SessionState) -> Result<(), FetchError> {
+        let url = state
+            .url
+            .as_deref()
+            .map(Url::parse)
+            .transpose()
+            .map_err(|e| FetchError::InvalidUrl(e.to_string()))?;
+        self.clear_page();
+        self.current_url = url;
+        self.history = state.history;
+        self.cookie_jar.replace(state.cookies);
+        Ok(())
+    }
+
+    /// Fetch the current URL again and make it the current page, without
+    /// adding it to the history a second time.
+    pub fn reload(&mut self) -> Result<SpatialDom, FetchError> {
+        let url = self.url().ok_or(FetchError::NoPageLoaded)?.to_string();
+        let dom = self.goto(&url)?;
+        self.history.pop();
+        Ok(dom)
+    }
+
+    /// Forget the cookies, history and current page, as a new session would
+    /// start. Settings are kept, and so are the cookies of a shared jar,
+    /// which other sessions use too.
+    pub fn reset(&mut self) {
+        self.clear_page();
+        self.current_url = None;
+        self.history.clear();
+        self.cookie_jar.replace(Vec::new());
+        self.page_cache = PageCache::default();
+        self.held.clear();
+    }
+
+    /// Drop the current and previous page and their form state.
+    fn clear_page(&mut self) {
+        self.annotations.clear();
+        self.dropped_notes.clear();
+        self.current_dom = None;
+        self.previous_dom = None;
+        self.current_html = None;
+        self.current_tree = None;
+        self.current_css.clear();
+        self.current_layout = None;
+        self.form_values.clear();
+        self.checked_ids.clear();
+        self.unchecked_ids.clear();
+    }
+
     // --- Findability methods ---
 
     /// Case-insensitive substring match on element text.
@@ -508,48 +2341,13 @@ impl Session {
             .unwrap_or_default()
     }
 
-    /// Find an input element by its semantic purpose.
+    /// Find the first visible input with the given purpose (see
+    /// `SpatialElement::purpose`).
     pub fn find_input_by_purpose(&self, purpose: InputPurpose) -> Option<&SpatialElement> {
         let dom = self.current_dom.as_ref()?;
-        dom.els.iter().find(|e| {
-            if e.hidden == Some(true) || e.tag != "input" {
-                return false;
-            }
-            let input_type = e.input_type.as_deref().unwrap_or("text");
-            let name = e.name.as_deref().unwrap_or("").to_lowercase();
-            let label = e.label.as_deref().unwrap_or("").to_lowercase();
-            let ph = e.ph.as_deref().unwrap_or("").to_lowercase();
-
-            match purpose {
-                InputPurpose::Password => input_type == "password",
-                InputPurpose::Email => {
-                    input_type == "email"
-                        || name.contains("email")
-                        || label.contains("email")
-                }
-                InputPurpose::Username => {
-                    (input_type == "text" || input_type == "email")
-                        && (name.contains("user") || name.contains("login")
-                            || label.contains("user") || label.contains("login"))
-                }
-                InputPurpose::VerificationCode => {
-                    (input_type == "text" || input_type == "number" || input_type == "tel")
-                        && (name.contains("code") || name.contains("otp") || name.contains("verify")
-                            || label.contains("code") || label.contains("otp") || label.contains("verify")
-                            || ph.contains("code") || ph.contains("otp") || ph.contains("verify"))
-                }
-                InputPurpose::Search => {
-                    input_type == "search"
-                        || e.role.as_deref() == Some("searchbox")
-                        || name.contains("search")
-                }
-                InputPurpose::Phone => {
-                    input_type == "tel"
-                        || name.contains("phone")
-                        || label.contains("phone")
-                }
-            }
-        })
+        dom.els
+            .iter()
+            .find(|e| e.hidden != Some(true) && e.purpose.as_deref() == Some(purpose.as_str()))
     }
 
     /// Find the nearest button to an input element.
@@ -561,50 +2359,117 @@ impl Session {
 
     // --- Compound actions ---
 
-    /// Fill in a login form and submit it.
-    pub fn login(&mut self, username: &str, password: &str) -> Result<SpatialDom, FetchError> {
-        let (uid, pid, sid) = {
-            let dom = self.dom_ref().ok_or_else(|| {
-                FetchError::ActionError("No page loaded".to_string())
-            })?;
-            dom.suggested_actions.iter().find_map(|a| match a {
-                SuggestedAction::Login { username_id, password_id, submit_id, .. } => {
-                    Some((*username_id, *password_id, *submit_id))
-                }
-                _ => None,
-            }).ok_or_else(|| {
-                FetchError::ActionError("No login form detected".to_string())
-            })?
-        };
-        self.type_text(uid, username)?;
-        self.type_text(pid, password)?;
-        self.click(sid)
+    /// Fill in a login form, submit it, and classify the resulting page.
+    ///
+    /// Sites that ask for the email first and the password on the next page
+    /// are followed through: a page with only a username or email field gets
+    /// the username and is submitted, then the password goes into the page
+    /// that follows. A CAPTCHA or 2FA page in between ends the login there.
+    pub fn login(&mut self, username: &str, password: &str) -> Result<LoginOutcome, FetchError> {
+        let mut username_sent = false;
+        let mut submitted: Option<SpatialDom> = None;
+        for step in 1..=MAX_LOGIN_STEPS {
+            let fields = {
+                let dom = self.dom_ref().ok_or(FetchError::NoPageLoaded)?;
+                LoginStep::find(dom, username_sent)
+            };
+            let Some(fields) = fields else {
+                let Some(dom) = submitted else {
+                    return Err(FetchError::ActionError("No login form detected".to_string()));
+                };
+                // The username was taken but no password field followed
+                let status = LoginStatus::from_page(&dom);
+                return Ok(LoginOutcome { dom, status, steps: step - 1 });
+            };
+
+            let mut filled = Vec::new();
+            if fields.username_id.is_some() {
+                filled.push("username".to_string());
+            }
+            if fields.password_id.is_some() {
+                filled.push("password".to_string());
+            }
+            self.audit.record(AuditAction::LoginStep { step, fields: filled });
+
+            if let Some(id) = fields.username_id {
+                self.type_text(id, username)?;
+                username_sent = true;
+            }
+            if let Some(id) = fields.password_id {
+                self.type_text(id, password)?;
+            }
+            let dom = self.click(fields.submit_id)?;
+            let status = LoginStatus::from_page(&dom);
+            tracing::debug!(step, %status, "login step submitted");
+
+            // Only a username went in: carry on unless the site stopped us.
+            // The password page itself would read as a failed login.
+            let next_step = fields.password_id.is_none()
+                && step < MAX_LOGIN_STEPS
+                && !matches!(status, LoginStatus::CaptchaRequired | LoginStatus::TwoFactorRequired);
+            if !next_step {
+                return Ok(LoginOutcome { dom, status, steps: step });
+            }
+            submitted = Some(dom);
+        }
+        unreachable!("the last login step always returns")
     }
 
     /// Fill in a verification code and submit it.
+    ///
+    /// Spaces and dashes in `code` are dropped. When the code is split across
+    /// one input per character, each input gets one character in document order.
     pub fn enter_code(&mut self, code: &str) -> Result<SpatialDom, FetchError> {
-        let (input_id, submit_id) = {
-            let dom = self.dom_ref().ok_or_else(|| {
-                FetchError::ActionError("No page loaded".to_string())
-            })?;
+        let (input_id, submit_id, code_length) = {
+            let dom = self.dom_ref().ok_or(FetchError::NoPageLoaded)?;
             dom.suggested_actions.iter().find_map(|a| match a {
-                SuggestedAction::EnterCode { input_id, submit_id, .. } => {
-                    Some((*input_id, *submit_id))
+                SuggestedAction::EnterCode { input_id, submit_id, code_length, .. } => {
+                    Some((*input_id, *submit_id, *code_length))
                 }
                 _ => None,
             }).ok_or_else(|| {
                 FetchError::ActionError("No verification code form detected".to_string())
             })?
         };
-        self.type_text(input_id, code)?;
+        let code: Vec<char> = code.chars().filter(|c| !c.is_whitespace() && *c != '-').collect();
+
+        if code_length.is_some() {
+            let boxes = self.code_digit_inputs(input_id);
+            if code.len() != boxes.len() {
+                return Err(FetchError::ActionError(format!(
+                    "Expected a {}-character code ({} separate inputs), got {} characters",
+                    boxes.len(), boxes.len(), code.len()
+                )));
+            }
+            for (id, c) in boxes.into_iter().zip(code) {
+                self.type_text(id, &c.to_string())?;
+            }
+        } else {
+            self.type_text(input_id, &code.into_iter().collect::<String>())?;
+        }
         self.click(submit_id)
     }
 
+    /// The boxes of a split code widget starting at `first_id`, in document order.
+    fn code_digit_inputs(&self, first_id: u32) -> Vec<u32> {
+        let Some(dom) = self.current_dom.as_ref() else {
+            return Vec::new();
+        };
+        crate::output::code_digit_run(dom, first_id)
+    }
+
     /// Extract a verification code from the current page.
     pub fn find_verification_code(&self) -> Option<String> {
         self.dom_ref()?.find_codes().into_iter().next()
     }
 
+    /// Verification codes and magic links on the current page.
+    pub fn find_verification(&self) -> crate::output::VerificationHints {
+        self.dom_ref()
+            .map(|dom| dom.find_verification())
+            .unwrap_or_default()
+    }
+
     /// Returns true if the current page is a CAPTCHA challenge.
     pub fn is_captcha(&self) -> bool {
         self.current_dom.as_ref()
@@ -633,11 +2498,20 @@ impl Session {
             SearchEngine::Google => format!("https://www.google.com/search?{}&num=10", encoded),
         };
 
+        self.audit.record(AuditAction::Search {
+            query: query.to_string(),
+            engine: format!("{:?}", engine),
+        });
         let parsed_url = Url::parse(&url).map_err(|e| FetchError::InvalidUrl(e.to_string()))?;
-        let html = match self.fetch_html_with_retry(&parsed_url) {
-            Ok(h) => h,
+        if let Err(err) = self.check_url_allowed(&parsed_url, UrlKind::Navigation) {
+            self.audit.record(AuditAction::Error { message: err.to_string() });
+            return Err(err);
+        }
+        let html = match self.fetch_with_retry(&parsed_url, &[]) {
+            Ok(response) => response.body,
             Err(e) => {
                 self.record_domain_error(&parsed_url, &e);
+                self.audit.record(AuditAction::Error { message: e.to_string() });
                 return Err(e);
             }
         };
@@ -645,12 +2519,13 @@ impl Session {
         let dom = crate::dom::parse_html(&html);
         if let Ok(url_obj) = Url::parse(&url) {
             // Use a lightweight parse to update domain memory without mutating session state.
-            let temp_dom = self.parse_html_only(&html, url_obj.as_str())?;
+            let (temp_dom, ..) = self.parse_html_only(&html, url_obj.as_str(), self.config.fetch_css)?;
             self.update_domain_from_dom(&url_obj, &temp_dom);
         }
+        let tracking = &self.config.search_tracking_params;
         match engine {
-            SearchEngine::DuckDuckGo => Ok(extract_ddg_results(&dom)),
-            SearchEngine::Google => Ok(extract_google_results(&dom)),
+            SearchEngine::DuckDuckGo => Ok(extract_ddg_results(&dom, tracking)),
+            SearchEngine::Google => Ok(extract_google_results(&dom, tracking)),
         }
     }
 
@@ -672,36 +2547,265 @@ impl Session {
         Ok(pages)
     }
 
-    fn submit_form(&mut self, button_id: u32) -> Result<SpatialDom, FetchError> {
-        let html = self.current_html.as_ref().ok_or_else(|| {
-            FetchError::ActionError("No page loaded".to_string())
-        })?.clone();
+    /// Find where the current page's site lists its content: the RSS and
+    /// Atom feeds the page links to, the sitemaps its robots.txt names, and
+    /// `/sitemap.xml` if a HEAD request finds one there. A site without a
+    /// robots.txt, or without `/sitemap.xml`, just lists fewer sitemaps.
+    pub fn discover_feeds(&mut self) -> Result<DiscoveredFeeds, FetchError> {
+        let url = self.current_url.clone().ok_or(FetchError::NoPageLoaded)?;
+        let dom_tree = self.current_tree.as_ref().ok_or(FetchError::NoPageLoaded)?;
+        let feeds = feeds::feed_links(dom_tree, &url);
+
+        let mut sitemaps: Vec<String> = Vec::new();
+        if let Some(robots) = self.robots_txt(&url) {
+            for sitemap in feeds::robots_sitemaps(&robots) {
+                if let Ok(sitemap) = url.join(&sitemap) {
+                    if !sitemaps.contains(&sitemap.to_string()) {
+                        sitemaps.push(sitemap.to_string());
+                    }
+                }
+            }
+        }
+        let default = url.join("/sitemap.xml").map_err(|e| FetchError::InvalidUrl(e.to_string()))?;
+        if !sitemaps.contains(&default.to_string()) && self.resource_exists(&default) {
+            sitemaps.push(default.to_string());
+        }
+        Ok(DiscoveredFeeds { sitemaps, feeds })
+    }
+
+    /// The pages a sitemap lists. A sitemap index is followed into the
+    /// sitemaps it lists, breadth first, reading at most [`MAX_SITEMAPS`]
+    /// files in all; entries come back in the order they were read. Sitemaps
+    /// are fetched as downloads, so `allowed_domains` and `blocked_patterns`
+    /// apply to each one.
+    pub fn fetch_sitemap(&mut self, url: &str) -> Result<Vec<SitemapEntry>, FetchError> {
+        let root = Url::parse(url).map_err(|e| FetchError::InvalidUrl(e.to_string()))?;
+        let mut queue = VecDeque::from([root]);
+        let mut seen = HashSet::new();
+        let mut entries = Vec::new();
+        while let Some(sitemap_url) = queue.pop_front() {
+            if seen.len() == MAX_SITEMAPS {
+                break;
+            }
+            if !seen.insert(sitemap_url.to_string()) {
+                continue;
+            }
+            let xml = self.fetch_resource(&sitemap_url)?;
+            let sitemap = feeds::parse_sitemap(&xml)
+                .map_err(|e| FetchError::ActionError(format!("{sitemap_url}: {e}")))?;
+            match sitemap {
+                Sitemap::Urls(urls) => entries.extend(urls),
+                Sitemap::Index(children) => {
+                    queue.extend(children.iter().filter_map(|child| sitemap_url.join(&child.loc).ok()));
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Read the current page and the pages after it, following each one's
+    /// next link (see [`SpatialDom::pagination`]), and merge what `extract`
+    /// takes from them. Stops at a page without a next link, after
+    /// `max_pages` pages (at least one), at a next link back to a page
+    /// already read, or when a page fails to load; what was collected by then
+    /// is returned either way. Each page waits out `per_host_delay_ms` from
+    /// when the one before it was read, and the session is left on the last
+    /// page read.
+    pub fn paginate_collect(&mut self, max_pages: usize, extract: Extract) -> Result<PaginatedResult, FetchError> {
+        let mut dom = self.dom().ok_or(FetchError::NoPageLoaded)?;
+        let mut url = self.current_url.clone().ok_or(FetchError::NoPageLoaded)?;
+        let mut columns = Vec::new();
+        let mut rows = Vec::new();
+        let mut visited: Vec<Url> = Vec::new();
+        let mut error = None;
+        let stop_reason = loop {
+            if columns.is_empty() {
+                columns = paginate::columns(&dom, extract);
+            }
+            let page_index = visited.len().to_string();
+            rows.extend(
+                paginate::rows(&dom, extract)
+                    .into_iter()
+                    .map(|row| std::iter::once(page_index.clone()).chain(row).collect::<Vec<_>>()),
+            );
+            visited.push(url.clone());
+
+            let Some(next) = dom.pagination().and_then(|p| p.next).and_then(|href| url.join(&href).ok()) else {
+                break StopReason::Exhausted;
+            };
+            if visited.len() >= max_pages.max(1) {
+                break StopReason::MaxPages;
+            }
+            if visited.iter().any(|v| same_page(v.as_str(), next.as_str())) {
+                break StopReason::LoopDetected;
+            }
+            // Pace from now rather than from the request for the page just read
+            if let Some(last) = next.host_str().and_then(|host| self.host_requests.get_mut(host)) {
+                *last = Instant::now();
+            }
+            match self.goto(next.as_str()) {
+                Ok(next_dom) => dom = next_dom,
+                Err(e) => {
+                    error = Some(e.to_string());
+                    break StopReason::Error;
+                }
+            }
+            url = self.current_url.clone().unwrap_or(next);
+            // A next link can redirect back to a page already read
+            if visited.iter().any(|v| same_page(v.as_str(), url.as_str())) {
+                break StopReason::LoopDetected;
+            }
+        };
+        Ok(PaginatedResult {
+            headers: std::iter::once("page_index".to_string()).chain(columns).collect(),
+            rows,
+            visited: visited.iter().map(Url::to_string).collect(),
+            stop_reason,
+            error,
+        })
+    }
+
+    /// Visit the pages reachable from `start_url` by links, breadth first,
+    /// and describe each one. URLs are compared normalized (see
+    /// [`normalize_url`](super::normalize_url)), so each page is visited
+    /// once. Links are followed only within `opts`' depth, host and pattern
+    /// limits, only where the session may navigate, and, with
+    /// `respect_robots`, only where the site's robots.txt allows. Pages are
+    /// loaded with `goto`, so `per_host_delay_ms` spaces them out; just the
+    /// current page's DOM is kept, and the session is left on the last page
+    /// visited. A page that fails to load is listed with its error.
+    pub fn crawl(&mut self, start_url: &str, opts: CrawlOpts) -> Result<Vec<CrawlEntry>, FetchError> {
+        let start = Url::parse(start_url).map_err(|e| FetchError::InvalidUrl(e.to_string()))?;
+        let start = crawl::normalize_url(&start);
+        self.check_url_allowed(&start, UrlKind::Navigation)?;
+        let start_host = start.host_str().unwrap_or_default().to_string();
+
+        let mut queue = VecDeque::from([(start.clone(), 0)]);
+        let mut seen = HashSet::from([start.to_string()]);
+        let mut entries = Vec::new();
+        while let Some((url, depth)) = queue.pop_front() {
+            if entries.len() >= opts.max_pages {
+                break;
+            }
+            if opts.respect_robots && !self.robots_allow(&url) {
+                continue;
+            }
+            let dom = match self.goto(url.as_str()) {
+                Ok(dom) => dom,
+                Err(e) => {
+                    entries.push(CrawlEntry::failed(&url, depth, e.to_string()));
+                    continue;
+                }
+            };
+            let landed = self.current_url.as_ref().map_or_else(|| url.clone(), crawl::normalize_url);
+            seen.insert(landed.to_string());
+            let entry = CrawlEntry::from_dom(&landed, depth, &dom);
+            if depth < opts.max_depth {
+                for link in &entry.outlinks {
+                    let Ok(link) = Url::parse(link) else { continue };
+                    let follow = (!opts.same_host_only || link.host_str() == Some(start_host.as_str()))
+                        && (opts.include_patterns.is_empty() || matching_pattern(&link, &opts.include_patterns).is_some())
+                        && matching_pattern(&link, &opts.exclude_patterns).is_none()
+                        && self.check_url_allowed(&link, UrlKind::Navigation).is_ok();
+                    if follow && seen.insert(link.to_string()) {
+                        queue.push_back((link, depth + 1));
+                    }
+                }
+            }
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    /// Whether `url`'s robots.txt lets the session's User-Agent fetch it. A
+    /// site without a robots.txt allows everything.
+    fn robots_allow(&mut self, url: &Url) -> bool {
+        let Some(robots) = self.robots_txt(url) else {
+            return true;
+        };
+        let path = &url[url::Position::BeforePath..url::Position::AfterQuery];
+        crawl::robots_allows(&robots, &self.config.user_agent, path)
+    }
+
+    /// The robots.txt of `url`'s origin, fetched once per session.
+    fn robots_txt(&mut self, url: &Url) -> Option<String> {
+        let origin = url.origin().ascii_serialization();
+        if let Some(robots) = self.robots.get(&origin) {
+            return robots.clone();
+        }
+        let robots = url.join("/robots.txt").ok().and_then(|robots_url| self.fetch_resource(&robots_url).ok());
+        self.robots.insert(origin, robots.clone());
+        robots
+    }
+
+    /// Whether a HEAD request for `url` succeeds.
+    fn resource_exists(&mut self, url: &Url) -> bool {
+        if self.check_url_allowed(url, UrlKind::Download).is_err() {
+            return false;
+        }
+        self.pace(url);
+        self.send(&HttpRequest {
+            method: "HEAD",
+            url,
+            form: &[],
+            user_agent: None,
+            headers: &self.config.extra_headers,
+            max_bytes: self.config.max_response_bytes,
+        })
+        .is_ok_and(|response| response.is_success())
+    }
+
+    /// Fetch a file that isn't a page, such as robots.txt or a sitemap,
+    /// without touching the current page or history.
+    fn fetch_resource(&mut self, url: &Url) -> Result<String, FetchError> {
+        self.check_url_allowed(url, UrlKind::Download)?;
+        self.pace(url);
+        let response = self.send(&HttpRequest {
+            method: "GET",
+            url,
+            form: &[],
+            user_agent: None,
+            headers: &self.config.extra_headers,
+            max_bytes: self.config.max_response_bytes,
+        })?;
+        if !response.is_success() {
+            return Err(FetchError::HttpError(response.status));
+        }
+        Ok(response.body)
+    }
+
+    /// Where the form of `button_id` goes, how, and the name/value pairs it
+    /// sends: its fields' defaults with the session's typed values and
+    /// checked state laid over them.
+    fn form_submission(&self, button_id: u32) -> Result<FormSubmission, FetchError> {
+        let html = self.current_html.as_ref().ok_or(FetchError::NoPageLoaded)?;
 
         let base_url = self.current_url.clone().ok_or_else(|| {
             FetchError::ActionError("No URL loaded".to_string())
         })?;
 
-        let dom_tree = crate::dom::parse_html(&html);
+        let dom_tree = crate::dom::parse_html(html);
         let forms = extract_forms(&dom_tree);
 
-        let button_el = self.element(button_id).ok_or_else(|| {
-            FetchError::ActionError(format!("Button {} not found", button_id))
-        })?.clone();
+        let button_el = self.require_element(button_id)?.clone();
 
         if forms.is_empty() {
             return Err(FetchError::ActionError("No form found on page".to_string()));
         }
 
-        // Find the form containing this button by matching name/text
-        let form_idx = find_form_index_for_button(
-            &forms,
-            button_el.name.as_deref(),
-            button_el.text.as_deref(),
-        );
+        // Use the button's form index when known, else match by name/text
+        let form_idx = match button_el.form {
+            Some(f) if (f as usize) <= forms.len() => f as usize - 1,
+            _ => find_form_index_for_button(
+                &forms,
+                button_el.name.as_deref(),
+                button_el.text.as_deref(),
+            ),
+        };
         let form = &forms[form_idx];
 
         // Check if the button has a formaction that overrides the form action
-        let button_formaction = form.buttons.iter().find(|b| {
+        let button_formaction = form.buttons.iter().filter(|_| is_submit(&button_el)).find(|b| {
             b.name.as_deref() == button_el.name.as_deref()
                 || b.value.as_deref() == button_el.text.as_deref()
                 || b.text.as_deref() == button_el.text.as_deref()
@@ -723,13 +2827,12 @@ impl Session {
         }
 
         // Overlay typed values and checkbox state from session (scoped to this form)
-        let dom = self.current_dom.as_ref().ok_or_else(|| {
-            FetchError::ActionError("No page loaded".to_string())
-        })?;
+        let dom = self.current_dom.as_ref().ok_or(FetchError::NoPageLoaded)?;
         let inputs: Vec<&SpatialElement> = dom
             .els
             .iter()
             .filter(|e| e.tag == "input" || e.tag == "textarea" || e.tag == "select")
+            .filter(|e| button_el.form.is_none() || e.form == button_el.form)
             .collect();
 
         let mut name_to_indices: HashMap<&str, Vec<usize>> = HashMap::new();
@@ -788,25 +2891,59 @@ impl Session {
         let target_url = base_url
             .join(action_str)
             .map_err(|e| FetchError::InvalidUrl(e.to_string()))?;
-        if !is_url_allowed(&target_url, self.config.allow_private_network, self.config.allow_non_http) {
-            return Err(FetchError::BlockedUrl(target_url.to_string()));
+        Ok((target_url, method, form_data))
+    }
+
+    /// Submit the form of `button_id`: a submit button, or the input
+    /// `press_enter` was called on.
+    fn submit_form(&mut self, button_id: u32) -> Result<SpatialDom, FetchError> {
+        let _span = tracing::info_span!("submit", button = button_id).entered();
+        let (target_url, method, form_data) = self.form_submission(button_id)?;
+        self.hold_submission(button_id, &target_url, &method, &form_data)?;
+        self.send_form(target_url, method, form_data)
+    }
+
+    /// Send a form built by `form_submission` and load the page it returns.
+    fn send_form(
+        &mut self,
+        target_url: Url,
+        method: String,
+        form_data: Vec<(String, String)>,
+    ) -> Result<SpatialDom, FetchError> {
+        self.audit.record(AuditAction::Submit {
+            url: target_url.to_string(),
+            method: method.to_uppercase(),
+            fields: form_data.iter().map(|(name, _)| name.clone()).collect(),
+        });
+        if let Err(err) = self.check_url_allowed(&target_url, UrlKind::Form) {
+            self.audit.record(AuditAction::Error { message: err.to_string() });
+            return Err(err);
         }
 
-        let (new_url, html) = match self.submit_with_retry(&target_url, &method, &form_data) {
+        self.select_site(&target_url);
+        let started = Instant::now();
+        let mut fetch_ms = 0.0;
+        let submitted = phase!(fetch_ms, "fetch", self.submit_with_retry(&target_url, &method, &form_data));
+        let response = match submitted {
             Ok(v) => v,
             Err(e) => {
                 self.record_domain_error(&target_url, &e);
+                self.audit.record(AuditAction::Error { message: e.to_string() });
                 return Err(e);
             }
         };
 
+        let new_url = response.url.clone();
+        let dom = self.load_response(&response, &new_url)?;
+        self.add_fetch_timing(fetch_ms, started);
+        self.record_nav_stats(response.body_len());
         self.history.push(new_url.clone());
-        self.current_url = Some(Url::parse(&new_url).unwrap_or(target_url));
-        let dom = self.load_html(&html, &new_url)?;
+        self.current_url = Some(Url::parse(&new_url).unwrap_or_else(|_| target_url.clone()));
         if let Some(url) = self.current_url.clone() {
             self.update_domain_from_dom(&url, &dom);
         }
-        Ok(dom)
+        let dom = self.auto_dismiss(dom);
+        Ok(self.warn_blocked_pattern(&target_url, dom))
     }
 
     pub fn domain_memory_for_current(&self) -> Option<DomainMemory> {
@@ -815,6 +2952,22 @@ impl Session {
         self.domain_memory.get(host).cloned()
     }
 
+    /// Note a CAPTCHA or anti-bot block on a freshly loaded page.
+    fn audit_detections(&mut self, dom: &SpatialDom) {
+        if let Some(captcha) = &dom.captcha {
+            self.audit.record(AuditAction::Captcha {
+                url: dom.url.clone(),
+                captcha_type: format!("{:?}", captcha.captcha_type),
+            });
+        }
+        if let Some(blocked) = &dom.blocked {
+            self.audit.record(AuditAction::Blocked {
+                url: dom.url.clone(),
+                reason: blocked.reason.clone(),
+            });
+        }
+    }
+
     fn update_domain_from_dom(&mut self, url: &Url, dom: &SpatialDom) {
         let outcome = if dom.blocked.is_some() || dom.page_type == PageType::Captcha {
             DomainOutcome::Blocked
@@ -831,10 +2984,18 @@ impl Session {
             FetchError::HttpError(code) if *code == 429 => (DomainOutcome::Blocked, Some("http_429".to_string())),
             FetchError::BlockedUrl(_) => (DomainOutcome::Blocked, Some("blocked_url".to_string())),
             FetchError::Network(_) => (DomainOutcome::Error, Some("network_error".to_string())),
+            FetchError::Timeout(_) => (DomainOutcome::Error, Some("timeout".to_string())),
             FetchError::ResponseTooLarge(_, _) => (DomainOutcome::Error, Some("response_too_large".to_string())),
-            FetchError::InvalidUrl(_) | FetchError::ActionError(_) | FetchError::HttpError(_) => {
-                (DomainOutcome::Error, Some("http_error".to_string()))
-            }
+            FetchError::TooManyRedirects { .. } => (DomainOutcome::Error, Some("too_many_redirects".to_string())),
+            FetchError::InvalidUrl(_)
+            | FetchError::ActionError(_)
+            | FetchError::ElementNotFound { .. }
+            | FetchError::WrongElementType { .. }
+            | FetchError::ElementUnavailable { .. }
+            | FetchError::NoPageLoaded
+            | FetchError::ConfirmationRequired { .. }
+            | FetchError::BinaryContent { .. }
+            | FetchError::HttpError(_) => (DomainOutcome::Error, Some("http_error".to_string())),
         };
         self.record_domain_outcome(url, outcome, reason);
     }
@@ -870,7 +3031,14 @@ impl Session {
         entry.last_seen_unix = now;
     }
 
-    fn fetch_html_with_retry(&self, url: &Url) -> Result<String, FetchError> {
+    fn fetch_with_retry(
+        &mut self,
+        url: &Url,
+        headers: &[(String, String)],
+    ) -> Result<HttpResponse, FetchError> {
+        self.pace(url);
+        let mut headers_with_extra = self.config.extra_headers.clone();
+        headers_with_extra.extend_from_slice(headers);
         let cfg = FetchConfig {
             viewport_width: self.config.viewport_width,
             viewport_height: self.config.viewport_height,
@@ -884,98 +3052,164 @@ impl Session {
             allow_private_network: self.config.allow_private_network,
             allow_non_http: self.config.allow_non_http,
             blocked_patterns: self.config.blocked_patterns.clone(),
-            retry_attempts: self.config.retry_attempts,
-            retry_delay_ms: self.config.retry_delay_ms,
-            retry_on_blocked: self.config.retry_on_blocked,
+            retry: self.config.retry.clone(),
             retry_user_agents: self.config.retry_user_agents.clone(),
         };
-        fetch_html_with_retry(&self.client, url, &cfg)
+        let mut attempts = Vec::new();
+        let result = fetch_with_retry(
+            &|request| self.send(request),
+            url,
+            &headers_with_extra,
+            &cfg,
+            &mut attempts,
+        );
+        self.note_attempts(url, attempts);
+        result
+    }
+
+    /// Keep the attempts behind the last request, logging each retry.
+    fn note_attempts(&mut self, url: &Url, attempts: Vec<Attempt>) {
+        for (i, attempt) in attempts.iter().enumerate() {
+            if let Some(reason) = attempt.retried_for {
+                self.audit.record(AuditAction::Retry {
+                    url: url.to_string(),
+                    attempt: i as u32 + 1,
+                    reason,
+                    delay_ms: attempt.delay_ms,
+                });
+            }
+        }
+        self.last_attempts = attempts;
+        self.last_redirects = self.redirect_log.take();
+    }
+
+    /// Send a request, failing with `BlockedUrl` when it redirects somewhere
+    /// the session may not go.
+    fn send(&self, request: &HttpRequest<'_>) -> Result<HttpResponse, FetchError> {
+        let response = self.exchange(request)?;
+        if let Some(target) = self.blocked_redirect(&response) {
+            return Err(FetchError::BlockedUrl(target.to_string()));
+        }
+        Ok(response)
+    }
+
+    /// The target of a redirect the client stopped at because it's off limits.
+    fn blocked_redirect(&self, response: &HttpResponse) -> Option<Url> {
+        if !(300..400).contains(&response.status) {
+            return None;
+        }
+        let target = Url::parse(&response.url).ok()?.join(response.header("location")?).ok()?;
+        self.check_url_allowed(&target, UrlKind::Navigation).is_err().then_some(target)
+    }
+
+    /// Whether the session may fetch `url` for `kind`. Every kind must be a
+    /// public http(s) address, unless configured otherwise, outside
+    /// `blocked_domains`. Pages, form submissions and downloads must also be
+    /// within `allowed_domains`; stylesheets needn't, since they are often
+    /// served from a CDN. A URL matching `blocked_patterns` is refused as a
+    /// stylesheet or download but loads as a page or form submission, with a
+    /// note in [`SpatialDom::warnings`].
+    pub fn check_url_allowed(&self, url: &Url, kind: UrlKind) -> Result<(), FetchError> {
+        check_url(&self.config, url, kind)
+    }
+
+    /// Note on `dom`, and the current page, that the page at `url` was loaded
+    /// although it matches a blocked pattern.
+    fn warn_blocked_pattern(&mut self, url: &Url, mut dom: SpatialDom) -> SpatialDom {
+        let Some(pattern) = matching_pattern(url, &self.config.blocked_patterns) else {
+            return dom;
+        };
+        let warning = format!("{url} matches blocked pattern {pattern:?}");
+        tracing::warn!(%warning, "blocked pattern");
+        if let Some(current) = self.current_dom.as_mut() {
+            current.warnings.push(warning.clone());
+        }
+        dom.warnings.push(warning);
+        dom
+    }
+
+    /// Send a request live, recording it if asked, or answer it from fixtures.
+    fn exchange(&self, request: &HttpRequest<'_>) -> Result<HttpResponse, FetchError> {
+        self.redirect_log.clear();
+        let Some(recorder) = &self.recorder else {
+            return send_http(&self.client, request);
+        };
+        if recorder.is_replay() {
+            let response = recorder.replay(request)?;
+            // Recordings leave cookies out, but hand-written fixtures may set them
+            if let Ok(url) = Url::parse(&response.url) {
+                for (name, value) in &response.headers {
+                    if name.eq_ignore_ascii_case("set-cookie") {
+                        self.cookie_jar.add_cookie_str(value, &url);
+                    }
+                }
+            }
+            return Ok(response);
+        }
+        let response = send_http(&self.client, request)?;
+        recorder.record(request, &response)?;
+        Ok(response)
     }
 
+    /// Send a form. GET submissions are retried under the session's policy;
+    /// POSTs only when no response arrived, so a form is never posted twice.
     fn submit_with_retry(
-        &self,
+        &mut self,
         target_url: &Url,
         method: &str,
         form_data: &[(String, String)],
-    ) -> Result<(String, String), FetchError> {
-        let mut attempts = 0usize;
-        let max_attempts = self.config.retry_attempts + 1;
-
-        while attempts < max_attempts {
-            let ua = self
-                .config
-                .retry_user_agents
-                .get(attempts)
-                .cloned()
-                .unwrap_or_else(|| self.config.user_agent.clone());
-
-            let response = if method == "post" {
-                self.client
-                    .post(target_url.as_str())
-                    .header(USER_AGENT, ua)
-                    .form(form_data)
-                    .send()
-            } else {
-                self.client
-                    .get(target_url.as_str())
-                    .header(USER_AGENT, ua)
-                    .query(form_data)
-                    .send()
-            }
-            .map_err(|e| FetchError::Network(e.to_string()))?;
+    ) -> Result<HttpResponse, FetchError> {
+        let is_post = method == "post";
+        let mut url = target_url.clone();
+        if !is_post && !form_data.is_empty() {
+            url.query_pairs_mut().extend_pairs(form_data);
+        }
 
-            let status = response.status();
-            let retry_after = response.headers().get(RETRY_AFTER).and_then(parse_retry_after);
-            if !status.is_success() {
-                if matches!(status.as_u16(), 403 | 408 | 429 | 500 | 502 | 503 | 504)
-                    && attempts + 1 < max_attempts
-                {
-                    attempts += 1;
-                    let delay = retry_delay_ms(self.config.retry_delay_ms, attempts - 1, retry_after);
-                    thread::sleep(Duration::from_millis(delay));
-                    continue;
-                }
-                return Err(FetchError::HttpError(status.as_u16()));
-            }
+        self.pace(target_url);
+        let mut attempts = Vec::new();
+        let result = retry::run(
+            &self.config.retry,
+            !is_post,
+            &mut attempts,
+            |n| {
+                let ua = super::retry_user_agent(&self.config.user_agent, &self.config.retry_user_agents, n);
+                self.send(&HttpRequest {
+                    method: if is_post { "POST" } else { "GET" },
+                    url: &url,
+                    form: if is_post { form_data } else { &[] },
+                    user_agent: Some(ua),
+                    headers: &self.config.extra_headers,
+                    max_bytes: self.config.max_response_bytes,
+                })
+            },
+            |ms| thread::sleep(Duration::from_millis(ms)),
+        );
+        self.note_attempts(target_url, attempts);
 
-            let new_url = response.url().to_string();
-            let html = read_response_text_limited(response, self.config.max_response_bytes)?;
-            if self.config.retry_on_blocked {
-                let lower = html.to_lowercase();
-                let blocked = lower.contains("captcha")
-                    || lower.contains("access denied")
-                    || lower.contains("verify you are a human")
-                    || lower.contains("unusual traffic")
-                    || lower.contains("cloudflare")
-                    || lower.contains("perimeterx")
-                    || lower.contains("datadome");
-                if blocked && attempts + 1 < max_attempts {
-                    attempts += 1;
-                    let delay = retry_delay_ms(self.config.retry_delay_ms, attempts - 1, None);
-                    thread::sleep(Duration::from_millis(delay));
-                    continue;
-                }
-            }
-            return Ok((new_url, html));
+        let response = result?;
+        if !response.is_success() {
+            return Err(FetchError::HttpError(response.status));
         }
-
-        Err(FetchError::Network("Retry attempts exhausted".to_string()))
+        Ok(response)
     }
 }
 
-fn retry_delay_ms(base_ms: u64, attempt: usize, retry_after_secs: Option<u64>) -> u64 {
-    let base = base_ms.max(50);
-    let exp = 1u64 << attempt.min(6);
-    let mut delay = base.saturating_mul(exp).min(30_000);
-    if let Some(secs) = retry_after_secs {
-        delay = delay.max(secs.saturating_mul(1000));
+/// Whether two URLs address the same page (everything but the fragment matches).
+fn same_page(a: &str, b: &str) -> bool {
+    match (Url::parse(a), Url::parse(b)) {
+        (Ok(a), Ok(b)) => a[..url::Position::AfterQuery] == b[..url::Position::AfterQuery],
+        _ => a == b,
     }
-    delay
 }
 
-fn parse_retry_after(value: &reqwest::header::HeaderValue) -> Option<u64> {
-    let s = value.to_str().ok()?;
-    s.trim().parse::<u64>().ok()
+/// Milliseconds since `started`, rounded to hundredths.
+fn elapsed_ms(started: Instant) -> f64 {
+    (started.elapsed().as_secs_f64() * 100_000.0).round() / 100.0
+}
+
+/// Whether clicking `el` submits its form.
+fn is_submit(el: &SpatialElement) -> bool {
+    el.tag == "button" || (el.tag == "input" && el.input_type.as_deref() == Some("submit"))
 }
 
 /// Search engine to use.
@@ -991,8 +3225,13 @@ pub enum SearchEngine {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResult {
     pub title: String,
+    /// The result's target, unwrapped from the engine's redirect and without
+    /// tracking parameters.
     pub url: String,
     pub snippet: String,
+    /// The link as it appeared on the results page.
+    #[serde(default)]
+    pub raw_url: String,
 }
 
 /// A search result paired with its fetched page (if successful).
@@ -1004,23 +3243,23 @@ pub struct SearchPage {
 
 /// Parse search results from a DOM tree. Public for testing.
 pub fn extract_search_results_from(dom: &crate::dom::DomNode) -> Vec<SearchResult> {
-    extract_ddg_results(dom)
+    extract_ddg_results(dom, &super::default_tracking_params())
 }
 
 /// Parse search results from a Google DOM tree. Public for testing.
 pub fn extract_google_results_from(dom: &crate::dom::DomNode) -> Vec<SearchResult> {
-    extract_google_results(dom)
+    extract_google_results(dom, &super::default_tracking_params())
 }
 
 // ---- DuckDuckGo parser ----
 
-fn extract_ddg_results(dom: &crate::dom::DomNode) -> Vec<SearchResult> {
+fn extract_ddg_results(dom: &crate::dom::DomNode, tracking: &[String]) -> Vec<SearchResult> {
     let mut results = Vec::new();
-    find_ddg_result_nodes(dom, &mut results);
+    find_ddg_result_nodes(dom, tracking, &mut results);
     results
 }
 
-fn find_ddg_result_nodes(node: &crate::dom::DomNode, results: &mut Vec<SearchResult>) {
+fn find_ddg_result_nodes(node: &crate::dom::DomNode, tracking: &[String], results: &mut Vec<SearchResult>) {
     let classes = node.get_attr("class").unwrap_or("");
     let is_result = classes.split_whitespace().any(|c| c == "result")
         && !classes.contains("result--ad");
@@ -1032,17 +3271,17 @@ fn find_ddg_result_nodes(node: &crate::dom::DomNode, results: &mut Vec<SearchRes
         extract_ddg_fields(node, &mut title, &mut url, &mut snippet);
 
         if !title.is_empty() || !url.is_empty() {
-            let resolved_url = decode_redirect_url(&url, "uddg").unwrap_or(url);
             results.push(SearchResult {
                 title: title.trim().to_string(),
-                url: resolved_url,
+                url: normalize_result_url(&url, tracking),
                 snippet: snippet.trim().to_string(),
+                raw_url: url,
             });
         }
     }
 
     for child in &node.children {
-        find_ddg_result_nodes(child, results);
+        find_ddg_result_nodes(child, tracking, results);
     }
 }
 
@@ -1080,16 +3319,16 @@ fn extract_ddg_fields(
 // Uses structural patterns (h3 for titles, parent a for URLs) rather than
 // fragile class names that Google changes every few months.
 
-fn extract_google_results(dom: &crate::dom::DomNode) -> Vec<SearchResult> {
+fn extract_google_results(dom: &crate::dom::DomNode, tracking: &[String]) -> Vec<SearchResult> {
     let mut results = Vec::new();
     let search_root = find_node_by_id(dom, "rso").unwrap_or(dom);
-    find_google_links(search_root, &mut results);
+    find_google_links(search_root, tracking, &mut results);
     results
 }
 
 /// Find result links: anchor tags that contain an h3 (Google's consistent pattern).
 /// For each match, extract title from h3, URL from href, snippet from sibling divs.
-fn find_google_links(node: &crate::dom::DomNode, results: &mut Vec<SearchResult>) {
+fn find_google_links(node: &crate::dom::DomNode, tracking: &[String], results: &mut Vec<SearchResult>) {
     if node.tag == "a" && has_h3_descendant(node) {
         if let Some(href) = node.get_attr("href") {
             if href.starts_with("http") || href.starts_with("/url?") {
@@ -1100,8 +3339,7 @@ fn find_google_links(node: &crate::dom::DomNode, results: &mut Vec<SearchResult>
 
                 let title = find_h3_text(node);
                 if !title.is_empty() {
-                    let resolved = decode_redirect_url(&href, "q")
-                        .unwrap_or_else(|| href.to_string());
+                    let resolved = normalize_result_url(href, tracking);
 
                     // Skip internal Google links
                     if resolved.contains("google.com/search") || resolved.starts_with("/search") {
@@ -1114,6 +3352,7 @@ fn find_google_links(node: &crate::dom::DomNode, results: &mut Vec<SearchResult>
                         title: title.trim().to_string(),
                         url: resolved,
                         snippet: String::new(),
+                        raw_url: href.to_string(),
                     });
                     return;
                 }
@@ -1122,7 +3361,7 @@ fn find_google_links(node: &crate::dom::DomNode, results: &mut Vec<SearchResult>
     }
 
     for child in &node.children {
-        find_google_links(child, results);
+        find_google_links(child, tracking, results);
     }
 
     // Second pass: fill in snippets by matching result URLs to nearby text
@@ -1201,33 +3440,3 @@ fn find_node_by_id<'a>(node: &'a crate::dom::DomNode, id: &str) -> Option<&'a cr
     }
     None
 }
-
-// ---- Shared helpers ----
-
-/// Decode a redirect URL by extracting a query parameter.
-/// Works for both DDG (?uddg=...) and Google (?q=...).
-fn decode_redirect_url(redirect_url: &str, param: &str) -> Option<String> {
-    // Normalize to a full URL for parsing
-    let full = if redirect_url.starts_with("//") {
-        format!("https:{}", redirect_url)
-    } else if redirect_url.starts_with('/') {
-        // Relative URL like /url?q=... — prepend a dummy base
-        format!("https://dummy.local{}", redirect_url)
-    } else {
-        redirect_url.to_string()
-    };
-
-    if let Ok(parsed) = Url::parse(&full) {
-        for (key, value) in parsed.query_pairs() {
-            if key == param {
-                return Some(value.into_owned());
-            }
-        }
-    }
-
-    if redirect_url.starts_with("http") {
-        Some(redirect_url.to_string())
-    } else {
-        None
-    }
-}
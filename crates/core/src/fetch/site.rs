@@ -0,0 +1,135 @@
+//! Per-site overrides of session settings, read from a `browsy.toml` file.
+//!
+//! Each table under `sites` is keyed by a domain suffix and sets any of the
+//! settings some sites need changed:
+//!
+//! ```toml
+//! [sites."example.com"]
+//! user_agent = "Mozilla/5.0 (iPhone; CPU iPhone OS 17_0 like Mac OS X)"
+//! viewport = [390, 844]
+//!
+//! [sites."docs.example.com"]
+//! fetch_css = false
+//! per_host_delay_ms = 500
+//! auto_consent = "reject"
+//! headers = { "X-Requested-With" = "browsy" }
+//! ```
+//!
+//! A suffix matches its domain and every subdomain of it. When several
+//! match a host, the longest one wins, so `docs.example.com` above gets its
+//! own settings and not the ones for `example.com`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::{ConsentChoice, FetchError, SessionConfig};
+
+/// Settings one site overrides. Settings left out keep the session's value.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SiteOverride {
+    pub user_agent: Option<String>,
+    pub fetch_css: Option<bool>,
+    /// Width and height.
+    pub viewport: Option<[f32; 2]>,
+    /// Sent with every page request and form submission, after the
+    /// session's own `extra_headers`.
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    pub per_host_delay_ms: Option<u64>,
+    pub auto_consent: Option<ConsentChoice>,
+}
+
+impl SiteOverride {
+    /// Write the settings this override sets into `config`.
+    pub fn apply(&self, config: &mut SessionConfig) {
+        if let Some(ref user_agent) = self.user_agent {
+            config.user_agent = user_agent.clone();
+        }
+        if let Some(fetch_css) = self.fetch_css {
+            config.fetch_css = fetch_css;
+        }
+        if let Some([width, height]) = self.viewport {
+            config.viewport_width = width;
+            config.viewport_height = height;
+        }
+        for (name, value) in &self.headers {
+            config.extra_headers.retain(|(n, _)| !n.eq_ignore_ascii_case(name));
+            config.extra_headers.push((name.clone(), value.clone()));
+        }
+        if let Some(delay) = self.per_host_delay_ms {
+            config.per_host_delay_ms = delay;
+        }
+        if let Some(choice) = self.auto_consent {
+            config.auto_dismiss_cookie_banners = Some(choice);
+        }
+    }
+}
+
+/// Put back into `config` every setting an override can change, from `base`.
+pub(super) fn restore(config: &mut SessionConfig, base: &SessionConfig) {
+    config.user_agent = base.user_agent.clone();
+    config.fetch_css = base.fetch_css;
+    config.viewport_width = base.viewport_width;
+    config.viewport_height = base.viewport_height;
+    config.extra_headers = base.extra_headers.clone();
+    config.per_host_delay_ms = base.per_host_delay_ms;
+    config.auto_dismiss_cookie_banners = base.auto_dismiss_cookie_banners;
+}
+
+/// The overrides of a `browsy.toml` file, by domain suffix.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SiteOverrides {
+    #[serde(default)]
+    sites: BTreeMap<String, SiteOverride>,
+}
+
+impl SiteOverrides {
+    /// Read a `browsy.toml` file. Errors name the file and the line and
+    /// column the problem is at.
+    pub fn load(path: &Path) -> Result<Self, FetchError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| FetchError::ActionError(format!("{}: {e}", path.display())))?;
+        Self::parse(&text).map_err(|e| FetchError::ActionError(format!("{}: {e}", path.display())))
+    }
+
+    /// Parse the text of a `browsy.toml` file.
+    pub fn parse(text: &str) -> Result<Self, toml::de::Error> {
+        let mut overrides: SiteOverrides = toml::from_str(text)?;
+        overrides.sites = overrides
+            .sites
+            .into_iter()
+            .map(|(suffix, site)| (normalize_suffix(&suffix), site))
+            .collect();
+        Ok(overrides)
+    }
+
+    /// The most specific override for `host` and the suffix it's keyed by.
+    pub fn for_host(&self, host: &str) -> Option<(&str, &SiteOverride)> {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        self.sites
+            .iter()
+            .filter(|(suffix, _)| {
+                host == **suffix
+                    || host.strip_suffix(suffix.as_str()).is_some_and(|sub| sub.ends_with('.'))
+            })
+            .max_by_key(|(suffix, _)| suffix.len())
+            .map(|(suffix, site)| (suffix.as_str(), site))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sites.is_empty()
+    }
+}
+
+/// `*.Example.com.` and `example.com` name the same sites.
+fn normalize_suffix(suffix: &str) -> String {
+    suffix
+        .trim()
+        .trim_start_matches("*.")
+        .trim_start_matches('.')
+        .trim_end_matches('.')
+        .to_ascii_lowercase()
+}
@@ -0,0 +1,119 @@
+//! Snapshots of a session's page, for reproducing misbehaviour later.
+//!
+//! A snapshot is a folder holding what's needed to replay a page offline:
+//!
+//! - `meta.json`: URL, title, when it was taken and why
+//! - `page.html`: the raw HTML the page was built from
+//! - `dom.json`: the Spatial DOM with form state overlaid
+//! - `form_state.json`: typed and selected values, checked and unchecked IDs
+//! - `audit.json`: the session's most recent audit events
+//!
+//! Values typed into sensitive fields are redacted in `dom.json` and
+//! `form_state.json` following `SessionConfig::redact_sensitive`.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use super::{AuditEvent, FetchError};
+use crate::output::SpatialDom;
+
+/// Audit events kept in a snapshot.
+pub(crate) const AUDIT_EVENTS: usize = 50;
+
+#[derive(Debug, Serialize)]
+pub(crate) struct FieldValue {
+    pub id: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub value: String,
+}
+
+/// Form state as the session holds it, ordered by element ID.
+#[derive(Debug, Default, Serialize)]
+pub(crate) struct FormState {
+    pub values: Vec<FieldValue>,
+    pub checked: Vec<u32>,
+    pub unchecked: Vec<u32>,
+}
+
+#[derive(Serialize)]
+struct Meta<'a> {
+    url: Option<&'a str>,
+    title: Option<&'a str>,
+    taken_at_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<&'a str>,
+}
+
+/// Everything that goes into one snapshot.
+pub(crate) struct Snapshot<'a> {
+    pub url: Option<&'a str>,
+    pub reason: Option<&'a str>,
+    pub html: Option<&'a str>,
+    pub dom: Option<SpatialDom>,
+    pub form_state: FormState,
+    pub audit: Vec<AuditEvent>,
+}
+
+impl Snapshot<'_> {
+    /// Write the snapshot into a new timestamped folder under `dir` and
+    /// return the folder's path.
+    pub fn write(&self, dir: &Path) -> Result<PathBuf, FetchError> {
+        let taken_at_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        let host = self
+            .url
+            .and_then(|u| url::Url::parse(u).ok())
+            .and_then(|u| u.host_str().map(str::to_string))
+            .unwrap_or_else(|| "blank".to_string());
+        let folder = new_folder(dir, &format!("{taken_at_ms}-{host}"))?;
+
+        let meta = Meta {
+            url: self.url,
+            title: self.dom.as_ref().map(|d| d.title.as_str()),
+            taken_at_ms,
+            reason: self.reason,
+        };
+        write_json(&folder.join("meta.json"), &meta)?;
+        if let Some(html) = self.html {
+            write_file(&folder.join("page.html"), html)?;
+        }
+        if let Some(dom) = &self.dom {
+            write_json(&folder.join("dom.json"), dom)?;
+        }
+        write_json(&folder.join("form_state.json"), &self.form_state)?;
+        write_json(&folder.join("audit.json"), &self.audit)?;
+        Ok(folder)
+    }
+}
+
+/// Create `dir/name`, adding `-2`, `-3`, ... when it already exists.
+fn new_folder(dir: &Path, name: &str) -> Result<PathBuf, FetchError> {
+    std::fs::create_dir_all(dir).map_err(|e| snapshot_error(dir, e))?;
+    let mut n = 1;
+    loop {
+        let folder = if n == 1 { dir.join(name) } else { dir.join(format!("{name}-{n}")) };
+        match std::fs::create_dir(&folder) {
+            Ok(()) => return Ok(folder),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => n += 1,
+            Err(e) => return Err(snapshot_error(&folder, e)),
+        }
+    }
+}
+
+fn write_json(path: &Path, value: &impl Serialize) -> Result<(), FetchError> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| FetchError::ActionError(format!("{}: {e}", path.display())))?;
+    write_file(path, &json)
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), FetchError> {
+    std::fs::write(path, contents).map_err(|e| snapshot_error(path, e))
+}
+
+fn snapshot_error(path: &Path, e: std::io::Error) -> FetchError {
+    FetchError::ActionError(format!("{}: {e}", path.display()))
+}
@@ -0,0 +1,78 @@
+//! A session's browsing state on disk, so a restarted process can pick up
+//! where the last one stopped.
+//!
+//! The state file is JSON holding the current URL, the history and the
+//! cookies, session cookies included. Pages are not saved: whoever restores
+//! the state fetches the current URL again when it needs the page.
+
+use std::io::Write;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::FetchError;
+
+/// What [`Session::state`](super::Session::state) saves and
+/// [`Session::restore_state`](super::Session::restore_state) brings back.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionState {
+    /// The current page's URL.
+    pub url: Option<String>,
+    /// Every URL navigated to, oldest first, ending with `url`.
+    pub history: Vec<String>,
+    /// Unexpired cookies, in cookie_store's format.
+    #[serde(default)]
+    pub(crate) cookies: Vec<cookie_store::Cookie<'static>>,
+}
+
+impl SessionState {
+    /// Read the state saved at `path`. `Ok(None)` when there is no file; an
+    /// error when it can't be read or isn't a saved state.
+    pub fn load(path: &Path) -> Result<Option<Self>, FetchError> {
+        let json = match std::fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(state_error(path, e)),
+        };
+        serde_json::from_str(&json).map(Some).map_err(|e| state_error(path, e))
+    }
+
+    /// Write the state to `path`, creating its folder. The file is replaced
+    /// in one step, so a crash mid-write leaves the previous state. It holds
+    /// session cookies, so on unix only its owner can read it.
+    pub fn save(&self, path: &Path) -> Result<(), FetchError> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(|e| state_error(dir, e))?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| state_error(path, e))?;
+        let partial = path.with_extension("json.partial");
+        // A leftover partial file would keep its mode
+        let _ = std::fs::remove_file(&partial);
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&partial).map_err(|e| state_error(&partial, e))?;
+        file.write_all(json.as_bytes()).map_err(|e| state_error(&partial, e))?;
+        drop(file);
+        std::fs::rename(&partial, path).map_err(|e| state_error(path, e))
+    }
+
+    /// Delete the state saved at `path`, if any.
+    pub fn remove(path: &Path) -> Result<(), FetchError> {
+        match std::fs::remove_file(path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(state_error(path, e)),
+        }
+    }
+
+    /// How many cookies the state holds.
+    pub fn cookie_count(&self) -> usize {
+        self.cookies.len()
+    }
+}
+
+fn state_error(path: &Path, e: impl std::fmt::Display) -> FetchError {
+    FetchError::ActionError(format!("{}: {e}", path.display()))
+}
@@ -0,0 +1,179 @@
+//! Watch a URL: poll it with a persistent Session and report deltas between snapshots.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use super::{FetchError, Session};
+use crate::output::{self, BlockedInfo, DeltaDom, DiffOptions, SpatialDom};
+
+/// First retry delay after a transient failure; doubles on each consecutive failure.
+const RETRY_BASE: Duration = Duration::from_secs(5);
+/// Upper bound on the delay between polls while rate-limited.
+const MAX_BACKOFF: Duration = Duration::from_secs(60 * 60);
+const SNAPSHOT_FILE: &str = "last.json";
+
+/// Configuration for a [`Watcher`].
+#[derive(Debug, Clone)]
+pub struct WatchConfig {
+    /// Delay between successful polls.
+    pub interval: Duration,
+    /// Stop after this many polls (successful or not). `None` runs forever.
+    pub max_iterations: Option<u64>,
+    /// Directory holding the last snapshot so restarts resume from it.
+    pub state_dir: Option<PathBuf>,
+    /// How elements are matched between snapshots.
+    pub diff: DiffOptions,
+}
+
+impl Default for WatchConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(300),
+            max_iterations: None,
+            state_dir: None,
+            diff: DiffOptions::default(),
+        }
+    }
+}
+
+/// What happened on one poll.
+#[derive(Debug)]
+pub enum WatchEvent {
+    /// First snapshot; nothing to compare against yet.
+    Baseline { els: usize },
+    /// The page matches the previous snapshot.
+    Unchanged,
+    /// The page changed; the delta is non-empty.
+    Changed(DeltaDom),
+    /// The poll failed or was blocked; the previous snapshot is kept.
+    Failed { error: String, retry_in: Duration },
+}
+
+impl WatchEvent {
+    /// Whether this event should trigger a notification.
+    pub fn should_alert(&self) -> bool {
+        matches!(self, WatchEvent::Changed(_))
+    }
+}
+
+/// Polls a URL and diffs each snapshot against the previous one.
+pub struct Watcher {
+    url: String,
+    config: WatchConfig,
+    previous: Option<SpatialDom>,
+    failures: u32,
+}
+
+impl Watcher {
+    /// Create a watcher, resuming from the snapshot in `state_dir` if present.
+    pub fn new(url: &str, config: WatchConfig) -> Self {
+        let previous = config
+            .state_dir
+            .as_ref()
+            .and_then(|dir| std::fs::read_to_string(dir.join(SNAPSHOT_FILE)).ok())
+            .and_then(|json| SpatialDom::from_json(&json).ok());
+        Self {
+            url: url.to_string(),
+            config,
+            previous,
+            failures: 0,
+        }
+    }
+
+    /// The last successful snapshot, if any.
+    pub fn previous(&self) -> Option<&SpatialDom> {
+        self.previous.as_ref()
+    }
+
+    /// Fetch the page once and compare it with the previous snapshot.
+    /// Invalid or disallowed URLs are returned as errors; transient failures
+    /// become [`WatchEvent::Failed`] with a backoff delay.
+    pub fn poll(&mut self, session: &mut Session) -> Result<WatchEvent, FetchError> {
+        match session.goto(&self.url) {
+            Ok(dom) => Ok(self.observe(dom)),
+            Err(e @ (FetchError::InvalidUrl(_) | FetchError::BlockedUrl(_))) => Err(e),
+            Err(e) => Ok(self.fail(e.to_string(), None)),
+        }
+    }
+
+    /// Compare a freshly fetched page with the previous snapshot and persist it.
+    /// Blocked pages are treated as failures so they never replace a good snapshot.
+    pub fn observe(&mut self, dom: SpatialDom) -> WatchEvent {
+        if let Some(blocked) = dom.blocked.clone() {
+            return self.fail(format!("blocked ({})", blocked.reason), Some(&blocked));
+        }
+        self.failures = 0;
+        let event = match self.previous {
+            None => WatchEvent::Baseline { els: dom.els.len() },
+            Some(ref previous) => {
+                let delta = output::diff_with_options(previous, &dom, &self.config.diff);
+                if delta.changed.is_empty() && delta.removed.is_empty() {
+                    WatchEvent::Unchanged
+                } else {
+                    WatchEvent::Changed(delta)
+                }
+            }
+        };
+        self.persist(&dom);
+        self.previous = Some(dom);
+        event
+    }
+
+    /// Delay before the next poll after `event`.
+    pub fn next_delay(&self, event: &WatchEvent) -> Duration {
+        match event {
+            WatchEvent::Failed { retry_in, .. } => *retry_in,
+            _ => self.config.interval,
+        }
+    }
+
+    /// Poll until `max_iterations` is reached, sleeping between polls.
+    pub fn run(
+        &mut self,
+        session: &mut Session,
+        mut on_event: impl FnMut(&WatchEvent),
+    ) -> Result<(), FetchError> {
+        let mut iteration = 0u64;
+        loop {
+            let event = self.poll(session)?;
+            on_event(&event);
+            iteration += 1;
+            if self.config.max_iterations.is_some_and(|max| iteration >= max) {
+                return Ok(());
+            }
+            std::thread::sleep(self.next_delay(&event));
+        }
+    }
+
+    fn fail(&mut self, error: String, blocked: Option<&BlockedInfo>) -> WatchEvent {
+        self.failures += 1;
+        let retry_in = backoff_delay(self.failures, self.config.interval, blocked);
+        WatchEvent::Failed { error, retry_in }
+    }
+
+    fn persist(&self, dom: &SpatialDom) {
+        let Some(ref dir) = self.config.state_dir else {
+            return;
+        };
+        if std::fs::create_dir_all(dir).is_ok() {
+            if let Ok(json) = serde_json::to_string(dom) {
+                let _ = std::fs::write(dir.join(SNAPSHOT_FILE), json);
+            }
+        }
+    }
+}
+
+/// Delay before retrying after `failures` consecutive failures.
+///
+/// Rate-limited responses slow down beyond the regular interval (doubling up
+/// to an hour); other transient errors retry sooner, doubling from a few
+/// seconds but never waiting longer than the interval.
+pub fn backoff_delay(failures: u32, interval: Duration, blocked: Option<&BlockedInfo>) -> Duration {
+    let factor = 1u32 << failures.saturating_sub(1).min(10);
+    let rate_limited = blocked.is_some_and(|b| b.signals.iter().any(|s| s == "rate_limit"));
+    if rate_limited {
+        interval.saturating_mul(factor * 2).min(MAX_BACKOFF.max(interval))
+    } else {
+        (RETRY_BASE * factor).min(interval)
+    }
+}
@@ -1,7 +1,7 @@
 //! Behavior detection — infers interactive patterns from HTML attributes.
 //!
 //! This module does NOT execute JavaScript. It detects common UI patterns
-//! from HTML attributes (onclick, data-toggle, aria-controls, role="tab")
+//! from HTML attributes (onclick, data-toggle, data-url, aria-controls, role="tab")
 //! and reports what interactions are available. Combined with browsy's
 //! hidden content exposure (where display:none elements are included with
 //! `hidden: true`), this gives agents full visibility into page content
@@ -9,6 +9,7 @@
 
 use crate::dom::{DomNode, NodeType};
 use serde::Serialize;
+use std::collections::HashMap;
 
 /// A detected interactive behavior on the page.
 #[derive(Debug, Clone, Serialize)]
@@ -49,7 +50,30 @@ pub enum JsAction {
     },
 }
 
+impl JsAction {
+    /// The `id` attributes of the elements [`apply_action`] changes, which
+    /// only ever gain or lose a style, class or `hidden` attribute. Empty
+    /// for actions that leave the DOM alone.
+    pub fn target_ids(&self) -> Vec<&str> {
+        match self {
+            JsAction::ToggleVisibility { target } | JsAction::ToggleClass { target, .. } => {
+                vec![target.strip_prefix('#').unwrap_or(target)]
+            }
+            JsAction::TabSwitch { show_target, hide_targets } => std::iter::once(show_target)
+                .chain(hide_targets)
+                .map(String::as_str)
+                .collect(),
+            JsAction::FormSubmit { .. } | JsAction::Navigate { .. } => Vec::new(),
+        }
+    }
+}
+
 /// Analyze the DOM for common JS patterns and return detected behaviors.
+///
+/// Trigger IDs here come from counting the nodes the Spatial DOM would
+/// likely emit, without layout, so they can drift from a rendered page's.
+/// [`SpatialDom::behaviors`](crate::output::SpatialDom::behaviors) carries
+/// the behaviors of the elements a page actually emitted.
 pub fn detect_behaviors(dom: &DomNode) -> Vec<JsBehavior> {
     let mut behaviors = Vec::new();
     let mut id_counter = 1u32;
@@ -65,82 +89,92 @@ fn detect_behaviors_recursive(
     id_counter: &mut u32,
 ) {
     if node.node_type == NodeType::Element {
-        // Check for onclick handlers
-        if let Some(onclick) = node.get_attr("onclick") {
-            if let Some(action) = parse_onclick(onclick) {
-                behaviors.push(JsBehavior {
-                    trigger_id: *id_counter,
-                    action,
-                });
-            }
+        for action in node_actions(&node.attributes) {
+            behaviors.push(JsBehavior {
+                trigger_id: *id_counter,
+                action,
+            });
         }
 
-        // Check for data-toggle patterns (Bootstrap-style)
-        if let Some(toggle) = node.get_attr("data-toggle") {
-            if let Some(target) = node.get_attr("data-target")
-                .or_else(|| node.get_attr("href"))
-            {
-                let action = match toggle {
-                    "collapse" | "dropdown" | "modal" => JsAction::ToggleVisibility {
-                        target: target.to_string(),
-                    },
-                    "tab" | "pill" => {
-                        if let Some(target_id) = target.strip_prefix('#') {
-                            JsAction::TabSwitch {
-                                show_target: target_id.to_string(),
-                                hide_targets: Vec::new(),
-                            }
-                        } else {
-                            JsAction::ToggleVisibility {
-                                target: target.to_string(),
-                            }
-                        }
-                    }
-                    _ => JsAction::ToggleVisibility {
-                        target: target.to_string(),
-                    },
-                };
-                behaviors.push(JsBehavior {
-                    trigger_id: *id_counter,
-                    action,
-                });
-            }
+        // Increment ID for elements that would be emitted in the spatial DOM
+        if should_emit_node(node) {
+            *id_counter += 1;
         }
+    }
 
-        // Check for aria-controls (accessibility pattern for toggles)
-        if let Some(controls) = node.get_attr("aria-controls") {
-            if node.get_attr("aria-expanded").is_some() {
-                behaviors.push(JsBehavior {
-                    trigger_id: *id_counter,
-                    action: JsAction::ToggleVisibility {
-                        target: format!("#{}", controls),
-                    },
-                });
-            }
+    for child in &node.children {
+        detect_behaviors_recursive(child, behaviors, id_counter);
+    }
+}
+
+/// The actions an element with these attributes triggers when clicked.
+pub(crate) fn node_actions(attributes: &HashMap<String, String>) -> Vec<JsAction> {
+    let attr = |name: &str| attributes.get(name).map(String::as_str);
+    let mut actions = Vec::new();
+
+    // Check for onclick handlers
+    let onclick_action = attr("onclick").and_then(parse_onclick);
+    let has_onclick_action = onclick_action.is_some();
+    if let Some(action) = onclick_action {
+        actions.push(action);
+    }
+
+    // Check for data-url / data-href on non-links ("Load more" buttons, clickable rows)
+    if !has_onclick_action && attr("href").is_none() {
+        if let Some(url) = attr("data-url").or_else(|| attr("data-href")) {
+            actions.push(JsAction::Navigate {
+                url: url.trim().to_string(),
+            });
         }
+    }
 
-        // Check for role="tab" with aria-controls
-        if node.get_attr("role") == Some("tab") {
-            if let Some(controls) = node.get_attr("aria-controls") {
-                behaviors.push(JsBehavior {
-                    trigger_id: *id_counter,
-                    action: JsAction::TabSwitch {
-                        show_target: controls.to_string(),
-                        hide_targets: Vec::new(),
-                    },
-                });
-            }
+    // Check for data-toggle patterns (Bootstrap-style)
+    if let Some(toggle) = attr("data-toggle") {
+        if let Some(target) = attr("data-target").or_else(|| attr("href")) {
+            let action = match toggle {
+                "collapse" | "dropdown" | "modal" => JsAction::ToggleVisibility {
+                    target: target.to_string(),
+                },
+                "tab" | "pill" => {
+                    if let Some(target_id) = target.strip_prefix('#') {
+                        JsAction::TabSwitch {
+                            show_target: target_id.to_string(),
+                            hide_targets: Vec::new(),
+                        }
+                    } else {
+                        JsAction::ToggleVisibility {
+                            target: target.to_string(),
+                        }
+                    }
+                }
+                _ => JsAction::ToggleVisibility {
+                    target: target.to_string(),
+                },
+            };
+            actions.push(action);
         }
+    }
 
-        // Increment ID for elements that would be emitted in the spatial DOM
-        if should_emit_node(node) {
-            *id_counter += 1;
+    // Check for aria-controls (accessibility pattern for toggles)
+    if let Some(controls) = attr("aria-controls") {
+        if attr("aria-expanded").is_some() {
+            actions.push(JsAction::ToggleVisibility {
+                target: format!("#{}", controls),
+            });
         }
     }
 
-    for child in &node.children {
-        detect_behaviors_recursive(child, behaviors, id_counter);
+    // Check for role="tab" with aria-controls
+    if attr("role") == Some("tab") {
+        if let Some(controls) = attr("aria-controls") {
+            actions.push(JsAction::TabSwitch {
+                show_target: controls.to_string(),
+                hide_targets: Vec::new(),
+            });
+        }
     }
+
+    actions
 }
 
 /// Parse an onclick attribute value into a JsAction.
@@ -9,11 +9,22 @@ pub struct LayoutNode {
     pub tag: String,
     pub attributes: std::collections::HashMap<String, String>,
     pub text: String,
+    /// All text inside the node, trimmed and joined by spaces; a textarea's
+    /// text as written, since it is the field's value.
     pub text_content: String,
     pub node_type: NodeType,
     pub style: css::LayoutStyle,
     pub bounds: Bounds,
+    /// Intersection of the overflow-clipping boxes of all ancestors, or `None`
+    /// when no ancestor clips. Content outside it is not visible without
+    /// scrolling its container.
+    pub clip: Option<Bounds>,
+    /// Whether this node or an ancestor is `position: fixed` or `sticky`, so it
+    /// stays in view while the page scrolls.
+    pub pinned: bool,
     pub children: Vec<LayoutNode>,
+    /// Line and column of the element's start tag, if positions were tracked.
+    pub src: Option<[u32; 2]>,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -24,29 +35,115 @@ pub struct Bounds {
     pub height: f32,
 }
 
+impl Bounds {
+    /// The overlapping area of two rectangles; zero-sized when they don't overlap.
+    pub fn intersect(&self, other: &Bounds) -> Bounds {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bottom = (self.y + self.height).min(other.y + other.height);
+        Bounds {
+            x,
+            y,
+            width: (right - x).max(0.0),
+            height: (bottom - y).max(0.0),
+        }
+    }
+}
+
 /// Compute layout for the entire styled tree using taffy.
 pub fn compute_layout(
     root: &StyledNode,
     viewport_width: f32,
     viewport_height: f32,
 ) -> LayoutNode {
-    let mut tree = TaffyTree::new();
+    LayoutTree::new(root, viewport_width, viewport_height).layout(root)
+}
 
-    // Build taffy tree from styled nodes
-    let root_taffy = build_taffy_tree(&mut tree, root, viewport_width);
+/// The taffy tree built for a styled tree, kept so nodes whose styles change
+/// can be rebuilt in place. Laying it out again only recomputes what those
+/// nodes affect; taffy keeps the results of untouched subtrees.
+pub struct LayoutTree {
+    tree: TaffyTree,
+    root: NodeId,
+    viewport_width: f32,
+    viewport_height: f32,
+}
 
-    // Compute layout
-    tree.compute_layout(
-        root_taffy,
-        Size {
-            width: AvailableSpace::Definite(viewport_width),
-            height: AvailableSpace::Definite(viewport_height),
-        },
-    )
-    .expect("layout computation failed");
+impl LayoutTree {
+    pub fn new(root: &StyledNode, viewport_width: f32, viewport_height: f32) -> Self {
+        let mut tree = TaffyTree::new();
+        let root = build_taffy_tree(&mut tree, root, viewport_width);
+        Self { tree, root, viewport_width, viewport_height }
+    }
+
+    /// Rebuild the node at `path` (child indices from the root) and its
+    /// descendants from `root`, the styled tree this was built from with
+    /// that subtree restyled. Returns false, changing nothing, when there
+    /// is no node at `path`.
+    pub fn rebuild(&mut self, root: &StyledNode, path: &[usize]) -> bool {
+        let mut node = root;
+        let mut taffy_node = self.root;
+        let mut parent = None;
+        let mut parent_width = self.viewport_width;
+        for &i in path {
+            let (Some(child), Ok(taffy_child)) = (node.children.get(i), self.tree.child_at_index(taffy_node, i)) else {
+                return false;
+            };
+            parent_width = if node.style.display == css::Display::None {
+                0.0
+            } else {
+                child_parent_width(&node.style, parent_width)
+            };
+            parent = Some((taffy_node, i));
+            node = child;
+            taffy_node = taffy_child;
+        }
+
+        let rebuilt = build_taffy_tree(&mut self.tree, node, parent_width);
+        match parent {
+            Some((parent, i)) => {
+                self.tree
+                    .replace_child_at_index(parent, i, rebuilt)
+                    .expect("taffy: failed to replace rebuilt node");
+            }
+            None => self.root = rebuilt,
+        }
+        remove_subtree(&mut self.tree, taffy_node);
+        true
+    }
 
-    // Extract results back into our tree
-    extract_layout(&tree, root_taffy, root, 0.0, 0.0)
+    /// Lay the tree out and read the boxes back onto `root`, the styled tree
+    /// it was built from.
+    pub fn layout(&mut self, root: &StyledNode) -> LayoutNode {
+        self.tree.compute_layout(
+            self.root,
+            Size {
+                width: AvailableSpace::Definite(self.viewport_width),
+                height: AvailableSpace::Definite(self.viewport_height),
+            },
+        )
+        .expect("layout computation failed");
+
+        extract_layout(&self.tree, self.root, root, 0.0, 0.0, None, false)
+    }
+}
+
+fn remove_subtree(tree: &mut TaffyTree, node: NodeId) {
+    for child in tree.children(node).unwrap_or_default() {
+        remove_subtree(tree, child);
+    }
+    let _ = tree.remove(node);
+}
+
+/// The width a node gives its children to resolve calc() and percentages.
+fn child_parent_width(style: &css::LayoutStyle, parent_width: f32) -> f32 {
+    match &style.width {
+        css::Dimension::Px(w) => *w,
+        css::Dimension::Percent(p) => parent_width * p,
+        css::Dimension::Calc(pct, px) => pct * parent_width + px,
+        css::Dimension::Auto => parent_width,
+    }
 }
 
 fn build_taffy_tree(
@@ -102,12 +199,7 @@ fn build_taffy_tree(
             let line_height = node.style.font_size * node.style.line_height;
 
             // Determine available width for wrapping
-            let avail_width = match &node.style.width {
-                css::Dimension::Px(w) => *w,
-                css::Dimension::Percent(p) => parent_width * p,
-                css::Dimension::Calc(pct, px) => pct * parent_width + px,
-                css::Dimension::Auto => parent_width,
-            };
+            let avail_width = child_parent_width(&node.style, parent_width);
 
             let (wrapped_width, wrapped_height) = if text_width > avail_width && avail_width > 0.0 {
                 let lines = (text_width / avail_width).ceil();
@@ -128,19 +220,12 @@ fn build_taffy_tree(
         }
     }
 
-    // Compute the width this node provides to its children for calc/% resolution
-    let child_parent_width = match &node.style.width {
-        css::Dimension::Px(w) => *w,
-        css::Dimension::Percent(p) => parent_width * p,
-        css::Dimension::Calc(pct, px) => pct * parent_width + px,
-        css::Dimension::Auto => parent_width,
-    };
-
-    // Build children
+    // Build children, which resolve calc() and percentages against this node
+    let child_width = child_parent_width(&node.style, parent_width);
     let child_ids: Vec<NodeId> = node
         .children
         .iter()
-        .map(|c| build_taffy_tree(tree, c, child_parent_width))
+        .map(|c| build_taffy_tree(tree, c, child_width))
         .collect();
 
     tree.new_with_children(taffy_style, &child_ids).expect("taffy: failed to create node with children")
@@ -207,7 +292,8 @@ fn to_taffy_style(style: &css::LayoutStyle, parent_width: f32) -> Style {
             css::Display::Inline | css::Display::InlineBlock => Display::Flex,
         },
         position: match style.position {
-            css::Position::Relative | css::Position::Static => Position::Relative,
+            // Sticky boxes keep their place in the flow until scrolled past
+            css::Position::Relative | css::Position::Static | css::Position::Sticky => Position::Relative,
             css::Position::Absolute | css::Position::Fixed => Position::Absolute,
         },
         size: Size {
@@ -399,17 +485,45 @@ fn collect_all_text_recursive(node: &StyledNode, out: &mut String) {
     }
 }
 
+/// A textarea's text as written, line breaks included. Its children get no
+/// layout of their own, so this is the only place their text is kept.
+fn textarea_text(node: &StyledNode) -> String {
+    node.children
+        .iter()
+        .filter(|child| child.node_type == NodeType::Text)
+        .map(|child| child.text.as_str())
+        .collect()
+}
+
 fn extract_layout(
     tree: &TaffyTree,
     node_id: NodeId,
     styled: &StyledNode,
     parent_x: f32,
     parent_y: f32,
+    clip: Option<&Bounds>,
+    parent_pinned: bool,
 ) -> LayoutNode {
     let taffy_layout = tree.layout(node_id).expect("node should have layout");
 
     let x = parent_x + taffy_layout.location.x;
     let y = parent_y + taffy_layout.location.y;
+    let bounds = Bounds {
+        x,
+        y,
+        width: taffy_layout.size.width,
+        height: taffy_layout.size.height,
+    };
+
+    // Children are clipped to this box too when it hides or scrolls overflow
+    let child_clip = if clips_overflow(styled) {
+        Some(clip.map_or_else(|| bounds.clone(), |c| c.intersect(&bounds)))
+    } else {
+        clip.cloned()
+    };
+
+    let pinned = parent_pinned
+        || matches!(styled.style.position, css::Position::Fixed | css::Position::Sticky);
 
     let taffy_children: Vec<NodeId> = tree.children(node_id).unwrap_or_default();
 
@@ -417,22 +531,31 @@ fn extract_layout(
         .children
         .iter()
         .zip(taffy_children.iter())
-        .map(|(styled_child, &taffy_child)| extract_layout(tree, taffy_child, styled_child, x, y))
+        .map(|(styled_child, &taffy_child)| {
+            extract_layout(tree, taffy_child, styled_child, x, y, child_clip.as_ref(), pinned)
+        })
         .collect();
 
     LayoutNode {
         tag: styled.tag.clone(),
         attributes: styled.attributes.clone(),
         text: styled.text.clone(),
-        text_content: collect_all_text(styled),
+        text_content: if styled.tag == "textarea" { textarea_text(styled) } else { collect_all_text(styled) },
         node_type: styled.node_type.clone(),
         style: styled.style.clone(),
-        bounds: Bounds {
-            x,
-            y,
-            width: taffy_layout.size.width,
-            height: taffy_layout.size.height,
-        },
+        bounds,
+        clip: clip.cloned(),
+        pinned,
         children,
+        src: styled.src,
     }
 }
+
+/// Whether a node clips its descendants to its own box. Overflow on `<html>`
+/// and `<body>` applies to the viewport, which is handled by the fold instead.
+fn clips_overflow(node: &StyledNode) -> bool {
+    node.node_type == NodeType::Element
+        && node.style.display != css::Display::None
+        && node.style.overflow != css::Overflow::Visible
+        && !matches!(node.tag.as_str(), "html" | "body")
+}
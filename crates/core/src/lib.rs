@@ -5,14 +5,28 @@ pub mod output;
 pub mod js;
 #[cfg(feature = "fetch")]
 pub mod fetch;
+#[cfg(feature = "pdf")]
+pub mod pdf;
 
 use output::SpatialDom;
 
 /// Parse an HTML string and compute the Spatial DOM.
 /// This is the primary entry point for browsy-core.
 pub fn parse(html: &str, viewport_width: f32, viewport_height: f32) -> SpatialDom {
-    let dom_tree = dom::parse_html(html);
-    let styled = css::compute_styles_with_viewport(&dom_tree, viewport_width, viewport_height);
-    let laid_out = layout::compute_layout(&styled, viewport_width, viewport_height);
-    output::generate_spatial_dom(&laid_out, viewport_width, viewport_height)
+    layout_dom(&dom::parse_html(html), viewport_width, viewport_height)
+}
+
+/// Like [`parse`], but each element's `src` holds the line and column of its
+/// start tag in `html`.
+pub fn parse_with_positions(html: &str, viewport_width: f32, viewport_height: f32) -> SpatialDom {
+    layout_dom(&dom::parse_html_with_positions(html), viewport_width, viewport_height)
+}
+
+fn layout_dom(dom_tree: &dom::DomNode, viewport_width: f32, viewport_height: f32) -> SpatialDom {
+    let layout_width = css::layout_viewport_width(dom_tree, viewport_width);
+    let styled = css::compute_styles_with_viewport(dom_tree, layout_width, viewport_height);
+    let laid_out = layout::compute_layout(&styled, layout_width, viewport_height);
+    let mut spatial = output::generate_spatial_dom(&laid_out, viewport_width, viewport_height);
+    spatial.set_layout_width(layout_width);
+    spatial
 }
@@ -0,0 +1,91 @@
+//! The last pass over a page's suggested actions, so the same page always
+//! gets the same list: one action per set of elements, in a fixed order,
+//! and no longer than an agent needs.
+
+use super::SuggestedAction;
+
+/// Most suggested actions a page gets. The least specific are dropped first.
+pub const MAX_SUGGESTED_ACTIONS: usize = 8;
+
+impl SuggestedAction {
+    /// How sure the detection is, from 0 to 1; `None` when nothing about it
+    /// was guessed. Hedged actions come after the confident ones.
+    pub fn confidence(&self) -> Option<f32> {
+        match self {
+            SuggestedAction::EnterCode { confidence, .. } => *confidence,
+            _ => None,
+        }
+    }
+
+    /// Where the action goes in the list: auth, then CAPTCHAs and blocks,
+    /// consent, navigation helpers, and generic forms and downloads. Within
+    /// a group the more specific action comes first.
+    fn priority(&self) -> u8 {
+        match self {
+            SuggestedAction::Login { .. } => 0,
+            SuggestedAction::Register { .. } => 1,
+            SuggestedAction::EnterCode { .. } => 2,
+            SuggestedAction::CaptchaChallenge { .. } => 3,
+            SuggestedAction::RetryGuidance { .. } => 4,
+            SuggestedAction::Consent { .. } => 5,
+            SuggestedAction::CookieConsent { .. } => 6,
+            SuggestedAction::Search { .. } => 7,
+            SuggestedAction::Paginate { .. } => 8,
+            SuggestedAction::LoadMore { .. } => 9,
+            SuggestedAction::SelectFromList { .. } => 10,
+            SuggestedAction::Contact { .. } => 11,
+            SuggestedAction::Download { .. } => 12,
+            SuggestedAction::FillForm { .. } => 13,
+        }
+    }
+
+    /// What two actions must share to be duplicates: their kind and the
+    /// elements that carry them out, sorted. The form actions are one kind
+    /// keyed by their submit button, so a Login and a FillForm on the same
+    /// form are duplicates. `None` for actions on no element.
+    fn duplicate_key(&self) -> Option<(&'static str, Vec<u32>)> {
+        let (kind, mut ids) = match self {
+            SuggestedAction::Login { submit_id, .. }
+            | SuggestedAction::Register { submit_id, .. }
+            | SuggestedAction::EnterCode { submit_id, .. }
+            | SuggestedAction::Contact { submit_id, .. }
+            | SuggestedAction::FillForm { submit_id, .. } => ("form", vec![*submit_id]),
+            SuggestedAction::Search { input_id, .. } => ("search", vec![*input_id]),
+            SuggestedAction::Consent { approve_ids, deny_ids } => {
+                ("consent", approve_ids.iter().chain(deny_ids).copied().collect())
+            }
+            SuggestedAction::CookieConsent { accept_id, reject_id } => {
+                ("cookie_consent", std::iter::once(*accept_id).chain(*reject_id).collect())
+            }
+            SuggestedAction::Paginate { next_id, prev_id } => {
+                ("paginate", next_id.iter().chain(prev_id).copied().collect())
+            }
+            SuggestedAction::LoadMore { button_id } => ("load_more", vec![*button_id]),
+            SuggestedAction::SelectFromList { items } => ("select_from_list", items.clone()),
+            SuggestedAction::Download { items } => ("download", items.iter().map(|item| item.id).collect()),
+            SuggestedAction::CaptchaChallenge { .. } | SuggestedAction::RetryGuidance { .. } => return None,
+        };
+        ids.sort_unstable();
+        (!ids.is_empty()).then_some((kind, ids))
+    }
+}
+
+/// `actions` without those duplicating a more specific one (Login or
+/// Contact over FillForm, the first of two Searches), in priority
+/// order with hedged actions last, and at most [`MAX_SUGGESTED_ACTIONS`].
+pub(super) fn normalize(mut actions: Vec<SuggestedAction>) -> Vec<SuggestedAction> {
+    actions.sort_by_key(SuggestedAction::priority);
+    let mut seen = Vec::new();
+    actions.retain(|action| match action.duplicate_key() {
+        Some(key) if seen.contains(&key) => false,
+        Some(key) => {
+            seen.push(key);
+            true
+        }
+        None => true,
+    });
+    let confidence = |a: &SuggestedAction| a.confidence().unwrap_or(1.0);
+    actions.sort_by(|a, b| confidence(b).total_cmp(&confidence(a)).then(a.priority().cmp(&b.priority())));
+    actions.truncate(MAX_SUGGESTED_ACTIONS);
+    actions
+}
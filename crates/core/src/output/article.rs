@@ -0,0 +1,168 @@
+//! Reading article dates and bylines out of attribute values and text.
+//!
+//! Dates are normalized to ISO 8601: machine-readable values keep their time
+//! and offset (`2026-03-03T09:14:00Z`), written-out dates become a plain
+//! date (`March 3, 2026` → `2026-03-03`). Relative dates ("3 hours ago")
+//! are never converted, since what they're relative to isn't known.
+
+const MONTHS: [&str; 12] = [
+    "january", "february", "march", "april", "may", "june", "july", "august", "september",
+    "october", "november", "december",
+];
+
+const TIME_UNITS: &[&str] = &[
+    "second", "sec", "minute", "min", "hour", "hr", "day", "week", "month", "year", "yr",
+];
+
+/// Name particles allowed in lowercase inside a byline.
+const NAME_PARTICLES: &[&str] = &["and", "&", "de", "da", "del", "der", "van", "von", "la", "le"];
+
+/// Normalize an ISO 8601 or written-out date. Returns `None` for anything
+/// that isn't recognizably a date.
+pub(crate) fn normalize_date(text: &str) -> Option<String> {
+    let text = text.trim();
+    normalize_iso(text).or_else(|| normalize_written(text))
+}
+
+/// `YYYY-MM-DD`, optionally followed by `T` or a space, `HH:MM[:SS[.fff]]`
+/// and a `Z` or `±HH[:MM]` offset. Fractional seconds are dropped.
+fn normalize_iso(text: &str) -> Option<String> {
+    let b = text.as_bytes();
+    let digits = |range: std::ops::Range<usize>| b[range].iter().all(u8::is_ascii_digit);
+    if b.len() < 10 || !digits(0..4) || b[4] != b'-' || !digits(5..7) || b[7] != b'-' || !digits(8..10) {
+        return None;
+    }
+    let month: u32 = text[5..7].parse().ok()?;
+    let day: u32 = text[8..10].parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    let date = &text[..10];
+    let Some(time) = text[10..].strip_prefix(['T', 't', ' ']) else {
+        return Some(date.to_string());
+    };
+
+    let clock_len = time.find(|c: char| !(c.is_ascii_digit() || c == ':' || c == '.')).unwrap_or(time.len());
+    let whole_seconds = time[..clock_len].split('.').next().unwrap_or_default();
+    let parts: Vec<&str> = whole_seconds.split(':').collect();
+    if !(2..=3).contains(&parts.len()) || parts.iter().any(|p| p.len() != 2 || !p.bytes().all(|c| c.is_ascii_digit())) {
+        return Some(date.to_string());
+    }
+    let seconds = parts.get(2).copied().unwrap_or("00");
+
+    let zone = time[clock_len..].trim();
+    let offset = match zone.chars().next() {
+        Some('Z' | 'z') => "Z".to_string(),
+        Some(sign @ ('+' | '-')) => {
+            let digits: String = zone[1..].chars().filter(char::is_ascii_digit).collect();
+            match digits.len() {
+                2 => format!("{sign}{digits}:00"),
+                4 => format!("{sign}{}:{}", &digits[..2], &digits[2..]),
+                _ => String::new(),
+            }
+        }
+        _ => String::new(),
+    };
+    Some(format!("{date}T{}:{}:{seconds}{offset}", parts[0], parts[1]))
+}
+
+/// Dates written with a month name, in any order: "March 3, 2026",
+/// "3rd Mar 2026", "Tue, 03 Mar 2026 09:14:00 GMT".
+fn normalize_written(text: &str) -> Option<String> {
+    if text.chars().count() > 40 {
+        return None;
+    }
+    let (mut year, mut month, mut day) = (None, None, None);
+    for word in text.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+        let lower = word.to_lowercase();
+        if lower.chars().all(char::is_alphabetic) {
+            if month.is_none() && lower.len() >= 3 {
+                month = MONTHS.iter().position(|m| m.starts_with(lower.as_str()));
+            }
+            continue;
+        }
+        let number = lower.trim_end_matches(['s', 't', 'n', 'd', 'r', 'h']);
+        let Ok(n) = number.parse::<u32>() else { continue };
+        if number.len() == 4 && (1900..=2100).contains(&n) && year.is_none() {
+            year = Some(n);
+        } else if number.len() <= 2 && (1..=31).contains(&n) && day.is_none() {
+            day = Some(n);
+        }
+    }
+    Some(format!("{:04}-{:02}-{:02}", year?, month? + 1, day?))
+}
+
+/// A relative date such as "3 hours ago", "5m ago" or "yesterday", as written.
+pub(crate) fn relative_date(text: &str) -> Option<String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let bare = |w: &str| w.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase();
+    for (i, &word) in words.iter().enumerate() {
+        match bare(word).as_str() {
+            "yesterday" => return Some("yesterday".to_string()),
+            "just" if words.get(i + 1).is_some_and(|&w| bare(w) == "now") => {
+                return Some("just now".to_string());
+            }
+            "ago" if i >= 1 => {
+                let unit = bare(words[i - 1]);
+                // "5m ago", "3h ago"
+                let compact = unit.is_ascii()
+                    && unit.len() >= 2
+                    && unit[..unit.len() - 1].bytes().all(|c| c.is_ascii_digit())
+                    && unit.ends_with(['s', 'm', 'h', 'd', 'w', 'y']);
+                if compact {
+                    return Some(format!("{unit} ago"));
+                }
+                let is_unit = TIME_UNITS.iter().any(|u| unit == *u || unit == format!("{u}s"));
+                let quantity = i.checked_sub(2).map(|j| bare(words[j]));
+                let counted = quantity.as_deref().is_some_and(|q| {
+                    matches!(q, "a" | "an" | "one") || (!q.is_empty() && q.bytes().all(|c| c.is_ascii_digit()))
+                });
+                if is_unit && counted {
+                    return Some(format!("{} {unit} ago", quantity.unwrap_or_default()));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// The author named in a byline such as "By Jane Doe · 3 hours ago".
+/// Unless `requires_by`, the leading "By" is optional, for text already
+/// known to be a byline.
+pub(crate) fn byline_author(text: &str, requires_by: bool) -> Option<String> {
+    let text = text.trim();
+    let lower = text.to_ascii_lowercase();
+    let name = ["written by ", "by "]
+        .iter()
+        .find(|prefix| lower.starts_with(*prefix))
+        .map(|prefix| &text[prefix.len()..]);
+    let name = match name {
+        Some(name) => name,
+        None if requires_by => return None,
+        None => text,
+    };
+
+    // Cut at whatever follows the name: a separator, a date, "on ..." or
+    // "updated ...".
+    let lower = name.to_ascii_lowercase();
+    let mut end = name
+        .find(|c: char| matches!(c, '|' | '·' | '•' | '—' | '–' | ',' | '\n') || c.is_ascii_digit())
+        .unwrap_or(name.len());
+    for marker in [" on ", " updated", " published", " - "] {
+        if let Some(i) = lower.find(marker) {
+            end = end.min(i);
+        }
+    }
+    let name = name[..end].trim().trim_end_matches(['.', ':']).trim();
+
+    let words: Vec<&str> = name.split_whitespace().collect();
+    let plausible = (1..=5).contains(&words.len())
+        && name.chars().count() <= 60
+        && words.first().is_some_and(|w| w.starts_with(char::is_uppercase))
+        && words.iter().all(|w| {
+            w.starts_with(char::is_uppercase) || NAME_PARTICLES.contains(&w.to_lowercase().as_str())
+        });
+    plausible.then(|| words.join(" "))
+}
+
@@ -0,0 +1,175 @@
+//! Cheap-to-clone strings for the values pages repeat thousands of times:
+//! tag names, roles and alert types.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::{Arc, OnceLock};
+
+/// A string that is one of a fixed set of known tag names, roles and alert
+/// types, stored as a `&'static str`, or any other value, shared behind an
+/// `Arc`. Either way cloning it doesn't allocate. Compares, hashes and
+/// serializes as the string it holds.
+#[derive(Clone)]
+pub struct Atom(Repr);
+
+#[derive(Clone)]
+enum Repr {
+    Static(&'static str),
+    Shared(Arc<str>),
+}
+
+/// The values stored without an allocation: the HTML tags and ARIA roles
+/// browsy emits, and the alert types it detects.
+const KNOWN: &[&str] = &[
+    // Tags
+    "a", "abbr", "article", "aside", "b", "blockquote", "body", "br", "button", "caption", "cite",
+    "code", "dd", "del", "details", "dialog", "div", "dl", "dt", "em", "fieldset", "figcaption",
+    "figure", "footer", "form", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "i", "iframe",
+    "img", "input", "ins", "label", "legend", "li", "main", "mark", "nav", "ol", "optgroup",
+    "option", "output", "p", "picture", "pre", "progress", "q", "s", "section", "select", "small",
+    "span", "strong", "sub", "summary", "sup", "svg", "table", "tbody", "td", "textarea", "tfoot",
+    "th", "thead", "time", "tr", "u", "ul", "video",
+    // Roles not already listed as tags
+    "alert", "alertdialog", "banner", "cell", "checkbox", "columnheader", "combobox",
+    "complementary", "contentinfo", "grid", "gridcell", "group", "heading", "link", "list",
+    "listbox", "listitem", "menu", "menubar", "menuitem", "menuitemcheckbox", "menuitemradio",
+    "navigation", "none", "presentation", "progressbar", "radio", "radiogroup", "region", "row",
+    "rowheader", "search", "searchbox", "slider", "spinbutton", "status", "switch", "tab",
+    "tablist", "tabpanel", "textbox", "toolbar", "tooltip", "tree", "treeitem",
+    // Alert types not already listed
+    "error", "success", "warning",
+];
+
+impl Atom {
+    /// `value` as an atom, without allocating when it is a known value.
+    pub fn new(value: &str) -> Self {
+        static KNOWN_SET: OnceLock<HashSet<&'static str>> = OnceLock::new();
+        match KNOWN_SET.get_or_init(|| KNOWN.iter().copied().collect()).get(value) {
+            Some(known) => Atom(Repr::Static(known)),
+            None => Atom(Repr::Shared(Arc::from(value))),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match &self.0 {
+            Repr::Static(s) => s,
+            Repr::Shared(s) => s,
+        }
+    }
+
+    /// Whether the value isn't a known one and so sits behind an `Arc`.
+    pub(crate) fn is_shared(&self) -> bool {
+        matches!(self.0, Repr::Shared(_))
+    }
+}
+
+impl Deref for Atom {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl AsRef<str> for Atom {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl Borrow<str> for Atom {
+    fn borrow(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl From<&str> for Atom {
+    fn from(value: &str) -> Self {
+        Atom::new(value)
+    }
+}
+
+impl From<String> for Atom {
+    fn from(value: String) -> Self {
+        Atom::new(&value)
+    }
+}
+
+impl fmt::Debug for Atom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Atom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl PartialEq for Atom {
+    fn eq(&self, other: &Atom) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for Atom {}
+
+impl Hash for Atom {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state)
+    }
+}
+
+impl PartialEq<str> for Atom {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Atom {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Atom {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<Atom> for str {
+    fn eq(&self, other: &Atom) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl PartialEq<Atom> for &str {
+    fn eq(&self, other: &Atom) -> bool {
+        *self == other.as_str()
+    }
+}
+
+impl PartialEq<Atom> for String {
+    fn eq(&self, other: &Atom) -> bool {
+        self == other.as_str()
+    }
+}
+
+impl Serialize for Atom {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Atom {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = std::borrow::Cow::<'de, str>::deserialize(deserializer)?;
+        Ok(Atom::new(&value))
+    }
+}
@@ -0,0 +1,221 @@
+//! Reading and writing the parts of a compact element line.
+//!
+//! Text between quotes has its whitespace collapsed to single spaces, so an
+//! element never spans more than one line, and `\` and `"` escaped with a
+//! backslash, so the closing quote is the first unescaped one.
+//! [`parse_compact_line`] reads a line back.
+
+use super::legend;
+use super::purpose::PURPOSES;
+
+/// One element line of the compact format, read back by
+/// [`parse_compact_line`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompactElement {
+    pub id: u32,
+    pub tag: String,
+    pub input_type: Option<String>,
+    pub hidden: bool,
+    pub pinned: bool,
+    /// Set on lines of [`super::delta_to_compact_string`], which mark added
+    /// or changed elements with `+`.
+    pub changed: bool,
+    pub form: Option<u16>,
+    /// The level of a heading whose tag doesn't show it.
+    pub level: Option<u8>,
+    pub name: Option<String>,
+    pub checked: bool,
+    pub required: bool,
+    /// What the input is for, such as `username`.
+    pub purpose: Option<String>,
+    pub val: Option<String>,
+    pub suggestions: Vec<String>,
+    /// How many suggestions the line left out.
+    pub more_suggestions: usize,
+    /// The quoted text, unescaped. Ends with `…` when it was cut short.
+    pub text: Option<String>,
+    /// Set when the text was marked `~`: an accessible name the page
+    /// doesn't show.
+    pub name_only: bool,
+    pub href: Option<String>,
+    /// `narrow`, `wide` or `full`.
+    pub size: Option<String>,
+    pub region: Option<String>,
+    /// The note left on the element, unescaped.
+    pub note: Option<String>,
+    pub desc: Option<String>,
+    pub src: Option<[u32; 2]>,
+}
+
+/// `text` ready to go between quotes: whitespace runs, newlines and tabs
+/// included, become one space, and `\` and `"` are escaped.
+pub(super) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for word in text.split_whitespace() {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        for c in word.chars() {
+            if c == '"' || c == '\\' {
+                out.push('\\');
+            }
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Read one line of [`super::to_compact_string`] or
+/// [`super::delta_to_compact_string`]. `None` for lines that aren't an
+/// element, such as the boilerplate count, or don't follow the format.
+pub fn parse_compact_line(line: &str) -> Option<CompactElement> {
+    let body = line.trim().strip_prefix('[')?.strip_suffix(']')?;
+    let (head, rest) = body.split_once(' ').unwrap_or((body, ""));
+    let mut el = parse_head(head)?;
+    let (mut rest, src) = split_src(rest.trim());
+    el.src = src;
+
+    while !rest.is_empty() {
+        if let Some(quoted) = rest.strip_prefix(legend::NAME_ONLY).filter(|q| q.starts_with('"')) {
+            el.name_only = true;
+            rest = quoted;
+        } else if let Some(quoted) = rest.strip_prefix(legend::NOTE).and_then(|q| q.strip_prefix('"')) {
+            let (note, after) = read_quoted(quoted)?;
+            el.note = Some(note);
+            rest = after;
+        } else if let Some(quoted) = rest.strip_prefix('"') {
+            let (text, after) = read_quoted(quoted)?;
+            rest = after.trim_start();
+            if text.ends_with(legend::TRUNCATED) {
+                rest = skip_length(rest);
+            }
+            el.text = Some(text);
+        } else if let Some(value) = rest.strip_prefix(legend::VALUE) {
+            let (value, after) = read_until(value, ']')?;
+            el.val = Some(value.to_string());
+            rest = after;
+        } else if let Some(values) = rest.strip_prefix(legend::SUGGESTIONS_OPEN) {
+            let (values, after) = read_until(values, legend::SUGGESTIONS_CLOSE.chars().next()?)?;
+            for value in values.split(legend::SUGGESTIONS_SEP) {
+                match value.strip_prefix(legend::MORE).and_then(|n| n.parse().ok()) {
+                    Some(more) => el.more_suggestions = more,
+                    None => el.suggestions.push(value.to_string()),
+                }
+            }
+            rest = after;
+        } else if let Some((purpose, after)) = rest.strip_prefix('(').and_then(|r| read_purpose(r)) {
+            el.purpose = Some(purpose.to_string());
+            rest = after;
+        } else if let Some(desc) = rest.strip_prefix('(') {
+            // Help text is the last part, so it runs to the closing parenthesis
+            el.desc = Some(desc.strip_suffix(')')?.to_string());
+            rest = "";
+        } else {
+            let (token, after) = rest.split_once(' ').unwrap_or((rest, ""));
+            parse_token(&mut el, token)?;
+            rest = after;
+        }
+        rest = rest.trim_start();
+    }
+    Some(el)
+}
+
+/// `[!][^][+]id:tag[:type]`
+fn parse_head(head: &str) -> Option<CompactElement> {
+    let mut el = CompactElement::default();
+    let mut head = head;
+    if let Some(after) = head.strip_prefix('+') {
+        el.changed = true;
+        head = after;
+    }
+    if let Some(after) = head.strip_prefix(legend::HIDDEN) {
+        el.hidden = true;
+        head = after;
+    }
+    if let Some(after) = head.strip_prefix(legend::PINNED) {
+        el.pinned = true;
+        head = after;
+    }
+    let mut parts = head.splitn(3, ':');
+    el.id = parts.next()?.parse().ok()?;
+    el.tag = parts.next().filter(|tag| !tag.is_empty())?.to_string();
+    el.input_type = parts.next().map(str::to_string);
+    Some(el)
+}
+
+/// A part without spaces: a marker, the name, a heading level, a link, a
+/// size or a region.
+fn parse_token(el: &mut CompactElement, token: &str) -> Option<()> {
+    if token == legend::CHECKED {
+        el.checked = true;
+    } else if token == legend::REQUIRED {
+        el.required = true;
+    } else if let Some(href) = token.strip_prefix(legend::LINK) {
+        el.href = Some(href.to_string());
+    } else if let Some(region) = token.strip_prefix(legend::REGION) {
+        el.region = Some(region.to_string());
+    } else if matches!(token, legend::NARROW | legend::WIDE | legend::FULL) {
+        el.size = Some(token.to_string());
+    } else if let Some(name) = token.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+        el.name = Some(name.to_string());
+    } else if let Some(form) = token.strip_prefix(legend::FORM).and_then(|f| f.parse().ok()) {
+        el.form = Some(form);
+    } else if let Some(level) = token.strip_prefix(legend::HEADING).and_then(|l| l.parse().ok()) {
+        el.level = Some(level);
+    } else {
+        return None;
+    }
+    Some(())
+}
+
+/// Unescaped text up to the closing quote, and what follows it.
+fn read_quoted(s: &str) -> Option<(String, &str)> {
+    let mut text = String::new();
+    let mut chars = s.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => text.push(chars.next()?.1),
+            '"' => return Some((text, &s[i + 1..])),
+            _ => text.push(c),
+        }
+    }
+    None
+}
+
+/// Text up to the first `close` that ends a part (followed by a space or
+/// the end of the line), and what follows it.
+fn read_until(s: &str, close: char) -> Option<(&str, &str)> {
+    s.char_indices()
+        .filter(|&(_, c)| c == close)
+        .map(|(i, _)| (&s[..i], &s[i + close.len_utf8()..]))
+        .find(|(_, after)| after.is_empty() || after.starts_with(' '))
+}
+
+/// A known purpose closing with `)`, and what follows it.
+fn read_purpose(s: &str) -> Option<(&str, &str)> {
+    let (purpose, after) = read_until(s, ')')?;
+    PURPOSES.contains(&purpose).then_some((purpose, after))
+}
+
+/// Skip the `(1.4k chars)` after text that was cut short.
+fn skip_length(s: &str) -> &str {
+    let Some(inner) = s.strip_prefix('(') else { return s };
+    match inner.split_once(" chars)") {
+        Some((count, after)) if count.chars().all(|c| c.is_ascii_digit() || c == '.' || c == 'k') => after,
+        _ => s,
+    }
+}
+
+/// Split off the trailing `(line:col)` source position, if there is one.
+fn split_src(rest: &str) -> (&str, Option<[u32; 2]>) {
+    let Some(open) = rest.strip_suffix(')').and_then(|r| r.rfind('(')) else {
+        return (rest, None);
+    };
+    let position = rest[open + 1..rest.len() - 1]
+        .split_once(':')
+        .and_then(|(line, column)| Some([line.parse().ok()?, column.parse().ok()?]));
+    match position {
+        Some(src) => (rest[..open].trim_end(), Some(src)),
+        None => (rest, None),
+    }
+}
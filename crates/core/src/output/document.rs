@@ -0,0 +1,107 @@
+//! Spatial DOMs for documents that aren't HTML, such as PDFs: their text
+//! blocks become headings and paragraphs in reading order, each with the
+//! page it's on.
+
+use super::*;
+
+/// A block of text read out of a document.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DocumentBlock {
+    /// From 1.
+    pub page: u16,
+    pub text: String,
+    /// Heading level, 1 for the largest text.
+    pub heading: Option<u8>,
+    /// Bounds in viewport pixels, pages stacked top to bottom.
+    pub b: [i32; 4],
+}
+
+/// A Spatial DOM with one `h1`-`h6` or `p` element per block, in the order
+/// given, and `page_type` `Document`.
+pub(crate) fn document_dom(url: &str, title: String, blocks: Vec<DocumentBlock>, vp: [f32; 2]) -> SpatialDom {
+    let els: Vec<SpatialElement> = blocks
+        .into_iter()
+        .enumerate()
+        .map(|(i, block)| {
+            let tag = match block.heading {
+                Some(level) => format!("h{}", level.clamp(1, 6)),
+                None => "p".to_string(),
+            };
+            SpatialElement {
+                id: i as u32 + 1,
+                tag: Atom::new(&tag),
+                role: block.heading.map(|_| Atom::new("heading")),
+                level: block.heading.map(|level| level.clamp(1, 6)),
+                text: Some(block.text),
+                text_source: Some(TextSource::Visible),
+                ph: None,
+                href: None,
+                val: None,
+                default_val: None,
+                input_type: None,
+                suggestions: None,
+                more_suggestions: None,
+                disabled: None,
+                readonly: None,
+                checked: None,
+                expanded: None,
+                selected: None,
+                required: None,
+                name: None,
+                label: None,
+                autocomplete: None,
+                purpose: None,
+                desc: None,
+                alert_type: None,
+                form: None,
+                action: None,
+                method: None,
+                hidden: None,
+                hidden_reason: None,
+                selector: None,
+                b: block.b,
+                clipped: None,
+                visible_b: None,
+                pinned: None,
+                src: None,
+                note: None,
+                page: Some(block.page),
+                prominence: None,
+                tone: None,
+            }
+        })
+        .collect();
+    let (lang, lang_confidence) = match lang::detect(&visible_text_sample(&els)) {
+        Some(d) => (Some(d.lang.to_string()), Some(d.confidence)),
+        None => (None, None),
+    };
+    let mut dom = SpatialDom {
+        schema: SCHEMA_VERSION,
+        url: url.to_string(),
+        title,
+        lang,
+        lang_confidence,
+        vp,
+        layout_vp: None,
+        scroll: [0.0, 0.0],
+        suggested_actions: Vec::new(),
+        page_type: PageType::Document,
+        captcha: None,
+        blocked: None,
+        article: None,
+        auto_actions: Vec::new(),
+        warnings: Vec::new(),
+        not_modified: false,
+        boilerplate_suppressed: 0,
+        outside_main: 0,
+        parse_stats: ParseStats::default(),
+        els,
+        text_limit: default_text_limit(),
+        id_index: HashMap::new(),
+        breadcrumbs: Vec::new(),
+        prices: Vec::new(),
+        behaviors: Vec::new(),
+    };
+    dom.rebuild_index();
+    dom
+}
@@ -0,0 +1,111 @@
+//! What the page type and suggested action detectors ask of every element,
+//! worked out in one pass so none of them lowercases the same text again.
+
+use super::{SpatialDom, SpatialElement};
+use rayon::prelude::*;
+
+/// Pages with at least this many elements run their detectors in parallel.
+pub const DEFAULT_PARALLEL_MIN_ELEMENTS: usize = 2_000;
+
+/// Lowercased text and common facts for each element of a page, in the
+/// same order as its `els`.
+pub(super) struct PageIndex {
+    /// The lowercased title.
+    pub title: String,
+    pub els: Vec<Features>,
+}
+
+pub(super) struct Features {
+    /// Lowercased `text`, `label`, `name` and `ph`.
+    pub text: Option<String>,
+    pub label: Option<String>,
+    pub name: Option<String>,
+    pub ph: Option<String>,
+    /// Not `hidden`.
+    pub visible: bool,
+    /// Role `heading`.
+    pub heading: bool,
+    /// A `<button>` or role `button`.
+    pub button: bool,
+    /// A visible password input.
+    pub password: bool,
+    /// A text input or textarea for search queries, visible or not.
+    pub search_input: bool,
+}
+
+impl PageIndex {
+    /// Index `dom`, spreading the work over rayon's pool when `parallel`.
+    pub fn new(dom: &SpatialDom, parallel: bool) -> Self {
+        let els = if parallel {
+            dom.els.par_iter().map(Features::of).collect()
+        } else {
+            dom.els.iter().map(Features::of).collect()
+        };
+        Self { title: dom.title.to_lowercase(), els }
+    }
+
+    /// Each element with its features.
+    pub fn iter<'a>(&'a self, dom: &'a SpatialDom) -> impl Iterator<Item = (&'a SpatialElement, &'a Features)> + Clone {
+        dom.els.iter().zip(&self.els)
+    }
+
+    pub fn title_has(&self, keywords: &[&str]) -> bool {
+        keywords.iter().any(|kw| self.title.contains(kw))
+    }
+
+    /// Whether any heading's text contains one of `keywords`.
+    pub fn heading_has(&self, keywords: &[&str]) -> bool {
+        self.els.iter().any(|f| f.heading && f.text_has(keywords))
+    }
+}
+
+impl Features {
+    fn of(e: &SpatialElement) -> Self {
+        let lower = |value: &Option<String>| value.as_deref().map(str::to_lowercase);
+        let mut features = Features {
+            text: lower(&e.text),
+            label: lower(&e.label),
+            name: lower(&e.name),
+            ph: lower(&e.ph),
+            visible: e.hidden != Some(true),
+            heading: e.role.as_deref() == Some("heading"),
+            button: e.tag == "button" || e.role.as_deref() == Some("button"),
+            password: false,
+            search_input: false,
+        };
+        features.password = features.visible && e.input_type.as_deref() == Some("password");
+        features.search_input = features.is_search_input(e);
+        features
+    }
+
+    /// Whether the lowercased text contains one of `words`.
+    pub fn text_has(&self, words: &[&str]) -> bool {
+        self.text.as_deref().is_some_and(|t| words.iter().any(|w| t.contains(w)))
+    }
+
+    pub fn name_is(&self, names: &[&str]) -> bool {
+        self.name.as_deref().is_some_and(|n| names.contains(&n))
+    }
+
+    /// Check if an element is a search input (input or textarea used for search queries).
+    fn is_search_input(&self, e: &SpatialElement) -> bool {
+        let is_text_input = match e.tag.as_str() {
+            "input" => !matches!(
+                e.input_type.as_deref(),
+                Some("checkbox") | Some("radio") | Some("hidden") | Some("submit")
+                | Some("button") | Some("image") | Some("password")
+            ),
+            "textarea" => true,
+            _ => return false,
+        };
+        if !is_text_input { return false; }
+
+        let mentions_search = |value: &Option<String>| value.as_deref().is_some_and(|v| v.contains("search"));
+        e.input_type.as_deref() == Some("search")
+            || e.role.as_deref() == Some("searchbox")
+            || e.name.as_deref() == Some("q")
+            || mentions_search(&self.name)
+            || mentions_search(&self.ph)
+            || mentions_search(&self.label)
+    }
+}
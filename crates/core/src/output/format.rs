@@ -0,0 +1,87 @@
+//! Pages as the servers and the CLI print them: a header of what the page
+//! holds, then its elements as compact lines, an ASCII map or JSON.
+
+use std::fmt::Write;
+
+use super::{
+    to_ascii_map, to_compact_string, DomView, HiddenReason, Scope, SpatialDom, DEFAULT_HIDDEN_DROP, MAP_COLS,
+    MAP_ROWS,
+};
+
+/// The lines above a page's elements, through `---`: its title, URL and
+/// element count, then how much of it is above the fold, hidden, in forms
+/// or in tables, so an agent can tell whether to ask for a narrower scope.
+pub fn format_header(dom: &SpatialDom) -> String {
+    header(dom, dom.els.len())
+}
+
+/// A page in the servers' `format`: `json`, `map`, or anything else for
+/// compact lines.
+pub fn format_page(dom: &SpatialDom, format: Option<&str>) -> String {
+    match format {
+        Some("json") => serde_json::to_string_pretty(dom).unwrap_or_default(),
+        Some("map") => header(dom, dom.els.len()) + &to_ascii_map(dom, MAP_COLS, MAP_ROWS),
+        _ => header(dom, dom.els.len()) + &to_compact_string(dom),
+    }
+}
+
+/// `format_page` of a scoped view, without copying its elements for the
+/// text formats.
+pub fn format_view(view: &DomView, format: Option<&str>) -> String {
+    let dom = view.dom();
+    match format {
+        Some("json") => serde_json::to_string_pretty(&view.to_dom()).unwrap_or_default(),
+        Some("map") => header(dom, view.len()) + &view.to_ascii_map(MAP_COLS, MAP_ROWS),
+        _ => header(dom, view.len()) + &view.to_compact_string(),
+    }
+}
+
+/// A copy of `dom` narrowed as the CLI's flags ask: without the elements
+/// hidden for one of the `drop_hidden` reasons ([`DEFAULT_HIDDEN_DROP`] when
+/// empty), then to those above the fold, then to the main content.
+pub fn apply_scope(
+    dom: &SpatialDom,
+    drop_hidden: Option<&[HiddenReason]>,
+    above_fold: bool,
+    main_content: bool,
+) -> SpatialDom {
+    let mut view = dom.view(Scope::All);
+    match drop_hidden {
+        Some([]) => view = view.strip_hidden(DEFAULT_HIDDEN_DROP),
+        Some(reasons) => view = view.strip_hidden(reasons),
+        None => {}
+    }
+    if above_fold {
+        view = view.above_fold_with(&Default::default());
+    }
+    if main_content {
+        view = view.main_content();
+    }
+    view.to_dom()
+}
+
+/// [`format_header`] for `els` of the page's elements shown. The fold,
+/// hidden, form and table counts are always the whole page's.
+fn header(dom: &SpatialDom, els: usize) -> String {
+    let mut out = format!("title: {}\nurl: {}\nels: {}\n", dom.title, dom.url, els);
+    if let Some(ref lang) = dom.lang {
+        let _ = writeln!(out, "lang: {lang}");
+    }
+    if !dom.page_type.is_other() {
+        let _ = writeln!(out, "page_type: {:?}", dom.page_type);
+    }
+    let above_fold = dom.view(Scope::AboveFold).len();
+    let _ = writeln!(out, "fold: {above_fold}/{} els", dom.els.len());
+    let hidden = dom.els.iter().filter(|e| e.hidden == Some(true)).count();
+    let _ = writeln!(out, "hidden: {hidden}");
+    let _ = writeln!(out, "forms: {}", dom.forms().len());
+    let _ = writeln!(out, "tables: {}", dom.tables().len());
+    for action in &dom.auto_actions {
+        let _ = writeln!(out, "auto: {action}");
+    }
+    for warning in &dom.warnings {
+        let _ = writeln!(out, "warning: {warning}");
+    }
+    out.push_str("---\n");
+    out
+}
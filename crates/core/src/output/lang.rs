@@ -0,0 +1,126 @@
+//! Guessing the language of page text when the page doesn't declare one.
+//!
+//! Non-Latin scripts mostly identify the language on their own (kana means
+//! Japanese, Hangul Korean). Latin-script text is scored against short lists
+//! of each language's most frequent function words, which is enough for a
+//! page's worth of text without shipping an n-gram model.
+
+/// A detected language with how sure the detector is, from 0 to 1.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Detection {
+    /// ISO 639-1 code.
+    pub lang: &'static str,
+    pub confidence: f32,
+}
+
+/// Fewer letters than this aren't worth guessing from.
+const MIN_LETTERS: usize = 20;
+
+/// Frequent words that are rare in the other listed languages.
+const STOPWORDS: &[(&str, &[&str])] = &[
+    ("en", &["the", "and", "of", "to", "is", "that", "with", "for", "this", "are", "was", "you", "have", "from", "which", "it", "be", "not"]),
+    ("de", &["der", "die", "das", "und", "ist", "nicht", "mit", "sich", "des", "auf", "ein", "eine", "dem", "den", "auch", "wird", "für", "sie"]),
+    ("fr", &["le", "la", "les", "et", "est", "des", "du", "une", "dans", "pour", "que", "qui", "pas", "sur", "avec", "sont", "nous", "au"]),
+    ("es", &["el", "los", "las", "y", "es", "del", "una", "por", "para", "con", "que", "se", "como", "más", "pero", "está", "son", "al"]),
+    ("it", &["il", "di", "che", "è", "della", "per", "non", "sono", "gli", "una", "nel", "anche", "come", "alla", "questo", "lo", "del", "ha"]),
+    ("pt", &["o", "os", "da", "do", "não", "uma", "em", "com", "para", "que", "são", "mais", "como", "dos", "das", "ao", "está", "é"]),
+    ("nl", &["de", "het", "een", "en", "van", "is", "niet", "dat", "op", "voor", "met", "zijn", "ook", "worden", "wordt", "maar", "bij", "je"]),
+    ("sv", &["och", "är", "att", "det", "som", "en", "på", "för", "med", "inte", "av", "till", "har", "den", "om", "ett", "var", "jag"]),
+    ("pl", &["i", "w", "nie", "się", "na", "jest", "że", "do", "to", "z", "jak", "ale", "co", "po", "od", "są", "przez", "oraz"]),
+];
+
+#[derive(Default)]
+struct ScriptCounts {
+    latin: usize,
+    han: usize,
+    kana: usize,
+    hangul: usize,
+    cyrillic: usize,
+    greek: usize,
+    arabic: usize,
+    hebrew: usize,
+    thai: usize,
+    devanagari: usize,
+}
+
+fn count_scripts(text: &str) -> ScriptCounts {
+    let mut counts = ScriptCounts::default();
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        match c as u32 {
+            0x3040..=0x30FF | 0x31F0..=0x31FF | 0xFF66..=0xFF9F => counts.kana += 1,
+            0x4E00..=0x9FFF | 0x3400..=0x4DBF | 0xF900..=0xFAFF => counts.han += 1,
+            0xAC00..=0xD7AF | 0x1100..=0x11FF | 0x3130..=0x318F => counts.hangul += 1,
+            0x0400..=0x04FF => counts.cyrillic += 1,
+            0x0370..=0x03FF => counts.greek += 1,
+            0x0600..=0x06FF | 0x0750..=0x077F => counts.arabic += 1,
+            0x0590..=0x05FF => counts.hebrew += 1,
+            0x0E00..=0x0E7F => counts.thai += 1,
+            0x0900..=0x097F => counts.devanagari += 1,
+            _ if c.is_ascii_alphabetic() || ('\u{00C0}'..='\u{024F}').contains(&c) => counts.latin += 1,
+            _ => {}
+        }
+    }
+    counts
+}
+
+/// Guess the language of `text`. `None` when there's too little text or
+/// Latin-script text matches none of the known word lists.
+pub(crate) fn detect(text: &str) -> Option<Detection> {
+    let counts = count_scripts(text);
+    let cjk = counts.han + counts.kana;
+    let scripts = [
+        (counts.latin, ""),
+        // Japanese mixes kana into Han; Chinese has none
+        (cjk, if counts.kana * 10 >= cjk { "ja" } else { "zh" }),
+        (counts.hangul, "ko"),
+        (counts.cyrillic, "ru"),
+        (counts.greek, "el"),
+        (counts.arabic, "ar"),
+        (counts.hebrew, "he"),
+        (counts.thai, "th"),
+        (counts.devanagari, "hi"),
+    ];
+    let total: usize = scripts.iter().map(|(n, _)| n).sum();
+    if total < MIN_LETTERS {
+        return None;
+    }
+    let &(count, lang) = scripts.iter().max_by_key(|(n, _)| *n)?;
+    let script_share = count as f32 / total as f32;
+    if !lang.is_empty() {
+        return Some(Detection { lang, confidence: script_share });
+    }
+
+    let mut scores = vec![0usize; STOPWORDS.len()];
+    for word in text.split(|c: char| !c.is_alphabetic()).filter(|w| !w.is_empty()) {
+        let word = word.to_lowercase();
+        for (score, (_, words)) in scores.iter_mut().zip(STOPWORDS) {
+            if words.contains(&word.as_str()) {
+                *score += 1;
+            }
+        }
+    }
+    let mut ranked: Vec<(usize, &str)> = scores.into_iter()
+        .zip(STOPWORDS)
+        .map(|(score, (lang, _))| (score, *lang))
+        .collect();
+    ranked.sort_by_key(|r| std::cmp::Reverse(r.0));
+    let (best, lang) = ranked[0];
+    let second = ranked.get(1).map_or(0, |r| r.0);
+    if best < 3 {
+        return None;
+    }
+    // Two languages scoring alike (a page half in each) means little is known
+    let margin = 1.0 - second as f32 / best as f32;
+    Some(Detection { lang, confidence: margin * script_share })
+}
+
+/// The primary subtag of a language tag or Accept-Language value, lowercased:
+/// `de` for `de-DE`, `en` for `en-US,en;q=0.9`.
+pub(crate) fn primary_subtag(tag: &str) -> String {
+    tag.split([',', ';', '-', '_'])
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase()
+}
+
@@ -0,0 +1,100 @@
+//! The markers of the compact format, and the legend describing them.
+//!
+//! `to_compact_string` writes its markers from the constants here, and
+//! [`format_legend`] is built from the same constants, so a marker can't be
+//! added to one without the other.
+
+use std::sync::OnceLock;
+
+/// Prefix of a hidden element's ID.
+pub(super) const HIDDEN: &str = "!";
+/// Prefix of a pinned (fixed or sticky) element's ID.
+pub(super) const PINNED: &str = "^";
+/// Prefix of the index of the form an element belongs to.
+pub(super) const FORM: &str = "f";
+/// Prefix of the level of a heading whose tag doesn't show it.
+pub(super) const HEADING: &str = "h";
+pub(super) const CHECKED: &str = "[v]";
+pub(super) const REQUIRED: &str = "[*]";
+/// Opens a field's current value, which is closed by `]`.
+pub(super) const VALUE: &str = "[=";
+/// Open and close the values a field suggests, which are split by `|`.
+pub(super) const SUGGESTIONS_OPEN: &str = "{";
+pub(super) const SUGGESTIONS_CLOSE: &str = "}";
+pub(super) const SUGGESTIONS_SEP: &str = "|";
+/// Prefix of the count of suggestions left out.
+pub(super) const MORE: &str = "+";
+/// Prefix of a link target.
+pub(super) const LINK: &str = "->";
+/// Marks text that isn't shown, only the element's accessible name.
+pub(super) const NAME_ONLY: &str = "~";
+/// Ends element text that was cut short, before its full length.
+pub(super) const TRUNCATED: &str = "…";
+/// Prefix of the quoted note an agent left on the element.
+pub(super) const NOTE: &str = "#";
+/// Prefix of a screen region.
+pub(super) const REGION: &str = "@";
+
+/// Width hints for form controls, from narrowest to widest.
+pub(super) const NARROW: &str = "narrow";
+pub(super) const WIDE: &str = "wide";
+pub(super) const FULL: &str = "full";
+
+/// Screen regions by row, then column.
+pub(super) const REGIONS: [[&str; 3]; 3] = [
+    ["top-L", "top", "top-R"],
+    ["mid-L", "mid", "mid-R"],
+    ["bot-L", "bot", "bot-R"],
+];
+/// The region of elements below the fold.
+pub(super) const BELOW_FOLD: &str = "below";
+
+/// Opens the line counting elements left out as boilerplate.
+pub(super) const BOILERPLATE: &str = "boilerplate:";
+/// Opens the line counting elements left out around the main content.
+pub(super) const OUTSIDE_MAIN: &str = "outside main:";
+
+/// What each marker of a compact element line means, one per line. Meant for
+/// prompts: it is plain text without Markdown.
+pub fn format_legend() -> &'static str {
+    static LEGEND: OnceLock<String> = OnceLock::new();
+    LEGEND.get_or_init(|| {
+        let regions: Vec<String> = REGIONS
+            .iter()
+            .flatten()
+            .chain([&BELOW_FOLD])
+            .map(|region| format!("{REGION}{region}"))
+            .collect();
+        [
+            "[id:tag ...]  one element per line; act on it by its id".to_string(),
+            "id:tag:type  an input's type, when it is not text (3:input:password)".to_string(),
+            format!("{HIDDEN}id  hidden (display:none, aria-hidden, ...), listed so menus and tabs can still be read"),
+            format!("{PINNED}id  pinned: fixed or sticky, stays on screen while scrolling"),
+            format!("{FORM}N  the form the element belongs to, when the page has several"),
+            format!("{HEADING}N  the level of a heading whose tag isn't h1-h6 (role=heading)"),
+            "[name]  a field's name attribute".to_string(),
+            format!("{CHECKED}  checked"),
+            format!("{REQUIRED}  required"),
+            format!("{VALUE}value]  a field's current value"),
+            format!("({})  what an input is for, when its type doesn't say", super::purpose::PURPOSES.join("|")),
+            format!(
+                "{SUGGESTIONS_OPEN}a{SUGGESTIONS_SEP}b{SUGGESTIONS_SEP}{MORE}N{SUGGESTIONS_CLOSE}  values the field suggests (a datalist); {MORE}N more are left out"
+            ),
+            "\"text\"  the element's text, or an empty field's placeholder, on one line; \\\" is a quote inside it".to_string(),
+            format!("{NAME_ONLY}\"text\"  not shown on the page: the element's aria-label, title or image alt text"),
+            format!("\"text{TRUNCATED}\" (1.4k chars)  long text cut short; the count is its full length"),
+            format!("{LINK}url  where a link goes"),
+            format!("{NARROW} / {WIDE} / {FULL}  a form control's width relative to the page"),
+            format!(
+                "{}  where on screen, only to tell apart elements with the same tag and text; {REGION}{BELOW_FOLD} is below the fold",
+                regions.join(" ")
+            ),
+            format!("{NOTE}\"note\"  a note left on the element with annotate"),
+            "(text)  a form field's help text, such as the format it expects".to_string(),
+            "(line:col)  where the element's start tag is in the HTML, when source positions are tracked".to_string(),
+            format!("[{BOILERPLATE} N els suppressed]  N elements the previous page also had (header, nav, footer) were left out"),
+            format!("[{OUTSIDE_MAIN} N els]  N elements around the main content (header, nav, sidebars, footer) were left out"),
+        ]
+        .join("\n")
+    })
+}
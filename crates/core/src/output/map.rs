@@ -0,0 +1,123 @@
+//! A coarse ASCII picture of where things sit on a page, for agents that
+//! can't be shown a screenshot.
+
+use super::{quote_text, SpatialDom, SpatialElement, INTERACTIVE_TAGS};
+
+/// Columns in the map `format=map` and `browsy fetch --map` draw.
+pub const MAP_COLS: usize = 80;
+/// Rows in the map `format=map` and `browsy fetch --map` draw.
+pub const MAP_ROWS: usize = 40;
+
+const TEXT: char = '.';
+const CONTROL: char = '#';
+const FOLD: &str = " fold ";
+/// Longest element text in the legend.
+const LEGEND_TEXT_LIMIT: usize = 40;
+
+/// Draw the page onto a `cols` × `rows` character grid. The grid spans the
+/// width the page was laid out at and its full height; each visible element
+/// fills the cells its bounds cover. Text is `.`; interactive elements are
+/// `#`, starting with their ID where it fits, and cover any text under them.
+/// A line of `-` marks the fold when there is content below it. The IDs
+/// shown are listed under the grid in the compact format.
+///
+/// ```text
+/// 1#### 2###### 3#####
+///
+/// .........................
+/// .........................6#########
+///
+/// [1:a "Home"]
+/// ```
+pub fn to_ascii_map(dom: &SpatialDom, cols: usize, rows: usize) -> String {
+    draw_map(dom, dom.els.iter(), cols, rows)
+}
+
+/// [`to_ascii_map`] of `els`, which belong to `dom`.
+pub(super) fn draw_map<'a>(
+    dom: &SpatialDom,
+    els: impl Iterator<Item = &'a SpatialElement>,
+    cols: usize,
+    rows: usize,
+) -> String {
+    let width = dom.layout_size()[0] as f64;
+    if cols == 0 || rows == 0 || width <= 0.0 {
+        return String::new();
+    }
+    let drawn: Vec<&SpatialElement> = els
+        .filter(|e| e.hidden != Some(true) && !e.is_fully_clipped())
+        .filter(|e| is_control(e) || e.text.as_deref().is_some_and(|t| !t.trim().is_empty()))
+        .collect();
+    let fold = (dom.scroll[1].max(0.0) + dom.vp[1]) as f64;
+    let bottom = drawn
+        .iter()
+        .map(|e| {
+            let b = e.effective_bounds();
+            b[1].saturating_add(b[3]) as f64
+        })
+        .fold(fold, f64::max);
+    if bottom <= 0.0 {
+        return String::new();
+    }
+
+    let span = |start: i32, len: i32, cells: usize, extent: f64| {
+        let cell = |px: f64| px * cells as f64 / extent;
+        let first = (cell(start as f64).floor().max(0.0) as usize).min(cells - 1);
+        let end = (cell(start as f64 + len as f64).ceil().max(0.0) as usize).clamp(first + 1, cells);
+        first..end
+    };
+    let mut grid = vec![vec![' '; cols]; rows];
+    let mut labels = Vec::new();
+    // Text first, so controls drawn over it win
+    for control in [false, true] {
+        for el in drawn.iter().filter(|e| is_control(e) == control) {
+            let b = el.effective_bounds();
+            let (xs, ys) = (span(b[0], b[2], cols, width), span(b[1], b[3], rows, bottom));
+            let fill = if control { CONTROL } else { TEXT };
+            for row in &mut grid[ys.clone()] {
+                row[xs.clone()].fill(fill);
+            }
+            let id: Vec<char> = el.id.to_string().chars().collect();
+            if control && id.len() <= xs.len() {
+                grid[ys.start][xs.start..xs.start + id.len()].copy_from_slice(&id);
+                labels.push((*el, ys.start, xs.start, id));
+            }
+        }
+    }
+
+    let mut lines: Vec<String> = grid
+        .iter()
+        .map(|row| row.iter().collect::<String>().trim_end().to_string())
+        .collect();
+    let fold_row = (fold * rows as f64 / bottom).round() as usize;
+    if fold_row < rows {
+        let mut line = "-".repeat(cols);
+        if cols >= FOLD.len() {
+            let start = (cols - FOLD.len()) / 2;
+            line.replace_range(start..start + FOLD.len(), FOLD);
+        }
+        lines.insert(fold_row, line);
+    }
+
+    // A label a later control drew over isn't shown
+    let mut shown: Vec<&SpatialElement> = labels
+        .into_iter()
+        .filter(|(_, row, col, id)| grid[*row][*col..*col + id.len()] == id[..])
+        .map(|(el, ..)| el)
+        .collect();
+    shown.sort_by_key(|e| e.id);
+    if !shown.is_empty() {
+        lines.push(String::new());
+        for el in shown {
+            match el.text.as_deref().or(el.ph.as_deref()) {
+                Some(text) => lines.push(format!("[{}:{} {}]", el.id, el.tag, quote_text(text, Some(LEGEND_TEXT_LIMIT)))),
+                None => lines.push(format!("[{}:{}]", el.id, el.tag)),
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+fn is_control(el: &SpatialElement) -> bool {
+    INTERACTIVE_TAGS.contains(&el.tag.as_str())
+}
@@ -1,15 +1,65 @@
-use crate::css::{Display, Visibility};
+use crate::css::{Dimension, Display, Position, Visibility};
 use crate::dom::NodeType;
-use crate::layout::LayoutNode;
-use serde::{Serialize, Deserialize};
-use std::collections::HashMap;
+use crate::js::JsBehavior;
+use crate::layout::{Bounds, LayoutNode};
+use features::{Features, PageIndex};
+use rayon::prelude::*;
+use serde::{Serialize, Serializer, Deserialize};
+use std::collections::{HashMap, HashSet};
+
+mod actions;
+mod article;
+mod atom;
+mod compact;
+#[cfg(feature = "pdf")]
+mod document;
+mod features;
+mod format;
+mod lang;
+mod legend;
+mod map;
+mod nearby;
+mod price;
+mod purpose;
+mod schema;
+mod tone;
+mod view;
+
+pub use actions::MAX_SUGGESTED_ACTIONS;
+pub use atom::Atom;
+pub use compact::{parse_compact_line, CompactElement};
+#[cfg(feature = "pdf")]
+pub(crate) use document::{document_dom, DocumentBlock};
+pub use features::DEFAULT_PARALLEL_MIN_ELEMENTS;
+pub use format::{apply_scope, format_header, format_page, format_view};
+pub use legend::format_legend;
+pub use map::{to_ascii_map, MAP_COLS, MAP_ROWS};
+pub use nearby::{ElementContext, DEFAULT_NEIGHBOR_RADIUS};
+pub use schema::{migrate, SCHEMA_VERSION};
+pub use tone::Tone;
+pub use view::{DomView, Scope};
 
 /// The Spatial DOM — the primary output of agentbrowser.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SpatialDom {
+    /// The [`SCHEMA_VERSION`] this was built with; `0` for JSON written
+    /// before versioning. [`migrate`] reads any version.
+    #[serde(default)]
+    pub schema: u16,
     pub url: String,
     pub title: String,
+    /// Page language: `<html lang>` when declared, otherwise guessed from
+    /// the visible text. See `lang_confidence`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    /// How sure `lang` is, from 0 to 1. Always 1 for a declared language.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lang_confidence: Option<f32>,
     pub vp: [f32; 2],
+    /// Width and height the page was laid out at, when the width differs from
+    /// `vp` (mobile viewports honoring or lacking a viewport meta tag).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout_vp: Option<[f32; 2]>,
     pub scroll: [f32; 2],
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub suggested_actions: Vec<SuggestedAction>,
@@ -19,10 +69,65 @@ pub struct SpatialDom {
     pub captcha: Option<CaptchaInfo>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub blocked: Option<BlockedInfo>,
+    /// Publication dates and author, when the page declares or shows them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub article: Option<ArticleMeta>,
+    /// Actions the session took on its own before returning this page, such
+    /// as dismissing a cookie banner.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub auto_actions: Vec<String>,
+    /// Things about this page the agent should know before acting on it,
+    /// such as its URL matching one of the session's blocked patterns.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// Set when a conditional request came back `304 Not Modified`, so this
+    /// is the page the session already had.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub not_modified: bool,
+    /// Elements left out because the previous page had them too; see
+    /// [`suppress_boilerplate`].
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub boilerplate_suppressed: usize,
+    /// Elements outside the main content left out by
+    /// [`SpatialDom::filter_main_content`].
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub outside_main: usize,
+    /// Counters from building `els`, for spotting pages that lose content.
+    #[serde(default, skip_serializing_if = "ParseStats::is_empty")]
+    pub parse_stats: ParseStats,
     pub els: Vec<SpatialElement>,
+    /// Most characters of an element's text [`to_compact_string`] shows
+    /// before cutting it short; `None` shows all of it. JSON keeps the whole
+    /// text either way.
+    #[serde(skip, default = "default_text_limit")]
+    pub text_limit: Option<usize>,
     /// O(1) lookup: element ID → index in `els`.
     #[serde(skip)]
     id_index: HashMap<u32, usize>,
+    /// Breadcrumb trail, outermost step first; see `breadcrumbs()`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    breadcrumbs: Vec<Crumb>,
+    /// Prices on the page, primary first; see `prices()`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    prices: Vec<PriceHit>,
+    /// JS behaviors of the emitted elements; see `behaviors()`.
+    #[serde(skip)]
+    behaviors: Vec<JsBehavior>,
+}
+
+/// What the element walk left out of a Spatial DOM. A page with many
+/// skipped nodes likely hit a layout quirk.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseStats {
+    /// Visible elements skipped for having no size, no text, no interactive
+    /// tag and nothing with a size inside.
+    pub zero_size_skipped: u32,
+}
+
+impl ParseStats {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
 }
 
 /// CAPTCHA information detected on the page.
@@ -69,22 +174,47 @@ pub enum CaptchaType {
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct SpatialElement {
     pub id: u32,
-    pub tag: String,
+    pub tag: Atom,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub role: Option<String>,
+    pub role: Option<Atom>,
+    /// A heading's level, from `aria-level` or its `h1`-`h6` tag, or how
+    /// many lists a list item is nested in, 1 for a top-level item.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub level: Option<u8>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub text: Option<String>,
+    /// Where `text` came from. Left out of JSON for visible text, which
+    /// [`migrate`] restores.
+    #[serde(default, skip_serializing_if = "TextSource::is_visible_or_none")]
+    pub text_source: Option<TextSource>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ph: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub href: Option<String>,
+    /// A field's current value: the `value` attribute, a textarea's text, or
+    /// what the session typed since the page loaded.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub val: Option<String>,
+    /// The value the HTML shipped with, set only by `Session::dom` on fields
+    /// the session has changed since. `None` there means the field was empty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_val: Option<String>,
     #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
     pub input_type: Option<String>,
+    /// Values the input suggests from the `<datalist>` its `list` attribute
+    /// names. Only the first [`MAX_SUGGESTIONS`] are serialized.
+    #[serde(default, skip_serializing_if = "Option::is_none", serialize_with = "serialize_suggestions")]
+    pub suggestions: Option<Vec<String>>,
+    /// How many of `suggestions` are past [`MAX_SUGGESTIONS`] and left out
+    /// when serializing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub more_suggestions: Option<usize>,
     /// ARIA: whether the element is disabled
     #[serde(skip_serializing_if = "Option::is_none")]
     pub disabled: Option<bool>,
+    /// Whether a field's value can't be changed (`readonly`, `aria-readonly`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub readonly: Option<bool>,
     /// ARIA: whether the element is checked (checkbox/radio)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub checked: Option<bool>,
@@ -103,16 +233,164 @@ pub struct SpatialElement {
     /// Associated label text (from `<label for="id">`)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub label: Option<String>,
+    /// Form fields only: the `autocomplete` attribute, as written.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub autocomplete: Option<String>,
+    /// Inputs only: what the field is for. One of `email`, `password`,
+    /// `username`, `search`, `phone`, `verification_code`, `address` and
+    /// `credit_card_number`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purpose: Option<String>,
+    /// Form fields only: help text, from `aria-describedby` or a short line
+    /// of text right below the field ("Use format DD/MM/YYYY").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub desc: Option<String>,
     /// Alert type: "alert", "status", "error", "success", "warning"
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub alert_type: Option<String>,
+    pub alert_type: Option<Atom>,
+    /// 1-based index of the control's `<form>` in document order: the one its
+    /// `form` attribute names, else the enclosing one. Set on form controls
+    /// (inputs, selects, textareas, buttons) and on the form landmark itself.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub form: Option<u16>,
+    /// Form landmarks only: the `action` attribute, resolved against the page URL.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub action: Option<String>,
+    /// Form landmarks only: the `method` attribute, lowercased.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
     /// Whether the element is hidden (display:none, visibility:hidden, aria-hidden, hidden attr).
     /// Hidden elements are still included so agents can see dropdown menus, accordion panels,
     /// modal content, tabs, etc. without JS execution.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub hidden: Option<bool>,
+    /// Why the element can't be seen, its own or an ancestor's. Set with
+    /// `hidden` for every reason but `OffscreenSrOnly`: screen-reader-only
+    /// text still counts as content, so `hidden` stays unset for it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hidden_reason: Option<HiddenReason>,
+    /// CSS selector that locates this element in the source HTML, for handing off to a
+    /// real browser. Only populated when selector emission is enabled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub selector: Option<String>,
     /// Bounds: [x, y, width, height]
     pub b: [i32; 4],
+    /// Whether the bounds extend outside an `overflow: hidden/scroll/auto` ancestor.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clipped: Option<bool>,
+    /// For clipped elements, the part of `b` inside the clipping ancestors.
+    /// Absent when the element is clipped entirely.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visible_b: Option<[i32; 4]>,
+    /// Set when the element or an ancestor is `position: fixed` or `sticky`,
+    /// so it stays in view however far the page is scrolled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pinned: Option<bool>,
+    /// Line and column (1-based) of the element's start tag in the source
+    /// HTML. Only populated when source positions are tracked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub src: Option<[u32; 2]>,
+    /// A note the agent left on the element with `Session::annotate`, set
+    /// only by `Session::dom`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// The page of a PDF the element is on, from 1.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<u16>,
+    /// How much the element stands out, from 0 to 1: its share of the
+    /// viewport, how near the top of the page it is and its font size, less
+    /// inside a banner or footer. `None` for hidden elements, elements
+    /// without a size and landmark markers. Left out of the compact format.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prominence: Option<f32>,
+    /// What the element's text or background color suggests, such as
+    /// `Danger` for red. `None` when the page gives it no color. Left out of
+    /// the compact format.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tone: Option<Tone>,
+}
+
+/// Where an element's text came from: printed on the page, or only an
+/// accessible name for an element without visible text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TextSource {
+    /// Text the page shows.
+    Visible,
+    /// The `aria-label` attribute.
+    AriaLabel,
+    /// The `title` attribute.
+    Title,
+    /// An image's `alt` text: the element's own, or an image inside it.
+    ImgAlt,
+    /// The `<title>` of an SVG icon inside the element.
+    SvgTitle,
+}
+
+impl TextSource {
+    fn is_visible_or_none(source: &Option<TextSource>) -> bool {
+        matches!(source, None | Some(TextSource::Visible))
+    }
+}
+
+/// Why an element can't be seen on the page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HiddenReason {
+    /// `display: none`, as closed menus, tabs and modals use.
+    Display,
+    /// `visibility: hidden`.
+    Visibility,
+    /// `aria-hidden="true"`: decoration kept from assistive technology.
+    AriaHidden,
+    /// The `hidden` attribute.
+    HiddenAttr,
+    /// Pushed off screen or clipped to nothing but still read aloud, the way
+    /// `.sr-only` / `.visually-hidden` classes work.
+    OffscreenSrOnly,
+}
+
+impl HiddenReason {
+    /// Whether the reason sets `hidden`. Screen-reader-only text is still
+    /// part of the page's content, so it doesn't.
+    pub fn hides(self) -> bool {
+        self != HiddenReason::OffscreenSrOnly
+    }
+}
+
+impl std::str::FromStr for HiddenReason {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('-', "_").as_str() {
+            "display" => Ok(HiddenReason::Display),
+            "visibility" => Ok(HiddenReason::Visibility),
+            "aria_hidden" => Ok(HiddenReason::AriaHidden),
+            "hidden_attr" => Ok(HiddenReason::HiddenAttr),
+            "offscreen_sr_only" => Ok(HiddenReason::OffscreenSrOnly),
+            other => Err(format!(
+                "unknown hidden reason '{other}' (expected display, visibility, aria_hidden, hidden_attr or offscreen_sr_only)"
+            )),
+        }
+    }
+}
+
+/// The hidden elements `scope=visible` drops by default: aria-hidden
+/// decoration. Elements hidden with `display: none` and the like stay, marked
+/// hidden, so agents can still find closed menus and panels.
+pub const DEFAULT_HIDDEN_DROP: &[HiddenReason] = &[HiddenReason::AriaHidden];
+
+impl SpatialElement {
+    /// Whether the element lies entirely outside its overflow container, so a
+    /// user would have to scroll that container to see it.
+    pub fn is_fully_clipped(&self) -> bool {
+        self.clipped == Some(true) && self.visible_b.is_none()
+    }
+
+    /// The bounds a user sees without scrolling any container: `visible_b`
+    /// for partially clipped elements, otherwise `b`.
+    pub fn effective_bounds(&self) -> [i32; 4] {
+        self.visible_b.unwrap_or(self.b)
+    }
 }
 
 /// A suggested action for the agent based on page content analysis.
@@ -131,10 +409,18 @@ pub enum SuggestedAction {
         submit_id: u32,
         #[serde(skip_serializing_if = "Option::is_none")]
         code_length: Option<usize>,
+        /// Set when the input was guessed: the only text input on a page
+        /// that mentions verification.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        confidence: Option<f32>,
     },
     Search {
         input_id: u32,
-        submit_id: u32,
+        /// `None` when the box has no button; pressing Enter in it submits.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        submit_id: Option<u32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        note: Option<String>,
     },
     Consent {
         approve_ids: Vec<u32>,
@@ -154,6 +440,9 @@ pub enum SuggestedAction {
         #[serde(skip_serializing_if = "Option::is_none")]
         prev_id: Option<u32>,
     },
+    LoadMore {
+        button_id: u32,
+    },
     Register {
         #[serde(skip_serializing_if = "Option::is_none")]
         email_id: Option<u32>,
@@ -217,12 +506,30 @@ pub struct FormField {
     pub input_type: Option<String>,
 }
 
+impl From<&SpatialElement> for FormField {
+    fn from(e: &SpatialElement) -> Self {
+        FormField {
+            id: e.id,
+            label: e.label.clone().or_else(|| e.ph.clone()),
+            name: e.name.clone(),
+            input_type: e.input_type.clone(),
+        }
+    }
+}
+
+/// A group of form fields and the button that submits them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FormGroup {
+    pub fields: Vec<FormField>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submit_id: Option<u32>,
+}
+
 impl SpatialDom {
-    /// Deserialize from JSON and rebuild the ID index.
+    /// Deserialize from JSON of any schema version (see [`migrate`]) and
+    /// rebuild the ID index.
     pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
-        let mut dom: SpatialDom = serde_json::from_str(json)?;
-        dom.rebuild_index();
-        Ok(dom)
+        migrate(json)
     }
 
     /// O(1) element lookup by ID.
@@ -230,6 +537,24 @@ impl SpatialDom {
         self.id_index.get(&id).map(|&idx| &self.els[idx])
     }
 
+    /// The JS behaviors the page's elements trigger, each with the ID of
+    /// the element it was read from. Empty for a Spatial DOM read back from
+    /// JSON.
+    pub fn behaviors(&self) -> &[JsBehavior] {
+        &self.behaviors
+    }
+
+    /// Record the width the page was laid out at (see
+    /// [`crate::css::layout_viewport_width`]).
+    pub fn set_layout_width(&mut self, width: f32) {
+        self.layout_vp = (width != self.vp[0]).then_some([width, self.vp[1]]);
+    }
+
+    /// Layout viewport size: the coordinate space element bounds are in.
+    pub fn layout_size(&self) -> [f32; 2] {
+        self.layout_vp.unwrap_or(self.vp)
+    }
+
     /// Rebuild the ID index (call after mutating `els`).
     pub fn rebuild_index(&mut self) {
         self.id_index = self.els.iter().enumerate().map(|(i, e)| (e.id, i)).collect();
@@ -240,38 +565,451 @@ impl SpatialDom {
         self.els.iter().filter(|e| e.hidden != Some(true)).collect()
     }
 
-    /// Return elements whose top edge is within the viewport (above the fold).
+    /// Return the headings, hidden ones too, top to bottom and then left to
+    /// right. Each has its `level`.
+    pub fn headings(&self) -> Vec<&SpatialElement> {
+        let mut headings: Vec<&SpatialElement> =
+            self.els.iter().filter(|e| e.role.as_deref() == Some("heading")).collect();
+        headings.sort_by_key(|e| (e.b[1], e.b[0]));
+        headings
+    }
+
+    /// Return elements within the viewport at the current scroll offset.
+    /// Clipped elements count by their visible part; fully clipped ones are excluded.
+    /// Pinned (fixed or sticky) elements are always included.
     pub fn above_fold(&self) -> Vec<&SpatialElement> {
-        let fold_y = self.vp[1] as i32;
-        self.els.iter().filter(|e| e.b[1] < fold_y).collect()
+        let window = self.fold_window();
+        self.els.iter().filter(|e| is_above_fold(e, window)).collect()
+    }
+
+    /// Return elements that are neither hidden nor clipped entirely by an
+    /// overflow container.
+    pub fn on_screen(&self) -> Vec<&SpatialElement> {
+        self.els
+            .iter()
+            .filter(|e| e.hidden != Some(true) && !e.is_fully_clipped())
+            .collect()
+    }
+
+    /// Elements whose text contains `text`, ignoring case and runs of
+    /// whitespace. Elements whose whole text matches come first; within each
+    /// group the most prominent come first, then document order.
+    pub fn find_by_text(&self, text: &str) -> Vec<&SpatialElement> {
+        let needle = normalize_for_match(text);
+        let mut found: Vec<(bool, &SpatialElement)> = self
+            .els
+            .iter()
+            .filter_map(|e| {
+                let haystack = normalize_for_match(e.text.as_deref()?);
+                haystack.contains(&needle).then_some((haystack != needle, e))
+            })
+            .collect();
+        found.sort_by(|(a_partial, a), (b_partial, b)| {
+            a_partial.cmp(b_partial).then(prominence_rank(b).total_cmp(&prominence_rank(a)))
+        });
+        found.into_iter().map(|(_, e)| e).collect()
+    }
+
+    /// The most prominent element `filter` accepts, the first in document
+    /// order on a tie. Elements without a `prominence`, such as hidden ones,
+    /// are only picked when no other element matches.
+    pub fn most_prominent(&self, filter: impl Fn(&SpatialElement) -> bool) -> Option<&SpatialElement> {
+        most_prominent_of(self.els.iter().filter(|e| filter(e)))
+    }
+
+    /// Elements whose text contains `text` exactly as given (case-sensitive),
+    /// in document order.
+    pub fn find_by_text_exact(&self, text: &str) -> Vec<&SpatialElement> {
+        self.els
+            .iter()
+            .filter(|e| e.text.as_deref().is_some_and(|t| t.contains(text)))
+            .collect()
+    }
+
+    /// Elements with the given ARIA role, or any of a comma-separated list of
+    /// them (`"button,link"`).
+    pub fn find_by_role(&self, role: &str) -> Vec<&SpatialElement> {
+        let roles: Vec<&str> = role.split(',').map(str::trim).filter(|r| !r.is_empty()).collect();
+        self.els
+            .iter()
+            .filter(|e| e.role.as_deref().is_some_and(|r| roles.contains(&r)))
+            .collect()
     }
 
-    /// Return elements whose top edge is below the viewport fold.
+    /// The element whose `label` matches `label`, ignoring case and surrounding
+    /// whitespace. Falls back to the first label containing it.
+    pub fn get_by_label(&self, label: &str) -> Option<&SpatialElement> {
+        let wanted = label.trim().to_lowercase();
+        if wanted.is_empty() {
+            return None;
+        }
+        let labelled = || self.els.iter().filter_map(|e| Some((e, e.label.as_deref()?.trim().to_lowercase())));
+        labelled()
+            .find(|(_, l)| *l == wanted)
+            .or_else(|| labelled().find(|(_, l)| l.contains(&wanted)))
+            .map(|(e, _)| e)
+    }
+
+    /// Data-entry fields grouped by their `<form>`, in document order, each
+    /// with the form's first submit button. Fields outside any form are
+    /// grouped by the submit button that follows them; those after the last
+    /// one form a group without one.
+    pub fn forms(&self) -> Vec<FormGroup> {
+        let mut groups: Vec<(Option<u16>, FormGroup)> = Vec::new();
+        // The group collecting fields outside any form, until a button ends it
+        let mut loose: Option<usize> = None;
+        for el in &self.els {
+            let field = is_data_entry_field(el);
+            if !field && !is_submit_button(el) {
+                continue;
+            }
+            let group = match el.form {
+                Some(form) => groups.iter().position(|(f, _)| *f == Some(form)),
+                None => loose,
+            };
+            let group = match group {
+                Some(group) => group,
+                // A button with no fields before it submits nothing
+                None if !field && el.form.is_none() => continue,
+                None => {
+                    groups.push((el.form, FormGroup { fields: Vec::new(), submit_id: None }));
+                    groups.len() - 1
+                }
+            };
+            if el.form.is_none() {
+                loose = if field { Some(group) } else { None };
+            }
+            let form = &mut groups[group].1;
+            if field {
+                form.fields.push(FormField::from(el));
+            } else if form.submit_id.is_none() {
+                form.submit_id = Some(el.id);
+            }
+        }
+        groups
+            .into_iter()
+            .map(|(_, form)| form)
+            .filter(|form| !form.fields.is_empty())
+            .collect()
+    }
+
+    /// Return elements whose top edge is below the viewport fold at the
+    /// current scroll offset. Pinned elements never are.
     pub fn below_fold(&self) -> Vec<&SpatialElement> {
-        let fold_y = self.vp[1] as i32;
-        self.els.iter().filter(|e| e.b[1] >= fold_y).collect()
+        let (_, fold_y) = self.fold_window();
+        self.els
+            .iter()
+            .filter(|e| e.pinned != Some(true) && e.b[1] >= fold_y)
+            .collect()
+    }
+
+    /// Return a new SpatialDom without the elements hidden for one of the
+    /// `drop` reasons. [`DEFAULT_HIDDEN_DROP`] is what `scope=visible` uses.
+    pub fn strip_hidden(&self, drop: &[HiddenReason]) -> SpatialDom {
+        self.view(Scope::All).strip_hidden(drop).to_dom()
+    }
+
+    /// Return a new SpatialDom without hidden or fully clipped elements,
+    /// whatever hid them; screen-reader-only text stays.
+    pub fn filter_on_screen(&self) -> SpatialDom {
+        self.view(Scope::OnScreen).to_dom()
     }
 
-    /// Return a new SpatialDom with only above-fold elements (for token-limited contexts).
+    /// Return a new SpatialDom with only above-fold elements (for token-limited
+    /// contexts), plus the context that explains them: see [`FoldOptions`].
     pub fn filter_above_fold(&self) -> SpatialDom {
-        let fold_y = self.vp[1] as i32;
-        let els: Vec<SpatialElement> = self.els.iter().filter(|e| e.b[1] < fold_y).cloned().collect();
+        self.view(Scope::AboveFold).to_dom()
+    }
+
+    /// Like `filter_above_fold`, with the cutoff half a viewport further down.
+    pub fn filter_fold_plus(&self) -> SpatialDom {
+        self.view(Scope::FoldPlus).to_dom()
+    }
+
+    /// Return a new SpatialDom with the elements inside the fold window, the
+    /// label of each kept form control, the nearest heading above each kept
+    /// element, and every visible alert wherever it is.
+    pub fn filter_above_fold_with(&self, options: &FoldOptions) -> SpatialDom {
+        self.view(Scope::All).above_fold_with(options).to_dom()
+    }
+
+    /// The part of the page holding its main content, as `[x, y, width,
+    /// height]`: the `main` landmark when there is one, otherwise the column
+    /// with the most text and controls outside the header, navigation,
+    /// sidebar and footer landmarks. `None` for an empty page.
+    pub fn main_content_region(&self) -> Option<[i32; 4]> {
+        let all: Vec<usize> = (0..self.els.len()).collect();
+        self.main_region(&all).map(|(region, _)| region)
+    }
+
+    /// Return a new SpatialDom with only the elements centered inside
+    /// [`main_content_region`](Self::main_content_region), plus every visible
+    /// alert wherever it is. `outside_main` counts what was left out.
+    pub fn filter_main_content(&self) -> SpatialDom {
+        self.view(Scope::Main).to_dom()
+    }
+
+    /// A copy with only the elements at `idx`, which are in document order.
+    fn select(&self, idx: &[usize]) -> SpatialDom {
+        let els: Vec<SpatialElement> = idx.iter().map(|&i| self.els[i].clone()).collect();
         let id_index = els.iter().enumerate().map(|(i, e)| (e.id, i)).collect();
         SpatialDom {
+            schema: self.schema,
             url: self.url.clone(),
             title: self.title.clone(),
+            lang: self.lang.clone(),
+            lang_confidence: self.lang_confidence,
             vp: self.vp,
+            layout_vp: self.layout_vp,
             scroll: self.scroll,
             suggested_actions: self.suggested_actions.clone(),
             page_type: self.page_type.clone(),
             captcha: self.captcha.clone(),
             blocked: self.blocked.clone(),
+            article: self.article.clone(),
+            auto_actions: self.auto_actions.clone(),
+            warnings: self.warnings.clone(),
+            not_modified: self.not_modified,
+            boilerplate_suppressed: self.boilerplate_suppressed,
+            outside_main: self.outside_main,
+            parse_stats: self.parse_stats.clone(),
             els,
+            text_limit: self.text_limit,
             id_index,
+            breadcrumbs: self.breadcrumbs.clone(),
+            prices: self.prices.clone(),
+            behaviors: self.behaviors.clone(),
+        }
+    }
+
+    /// The indices in `from` of elements not hidden for one of the `drop`
+    /// reasons.
+    fn unhidden_indices(&self, from: &[usize], drop: &[HiddenReason]) -> Vec<usize> {
+        from.iter()
+            .copied()
+            .filter(|&i| !self.els[i].hidden_reason.is_some_and(|r| drop.contains(&r)))
+            .collect()
+    }
+
+    /// The indices in `from` of elements neither hidden nor fully clipped.
+    fn on_screen_indices(&self, from: &[usize]) -> Vec<usize> {
+        from.iter()
+            .copied()
+            .filter(|&i| self.els[i].hidden != Some(true) && !self.els[i].is_fully_clipped())
+            .collect()
+    }
+
+    /// The indices in `from` that [`filter_above_fold_with`](Self::filter_above_fold_with)
+    /// keeps, looking for labels and headings among `from` only.
+    fn fold_indices(&self, from: &[usize], options: &FoldOptions) -> Vec<usize> {
+        let (top, bottom) = self.fold_window();
+        let window = (top, bottom.saturating_add(options.extend as i32));
+        let candidates: Vec<&SpatialElement> = from.iter().map(|&i| &self.els[i]).collect();
+        let in_window: Vec<&SpatialElement> = candidates.iter().copied().filter(|e| is_above_fold(e, window)).collect();
+
+        let mut keep: HashSet<u32> = in_window.iter().map(|e| e.id).collect();
+        for el in &in_window {
+            if let Some(label) = label_element(&candidates, el) {
+                keep.insert(label.id);
+            }
+            if let Some(heading) = heading_above(&candidates, el, options.heading_window) {
+                keep.insert(heading.id);
+            }
+        }
+        keep.extend(
+            candidates
+                .iter()
+                .filter(|e| e.alert_type.is_some() && e.hidden != Some(true))
+                .map(|e| e.id),
+        );
+        from.iter().copied().filter(|&i| keep.contains(&self.els[i].id)).collect()
+    }
+
+    /// The indices in `from` that [`filter_main_content`](Self::filter_main_content)
+    /// keeps, with the main region found among `from` only.
+    fn main_indices(&self, from: &[usize]) -> Vec<usize> {
+        let Some((region, is_landmark)) = self.main_region(from) else {
+            return from.to_vec();
+        };
+        // Without a main landmark, chrome overlapping the column still goes
+        let chrome: Vec<[i32; 4]> = if is_landmark { Vec::new() } else { self.chrome_bounds(from).collect() };
+        from.iter()
+            .copied()
+            .filter(|&i| {
+                let e = &self.els[i];
+                let b = e.effective_bounds();
+                (e.alert_type.is_some() && e.hidden != Some(true))
+                    || (center_within(&b, &region) && !chrome.iter().any(|c| bounds_contain(c, &b)))
+            })
+            .collect()
+    }
+
+    /// The main content region among the elements at `from`, and whether it
+    /// is the `main` landmark.
+    fn main_region(&self, from: &[usize]) -> Option<([i32; 4], bool)> {
+        let els = || from.iter().map(|&i| &self.els[i]);
+        let main = els()
+            .filter(|e| e.role.as_deref() == Some("main") && e.hidden != Some(true))
+            .map(|e| e.effective_bounds())
+            .filter(|b| b[2] > 0 && b[3] > 0)
+            .max_by_key(|b| b[2] as i64 * b[3] as i64);
+        if let Some(main) = main {
+            return Some((main, true));
+        }
+
+        let chrome: Vec<[i32; 4]> = self.chrome_bounds(from).collect();
+        let content: Vec<&SpatialElement> = els()
+            .filter(|e| e.hidden != Some(true) && !e.is_fully_clipped())
+            .filter(|e| !e.role.as_deref().is_some_and(|r| LANDMARK_ROLES.contains(&r)))
+            .filter(|e| {
+                let b = e.effective_bounds();
+                b[2] > 0 && b[3] > 0 && !chrome.iter().any(|c| bounds_contain(c, &b))
+            })
+            .collect();
+
+        // Columns are runs of elements overlapping horizontally. Elements
+        // spanning most of the page, like a title bar, would join them all.
+        let wide = self.layout_size()[0] * 0.75;
+        let mut narrow: Vec<&SpatialElement> =
+            content.iter().copied().filter(|e| (e.effective_bounds()[2] as f32) < wide).collect();
+        narrow.sort_by_key(|e| e.effective_bounds()[0]);
+        let mut columns: Vec<([i32; 4], usize)> = Vec::new();
+        for el in narrow {
+            let b = el.effective_bounds();
+            match columns.last_mut() {
+                Some((column, weight)) if b[0] < column[0] + column[2] => {
+                    *column = bounds_union(column, &b);
+                    *weight += content_weight(el);
+                }
+                _ => columns.push((b, content_weight(el))),
+            }
+        }
+        let densest = columns.into_iter().max_by_key(|(_, weight)| *weight).map(|(b, _)| b);
+        densest
+            .or_else(|| content.iter().map(|e| e.effective_bounds()).reduce(|a, b| bounds_union(&a, &b)))
+            .map(|region| (region, false))
+    }
+
+    /// Bounds of the header, navigation, sidebar and footer landmarks among
+    /// the elements at `from`.
+    fn chrome_bounds<'a>(&'a self, from: &'a [usize]) -> impl Iterator<Item = [i32; 4]> + 'a {
+        from.iter()
+            .map(|&i| &self.els[i])
+            .filter(|e| e.hidden != Some(true))
+            .filter(|e| e.role.as_deref().is_some_and(|r| CHROME_ROLES.contains(&r)))
+            .map(|e| e.effective_bounds())
+    }
+
+    /// Top and bottom of the visible page area, in page coordinates. At the top
+    /// of the page the window is open upward so content pulled above y=0 still
+    /// counts.
+    fn fold_window(&self) -> (i32, i32) {
+        let scroll_y = self.scroll[1] as i32;
+        let top = if scroll_y > 0 { scroll_y } else { i32::MIN };
+        (top, scroll_y.max(0) + self.vp[1] as i32)
+    }
+}
+
+/// The `<label>` element among `els` naming a form control, nearest first.
+fn label_element<'a>(els: &[&'a SpatialElement], el: &SpatialElement) -> Option<&'a SpatialElement> {
+    if !matches!(el.tag.as_str(), "input" | "select" | "textarea") {
+        return None;
+    }
+    let label = el.label.as_deref()?.trim();
+    els.iter()
+        .copied()
+        .filter(|e| e.tag == "label" && !e.is_fully_clipped())
+        .filter(|e| e.text.as_deref().is_some_and(|t| t.trim() == label))
+        .min_by_key(|e| (e.b[1] - el.b[1]).abs())
+}
+
+/// The closest heading among `els` starting above `el`, at most `window`
+/// pixels up.
+fn heading_above<'a>(els: &[&'a SpatialElement], el: &SpatialElement, window: f32) -> Option<&'a SpatialElement> {
+    if window <= 0.0 || is_heading(el) {
+        return None;
+    }
+    let y = el.b[1];
+    els.iter()
+        .copied()
+        .filter(|h| is_heading(h) && h.hidden != Some(true) && !h.is_fully_clipped())
+        .filter(|h| h.b[1] <= y && (y - h.b[1]) as f32 <= window)
+        .max_by_key(|h| h.b[1])
+}
+
+/// How `SpatialDom::filter_above_fold_with` draws the fold.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FoldOptions {
+    /// Pixels the cutoff is moved down past the bottom of the viewport.
+    pub extend: f32,
+    /// How far above a kept element, in pixels, the heading of its section
+    /// may start and still be kept with it. 0 keeps no extra headings.
+    pub heading_window: f32,
+}
+
+impl Default for FoldOptions {
+    fn default() -> Self {
+        Self {
+            extend: 0.0,
+            heading_window: 400.0,
         }
     }
 }
 
+fn is_heading(el: &SpatialElement) -> bool {
+    matches!(el.tag.as_str(), "h1" | "h2" | "h3" | "h4" | "h5" | "h6")
+}
+
+fn is_above_fold(el: &SpatialElement, (top, bottom): (i32, i32)) -> bool {
+    if el.is_fully_clipped() {
+        return false;
+    }
+    if el.pinned == Some(true) {
+        return true;
+    }
+    let b = el.effective_bounds();
+    b[1] < bottom && b[1].saturating_add(b[3]) > top
+}
+
+/// `prominence` for ordering, below any real one when there isn't one.
+fn prominence_rank(el: &SpatialElement) -> f32 {
+    el.prominence.unwrap_or(-1.0)
+}
+
+/// The element of `els` with the highest `prominence`, the first on a tie.
+fn most_prominent_of<'a>(els: impl Iterator<Item = &'a SpatialElement>) -> Option<&'a SpatialElement> {
+    els.reduce(|best, el| if prominence_rank(el) > prominence_rank(best) { el } else { best })
+}
+
+/// Landmark roles of what surrounds a page's main content.
+const CHROME_ROLES: &[&str] = &["navigation", "banner", "contentinfo", "complementary"];
+
+/// Whether `inner` lies within `outer`, give or take a pixel of rounding.
+fn bounds_contain(outer: &[i32; 4], inner: &[i32; 4]) -> bool {
+    inner[0] >= outer[0] - 1
+        && inner[1] >= outer[1] - 1
+        && inner[0] + inner[2] <= outer[0] + outer[2] + 1
+        && inner[1] + inner[3] <= outer[1] + outer[3] + 1
+}
+
+fn center_within(b: &[i32; 4], region: &[i32; 4]) -> bool {
+    let (x, y) = (b[0] + b[2] / 2, b[1] + b[3] / 2);
+    x >= region[0] && x <= region[0] + region[2] && y >= region[1] && y <= region[1] + region[3]
+}
+
+fn bounds_union(a: &[i32; 4], b: &[i32; 4]) -> [i32; 4] {
+    let (left, top) = (a[0].min(b[0]), a[1].min(b[1]));
+    let right = (a[0] + a[2]).max(b[0] + b[2]);
+    let bottom = (a[1] + a[3]).max(b[1] + b[3]);
+    [left, top, right - left, bottom - top]
+}
+
+/// How much an element adds to its column: its text, or 1 for a control
+/// without any.
+fn content_weight(el: &SpatialElement) -> usize {
+    el.text.as_deref().map_or(0, |t| t.chars().count()).max(1)
+}
+
 /// Tags that are always interactive.
 const INTERACTIVE_TAGS: &[&str] = &[
     "a", "button", "input", "select", "textarea", "details", "summary",
@@ -289,45 +1027,141 @@ const WRAPPER_TAGS: &[&str] = &["li", "td", "th", "span", "p", "dt", "dd"];
 /// Landmark tags — emitted as structural markers (role only, no recursive text).
 const LANDMARK_TAGS: &[&str] = &["nav", "main", "header", "footer", "aside", "section", "form"];
 
+/// Element text longer than this many characters is cut short in the
+/// compact format unless configured otherwise.
+pub const DEFAULT_TEXT_LIMIT: usize = 200;
+
+/// Most of an input's suggestions written out in JSON and the compact format.
+pub const MAX_SUGGESTIONS: usize = 20;
+
+/// Optional extras for Spatial DOM generation. Extras are off by default to
+/// keep the output small.
+#[derive(Debug, Clone)]
+pub struct OutputOptions {
+    /// Compute a CSS selector hint for every element (`SpatialElement::selector`).
+    pub emit_selectors: bool,
+    /// Becomes the page's [`SpatialDom::text_limit`].
+    pub text_limit: Option<usize>,
+    /// Pages with at least this many elements detect their page type and
+    /// suggested actions on rayon's thread pool. `usize::MAX` never does.
+    /// The result is the same either way.
+    pub parallel_min_elements: usize,
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        Self {
+            emit_selectors: false,
+            text_limit: Some(DEFAULT_TEXT_LIMIT),
+            parallel_min_elements: DEFAULT_PARALLEL_MIN_ELEMENTS,
+        }
+    }
+}
+
 /// Generate the Spatial DOM from a laid-out tree.
 pub fn generate_spatial_dom(
     root: &LayoutNode,
     viewport_width: f32,
     viewport_height: f32,
+) -> SpatialDom {
+    generate_spatial_dom_with_options(root, viewport_width, viewport_height, &OutputOptions::default())
+}
+
+/// Generate the Spatial DOM from a laid-out tree with optional extras enabled.
+pub fn generate_spatial_dom_with_options(
+    root: &LayoutNode,
+    viewport_width: f32,
+    viewport_height: f32,
+    options: &OutputOptions,
 ) -> SpatialDom {
     let mut els = Vec::new();
-    let mut id_counter = 1u32;
+    let mut state = CollectState {
+        next_id: 1,
+        forms_seen: 0,
+        form: None,
+        form_ids: HashMap::new(),
+        zero_size_skipped: 0,
+        text_owner: None,
+        datalists: HashMap::new(),
+        descriptions: HashMap::new(),
+        list_depth: 0,
+        page_edge: 0,
+        noscript: 0,
+        viewport: [viewport_width, viewport_height],
+        atoms: HashSet::new(),
+        behaviors: Vec::new(),
+    };
 
     // Collect label associations: HTML id -> label text
     let label_map = collect_label_associations(root);
+    collect_datalists(root, &mut state.datalists);
+    state.form_ids = collect_form_ids(root);
+    state.descriptions = collect_descriptions(root);
 
-    collect_elements(root, &mut els, &mut id_counter, false, &label_map);
+    let selectors = if options.emit_selectors {
+        Some(SelectorContext::new(root))
+    } else {
+        None
+    };
+
+    collect_elements(root, &mut els, &mut state, None, &label_map, selectors.as_ref(), "");
+    attach_help_text(&mut els);
+    mark_field_errors(&mut els);
 
     // Extract title from the tree
     let title = find_title(root).unwrap_or_default();
+    let (lang, lang_confidence) = match find_html_lang(root) {
+        Some(declared) => (Some(declared), Some(1.0)),
+        None => match lang::detect(&visible_text_sample(&els)) {
+            Some(d) => (Some(d.lang.to_string()), Some(d.confidence)),
+            None => (None, None),
+        },
+    };
 
     // Scan the layout tree for CAPTCHA signals before building the SpatialDom.
     // This must happen before detect_page_type since CAPTCHA detection uses these signals.
     let captcha = detect_captcha_from_tree(root);
+    let breadcrumb_hints = detect_breadcrumbs_from_tree(root, viewport_height);
+    let product_hints = detect_product_from_tree(root);
+    let article = detect_article_meta(root);
 
     let id_index = els.iter().enumerate().map(|(i, e)| (e.id, i)).collect();
     let mut dom = SpatialDom {
+        schema: SCHEMA_VERSION,
         url: String::new(), // Set by caller
         title,
+        lang,
+        lang_confidence,
         vp: [viewport_width, viewport_height],
+        layout_vp: None,
         scroll: [0.0, 0.0],
         suggested_actions: Vec::new(),
         page_type: PageType::Other,
         captcha,
         blocked: None,
+        article,
+        auto_actions: Vec::new(),
+        warnings: Vec::new(),
+        not_modified: false,
+        boilerplate_suppressed: 0,
+        outside_main: 0,
+        parse_stats: ParseStats { zero_size_skipped: state.zero_size_skipped },
         els,
+        text_limit: options.text_limit,
         id_index,
+        breadcrumbs: Vec::new(),
+        prices: Vec::new(),
+        behaviors: state.behaviors,
     };
+    dom.breadcrumbs = breadcrumb_hints.resolve(&dom);
+    dom.prices = detect_prices(&dom, &product_hints.struck);
 
     // Detect page type and suggested actions
-    dom.blocked = detect_blocked_info(&dom);
-    dom.page_type = detect_page_type(&dom);
-    dom.suggested_actions = detect_suggested_actions(&dom);
+    let parallel = dom.els.len() >= options.parallel_min_elements;
+    let index = PageIndex::new(&dom, parallel);
+    dom.blocked = detect_blocked_info(&dom, &index);
+    dom.page_type = detect_page_type(&dom, &index, product_hints.markup);
+    dom.suggested_actions = detect_suggested_actions(&dom, &index, parallel);
 
     dom
 }
@@ -357,75 +1191,382 @@ fn collect_labels_recursive(node: &LayoutNode, map: &mut std::collections::HashM
     }
 }
 
-/// Resolve all relative URLs in the SpatialDom against a base URL.
-pub fn resolve_urls(dom: &mut SpatialDom, base_url: &str) {
-    // Try to parse base URL; if invalid, skip resolution
-    let base = match url::Url::parse(base_url) {
-        Ok(u) => u,
-        Err(_) => return,
-    };
-
-    for el in &mut dom.els {
-        if let Some(ref href) = el.href {
-            // Skip already-absolute URLs, javascript:, mailto:, tel:, data:, #anchors
-            if href.starts_with("http://")
-                || href.starts_with("https://")
-                || href.starts_with("javascript:")
-                || href.starts_with("mailto:")
-                || href.starts_with("tel:")
-                || href.starts_with("data:")
-                || href.starts_with('#')
-            {
-                continue;
-            }
-            // Resolve relative URL
-            if let Ok(resolved) = base.join(href) {
-                el.href = Some(resolved.to_string());
+/// Map the HTML ids of `<form>` elements onto their 1-based index, counted as
+/// the element walk counts them.
+fn collect_form_ids(root: &LayoutNode) -> HashMap<String, u16> {
+    fn walk(node: &LayoutNode, seen: &mut u16, ids: &mut HashMap<String, u16>) {
+        if node.tag == "form" && node.node_type == NodeType::Element {
+            *seen += 1;
+            if let Some(id) = node.attributes.get("id") {
+                ids.entry(id.clone()).or_insert(*seen);
             }
+            // The walk doesn't count forms inside forms either
+            return;
+        }
+        for child in &node.children {
+            walk(child, seen, ids);
         }
     }
+
+    let mut ids = HashMap::new();
+    walk(root, &mut 0, &mut ids);
+    ids
 }
 
-fn collect_elements(
-    node: &LayoutNode,
-    els: &mut Vec<SpatialElement>,
-    id_counter: &mut u32,
-    parent_hidden: bool,
-    label_map: &std::collections::HashMap<String, String>,
-) {
-    // aria-hidden="true" hides the element and all children
-    let aria_hidden = node
-        .attributes
-        .get("aria-hidden")
-        .map(|v| v == "true")
-        .unwrap_or(false);
-
-    // Determine if this node is hidden (cascades to children)
-    let is_hidden = parent_hidden
-        || node.style.display == Display::None
-        || node.style.visibility == Visibility::Hidden
-        || aria_hidden
-        || node.attributes.contains_key("hidden");
-
-    // Skip zero-size visible elements (layout artifacts, not meaningful content)
-    if !is_hidden
-        && node.bounds.width <= 0.0
-        && node.bounds.height <= 0.0
-        && node.node_type == NodeType::Element
-    {
+/// Map the HTML ids that `aria-describedby` attributes refer to onto the
+/// text of the elements with those ids.
+fn collect_descriptions(root: &LayoutNode) -> HashMap<String, String> {
+    fn referenced(node: &LayoutNode, ids: &mut HashSet<String>) {
+        if let Some(refs) = node.attributes.get("aria-describedby") {
+            ids.extend(refs.split_whitespace().map(str::to_string));
+        }
         for child in &node.children {
-            collect_elements(child, els, id_counter, is_hidden, label_map);
+            referenced(child, ids);
+        }
+    }
+    fn texts(node: &LayoutNode, ids: &HashSet<String>, map: &mut HashMap<String, String>) {
+        if let Some(id) = node.attributes.get("id").filter(|id| ids.contains(*id)) {
+            let text = normalize_ws(&collect_visible_text(node));
+            if !text.is_empty() {
+                map.insert(id.clone(), text);
+            }
+        }
+        for child in &node.children {
+            texts(child, ids, map);
         }
-        return;
     }
 
-    let tag = node.tag.as_str();
-    let is_interactive = INTERACTIVE_TAGS.contains(&tag)
-        || node.attributes.contains_key("onclick")
-        || node.attributes.contains_key("role")
-        || node.attributes.get("tabindex").is_some();
+    let mut ids = HashSet::new();
+    referenced(root, &mut ids);
+    let mut map = HashMap::new();
+    if !ids.is_empty() {
+        texts(root, &ids, &mut map);
+    }
+    map
+}
 
-    let is_text = TEXT_TAGS.contains(&tag);
+/// Farthest below a form field, in pixels, that a line of text can start and
+/// still be taken for the field's help text.
+const HELP_TEXT_GAP: i32 = 40;
+
+/// Farthest above a form field, in pixels, that text ends and is taken for
+/// the field's label rather than help text for the field above.
+const LABEL_GAP: i32 = 12;
+
+/// Longest text, in characters, taken for a field's help text.
+const HELP_TEXT_MAX_CHARS: usize = 160;
+
+/// Tags help text under a field is usually written in.
+const HELP_TEXT_TAGS: &[&str] = &["p", "span", "small", "em", "i"];
+
+/// Give form fields without an `aria-describedby` description the text of a
+/// short text element directly below them, as hints like "Password must be
+/// 12+ characters" are usually laid out. Text that labels the next field
+/// (a `<label>`, a field's label text, or text with a field right under it
+/// or beside it) is left alone.
+fn attach_help_text(els: &mut [SpatialElement]) {
+    let overlaps = |a: [i32; 4], b: [i32; 4]| a[0] < b[0] + b[2] && b[0] < a[0] + a[2];
+    let below = |upper: [i32; 4], lower: [i32; 4], within: i32| {
+        let gap = lower[1] - (upper[1] + upper[3]);
+        (0..=within).contains(&gap) && overlaps(upper, lower)
+    };
+    let beside = |left: [i32; 4], right: [i32; 4]| {
+        let middle = right[1] + right[3] / 2;
+        right[0] >= left[0] + left[2] && (left[1]..=left[1] + left[3]).contains(&middle)
+    };
+
+    let fields: Vec<usize> = (0..els.len()).filter(|&i| is_form_field(&els[i])).collect();
+    let labels: HashSet<String> = fields
+        .iter()
+        .filter_map(|&i| els[i].label.as_deref().map(normalize_ws))
+        .collect();
+    let labels_a_field = |text_el: &SpatialElement| {
+        fields.iter().any(|&j| below(text_el.b, els[j].b, LABEL_GAP) || beside(text_el.b, els[j].b))
+    };
+
+    let mut found = Vec::new();
+    for &i in &fields {
+        if els[i].desc.is_some() {
+            continue;
+        }
+        let field = els[i].b;
+        let hint = els.iter().find(|el| {
+            HELP_TEXT_TAGS.contains(&el.tag.as_str())
+                && el.hidden != Some(true)
+                && below(field, el.b, HELP_TEXT_GAP)
+                && el.text.as_deref().is_some_and(|text| {
+                    text.chars().count() <= HELP_TEXT_MAX_CHARS && !labels.contains(&normalize_ws(text))
+                })
+                && !labels_a_field(el)
+        });
+        if let Some(text) = hint.and_then(|el| el.text.clone()) {
+            found.push((i, normalize_ws(&text)));
+        }
+    }
+    for (i, text) in found {
+        els[i].desc = Some(text);
+    }
+}
+
+/// A visible field that takes a value: a select, a textarea or an input
+/// that isn't a button.
+fn is_form_field(el: &SpatialElement) -> bool {
+    el.hidden != Some(true)
+        && match el.tag.as_str() {
+            "select" | "textarea" => true,
+            "input" => !matches!(el.input_type.as_deref(), Some("hidden" | "submit" | "button" | "reset" | "image")),
+            _ => false,
+        }
+}
+
+/// Farthest from a form field, in pixels, that danger-toned text can be and
+/// still be taken for the field's error message.
+const ERROR_TEXT_GAP: i32 = 40;
+
+/// Longest text, in characters, taken for a field's error message.
+const ERROR_TEXT_MAX_CHARS: usize = 120;
+
+/// Tags inline error messages are usually written in.
+const ERROR_TEXT_TAGS: &[&str] = &["p", "span", "small", "em", "i", "strong", "b", "li"];
+
+/// Mark short red text right next to a form field as an `error` alert, as
+/// inline validation like "Invalid card number" is often shown with only a
+/// color to go by. Red alone isn't enough: sale banners are red too. Required
+/// markers ("*", "(required)") are left alone.
+fn mark_field_errors(els: &mut [SpatialElement]) {
+    let fields: Vec<[i32; 4]> = els.iter().filter(|el| is_form_field(el)).map(|el| el.b).collect();
+    let near = |a: [i32; 4], b: [i32; 4]| {
+        let dx = (b[0] - (a[0] + a[2])).max(a[0] - (b[0] + b[2]));
+        let dy = (b[1] - (a[1] + a[3])).max(a[1] - (b[1] + b[3]));
+        dx <= ERROR_TEXT_GAP && dy <= ERROR_TEXT_GAP
+    };
+    for el in els.iter_mut() {
+        let is_error_text = el.tone == Some(Tone::Danger)
+            && el.alert_type.is_none()
+            && el.hidden != Some(true)
+            && ERROR_TEXT_TAGS.contains(&el.tag.as_str())
+            && el.text.as_deref().is_some_and(|text| {
+                let bare = text.trim_matches(|c: char| matches!(c, '*' | '(' | ')') || c.is_whitespace());
+                (2..=ERROR_TEXT_MAX_CHARS).contains(&bare.chars().count()) && !bare.eq_ignore_ascii_case("required")
+            });
+        if is_error_text && fields.iter().any(|&field| near(field, el.b)) {
+            el.alert_type = Some(Atom::new("error"));
+        }
+    }
+}
+
+/// Walk the tree for `<datalist id="xxx">` elements and map their IDs to
+/// their option values, in order.
+fn collect_datalists(node: &LayoutNode, map: &mut HashMap<String, Vec<String>>) {
+    if node.tag == "datalist" {
+        if let Some(id) = node.attributes.get("id") {
+            let mut values = Vec::new();
+            collect_option_values(node, &mut values);
+            map.insert(id.clone(), values);
+        }
+        return;
+    }
+    for child in &node.children {
+        collect_datalists(child, map);
+    }
+}
+
+/// Each `<option>`'s `value`, or its text when it has none.
+fn collect_option_values(node: &LayoutNode, values: &mut Vec<String>) {
+    for child in &node.children {
+        if child.tag == "option" {
+            let value = match child.attributes.get("value") {
+                Some(value) => value.trim().to_string(),
+                None => normalize_ws(&collect_visible_text(child)),
+            };
+            if !value.is_empty() && !values.contains(&value) {
+                values.push(value);
+            }
+        } else {
+            collect_option_values(child, values);
+        }
+    }
+}
+
+/// Resolve all relative URLs in the SpatialDom against a base URL.
+pub fn resolve_urls(dom: &mut SpatialDom, base_url: &str) {
+    // Try to parse base URL; if invalid, skip resolution
+    let base = match url::Url::parse(base_url) {
+        Ok(u) => u,
+        Err(_) => return,
+    };
+
+    let resolve_href = |href: &mut Option<String>| {
+        let Some(value) = href.as_deref() else { return };
+        // Skip already-absolute URLs, javascript:, mailto:, tel:, data:, #anchors
+        if value.starts_with("http://")
+            || value.starts_with("https://")
+            || value.starts_with("javascript:")
+            || value.starts_with("mailto:")
+            || value.starts_with("tel:")
+            || value.starts_with("data:")
+            || value.starts_with('#')
+        {
+            return;
+        }
+        // Resolve relative URL
+        if let Ok(resolved) = base.join(value) {
+            *href = Some(resolved.to_string());
+        }
+    };
+
+    for el in &mut dom.els {
+        // Form actions: an empty action submits to the page itself
+        if let Some(ref action) = el.action {
+            if !action.starts_with("javascript:") {
+                if let Ok(resolved) = base.join(action) {
+                    el.action = Some(resolved.to_string());
+                }
+            }
+        }
+        resolve_href(&mut el.href);
+    }
+    // JSON-LD steps use absolute URLs, so they may only match a link now
+    for crumb in &mut dom.breadcrumbs {
+        resolve_href(&mut crumb.href);
+        if crumb.id.is_none() {
+            crumb.id = dom.els.iter()
+                .find(|e| e.href.is_some() && e.href == crumb.href)
+                .map(|e| e.id);
+        }
+    }
+}
+
+/// Mutable state threaded through the element walk.
+struct CollectState {
+    /// ID for the next emitted element.
+    next_id: u32,
+    /// Number of `<form>` elements entered so far.
+    forms_seen: u16,
+    /// Index of the form currently being walked, if any.
+    form: Option<u16>,
+    /// Form indexes by HTML id, for controls placed outside their form.
+    form_ids: HashMap<String, u16>,
+    /// Zero-size elements left out; see `ParseStats`.
+    zero_size_skipped: u32,
+    /// Whitespace-normalized text of the innermost link or button being
+    /// walked. Text-tag descendants whose text it already contains are
+    /// not emitted again.
+    text_owner: Option<String>,
+    /// Option values of every `<datalist>`, by its HTML id.
+    datalists: HashMap<String, Vec<String>>,
+    /// Text of the elements `aria-describedby` refers to, by their HTML id.
+    descriptions: HashMap<String, String>,
+    /// Lists (`ul`, `ol`, `menu`, `role="list"`) the walk is inside.
+    list_depth: u8,
+    /// Banner and footer landmarks the walk is inside.
+    page_edge: u8,
+    /// `<noscript>` elements the walk is inside.
+    noscript: u8,
+    /// Viewport width and height, for `SpatialElement::prominence`.
+    viewport: [f32; 2],
+    /// Tags and roles outside [`Atom`]'s known set seen so far, so every
+    /// element with one shares a single copy.
+    atoms: HashSet<Atom>,
+    /// JS behaviors of the elements emitted so far.
+    behaviors: Vec<JsBehavior>,
+}
+
+impl CollectState {
+    /// `atom`, or the copy of it an earlier element already holds.
+    fn intern(&mut self, atom: Atom) -> Atom {
+        if !atom.is_shared() {
+            return atom;
+        }
+        if let Some(seen) = self.atoms.get(atom.as_str()) {
+            return seen.clone();
+        }
+        self.atoms.insert(atom.clone());
+        atom
+    }
+}
+
+/// Why `node` itself is hidden, ignoring its ancestors.
+fn hidden_reason(node: &LayoutNode) -> Option<HiddenReason> {
+    let style = &node.style;
+    if node.attributes.contains_key("hidden") {
+        Some(HiddenReason::HiddenAttr)
+    } else if style.display == Display::None {
+        Some(HiddenReason::Display)
+    } else if style.visibility == Visibility::Hidden {
+        Some(HiddenReason::Visibility)
+    } else if node.attributes.get("aria-hidden").is_some_and(|v| v == "true") {
+        Some(HiddenReason::AriaHidden)
+    } else if is_sr_only(style) {
+        Some(HiddenReason::OffscreenSrOnly)
+    } else {
+        None
+    }
+}
+
+/// The screen-reader-only patterns: an absolutely positioned box clipped to
+/// nothing, shrunk to a pixel, or moved far off screen.
+fn is_sr_only(style: &crate::css::LayoutStyle) -> bool {
+    if !matches!(style.position, Position::Absolute | Position::Fixed) {
+        return false;
+    }
+    let px = |d: &Dimension| match d {
+        Dimension::Px(v) => Some(*v),
+        _ => None,
+    };
+    let tiny = matches!((px(&style.width), px(&style.height)), (Some(w), Some(h)) if w <= 1.0 && h <= 1.0);
+    let offscreen = [&style.left, &style.top].into_iter().any(|d| px(d).is_some_and(|v| v <= -999.0));
+    style.clip_hidden || tiny || offscreen
+}
+
+fn collect_elements(
+    node: &LayoutNode,
+    els: &mut Vec<SpatialElement>,
+    state: &mut CollectState,
+    parent_hidden: Option<HiddenReason>,
+    label_map: &std::collections::HashMap<String, String>,
+    selectors: Option<&SelectorContext>,
+    path: &str,
+) {
+    // Everything inside a <form> belongs to it; forms cannot nest in parsed HTML.
+    if node.tag == "form" && node.node_type == NodeType::Element && state.form.is_none() {
+        state.forms_seen += 1;
+        state.form = Some(state.forms_seen);
+        collect_elements(node, els, state, parent_hidden, label_map, selectors, path);
+        state.form = None;
+        return;
+    }
+
+    // Why this node is hidden, if it is; cascades to children. Inside
+    // screen-reader-only text, a child's own reason to hide still wins.
+    let hidden = match (parent_hidden, hidden_reason(node)) {
+        (Some(parent), own) if parent.hides() || own.is_none() => Some(parent),
+        (_, own) => own,
+    };
+    let is_hidden = hidden.is_some_and(HiddenReason::hides);
+
+    // Skip zero-size visible elements (layout artifacts, not meaningful content).
+    // Positioned wrappers and inline parents our layout failed to size are
+    // still 0x0, so keep anything with text, an interactive tag or sized content.
+    if !is_hidden
+        && node.node_type == NodeType::Element
+        && is_zero_size(&node.bounds)
+        && !INTERACTIVE_TAGS.contains(&node.tag.as_str())
+        && !has_sized_descendants(node)
+        && collect_visible_text(node).is_empty()
+    {
+        state.zero_size_skipped += 1;
+        collect_children(node, els, state, hidden, label_map, selectors, path);
+        return;
+    }
+
+    let tag = node.tag.as_str();
+    let is_interactive = INTERACTIVE_TAGS.contains(&tag)
+        || node.attributes.contains_key("onclick")
+        || node.attributes.contains_key("role")
+        || node.attributes.get("tabindex").is_some();
+
+    let is_text = TEXT_TAGS.contains(&tag);
     let has_role = node.attributes.contains_key("role");
     let is_landmark = LANDMARK_TAGS.contains(&tag);
 
@@ -440,10 +1581,9 @@ fn collect_elements(
         let is_landmark_role = is_landmark || is_landmark_role_attr(node);
         if is_landmark_role {
             // Emit with empty text (role-only marker)
-            emit_element(node, els, id_counter, Some(String::new()), is_hidden, label_map);
-            for child in &node.children {
-                collect_elements(child, els, id_counter, is_hidden, label_map);
-            }
+            let selector = selectors.map(|ctx| ctx.selector_for(node, path));
+            emit_element(node, els, state, Some(String::new()), hidden, label_map, selector);
+            collect_children(node, els, state, hidden, label_map, selectors, path);
             return;
         }
 
@@ -455,9 +1595,12 @@ fn collect_elements(
                 ""
             };
             if is_trivial_text(text_content) {
-                for child in &node.children {
-                    collect_elements(child, els, id_counter, is_hidden, label_map);
-                }
+                collect_children(node, els, state, hidden, label_map, selectors, path);
+                return;
+            }
+            // Text inside a link or button already shows in its text
+            if is_covered_by_owner(node, state) {
+                collect_children(node, els, state, hidden, label_map, selectors, path);
                 return;
             }
         }
@@ -468,69 +1611,252 @@ fn collect_elements(
         let should_dedup = !is_interactive && has_interactive
             && (is_wrapper || is_text);
 
+        let selector = selectors.map(|ctx| ctx.selector_for(node, path));
         if should_dedup {
             let own_text = collect_own_text(node);
             if own_text.is_empty() || is_trivial_text(&own_text) {
-                for child in &node.children {
-                    collect_elements(child, els, id_counter, is_hidden, label_map);
-                }
+                collect_children(node, els, state, hidden, label_map, selectors, path);
                 return;
             }
-            emit_element(node, els, id_counter, Some(own_text), is_hidden, label_map);
+            emit_element(node, els, state, Some(own_text), hidden, label_map, selector);
         } else {
-            emit_element(node, els, id_counter, None, is_hidden, label_map);
+            emit_element(node, els, state, None, hidden, label_map, selector);
+            let owner_text = els.last().and_then(|e| e.text.as_deref()).map(normalize_ws);
+            if let Some(text) = owner_text.filter(|_| owns_descendant_text(node)) {
+                let outer = state.text_owner.replace(text);
+                collect_children(node, els, state, hidden, label_map, selectors, path);
+                state.text_owner = outer;
+                return;
+            }
         }
     }
 
     // Recurse into children
+    collect_children(node, els, state, hidden, label_map, selectors, path);
+}
+
+/// Recurse into a node's children, extending the selector path when enabled.
+fn collect_children(
+    node: &LayoutNode,
+    els: &mut Vec<SpatialElement>,
+    state: &mut CollectState,
+    hidden: Option<HiddenReason>,
+    label_map: &std::collections::HashMap<String, String>,
+    selectors: Option<&SelectorContext>,
+    path: &str,
+) {
+    let is_list = node.node_type == NodeType::Element
+        && (matches!(node.tag.as_str(), "ul" | "ol" | "menu")
+            || node.attributes.get("role").is_some_and(|r| r == "list"));
+    if is_list {
+        state.list_depth = state.list_depth.saturating_add(1);
+    }
+    let is_edge = node.node_type == NodeType::Element
+        && (matches!(node.tag.as_str(), "header" | "footer")
+            || node.attributes.get("role").is_some_and(|r| r == "banner" || r == "contentinfo"));
+    if is_edge {
+        state.page_edge = state.page_edge.saturating_add(1);
+    }
+    let is_noscript = node.node_type == NodeType::Element && node.tag == "noscript";
+    if is_noscript {
+        state.noscript = state.noscript.saturating_add(1);
+    }
+    let mut nth = 0;
     for child in &node.children {
-        collect_elements(child, els, id_counter, is_hidden, label_map);
+        let child_path = match selectors {
+            Some(ctx) if child.node_type == NodeType::Element => {
+                nth += 1;
+                ctx.path_segment(child, path, nth)
+            }
+            _ => String::new(),
+        };
+        collect_elements(child, els, state, hidden, label_map, selectors, &child_path);
+    }
+    if is_list {
+        state.list_depth = state.list_depth.saturating_sub(1);
+    }
+    if is_edge {
+        state.page_edge = state.page_edge.saturating_sub(1);
+    }
+    if is_noscript {
+        state.noscript = state.noscript.saturating_sub(1);
+    }
+}
+
+// --- Selector hints ---
+
+/// Document-wide counts used to decide which selector forms are unique.
+struct SelectorContext {
+    id_counts: HashMap<String, usize>,
+    name_counts: HashMap<(String, String), usize>,
+}
+
+impl SelectorContext {
+    fn new(root: &LayoutNode) -> Self {
+        let mut ctx = SelectorContext {
+            id_counts: HashMap::new(),
+            name_counts: HashMap::new(),
+        };
+        ctx.count(root);
+        ctx
+    }
+
+    fn count(&mut self, node: &LayoutNode) {
+        if node.node_type == NodeType::Element {
+            if let Some(id) = node.attributes.get("id") {
+                *self.id_counts.entry(id.clone()).or_insert(0) += 1;
+            }
+            if let Some(name) = node.attributes.get("name") {
+                *self.name_counts.entry((node.tag.clone(), name.clone())).or_insert(0) += 1;
+            }
+        }
+        for child in &node.children {
+            self.count(child);
+        }
+    }
+
+    /// Selector for a node by its `id` attribute, if the id is unique in the document.
+    fn id_selector(&self, node: &LayoutNode) -> Option<String> {
+        let id = node.attributes.get("id").filter(|id| !id.is_empty())?;
+        if self.id_counts.get(id).copied() != Some(1) {
+            return None;
+        }
+        if is_css_ident(id) {
+            Some(format!("#{}", id))
+        } else {
+            Some(format!("{}[id=\"{}\"]", node.tag, escape_css_string(id)))
+        }
+    }
+
+    /// Structural path for a child element: anchored at the nearest unique id,
+    /// otherwise `parent > tag:nth-child(n)`.
+    fn path_segment(&self, node: &LayoutNode, parent_path: &str, nth: usize) -> String {
+        if let Some(sel) = self.id_selector(node) {
+            return sel;
+        }
+        let segment = if matches!(node.tag.as_str(), "html" | "head" | "body") {
+            node.tag.clone()
+        } else {
+            format!("{}:nth-child({})", node.tag, nth)
+        };
+        if parent_path.is_empty() {
+            segment
+        } else {
+            format!("{} > {}", parent_path, segment)
+        }
+    }
+
+    /// Best selector for an emitted element: `#id`, then `tag[name="…"]`, then the path.
+    fn selector_for(&self, node: &LayoutNode, path: &str) -> String {
+        if let Some(sel) = self.id_selector(node) {
+            return sel;
+        }
+        if let Some(name) = node.attributes.get("name").filter(|n| !n.is_empty()) {
+            if self.name_counts.get(&(node.tag.clone(), name.clone())).copied() == Some(1) {
+                return format!("{}[name=\"{}\"]", node.tag, escape_css_string(name));
+            }
+        }
+        path.to_string()
+    }
+}
+
+/// Whether a value can be used verbatim after `#` in a CSS selector.
+fn is_css_ident(value: &str) -> bool {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Escape a value for use inside a double-quoted CSS attribute selector.
+fn escape_css_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\a "),
+            _ => out.push(c),
+        }
     }
+    out
 }
 
 fn emit_element(
     node: &LayoutNode,
     els: &mut Vec<SpatialElement>,
-    id_counter: &mut u32,
+    state: &mut CollectState,
     text_override: Option<String>,
-    is_hidden: bool,
+    hidden: Option<HiddenReason>,
     label_map: &std::collections::HashMap<String, String>,
+    selector: Option<String>,
 ) {
     let tag = node.tag.as_str();
+    let is_hidden = hidden.is_some_and(HiddenReason::hides);
+    let is_marker = text_override.as_deref() == Some("");
 
-    let text = if let Some(t) = text_override {
-        if t.is_empty() { None } else { Some(t) }
+    let (text, text_source) = if let Some(t) = text_override {
+        if t.is_empty() { (None, None) } else { (Some(t), Some(TextSource::Visible)) }
     } else if tag == "img" {
         // Use alt text for images
-        node.attributes.get("alt").cloned().filter(|s| !s.is_empty())
+        let alt = node.attributes.get("alt").cloned().filter(|s| !s.is_empty());
+        let source = alt.as_ref().map(|_| TextSource::ImgAlt);
+        (alt, source)
     } else {
-        let text_content = if !node.text_content.is_empty() {
+        // A textarea's text is its value, not its name
+        let text_content = if tag == "textarea" {
+            String::new()
+        } else if !node.text_content.is_empty() {
             node.text_content.clone()
         } else {
             collect_visible_text(node)
         };
-        if !text_content.is_empty() {
-            Some(text_content)
+        let attr = |name: &str, source: TextSource| {
+            node.attributes.get(name).filter(|s| !s.is_empty()).map(|s| (s.clone(), source))
+        };
+        let found = if !text_content.is_empty() {
+            Some((text_content, TextSource::Visible))
         } else {
             // Fallback chain for text-less interactive elements (links, buttons)
-            node.attributes.get("aria-label").cloned().filter(|s| !s.is_empty())
-                .or_else(|| node.attributes.get("title").cloned().filter(|s| !s.is_empty()))
+            attr("aria-label", TextSource::AriaLabel)
+                .or_else(|| attr("title", TextSource::Title))
                 .or_else(|| find_child_img_alt(node))
-        }
+        };
+        found.map_or((None, None), |(text, source)| (Some(text), Some(source)))
     };
 
-    let role = determine_role(node);
+    let role = determine_role(node).map(|r| state.intern(r));
+    let level = element_level(node, role.as_deref(), state.list_depth);
     let ph = node.attributes.get("placeholder").cloned();
     let href = node.attributes.get("href").cloned();
-    let val = node.attributes.get("value").cloned();
+    let val = if tag == "textarea" {
+        Some(node.text_content.clone()).filter(|v| !v.is_empty())
+    } else {
+        node.attributes.get("value").cloned()
+    };
     let input_type = if tag == "input" {
         node.attributes.get("type").cloned()
     } else {
         None
     };
+    let suggestions = if tag == "input" {
+        node.attributes.get("list")
+            .and_then(|list| state.datalists.get(list))
+            .filter(|values| !values.is_empty())
+            .cloned()
+    } else {
+        None
+    };
+    let more_suggestions = suggestions.as_ref()
+        .map(|values| values.len().saturating_sub(MAX_SUGGESTIONS))
+        .filter(|&more| more > 0);
 
     let disabled = parse_bool_attr(node, "disabled")
-        .or_else(|| parse_bool_attr(node, "aria-disabled"));
+        .or_else(|| parse_aria_bool(node, "aria-disabled"));
+    let readonly = parse_bool_attr(node, "readonly")
+        .or_else(|| parse_aria_bool(node, "aria-readonly"));
     let checked = parse_bool_attr(node, "checked")
         .or_else(|| parse_aria_bool(node, "aria-checked"));
     let expanded = parse_aria_bool(node, "aria-expanded");
@@ -555,48 +1881,179 @@ fn emit_element(
         None
     };
 
-    // Alert type detection from role or CSS classes
-    let alert_type = detect_alert_type(node);
+    let autocomplete = if matches!(tag, "input" | "select" | "textarea") {
+        node.attributes.get("autocomplete").map(|a| a.trim().to_string())
+    } else {
+        None
+    };
+    let purpose = if tag == "input" {
+        purpose::classify(&purpose::Field {
+            input_type: input_type.as_deref(),
+            autocomplete: autocomplete.as_deref(),
+            role: role.as_deref(),
+            name: name.as_deref(),
+            label: label.as_deref(),
+            ph: ph.as_deref(),
+        })
+        .map(str::to_string)
+    } else {
+        None
+    };
+
+    // Help text via aria-describedby, which may name several elements
+    let desc = match node.attributes.get("aria-describedby") {
+        Some(refs) if matches!(tag, "input" | "select" | "textarea") => {
+            let parts: Vec<&str> = refs
+                .split_whitespace()
+                .filter_map(|id| state.descriptions.get(id).map(String::as_str))
+                .collect();
+            (!parts.is_empty()).then(|| parts.join(" "))
+        }
+        _ => None,
+    };
+
+    // Alert type detection from role or CSS classes. An error inside
+    // <noscript> is the page asking for JavaScript, not a failure.
+    let alert_type = detect_alert_type(node).filter(|a| state.noscript == 0 || a.as_str() != "error");
+
+    // Form membership: controls and the form landmark carry the form index.
+    // A control's `form` attribute ties it to a form elsewhere on the page.
+    let form = if tag == "form" {
+        state.form
+    } else if matches!(tag, "input" | "select" | "textarea" | "button") {
+        node.attributes
+            .get("form")
+            .and_then(|id| state.form_ids.get(id).copied())
+            .or(state.form)
+    } else {
+        None
+    };
+    let (action, method) = if tag == "form" {
+        (
+            node.attributes.get("action").cloned(),
+            node.attributes.get("method").map(|m| m.to_lowercase()),
+        )
+    } else {
+        (None, None)
+    };
+
+    let b = [
+        node.bounds.x.round() as i32,
+        node.bounds.y.round() as i32,
+        node.bounds.width.round() as i32,
+        node.bounds.height.round() as i32,
+    ];
+    let (clipped, visible_b) = match node.clip {
+        Some(ref clip) if !is_hidden => clip_bounds(&node.bounds, b, clip),
+        _ => (None, None),
+    };
+    let prominence = match visible_b {
+        _ if is_hidden || is_marker => None,
+        None if clipped == Some(true) => None,
+        shown => prominence(shown.unwrap_or(b), node.style.font_size, node.pinned, state),
+    };
+    let tone = if is_marker { None } else { tone::tone(node.style.color, node.style.background_color) };
 
     let el = SpatialElement {
-        id: *id_counter,
-        tag: tag.to_string(),
+        id: state.next_id,
+        tag: state.intern(Atom::new(tag)),
         role,
+        level,
         text,
+        text_source,
         ph,
         href,
         val,
+        default_val: None,
         input_type,
+        suggestions,
+        more_suggestions,
         disabled,
+        readonly,
         checked,
         expanded,
         selected,
         required,
         name,
         label,
+        autocomplete,
+        purpose,
+        desc,
         alert_type,
+        form,
+        action,
+        method,
         hidden: if is_hidden { Some(true) } else { None },
-        b: [
-            node.bounds.x.round() as i32,
-            node.bounds.y.round() as i32,
-            node.bounds.width.round() as i32,
-            node.bounds.height.round() as i32,
-        ],
+        hidden_reason: hidden,
+        selector,
+        b,
+        clipped,
+        visible_b,
+        pinned: if node.pinned { Some(true) } else { None },
+        src: node.src,
+        note: None,
+        page: None,
+        prominence,
+        tone,
     };
 
-    *id_counter += 1;
+    state.behaviors.extend(
+        crate::js::node_actions(&node.attributes)
+            .into_iter()
+            .map(|action| JsBehavior { trigger_id: el.id, action }),
+    );
+    state.next_id += 1;
     els.push(el);
 }
 
+/// An element's `prominence` from the part of it that shows, `b`. Its
+/// share of the viewport (full marks at a twentieth), how far above the fold
+/// it starts (fading over the next two screens) and its font size (10px to
+/// 32px) count a third each; a banner or footer halves the total.
+fn prominence(b: [i32; 4], font_size: f32, pinned: bool, state: &CollectState) -> Option<f32> {
+    let [width, height] = state.viewport;
+    if b[2] <= 0 || b[3] <= 0 || width <= 0.0 || height <= 0.0 {
+        return None;
+    }
+    let share = (b[2] as f32 * b[3] as f32) / (width * height);
+    let area = (share / 0.05).sqrt().min(1.0);
+    let below_fold = (b[1] as f32 - height).max(0.0);
+    let position = if pinned { 1.0 } else { (1.0 - below_fold / (2.0 * height)).max(0.0) };
+    let font = ((font_size - 10.0) / 22.0).clamp(0.0, 1.0);
+    let edge = if state.page_edge > 0 { 0.5 } else { 1.0 };
+    let score = (area + position + font) / 3.0 * edge;
+    Some((score * 100.0).round() / 100.0)
+}
+
+/// Clipping state for an element with rounded bounds `b` inside `clip`:
+/// nothing when it fits, otherwise `clipped` plus the visible part (if any).
+fn clip_bounds(bounds: &Bounds, b: [i32; 4], clip: &Bounds) -> (Option<bool>, Option<[i32; 4]>) {
+    let c = [
+        clip.x.round() as i32,
+        clip.y.round() as i32,
+        clip.width.round() as i32,
+        clip.height.round() as i32,
+    ];
+    let inside = b[0] >= c[0] && b[1] >= c[1] && b[0] + b[2] <= c[0] + c[2] && b[1] + b[3] <= c[1] + c[3];
+    if inside {
+        return (None, None);
+    }
+    let v = bounds.intersect(clip);
+    let visible = (v.width >= 0.5 && v.height >= 0.5).then(|| {
+        [v.x.round() as i32, v.y.round() as i32, v.width.round() as i32, v.height.round() as i32]
+    });
+    (Some(true), visible)
+}
+
 /// Find alt text from child <img> or <title> from child <svg>.
 /// Used as fallback for text-less links/buttons that contain only images or icons.
-fn find_child_img_alt(node: &LayoutNode) -> Option<String> {
+fn find_child_img_alt(node: &LayoutNode) -> Option<(String, TextSource)> {
     for child in &node.children {
         // <img alt="...">
         if child.tag == "img" {
             if let Some(alt) = child.attributes.get("alt") {
                 if !alt.is_empty() {
-                    return Some(alt.clone());
+                    return Some((alt.clone(), TextSource::ImgAlt));
                 }
             }
         }
@@ -604,11 +2061,11 @@ fn find_child_img_alt(node: &LayoutNode) -> Option<String> {
         if child.tag == "svg" {
             if let Some(label) = child.attributes.get("aria-label") {
                 if !label.is_empty() {
-                    return Some(label.clone());
+                    return Some((label.clone(), TextSource::SvgTitle));
                 }
             }
             if let Some(title) = find_svg_title(child) {
-                return Some(title);
+                return Some((title, TextSource::SvgTitle));
             }
         }
         // Recurse (e.g., <a><span><img alt="..."></span></a>)
@@ -648,6 +2105,53 @@ fn is_landmark_role_attr(node: &LayoutNode) -> bool {
         .unwrap_or(false)
 }
 
+/// Links and buttons: their text is everything inside them.
+fn owns_descendant_text(node: &LayoutNode) -> bool {
+    matches!(node.tag.as_str(), "a" | "button" | "summary")
+        || node
+            .attributes
+            .get("role")
+            .is_some_and(|r| matches!(r.as_str(), "link" | "button"))
+}
+
+/// Whether `node`'s text is already part of the enclosing link or button's
+/// text and `node` has no href or alert of its own to add. Callers keep
+/// elements with a role of their own.
+fn is_covered_by_owner(node: &LayoutNode, state: &CollectState) -> bool {
+    let Some(owner) = &state.text_owner else {
+        return false;
+    };
+    if node.attributes.contains_key("href") || detect_alert_type(node).is_some() {
+        return false;
+    }
+    // Text nodes get no layout of their own, so their text is only in text_content
+    let text = if node.text_content.is_empty() { collect_visible_text(node) } else { node.text_content.clone() };
+    let text = normalize_ws(&text);
+    !text.is_empty() && owner.contains(&text)
+}
+
+fn normalize_ws(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// `text` lowercased with whitespace collapsed, non-breaking spaces included,
+/// for matching text the way it reads rather than how the markup spaced it.
+fn normalize_for_match(text: &str) -> String {
+    normalize_ws(text).to_lowercase()
+}
+
+fn is_zero_size(bounds: &Bounds) -> bool {
+    bounds.width <= 0.0 && bounds.height <= 0.0
+}
+
+/// Check if any element below `node` has a nonzero width or height.
+fn has_sized_descendants(node: &LayoutNode) -> bool {
+    node.children.iter().any(|child| {
+        (child.node_type == NodeType::Element && !is_zero_size(&child.bounds))
+            || has_sized_descendants(child)
+    })
+}
+
 /// Check if a node has any interactive descendants.
 fn has_interactive_descendants(node: &LayoutNode) -> bool {
     for child in &node.children {
@@ -736,16 +2240,33 @@ fn collect_text_recursive(node: &LayoutNode, out: &mut String) {
     }
 }
 
-fn determine_role(node: &LayoutNode) -> Option<String> {
+/// A heading's level, or a list item's nesting depth. `aria-level` wins
+/// over the tag; a `role="heading"` without it is level 2, as ARIA says.
+fn element_level(node: &LayoutNode, role: Option<&str>, list_depth: u8) -> Option<u8> {
+    let aria_level = node
+        .attributes
+        .get("aria-level")
+        .and_then(|l| l.trim().parse::<u8>().ok())
+        .filter(|&l| l > 0);
+    match role {
+        Some("heading") => aria_level
+            .or_else(|| node.tag.strip_prefix('h').and_then(|n| n.parse().ok()))
+            .or(Some(2)),
+        _ if node.tag == "li" || role == Some("listitem") => aria_level.or((list_depth > 0).then_some(list_depth)),
+        _ => None,
+    }
+}
+
+fn determine_role(node: &LayoutNode) -> Option<Atom> {
     // Explicit ARIA role
     if let Some(role) = node.attributes.get("role") {
-        return Some(role.clone());
+        return Some(Atom::new(role));
     }
 
     // Implicit roles from tag
     match node.tag.as_str() {
-        "a" => Some("link".to_string()),
-        "button" => Some("button".to_string()),
+        "a" => Some("link".into()),
+        "button" => Some("button".into()),
         "input" => {
             let input_type = node
                 .attributes
@@ -753,24 +2274,24 @@ fn determine_role(node: &LayoutNode) -> Option<String> {
                 .map(|s| s.as_str())
                 .unwrap_or("text");
             match input_type {
-                "checkbox" => Some("checkbox".to_string()),
-                "radio" => Some("radio".to_string()),
-                "submit" | "button" => Some("button".to_string()),
-                "search" => Some("searchbox".to_string()),
-                _ => Some("textbox".to_string()),
+                "checkbox" => Some("checkbox".into()),
+                "radio" => Some("radio".into()),
+                "submit" | "button" => Some("button".into()),
+                "search" => Some("searchbox".into()),
+                _ => Some("textbox".into()),
             }
         }
-        "select" => Some("combobox".to_string()),
-        "textarea" => Some("textbox".to_string()),
-        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => Some("heading".to_string()),
-        "nav" => Some("navigation".to_string()),
-        "main" => Some("main".to_string()),
-        "aside" => Some("complementary".to_string()),
-        "header" => Some("banner".to_string()),
-        "footer" => Some("contentinfo".to_string()),
-        "form" => Some("form".to_string()),
-        "section" => Some("region".to_string()),
-        "img" => Some("img".to_string()),
+        "select" => Some("combobox".into()),
+        "textarea" => Some("textbox".into()),
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => Some("heading".into()),
+        "nav" => Some("navigation".into()),
+        "main" => Some("main".into()),
+        "aside" => Some("complementary".into()),
+        "header" => Some("banner".into()),
+        "footer" => Some("contentinfo".into()),
+        "form" => Some("form".into()),
+        "section" => Some("region".into()),
+        "img" => Some("img".into()),
         _ => None,
     }
 }
@@ -804,44 +2325,120 @@ fn find_title(node: &LayoutNode) -> Option<String> {
     None
 }
 
+/// The language declared on the `<html>` element (`lang` or `xml:lang`).
+fn find_html_lang(node: &LayoutNode) -> Option<String> {
+    if node.tag == "html" {
+        return ["lang", "xml:lang"]
+            .iter()
+            .filter_map(|name| node.attributes.get(*name))
+            .map(|v| v.trim())
+            .find(|v| !v.is_empty())
+            .map(str::to_string);
+    }
+    node.children.iter().find_map(find_html_lang)
+}
+
+/// Text of the page's elements, enough of it to guess the language from.
+fn visible_text_sample(els: &[SpatialElement]) -> String {
+    const SAMPLE_LEN: usize = 10_000;
+    let mut sample = String::new();
+    for text in els.iter().filter_map(|e| e.text.as_deref()) {
+        if sample.len() >= SAMPLE_LEN {
+            break;
+        }
+        sample.push_str(text);
+        sample.push('\n');
+    }
+    sample
+}
+
+impl SpatialDom {
+    /// Whether the page is in a different language than the one asked for.
+    /// `accept_language` is an `Accept-Language` value or a bare tag; only
+    /// primary subtags are compared, so `de-AT` matches `de-DE`. Guesses
+    /// below 0.5 confidence never count as a mismatch.
+    pub fn language_mismatch(&self, accept_language: &str) -> bool {
+        let (Some(page), Some(confidence)) = (&self.lang, self.lang_confidence) else {
+            return false;
+        };
+        let wanted = lang::primary_subtag(accept_language);
+        confidence >= 0.5
+            && !wanted.is_empty()
+            && wanted != "*"
+            && lang::primary_subtag(page) != wanted
+    }
+}
+
 /// Classify element width as a semantic size hint for form elements.
 fn classify_size(width: i32, vp_width: f32) -> Option<&'static str> {
     let pct = width as f32 / vp_width * 100.0;
-    if pct > 90.0 { Some("full") }
-    else if pct > 50.0 { Some("wide") }
-    else if pct < 15.0 && width > 0 { Some("narrow") }
+    if pct > 90.0 { Some(legend::FULL) }
+    else if pct > 50.0 { Some(legend::WIDE) }
+    else if pct < 15.0 && width > 0 { Some(legend::NARROW) }
     else { None }
 }
 
 /// Classify element position into a 3×3 grid region (or "below" if below fold).
 fn classify_region(b: &[i32; 4], vp: &[f32; 2]) -> &'static str {
     let cy = b[1] as f32 + b[3] as f32 / 2.0;
-    if cy > vp[1] { return "below"; }
+    if cy > vp[1] { return legend::BELOW_FOLD; }
     let cx = b[0] as f32 + b[2] as f32 / 2.0;
     let col = if cx < vp[0] / 3.0 { 0 } else if cx < vp[0] * 2.0 / 3.0 { 1 } else { 2 };
     let row = if cy < vp[1] / 3.0 { 0 } else if cy < vp[1] * 2.0 / 3.0 { 1 } else { 2 };
-    const NAMES: [[&str; 3]; 3] = [
-        ["top-L", "top", "top-R"],
-        ["mid-L", "mid", "mid-R"],
-        ["bot-L", "bot", "bot-R"],
-    ];
-    NAMES[row][col]
+    legend::REGIONS[row][col]
 }
 
 /// Generate the compact string format for extreme token budgets.
+/// [`format_legend`] explains its markers.
 pub fn to_compact_string(dom: &SpatialDom) -> String {
-    // Pre-pass: count (tag, text) tuples to detect duplicates needing disambiguation
-    let mut tuple_counts: HashMap<(String, Option<String>), usize> = HashMap::new();
-    for el in &dom.els {
-        let key = (el.tag.clone(), el.text.clone());
-        *tuple_counts.entry(key).or_insert(0) += 1;
-    }
+    compact_string(dom, dom.outside_main, dom.els.iter())
+}
 
+/// [`to_compact_string`] of `els`, which belong to `dom`, with
+/// `outside_main` in place of the page's own count.
+fn compact_string<'a, I>(dom: &'a SpatialDom, outside_main: usize, els: I) -> String
+where
+    I: Iterator<Item = &'a SpatialElement> + Clone + 'a,
+{
     let mut lines = Vec::new();
-    for el in &dom.els {
+    if dom.boilerplate_suppressed > 0 {
+        lines.push(format!("[{} {} els suppressed]", legend::BOILERPLATE, dom.boilerplate_suppressed));
+    }
+    if outside_main > 0 {
+        lines.push(format!("[{} {} els]", legend::OUTSIDE_MAIN, outside_main));
+    }
+    lines.extend(element_lines(dom, els));
+    lines.join("\n")
+}
+
+/// The element lines of [`to_compact_string`], made one at a time as the
+/// iterator is read, so a large page can be written out in parts.
+pub fn compact_lines(dom: &SpatialDom) -> impl Iterator<Item = String> + '_ {
+    element_lines(dom, dom.els.iter())
+}
+
+/// [`compact_lines`] of `els`, which belong to `dom`.
+fn element_lines<'a, I>(dom: &'a SpatialDom, els: I) -> impl Iterator<Item = String> + 'a
+where
+    I: Iterator<Item = &'a SpatialElement> + Clone + 'a,
+{
+    // Pre-pass: count (tag, text) tuples to detect duplicates needing disambiguation.
+    // Text is counted as written out, so text differing only in spacing counts as one.
+    let key_of = |el: &SpatialElement| (el.tag.clone(), el.text.as_deref().map(compact::escape));
+    let mut tuple_counts: HashMap<(Atom, Option<String>), usize> = HashMap::new();
+    for el in els.clone() {
+        *tuple_counts.entry(key_of(el)).or_insert(0) += 1;
+    }
+    // Size hints and regions are relative to the width the page was laid out at
+    let layout = dom.layout_size();
+    // Form markers (f1, f2, …) only help when there is more than one form
+    let multi_form = els.clone().any(|e| e.form.is_some_and(|f| f > 1));
+
+    els.map(move |el| {
         let mut parts = Vec::new();
-        let hidden_marker = if el.hidden == Some(true) { "!" } else { "" };
-        parts.push(format!("{}{}:{}", hidden_marker, el.id, el.tag));
+        let hidden_marker = if el.hidden == Some(true) { legend::HIDDEN } else { "" };
+        let pinned_marker = if el.pinned == Some(true) { legend::PINNED } else { "" };
+        parts.push(format!("{}{}{}:{}", hidden_marker, pinned_marker, el.id, el.tag));
 
         if let Some(ref t) = el.input_type {
             if t != "text" {
@@ -849,48 +2446,78 @@ pub fn to_compact_string(dom: &SpatialDom) -> String {
             }
         }
 
+        if let Some(level) = heading_level_marker(el) {
+            parts.push(level);
+        }
+
+        if multi_form {
+            if let Some(f) = el.form {
+                parts.push(format!("{}{}", legend::FORM, f));
+            }
+        }
+
         // Form state markers
         if let Some(ref n) = el.name {
             parts.push(format!("[{}]", n));
         }
         if el.checked == Some(true) {
-            parts.push("[v]".to_string());
+            parts.push(legend::CHECKED.to_string());
         }
         if el.required == Some(true) {
-            parts.push("[*]".to_string());
+            parts.push(legend::REQUIRED.to_string());
+        }
+        // Purpose, unless the input type already says it
+        if let Some(ref p) = el.purpose {
+            if el.input_type.as_ref() != Some(p) {
+                parts.push(format!("({})", p));
+            }
         }
         if let Some(ref v) = el.val {
             if !v.is_empty() {
-                parts.push(format!("[={}]", v));
+                parts.push(format!("{}{}]", legend::VALUE, normalize_ws(v)));
             }
         }
 
+        if let Some(ref values) = el.suggestions {
+            parts.push(format_suggestions(values));
+        }
+
         if let Some(ref text) = el.text {
-            parts.push(format!("\"{}\"", text));
+            parts.push(format!("{}{}", name_marker(el), quote_text(text, dom.text_limit)));
         } else if let Some(ref ph) = el.ph {
-            parts.push(format!("\"{}\"", ph));
+            parts.push(format!("\"{}\"", compact::escape(ph)));
         }
 
         if let Some(ref href) = el.href {
-            parts.push(format!("->{}", href));
+            parts.push(format!("{}{}", legend::LINK, href));
         }
 
         // Size hint for form elements
         if matches!(el.tag.as_str(), "input" | "button" | "textarea" | "select") {
-            if let Some(size) = classify_size(el.b[2], dom.vp[0]) {
+            if let Some(size) = classify_size(el.b[2], layout[0]) {
                 parts.push(size.to_string());
             }
         }
 
         // Region label only when duplicate (tag, text) tuples exist
-        let key = (el.tag.clone(), el.text.clone());
-        if tuple_counts.get(&key).copied().unwrap_or(0) > 1 {
-            parts.push(format!("@{}", classify_region(&el.b, &dom.vp)));
+        if tuple_counts.get(&key_of(el)).copied().unwrap_or(0) > 1 {
+            parts.push(format!("{}{}", legend::REGION, classify_region(&el.effective_bounds(), &layout)));
         }
 
-        lines.push(format!("[{}]", parts.join(" ")));
-    }
-    lines.join("\n")
+        if let Some(ref note) = el.note {
+            parts.push(format!("{}\"{}\"", legend::NOTE, compact::escape(note)));
+        }
+
+        if let Some(ref desc) = el.desc {
+            parts.push(format!("({})", normalize_ws(desc)));
+        }
+
+        if let Some([line, column]) = el.src {
+            parts.push(format!("({}:{})", line, column));
+        }
+
+        format!("[{}]", parts.join(" "))
+    })
 }
 
 /// Delta output — only the changes between two SpatialDoms.
@@ -905,40 +2532,141 @@ pub struct DeltaDom {
     /// Viewport dimensions for size hint computation.
     #[serde(default = "default_vp")]
     pub vp: [f32; 2],
+    /// Notes `Session::annotate` left on elements that are gone, set only
+    /// by `Session::delta`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub dropped_notes: Vec<DroppedNote>,
+}
+
+/// A note whose element didn't survive a re-render or navigation.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DroppedNote {
+    /// The element's ID on the page the note was left on.
+    pub id: u32,
+    pub note: String,
+}
+
+/// `h3` for a level-3 heading whose tag doesn't already say so.
+fn heading_level_marker(el: &SpatialElement) -> Option<String> {
+    let level = el.level.filter(|_| el.role.as_deref() == Some("heading"))?;
+    let marker = format!("{}{}", legend::HEADING, level);
+    (el.tag != marker).then_some(marker)
+}
+
+/// `~` when the element's text isn't shown on the page, only an accessible
+/// name such as its `aria-label`.
+fn name_marker(el: &SpatialElement) -> &'static str {
+    if TextSource::is_visible_or_none(&el.text_source) { "" } else { legend::NAME_ONLY }
+}
+
+/// `text` escaped and in quotes, cut to `limit` characters with its full
+/// length after it when longer: `"Terms of…" (1.4k chars)`.
+fn quote_text(text: &str, limit: Option<usize>) -> String {
+    let length = text.chars().count();
+    let text = normalize_ws(text);
+    match limit {
+        Some(limit) if text.chars().count() > limit => {
+            let cut: String = text.chars().take(limit).collect();
+            format!("\"{}{}\" ({} chars)", compact::escape(&cut), legend::TRUNCATED, format_length(length))
+        }
+        _ => format!("\"{}\"", compact::escape(&text)),
+    }
+}
+
+/// Up to [`MAX_SUGGESTIONS`] values between braces, followed by how many
+/// more there are: `{France|Germany|+180}`.
+fn format_suggestions(values: &[String]) -> String {
+    let shown = &values[..values.len().min(MAX_SUGGESTIONS)];
+    let mut out = format!("{}{}", legend::SUGGESTIONS_OPEN, shown.join(legend::SUGGESTIONS_SEP));
+    if values.len() > shown.len() {
+        out.push_str(&format!("{}{}{}", legend::SUGGESTIONS_SEP, legend::MORE, values.len() - shown.len()));
+    }
+    out.push_str(legend::SUGGESTIONS_CLOSE);
+    out
+}
+
+fn serialize_suggestions<S: Serializer>(values: &Option<Vec<String>>, serializer: S) -> Result<S::Ok, S::Error> {
+    values
+        .as_ref()
+        .map(|values| &values[..values.len().min(MAX_SUGGESTIONS)])
+        .serialize(serializer)
+}
+
+/// A character count the way the compact format shows it: `850`, `1.4k`, `5k`.
+fn format_length(length: usize) -> String {
+    if length < 1000 {
+        return length.to_string();
+    }
+    let thousands = format!("{:.1}", length as f64 / 1000.0);
+    format!("{}k", thousands.trim_end_matches(".0"))
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+fn default_text_limit() -> Option<usize> {
+    Some(DEFAULT_TEXT_LIMIT)
 }
 
 fn default_vp() -> [f32; 2] {
     [1920.0, 1080.0]
 }
 
+/// Options controlling how [`diff_with_options`] matches elements.
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    /// Match elements without comparing bounds, so layout shifts alone
+    /// don't count as changes.
+    pub ignore_bounds: bool,
+}
+
 /// Compute the diff between two SpatialDoms.
 /// Returns only added/changed/removed elements.
 pub fn diff(old: &SpatialDom, new: &SpatialDom) -> DeltaDom {
+    diff_with_options(old, new, &DiffOptions::default())
+}
+
+/// Compute the diff between two SpatialDoms with explicit matching options.
+pub fn diff_with_options(old: &SpatialDom, new: &SpatialDom, options: &DiffOptions) -> DeltaDom {
     let mut changed = Vec::new();
     let mut removed = Vec::new();
-
-    // Build lookup of old elements by a content key (tag + text + href + bounds)
-    // We match by content similarity, not by ID (since IDs are assigned sequentially)
-    let old_set: std::collections::HashSet<ElementKey> = old.els.iter().map(ElementKey::from).collect();
-    let new_set: std::collections::HashSet<ElementKey> = new.els.iter().map(ElementKey::from).collect();
+    let key_of = |el: &SpatialElement| ElementKey::new(el, options.ignore_bounds);
+
+    // Count old elements by a content key (tag + text + href + bounds)
+    // We match by content similarity, not by ID (since IDs are assigned sequentially),
+    // and each element matches at most one on the other side, in document order
+    let counts = |els: &[SpatialElement]| {
+        let mut counts: HashMap<ElementKey, usize> = HashMap::new();
+        for el in els {
+            *counts.entry(key_of(el)).or_default() += 1;
+        }
+        counts
+    };
+    let unmatched = |counts: &mut HashMap<ElementKey, usize>, el: &SpatialElement| match counts.get_mut(&key_of(el)) {
+        Some(count) if *count > 0 => {
+            *count -= 1;
+            false
+        }
+        _ => true,
+    };
+    let (mut old_counts, mut new_counts) = (counts(&old.els), counts(&new.els));
 
     // Elements in new but not in old → added/changed
     for el in &new.els {
-        let key = ElementKey::from(el);
-        if !old_set.contains(&key) {
+        if unmatched(&mut old_counts, el) {
             changed.push(el.clone());
         }
     }
 
     // Elements in old but not in new → removed
     for el in &old.els {
-        let key = ElementKey::from(el);
-        if !new_set.contains(&key) {
+        if unmatched(&mut new_counts, el) {
             removed.push(el.id);
         }
     }
 
-    DeltaDom { changed, removed, vp: new.vp }
+    DeltaDom { changed, removed, vp: new.layout_size(), dropped_notes: Vec::new() }
 }
 
 /// Generate compact string format for a delta.
@@ -948,6 +2676,9 @@ pub fn delta_to_compact_string(delta: &DeltaDom) -> String {
     if !delta.removed.is_empty() {
         lines.push(format!("-[{}]", delta.removed.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(",")));
     }
+    for dropped in &delta.dropped_notes {
+        lines.push(format!("-{}{} \"{}\"", legend::NOTE, dropped.id, compact::escape(&dropped.note)));
+    }
 
     for el in &delta.changed {
         let mut parts = Vec::new();
@@ -960,13 +2691,13 @@ pub fn delta_to_compact_string(delta: &DeltaDom) -> String {
         }
 
         if let Some(ref text) = el.text {
-            parts.push(format!("\"{}\"", text));
+            parts.push(format!("{}\"{}\"", name_marker(el), compact::escape(text)));
         } else if let Some(ref ph) = el.ph {
-            parts.push(format!("\"{}\"", ph));
+            parts.push(format!("\"{}\"", compact::escape(ph)));
         }
 
         if let Some(ref href) = el.href {
-            parts.push(format!("->{}", href));
+            parts.push(format!("{}{}", legend::LINK, href));
         }
 
         // Size hint for form elements (no disambiguation in deltas — changed elements have unique +id:tag)
@@ -976,31 +2707,140 @@ pub fn delta_to_compact_string(delta: &DeltaDom) -> String {
             }
         }
 
+        if let Some(ref note) = el.note {
+            parts.push(format!("{}\"{}\"", legend::NOTE, compact::escape(note)));
+        }
+
         lines.push(format!("[{}]", parts.join(" ")));
     }
 
     lines.join("\n")
 }
 
+/// Carry element IDs over from `previous` so IDs an agent already holds stay
+/// valid when the same page is rendered again.
+///
+/// Elements are matched on content and bounds first, then on content alone
+/// (so layout shifts don't break matches), pairing duplicates in document
+/// order. Unmatched elements get fresh IDs above the previous maximum.
+/// Suggested actions are recomputed so they refer to the new IDs.
+pub fn stabilize_ids(dom: &mut SpatialDom, previous: &SpatialDom) {
+    let assigned = matching_ids(dom, previous);
+
+    let mut next_id = previous.els.iter().map(|e| e.id).max().unwrap_or(0) + 1;
+    let mut renamed = HashMap::new();
+    for (el, slot) in dom.els.iter_mut().zip(assigned) {
+        let id = slot.unwrap_or_else(|| {
+            next_id += 1;
+            next_id - 1
+        });
+        renamed.insert(el.id, id);
+        el.id = id;
+    }
+    for crumb in &mut dom.breadcrumbs {
+        crumb.id = crumb.id.and_then(|id| renamed.get(&id).copied());
+    }
+    for hit in &mut dom.prices {
+        if let Some(&id) = renamed.get(&hit.element_id) {
+            hit.element_id = id;
+        }
+    }
+    for behavior in &mut dom.behaviors {
+        if let Some(&id) = renamed.get(&behavior.trigger_id) {
+            behavior.trigger_id = id;
+        }
+    }
+    dom.rebuild_index();
+    let parallel = dom.els.len() >= DEFAULT_PARALLEL_MIN_ELEMENTS;
+    let index = PageIndex::new(dom, parallel);
+    dom.suggested_actions = detect_suggested_actions(dom, &index, parallel);
+}
+
+/// For each element of `dom`, the ID of the element of `previous` it
+/// matches, the way [`stabilize_ids`] matches them.
+pub(crate) fn matching_ids(dom: &SpatialDom, previous: &SpatialDom) -> Vec<Option<u32>> {
+    let mut assigned: Vec<Option<u32>> = vec![None; dom.els.len()];
+    let mut reused = std::collections::HashSet::new();
+    // A page re-rendered after a click keeps its links as written, so match them resolved
+    let base = url::Url::parse(&dom.url).ok();
+    let key_of = |el: &SpatialElement, ignore_bounds: bool| {
+        let mut key = ElementKey::new(el, ignore_bounds);
+        if let (Some(base), Some(href)) = (&base, &key.href) {
+            key.href = base.join(href).map(String::from).ok().or(key.href);
+        }
+        key
+    };
+    for ignore_bounds in [false, true] {
+        let mut available: HashMap<ElementKey, std::collections::VecDeque<u32>> = HashMap::new();
+        for el in previous.els.iter().filter(|e| !reused.contains(&e.id)) {
+            available.entry(key_of(el, ignore_bounds)).or_default().push_back(el.id);
+        }
+        for (slot, el) in assigned.iter_mut().zip(&dom.els) {
+            if slot.is_some() {
+                continue;
+            }
+            if let Some(id) = available.get_mut(&key_of(el, ignore_bounds)).and_then(|ids| ids.pop_front()) {
+                *slot = Some(id);
+                reused.insert(id);
+            }
+        }
+    }
+    assigned
+}
+
+/// `dom` without the elements `previous` also had, matched on content
+/// alone (tag, text, placeholder, link, input type, name and label) so the
+/// same header, nav and footer drop out wherever they sit on the page. An
+/// element is dropped at most as many times as `previous` had it. Pages on different
+/// hosts share no boilerplate, so `dom` is returned as it is.
+pub fn suppress_boilerplate(mut dom: SpatialDom, previous: &SpatialDom) -> SpatialDom {
+    let host = |url: &str| url::Url::parse(url).ok()?.host_str().map(str::to_string);
+    if host(&dom.url).is_none() || host(&dom.url) != host(&previous.url) {
+        return dom;
+    }
+
+    let mut seen: HashMap<ElementKey, usize> = HashMap::new();
+    for el in &previous.els {
+        *seen.entry(ElementKey::new(el, true)).or_default() += 1;
+    }
+    let before = dom.els.len();
+    dom.els.retain(|el| match seen.get_mut(&ElementKey::new(el, true)) {
+        Some(count) if *count > 0 => {
+            *count -= 1;
+            false
+        }
+        _ => true,
+    });
+    dom.boilerplate_suppressed += before - dom.els.len();
+    dom.rebuild_index();
+    dom
+}
+
 #[derive(Hash, PartialEq, Eq)]
 struct ElementKey {
-    tag: String,
+    tag: Atom,
     text: Option<String>,
     ph: Option<String>,
     href: Option<String>,
     input_type: Option<String>,
-    bounds: [i32; 4],
+    /// Form fields often have no text, so these tell them apart.
+    name: Option<String>,
+    label: Option<String>,
+    /// `None` when bounds are ignored for matching.
+    bounds: Option<[i32; 4]>,
 }
 
-impl From<&SpatialElement> for ElementKey {
-    fn from(el: &SpatialElement) -> Self {
+impl ElementKey {
+    fn new(el: &SpatialElement, ignore_bounds: bool) -> Self {
         Self {
             tag: el.tag.clone(),
             text: el.text.clone(),
             ph: el.ph.clone(),
             href: el.href.clone(),
             input_type: el.input_type.clone(),
-            bounds: el.b,
+            name: el.name.clone(),
+            label: el.label.clone(),
+            bounds: if ignore_bounds { None } else { Some(el.b) },
         }
     }
 }
@@ -1008,12 +2848,12 @@ impl From<&SpatialElement> for ElementKey {
 // --- Alert detection ---
 
 /// Detect alert type from role attributes or CSS class names.
-fn detect_alert_type(node: &LayoutNode) -> Option<String> {
+fn detect_alert_type(node: &LayoutNode) -> Option<Atom> {
     // Check role attribute first
     if let Some(role) = node.attributes.get("role") {
         match role.as_str() {
-            "alert" => return Some("alert".to_string()),
-            "status" => return Some("status".to_string()),
+            "alert" => return Some("alert".into()),
+            "status" => return Some("status".into()),
             _ => {}
         }
     }
@@ -1034,20 +2874,20 @@ fn detect_alert_type(node: &LayoutNode) -> Option<String> {
             // Require compound patterns: "alert-error", "msg-error", "form-error", etc.
             // A bare "error" class is too ambiguous.
             if (cls.contains("error") || cls.contains("danger")) && is_compound(cls) {
-                return Some("error".to_string());
+                return Some("error".into());
             }
             if cls.contains("success") && is_compound(cls) {
-                return Some("success".to_string());
+                return Some("success".into());
             }
             if cls.contains("warning") && is_compound(cls) {
-                return Some("warning".to_string());
+                return Some("warning".into());
             }
             // "alert" as a class by itself is typically intentional (Bootstrap, etc.)
             if *cls == "alert" || cls.starts_with("alert-") || cls.starts_with("alert_") {
-                return Some("alert".to_string());
+                return Some("alert".into());
             }
             if cls.contains("notice") || cls.contains("flash") {
-                return Some("alert".to_string());
+                return Some("alert".into());
             }
         }
     }
@@ -1115,6 +2955,46 @@ impl SpatialDom {
     }
 }
 
+/// Counts describing a page, for a quick look without the element list.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageSummary {
+    /// Elements per ARIA role; elements without one are counted under their tag.
+    pub counts_by_role: std::collections::BTreeMap<String, usize>,
+    pub hidden_count: usize,
+    /// Forms as `forms()` groups them.
+    pub form_count: usize,
+    /// Tables as `tables()` extracts them.
+    pub table_count: usize,
+    pub link_count: usize,
+    /// Links, buttons, form controls and disclosure widgets, hidden or not.
+    pub interactive_count: usize,
+}
+
+impl SpatialDom {
+    /// Counts of the page's elements by role and kind.
+    pub fn summary(&self) -> PageSummary {
+        let mut summary = PageSummary {
+            form_count: self.forms().len(),
+            table_count: self.tables().len(),
+            ..PageSummary::default()
+        };
+        for el in &self.els {
+            let role = el.role.as_deref().unwrap_or(&el.tag);
+            *summary.counts_by_role.entry(role.to_string()).or_default() += 1;
+            if el.hidden == Some(true) {
+                summary.hidden_count += 1;
+            }
+            if el.role.as_deref() == Some("link") {
+                summary.link_count += 1;
+            }
+            if INTERACTIVE_TAGS.contains(&el.tag.as_str()) {
+                summary.interactive_count += 1;
+            }
+        }
+        summary
+    }
+}
+
 /// Group elements into rows by Y coordinate (elements at the same Y = same row).
 fn group_by_row<'a>(elements: &[&'a SpatialElement]) -> Vec<Vec<&'a SpatialElement>> {
     if elements.is_empty() {
@@ -1138,87 +3018,813 @@ fn group_by_row<'a>(elements: &[&'a SpatialElement]) -> Vec<Vec<&'a SpatialEleme
             current_y = el.b[1];
         }
     }
-    rows.push(current_row);
-    rows
+    rows.push(current_row);
+    rows
+}
+
+// --- Page type detection ---
+
+/// Detected page type for agent decision-making.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
+pub enum PageType {
+    Login,
+    TwoFactorAuth,
+    OAuthConsent,
+    Captcha,
+    Blocked,
+    Search,
+    SearchResults,
+    Inbox,
+    EmailBody,
+    Dashboard,
+    Product,
+    Form,
+    Article,
+    List,
+    Error,
+    /// A document that isn't HTML, such as a PDF.
+    Document,
+    #[default]
+    Other,
+}
+
+impl PageType {
+    pub fn is_other(&self) -> bool {
+        matches!(self, PageType::Other)
+    }
+}
+
+// --- Pagination detection ---
+
+/// Pagination links detected on the page.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pagination {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prev: Option<String>,
+    /// Numbered page links: (label, url)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pages: Vec<(String, String)>,
+}
+
+impl SpatialDom {
+    /// Detect pagination links on the page.
+    pub fn pagination(&self) -> Option<Pagination> {
+        let links: Vec<&SpatialElement> = self.els.iter()
+            .filter(|e| e.role.as_deref() == Some("link") && e.href.is_some())
+            .collect();
+
+        let mut next: Option<String> = None;
+        let mut prev: Option<String> = None;
+        let mut pages: Vec<(String, String)> = Vec::new();
+
+        for link in &links {
+            let text = link.text.as_deref().unwrap_or("").trim().to_lowercase();
+            let href = link.href.as_deref().unwrap_or("");
+
+            if text == "next" || text == "next page" || text == ">" || text == ">>"
+                || text == "\u{203a}" || text == "\u{00bb}"
+            {
+                next = Some(href.to_string());
+            } else if text == "previous" || text == "prev" || text == "prev page"
+                || text == "<" || text == "<<"
+                || text == "\u{2039}" || text == "\u{00ab}"
+            {
+                prev = Some(href.to_string());
+            } else if text.chars().all(|c| c.is_ascii_digit()) && !text.is_empty() {
+                pages.push((text.clone(), href.to_string()));
+            }
+        }
+
+        if next.is_some() || prev.is_some() || !pages.is_empty() {
+            Some(Pagination { next, prev, pages })
+        } else {
+            None
+        }
+    }
+
+    /// Find the "Load more" button that appends the next batch of a list.
+    ///
+    /// Candidates are visible buttons and links whose text is one of a few
+    /// load-more phrases, in the lower third of the content. Several
+    /// candidates usually mean per-card links, so the bottom-most one only
+    /// counts when no other candidate shares its text.
+    pub fn load_more_button(&self) -> Option<u32> {
+        let phrases = ["load more", "show more", "see more results", "more stories"];
+
+        let content_bottom = self.els.iter()
+            .filter(|e| e.hidden != Some(true))
+            .map(|e| e.b[1] + e.b[3])
+            .max()?;
+        let lower_third = content_bottom * 2 / 3;
+
+        let normalize = |t: &str| {
+            t.trim()
+                .trim_end_matches(['.', '\u{2026}', '\u{203a}', '\u{2192}', '+', ' '])
+                .to_lowercase()
+        };
+        let candidates: Vec<(&SpatialElement, String)> = self.els.iter()
+            .filter(|e| e.hidden != Some(true) && e.b[1] >= lower_third)
+            .filter(|e| {
+                e.tag == "a" || e.tag == "button"
+                    || matches!(e.role.as_deref(), Some("link") | Some("button"))
+            })
+            .filter_map(|e| {
+                let text = normalize(e.text.as_deref()?);
+                let matches = phrases.iter().any(|p| {
+                    text == *p || (text.len() < 40 && text.starts_with(&format!("{p} ")))
+                });
+                matches.then_some((e, text))
+            })
+            .collect();
+
+        let (bottom, text) = candidates.iter().max_by_key(|(e, _)| e.b[1])?;
+        let shared = candidates.iter().filter(|(_, t)| t == text).count() > 1;
+        if shared {
+            return None;
+        }
+        Some(bottom.id)
+    }
+}
+
+// --- Breadcrumbs ---
+
+/// One step of a breadcrumb trail, outermost first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Crumb {
+    pub text: String,
+    /// Link target; `None` for the current page, which is usually plain text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub href: Option<String>,
+    /// The element showing this step. `None` for JSON-LD steps not shown on the page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<u32>,
+}
+
+/// Where the layout tree declared a breadcrumb trail. Element IDs aren't
+/// known while the tree is scanned, so containers are kept as bounds.
+#[derive(Debug, Default)]
+struct BreadcrumbHints {
+    /// A `nav[aria-label=breadcrumb]`, `.breadcrumb` or BreadcrumbList microdata container.
+    container: Option<[i32; 4]>,
+    /// Steps of a schema.org BreadcrumbList in JSON-LD.
+    json_ld: Vec<Crumb>,
+    /// A row of links near the top separated by `/`, `>` or `›`.
+    row: Option<[i32; 4]>,
+}
+
+impl BreadcrumbHints {
+    /// Turn the hints into a trail over `dom`'s elements. An explicit
+    /// container wins over JSON-LD, which wins over a heuristic row.
+    fn resolve(&self, dom: &SpatialDom) -> Vec<Crumb> {
+        let from_container = self.container.map(|b| crumbs_within(dom, b)).unwrap_or_default();
+        if !from_container.is_empty() {
+            return from_container;
+        }
+        if !self.json_ld.is_empty() {
+            return self.json_ld.iter().map(|c| Crumb {
+                id: c.href.as_deref().and_then(|href| {
+                    dom.els.iter().find(|e| e.href.as_deref() == Some(href)).map(|e| e.id)
+                }),
+                ..c.clone()
+            }).collect();
+        }
+        self.row.map(|b| crumbs_within(dom, b)).unwrap_or_default()
+    }
+}
+
+/// Visible links and text inside `container`, skipping separators and
+/// wrappers whose box holds another candidate.
+fn crumbs_within(dom: &SpatialDom, container: [i32; 4]) -> Vec<Crumb> {
+    let candidates: Vec<&SpatialElement> = dom.els.iter()
+        .filter(|e| e.hidden != Some(true) && box_within(&e.b, &container))
+        .filter(|e| e.text.as_deref().is_some_and(|t| {
+            !is_trivial_text(t) && !is_breadcrumb_separator(t)
+        }))
+        .collect();
+    candidates.iter()
+        .filter(|e| !candidates.iter().any(|o| o.id != e.id && o.b != e.b && box_within(&o.b, &e.b)))
+        .map(|e| Crumb {
+            text: e.text.clone().unwrap_or_default(),
+            href: e.href.clone(),
+            id: Some(e.id),
+        })
+        .collect()
+}
+
+/// Whether box `b` lies inside `outer`, allowing 2px for rounding.
+fn box_within(b: &[i32; 4], outer: &[i32; 4]) -> bool {
+    b[0] >= outer[0] - 2 && b[1] >= outer[1] - 2
+        && b[0] + b[2] <= outer[0] + outer[2] + 2
+        && b[1] + b[3] <= outer[1] + outer[3] + 2
+}
+
+impl SpatialDom {
+    /// The breadcrumb trail showing where this page sits in the site.
+    ///
+    /// Taken from an explicit breadcrumb container (`nav[aria-label=breadcrumb]`,
+    /// `.breadcrumb`), then schema.org BreadcrumbList JSON-LD, then a short
+    /// row of links near the top of the page separated by `/`, `>` or `›`.
+    pub fn breadcrumbs(&self) -> Option<Vec<Crumb>> {
+        if self.breadcrumbs.is_empty() {
+            None
+        } else {
+            Some(self.breadcrumbs.clone())
+        }
+    }
+}
+
+/// Characters that separate breadcrumb steps, as opposed to `|` or `·`
+/// between unrelated links.
+const BREADCRUMB_MARKS: [char; 6] = ['/', '\\', '>', '\u{203a}', '\u{00bb}', '\u{2192}'];
+
+/// Whether `text` is a breadcrumb separator: a hierarchy mark, possibly
+/// padded with other trivial characters.
+fn is_breadcrumb_separator(text: &str) -> bool {
+    let t = text.trim();
+    t.chars().count() <= 3
+        && t.contains(BREADCRUMB_MARKS)
+        && is_trivial_text(&t.replace(BREADCRUMB_MARKS, ""))
+}
+
+fn detect_breadcrumbs_from_tree(root: &LayoutNode, viewport_height: f32) -> BreadcrumbHints {
+    let json_ld = collect_json_ld(root)
+        .iter()
+        .find(|v| has_schema_type(v, "BreadcrumbList"))
+        .map(breadcrumb_list_steps)
+        .unwrap_or_default();
+    BreadcrumbHints {
+        container: find_breadcrumb_container(root),
+        json_ld,
+        row: find_breadcrumb_row(root, viewport_height / 2.0),
+    }
+}
+
+fn bounds_box(b: &Bounds) -> [i32; 4] {
+    [b.x as i32, b.y as i32, b.width as i32, b.height as i32]
+}
+
+fn is_tree_hidden(node: &LayoutNode) -> bool {
+    node.style.display == Display::None || node.attributes.contains_key("hidden")
+}
+
+fn find_breadcrumb_container(node: &LayoutNode) -> Option<[i32; 4]> {
+    if node.node_type == NodeType::Element && is_tree_hidden(node) {
+        return None;
+    }
+    let attr = |name: &str| node.attributes.get(name).map(|v| v.to_lowercase()).unwrap_or_default();
+    let is_container = attr("aria-label").contains("breadcrumb")
+        || attr("class").split_whitespace().any(|c| c.contains("breadcrumb"))
+        || attr("itemtype").contains("breadcrumblist");
+    if is_container && node.bounds.width > 0.0 && node.bounds.height > 0.0 {
+        return Some(bounds_box(&node.bounds));
+    }
+    node.children.iter().find_map(find_breadcrumb_container)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum CrumbToken {
+    Link,
+    Text,
+    Separator,
+}
+
+/// Find the first short run of 2–6 links starting above `max_y` whose steps
+/// are separated by hierarchy marks. Inline runs are stacked by the layout
+/// approximation, so "short" is judged by text length rather than height.
+fn find_breadcrumb_row(node: &LayoutNode, max_y: f32) -> Option<[i32; 4]> {
+    if node.node_type == NodeType::Text || is_tree_hidden(node) || node.bounds.y > max_y {
+        return None;
+    }
+    let short = node.text_content.chars().count() <= 200;
+    if node.node_type == NodeType::Element && short && node.bounds.height > 0.0 {
+        let mut tokens = Vec::new();
+        if node.children.iter().all(|c| crumb_tokens(c, &mut tokens)) && is_breadcrumb_sequence(&tokens) {
+            return Some(bounds_box(&node.bounds));
+        }
+    }
+    node.children.iter().find_map(|c| find_breadcrumb_row(c, max_y))
+}
+
+/// Flatten `node` into breadcrumb tokens. Returns false when it holds
+/// something a breadcrumb step wouldn't, such as long text.
+fn crumb_tokens(node: &LayoutNode, out: &mut Vec<CrumbToken>) -> bool {
+    match node.node_type {
+        NodeType::Text => {
+            let t = node.text.trim();
+            if t.is_empty() {
+                true
+            } else if is_breadcrumb_separator(t) {
+                out.push(CrumbToken::Separator);
+                true
+            } else if t.chars().count() <= 60 && !is_trivial_text(t) {
+                out.push(CrumbToken::Text);
+                true
+            } else {
+                false
+            }
+        }
+        NodeType::Element if is_tree_hidden(node) => true,
+        NodeType::Element if node.tag == "a" && node.attributes.contains_key("href") => {
+            let len = node.text_content.trim().chars().count();
+            out.push(CrumbToken::Link);
+            len > 0 && len <= 60
+        }
+        NodeType::Element => node.children.iter().all(|c| crumb_tokens(c, out)),
+        NodeType::Document => false,
+    }
+}
+
+/// Steps alternate with separators, and there are 2–6 linked steps.
+fn is_breadcrumb_sequence(tokens: &[CrumbToken]) -> bool {
+    let links = tokens.iter().filter(|t| **t == CrumbToken::Link).count();
+    if !(2..=6).contains(&links) || tokens.first() == Some(&CrumbToken::Separator) {
+        return false;
+    }
+    tokens.windows(2).all(|w| (w[0] == CrumbToken::Separator) != (w[1] == CrumbToken::Separator))
+}
+
+/// The steps of a schema.org BreadcrumbList, ordered by `position`.
+fn breadcrumb_list_steps(list: &serde_json::Value) -> Vec<Crumb> {
+    let Some(items) = list.get("itemListElement").and_then(|v| v.as_array()) else {
+        return Vec::new();
+    };
+    let mut steps: Vec<(i64, Crumb)> = items.iter().filter_map(|item| {
+        let target = item.get("item");
+        let name = item.get("name")
+            .or_else(|| target.and_then(|t| t.get("name")))
+            .and_then(|v| v.as_str())?;
+        let href = target.and_then(|t| {
+            t.as_str().or_else(|| t.get("@id").or_else(|| t.get("url")).and_then(|v| v.as_str()))
+        });
+        let position = item.get("position").and_then(|p| {
+            p.as_i64().or_else(|| p.as_str().and_then(|s| s.trim().parse().ok()))
+        });
+        Some((position.unwrap_or(i64::MAX), Crumb {
+            text: name.trim().to_string(),
+            href: href.map(str::to_string),
+            id: None,
+        }))
+    }).collect();
+    steps.sort_by_key(|(position, _)| *position);
+    steps.into_iter().map(|(_, crumb)| crumb).collect()
+}
+
+// --- Sign-in state ---
+
+/// Whether the session looks signed in to the site, judged from the page.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum AuthState {
+    /// A sign-out control or an account greeting is shown. `account_hint` is
+    /// the name or email address the page shows for the account, if any.
+    LoggedIn {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        account_hint: Option<String>,
+    },
+    /// A sign-in link or button is shown and nothing says who is signed in.
+    LoggedOut,
+    Unknown,
+}
+
+const SIGN_OUT_PHRASES: &[&str] = &["sign out", "signout", "log out", "logout", "sign off", "log off"];
+const SIGN_OUT_PATHS: &[&str] = &["logout", "signout", "sign_out", "sign-out", "log_out", "log-out"];
+const SIGN_IN_PHRASES: &[&str] = &["sign in", "signin", "log in", "login"];
+/// Phrases that come before the account's name, such as "Hi, Ada".
+const GREETINGS: &[&str] = &[
+    "signed in as", "logged in as", "hi,", "hello,", "welcome back,", "welcome,", "hi ", "hello ",
+];
+/// Account menus sit in the page header; lower down, an email address is
+/// more likely a contact address.
+const ACCOUNT_MENU_MAX_Y: i32 = 200;
+
+impl SpatialDom {
+    /// Whether the session is signed in to the site, from the page's
+    /// controls: a "Sign out" link or button, or an account menu showing a
+    /// greeting ("Hi, Ada", "Signed in as ada") or an email address, versus
+    /// a "Sign in" link. Pages showing both, like "Log in as a different
+    /// user" next to "Sign out", count as signed in. Hidden elements count
+    /// too, since account menus are usually collapsed.
+    pub fn auth_state(&self) -> AuthState {
+        let controls = || self.els.iter().filter(|e| {
+            matches!(e.tag.as_str(), "a" | "button" | "input")
+                || matches!(e.role.as_deref(), Some("link") | Some("button") | Some("menuitem"))
+        });
+        let control_text = |e: &SpatialElement| {
+            e.text.as_deref().or(e.val.as_deref()).unwrap_or("").trim().to_lowercase()
+        };
+
+        let sign_out = controls().any(|e| {
+            let text = control_text(e);
+            let href = e.href.as_deref().unwrap_or("").to_lowercase();
+            SIGN_OUT_PHRASES.iter().any(|p| text.starts_with(p))
+                || (!href.starts_with("mailto:") && SIGN_OUT_PATHS.iter().any(|p| href.contains(p)))
+        });
+        let greeting = self.els.iter().find_map(|e| account_greeting(e.text.as_deref()?));
+        let email = self.els.iter()
+            .filter(|e| e.b[1] < ACCOUNT_MENU_MAX_Y)
+            .filter(|e| !e.href.as_deref().is_some_and(|h| h.starts_with("mailto:")))
+            .filter_map(|e| e.text.as_deref().map(str::trim))
+            .find(|t| looks_like_email(t))
+            .map(str::to_string);
+        let account_hint = greeting.or(email);
+
+        if sign_out || account_hint.is_some() {
+            return AuthState::LoggedIn { account_hint };
+        }
+        let sign_in = controls().any(|e| {
+            let text = control_text(e);
+            SIGN_IN_PHRASES.iter().any(|p| text == *p || text.starts_with(&format!("{p} ")))
+        });
+        if sign_in {
+            AuthState::LoggedOut
+        } else {
+            AuthState::Unknown
+        }
+    }
+}
+
+/// The name in "Hi, Ada" or "Signed in as ada": one to three words after a
+/// greeting, without trailing punctuation.
+fn account_greeting(text: &str) -> Option<String> {
+    let text = text.trim();
+    let lower = text.to_lowercase();
+    let greeting = GREETINGS.iter().find(|g| lower.starts_with(*g))?;
+    let name = text.get(greeting.len()..)?.trim().trim_end_matches(['!', '.', ',']).trim();
+    let words = name.split_whitespace().count();
+    let generic = ["there", "guest", "everyone", "friend", "world"];
+    if words == 0 || words > 3 || name.len() > 40 || generic.contains(&name.to_lowercase().as_str()) {
+        return None;
+    }
+    // "Hi there, welcome to ..." or a sentence, not a name
+    if name.contains(',') {
+        return None;
+    }
+    Some(name.to_string())
+}
+
+/// Whether `text` is a single email address.
+fn looks_like_email(text: &str) -> bool {
+    let Some((local, domain)) = text.split_once('@') else { return false };
+    !local.is_empty()
+        && !text.contains(char::is_whitespace)
+        && !domain.contains('@')
+        && domain.split('.').count() >= 2
+        && domain.split('.').all(|part| !part.is_empty() && part.chars().all(|c| c.is_alphanumeric() || c == '-'))
+}
+
+// --- Prices ---
+
+/// A price shown on the page.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PriceHit {
+    /// The element whose text holds the price.
+    pub element_id: u32,
+    pub amount: f64,
+    /// ISO 4217 code. A bare `$` is read as USD.
+    pub currency: String,
+    /// The element's text, so the price can be told apart from its neighbors.
+    pub context: String,
+    /// A struck-through or "was" price that a sale price replaces.
+    pub original: bool,
+    /// A price per kilo, litre, item etc. rather than for the product.
+    pub per_unit: bool,
+}
+
+impl SpatialDom {
+    /// Prices shown on the page, primary first.
+    ///
+    /// The primary price is the current price nearest the "Add to cart"
+    /// button, or the first one on the page when there's no such button.
+    /// Prices standing alone in their element beat prices inside a sentence,
+    /// and struck-through, "was" and per-unit prices come after every
+    /// current price.
+    pub fn prices(&self) -> Vec<PriceHit> {
+        self.prices.clone()
+    }
+
+    /// What the product on this page costs now, if a current price is shown.
+    pub fn primary_price(&self) -> Option<&PriceHit> {
+        self.prices.first().filter(|p| !p.original && !p.per_unit)
+    }
+}
+
+/// Button texts that put the product in the cart.
+const ADD_TO_CART_PHRASES: &[&str] = &[
+    "add to cart", "add to bag", "add to basket", "add to trolley", "buy now", "buy it now",
+    "in den warenkorb", "ajouter au panier", "añadir al carrito",
+];
+
+/// Classes that mark a struck-through "was" price.
+const STRUCK_PRICE_CLASSES: &[&str] = &[
+    "strike", "old-price", "price-old", "was-price", "price-was", "compare-at", "original-price",
+];
+
+/// Product signals in the layout tree that the Spatial DOM doesn't keep.
+#[derive(Debug, Default)]
+struct ProductHints {
+    /// `og:type=product`, or a schema.org Product with `offers` in JSON-LD.
+    markup: bool,
+    /// Prices shown struck through: inside `<del>`, `<s>` or `<strike>`,
+    /// styled `line-through`, or with a "was price" class.
+    struck: Vec<(f64, &'static str)>,
+}
+
+fn detect_product_from_tree(root: &LayoutNode) -> ProductHints {
+    let mut hints = ProductHints {
+        markup: collect_json_ld(root)
+            .iter()
+            .any(|v| has_schema_type(v, "Product") && v.get("offers").is_some()),
+        struck: Vec::new(),
+    };
+    scan_product_recursive(root, &mut hints);
+    hints
+}
+
+fn scan_product_recursive(node: &LayoutNode, hints: &mut ProductHints) {
+    let attr = |name: &str| node.attributes.get(name).map(|v| v.to_lowercase()).unwrap_or_default();
+    if node.tag == "meta" && attr("property") == "og:type" && attr("content").starts_with("product") {
+        hints.markup = true;
+    }
+    let struck = matches!(node.tag.as_str(), "del" | "s" | "strike")
+        || attr("style").contains("line-through")
+        || attr("class").split_whitespace().any(|c| STRUCK_PRICE_CLASSES.iter().any(|s| c.contains(s)));
+    if struck {
+        let prices = price::find_prices(&node.text_content);
+        hints.struck.extend(prices.into_iter().map(|p| (p.amount, p.currency)));
+        return;
+    }
+    for child in &node.children {
+        scan_product_recursive(child, hints);
+    }
+}
+
+/// The visible button that adds the product to the cart.
+fn find_add_to_cart(dom: &SpatialDom) -> Option<&SpatialElement> {
+    dom.els.iter().find(|e| {
+        let is_button = matches!(e.tag.as_str(), "button" | "a" | "input")
+            || e.role.as_deref() == Some("button");
+        let text = e.text.as_deref().or(e.val.as_deref()).unwrap_or("").trim().to_lowercase();
+        e.hidden != Some(true)
+            && is_button
+            && text.len() < 40
+            && ADD_TO_CART_PHRASES.iter().any(|p| text.starts_with(p))
+    })
+}
+
+/// Squared distance between the centers of two boxes.
+fn center_distance(a: &[i32; 4], b: &[i32; 4]) -> i64 {
+    let dx = (a[0] * 2 + a[2] - b[0] * 2 - b[2]) as i64 / 2;
+    let dy = (a[1] * 2 + a[3] - b[1] * 2 - b[3]) as i64 / 2;
+    dx * dx + dy * dy
+}
+
+fn detect_prices(dom: &SpatialDom, struck: &[(f64, &'static str)]) -> Vec<PriceHit> {
+    let mut found: Vec<(&SpatialElement, PriceHit)> = Vec::new();
+    for el in dom.els.iter().filter(|e| e.hidden != Some(true)) {
+        let Some(text) = el.text.as_deref() else { continue };
+        for m in price::find_prices(text) {
+            let original = m.was || struck.contains(&(m.amount, m.currency));
+            found.push((el, PriceHit {
+                element_id: el.id,
+                amount: m.amount,
+                currency: m.currency.to_string(),
+                context: truncate_context(text),
+                original,
+                per_unit: m.per_unit,
+            }));
+        }
+    }
+
+    // Text tags nest (a price in a <strong> inside a <p>), so one price can
+    // be found in an element and again in its wrapper. Keep the innermost.
+    let mut hits: Vec<(&SpatialElement, PriceHit)> = found.iter()
+        .filter(|(el, hit)| !found.iter().any(|(other, o)| {
+            o.amount == hit.amount && o.currency == hit.currency && nested_in(el, other)
+        }))
+        .cloned()
+        .collect();
+
+    // A price mentioned in a sentence ("free shipping over $35") ranks below
+    // one that stands alone as a label. Stable sort: without a cart button,
+    // current prices keep document order.
+    let cart = find_add_to_cart(dom);
+    hits.sort_by_key(|(el, hit)| {
+        let secondary = hit.original || hit.per_unit;
+        let in_sentence = hit.context.chars().filter(|c| c.is_alphabetic()).count() > 15;
+        (secondary, in_sentence, cart.map_or(0, |c| center_distance(&el.b, &c.b)))
+    });
+    hits.into_iter().map(|(_, hit)| hit).collect()
+}
+
+/// Whether `inner` sits inside `outer`. Of two elements with the same box,
+/// the later one is the inner.
+fn nested_in(outer: &SpatialElement, inner: &SpatialElement) -> bool {
+    inner.id != outer.id
+        && box_within(&inner.b, &outer.b)
+        && (inner.b != outer.b || inner.id > outer.id)
+}
+
+fn truncate_context(text: &str) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(80) {
+        Some((cut, _)) => format!("{}…", text[..cut].trim_end()),
+        None => text.to_string(),
+    }
+}
+
+/// A current price within 400px of an add-to-cart button.
+fn has_price_near_cart(dom: &SpatialDom) -> bool {
+    let Some(cart) = find_add_to_cart(dom) else { return false };
+    dom.primary_price()
+        .and_then(|p| dom.get(p.element_id))
+        .is_some_and(|el| center_distance(&el.b, &cart.b) <= 400 * 400)
+}
+
+// --- Article metadata ---
+
+/// When an article was published and who wrote it.
+///
+/// Each field is taken from the first source that has it: `<meta>` tags
+/// (`article:published_time`, `author`, …), then schema.org Article
+/// JSON-LD, then what the page shows (`<time datetime>`, bylines).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ArticleMeta {
+    /// First publication, ISO 8601.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<String>,
+    /// Last update, ISO 8601.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// A relative date shown on the page ("3 hours ago"), as written.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relative_date: Option<String>,
+}
+
+/// schema.org types whose dates and author describe the page.
+const ARTICLE_SCHEMA_TYPES: &[&str] = &[
+    "Article", "NewsArticle", "BlogPosting", "Report", "ScholarlyArticle", "TechArticle",
+    "LiveBlogPosting", "OpinionNewsArticle", "ReportageNewsArticle",
+];
+
+fn fill(slot: &mut Option<String>, value: Option<String>) {
+    if slot.is_none() {
+        *slot = value;
+    }
+}
+
+fn detect_article_meta(root: &LayoutNode) -> Option<ArticleMeta> {
+    let mut meta = ArticleMeta::default();
+    scan_article_meta_tags(root, &mut meta);
+    for object in collect_json_ld(root) {
+        if ARTICLE_SCHEMA_TYPES.iter().any(|t| has_schema_type(&object, t)) {
+            let date = |key: &str| object.get(key).and_then(|v| v.as_str()).and_then(article::normalize_date);
+            fill(&mut meta.published_at, date("datePublished"));
+            fill(&mut meta.updated_at, date("dateModified"));
+            fill(&mut meta.author, object.get("author").and_then(json_ld_names));
+        }
+    }
+    scan_visible_article_meta(root, &mut meta);
+    (meta != ArticleMeta::default()).then_some(meta)
 }
 
-// --- Page type detection ---
+/// Author names from a JSON-LD `author`: a string, a Person, or a list of either.
+fn json_ld_names(author: &serde_json::Value) -> Option<String> {
+    let names: Vec<&str> = match author.as_array() {
+        Some(list) => list.iter().filter_map(json_ld_name).collect(),
+        None => json_ld_name(author).into_iter().collect(),
+    };
+    (!names.is_empty()).then(|| names.join(", "))
+}
 
-/// Detected page type for agent decision-making.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
-pub enum PageType {
-    Login,
-    TwoFactorAuth,
-    OAuthConsent,
-    Captcha,
-    Blocked,
-    Search,
-    SearchResults,
-    Inbox,
-    EmailBody,
-    Dashboard,
-    Form,
-    Article,
-    List,
-    Error,
-    #[default]
-    Other,
+fn json_ld_name(value: &serde_json::Value) -> Option<&str> {
+    value.as_str()
+        .or_else(|| value.get("name").and_then(|n| n.as_str()))
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
 }
 
-impl PageType {
-    pub fn is_other(&self) -> bool {
-        matches!(self, PageType::Other)
+/// `<meta>` tags in the head, which sits under `display: none`.
+fn scan_article_meta_tags(node: &LayoutNode, meta: &mut ArticleMeta) {
+    if node.tag == "meta" {
+        let attr = |name: &str| node.attributes.get(name).map(|v| v.trim()).unwrap_or_default();
+        let key = [attr("property"), attr("name"), attr("itemprop")]
+            .into_iter()
+            .find(|k| !k.is_empty())
+            .unwrap_or_default()
+            .to_lowercase();
+        let content = attr("content");
+        match key.as_str() {
+            "article:published_time" | "og:article:published_time" | "datepublished" | "pubdate"
+            | "publishdate" | "date" | "dc.date.issued" => {
+                fill(&mut meta.published_at, article::normalize_date(content));
+            }
+            "article:modified_time" | "og:article:modified_time" | "og:updated_time"
+            | "datemodified" | "dc.date.modified" => {
+                fill(&mut meta.updated_at, article::normalize_date(content));
+            }
+            // `article:author` is often a profile URL rather than a name
+            "author" | "article:author" | "dc.creator" if !content.is_empty() && !content.contains("://") => {
+                fill(&mut meta.author, Some(content.to_string()));
+            }
+            _ => {}
+        }
+    }
+    for child in &node.children {
+        scan_article_meta_tags(child, meta);
     }
 }
 
-// --- Pagination detection ---
-
-/// Pagination links detected on the page.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Pagination {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub next: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub prev: Option<String>,
-    /// Numbered page links: (label, url)
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub pages: Vec<(String, String)>,
+/// `<time>` elements, bylines and date labels the reader can see.
+fn scan_visible_article_meta(node: &LayoutNode, meta: &mut ArticleMeta) {
+    if node.node_type == NodeType::Text || is_tree_hidden(node) {
+        return;
+    }
+    let attr = |name: &str| node.attributes.get(name).map(|v| v.to_lowercase()).unwrap_or_default();
+    let class = attr("class");
+    let text = node.text_content.trim();
+
+    if node.tag == "time" {
+        let updated = attr("itemprop") == "datemodified"
+            || class.contains("updated") || class.contains("modified");
+        let date = node.attributes.get("datetime")
+            .and_then(|d| article::normalize_date(d))
+            .or_else(|| article::normalize_date(text));
+        fill(if updated { &mut meta.updated_at } else { &mut meta.published_at }, date);
+        fill(&mut meta.relative_date, article::relative_date(text));
+    } else if text.chars().count() <= 80 {
+        let is_byline = class.contains("byline") || class.contains("author")
+            || attr("rel") == "author" || attr("itemprop") == "author";
+        if is_byline {
+            fill(&mut meta.author, article::byline_author(text, false));
+            fill(&mut meta.relative_date, article::relative_date(text));
+        } else if matches!(node.tag.as_str(), "p" | "span" | "div" | "address") {
+            fill(&mut meta.author, article::byline_author(text, true));
+        }
+        let is_date_label = ["date", "timestamp", "published"].iter().any(|c| class.contains(c));
+        if is_date_label {
+            fill(&mut meta.published_at, article::normalize_date(text));
+            fill(&mut meta.relative_date, article::relative_date(text));
+        }
+    }
+    for child in &node.children {
+        scan_visible_article_meta(child, meta);
+    }
 }
 
-impl SpatialDom {
-    /// Detect pagination links on the page.
-    pub fn pagination(&self) -> Option<Pagination> {
-        let links: Vec<&SpatialElement> = self.els.iter()
-            .filter(|e| e.role.as_deref() == Some("link") && e.href.is_some())
-            .collect();
+// --- Structured data (JSON-LD) ---
 
-        let mut next: Option<String> = None;
-        let mut prev: Option<String> = None;
-        let mut pages: Vec<(String, String)> = Vec::new();
+/// Every JSON-LD object on the page, with top-level arrays and `@graph`
+/// lists flattened. Blocks that fail to parse are skipped.
+pub(crate) fn collect_json_ld(root: &LayoutNode) -> Vec<serde_json::Value> {
+    let mut out = Vec::new();
+    collect_json_ld_recursive(root, &mut out);
+    out
+}
 
-        for link in &links {
-            let text = link.text.as_deref().unwrap_or("").trim().to_lowercase();
-            let href = link.href.as_deref().unwrap_or("");
+fn collect_json_ld_recursive(node: &LayoutNode, out: &mut Vec<serde_json::Value>) {
+    let is_json_ld = node.tag == "script"
+        && node.attributes.get("type")
+            .is_some_and(|t| t.trim().eq_ignore_ascii_case("application/ld+json"));
+    if is_json_ld {
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(node.text.trim()) {
+            flatten_json_ld(value, out);
+        }
+        return;
+    }
+    for child in &node.children {
+        collect_json_ld_recursive(child, out);
+    }
+}
 
-            if text == "next" || text == "next page" || text == ">" || text == ">>"
-                || text == "\u{203a}" || text == "\u{00bb}"
-            {
-                next = Some(href.to_string());
-            } else if text == "previous" || text == "prev" || text == "prev page"
-                || text == "<" || text == "<<"
-                || text == "\u{2039}" || text == "\u{00ab}"
-            {
-                prev = Some(href.to_string());
-            } else if text.chars().all(|c| c.is_ascii_digit()) && !text.is_empty() {
-                pages.push((text.clone(), href.to_string()));
+fn flatten_json_ld(value: serde_json::Value, out: &mut Vec<serde_json::Value>) {
+    match value {
+        serde_json::Value::Array(items) => {
+            for item in items {
+                flatten_json_ld(item, out);
             }
         }
-
-        if next.is_some() || prev.is_some() || !pages.is_empty() {
-            Some(Pagination { next, prev, pages })
-        } else {
-            None
+        serde_json::Value::Object(mut map) => {
+            if let Some(graph) = map.remove("@graph") {
+                flatten_json_ld(graph, out);
+            }
+            if map.contains_key("@type") {
+                out.push(serde_json::Value::Object(map));
+            }
         }
+        _ => {}
+    }
+}
+
+/// Whether a JSON-LD object's `@type` (a string or a list) includes `name`.
+pub(crate) fn has_schema_type(value: &serde_json::Value, name: &str) -> bool {
+    match value.get("@type") {
+        Some(serde_json::Value::String(t)) => t == name,
+        Some(serde_json::Value::Array(types)) => types.iter().any(|t| t.as_str() == Some(name)),
+        _ => false,
     }
 }
 
@@ -1295,62 +3901,101 @@ impl SpatialDom {
 
         codes
     }
-}
 
-// --- Search input matching ---
+    /// Find "magic links": links whose URL carries a verification token and
+    /// whose own or nearby text talks about verifying. Returns element ID and href.
+    pub fn find_verification_links(&self) -> Vec<(u32, String)> {
+        let href_markers = ["verify", "confirm", "token=", "code="];
+        let text_keywords = [
+            "verification code", "security code", "your code",
+            "enter code", "otp", "passcode", "one-time",
+            "verify", "confirm", "magic link", "sign in", "log in", "activate",
+        ];
+        let has_keyword = |text: &str| {
+            let lower = text.to_lowercase();
+            text_keywords.iter().any(|kw| lower.contains(kw))
+        };
 
-/// Check if an element is a search input (input or textarea used for search queries).
-fn is_search_input(e: &SpatialElement) -> bool {
-    let is_text_input = match e.tag.as_str() {
-        "input" => !matches!(
-            e.input_type.as_deref(),
-            Some("checkbox") | Some("radio") | Some("hidden") | Some("submit")
-            | Some("button") | Some("image") | Some("password")
-        ),
-        "textarea" => true,
-        _ => return false,
-    };
-    if !is_text_input { return false; }
+        let mut links: Vec<(u32, String)> = Vec::new();
+        for el in &self.els {
+            let href = match (el.tag.as_str(), &el.href) {
+                ("a", Some(href)) => href,
+                _ => continue,
+            };
+            let href_lower = href.to_lowercase();
+            if !href_markers.iter().any(|m| href_lower.contains(m)) {
+                continue;
+            }
+            // The link text itself, or a short line of text within 100px Y
+            let text_matches = el.text.as_deref().is_some_and(has_keyword)
+                || self.els.iter().any(|other| {
+                    (other.b[1] - el.b[1]).abs() < 100
+                        && other.text.as_deref().is_some_and(|t| t.len() < 200 && has_keyword(t))
+                });
+            if text_matches && !links.iter().any(|(_, h)| h == href) {
+                links.push((el.id, href.clone()));
+            }
+        }
+        links
+    }
+
+    /// Codes and magic links that could complete a verification step.
+    pub fn find_verification(&self) -> VerificationHints {
+        VerificationHints {
+            codes: self.find_codes(),
+            links: self.find_verification_links(),
+        }
+    }
+}
+
+/// Everything on a page that could complete a verification step.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct VerificationHints {
+    /// Verification codes found in the page text, as from `find_codes`.
+    pub codes: Vec<String>,
+    /// Magic links as (element ID, href), as from `find_verification_links`.
+    pub links: Vec<(u32, String)>,
+}
 
-    e.input_type.as_deref() == Some("search")
-        || e.role.as_deref() == Some("searchbox")
-        || e.name.as_deref() == Some("q")
-        || e.name.as_ref().map(|n| n.to_lowercase().contains("search")).unwrap_or(false)
-        || e.ph.as_ref().map(|p| p.to_lowercase().contains("search")).unwrap_or(false)
-        || e.label.as_ref().map(|l| l.to_lowercase().contains("search")).unwrap_or(false)
+impl VerificationHints {
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty() && self.links.is_empty()
+    }
 }
 
 /// Find the ID of a visible email input field.
-fn find_email_input_id(dom: &SpatialDom) -> Option<u32> {
-    dom.els.iter().find(|e| {
-        e.hidden != Some(true) && (
+/// Whether a field's `autocomplete` attribute names `token`, ignoring the
+/// section and `shipping`/`billing` tokens before it.
+fn autocompletes(e: &SpatialElement, token: &str) -> bool {
+    e.autocomplete
+        .as_deref()
+        .and_then(|a| a.split_whitespace().last())
+        .is_some_and(|last| last.eq_ignore_ascii_case(token))
+}
+
+fn find_email_input_id(dom: &SpatialDom, index: &PageIndex) -> Option<u32> {
+    let autocompleted = dom.els.iter()
+        .find(|e| e.hidden != Some(true) && e.tag == "input" && autocompletes(e, "email"));
+    if let Some(e) = autocompleted {
+        return Some(e.id);
+    }
+    index.iter(dom).find(|(e, f)| {
+        f.visible && (
             e.input_type.as_deref() == Some("email")
-            || e.name.as_ref().map(|n| {
-                let lower = n.to_lowercase();
-                lower == "email" || lower == "e-mail"
-            }).unwrap_or(false)
+            || f.name_is(&["email", "e-mail"])
         )
-    }).map(|e| e.id)
+    }).map(|(e, _)| e.id)
 }
 
-fn detect_blocked_info(dom: &SpatialDom) -> Option<BlockedInfo> {
+fn detect_blocked_info(dom: &SpatialDom, index: &PageIndex) -> Option<BlockedInfo> {
     let mut signals = Vec::new();
 
-    let title_lower = dom.title.to_lowercase();
     let mut text = String::new();
-    text.push_str(&title_lower);
-    for e in &dom.els {
-        if let Some(t) = &e.text {
-            text.push(' ');
-            text.push_str(&t.to_lowercase());
-        }
-        if let Some(l) = &e.label {
-            text.push(' ');
-            text.push_str(&l.to_lowercase());
-        }
-        if let Some(p) = &e.ph {
+    text.push_str(&index.title);
+    for (e, f) in index.iter(dom) {
+        for value in [&f.text, &f.label, &f.ph].into_iter().flatten() {
             text.push(' ');
-            text.push_str(&p.to_lowercase());
+            text.push_str(value);
         }
         if let Some(a) = &e.alert_type {
             text.push(' ');
@@ -1428,19 +4073,9 @@ fn detect_blocked_info(dom: &SpatialDom) -> Option<BlockedInfo> {
 
 // --- Page type detection ---
 
-fn detect_page_type(dom: &SpatialDom) -> PageType {
-    let title_lower = dom.title.to_lowercase();
-
-    let title_has = |keywords: &[&str]| keywords.iter().any(|kw| title_lower.contains(kw));
-    let heading_has = |keywords: &[&str]| {
-        dom.els.iter().any(|e| {
-            e.role.as_deref() == Some("heading")
-                && e.text.as_ref().map(|t| {
-                    let lower = t.to_lowercase();
-                    keywords.iter().any(|kw| lower.contains(kw))
-                }).unwrap_or(false)
-        })
-    };
+fn detect_page_type(dom: &SpatialDom, index: &PageIndex, product_markup: bool) -> PageType {
+    let title_has = |keywords: &[&str]| index.title_has(keywords);
+    let heading_has = |keywords: &[&str]| index.heading_has(keywords);
 
     let visible_count = dom.els.iter().filter(|e| e.hidden != Some(true)).count();
 
@@ -1478,9 +4113,7 @@ fn detect_page_type(dom: &SpatialDom) -> PageType {
     }
 
     // Login
-    let has_password = dom.els.iter().any(|e| {
-        e.hidden != Some(true) && e.input_type.as_deref() == Some("password")
-    });
+    let has_password = index.els.iter().any(|f| f.password);
     if has_password {
         return PageType::Login;
     }
@@ -1520,9 +4153,7 @@ fn detect_page_type(dom: &SpatialDom) -> PageType {
     // EmailBody
     let email_markers = ["from:", "to:", "subject:", "date:"];
     let marker_count = email_markers.iter().filter(|marker| {
-        dom.els.iter().any(|e| {
-            e.text.as_ref().map(|t| t.to_lowercase().contains(*marker)).unwrap_or(false)
-        })
+        index.els.iter().any(|f| f.text_has(&[**marker]))
     }).count();
     if marker_count >= 3 {
         return PageType::EmailBody;
@@ -1536,6 +4167,13 @@ fn detect_page_type(dom: &SpatialDom) -> PageType {
         return PageType::Dashboard;
     }
 
+    // Product — og:type or JSON-LD offers, or a price beside an add-to-cart
+    // button. Before Article and List: product pages carry long descriptions
+    // and plenty of navigation links.
+    if product_markup || has_price_near_cart(dom) {
+        return PageType::Product;
+    }
+
     // Article (before Search — many content pages have search bars)
     // When a page has many links (typical of list pages), require more long text to
     // classify as Article. This prevents content-heavy list pages (e.g. subreddits with
@@ -1560,10 +4198,8 @@ fn detect_page_type(dom: &SpatialDom) -> PageType {
 
     // SearchResults — must come before List, since search result pages have many links.
     // Multiple signals: title/heading keywords, URL query params, search input presence.
-    let has_visible_search_input = dom.els.iter().any(|e| {
-        e.hidden != Some(true) && is_search_input(e)
-    });
-    let has_any_search_input = dom.els.iter().any(|e| is_search_input(e));
+    let has_visible_search_input = index.els.iter().any(|f| f.visible && f.search_input);
+    let has_any_search_input = index.els.iter().any(|f| f.search_input);
     let search_results_keywords = &["search results", "results for", "search:", "found"];
     let has_search_results_context = title_has(search_results_keywords)
         || heading_has(search_results_keywords)
@@ -1619,9 +4255,7 @@ fn detect_page_type(dom: &SpatialDom) -> PageType {
     // Search (hidden fallback) — JS-rendered search engines (e.g. DuckDuckGo) hide the search
     // input without JS. Detect these when the page has very few visible elements.
     if visible_count < 5 {
-        let has_hidden_search = dom.els.iter().any(|e| {
-            e.hidden == Some(true) && is_search_input(e)
-        });
+        let has_hidden_search = index.els.iter().any(|f| !f.visible && f.search_input);
         if has_hidden_search {
             return PageType::Search;
         }
@@ -1632,56 +4266,48 @@ fn detect_page_type(dom: &SpatialDom) -> PageType {
 
 // --- Suggested action detection ---
 
-fn detect_suggested_actions(dom: &SpatialDom) -> Vec<SuggestedAction> {
-    let mut actions = Vec::new();
-
-    if let Some(a) = detect_blocked_action(dom) {
-        actions.push(a);
-    }
+/// A suggested action detector. Detectors only read the page, so they can
+/// run side by side.
+type Detector = fn(&SpatialDom, &PageIndex) -> Option<SuggestedAction>;
+
+/// The detectors whose actions come before FillForm, in the order listed.
+const BEFORE_FILL_FORM: [Detector; 10] = [
+    detect_blocked_action,
+    detect_account_action,
+    detect_enter_code_action,
+    detect_consent_action,
+    detect_contact_action,
+    detect_search_action,
+    detect_select_from_list_action,
+    detect_cookie_consent_action,
+    detect_paginate_action,
+    detect_load_more_action,
+];
 
-    // Register vs Login: prefer Register when registration context is present.
-    // Both detect password fields, but Register also checks for confirm-password or
-    // registration keywords in title/heading.
-    if let Some(a) = detect_register_action(dom) {
-        actions.push(a);
-    } else if let Some(a) = detect_login_action(dom) {
-        actions.push(a);
-    }
-    if let Some(a) = detect_enter_code_action(dom) {
-        actions.push(a);
-    }
-    if let Some(a) = detect_consent_action(dom) {
-        actions.push(a);
-    }
-    if let Some(a) = detect_contact_action(dom) {
-        actions.push(a);
-    }
-    if let Some(a) = detect_search_action(dom) {
-        actions.push(a);
-    }
-    if let Some(a) = detect_select_from_list_action(dom) {
-        actions.push(a);
-    }
-    if let Some(a) = detect_cookie_consent_action(dom) {
-        actions.push(a);
-    }
-    if let Some(a) = detect_paginate_action(dom) {
-        actions.push(a);
-    }
-    if let Some(a) = detect_fill_form_action(dom, &actions) {
-        actions.push(a);
-    }
-    if let Some(a) = detect_download_action(dom) {
-        actions.push(a);
-    }
-    if let Some(a) = detect_captcha_challenge_action(dom) {
-        actions.push(a);
-    }
+/// The detectors whose actions come after FillForm.
+const AFTER_FILL_FORM: [Detector; 2] = [detect_download_action, detect_captcha_challenge_action];
 
-    actions
+/// Every suggested action for `dom`, deduplicated and always in the same
+/// order (see [`actions::normalize`]). With `parallel` the detectors run on
+/// rayon's pool.
+fn detect_suggested_actions(dom: &SpatialDom, index: &PageIndex, parallel: bool) -> Vec<SuggestedAction> {
+    let run = |detect: &Detector| detect(dom, index);
+    let mut found: Vec<Option<SuggestedAction>> = if parallel {
+        BEFORE_FILL_FORM.par_iter().chain(AFTER_FILL_FORM.par_iter()).map(run).collect()
+    } else {
+        BEFORE_FILL_FORM.iter().chain(&AFTER_FILL_FORM).map(run).collect()
+    };
+    let after = found.split_off(BEFORE_FILL_FORM.len());
+
+    // FillForm only fires when no more specific form action did
+    let mut actions: Vec<SuggestedAction> = found.into_iter().flatten().collect();
+    let fill_form = detect_fill_form_actions(dom, &actions);
+    actions.extend(fill_form);
+    actions.extend(after.into_iter().flatten());
+    actions::normalize(actions)
 }
 
-fn detect_blocked_action(dom: &SpatialDom) -> Option<SuggestedAction> {
+fn detect_blocked_action(dom: &SpatialDom, _index: &PageIndex) -> Option<SuggestedAction> {
     let info = dom.blocked.as_ref()?;
     Some(SuggestedAction::RetryGuidance {
         reason: info.reason.clone(),
@@ -1690,36 +4316,53 @@ fn detect_blocked_action(dom: &SpatialDom) -> Option<SuggestedAction> {
     })
 }
 
-fn detect_login_action(dom: &SpatialDom) -> Option<SuggestedAction> {
-    let password = dom.els.iter().find(|e| {
-        e.hidden != Some(true) && e.input_type.as_deref() == Some("password")
-    })?;
+/// Register vs Login: prefer Register when registration context is present.
+/// Both detect password fields, but Register also checks for confirm-password or
+/// registration keywords in title/heading.
+fn detect_account_action(dom: &SpatialDom, index: &PageIndex) -> Option<SuggestedAction> {
+    detect_register_action(dom, index).or_else(|| detect_login_action(dom, index))
+}
+
+fn detect_login_action(dom: &SpatialDom, index: &PageIndex) -> Option<SuggestedAction> {
+    let passwords: Vec<&SpatialElement> = index.iter(dom)
+        .filter(|(_, f)| f.password)
+        .map(|(e, _)| e)
+        .collect();
+    // autocomplete="current-password" names the field outright; a new password is for registering
+    let password = passwords.iter()
+        .find(|e| autocompletes(e, "current-password"))
+        .or_else(|| passwords.iter().find(|e| !autocompletes(e, "new-password")))?;
     let password_id = password.id;
     let password_y = password.b[1];
 
-    // Find nearest text/email input within 500px Y distance
-    let username = dom.els.iter()
+    // autocomplete="username" (or "email") pairs fields however far apart they are,
+    // otherwise find the nearest text/email input within 500px Y distance
+    let inputs = || dom.els.iter()
         .filter(|e| e.hidden != Some(true) && e.tag == "input")
         .filter(|e| {
             let t = e.input_type.as_deref().unwrap_or("text");
             t == "text" || t == "email"
-        })
-        .filter(|e| (e.b[1] - password_y).abs() < 500)
-        .min_by_key(|e| (e.b[1] - password_y).abs())?;
+        });
+    let username = inputs()
+        .find(|e| autocompletes(e, "username"))
+        .or_else(|| inputs().find(|e| autocompletes(e, "email")))
+        .or_else(|| {
+            inputs()
+                .filter(|e| (e.b[1] - password_y).abs() < 500)
+                .min_by_key(|e| (e.b[1] - password_y).abs())
+        })?;
     let username_id = username.id;
 
     let submit_id = find_nearest_submit_button(dom, password_id)?;
 
     // Optional: find "remember me" checkbox
-    let remember_me_id = dom.els.iter()
-        .filter(|e| e.hidden != Some(true))
-        .filter(|e| e.input_type.as_deref() == Some("checkbox"))
-        .find(|e| {
-            let label = e.label.as_deref().unwrap_or("").to_lowercase();
-            let name = e.name.as_deref().unwrap_or("").to_lowercase();
-            label.contains("remember") || name.contains("remember")
+    let remember_me_id = index.iter(dom)
+        .filter(|(e, f)| f.visible && e.input_type.as_deref() == Some("checkbox"))
+        .find(|(_, f)| {
+            let mentions = |value: &Option<String>| value.as_deref().is_some_and(|v| v.contains("remember"));
+            mentions(&f.label) || mentions(&f.name)
         })
-        .map(|e| e.id);
+        .map(|(e, _)| e.id);
 
     Some(SuggestedAction::Login {
         username_id,
@@ -1729,63 +4372,68 @@ fn detect_login_action(dom: &SpatialDom) -> Option<SuggestedAction> {
     })
 }
 
-fn detect_enter_code_action(dom: &SpatialDom) -> Option<SuggestedAction> {
+/// Confidence of an EnterCode action on an input nothing marks as a code
+/// field, picked only because the page mentions verification.
+const FALLBACK_CODE_CONFIDENCE: f32 = 0.5;
+
+fn detect_enter_code_action(dom: &SpatialDom, index: &PageIndex) -> Option<SuggestedAction> {
+    // autocomplete="one-time-code" identifies the input without any wording around it
+    if let Some(input) = dom.els.iter()
+        .find(|e| e.hidden != Some(true) && e.tag == "input" && autocompletes(e, "one-time-code"))
+    {
+        let submit_id = find_nearest_submit_button(dom, input.id)?;
+        return Some(SuggestedAction::EnterCode {
+            input_id: input.id,
+            submit_id,
+            code_length: None,
+            confidence: None,
+        });
+    }
+
     let verification_keywords = [
         "verification", "verify your", "enter code", "security code", "verification code",
         "2fa", "two-factor", "two factor", "otp", "one-time", "passcode",
     ];
 
-    let title_lower = dom.title.to_lowercase();
-    let has_keyword_in_title = verification_keywords.iter().any(|kw| title_lower.contains(kw));
-
-    let has_keyword_in_heading = dom.els.iter().any(|e| {
-        e.role.as_deref() == Some("heading")
-            && e.text.as_ref().map(|t| {
-                let lower = t.to_lowercase();
-                verification_keywords.iter().any(|kw| lower.contains(kw))
-            }).unwrap_or(false)
-    });
-
-    if !has_keyword_in_title && !has_keyword_in_heading {
+    if !index.title_has(&verification_keywords) && !index.heading_has(&verification_keywords) {
         return None;
     }
 
     // Don't emit EnterCode if there's a password field (that's Login)
-    if dom.els.iter().any(|e| e.hidden != Some(true) && e.input_type.as_deref() == Some("password")) {
+    if index.els.iter().any(|f| f.password) {
         return None;
     }
 
     let code_keywords = ["code", "otp", "pin", "verify"];
 
     // Find code-like inputs by name/label/placeholder
-    let code_inputs: Vec<&SpatialElement> = dom.els.iter()
-        .filter(|e| e.hidden != Some(true) && e.tag == "input")
-        .filter(|e| {
+    let code_inputs: Vec<&SpatialElement> = index.iter(dom)
+        .filter(|(e, f)| f.visible && e.tag == "input")
+        .filter(|(e, _)| {
             let t = e.input_type.as_deref().unwrap_or("text");
             t == "text" || t == "number" || t == "tel"
         })
-        .filter(|e| {
-            let name = e.name.as_deref().unwrap_or("").to_lowercase();
-            let label = e.label.as_deref().unwrap_or("").to_lowercase();
-            let ph = e.ph.as_deref().unwrap_or("").to_lowercase();
-            code_keywords.iter().any(|kw| name.contains(kw) || label.contains(kw) || ph.contains(kw))
+        .filter(|(_, f)| {
+            [&f.name, &f.label, &f.ph]
+                .into_iter()
+                .flatten()
+                .any(|value| code_keywords.iter().any(|kw| value.contains(kw)))
         })
+        .map(|(e, _)| e)
         .collect();
 
-    // Check for separate digit inputs (width < 60px, count 4-8)
-    let narrow_inputs: Vec<&SpatialElement> = dom.els.iter()
-        .filter(|e| e.hidden != Some(true) && e.tag == "input")
-        .filter(|e| {
-            let t = e.input_type.as_deref().unwrap_or("text");
-            (t == "text" || t == "number" || t == "tel") && e.b[2] < 60
-        })
-        .collect();
+    // Check for separate digit inputs (width < 60px, a run of 4-8 in one form)
+    let digit_run = dom.els.iter()
+        .find(|e| is_code_digit_input(e))
+        .map(|e| code_digit_run(dom, e.id))
+        .unwrap_or_default();
 
     let (input_id, code_length);
+    let mut confidence = None;
 
-    if narrow_inputs.len() >= 4 && narrow_inputs.len() <= 8 {
-        code_length = Some(narrow_inputs.len());
-        input_id = narrow_inputs[0].id;
+    if digit_run.len() >= 4 && digit_run.len() <= 8 {
+        code_length = Some(digit_run.len());
+        input_id = digit_run[0];
     } else if !code_inputs.is_empty() {
         input_id = code_inputs[0].id;
         code_length = None;
@@ -1799,6 +4447,7 @@ fn detect_enter_code_action(dom: &SpatialDom) -> Option<SuggestedAction> {
             })?;
         input_id = any_input.id;
         code_length = None;
+        confidence = Some(FALLBACK_CODE_CONFIDENCE);
     }
 
     let submit_id = find_nearest_submit_button(dom, input_id)?;
@@ -1807,38 +4456,35 @@ fn detect_enter_code_action(dom: &SpatialDom) -> Option<SuggestedAction> {
         input_id,
         submit_id,
         code_length,
+        confidence,
     })
 }
 
-fn detect_search_action(dom: &SpatialDom) -> Option<SuggestedAction> {
+fn detect_search_action(dom: &SpatialDom, index: &PageIndex) -> Option<SuggestedAction> {
     // Prefer visible search inputs, but fall back to hidden ones (JS-rendered search engines)
-    let search_input = dom.els.iter()
-        .filter(|e| e.hidden != Some(true))
-        .find(|e| is_search_input(e))
-        .or_else(|| dom.els.iter().find(|e| is_search_input(e)))?;
+    let (search_input, _) = index.iter(dom)
+        .find(|(_, f)| f.visible && f.search_input)
+        .or_else(|| index.iter(dom).find(|(_, f)| f.search_input))?;
 
     // Try visible submit buttons first, then fall back to any (including hidden)
     let submit_id = find_nearest_submit_button(dom, search_input.id)
         .or_else(|| find_nearest_button(dom, search_input.id));
 
+    // Many search boxes submit on Enter and have no button at all
+    let note = submit_id.is_none().then(|| "No search button; press Enter in the input".to_string());
     Some(SuggestedAction::Search {
         input_id: search_input.id,
-        submit_id: submit_id?,
+        submit_id,
+        note,
     })
 }
 
-fn detect_consent_action(dom: &SpatialDom) -> Option<SuggestedAction> {
+fn detect_consent_action(dom: &SpatialDom, index: &PageIndex) -> Option<SuggestedAction> {
     let oauth_keywords = ["authorize", "allow access", "grant permission", "oauth", "consent"];
 
-    let title_lower = dom.title.to_lowercase();
-    let has_keyword_in_title = oauth_keywords.iter().any(|kw| title_lower.contains(kw));
-
-    let has_keyword_in_heading = dom.els.iter().any(|e| {
-        matches!(e.tag.as_str(), "h1" | "h2")
-            && e.text.as_ref().map(|t| {
-                let lower = t.to_lowercase();
-                oauth_keywords.iter().any(|kw| lower.contains(kw))
-            }).unwrap_or(false)
+    let has_keyword_in_title = index.title_has(&oauth_keywords);
+    let has_keyword_in_heading = index.iter(dom).any(|(e, f)| {
+        matches!(e.tag.as_str(), "h1" | "h2") && f.text_has(&oauth_keywords)
     });
 
     if !has_keyword_in_title && !has_keyword_in_heading {
@@ -1849,16 +4495,9 @@ fn detect_consent_action(dom: &SpatialDom) -> Option<SuggestedAction> {
     let deny_words = ["deny", "cancel", "decline", "reject"];
 
     let find_button_ids = |words: &[&str]| -> Vec<u32> {
-        dom.els.iter()
-            .filter(|e| e.hidden != Some(true))
-            .filter(|e| e.tag == "button" || e.role.as_deref() == Some("button"))
-            .filter(|e| {
-                e.text.as_ref().map(|t| {
-                    let lower = t.to_lowercase();
-                    words.iter().any(|w| lower.contains(w))
-                }).unwrap_or(false)
-            })
-            .map(|e| e.id)
+        index.iter(dom)
+            .filter(|(_, f)| f.visible && f.button && f.text_has(words))
+            .map(|(e, _)| e.id)
             .collect()
     };
 
@@ -1872,7 +4511,7 @@ fn detect_consent_action(dom: &SpatialDom) -> Option<SuggestedAction> {
     Some(SuggestedAction::Consent { approve_ids, deny_ids })
 }
 
-fn detect_select_from_list_action(dom: &SpatialDom) -> Option<SuggestedAction> {
+fn detect_select_from_list_action(dom: &SpatialDom, _index: &PageIndex) -> Option<SuggestedAction> {
     let mut links: Vec<&SpatialElement> = dom.els.iter()
         .filter(|e| e.hidden != Some(true))
         .filter(|e| e.tag == "a" && e.href.is_some())
@@ -1908,6 +4547,46 @@ fn detect_select_from_list_action(dom: &SpatialDom) -> Option<SuggestedAction> {
     Some(SuggestedAction::SelectFromList { items })
 }
 
+/// Whether `e` looks like one box of a split verification code widget:
+/// a visible, narrow (< 60px) text, number or tel input.
+pub(crate) fn is_code_digit_input(e: &SpatialElement) -> bool {
+    let t = e.input_type.as_deref().unwrap_or("text");
+    e.hidden != Some(true)
+        && e.tag == "input"
+        && (t == "text" || t == "number" || t == "tel")
+        && e.b[2] < 60
+}
+
+/// The split code boxes that run on from `first_id`: narrow inputs of the
+/// same form and size, each right after the one before. Text and hidden
+/// elements between the boxes are passed over; any other control ends the run.
+///
+/// A box follows the one before it when it sits to its right on the same row,
+/// or directly below it at the same x, which is where layout puts inline
+/// boxes that share a block parent.
+pub(crate) fn code_digit_run(dom: &SpatialDom, first_id: u32) -> Vec<u32> {
+    let mut els = dom.els.iter().skip_while(|e| e.id != first_id);
+    let Some(first) = els.next().filter(|e| is_code_digit_input(e)) else {
+        return Vec::new();
+    };
+    let mut run = vec![first.id];
+    let mut prev = first;
+    for e in els {
+        if e.hidden == Some(true) || !matches!(e.tag.as_str(), "input" | "select" | "textarea" | "button") {
+            continue;
+        }
+        let prev_bottom = prev.b[1] + prev.b[3];
+        let beside = e.b[0] > prev.b[0] && e.b[1] < prev_bottom && prev.b[1] < e.b[1] + e.b[3];
+        let below = e.b[0] == prev.b[0] && e.b[1] >= prev_bottom && e.b[1] - prev_bottom < prev.b[3];
+        if !is_code_digit_input(e) || e.form != first.form || e.b[2..] != first.b[2..] || !(beside || below) {
+            break;
+        }
+        run.push(e.id);
+        prev = e;
+    }
+    run
+}
+
 /// Find the nearest visible submit button to an input element.
 /// Prefers buttons below the input; scores by Manhattan distance (Y weighted 2x).
 pub(crate) fn find_nearest_submit_button(dom: &SpatialDom, input_id: u32) -> Option<u32> {
@@ -1920,20 +4599,14 @@ fn find_nearest_button(dom: &SpatialDom, input_id: u32) -> Option<u32> {
     find_nearest_button_impl(dom, input_id, false)
 }
 
-fn detect_cookie_consent_action(dom: &SpatialDom) -> Option<SuggestedAction> {
-    // Find elements containing cookie-related text (the banner/notice itself)
+fn detect_cookie_consent_action(dom: &SpatialDom, index: &PageIndex) -> Option<SuggestedAction> {
+    // Find elements containing cookie-related text (the banner/notice itself).
+    // Must be a substantial text block mentioning cookies, not just a word
     let cookie_keywords = ["cookie", "cookies", "gdpr", "privacy notice", "consent to"];
-    let cookie_elements: Vec<&SpatialElement> = dom.els.iter()
-        .filter(|e| {
-            e.text.as_ref().map(|t| {
-                // Must be a substantial text block mentioning cookies, not just a word
-                let lower = t.to_lowercase();
-                t.len() > 30 && cookie_keywords.iter().any(|kw| lower.contains(kw))
-            }).unwrap_or(false)
-        })
-        .collect();
-
-    if cookie_elements.is_empty() {
+    let has_cookie_notice = index.iter(dom).any(|(e, f)| {
+        e.text.as_ref().is_some_and(|t| t.len() > 30) && f.text_has(&cookie_keywords)
+    });
+    if !has_cookie_notice {
         return None;
     }
 
@@ -1944,18 +4617,12 @@ fn detect_cookie_consent_action(dom: &SpatialDom) -> Option<SuggestedAction> {
     ];
     let reject_words = ["reject all", "reject cookies", "decline all", "refuse"];
 
-    // Only look at buttons (not links — "accept" as a link is usually not a cookie button)
-    let buttons: Vec<&SpatialElement> = dom.els.iter()
-        .filter(|e| e.tag == "button" || e.role.as_deref() == Some("button"))
-        .collect();
-
+    // Only look at buttons (not links — "accept" as a link is usually not a cookie button).
+    // Banners often repeat their buttons, in a compact bar and a settings panel: take the
+    // one that stands out.
     let find_button_with_words = |words: &[&str]| -> Option<u32> {
-        buttons.iter().find(|e| {
-            e.text.as_ref().map(|t| {
-                let lower = t.to_lowercase().trim().to_string();
-                words.iter().any(|w| lower == *w || lower.contains(w))
-            }).unwrap_or(false)
-        }).map(|e| e.id)
+        let buttons = index.iter(dom).filter(|(_, f)| f.button && f.text_has(words)).map(|(e, _)| e);
+        most_prominent_of(buttons).map(|e| e.id)
     };
 
     let accept_id = find_button_with_words(&accept_words)?;
@@ -1964,7 +4631,7 @@ fn detect_cookie_consent_action(dom: &SpatialDom) -> Option<SuggestedAction> {
     Some(SuggestedAction::CookieConsent { accept_id, reject_id })
 }
 
-fn detect_paginate_action(dom: &SpatialDom) -> Option<SuggestedAction> {
+fn detect_paginate_action(dom: &SpatialDom, _index: &PageIndex) -> Option<SuggestedAction> {
     let pagination = dom.pagination()?;
 
     // Find element IDs for next/prev links
@@ -1984,83 +4651,87 @@ fn detect_paginate_action(dom: &SpatialDom) -> Option<SuggestedAction> {
     Some(SuggestedAction::Paginate { next_id, prev_id })
 }
 
+fn detect_load_more_action(dom: &SpatialDom, _index: &PageIndex) -> Option<SuggestedAction> {
+    dom.load_more_button().map(|button_id| SuggestedAction::LoadMore { button_id })
+}
+
 /// Detect a registration form: password + confirm password or email/name fields,
 /// but NOT already a login page (login has its own action).
-fn detect_register_action(dom: &SpatialDom) -> Option<SuggestedAction> {
-    // Must have a visible password field
-    let password = dom.els.iter().find(|e| {
-        e.hidden != Some(true) && e.input_type.as_deref() == Some("password")
-    })?;
-    let password_id = password.id;
-
+fn detect_register_action(dom: &SpatialDom, index: &PageIndex) -> Option<SuggestedAction> {
     // Must NOT be a login page — registration has either a confirm password field,
     // or registration-related keywords in heading/title, or "register"/"sign up" in submit button
-    let all_passwords: Vec<&SpatialElement> = dom.els.iter().filter(|e| {
-        e.hidden != Some(true) && e.input_type.as_deref() == Some("password")
-    }).collect();
+    let all_passwords: Vec<&SpatialElement> = index.iter(dom)
+        .filter(|(_, f)| f.password)
+        .map(|(e, _)| e)
+        .collect();
+
+    // Must have a visible password field
+    let mut password_id = all_passwords.first()?.id;
+
+    // autocomplete="new-password" settles it, unless the page also asks for the
+    // current password (changing a password is not registering)
+    let new_passwords: Vec<&SpatialElement> = all_passwords.iter()
+        .copied()
+        .filter(|e| autocompletes(e, "new-password"))
+        .collect();
+    let autocompleted = !new_passwords.is_empty()
+        && !all_passwords.iter().any(|e| autocompletes(e, "current-password"));
+    if autocompleted {
+        password_id = new_passwords[0].id;
+    } else if all_passwords.iter().all(|e| autocompletes(e, "current-password")) {
+        // Every password field asks for an existing password: a login form
+        return None;
+    }
 
-    let title_lower = dom.title.to_lowercase();
     let register_keywords = ["register", "sign up", "signup", "create account", "join", "new account"];
     let login_keywords = ["login", "log in", "sign in", "signin"];
 
     // If title or any visible text says "login"/"sign in", this is at least partially a
     // login page. Pages like HN have both login and registration — Login takes priority.
-    let has_login_title = login_keywords.iter().any(|kw| title_lower.contains(kw));
+    let has_login_title = index.title_has(&login_keywords);
     // Check headings and bold text for login keywords (catches HN's <b>Login</b>)
     let heading_or_bold_tags = ["h1", "h2", "h3", "h4", "h5", "h6", "b", "strong"];
-    let has_login_heading_or_bold = dom.els.iter().any(|e| {
-        e.hidden != Some(true)
-            && (e.role.as_deref() == Some("heading") || heading_or_bold_tags.contains(&e.tag.as_str()))
-            && e.text.as_ref().map(|t| {
-                let lower = t.to_lowercase();
-                login_keywords.iter().any(|kw| lower == *kw || lower.contains(kw))
-            }).unwrap_or(false)
+    let has_login_heading_or_bold = index.iter(dom).any(|(e, f)| {
+        f.visible
+            && (f.heading || heading_or_bold_tags.contains(&e.tag.as_str()))
+            && f.text_has(&login_keywords)
     });
 
     let has_confirm_password = all_passwords.len() >= 2;
 
     // When a page has both login and registration sections (2+ password fields + login text),
     // prefer Login. The Login detector will pick up the first form.
-    if (has_login_title || has_login_heading_or_bold) && has_confirm_password {
+    if !autocompleted && (has_login_title || has_login_heading_or_bold) && has_confirm_password {
         return None;
     }
-    let has_register_title = register_keywords.iter().any(|kw| title_lower.contains(kw));
-    let has_register_heading = dom.els.iter().any(|e| {
-        e.role.as_deref() == Some("heading")
-            && e.text.as_ref().map(|t| {
-                let lower = t.to_lowercase();
-                register_keywords.iter().any(|kw| lower.contains(kw))
-            }).unwrap_or(false)
-    });
+    let has_register_title = index.title_has(&register_keywords);
+    let has_register_heading = index.heading_has(&register_keywords);
 
-    if !has_confirm_password && !has_register_title && !has_register_heading {
+    if !autocompleted && !has_confirm_password && !has_register_title && !has_register_heading {
         return None;
     }
 
-    let email_id = find_email_input_id(dom);
+    let email_id = find_email_input_id(dom, index);
 
-    // Find username field (text input with name suggesting username)
+    let text_inputs = || index.iter(dom).filter(|(e, f)| {
+        f.visible && e.tag == "input" && matches!(e.input_type.as_deref(), Some("text") | None)
+    });
+    // Find username field (autocomplete="username", or a text input with name suggesting username)
     let username_id = dom.els.iter().find(|e| {
-        e.hidden != Some(true) && e.tag == "input"
-            && matches!(e.input_type.as_deref(), Some("text") | None)
-            && e.name.as_ref().map(|n| {
-                let lower = n.to_lowercase();
-                lower == "username" || lower == "user" || lower == "login"
-            }).unwrap_or(false)
+        e.hidden != Some(true) && e.tag == "input" && autocompletes(e, "username")
+    }).or_else(|| {
+        text_inputs().find(|(_, f)| f.name_is(&["username", "user", "login"])).map(|(e, _)| e)
     }).map(|e| e.id);
 
     // Find name field
-    let name_id = dom.els.iter().find(|e| {
-        e.hidden != Some(true) && e.tag == "input"
-            && matches!(e.input_type.as_deref(), Some("text") | None)
-            && e.name.as_ref().map(|n| {
-                let lower = n.to_lowercase();
-                lower == "name" || lower == "fullname" || lower == "full_name" || lower == "display_name"
-            }).unwrap_or(false)
-    }).map(|e| e.id);
-
-    // Confirm password is the second password field
-    let confirm_password_id = if has_confirm_password {
+    let name_id = text_inputs()
+        .find(|(_, f)| f.name_is(&["name", "fullname", "full_name", "display_name"]))
+        .map(|(e, _)| e.id);
+
+    // Confirm password is the second (new) password field
+    let confirm_password_id = if autocompleted {
+        new_passwords.get(1).map(|e| e.id)
+    } else if has_confirm_password {
         Some(all_passwords[1].id)
     } else {
         None
@@ -2080,7 +4751,7 @@ fn detect_register_action(dom: &SpatialDom) -> Option<SuggestedAction> {
 }
 
 /// Detect a contact form: email + message textarea.
-fn detect_contact_action(dom: &SpatialDom) -> Option<SuggestedAction> {
+fn detect_contact_action(dom: &SpatialDom, index: &PageIndex) -> Option<SuggestedAction> {
     // Must have a visible textarea (message body)
     let textarea = dom.els.iter().find(|e| {
         e.hidden != Some(true) && e.tag == "textarea"
@@ -2088,36 +4759,24 @@ fn detect_contact_action(dom: &SpatialDom) -> Option<SuggestedAction> {
     let message_id = textarea.id;
 
     // Must have contact-related context (title, heading, or form action)
-    let title_lower = dom.title.to_lowercase();
     let contact_keywords = ["contact us", "contact form", "get in touch", "reach out", "send us a message", "inquiry"];
-    let has_contact_context = contact_keywords.iter().any(|kw| title_lower.contains(kw))
-        || dom.els.iter().any(|e| {
-            e.role.as_deref() == Some("heading")
-                && e.text.as_ref().map(|t| {
-                    let lower = t.to_lowercase();
-                    contact_keywords.iter().any(|kw| lower.contains(kw))
-                }).unwrap_or(false)
-        });
+    let has_contact_context = index.title_has(&contact_keywords) || index.heading_has(&contact_keywords);
 
     if !has_contact_context {
         return None;
     }
 
-    let email_id = find_email_input_id(dom);
+    let email_id = find_email_input_id(dom, index);
 
     // Find name field
-    let name_id = dom.els.iter().find(|e| {
-        e.hidden != Some(true) && e.tag == "input"
+    let name_id = index.iter(dom).find(|(e, f)| {
+        f.visible && e.tag == "input"
             && matches!(e.input_type.as_deref(), Some("text") | None)
-            && (e.name.as_ref().map(|n| {
-                let lower = n.to_lowercase();
-                lower == "name" || lower == "fullname" || lower == "full_name"
-            }).unwrap_or(false)
-            || e.label.as_ref().map(|l| {
-                let lower = l.to_lowercase();
-                lower.contains("name") && !lower.contains("email") && !lower.contains("user")
-            }).unwrap_or(false))
-    }).map(|e| e.id);
+            && (f.name_is(&["name", "fullname", "full_name"])
+            || f.label.as_deref().is_some_and(|l| {
+                l.contains("name") && !l.contains("email") && !l.contains("user")
+            }))
+    }).map(|(e, _)| e.id);
 
     let submit_id = find_nearest_submit_button(dom, message_id)?;
 
@@ -2129,13 +4788,35 @@ fn detect_contact_action(dom: &SpatialDom) -> Option<SuggestedAction> {
     })
 }
 
-/// Detect a generic form and extract labeled fields for the FillForm action.
-/// Only fires for pages already classified as Form (2+ data-entry inputs) that
+/// A visible input, textarea or select that takes user data.
+fn is_data_entry_field(e: &SpatialElement) -> bool {
+    e.hidden != Some(true) && match e.tag.as_str() {
+        "textarea" | "select" => true,
+        "input" => !matches!(
+            e.input_type.as_deref(),
+            Some("hidden") | Some("submit") | Some("button") | Some("image")
+        ),
+        _ => false,
+    }
+}
+
+/// A visible `<button>` or submit/image `<input>`.
+fn is_submit_button(e: &SpatialElement) -> bool {
+    e.hidden != Some(true) && match e.tag.as_str() {
+        "button" => true,
+        "input" => matches!(e.input_type.as_deref(), Some("submit") | Some("image")),
+        _ => false,
+    }
+}
+
+/// Detect generic forms and extract labeled fields for FillForm actions, one
+/// per `<form>` with 2+ data-entry fields (or one page-wide when fields sit
+/// outside any form). Only fires for pages already classified as Form that
 /// don't match more specific form actions (Login, Register, Contact).
-fn detect_fill_form_action(dom: &SpatialDom, existing_actions: &[SuggestedAction]) -> Option<SuggestedAction> {
+fn detect_fill_form_actions(dom: &SpatialDom, existing_actions: &[SuggestedAction]) -> Vec<SuggestedAction> {
     // Only for Form/Error page types — don't generate FillForm on Login/Search pages
     if dom.page_type != PageType::Form && dom.page_type != PageType::Error {
-        return None;
+        return Vec::new();
     }
 
     // Already have a more specific action?
@@ -2147,40 +4828,33 @@ fn detect_fill_form_action(dom: &SpatialDom, existing_actions: &[SuggestedAction
         )
     });
     if has_specific {
-        return None;
+        return Vec::new();
     }
 
-    // Collect visible data-entry fields
-    let fields: Vec<FormField> = dom.els.iter().filter(|e| {
-        e.hidden != Some(true) && match e.tag.as_str() {
-            "textarea" | "select" => true,
-            "input" => !matches!(
-                e.input_type.as_deref(),
-                Some("hidden") | Some("submit") | Some("button") | Some("image")
-            ),
-            _ => false,
+    // Collect visible data-entry fields, grouped by form in document order
+    let mut groups: Vec<(Option<u16>, Vec<FormField>)> = Vec::new();
+    for el in dom.els.iter().filter(|e| is_data_entry_field(e)) {
+        match groups.iter_mut().find(|(form, _)| *form == el.form) {
+            Some((_, fields)) => fields.push(FormField::from(el)),
+            None => groups.push((el.form, vec![FormField::from(el)])),
         }
-    }).map(|e| FormField {
-        id: e.id,
-        label: e.label.clone().or_else(|| e.ph.clone()),
-        name: e.name.clone(),
-        input_type: e.input_type.clone(),
-    }).collect();
-
-    if fields.len() < 2 {
-        return None;
     }
 
-    // Find a submit button near the last field
-    let last_field_id = fields.last().unwrap().id;
-    let submit_id = find_nearest_submit_button(dom, last_field_id)?;
-
-    Some(SuggestedAction::FillForm { fields, submit_id })
+    groups
+        .into_iter()
+        .filter(|(_, fields)| fields.len() >= 2)
+        .filter_map(|(_, fields)| {
+            // Find a submit button near the last field
+            let last_field_id = fields.last()?.id;
+            let submit_id = find_nearest_submit_button(dom, last_field_id)?;
+            Some(SuggestedAction::FillForm { fields, submit_id })
+        })
+        .collect()
 }
 
 /// Detect download links/buttons on the page.
 /// Looks for links with download-related text or file extension hrefs.
-fn detect_download_action(dom: &SpatialDom) -> Option<SuggestedAction> {
+fn detect_download_action(dom: &SpatialDom, index: &PageIndex) -> Option<SuggestedAction> {
     let file_extensions = [
         ".zip", ".tar.gz", ".dmg", ".exe", ".msi", ".deb", ".rpm",
         ".pkg", ".appimage", ".pdf", ".csv", ".xlsx",
@@ -2188,20 +4862,18 @@ fn detect_download_action(dom: &SpatialDom) -> Option<SuggestedAction> {
 
     let mut items = Vec::new();
 
-    for el in &dom.els {
-        if el.hidden == Some(true) { continue; }
+    for (el, f) in index.iter(dom) {
+        if !f.visible { continue; }
 
-        let is_link_or_button = el.tag == "a" || el.tag == "button"
-            || el.role.as_deref() == Some("link")
-            || el.role.as_deref() == Some("button");
+        let is_link_or_button = el.tag == "a" || f.button || el.role.as_deref() == Some("link");
         if !is_link_or_button { continue; }
 
         // Text match: "download" must be the primary action — starts with "download"
         // or is a short button text like "Download" / "Download now"
-        let text_match = el.text.as_ref().map(|t| {
-            let lower = t.to_lowercase().trim().to_string();
+        let text_match = f.text.as_deref().is_some_and(|t| {
+            let lower = t.trim();
             lower.starts_with("download") && (lower.len() < 40 || lower.contains('.'))
-        }).unwrap_or(false);
+        });
 
         let href_match = el.href.as_ref().map(|h| {
             let lower = h.to_lowercase();
@@ -2227,7 +4899,7 @@ fn detect_download_action(dom: &SpatialDom) -> Option<SuggestedAction> {
 /// Detect a CaptchaChallenge action when CAPTCHA info is present or when the
 /// page type is Captcha with image-grid patterns (custom CAPTCHAs without a
 /// known service like reCAPTCHA/hCaptcha).
-fn detect_captcha_challenge_action(dom: &SpatialDom) -> Option<SuggestedAction> {
+fn detect_captcha_challenge_action(dom: &SpatialDom, index: &PageIndex) -> Option<SuggestedAction> {
     // Determine CAPTCHA type and sitekey: either from tree-detected service or
     // by inferring from SpatialDom elements on a Captcha page.
     let (ct, sk) = if let Some(captcha) = dom.captcha.as_ref() {
@@ -2236,14 +4908,11 @@ fn detect_captcha_challenge_action(dom: &SpatialDom) -> Option<SuggestedAction>
         // No known CAPTCHA service detected in the HTML tree.
         // Check for image grid pattern: multiple image buttons (≥4) suggest a
         // "select all images containing X" challenge.
-        let image_buttons = dom.els.iter().filter(|e| {
-            e.hidden != Some(true)
+        let image_buttons = index.iter(dom).filter(|(e, f)| {
+            f.visible
                 && e.tag == "button"
                 && e.input_type.as_deref() != Some("submit")
-                && e.text.as_ref().map(|t| {
-                    let lower = t.to_lowercase();
-                    lower.contains("image") || lower.contains("img")
-                }).unwrap_or(false)
+                && f.text_has(&["image", "img"])
         }).count();
 
         if image_buttons >= 4 {
@@ -2257,20 +4926,14 @@ fn detect_captcha_challenge_action(dom: &SpatialDom) -> Option<SuggestedAction>
     };
 
     // Find the submit button: prefer one with action text, fall back to any visible button
-    let visible_buttons: Vec<&SpatialElement> = dom.els.iter().filter(|e| {
-        e.hidden != Some(true)
+    let visible_buttons: Vec<(&SpatialElement, &Features)> = index.iter(dom).filter(|(e, f)| {
+        f.visible
             && (e.tag == "button" || (e.tag == "input" && e.input_type.as_deref() == Some("submit")))
     }).collect();
     let submit_id = visible_buttons.iter()
-        .find(|e| {
-            e.text.as_ref().map(|t| {
-                let lower = t.to_lowercase();
-                lower.contains("verify") || lower.contains("submit") || lower.contains("continue")
-                    || lower.contains("proceed")
-            }).unwrap_or(false)
-        })
+        .find(|(_, f)| f.text_has(&["verify", "submit", "continue", "proceed"]))
         .or_else(|| visible_buttons.first())
-        .map(|e| e.id);
+        .map(|(e, _)| e.id);
 
     Some(SuggestedAction::CaptchaChallenge {
         captcha_type: ct,
@@ -2331,7 +4994,10 @@ fn scan_captcha_recursive(
         }
         if let Some(id) = node.attributes.get("id") {
             let id_lower = id.to_lowercase();
-            if (id_lower.contains("challenge-running") || id_lower.contains("cf-challenge"))
+            // challenge-error-text is the <noscript> notice shown without JS
+            if (id_lower.contains("challenge-running")
+                || id_lower.contains("cf-challenge")
+                || id_lower.contains("challenge-error"))
                 && captcha_type.is_none()
             {
                 *captcha_type = Some(CaptchaType::CloudflareChallenge);
@@ -2356,13 +5022,20 @@ fn find_nearest_button_impl(dom: &SpatialDom, input_id: u32, visible_only: bool)
     let input_y = input.b[1];
     let input_x = input.b[0];
 
+    let is_candidate = |el: &SpatialElement| {
+        (!visible_only || el.hidden != Some(true))
+            && (el.tag == "button"
+                || (el.tag == "input" && el.input_type.as_deref() == Some("submit")))
+    };
+    // A button in the input's own form beats a closer one in another form
+    let same_form_only = input.form.is_some()
+        && dom.els.iter().any(|el| is_candidate(el) && el.form == input.form);
+
     let mut best: Option<(u32, i32)> = None;
 
     for el in &dom.els {
-        if visible_only && el.hidden == Some(true) { continue; }
-        let is_button = el.tag == "button"
-            || (el.tag == "input" && el.input_type.as_deref() == Some("submit"));
-        if !is_button { continue; }
+        if !is_candidate(el) { continue; }
+        if same_form_only && el.form != input.form { continue; }
 
         let dy = el.b[1] - input_y;
         let dx = (el.b[0] - input_x).abs();
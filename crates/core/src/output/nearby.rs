@@ -0,0 +1,142 @@
+//! What surrounds an element on the page: the elements near it, the row it
+//! sits in, the label to its left or above it, and the landmark or card
+//! holding it. Rows are grouped as table extraction groups them.
+
+use serde::Serialize;
+
+use super::{bounds_contain, group_by_row, SpatialDom, SpatialElement, LANDMARK_ROLES};
+
+/// How far around an element `GET /api/element/{id}/context` and the MCP
+/// `element_context` tool look for neighbors, in CSS pixels.
+pub const DEFAULT_NEIGHBOR_RADIUS: i32 = 50;
+
+/// How many times an element's area a box must be to count as its container
+/// when it isn't a landmark.
+const CONTAINER_MIN_AREA_RATIO: i64 = 4;
+
+/// An element with what surrounds it, as [`SpatialDom::context`] returns it.
+#[derive(Debug, Clone, Serialize)]
+pub struct ElementContext<'a> {
+    pub element: &'a SpatialElement,
+    /// See [`SpatialDom::container_of`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container: Option<&'a SpatialElement>,
+    /// See [`SpatialDom::row_of`].
+    pub row: Vec<&'a SpatialElement>,
+    /// See [`SpatialDom::left_of`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub left: Option<&'a SpatialElement>,
+    /// See [`SpatialDom::above`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub above: Option<&'a SpatialElement>,
+    /// See [`SpatialDom::neighbors`].
+    pub neighbors: Vec<&'a SpatialElement>,
+}
+
+impl SpatialDom {
+    /// Visible elements within `radius_px` of element `id`'s bounds, nearest
+    /// first and then in document order. Elements holding `id`, such as its
+    /// card or landmark, are left out; see [`container_of`](Self::container_of).
+    pub fn neighbors(&self, id: u32, radius_px: i32) -> Vec<&SpatialElement> {
+        let Some(target) = self.get(id) else {
+            return Vec::new();
+        };
+        let mut near: Vec<(f32, &SpatialElement)> = self
+            .els
+            .iter()
+            .filter(|e| e.id != id && e.hidden != Some(true) && !bounds_contain(&e.b, &target.b))
+            .map(|e| (gap(&e.b, &target.b), e))
+            .filter(|(distance, _)| *distance <= radius_px as f32)
+            .collect();
+        near.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        near.into_iter().map(|(_, e)| e).collect()
+    }
+
+    /// The smallest landmark, or box at least four times element `id`'s
+    /// size, whose bounds hold it: the form around a field, the card around
+    /// a price. `None` when nothing does.
+    pub fn container_of(&self, id: u32) -> Option<&SpatialElement> {
+        let target = self.get(id)?;
+        let area = |b: &[i32; 4]| i64::from(b[2]) * i64::from(b[3]);
+        self.els
+            .iter()
+            .filter(|e| e.id != id && e.hidden != Some(true) && bounds_contain(&e.b, &target.b))
+            .filter(|e| {
+                let landmark = e.role.as_deref().is_some_and(|r| LANDMARK_ROLES.contains(&r));
+                landmark || area(&e.b) >= area(&target.b) * CONTAINER_MIN_AREA_RATIO
+            })
+            .min_by_key(|e| area(&e.b))
+    }
+
+    /// The elements on the same row as element `id` within its container,
+    /// left to right, `id` included: a card's title, price and button. Empty
+    /// when there is no such element.
+    pub fn row_of(&self, id: u32) -> Vec<&SpatialElement> {
+        let rows = self.rows_around(id);
+        let mut row = rows.into_iter().find(|row| row.iter().any(|e| e.id == id)).unwrap_or_default();
+        row.sort_by_key(|e| e.b[0]);
+        row
+    }
+
+    /// The nearest element left of element `id` on its row: the label in
+    /// front of a field.
+    pub fn left_of(&self, id: u32) -> Option<&SpatialElement> {
+        let target = self.get(id)?;
+        self.row_of(id).into_iter().rev().find(|e| e.b[0] + e.b[2] <= target.b[0] + 1)
+    }
+
+    /// The nearest element above element `id` within its container that
+    /// overlaps it horizontally: the label stacked over a field, the heading
+    /// over a paragraph.
+    pub fn above(&self, id: u32) -> Option<&SpatialElement> {
+        let target = self.get(id)?;
+        let rows = self.rows_around(id);
+        let index = rows.iter().position(|row| row.iter().any(|e| e.id == id))?;
+        let overlaps = |e: &SpatialElement| e.b[0] < target.b[0] + target.b[2] && target.b[0] < e.b[0] + e.b[2];
+        rows[..index].iter().rev().find_map(|row| {
+            row.iter().copied().filter(|e| overlaps(e)).min_by_key(|e| (e.b[0] - target.b[0]).abs())
+        })
+    }
+
+    /// Element `id` with its container, row, left and above elements, and
+    /// neighbors within `radius_px`.
+    pub fn context(&self, id: u32, radius_px: i32) -> Option<ElementContext<'_>> {
+        Some(ElementContext {
+            element: self.get(id)?,
+            container: self.container_of(id),
+            row: self.row_of(id),
+            left: self.left_of(id),
+            above: self.above(id),
+            neighbors: self.neighbors(id, radius_px),
+        })
+    }
+
+    /// The visible elements in element `id`'s container (the whole page
+    /// without one), grouped into rows top to bottom. Elements holding `id`
+    /// or held by it are left out, so a row is its siblings.
+    fn rows_around(&self, id: u32) -> Vec<Vec<&SpatialElement>> {
+        let Some(target) = self.get(id) else {
+            return Vec::new();
+        };
+        let container = self.container_of(id);
+        let candidates: Vec<&SpatialElement> = self
+            .els
+            .iter()
+            .filter(|e| {
+                e.id == id
+                    || (e.hidden != Some(true)
+                        && !bounds_contain(&e.b, &target.b)
+                        && !bounds_contain(&target.b, &e.b))
+            })
+            .filter(|e| container.is_none_or(|c| e.id != c.id && bounds_contain(&c.b, &e.b)))
+            .collect();
+        group_by_row(&candidates)
+    }
+}
+
+/// The distance between the edges of two boxes; 0 when they overlap.
+fn gap(a: &[i32; 4], b: &[i32; 4]) -> f32 {
+    let dx = (b[0] - (a[0] + a[2])).max(a[0] - (b[0] + b[2])).max(0) as f32;
+    let dy = (b[1] - (a[1] + a[3])).max(a[1] - (b[1] + b[3])).max(0) as f32;
+    dx.hypot(dy)
+}
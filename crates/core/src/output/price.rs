@@ -0,0 +1,218 @@
+//! Reading prices out of element text.
+//!
+//! A price is a number next to a currency symbol (`$`, `€`, `£`, …) or an
+//! ISO 4217 code, on either side. Digit grouping is worked out per number,
+//! so `$1,299.00`, `1.299,00 €` and `CHF 1'299.00` all read as 1299.
+
+/// A price found in a run of text.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PriceMatch {
+    pub amount: f64,
+    /// ISO 4217 code.
+    pub currency: &'static str,
+    /// Preceded by "was", "RRP", "list price" or similar.
+    pub was: bool,
+    /// A unit price such as `£2.50/kg` or `1,20 € per 100 g`.
+    pub per_unit: bool,
+}
+
+/// Currency symbols, longest first so `US$` wins over `$`.
+const SYMBOLS: &[(&str, &str)] = &[
+    ("US$", "USD"), ("CA$", "CAD"), ("AU$", "AUD"), ("NZ$", "NZD"), ("HK$", "HKD"),
+    ("C$", "CAD"), ("A$", "AUD"), ("R$", "BRL"), ("S$", "SGD"),
+    ("$", "USD"), ("€", "EUR"), ("£", "GBP"), ("¥", "JPY"), ("₹", "INR"), ("₩", "KRW"),
+    ("zł", "PLN"),
+];
+
+/// ISO codes recognized when written out in capitals.
+const ISO_CODES: &[&str] = &[
+    "USD", "EUR", "GBP", "JPY", "CHF", "CAD", "AUD", "NZD", "SEK", "NOK", "DKK", "PLN",
+    "CZK", "HUF", "INR", "CNY", "BRL", "MXN", "ZAR", "HKD", "SGD", "KRW",
+];
+
+/// Words that introduce the price an item used to cost.
+const WAS_MARKERS: &[&str] = &[
+    "was", "rrp", "msrp", "list price", "regular price", "reg.", "originally", "compare at",
+    "uvp", "statt",
+];
+
+/// Units a price can be quoted per, after `/` or `per`.
+const UNITS: &[&str] = &[
+    "kg", "g", "mg", "lb", "lbs", "oz", "l", "ml", "cl", "litre", "liter", "m", "m²", "m2",
+    "cm", "ft", "sq ft", "unit", "item", "piece", "pc", "pcs", "ct", "count",
+];
+
+#[derive(Clone, Copy)]
+enum Token {
+    Currency(&'static str),
+    Number(f64),
+}
+
+struct Span {
+    start: usize,
+    end: usize,
+    token: Token,
+}
+
+/// Every price in `text`, in order.
+pub(crate) fn find_prices(text: &str) -> Vec<PriceMatch> {
+    let spans = tokenize(text);
+    let mut used = vec![false; spans.len()];
+    let mut out = Vec::new();
+    for (i, span) in spans.iter().enumerate() {
+        let Token::Number(amount) = span.token else { continue };
+        let pairs_with = |j: usize| {
+            let other = &spans[j];
+            let (left, right) = if j < i { (other, span) } else { (span, other) };
+            !used[j] && matches!(other.token, Token::Currency(_)) && adjacent(text, left, right)
+        };
+        // A symbol after the number belongs to it only if no number follows
+        // the symbol: in "12 $5" the dollar sign is 5's.
+        let leads_next = |j: usize| {
+            spans.get(j + 1).is_some_and(|next| {
+                matches!(next.token, Token::Number(_)) && adjacent(text, &spans[j], next)
+            })
+        };
+        let before = i.checked_sub(1).filter(|&j| pairs_with(j));
+        let after = Some(i + 1).filter(|&j| j < spans.len() && pairs_with(j) && !leads_next(j));
+        let Some(j) = before.or(after) else { continue };
+        let Token::Currency(currency) = spans[j].token else { continue };
+        used[j] = true;
+
+        let start = span.start.min(spans[j].start);
+        let end = span.end.max(spans[j].end);
+        out.push(PriceMatch {
+            amount,
+            currency,
+            was: follows_was_marker(&text[..start]),
+            per_unit: is_unit_price(&text[..start], &text[end..]),
+        });
+    }
+    out
+}
+
+/// Whether at most one whitespace character separates two spans.
+fn adjacent(text: &str, left: &Span, right: &Span) -> bool {
+    let gap = &text[left.end..right.start];
+    gap.chars().count() <= 1 && gap.chars().all(char::is_whitespace)
+}
+
+fn tokenize(text: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while let Some(c) = text[i..].chars().next() {
+        if c.is_ascii_digit() {
+            let end = number_end(text, i);
+            if let Some(amount) = parse_amount(&text[i..end]) {
+                spans.push(Span { start: i, end, token: Token::Number(amount) });
+            }
+            i = end;
+        } else if let Some((len, code)) = currency_at(text, i) {
+            spans.push(Span { start: i, end: i + len, token: Token::Currency(code) });
+            i += len;
+        } else {
+            i += c.len_utf8();
+        }
+    }
+    spans
+}
+
+/// The currency symbol or code starting at byte `i`, with its length.
+/// Letters in a symbol or code must not run into a surrounding word.
+fn currency_at(text: &str, i: usize) -> Option<(usize, &'static str)> {
+    let rest = &text[i..];
+    let after_letter = text[..i].chars().next_back().is_some_and(char::is_alphabetic);
+    let symbol = SYMBOLS.iter().find(|(symbol, _)| {
+        rest.starts_with(symbol)
+            && !(after_letter && symbol.starts_with(char::is_alphabetic))
+            && !(symbol.ends_with(char::is_alphabetic)
+                && rest[symbol.len()..].starts_with(char::is_alphabetic))
+    });
+    if let Some((symbol, code)) = symbol {
+        return Some((symbol.len(), *code));
+    }
+    if after_letter {
+        return None;
+    }
+    ISO_CODES.iter()
+        .find(|code| rest.starts_with(**code) && !rest[3..].starts_with(char::is_alphabetic))
+        .map(|code| (3, *code))
+}
+
+/// End of the number starting at byte `start`. `.` and `,` continue a
+/// number when a digit follows; spaces and `'` only as thousands groups.
+fn number_end(text: &str, start: usize) -> usize {
+    let mut end = start;
+    for (offset, c) in text[start..].char_indices() {
+        let pos = start + offset;
+        if c.is_ascii_digit() {
+            end = pos + 1;
+            continue;
+        }
+        let digits_after = text[pos + c.len_utf8()..].chars().take_while(char::is_ascii_digit).count();
+        let continues = match c {
+            '.' | ',' => digits_after > 0,
+            ' ' | '\u{a0}' | '\u{202f}' | '\'' => digits_after == 3,
+            _ => false,
+        };
+        if !continues {
+            break;
+        }
+    }
+    end
+}
+
+/// Parse a number written with either `.` or `,` as the decimal separator.
+///
+/// With both present, the last one is the decimal point. With just one
+/// kind, a single separator followed by exactly three digits groups
+/// thousands (`1,299`, `1.299`) unless the integer part is zero; anything
+/// else is a decimal point. A separator that repeats always groups.
+pub(crate) fn parse_amount(raw: &str) -> Option<f64> {
+    let digits: String = raw.chars()
+        .filter(|c| !matches!(c, ' ' | '\u{a0}' | '\u{202f}' | '\''))
+        .collect();
+    let decimal = match (digits.rfind('.'), digits.rfind(',')) {
+        (Some(dot), Some(comma)) => Some(dot.max(comma)),
+        (Some(p), None) | (None, Some(p)) => {
+            let sep = &digits[p..p + 1];
+            let repeated = digits.matches(sep).count() > 1;
+            let grouped = digits.len() - p - 1 == 3 && !digits[..p].trim_start_matches('0').is_empty();
+            (!repeated && !grouped).then_some(p)
+        }
+        (None, None) => None,
+    };
+    let normalized: String = digits.char_indices()
+        .filter_map(|(i, c)| match c {
+            '0'..='9' => Some(c),
+            _ if Some(i) == decimal => Some('.'),
+            _ => None,
+        })
+        .collect();
+    normalized.parse().ok()
+}
+
+fn follows_was_marker(before: &str) -> bool {
+    let before = before.trim_end().trim_end_matches(':').trim_end().to_lowercase();
+    WAS_MARKERS.iter().any(|marker| {
+        before.strip_suffix(marker).is_some_and(|head| !head.ends_with(char::is_alphabetic))
+    })
+}
+
+fn is_unit_price(before: &str, after: &str) -> bool {
+    let before = before.trim_end().trim_end_matches(':').trim_end().to_lowercase();
+    if before.ends_with("unit price") || before.ends_with("price per") {
+        return true;
+    }
+    let after = after.trim_start().to_lowercase();
+    let Some(rest) = after.strip_prefix('/').or_else(|| after.strip_prefix("per ")) else {
+        return false;
+    };
+    let rest = rest.trim_start()
+        .trim_start_matches(|c: char| c.is_ascii_digit() || c == '.' || c == ',')
+        .trim_start();
+    UNITS.iter().any(|unit| {
+        rest.strip_prefix(unit).is_some_and(|tail| !tail.starts_with(char::is_alphanumeric))
+    })
+}
+
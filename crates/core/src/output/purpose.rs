@@ -0,0 +1,110 @@
+//! Working out what an input is for: the email, the password, the card
+//! number.
+//!
+//! The `autocomplete` attribute is the most precise signal and is checked
+//! first, then the input type, then words in the field's name, label and
+//! placeholder. Fields the words don't settle, like "Promo code", get none.
+
+/// Every purpose an input can be given.
+pub(super) const PURPOSES: &[&str] = &[
+    EMAIL, PASSWORD, USERNAME, SEARCH, PHONE, VERIFICATION_CODE, ADDRESS, CREDIT_CARD_NUMBER,
+];
+
+pub(super) const EMAIL: &str = "email";
+pub(super) const PASSWORD: &str = "password";
+pub(super) const USERNAME: &str = "username";
+pub(super) const SEARCH: &str = "search";
+pub(super) const PHONE: &str = "phone";
+pub(super) const VERIFICATION_CODE: &str = "verification_code";
+pub(super) const ADDRESS: &str = "address";
+pub(super) const CREDIT_CARD_NUMBER: &str = "credit_card_number";
+
+/// Input types that never take typed text.
+const NON_TEXT_TYPES: &[&str] = &[
+    "hidden", "checkbox", "radio", "submit", "button", "reset", "image", "file", "range", "color",
+];
+
+/// Words that make a "code" field something other than a verification code.
+const OTHER_CODES: &[&str] = &[
+    "post", "zip", "promo", "coupon", "discount", "gift", "voucher", "security", "cvc", "cvv", "country",
+    "area", "referral", "source",
+];
+
+/// What an input is for, from its attributes.
+pub(super) struct Field<'a> {
+    pub input_type: Option<&'a str>,
+    pub autocomplete: Option<&'a str>,
+    pub role: Option<&'a str>,
+    pub name: Option<&'a str>,
+    pub label: Option<&'a str>,
+    pub ph: Option<&'a str>,
+}
+
+pub(super) fn classify(field: &Field) -> Option<&'static str> {
+    let input_type = field.input_type.unwrap_or("text").to_ascii_lowercase();
+    if NON_TEXT_TYPES.contains(&input_type.as_str()) {
+        return None;
+    }
+    if let Some(purpose) = field.autocomplete.and_then(from_autocomplete) {
+        return Some(purpose);
+    }
+    match input_type.as_str() {
+        "password" => return Some(PASSWORD),
+        "email" => return Some(EMAIL),
+        "search" => return Some(SEARCH),
+        _ => {}
+    }
+    if field.role == Some("searchbox") {
+        return Some(SEARCH);
+    }
+
+    let words = [field.name, field.label, field.ph]
+        .iter()
+        .flatten()
+        .map(|s| s.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let has = |needles: &[&str]| needles.iter().any(|n| words.contains(n));
+
+    if has(&["otp", "one-time", "one_time", "verif", "2fa", "mfa", "totp"])
+        || (words.contains("code") && !has(OTHER_CODES))
+    {
+        return Some(VERIFICATION_CODE);
+    }
+    if has(&["cardnumber", "card number", "card_number", "card-number", "ccnum", "cc-num", "cc_num", "credit card"]) {
+        return Some(CREDIT_CARD_NUMBER);
+    }
+    if has(&["email", "e-mail"]) {
+        return Some(EMAIL);
+    }
+    if input_type == "tel" || has(&["phone", "mobile"]) {
+        return Some(PHONE);
+    }
+    if has(&["search"]) || field.name == Some("q") {
+        return Some(SEARCH);
+    }
+    if has(&["address", "street"]) {
+        return Some(ADDRESS);
+    }
+    if has(&["user", "login"]) {
+        return Some(USERNAME);
+    }
+    None
+}
+
+/// The purpose an `autocomplete` value names. Its last token is the field
+/// name; section and `shipping`/`billing` tokens come before it.
+fn from_autocomplete(value: &str) -> Option<&'static str> {
+    let token = value.split_whitespace().last()?.to_ascii_lowercase();
+    let purpose = match token.as_str() {
+        "email" => EMAIL,
+        "current-password" | "new-password" => PASSWORD,
+        "username" => USERNAME,
+        "one-time-code" => VERIFICATION_CODE,
+        "tel" | "tel-national" | "tel-local" => PHONE,
+        "cc-number" => CREDIT_CARD_NUMBER,
+        "street-address" | "address-line1" | "address-line2" | "address-line3" => ADDRESS,
+        _ => return None,
+    };
+    Some(purpose)
+}
@@ -0,0 +1,58 @@
+//! Versioning of serialized Spatial DOMs.
+//!
+//! Every Spatial DOM is written with a `schema` number. New optional fields
+//! don't need a new version, since readers skip fields they don't know and
+//! default the ones a snapshot lacks. A version is only added when old JSON
+//! can't be read as is: a field became required, changed shape or was
+//! renamed. Its migration rewrites the JSON of the version before it.
+
+use serde_json::{Map, Value};
+
+use super::{SpatialDom, TextSource};
+
+/// Version of the JSON [`SpatialDom`]s are serialized as.
+pub const SCHEMA_VERSION: u16 = 1;
+
+/// A migration from the version at its index to the next one.
+type Migration = fn(&mut Map<String, Value>);
+
+/// `MIGRATIONS[v]` upgrades version `v` JSON to version `v + 1`.
+const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [from_unversioned];
+
+/// Read Spatial DOM JSON written by any version of browsy, upgrading older
+/// snapshots to the current schema first. Snapshots from newer versions are
+/// read as far as this version understands them.
+pub fn migrate(json: &str) -> Result<SpatialDom, serde_json::Error> {
+    let mut value: Value = serde_json::from_str(json)?;
+    if let Value::Object(ref mut fields) = value {
+        let version = fields.get("schema").and_then(Value::as_u64).unwrap_or(0);
+        for migration in MIGRATIONS.iter().skip(version as usize) {
+            migration(fields);
+        }
+    }
+    let mut dom: SpatialDom = serde_json::from_value(value)?;
+    dom.schema = SCHEMA_VERSION;
+    // Visible text is written without its source
+    for el in &mut dom.els {
+        if el.text.is_some() && el.text_source.is_none() {
+            el.text_source = Some(TextSource::Visible);
+        }
+    }
+    dom.rebuild_index();
+    Ok(dom)
+}
+
+/// Snapshots from before versioning could leave out the viewport and scroll
+/// position, and the element list of an empty page.
+fn from_unversioned(fields: &mut Map<String, Value>) {
+    let defaults = [
+        ("url", Value::from("")),
+        ("title", Value::from("")),
+        ("vp", Value::from(super::default_vp().to_vec())),
+        ("scroll", Value::from(vec![0.0, 0.0])),
+        ("els", Value::Array(Vec::new())),
+    ];
+    for (key, default) in defaults {
+        fields.entry(key).or_insert(default);
+    }
+}
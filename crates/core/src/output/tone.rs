@@ -0,0 +1,52 @@
+//! What an element's colors say: red for errors, green for success, amber
+//! for warnings, grey for hints. Hue and lightness are bucketed coarsely,
+//! so brand colors near those hues get a tone too; it's a hint, not a verdict.
+
+use crate::css::color::Color;
+use serde::{Deserialize, Serialize};
+
+/// What an element's color suggests about its text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Tone {
+    /// Red, as error messages are.
+    Danger,
+    /// Green.
+    Success,
+    /// Orange to amber.
+    Warning,
+    /// Mid grey or faded text, as hints and captions are.
+    Muted,
+    /// Any other color.
+    Normal,
+}
+
+/// The tone of an element with text `color` on `background`. A strongly
+/// colored background decides it, then the text color; `None` when the
+/// page sets neither.
+pub(super) fn tone(color: Option<Color>, background: Option<Color>) -> Option<Tone> {
+    if let Some(tone) = background.and_then(|bg| hue_tone(bg, 0.95)) {
+        return Some(tone);
+    }
+    let color = color.filter(|c| c.a >= 0.1)?;
+    if let Some(tone) = hue_tone(color, 0.75) {
+        return Some(tone);
+    }
+    let (_, saturation, lightness) = color.to_hsl();
+    let grey = saturation < 0.15 && (0.35..=0.8).contains(&lightness);
+    Some(if grey || color.a < 0.7 { Tone::Muted } else { Tone::Normal })
+}
+
+/// Danger, Success or Warning for a saturated, opaque enough `color` no
+/// lighter than `max_lightness`. Backgrounds are allowed paler tints.
+fn hue_tone(color: Color, max_lightness: f32) -> Option<Tone> {
+    let (hue, saturation, lightness) = color.to_hsl();
+    if color.a < 0.5 || saturation < 0.4 || !(0.2..=max_lightness).contains(&lightness) {
+        return None;
+    }
+    match hue {
+        h if !(15.0..340.0).contains(&h) => Some(Tone::Danger),
+        h if h <= 55.0 => Some(Tone::Warning),
+        h if (85.0..=165.0).contains(&h) => Some(Tone::Success),
+        _ => None,
+    }
+}
@@ -0,0 +1,179 @@
+//! Scoped views of a page that borrow its elements instead of copying them,
+//! for formatting large pages cheaply.
+
+use super::{
+    compact_string, element_lines, map, FoldOptions, HiddenReason, SpatialDom, SpatialElement,
+    DEFAULT_HIDDEN_DROP,
+};
+
+/// Which part of a page to show; the servers' `scope` parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Scope {
+    /// Every element.
+    #[default]
+    All,
+    /// Without the elements [`DEFAULT_HIDDEN_DROP`] names.
+    Visible,
+    /// Without hidden or fully clipped elements.
+    OnScreen,
+    /// See [`SpatialDom::filter_above_fold`].
+    AboveFold,
+    /// `Visible`, then `AboveFold`.
+    VisibleAboveFold,
+    /// See [`SpatialDom::filter_fold_plus`].
+    FoldPlus,
+    /// See [`SpatialDom::filter_main_content`].
+    Main,
+}
+
+impl std::str::FromStr for Scope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "all" => Ok(Scope::All),
+            "visible" => Ok(Scope::Visible),
+            "on_screen" => Ok(Scope::OnScreen),
+            "above_fold" => Ok(Scope::AboveFold),
+            "visible_above_fold" => Ok(Scope::VisibleAboveFold),
+            "fold_plus" => Ok(Scope::FoldPlus),
+            "main" => Ok(Scope::Main),
+            other => Err(format!(
+                "unknown scope '{other}' (expected all, visible, on_screen, above_fold, visible_above_fold, fold_plus or main)"
+            )),
+        }
+    }
+}
+
+impl Scope {
+    /// The servers' `scope` parameter as a scope. Unknown values, and
+    /// `new_content` once `Session::scoped` has applied it, show everything.
+    pub fn from_param(scope: Option<&str>) -> Scope {
+        scope.and_then(|s| s.parse().ok()).unwrap_or_default()
+    }
+}
+
+/// Some of a [`SpatialDom`]'s elements, held as indices into its `els`.
+/// Formats exactly like the copy [`to_dom`](Self::to_dom) would make,
+/// without copying any element.
+#[derive(Debug, Clone)]
+pub struct DomView<'a> {
+    dom: &'a SpatialDom,
+    /// Indices into `dom.els`, in document order.
+    idx: Vec<usize>,
+    /// The page's `outside_main` plus what `main_content` left out.
+    outside_main: usize,
+}
+
+impl SpatialDom {
+    /// The elements in `scope`, borrowed. The cloning filters such as
+    /// [`filter_above_fold`](Self::filter_above_fold) are `view(..).to_dom()`.
+    pub fn view(&self, scope: Scope) -> DomView<'_> {
+        let all = DomView {
+            dom: self,
+            idx: (0..self.els.len()).collect(),
+            outside_main: self.outside_main,
+        };
+        match scope {
+            Scope::All => all,
+            Scope::Visible => all.strip_hidden(DEFAULT_HIDDEN_DROP),
+            Scope::OnScreen => all.on_screen(),
+            Scope::AboveFold => all.above_fold_with(&FoldOptions::default()),
+            Scope::VisibleAboveFold => all
+                .strip_hidden(DEFAULT_HIDDEN_DROP)
+                .above_fold_with(&FoldOptions::default()),
+            Scope::FoldPlus => all.above_fold_with(&FoldOptions {
+                extend: self.vp[1] / 2.0,
+                ..FoldOptions::default()
+            }),
+            Scope::Main => all.main_content(),
+        }
+    }
+}
+
+impl<'a> DomView<'a> {
+    /// The page the view borrows from.
+    pub fn dom(&self) -> &'a SpatialDom {
+        self.dom
+    }
+
+    pub fn len(&self) -> usize {
+        self.idx.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.idx.is_empty()
+    }
+
+    /// Elements outside the main content left out, by this view and before.
+    pub fn outside_main(&self) -> usize {
+        self.outside_main
+    }
+
+    /// The elements in the view, in document order.
+    pub fn els(&self) -> impl Iterator<Item = &'a SpatialElement> + '_ {
+        let dom = self.dom;
+        self.idx.iter().map(move |&i| &dom.els[i])
+    }
+
+    /// Narrow to the elements not hidden for one of the `drop` reasons.
+    pub fn strip_hidden(self, drop: &[HiddenReason]) -> Self {
+        let idx = self.dom.unhidden_indices(&self.idx, drop);
+        Self { idx, ..self }
+    }
+
+    /// Narrow to the elements neither hidden nor fully clipped.
+    pub fn on_screen(self) -> Self {
+        let idx = self.dom.on_screen_indices(&self.idx);
+        Self { idx, ..self }
+    }
+
+    /// Narrow as [`SpatialDom::filter_above_fold_with`] does, finding labels
+    /// and headings among the elements already in the view.
+    pub fn above_fold_with(self, options: &FoldOptions) -> Self {
+        let idx = self.dom.fold_indices(&self.idx, options);
+        Self { idx, ..self }
+    }
+
+    /// Narrow as [`SpatialDom::filter_main_content`] does, finding the main
+    /// region among the elements already in the view.
+    pub fn main_content(self) -> Self {
+        let idx = self.dom.main_indices(&self.idx);
+        let outside_main = self.outside_main + (self.idx.len() - idx.len());
+        Self { idx, outside_main, ..self }
+    }
+
+    /// A SpatialDom with copies of only the elements in the view.
+    pub fn to_dom(&self) -> SpatialDom {
+        let mut dom = self.dom.select(&self.idx);
+        dom.outside_main = self.outside_main;
+        dom
+    }
+
+    /// [`super::to_compact_string`] of [`to_dom`](Self::to_dom).
+    pub fn to_compact_string(&self) -> String {
+        compact_string(self.dom, self.outside_main, self.refs().into_iter())
+    }
+
+    /// [`super::compact_lines`] of [`to_dom`](Self::to_dom).
+    pub fn compact_lines(&self) -> impl Iterator<Item = String> + 'a {
+        element_lines(self.dom, self.refs().into_iter())
+    }
+
+    /// Each element as one line of JSON, in document order.
+    pub fn ndjson_lines(&self) -> impl Iterator<Item = String> + 'a {
+        self.refs()
+            .into_iter()
+            .map(|el| serde_json::to_string(el).unwrap_or_default())
+    }
+
+    /// [`super::to_ascii_map`] of [`to_dom`](Self::to_dom).
+    pub fn to_ascii_map(&self, cols: usize, rows: usize) -> String {
+        map::draw_map(self.dom, self.els(), cols, rows)
+    }
+
+    /// The elements in the view as references that outlive it.
+    fn refs(&self) -> Vec<&'a SpatialElement> {
+        self.els().collect()
+    }
+}
@@ -0,0 +1,722 @@
+//! Reading PDFs into a Spatial DOM, behind the `pdf` feature.
+//!
+//! Text is taken from each page's content stream with its position and
+//! font size. Runs on one baseline make a line, and lines of one size close
+//! together make a block. Blocks in text noticeably larger than the body
+//! text become headings, the largest `h1`. Text is read through each font's
+//! `/ToUnicode` CMap; where that has no entry, simple fonts fall back to the
+//! glyph names of their `/Encoding /Differences`, then to Latin-1 (with the
+//! WinAnsi punctuation). Text a font gives no way to read is left out, with a
+//! warning on the page, as are images and tables' structure.
+
+use std::collections::HashMap;
+
+use lopdf::content::{Content, Operation};
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+
+use crate::output::{document_dom, DocumentBlock, SpatialDom};
+
+/// Letter-size pages, in points, for pages without a `MediaBox`.
+const DEFAULT_MEDIA_BOX: [f32; 4] = [0.0, 0.0, 612.0, 792.0];
+/// Width of a glyph a font gives no width for, in thousandths of an em.
+const DEFAULT_GLYPH_WIDTH: f32 = 500.0;
+/// A `TJ` adjustment wider than this (thousandths of an em) is a word break.
+const TJ_SPACE: f32 = 200.0;
+/// Text this much larger than the body text is a heading.
+const HEADING_RATIO: f32 = 1.15;
+/// Blocks longer than this are paragraphs whatever their size.
+const MAX_HEADING_CHARS: usize = 200;
+
+/// A PDF that couldn't be read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdfError(pub String);
+
+impl std::fmt::Display for PdfError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Unreadable PDF: {}", self.0)
+    }
+}
+
+impl std::error::Error for PdfError {}
+
+/// Whether a response is a PDF: by its `Content-Type`, or by the `%PDF-`
+/// its bytes start with when it's served as something else.
+pub fn is_pdf(content_type: Option<&str>, bytes: &[u8]) -> bool {
+    let mime = content_type.and_then(|value| value.split(';').next()).unwrap_or_default().trim();
+    let named = ["application/pdf", "application/x-pdf"].iter().any(|pdf| mime.eq_ignore_ascii_case(pdf));
+    named || bytes.starts_with(b"%PDF-")
+}
+
+/// Read the PDF in `bytes` into a Spatial DOM with `page_type` `Document`:
+/// one heading or paragraph per block of text, in reading order, each with
+/// the `page` it's on. Pages are scaled to `viewport_width` and stacked top
+/// to bottom. The title is the document's `/Title`, or its first heading.
+pub fn parse_pdf(bytes: &[u8], url: &str, viewport_width: f32, viewport_height: f32) -> Result<SpatialDom, PdfError> {
+    let doc = Document::load_mem(bytes).map_err(|e| PdfError(e.to_string()))?;
+    let mut lines = Vec::new();
+    let mut skipped = 0;
+    let mut top = 0.0;
+    for (number, page_id) in doc.get_pages() {
+        let page = u16::try_from(number).unwrap_or(u16::MAX);
+        let media_box = media_box(&doc, page_id);
+        let scale = viewport_width / (media_box[2] - media_box[0]).max(1.0);
+        let content = doc.get_page_content(page_id).map_err(|e| PdfError(e.to_string()))?;
+        let operations = Content::decode(&content).map_err(|e| PdfError(e.to_string()))?.operations;
+        let (runs, unreadable) = read_runs(&operations, &page_fonts(&doc, page_id));
+        skipped += unreadable;
+        for line in group_lines(runs) {
+            let b = [
+                ((line.x0 - media_box[0]) * scale).round() as i32,
+                (top + (media_box[3] - line.baseline - line.size * 0.8) * scale).round() as i32,
+                ((line.x1 - line.x0) * scale).round() as i32,
+                (line.size * scale).round() as i32,
+            ];
+            lines.push(Line { page, b, ..line });
+        }
+        top += (media_box[3] - media_box[1]) * scale;
+    }
+    let blocks = group_blocks(lines);
+    let title = info_title(&doc)
+        .or_else(|| blocks.iter().find(|b| b.heading.is_some()).map(|b| b.text.clone()))
+        .unwrap_or_default();
+    let mut dom = document_dom(url, title, blocks, [viewport_width, viewport_height]);
+    if skipped > 0 {
+        dom.warnings.push(format!("Characters in fonts without a text mapping were left out: {skipped}"));
+    }
+    Ok(dom)
+}
+
+/// Text shown by one text-showing operator, in page space.
+#[derive(Debug, Clone, PartialEq)]
+struct Run {
+    text: String,
+    x: f32,
+    baseline: f32,
+    size: f32,
+    end_x: f32,
+}
+
+/// Runs sharing a baseline, left to right. `b` is set once the line is
+/// placed on its page.
+#[derive(Debug, Clone, PartialEq)]
+struct Line {
+    text: String,
+    x0: f32,
+    x1: f32,
+    baseline: f32,
+    size: f32,
+    page: u16,
+    b: [i32; 4],
+}
+
+/// What the text operators need of a font.
+#[derive(Debug, Clone, Default)]
+struct Font {
+    first_char: u32,
+    /// Glyph widths from `first_char` on, in thousandths of an em.
+    widths: Vec<f32>,
+    /// `Type0` fonts use two-byte codes, which only their `/ToUnicode` CMap
+    /// turns into text.
+    composite: bool,
+    /// Composite fonts' glyph widths by code, and the width of the rest.
+    cid_widths: HashMap<u32, f32>,
+    default_width: f32,
+    /// The text each code stands for, from the font's `/ToUnicode` CMap.
+    to_unicode: HashMap<u32, String>,
+    /// Codes `/Differences` gives glyph names, with the text the name stands
+    /// for when it's a standard one.
+    differences: HashMap<u32, Option<String>>,
+}
+
+impl Font {
+    fn code_bytes(&self) -> usize {
+        if self.composite { 2 } else { 1 }
+    }
+
+    fn width(&self, code: u32) -> f32 {
+        let width = if self.composite {
+            self.cid_widths.get(&code).copied().unwrap_or(self.default_width)
+        } else {
+            code.checked_sub(self.first_char).and_then(|i| self.widths.get(i as usize)).copied().unwrap_or(0.0)
+        };
+        if width > 0.0 { width } else { DEFAULT_GLYPH_WIDTH }
+    }
+
+    /// The text `code` stands for, or `None` when the font gives no way to
+    /// tell. Control codes stand for no text.
+    fn text(&self, code: u32) -> Option<String> {
+        if let Some(text) = self.to_unicode.get(&code) {
+            return Some(text.clone());
+        }
+        if self.composite {
+            return None;
+        }
+        match self.differences.get(&code) {
+            Some(text) => text.clone(),
+            None => Some(decode(code as u8).map(String::from).unwrap_or_default()),
+        }
+    }
+}
+
+type Matrix = [f32; 6];
+
+const IDENTITY: Matrix = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// `m` then `n`.
+fn multiply(m: &Matrix, n: &Matrix) -> Matrix {
+    [
+        m[0] * n[0] + m[1] * n[2],
+        m[0] * n[1] + m[1] * n[3],
+        m[2] * n[0] + m[3] * n[2],
+        m[2] * n[1] + m[3] * n[3],
+        m[4] * n[0] + m[5] * n[2] + n[4],
+        m[4] * n[1] + m[5] * n[3] + n[5],
+    ]
+}
+
+fn translate(tx: f32, ty: f32) -> Matrix {
+    [1.0, 0.0, 0.0, 1.0, tx, ty]
+}
+
+/// The graphics and text state the text operators change.
+struct TextState<'a> {
+    fonts: &'a HashMap<Vec<u8>, Font>,
+    font: Option<&'a Font>,
+    ctm: Matrix,
+    saved: Vec<Matrix>,
+    tm: Matrix,
+    line_matrix: Matrix,
+    font_size: f32,
+    leading: f32,
+    char_spacing: f32,
+    word_spacing: f32,
+    h_scale: f32,
+    runs: Vec<Run>,
+    /// Characters shown in a font that gives no way to read them.
+    skipped: usize,
+}
+
+impl TextState<'_> {
+    fn next_line(&mut self, tx: f32, ty: f32) {
+        self.line_matrix = multiply(&translate(tx, ty), &self.line_matrix);
+        self.tm = self.line_matrix;
+    }
+
+    /// Show `parts`: strings, and numbers moving left by thousandths of an em.
+    fn show(&mut self, parts: &[Object]) {
+        let start = multiply(&self.tm, &self.ctm);
+        let code_bytes = self.font.map_or(1, Font::code_bytes);
+        let mut text = String::new();
+        for part in parts {
+            match part {
+                Object::String(bytes, _) => {
+                    for chunk in bytes.chunks(code_bytes) {
+                        let code = chunk.iter().fold(0, |code, &byte| code << 8 | byte as u32);
+                        let width = self.font.map_or(DEFAULT_GLYPH_WIDTH, |f| f.width(code)) / 1000.0;
+                        // Word spacing applies to the single-byte space only
+                        let word = code_bytes == 1 && code == b' ' as u32;
+                        let spacing = self.char_spacing + if word { self.word_spacing } else { 0.0 };
+                        self.advance((width * self.font_size + spacing) * self.h_scale);
+                        let glyph = match self.font {
+                            Some(font) => font.text(code),
+                            None => Some(decode(code as u8).map(String::from).unwrap_or_default()),
+                        };
+                        match glyph {
+                            Some(glyph) => text.push_str(&glyph),
+                            None => self.skipped += 1,
+                        }
+                    }
+                }
+                other => {
+                    let Ok(adjust) = other.as_float() else { continue };
+                    self.advance(-adjust / 1000.0 * self.font_size * self.h_scale);
+                    if adjust < -TJ_SPACE && !text.ends_with(' ') {
+                        text.push(' ');
+                    }
+                }
+            }
+        }
+        let end = multiply(&self.tm, &self.ctm);
+        let scale = (start[2] * start[2] + start[3] * start[3]).sqrt();
+        if !text.trim().is_empty() {
+            self.runs.push(Run { text, x: start[4], baseline: start[5], size: self.font_size * scale, end_x: end[4] });
+        }
+    }
+
+    fn advance(&mut self, tx: f32) {
+        self.tm = multiply(&translate(tx, 0.0), &self.tm);
+    }
+}
+
+/// The text runs a page's content stream shows, in the order it shows them,
+/// and how many characters it shows in fonts that give no way to read them.
+fn read_runs(operations: &[Operation], fonts: &HashMap<Vec<u8>, Font>) -> (Vec<Run>, usize) {
+    let mut state = TextState {
+        fonts,
+        font: None,
+        ctm: IDENTITY,
+        saved: Vec::new(),
+        tm: IDENTITY,
+        line_matrix: IDENTITY,
+        font_size: 12.0,
+        leading: 0.0,
+        char_spacing: 0.0,
+        word_spacing: 0.0,
+        h_scale: 1.0,
+        runs: Vec::new(),
+        skipped: 0,
+    };
+    for op in operations {
+        let num = |i: usize| op.operands.get(i).and_then(|o| o.as_float().ok()).unwrap_or(0.0);
+        let matrix = || -> Matrix { [num(0), num(1), num(2), num(3), num(4), num(5)] };
+        match op.operator.as_str() {
+            "q" => state.saved.push(state.ctm),
+            "Q" => state.ctm = state.saved.pop().unwrap_or(IDENTITY),
+            "cm" => state.ctm = multiply(&matrix(), &state.ctm),
+            "BT" => {
+                state.tm = IDENTITY;
+                state.line_matrix = IDENTITY;
+            }
+            "Tf" => {
+                state.font = op.operands.first().and_then(|o| o.as_name().ok()).and_then(|name| state.fonts.get(name));
+                state.font_size = num(1);
+            }
+            "TL" => state.leading = num(0),
+            "Tc" => state.char_spacing = num(0),
+            "Tw" => state.word_spacing = num(0),
+            "Tz" => state.h_scale = num(0) / 100.0,
+            "Td" => state.next_line(num(0), num(1)),
+            "TD" => {
+                state.leading = -num(1);
+                state.next_line(num(0), num(1));
+            }
+            "Tm" => {
+                state.line_matrix = matrix();
+                state.tm = state.line_matrix;
+            }
+            "T*" => state.next_line(0.0, -state.leading),
+            "Tj" => state.show(&op.operands),
+            "TJ" => {
+                if let Some(Object::Array(parts)) = op.operands.first() {
+                    state.show(parts);
+                }
+            }
+            "'" => {
+                state.next_line(0.0, -state.leading);
+                state.show(&op.operands);
+            }
+            "\"" => {
+                state.word_spacing = num(0);
+                state.char_spacing = num(1);
+                state.next_line(0.0, -state.leading);
+                state.show(&op.operands[op.operands.len().min(2)..]);
+            }
+            _ => {}
+        }
+    }
+    (state.runs, state.skipped)
+}
+
+/// A byte of text in a simple font: Latin-1, with WinAnsi's quotes and
+/// dashes. Control codes are dropped.
+fn decode(code: u8) -> Option<char> {
+    let c = match code {
+        0x91 => '‘',
+        0x92 => '’',
+        0x93 => '“',
+        0x94 => '”',
+        0x95 => '•',
+        0x96 => '–',
+        0x97 => '—',
+        0x85 => '…',
+        0x80 => '€',
+        c if c < 0x20 || (0x7f..0xa0).contains(&c) => return None,
+        c => c as char,
+    };
+    Some(c)
+}
+
+/// Join runs into lines, top of the page first.
+fn group_lines(mut runs: Vec<Run>) -> Vec<Line> {
+    runs.sort_by(|a, b| b.baseline.total_cmp(&a.baseline).then(a.x.total_cmp(&b.x)));
+    let mut lines: Vec<Vec<Run>> = Vec::new();
+    for run in runs {
+        match lines.last_mut() {
+            Some(line) if (line[0].baseline - run.baseline).abs() <= line[0].size.max(run.size) * 0.3 => line.push(run),
+            _ => lines.push(vec![run]),
+        }
+    }
+    lines
+        .into_iter()
+        .map(|mut runs| {
+            runs.sort_by(|a, b| a.x.total_cmp(&b.x));
+            let mut text = String::new();
+            let mut end_x = f32::MIN;
+            for run in &runs {
+                let gap = run.x - end_x;
+                if !text.is_empty() && gap > run.size * 0.15 && !text.ends_with(' ') && !run.text.starts_with(' ') {
+                    text.push(' ');
+                }
+                text.push_str(&run.text);
+                end_x = end_x.max(run.end_x);
+            }
+            let size = runs.iter().map(|r| r.size).fold(0.0, f32::max);
+            Line {
+                text: collapse_spaces(&text),
+                x0: runs[0].x,
+                x1: end_x,
+                baseline: runs[0].baseline,
+                size: (size * 2.0).round() / 2.0,
+                page: 0,
+                b: [0; 4],
+            }
+        })
+        .collect()
+}
+
+/// Join consecutive lines of one size and page, close enough together to
+/// be one paragraph, into blocks, and tell headings by their size.
+fn group_blocks(lines: Vec<Line>) -> Vec<DocumentBlock> {
+    let mut groups: Vec<Vec<Line>> = Vec::new();
+    for line in lines {
+        match groups.last_mut() {
+            Some(group) if continues(group.last().unwrap(), &line) => group.push(line),
+            _ => groups.push(vec![line]),
+        }
+    }
+
+    // The body size is the one most of the text is in
+    let mut chars_by_size: HashMap<u32, usize> = HashMap::new();
+    for line in groups.iter().flatten() {
+        *chars_by_size.entry((line.size * 2.0) as u32).or_default() += line.text.chars().count();
+    }
+    let body = chars_by_size
+        .iter()
+        .max_by_key(|(size, chars)| (**chars, **size))
+        .map_or(0.0, |(size, _)| *size as f32 / 2.0);
+    let mut heading_sizes: Vec<f32> = groups.iter().map(|g| g[0].size).filter(|s| *s >= body * HEADING_RATIO).collect();
+    heading_sizes.sort_by(|a, b| b.total_cmp(a));
+    heading_sizes.dedup();
+
+    groups
+        .into_iter()
+        .map(|group| {
+            let text = group.iter().map(|l| l.text.as_str()).collect::<Vec<_>>().join(" ");
+            let heading = heading_sizes
+                .iter()
+                .position(|s| *s == group[0].size)
+                .filter(|_| text.chars().count() <= MAX_HEADING_CHARS)
+                .map(|rank| (rank + 1).min(6) as u8);
+            let left = group.iter().map(|l| l.b[0]).min().unwrap_or(0);
+            let right = group.iter().map(|l| l.b[0] + l.b[2]).max().unwrap_or(0);
+            let last = group.last().unwrap().b;
+            let b = [left, group[0].b[1], right - left, last[1] + last[3] - group[0].b[1]];
+            DocumentBlock { page: group[0].page, text, heading, b }
+        })
+        .collect()
+}
+
+/// Whether `line` carries on the paragraph `previous` is in.
+fn continues(previous: &Line, line: &Line) -> bool {
+    let gap = previous.baseline - line.baseline;
+    previous.page == line.page && previous.size == line.size && gap > 0.0 && gap <= line.size * 1.6
+}
+
+fn collapse_spaces(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// `obj`, following it if it's a reference.
+fn resolve<'a>(doc: &'a Document, obj: &'a Object) -> Option<&'a Object> {
+    match obj {
+        Object::Reference(id) => doc.get_object(*id).ok(),
+        other => Some(other),
+    }
+}
+
+fn dict_entry<'a>(doc: &'a Document, dict: &'a Dictionary, key: &[u8]) -> Option<&'a Object> {
+    dict.get(key).ok().and_then(|obj| resolve(doc, obj))
+}
+
+/// An entry of a page's dictionary, or of the page tree above it when the
+/// page inherits it.
+fn inherited<'a>(doc: &'a Document, page_id: ObjectId, key: &[u8]) -> Option<&'a Object> {
+    let mut node = doc.get_object(page_id).ok()?.as_dict().ok()?;
+    for _ in 0..32 {
+        if let Some(value) = dict_entry(doc, node, key) {
+            return Some(value);
+        }
+        node = dict_entry(doc, node, b"Parent")?.as_dict().ok()?;
+    }
+    None
+}
+
+fn media_box(doc: &Document, page_id: ObjectId) -> [f32; 4] {
+    let Some(Object::Array(values)) = inherited(doc, page_id, b"MediaBox") else {
+        return DEFAULT_MEDIA_BOX;
+    };
+    let numbers: Vec<f32> = values.iter().filter_map(|v| resolve(doc, v)?.as_float().ok()).collect();
+    match numbers[..] {
+        [x0, y0, x1, y1] if x1 > x0 && y1 > y0 => [x0, y0, x1, y1],
+        _ => DEFAULT_MEDIA_BOX,
+    }
+}
+
+/// The fonts a page's resources name, by resource name.
+fn page_fonts(doc: &Document, page_id: ObjectId) -> HashMap<Vec<u8>, Font> {
+    let Some(fonts) = inherited(doc, page_id, b"Resources")
+        .and_then(|r| r.as_dict().ok())
+        .and_then(|r| dict_entry(doc, r, b"Font"))
+        .and_then(|f| f.as_dict().ok())
+    else {
+        return HashMap::new();
+    };
+    fonts
+        .iter()
+        .filter_map(|(name, font)| {
+            let font = resolve(doc, font)?.as_dict().ok()?;
+            let number = |key: &[u8]| dict_entry(doc, font, key).and_then(|v| v.as_float().ok());
+            let widths = match dict_entry(doc, font, b"Widths") {
+                Some(Object::Array(widths)) => {
+                    widths.iter().map(|w| resolve(doc, w).and_then(|w| w.as_float().ok()).unwrap_or(0.0)).collect()
+                }
+                _ => Vec::new(),
+            };
+            let composite = dict_entry(doc, font, b"Subtype").and_then(|s| s.as_name().ok()) == Some(b"Type0".as_slice());
+            let descendant = match dict_entry(doc, font, b"DescendantFonts") {
+                Some(Object::Array(fonts)) => fonts.first().and_then(|f| resolve(doc, f)?.as_dict().ok()),
+                _ => None,
+            };
+            let cid_number = |key: &[u8]| descendant.and_then(|d| dict_entry(doc, d, key)?.as_float().ok());
+            let cid_widths = match descendant.and_then(|d| dict_entry(doc, d, b"W")) {
+                Some(Object::Array(widths)) => cid_widths(doc, widths),
+                _ => HashMap::new(),
+            };
+            let to_unicode = dict_entry(doc, font, b"ToUnicode")
+                .and_then(|s| s.as_stream().ok())
+                .map(to_unicode)
+                .unwrap_or_default();
+            Some((
+                name.clone(),
+                Font {
+                    first_char: number(b"FirstChar").unwrap_or(0.0) as u32,
+                    widths,
+                    composite,
+                    cid_widths,
+                    default_width: cid_number(b"DW").unwrap_or(1000.0),
+                    to_unicode,
+                    differences: differences(doc, font),
+                },
+            ))
+        })
+        .collect()
+}
+
+/// A composite font's `/W` array: a first code followed by the widths of it
+/// and the codes after it, or a first and last code sharing one width.
+fn cid_widths(doc: &Document, entries: &[Object]) -> HashMap<u32, f32> {
+    let mut widths = HashMap::new();
+    let mut entries = entries.iter().filter_map(|e| resolve(doc, e));
+    while let Some(first) = entries.next() {
+        let Ok(first) = first.as_i64() else { break };
+        let first = first.max(0) as u32;
+        match entries.next() {
+            Some(Object::Array(list)) => {
+                for (i, width) in list.iter().enumerate() {
+                    if let Some(width) = resolve(doc, width).and_then(|w| w.as_float().ok()) {
+                        widths.insert(first + i as u32, width);
+                    }
+                }
+            }
+            Some(last) => {
+                let (Ok(last), Some(width)) = (last.as_i64(), entries.next().and_then(|w| w.as_float().ok())) else {
+                    break;
+                };
+                for code in first..=(last.max(0) as u32).min(first + 0xffff) {
+                    widths.insert(code, width);
+                }
+            }
+            None => break,
+        }
+    }
+    widths
+}
+
+/// The text each code of a `/ToUnicode` CMap stands for, from its `bfchar`
+/// and `bfrange` sections.
+fn to_unicode(stream: &Stream) -> HashMap<u32, String> {
+    let mut map = HashMap::new();
+    let Some(cmap) = stream.get_plain_content().ok().and_then(|content| Content::decode(&content).ok()) else {
+        return map;
+    };
+    for op in cmap.operations {
+        match op.operator.as_str() {
+            "endbfchar" => {
+                for pair in op.operands.chunks_exact(2) {
+                    if let (Some(code), Object::String(text, _)) = (cmap_code(&pair[0]), &pair[1]) {
+                        map.insert(code, utf16_text(utf16_units(text)));
+                    }
+                }
+            }
+            "endbfrange" => {
+                for range in op.operands.chunks_exact(3) {
+                    let (Some(low), Some(high)) = (cmap_code(&range[0]), cmap_code(&range[1])) else { continue };
+                    for (i, code) in (low..=high.min(low + 0xffff)).enumerate() {
+                        let text = match &range[2] {
+                            // The last unit counts up from the first code's
+                            Object::String(start, _) => {
+                                let mut units = utf16_units(start);
+                                if let Some(last) = units.last_mut() {
+                                    *last = last.wrapping_add(i as u16);
+                                }
+                                utf16_text(units)
+                            }
+                            Object::Array(texts) => match texts.get(i) {
+                                Some(Object::String(text, _)) => utf16_text(utf16_units(text)),
+                                _ => continue,
+                            },
+                            _ => break,
+                        };
+                        map.insert(code, text);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    map
+}
+
+/// A CMap's source code: a hex string read as a big-endian number.
+fn cmap_code(obj: &Object) -> Option<u32> {
+    match obj {
+        Object::String(bytes, _) if !bytes.is_empty() && bytes.len() <= 4 => {
+            Some(bytes.iter().fold(0, |code, &byte| code << 8 | byte as u32))
+        }
+        _ => None,
+    }
+}
+
+fn utf16_units(bytes: &[u8]) -> Vec<u16> {
+    bytes.chunks(2).map(|pair| u16::from_be_bytes([pair[0], pair.get(1).copied().unwrap_or(0)])).collect()
+}
+
+/// UTF-16 text, without the control codes some CMaps map glyphs to.
+fn utf16_text(units: Vec<u16>) -> String {
+    String::from_utf16_lossy(&units).chars().filter(|c| !c.is_control()).collect()
+}
+
+/// The codes a simple font's `/Encoding /Differences` renames, with the text
+/// each new glyph name stands for, if any.
+fn differences(doc: &Document, font: &Dictionary) -> HashMap<u32, Option<String>> {
+    let mut map = HashMap::new();
+    let Some(Object::Array(entries)) = dict_entry(doc, font, b"Encoding")
+        .and_then(|e| e.as_dict().ok())
+        .and_then(|e| dict_entry(doc, e, b"Differences"))
+    else {
+        return map;
+    };
+    let mut code = 0;
+    for entry in entries {
+        match resolve(doc, entry) {
+            Some(Object::Integer(n)) => code = (*n).max(0) as u32,
+            Some(Object::Name(name)) => {
+                map.insert(code, glyph_text(name));
+                code += 1;
+            }
+            _ => {}
+        }
+    }
+    map
+}
+
+/// The text a glyph name stands for: `uniXXXX` and `uXXXX` name code points,
+/// a suffix after `.` names a variant of the same glyph, ligatures join
+/// their parts with `_`, and the rest are the standard Latin names. `None`
+/// for names made up by the font, such as a subset's `g42`.
+fn glyph_text(name: &[u8]) -> Option<String> {
+    let name = std::str::from_utf8(name).ok()?.split('.').next()?;
+    if name.is_empty() {
+        return None;
+    }
+    name.split('_').map(glyph_part).collect()
+}
+
+fn glyph_part(name: &str) -> Option<String> {
+    let hex = |hex: &str| u32::from_str_radix(hex, 16).ok().and_then(char::from_u32);
+    if let Some(units) = name.strip_prefix("uni").filter(|h| !h.is_empty() && h.len() % 4 == 0) {
+        return (0..units.len()).step_by(4).map(|i| hex(units.get(i..i + 4)?)).collect();
+    }
+    if let Some(code) = name.strip_prefix('u').filter(|h| (4..=6).contains(&h.len())) {
+        return hex(code).map(String::from);
+    }
+    if name.len() == 1 && name.as_bytes()[0].is_ascii_alphabetic() {
+        return Some(name.to_string());
+    }
+    if let Some(i) = ASCII_GLYPHS.iter().position(|n| *n == name) {
+        return Some(char::from(b' ' + i as u8).to_string());
+    }
+    if let Some(i) = LATIN1_GLYPHS.iter().position(|n| *n == name) {
+        return Some(char::from(0xa0 + i as u8).to_string());
+    }
+    OTHER_GLYPHS.iter().find(|(n, _)| *n == name).map(|(_, text)| text.to_string())
+}
+
+/// Glyph names of the printable ASCII characters other than letters, from
+/// the space on; letters are named by themselves.
+const ASCII_GLYPHS: [&str; 95] = [
+    "space", "exclam", "quotedbl", "numbersign", "dollar", "percent", "ampersand", "quotesingle",
+    "parenleft", "parenright", "asterisk", "plus", "comma", "hyphen", "period", "slash",
+    "zero", "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+    "colon", "semicolon", "less", "equal", "greater", "question", "at",
+    "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M",
+    "N", "O", "P", "Q", "R", "S", "T", "U", "V", "W", "X", "Y", "Z",
+    "bracketleft", "backslash", "bracketright", "asciicircum", "underscore", "grave",
+    "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m",
+    "n", "o", "p", "q", "r", "s", "t", "u", "v", "w", "x", "y", "z",
+    "braceleft", "bar", "braceright", "asciitilde",
+];
+
+/// Glyph names of the Latin-1 characters from the no-break space on.
+const LATIN1_GLYPHS: [&str; 96] = [
+    "nbspace", "exclamdown", "cent", "sterling", "currency", "yen", "brokenbar", "section",
+    "dieresis", "copyright", "ordfeminine", "guillemotleft", "logicalnot", "sfthyphen", "registered", "macron",
+    "degree", "plusminus", "twosuperior", "threesuperior", "acute", "mu", "paragraph", "periodcentered",
+    "cedilla", "onesuperior", "ordmasculine", "guillemotright", "onequarter", "onehalf", "threequarters", "questiondown",
+    "Agrave", "Aacute", "Acircumflex", "Atilde", "Adieresis", "Aring", "AE", "Ccedilla",
+    "Egrave", "Eacute", "Ecircumflex", "Edieresis", "Igrave", "Iacute", "Icircumflex", "Idieresis",
+    "Eth", "Ntilde", "Ograve", "Oacute", "Ocircumflex", "Otilde", "Odieresis", "multiply",
+    "Oslash", "Ugrave", "Uacute", "Ucircumflex", "Udieresis", "Yacute", "Thorn", "germandbls",
+    "agrave", "aacute", "acircumflex", "atilde", "adieresis", "aring", "ae", "ccedilla",
+    "egrave", "eacute", "ecircumflex", "edieresis", "igrave", "iacute", "icircumflex", "idieresis",
+    "eth", "ntilde", "ograve", "oacute", "ocircumflex", "otilde", "odieresis", "divide",
+    "oslash", "ugrave", "uacute", "ucircumflex", "udieresis", "yacute", "thorn", "ydieresis",
+];
+
+/// Other common glyph names: WinAnsi's punctuation and the ligatures.
+const OTHER_GLYPHS: &[(&str, &str)] = &[
+    ("quoteleft", "‘"), ("quoteright", "’"), ("quotedblleft", "“"), ("quotedblright", "”"),
+    ("quotesinglbase", "‚"), ("quotedblbase", "„"), ("guilsinglleft", "‹"), ("guilsinglright", "›"),
+    ("bullet", "•"), ("endash", "–"), ("emdash", "—"), ("ellipsis", "…"), ("minus", "−"),
+    ("dagger", "†"), ("daggerdbl", "‡"), ("perthousand", "‰"), ("trademark", "™"), ("Euro", "€"),
+    ("florin", "ƒ"), ("circumflex", "ˆ"), ("tilde", "˜"), ("dotlessi", "ı"),
+    ("OE", "Œ"), ("oe", "œ"), ("Scaron", "Š"), ("scaron", "š"), ("Zcaron", "Ž"), ("zcaron", "ž"), ("Ydieresis", "Ÿ"),
+    ("fi", "fi"), ("fl", "fl"), ("ff", "ff"), ("ffi", "ffi"), ("ffl", "ffl"),
+];
+
+/// The document's `/Title`, when it has a non-blank one.
+fn info_title(doc: &Document) -> Option<String> {
+    let info = resolve(doc, doc.trailer.get(b"Info").ok()?)?.as_dict().ok()?;
+    let Object::String(bytes, _) = dict_entry(doc, info, b"Title")? else {
+        return None;
+    };
+    let title = match bytes.strip_prefix(&[0xfe_u8, 0xff]) {
+        Some(utf16) => {
+            let units: Vec<u16> = utf16.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+            String::from_utf16_lossy(&units)
+        }
+        None => bytes.iter().filter_map(|&b| decode(b)).collect(),
+    };
+    let title = collapse_spaces(&title);
+    (!title.is_empty()).then_some(title)
+}
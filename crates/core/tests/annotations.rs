@@ -0,0 +1,85 @@
+//! `Session::annotate`: notes follow their elements when the page is
+//! rendered again, and are dropped, and reported in the delta, when the
+//! element or the page goes away.
+
+#![cfg(feature = "fetch")]
+
+use browsy_core::fetch::{FetchError, Session, SessionConfig};
+use browsy_core::output::{self, DroppedNote};
+
+const PAGE: &str = r#"<html><body>
+    <a href="/">Home</a>
+    <button onclick="toggle('menu')">Account</button>
+    <ul id="menu" style="display: none;">
+        <li><a href="/profile">Profile</a></li>
+        <li><a href="/logout">Sign out</a></li>
+    </ul>
+    <p>Welcome back.</p>
+</body></html>"#;
+
+fn labeled(session: &Session, text: &str) -> u32 {
+    session.find_by_text_exact(text)[0].id
+}
+
+#[test]
+fn test_notes_survive_a_toggle_rerender() {
+    let mut session = Session::new().unwrap();
+    session.load_html_offline(PAGE, "http://localhost/page").unwrap();
+    let profile = labeled(&session, "Profile");
+    session.annotate(profile, "has the email settings".into()).unwrap();
+    session.annotate(labeled(&session, "Home"), "  ".into()).unwrap();
+    assert_eq!(session.annotations(), [(profile, "has the email settings".to_string())]);
+
+    session.click(labeled(&session, "Account")).unwrap();
+    let profile = labeled(&session, "Profile");
+    assert_eq!(session.annotations(), [(profile, "has the email settings".to_string())]);
+    let dom = session.dom().unwrap();
+    assert_eq!(dom.get(profile).unwrap().note.as_deref(), Some("has the email settings"));
+    assert!(session.delta().unwrap().dropped_notes.is_empty());
+
+    let compact = output::to_compact_string(&dom);
+    let line = compact.lines().find(|l| l.contains("\"Profile\"")).unwrap();
+    assert_eq!(output::parse_compact_line(line).unwrap().note.as_deref(), Some("has the email settings"));
+
+    // An empty note removes it
+    session.annotate(profile, String::new()).unwrap();
+    assert!(session.annotations().is_empty());
+}
+
+#[test]
+fn test_notes_are_dropped_on_another_page() {
+    let mut session = Session::new().unwrap();
+    session.load_html_offline(PAGE, "http://localhost/page").unwrap();
+    let home = labeled(&session, "Home");
+    session.annotate(home, "start here".into()).unwrap();
+
+    session.load_html_offline(PAGE, "http://localhost/other").unwrap();
+    assert!(session.annotations().is_empty());
+    assert!(session.dom().unwrap().els.iter().all(|e| e.note.is_none()));
+    let delta = session.delta().unwrap();
+    assert_eq!(delta.dropped_notes, [DroppedNote { id: home, note: "start here".into() }]);
+    assert!(output::delta_to_compact_string(&delta).contains(&format!("-#{home} \"start here\"")));
+
+    let err = session.annotate(9999, "missing".into()).unwrap_err();
+    assert!(matches!(err, FetchError::ElementNotFound { id: 9999, .. }), "{err:?}");
+}
+
+#[test]
+fn test_notes_follow_elements_whose_ids_change() {
+    let mut session = Session::with_config(SessionConfig { stable_ids: false, ..SessionConfig::default() }).unwrap();
+    session.load_html_offline(PAGE, "http://localhost/page").unwrap();
+    let welcome = labeled(&session, "Welcome back.");
+    session.annotate(welcome, "greeting".into()).unwrap();
+    session.annotate(labeled(&session, "Sign out"), "don't".into()).unwrap();
+
+    // The same page again, with a banner above and without the sign-out link
+    let changed = PAGE
+        .replace("<body>", "<body><p>Sale ends today</p><p>Free shipping</p>")
+        .replace(r#"<li><a href="/logout">Sign out</a></li>"#, "");
+    session.load_html_offline(&changed, "http://localhost/page").unwrap();
+    let moved = labeled(&session, "Welcome back.");
+    assert_ne!(moved, welcome);
+    assert_eq!(session.annotations(), [(moved, "greeting".to_string())]);
+    let dropped = session.delta().unwrap().dropped_notes;
+    assert_eq!(dropped.iter().map(|d| d.note.as_str()).collect::<Vec<_>>(), ["don't"]);
+}
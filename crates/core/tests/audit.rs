@@ -0,0 +1,139 @@
+//! The per-session audit log: a small login flow against a local server,
+//! checked event by event.
+
+#![cfg(feature = "fetch")]
+
+mod common;
+
+use browsy_core::fetch::{AuditAction, AuditEvent, Session, SessionConfig};
+use common::Response;
+
+const LOGIN_PAGE: &str = r#"<html><head><title>Sign in</title></head><body>
+    <form action="/login" method="post">
+        <input type="text" name="username" placeholder="Username">
+        <input type="password" name="password" placeholder="Password">
+        <select name="plan"><option value="free">Free</option><option value="pro">Pro</option></select>
+        <button type="submit">Sign in</button>
+    </form>
+</body></html>"#;
+
+const WELCOME_PAGE: &str = "<html><head><title>Welcome</title></head><body><h1>Hello</h1></body></html>";
+
+/// Serve `LOGIN_PAGE` for GETs and `WELCOME_PAGE` for POSTs. Returns the base URL.
+fn serve() -> String {
+    let port =
+        common::serve(|request| Response::html(if request.method == "POST" { WELCOME_PAGE } else { LOGIN_PAGE }));
+    format!("http://127.0.0.1:{port}")
+}
+
+fn session(audit_log_limit: usize) -> Session {
+    Session::with_config(SessionConfig {
+        fetch_css: false,
+        allow_private_network: true,
+        audit_log_limit,
+        ..SessionConfig::default()
+    })
+    .unwrap()
+}
+
+fn input(session: &Session, name: &str) -> u32 {
+    session.dom().unwrap().els.iter().find(|e| e.name.as_deref() == Some(name)).unwrap().id
+}
+
+#[test]
+fn test_login_flow_is_audited_in_order() {
+    let base = serve();
+    let mut session = session(1000);
+    session.goto(&base).unwrap();
+
+    let username = input(&session, "username");
+    let password = input(&session, "password");
+    let plan = input(&session, "plan");
+    let submit = session.dom().unwrap().find_by_text("Sign in")[0].id;
+    session.type_text(username, "ada").unwrap();
+    session.type_text(password, "hunter22").unwrap();
+    session.select(plan, "pro").unwrap();
+    assert_eq!(session.click(submit).unwrap().title, "Welcome");
+
+    let log = session.audit_log();
+    let actions: Vec<AuditAction> = log.iter().map(|e| e.action.clone()).collect();
+    assert_eq!(
+        actions,
+        vec![
+            AuditAction::Navigate { url: base.clone() },
+            AuditAction::Type { id: username, value: "ada".to_string() },
+            AuditAction::Type { id: password, value: "•••• (8 chars)".to_string() },
+            AuditAction::Select { id: plan, value: "pro".to_string() },
+            AuditAction::Click { id: submit, text: Some("Sign in".to_string()) },
+            AuditAction::Submit {
+                url: format!("{base}/login"),
+                method: "POST".to_string(),
+                fields: vec!["username".to_string(), "password".to_string(), "plan".to_string()],
+            },
+        ]
+    );
+    let seqs: Vec<u64> = log.iter().map(|e| e.seq).collect();
+    assert_eq!(seqs, vec![1, 2, 3, 4, 5, 6]);
+    assert!(log.windows(2).all(|w| w[0].ts_ms <= w[1].ts_ms));
+
+    let since = session.audit_log_since(4);
+    assert_eq!(since.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![5, 6]);
+}
+
+#[test]
+fn test_audit_log_is_capped() {
+    let base = serve();
+    let mut session = session(2);
+    session.goto(&base).unwrap();
+    let username = input(&session, "username");
+    for name in ["a", "b", "c"] {
+        session.type_text(username, name).unwrap();
+    }
+
+    let log = session.audit_log();
+    assert_eq!(log.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![3, 4]);
+    assert_eq!(log[1].action, AuditAction::Type { id: username, value: "c".to_string() });
+    // Sequence numbers keep counting past evicted events
+    assert_eq!(session.audit_log_since(3).len(), 1);
+    assert!(session.audit_log_since(4).is_empty());
+}
+
+#[test]
+fn test_zero_limit_records_nothing() {
+    let base = serve();
+    let mut session = session(0);
+    session.goto(&base).unwrap();
+    assert!(session.audit_log().is_empty());
+}
+
+#[test]
+fn test_serialized_schema() {
+    let event = AuditEvent {
+        seq: 7,
+        ts_ms: 1_700_000_000_000,
+        action: AuditAction::Click { id: 4, text: Some("Sign in".to_string()) },
+    };
+    let json = serde_json::to_value(&event).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({"seq": 7, "ts_ms": 1_700_000_000_000u64, "type": "click", "id": 4, "text": "Sign in"})
+    );
+    let back: AuditEvent = serde_json::from_value(json).unwrap();
+    assert_eq!(back, event);
+}
+
+#[test]
+fn test_blocked_navigation_is_audited_as_error() {
+    let mut session = Session::with_config(SessionConfig {
+        fetch_css: false,
+        blocked_domains: vec!["blocked.test".to_string()],
+        ..SessionConfig::default()
+    })
+    .unwrap();
+    assert!(session.goto("https://blocked.test/").is_err());
+
+    let log = session.audit_log();
+    assert_eq!(log.len(), 2);
+    assert_eq!(log[0].action, AuditAction::Navigate { url: "https://blocked.test/".to_string() });
+    assert!(matches!(&log[1].action, AuditAction::Error { message } if message.contains("blocked.test")));
+}
@@ -71,13 +71,14 @@ fn parse_page_type(s: &str) -> Option<PageType> {
         "Inbox" => Some(PageType::Inbox),
         "EmailBody" => Some(PageType::EmailBody),
         "Dashboard" => Some(PageType::Dashboard),
+        "Product" => Some(PageType::Product),
         "Form" => Some(PageType::Form),
         "Article" => Some(PageType::Article),
         "List" => Some(PageType::List),
         "Error" => Some(PageType::Error),
         "Other" => Some(PageType::Other),
         "Any" => None,
-        _ => panic!("Unknown page type in manifest: {:?}. Valid values: Login, TwoFactorAuth, OAuthConsent, Captcha, Blocked, Search, SearchResults, Inbox, EmailBody, Dashboard, Form, Article, List, Error, Other, Any", s),
+        _ => panic!("Unknown page type in manifest: {:?}. Valid values: Login, TwoFactorAuth, OAuthConsent, Captcha, Blocked, Search, SearchResults, Inbox, EmailBody, Dashboard, Product, Form, Article, List, Error, Other, Any", s),
     }
 }
 
@@ -90,6 +91,7 @@ fn action_type_name(action: &SuggestedAction) -> &str {
         SuggestedAction::SelectFromList { .. } => "SelectFromList",
         SuggestedAction::CookieConsent { .. } => "CookieConsent",
         SuggestedAction::Paginate { .. } => "Paginate",
+        SuggestedAction::LoadMore { .. } => "LoadMore",
         SuggestedAction::Register { .. } => "Register",
         SuggestedAction::Contact { .. } => "Contact",
         SuggestedAction::FillForm { .. } => "FillForm",
@@ -109,8 +111,8 @@ fn action_ids(action: &SuggestedAction) -> Vec<u32> {
         SuggestedAction::EnterCode { input_id, submit_id, .. } => {
             vec![*input_id, *submit_id]
         }
-        SuggestedAction::Search { input_id, submit_id } => {
-            vec![*input_id, *submit_id]
+        SuggestedAction::Search { input_id, submit_id, .. } => {
+            std::iter::once(*input_id).chain(*submit_id).collect()
         }
         SuggestedAction::Consent { approve_ids, deny_ids } => {
             approve_ids.iter().chain(deny_ids.iter()).copied().collect()
@@ -129,6 +131,7 @@ fn action_ids(action: &SuggestedAction) -> Vec<u32> {
             if let Some(id) = prev_id { ids.push(*id); }
             ids
         }
+        SuggestedAction::LoadMore { button_id } => vec![*button_id],
         SuggestedAction::Register { email_id, username_id, password_id, confirm_password_id, name_id, submit_id } => {
             let mut ids = vec![*password_id, *submit_id];
             if let Some(id) = email_id { ids.push(*id); }
@@ -230,6 +233,7 @@ fn abbrev_actions(actions: &[String]) -> String {
         "SelectFromList" => "SFL",
         "CookieConsent" => "CC",
         "Paginate" => "PG",
+        "LoadMore" => "LM",
         "Register" => "R",
         "Contact" => "CT",
         "FillForm" => "FF",
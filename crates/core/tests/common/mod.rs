@@ -0,0 +1,141 @@
+//! A local HTTP server for the tests that fetch pages. Each test file
+//! supplies a handler that answers its requests.
+
+// Each test crate uses a different part of this
+#![allow(dead_code)]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+
+/// A request as the server read it.
+pub struct Request {
+    pub method: String,
+    /// The request target, with any query string.
+    pub path: String,
+    /// Header names are lowercased.
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+    /// The port the server listens on, for pages that link back to it.
+    pub port: u16,
+}
+
+impl Request {
+    /// The value of header `name` (lowercase), if it was sent.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+
+    /// Whether the request carried `cookie`, such as `"sid=ada"`.
+    pub fn has_cookie(&self, cookie: &str) -> bool {
+        self.header("cookie")
+            .is_some_and(|value| value.split(';').any(|c| c.trim() == cookie))
+    }
+}
+
+/// What the server sends back. `Content-Length` and `Connection: close`
+/// are added when it is written.
+pub struct Response {
+    pub status: &'static str,
+    pub headers: Vec<String>,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// `200 OK` with an HTML page.
+    pub fn html(page: impl Into<String>) -> Response {
+        Response::ok("text/html", page.into())
+    }
+
+    /// `200 OK` with a body of `content_type`.
+    pub fn ok(content_type: &str, body: impl Into<Vec<u8>>) -> Response {
+        Response {
+            status: "200 OK",
+            headers: vec![format!("Content-Type: {content_type}")],
+            body: body.into(),
+        }
+    }
+
+    /// An empty response, such as `"404 Not Found"`.
+    pub fn empty(status: &'static str) -> Response {
+        Response { status, headers: Vec::new(), body: Vec::new() }
+    }
+
+    /// A `302 Found` redirect to `location`.
+    pub fn redirect(location: &str) -> Response {
+        Response::empty("302 Found").with_header(format!("Location: {location}"))
+    }
+
+    /// Add a header line, such as `"Set-Cookie: sid=ada; Path=/"`.
+    pub fn with_header(mut self, header: impl Into<String>) -> Response {
+        self.headers.push(header.into());
+        self
+    }
+}
+
+/// Serve `handler` on a random local port and return the port. Responses
+/// to `HEAD` requests leave the body out.
+pub fn serve(handler: impl Fn(&Request) -> Response + Send + 'static) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            if reader.read_line(&mut line).is_err() {
+                continue;
+            }
+            let mut parts = line.split_whitespace();
+            let method = parts.next().unwrap_or("GET").to_string();
+            let path = parts.next().unwrap_or("/").to_string();
+            let mut headers = Vec::new();
+            loop {
+                let mut header = String::new();
+                if reader.read_line(&mut header).is_err() || header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+                }
+            }
+            let length = headers
+                .iter()
+                .find(|(name, _)| name == "content-length")
+                .and_then(|(_, value)| value.parse().ok())
+                .unwrap_or(0);
+            let mut body = vec![0u8; length];
+            let _ = reader.read_exact(&mut body);
+
+            let request = Request { method, path, headers, body: String::from_utf8_lossy(&body).to_string(), port };
+            let response = handler(&request);
+            let mut head = format!("HTTP/1.1 {}\r\n", response.status);
+            for header in &response.headers {
+                head.push_str(&format!("{header}\r\n"));
+            }
+            head.push_str(&format!("Content-Length: {}\r\nConnection: close\r\n\r\n", response.body.len()));
+            let _ = stream.write_all(head.as_bytes());
+            if request.method != "HEAD" {
+                let _ = stream.write_all(&response.body);
+            }
+        }
+    });
+    port
+}
+
+/// Serve a site where `/login` sets a `sid` session cookie and every other
+/// page says whether the request carried it. Pages are titled with their
+/// path. Returns the port, so it can be reached as both `localhost` and
+/// `127.0.0.1`.
+pub fn serve_sign_in() -> u16 {
+    serve(|request| {
+        let path = request.path.as_str();
+        let status = if request.has_cookie("sid=ada") { "Signed in as Ada" } else { "Not signed in" };
+        let response =
+            Response::html(format!("<html><head><title>{path}</title></head><body><p>{status}</p></body></html>"));
+        if path == "/login" {
+            response.with_header("Set-Cookie: sid=ada; Path=/")
+        } else {
+            response
+        }
+    })
+}
@@ -0,0 +1,98 @@
+//! Conditional requests: a local server that answers `304 Not Modified` when
+//! the client sends back the validator it handed out.
+
+#![cfg(feature = "fetch")]
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use browsy_core::fetch::{Session, SessionConfig};
+use common::Response;
+
+const PAGE: &str = "<html><head><title>Prices</title></head><body><h1>Widget: $5</h1></body></html>";
+const LAST_MODIFIED: &str = "Wed, 21 Oct 2026 07:28:00 GMT";
+
+/// Serve `PAGE` with `ETag: "v1"` at `/etag` and a `Last-Modified` date
+/// everywhere else, answering 304 to matching validators. Returns the base
+/// URL and the status of each response sent.
+fn serve() -> (String, Arc<Mutex<Vec<u16>>>) {
+    let statuses = Arc::new(Mutex::new(Vec::new()));
+    let sent = statuses.clone();
+    let port = common::serve(move |request| {
+        let (validator, unchanged) = if request.path == "/etag" {
+            ("ETag: \"v1\"".to_string(), request.header("if-none-match") == Some("\"v1\""))
+        } else {
+            (format!("Last-Modified: {LAST_MODIFIED}"), request.header("if-modified-since") == Some(LAST_MODIFIED))
+        };
+        if unchanged {
+            sent.lock().unwrap().push(304);
+            Response::empty("304 Not Modified").with_header(validator)
+        } else {
+            sent.lock().unwrap().push(200);
+            Response::html(PAGE).with_header(validator)
+        }
+    });
+    (format!("http://127.0.0.1:{port}"), statuses)
+}
+
+fn session(use_conditional_requests: bool) -> Session {
+    Session::with_config(SessionConfig {
+        fetch_css: false,
+        allow_private_network: true,
+        use_conditional_requests,
+        ..SessionConfig::default()
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_goto_if_modified_reuses_page_on_304() {
+    let (base, statuses) = serve();
+    let url = format!("{base}/etag");
+    let mut session = session(false);
+
+    let first = session.goto_if_modified(&url).unwrap();
+    assert!(!first.not_modified);
+    let second = session.goto_if_modified(&url).unwrap();
+    assert!(second.not_modified);
+    assert_eq!(second.title, "Prices");
+    assert_eq!(second.els.len(), first.els.len());
+    assert_eq!(session.history_len(), 2);
+    assert_eq!(*statuses.lock().unwrap(), vec![200, 304]);
+
+    // Plain goto doesn't send validators unless configured to
+    assert!(!session.goto(&url).unwrap().not_modified);
+    assert_eq!(*statuses.lock().unwrap(), vec![200, 304, 200]);
+}
+
+#[test]
+fn test_conditional_goto_with_last_modified() {
+    let (base, statuses) = serve();
+    let url = format!("{base}/dated");
+    let mut session = session(true);
+
+    assert!(!session.goto(&url).unwrap().not_modified);
+    let again = session.goto(&url).unwrap();
+    assert!(again.not_modified);
+    assert_eq!(session.dom().unwrap().title, "Prices");
+    assert_eq!(*statuses.lock().unwrap(), vec![200, 304]);
+}
+
+#[test]
+fn test_least_recently_used_page_is_forgotten() {
+    let (base, statuses) = serve();
+    let mut session = session(true);
+    // The session remembers 32 pages
+    for i in 0..32 {
+        session.goto(&format!("{base}/{i}")).unwrap();
+    }
+    // Revisiting page 0 makes page 1 the oldest
+    assert!(session.goto(&format!("{base}/0")).unwrap().not_modified);
+    session.goto(&format!("{base}/32")).unwrap();
+
+    assert!(!session.goto(&format!("{base}/1")).unwrap().not_modified);
+    // The fragment doesn't matter
+    assert!(session.goto(&format!("{base}/0#top")).unwrap().not_modified);
+    assert_eq!(statuses.lock().unwrap()[32..], [304, 200, 200, 304]);
+}
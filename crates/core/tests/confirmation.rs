@@ -0,0 +1,150 @@
+//! `SessionConfig::action_policy`: matching clicks and submissions are held
+//! until `Session::confirm` takes them, and everything else goes through.
+
+#![cfg(feature = "fetch")]
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use browsy_core::fetch::{ActionPolicy, AuditAction, FetchError, Session, SessionConfig};
+use common::Response;
+
+const ACCOUNT: &str = r#"<html><head><title>Account</title></head><body>
+    <form action="/account/delete" method="post">
+        <input type="text" name="reason" />
+        <button type="submit">Delete account</button>
+    </form>
+    <form action="/search" method="get">
+        <input type="text" name="q" />
+        <button type="submit">Search</button>
+    </form>
+    <a href="http://localhost:PORT/elsewhere">Elsewhere</a>
+</body></html>"#;
+
+/// Serve `ACCOUNT` at `/`, and for any other target a page titled with the
+/// request line. Returns the port and the request lines served.
+fn serve() -> (u16, Arc<Mutex<Vec<String>>>) {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let log = requests.clone();
+    let port = common::serve(move |request| {
+        let line = format!("{} {}", request.method, request.path);
+        log.lock().unwrap().push(line.clone());
+        if line == "GET /" {
+            Response::html(ACCOUNT.replace("PORT", &request.port.to_string()))
+        } else {
+            Response::html(format!("<html><head><title>{line}</title></head><body><p>Done</p></body></html>"))
+        }
+    });
+    (port, requests)
+}
+
+fn session(policy: ActionPolicy) -> Session {
+    Session::with_config(SessionConfig {
+        fetch_css: false,
+        allow_private_network: true,
+        action_policy: Some(policy),
+        ..SessionConfig::default()
+    })
+    .unwrap()
+}
+
+fn policy() -> ActionPolicy {
+    ActionPolicy { allowed_domains: vec!["127.0.0.1".to_string()], ..ActionPolicy::default() }
+}
+
+fn labeled(session: &Session, text: &str) -> u32 {
+    session.dom_ref().unwrap().els.iter().find(|e| e.text.as_deref() == Some(text)).unwrap().id
+}
+
+fn named(session: &Session, name: &str) -> u32 {
+    session.dom_ref().unwrap().els.iter().find(|e| e.name.as_deref() == Some(name)).unwrap().id
+}
+
+fn held(result: Result<impl std::fmt::Debug, FetchError>) -> (String, String) {
+    match result {
+        Err(FetchError::ConfirmationRequired { token, description }) => (token, description),
+        other => panic!("expected ConfirmationRequired, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_patterns_match_whole_words() {
+    let policy = ActionPolicy::default();
+    assert_eq!(policy.matching_pattern("Delete account"), Some("delete"));
+    assert_eq!(policy.matching_pattern("Pay now"), Some("pay"));
+    assert_eq!(policy.matching_pattern("CONFIRM PURCHASE"), Some("confirm purchase"));
+    assert_eq!(policy.matching_pattern("Display options"), None);
+    assert_eq!(policy.matching_pattern("Undeleted items"), None);
+    assert_eq!(policy.matching_pattern("Payment details"), None);
+}
+
+#[test]
+fn test_matching_submission_waits_for_confirm() {
+    let (port, requests) = serve();
+    let mut session = session(policy());
+    session.goto(&format!("http://127.0.0.1:{port}/")).unwrap();
+    let reason = named(&session, "reason");
+    session.type_text(reason, "moving").unwrap();
+
+    let (token, description) = held(session.click(labeled(&session, "Delete account")));
+    assert_eq!(
+        description,
+        format!("Submit \"Delete account\" (POST http://127.0.0.1:{port}/account/delete) because the button matches \"delete\"")
+    );
+    // Pressing Enter in the form's field submits it too
+    let (other, _) = held(session.press_enter(reason));
+    assert_ne!(token, other);
+    assert_eq!(*requests.lock().unwrap(), ["GET /"], "a held action was sent");
+    assert_eq!(session.dom_ref().unwrap().title, "Account");
+
+    let dom = session.confirm(&token).unwrap();
+    assert_eq!(dom.title, "POST /account/delete");
+    assert!(session.confirm(&token).is_err(), "a token confirms its action once");
+
+    let audit: Vec<_> = session.audit_log().into_iter().map(|e| e.action).collect();
+    assert!(audit.contains(&AuditAction::Held { description: description.clone() }), "{audit:?}");
+    assert!(audit.contains(&AuditAction::Confirmed { description }), "{audit:?}");
+}
+
+#[test]
+fn test_other_actions_go_through() {
+    let (port, requests) = serve();
+    let mut session = session(policy());
+    session.goto(&format!("http://127.0.0.1:{port}/")).unwrap();
+    session.type_text(named(&session, "q"), "rust").unwrap();
+
+    let dom = session.click(labeled(&session, "Search")).unwrap();
+    assert_eq!(dom.title, "GET /search?q=rust");
+    assert_eq!(requests.lock().unwrap().len(), 2);
+}
+
+#[test]
+fn test_leaving_the_allowed_domains_is_held() {
+    let (port, requests) = serve();
+    let mut session = session(policy());
+    session.goto(&format!("http://127.0.0.1:{port}/")).unwrap();
+    let elsewhere = labeled(&session, "Elsewhere");
+
+    let (_, description) = held(session.click(elsewhere));
+    assert!(description.contains("outside the allowed domains"), "{description}");
+    assert_eq!(requests.lock().unwrap().len(), 1);
+
+    // Approved up front
+    let dom = session.confirmed(|s| s.click(elsewhere)).unwrap();
+    assert_eq!(dom.title, "GET /elsewhere");
+    assert_eq!(session.url(), Some(format!("http://localhost:{port}/elsewhere").as_str()));
+}
+
+#[test]
+fn test_held_actions_expire() {
+    let (port, requests) = serve();
+    let mut session = session(ActionPolicy { confirm_timeout_secs: 0, ..policy() });
+    session.goto(&format!("http://127.0.0.1:{port}/")).unwrap();
+
+    let (token, _) = held(session.click(labeled(&session, "Delete account")));
+    let err = session.confirm(&token).unwrap_err();
+    assert!(matches!(err, FetchError::ActionError(_)), "{err:?}");
+    assert!(err.to_string().contains("expired"), "{err}");
+    assert_eq!(requests.lock().unwrap().len(), 1);
+}
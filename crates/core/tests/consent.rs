@@ -0,0 +1,117 @@
+//! Automatic cookie banner dismissal. Form posts are answered from replay
+//! fixtures so nothing touches the network.
+
+#![cfg(feature = "fetch")]
+
+use std::path::PathBuf;
+
+use browsy_core::fetch::{ConsentChoice, Exchange, RecordingMode, Session, SessionConfig};
+use browsy_core::output::SuggestedAction;
+
+const JS_BANNER: &str = r#"<html><head><title>News</title></head><body>
+    <div id="cookie-banner">
+        <p>We use cookies to improve your experience and to measure traffic.</p>
+        <button onclick="document.getElementById('cookie-banner').style.display='none'">Accept all</button>
+        <button onclick="document.getElementById('cookie-banner').style.display='none'">Reject all</button>
+    </div>
+    <h1>Today's headlines</h1>
+</body></html>"#;
+
+const FORM_BANNER: &str = r#"<html><head><title>Shop</title></head><body>
+    <div class="consent">
+        <p>This site uses cookies for analytics and personalised ads.</p>
+        <form action="/consent" method="post">
+            <input type="hidden" name="choice" value="accept">
+            <button type="submit">Accept all</button>
+        </form>
+        <form action="/consent" method="post">
+            <input type="hidden" name="choice" value="reject">
+            <button type="submit">Reject all</button>
+        </form>
+    </div>
+</body></html>"#;
+
+const AFTER_CONSENT: &str = "<html><head><title>Shop</title></head><body><h1>Welcome to the shop</h1></body></html>";
+
+fn session(choice: Option<ConsentChoice>, recording: Option<RecordingMode>) -> Session {
+    Session::with_config(SessionConfig {
+        fetch_css: false,
+        auto_dismiss_cookie_banners: choice,
+        recording,
+        ..SessionConfig::default()
+    })
+    .unwrap()
+}
+
+fn has_consent_action(dom: &browsy_core::output::SpatialDom) -> bool {
+    dom.suggested_actions.iter().any(|a| matches!(a, SuggestedAction::CookieConsent { .. }))
+}
+
+#[test]
+fn test_js_banner_is_hidden_on_load() {
+    let mut session = session(Some(ConsentChoice::Accept), None);
+    let dom = session.load_html(JS_BANNER, "https://news.example/").unwrap();
+
+    assert_eq!(dom.auto_actions.len(), 1, "{:?}", dom.auto_actions);
+    assert!(dom.auto_actions[0].contains("\"Accept all\""), "{:?}", dom.auto_actions);
+    assert!(dom.auto_actions[0].contains("accept cookies"), "{:?}", dom.auto_actions);
+    let notice = dom.find_by_text("We use cookies")[0];
+    assert_eq!(notice.hidden, Some(true), "banner should be hidden after accepting");
+    // The session's view of the page agrees
+    assert_eq!(session.dom().unwrap().auto_actions, dom.auto_actions);
+}
+
+#[test]
+fn test_banner_left_alone_by_default() {
+    let mut session = session(None, None);
+    let dom = session.load_html(JS_BANNER, "https://news.example/").unwrap();
+    assert!(dom.auto_actions.is_empty());
+    assert!(has_consent_action(&dom));
+    assert_ne!(dom.find_by_text("We use cookies")[0].hidden, Some(true));
+}
+
+fn replay_fixtures() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("browsy-consent-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let exchanges = [
+        ("GET", "https://shop.example/", None, FORM_BANNER),
+        // FNV-1a of the form body "choice=reject"
+        ("POST", "https://shop.example/consent", Some("9ffd052b91a6555a"), AFTER_CONSENT),
+    ];
+    for (i, (method, url, body_hash, body)) in exchanges.iter().enumerate() {
+        let exchange = Exchange {
+            method: method.to_string(),
+            url: url.to_string(),
+            body_hash: body_hash.map(str::to_string),
+            status: 200,
+            final_url: url.to_string(),
+            headers: vec![("content-type".to_string(), "text/html".to_string())],
+            body: body.to_string(),
+        };
+        let json = serde_json::to_string_pretty(&exchange).unwrap();
+        std::fs::write(dir.join(format!("{i:04}.json")), json).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn test_form_banner_posts_the_chosen_form() {
+    let dir = replay_fixtures();
+    let mut session = session(Some(ConsentChoice::Reject), Some(RecordingMode::Replay(dir.clone())));
+
+    let dom = session.goto("https://shop.example/").unwrap();
+    assert_eq!(dom.url, "https://shop.example/consent");
+    assert!(dom.find_by_text("Welcome to the shop").len() == 1, "{:?}", dom.auto_actions);
+    assert_eq!(dom.auto_actions.len(), 1);
+    assert!(dom.auto_actions[0].contains("\"Reject all\""), "{:?}", dom.auto_actions);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_consent_choice_parses() {
+    assert_eq!("accept".parse::<ConsentChoice>(), Ok(ConsentChoice::Accept));
+    assert_eq!("Reject".parse::<ConsentChoice>(), Ok(ConsentChoice::Reject));
+    assert!("maybe".parse::<ConsentChoice>().is_err());
+}
@@ -0,0 +1,179 @@
+//! `Session::crawl` over a small local site. The server is reached as
+//! `localhost`, so its links to `127.0.0.1` count as another host.
+
+#![cfg(feature = "fetch")]
+
+mod common;
+
+use std::collections::BTreeSet;
+
+use browsy_core::fetch::{normalize_url, CrawlEntry, CrawlOpts, FetchError, Session, SessionConfig};
+use common::Response;
+use url::Url;
+
+/// Serve the site: a home page linking to docs, blog, about and a page
+/// robots.txt disallows; docs pages two and three links deep; and a broken
+/// link. Returns the port.
+fn serve() -> u16 {
+    common::serve(|request| {
+        let path = request.path.split('?').next().unwrap_or("/");
+        let links: Option<&[&str]> = match path {
+            "/" => Some(&[
+                "/docs",
+                "/blog",
+                "/about#team",
+                "/docs?b=2&a=1",
+                "/private/notes",
+                "/gone",
+                "mailto:hi@example.com",
+            ]),
+            "/docs" => Some(&["/docs/intro", "/docs/api", "/"]),
+            "/docs/intro" => Some(&["/docs/intro/deep", "/docs?a=1&b=2"]),
+            "/blog" => Some(&["/blog/post-1", "http://127.0.0.1:PORT/elsewhere"]),
+            "/about" | "/docs/api" | "/docs/intro/deep" | "/blog/post-1" | "/private/notes" | "/elsewhere" => Some(&[]),
+            _ => None,
+        };
+        if path == "/robots.txt" {
+            Response::ok("text/plain", "User-agent: *\nDisallow: /private\n")
+        } else if let Some(links) = links {
+            let anchors: String = links
+                .iter()
+                .map(|href| format!(r#"<li><a href="{}">{href}</a></li>"#, href.replace("PORT", &request.port.to_string())))
+                .collect();
+            Response::html(format!(
+                "<html><head><title>Page {path}</title></head><body><h1>{path}</h1><ul>{anchors}</ul></body></html>"
+            ))
+        } else {
+            Response::empty("404 Not Found")
+        }
+    })
+}
+
+fn session() -> Session {
+    Session::with_config(SessionConfig {
+        fetch_css: false,
+        allow_private_network: true,
+        ..SessionConfig::default()
+    })
+    .unwrap()
+}
+
+/// Visited paths with their query, without the origin.
+fn paths(port: u16, entries: &[CrawlEntry]) -> BTreeSet<String> {
+    let origin = format!("http://localhost:{port}");
+    entries.iter().map(|e| e.url.strip_prefix(&origin).unwrap_or(&e.url).to_string()).collect()
+}
+
+#[test]
+fn test_crawl_visits_each_page_once_within_depth() {
+    let port = serve();
+    let mut session = session();
+    let opts = CrawlOpts { max_depth: 2, ..CrawlOpts::default() };
+    let entries = session.crawl(&format!("http://localhost:{port}/"), opts).unwrap();
+
+    let expected: BTreeSet<String> =
+        ["/", "/docs", "/blog", "/about", "/docs?a=1&b=2", "/gone", "/docs/intro", "/docs/api", "/blog/post-1"]
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+    assert_eq!(paths(port, &entries), expected);
+
+    let home = &entries[0];
+    assert_eq!((home.depth, home.title.as_str()), (0, "Page /"));
+    assert!(home.element_count > 0);
+    assert!(home.outlinks.contains(&format!("http://localhost:{port}/about")), "{:?}", home.outlinks);
+    assert!(home.outlinks.contains(&format!("http://localhost:{port}/docs?a=1&b=2")), "{:?}", home.outlinks);
+    assert!(!home.outlinks.iter().any(|l| l.starts_with("mailto:")));
+
+    let by_path = |path: &str| entries.iter().find(|e| e.url.ends_with(path)).unwrap();
+    assert_eq!(by_path("/docs/intro").depth, 2);
+    assert!(by_path("/gone").error.as_deref().unwrap().contains("404"));
+    // Breadth first: every depth-1 page comes before any depth-2 page
+    let depths: Vec<usize> = entries.iter().map(|e| e.depth).collect();
+    assert!(depths.windows(2).all(|w| w[0] <= w[1]), "{depths:?}");
+}
+
+#[test]
+fn test_crawl_limits() {
+    let port = serve();
+    let start = format!("http://localhost:{port}/");
+    let mut session = session();
+
+    let entries = session.crawl(&start, CrawlOpts { max_depth: 0, ..CrawlOpts::default() }).unwrap();
+    assert_eq!(paths(port, &entries), BTreeSet::from(["/".to_string()]));
+
+    let entries = session.crawl(&start, CrawlOpts { max_pages: 3, ..CrawlOpts::default() }).unwrap();
+    assert_eq!(entries.len(), 3);
+
+    let opts = CrawlOpts {
+        same_host_only: false,
+        respect_robots: false,
+        exclude_patterns: vec!["/docs".to_string()],
+        ..CrawlOpts::default()
+    };
+    let visited = paths(port, &session.crawl(&start, opts).unwrap());
+    assert!(visited.contains("/private/notes"), "{visited:?}");
+    assert!(visited.contains(&format!("http://127.0.0.1:{port}/elsewhere")), "{visited:?}");
+    assert!(!visited.iter().any(|p| p.starts_with("/docs")), "{visited:?}");
+
+    let opts = CrawlOpts { include_patterns: vec!["/blog".to_string()], ..CrawlOpts::default() };
+    let visited = paths(port, &session.crawl(&start, opts).unwrap());
+    assert_eq!(visited, BTreeSet::from(["/".to_string(), "/blog".to_string(), "/blog/post-1".to_string()]));
+
+    // The domain lists still apply, to the start page too
+    session.set_domain_lists(Some(vec!["127.0.0.1".to_string()]), Vec::new()).unwrap();
+    assert!(matches!(session.crawl(&start, CrawlOpts::default()), Err(FetchError::BlockedUrl(_))));
+}
+
+#[test]
+fn test_normalize_url() {
+    let url = Url::parse("https://example.com/a?b=2&a=1#top").unwrap();
+    assert_eq!(normalize_url(&url).as_str(), "https://example.com/a?a=1&b=2");
+    let url = Url::parse("https://example.com/a?#x").unwrap();
+    assert_eq!(normalize_url(&url).as_str(), "https://example.com/a");
+}
+
+/// Serve a home page linking to `LINKS`, each of them an empty page, and
+/// `robots` as robots.txt, or none where it is `None`. Returns the port.
+fn serve_with_robots(robots: Option<&'static str>) -> u16 {
+    common::serve(move |request| match (request.path.as_str(), robots) {
+        ("/robots.txt", Some(robots)) => Response::ok("text/plain", robots),
+        ("/robots.txt", None) => Response::empty("404 Not Found"),
+        ("/", _) => {
+            let anchors: String = LINKS.iter().map(|href| format!(r#"<a href="{href}">{href}</a>"#)).collect();
+            Response::html(format!("<html><body>{anchors}</body></html>"))
+        }
+        _ => Response::html("<html><body><p>Page</p></body></html>"),
+    })
+}
+
+const LINKS: [&str; 5] = ["/docs", "/private/x", "/private/open/x", "/files/a.pdf", "/files/a.pdf?x=1"];
+
+const ROBOTS: &str = "User-agent: *\nDisallow: /private\nAllow: /private/open\nDisallow: /*.pdf$\n\n\
+                      User-agent: browsy\nUser-agent: other\nDisallow: /\n";
+
+#[test]
+fn test_crawl_follows_robots_rules() {
+    let port = serve_with_robots(Some(ROBOTS));
+    let start = format!("http://localhost:{port}/");
+    let mut session = session();
+
+    // The longest matching rule wins, and a `$` pattern leaves a query alone
+    let visited = paths(port, &session.crawl(&start, CrawlOpts::default()).unwrap());
+    let expected: BTreeSet<String> =
+        ["/", "/docs", "/private/open/x", "/files/a.pdf?x=1"].into_iter().map(str::to_string).collect();
+    assert_eq!(visited, expected);
+
+    // A group naming the User-Agent replaces the `*` group
+    session.set_user_agent("browsy/0.1").unwrap();
+    assert!(session.crawl(&start, CrawlOpts::default()).unwrap().is_empty());
+}
+
+#[test]
+fn test_crawl_without_robots_txt_visits_everything() {
+    let port = serve_with_robots(None);
+    let mut session = session();
+    session.set_user_agent("browsy/0.1").unwrap();
+    let entries = session.crawl(&format!("http://localhost:{port}/"), CrawlOpts::default()).unwrap();
+    assert_eq!(entries.len(), 1 + LINKS.len());
+}
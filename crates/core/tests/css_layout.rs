@@ -1,5 +1,6 @@
 //! Tests for CSS parsing, style computation, and layout positioning.
 
+use browsy_core::css::color::Color;
 use browsy_core::output;
 
 #[test]
@@ -463,10 +464,78 @@ fn test_calc_em_units() {
 }
 
 #[test]
-fn test_media_query_max_width() {
+fn test_viewport_height_units() {
+    let html = r#"
+    <html>
+    <head><style>.hero { height: 100vh; }</style></head>
+    <body style="margin: 0;">
+        <section class="hero"><div>Welcome</div></section>
+        <a href="/more">Below the hero</a>
+    </body>
+    </html>
+    "#;
+
+    for height in [1080.0, 812.0] {
+        let dom = browsy_core::parse(html, 375.0, height);
+        let link = dom.find_by_text("Below the hero")[0];
+        assert_eq!(link.b[1], height as i32, "100vh section at viewport height {height}");
+        assert!(dom.above_fold().iter().all(|e| e.id != link.id));
+    }
+}
+
+#[test]
+fn test_viewport_width_units() {
+    let html = r#"
+    <html>
+    <body style="margin: 0;">
+        <div style="width: 50vw; height: 20px;"><button>Half</button></div>
+        <div style="width: 10vmin; height: 20px;"><button>Min</button></div>
+        <div style="width: 10vmax; height: 20px;"><button>Max</button></div>
+        <div style="width: calc(50vw - 40px); height: 20px;"><button>Mixed</button></div>
+    </body>
+    </html>
+    "#;
+
+    let dom = browsy_core::parse(html, 1200.0, 800.0);
+    let width = |text: &str| dom.els.iter().find(|e| e.text.as_deref() == Some(text)).unwrap().b[2];
+    assert_eq!(width("Half"), 600);
+    assert_eq!(width("Min"), 80);
+    assert_eq!(width("Max"), 120);
+    assert_eq!(width("Mixed"), 560);
+}
+
+#[test]
+fn test_calc_unsupported_falls_back_to_dominant_term() {
     let html = r#"
     <html>
     <head><style>
+        .container { width: 1000px; display: flex; flex-direction: column; }
+        .sidebar { width: calc(100% - 2ch); }
+        .clamped { width: calc(min(300px, 50%) + 2ex); }
+        .fixed { width: calc(120px + 3ch); }
+    </style></head>
+    <body style="margin: 0;">
+        <div class="container">
+            <div class="sidebar"><button>Sidebar</button></div>
+            <div class="clamped"><button>Clamped</button></div>
+            <div class="fixed"><button>Fixed</button></div>
+        </div>
+    </body>
+    </html>
+    "#;
+
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+    let width = |text: &str| dom.els.iter().find(|e| e.text.as_deref() == Some(text)).unwrap().b[2];
+    assert_eq!(width("Sidebar"), 1000);
+    assert_eq!(width("Clamped"), 500);
+    assert_eq!(width("Fixed"), 120);
+}
+
+#[test]
+fn test_media_query_max_width() {
+    let html = r#"
+    <html>
+    <head><meta name="viewport" content="width=device-width, initial-scale=1"><style>
         .box { width: 500px; }
         @media (max-width: 768px) {
             .box { width: 300px; }
@@ -493,7 +562,7 @@ fn test_media_query_max_width() {
 fn test_media_query_min_width() {
     let html = r#"
     <html>
-    <head><style>
+    <head><meta name="viewport" content="width=device-width, initial-scale=1"><style>
         .box { width: 200px; }
         @media (min-width: 1024px) {
             .box { width: 600px; }
@@ -520,7 +589,7 @@ fn test_media_query_min_width() {
 fn test_media_query_range() {
     let html = r#"
     <html>
-    <head><style>
+    <head><meta name="viewport" content="width=device-width, initial-scale=1"><style>
         .nav { display: none; }
         @media (min-width: 768px) and (max-width: 1024px) {
             .nav { display: flex; width: 768px; }
@@ -548,6 +617,50 @@ fn test_media_query_range() {
     assert_eq!(tab_nav.hidden, Some(true));
 }
 
+#[test]
+fn test_viewport_meta_layout_width() {
+    let page = |meta: &str| {
+        format!(
+            r#"<html><head>{meta}<style>
+                .content {{ width: 100%; }}
+                @media (max-width: 600px) {{ .desktop-only {{ display: none; }} }}
+            </style></head>
+            <body style="margin: 0;">
+                <div class="content"><input type="search" style="width: 100%;" placeholder="Search"></div>
+                <a class="desktop-only" href="/full">Full site</a>
+            </body></html>"#
+        )
+    };
+    let search = |dom: &browsy_core::output::SpatialDom| dom.els.iter().find(|e| e.tag == "input").unwrap().b[2];
+
+    // Responsive page: laid out at the device width
+    let responsive = page(r#"<meta name="viewport" content="width=device-width, initial-scale=1">"#);
+    let dom = browsy_core::parse(&responsive, 375.0, 812.0);
+    assert_eq!(search(&dom), 375);
+    assert_eq!(dom.layout_vp, None);
+    assert_eq!(dom.find_by_text("Full site")[0].hidden, Some(true));
+
+    // No meta tag: legacy 980px layout, fold height unchanged
+    let legacy = page("");
+    let dom = browsy_core::parse(&legacy, 375.0, 812.0);
+    assert_eq!(search(&dom), 980);
+    assert_eq!(dom.vp, [375.0, 812.0]);
+    assert_eq!(dom.layout_vp, Some([980.0, 812.0]));
+    assert_eq!(dom.layout_size(), [980.0, 812.0]);
+    assert_eq!(dom.find_by_text("Full site")[0].hidden, None);
+    // Size hints are relative to the layout width
+    assert!(browsy_core::output::to_compact_string(&dom).contains("full"));
+
+    // Explicit width
+    let fixed = page(r#"<meta name="viewport" content="width=600">"#);
+    assert_eq!(search(&browsy_core::parse(&fixed, 375.0, 812.0)), 600);
+
+    // Desktop viewports ignore the tag
+    let dom = browsy_core::parse(&legacy, 1280.0, 800.0);
+    assert_eq!(search(&dom), 1280);
+    assert_eq!(dom.layout_vp, None);
+}
+
 #[test]
 fn test_media_query_screen_and_print() {
     let html = r#"
@@ -735,3 +848,299 @@ fn test_css_variables_chain() {
     // Should resolve var chain: --element-width -> --base-size -> 150px
     assert!(button.b[2] <= 150, "button should fit in 150px item, got w={}", button.b[2]);
 }
+
+#[test]
+fn test_css_variables_layout() {
+    let html = r#"
+    <html><head><style>
+        :root { --header-height: 64px; --gutter: 10px; }
+        header { height: var(--header-height); }
+        .grid { display: grid; grid-template-columns: var(--col, 1fr) var(--col, 1fr); gap: var(--gutter); width: 610px; }
+        .grid.sized { --col: 100px; }
+        .card { width: var(--card-width, 240px); }
+        .late { width: var(--late-width); }
+        #panel { --late-width: 320px; }
+    </style></head>
+    <body style="margin: 0;">
+        <header><a href="/">Home</a></header>
+        <div class="grid"><button>A</button><button>B</button></div>
+        <div class="grid sized"><button>C</button><button>D</button></div>
+        <div class="card"><button style="width: 100%;">Card</button></div>
+        <div class="late" id="panel"><button style="width: 100%;">Late</button></div>
+    </body></html>
+    "#;
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+    let button = |text: &str| dom.els.iter().find(|e| e.text.as_deref() == Some(text)).unwrap();
+
+    // Content after the 64px header starts below it
+    assert_eq!(button("A").b[1], 64);
+
+    // Undefined --col falls back to 1fr: two 300px columns with a 10px gap
+    assert_eq!(button("A").b[0], 0);
+    assert_eq!(button("B").b[0], 310);
+
+    // Defined --col is substituted into the grid template
+    assert_eq!(button("C").b[0], 0);
+    assert_eq!(button("D").b[0], 110);
+
+    // Undefined variable with a px fallback
+    assert_eq!(button("Card").b[2], 240);
+
+    // Variables from a more specific rule apply to var() uses in less specific ones
+    assert_eq!(button("Late").b[2], 320);
+}
+
+#[test]
+fn test_css_variables_cycle_is_ignored() {
+    let html = r#"
+    <html><head><style>
+        .box { --a: var(--b); --b: var(--a); width: 200px; }
+        .box { width: var(--a); }
+    </style></head>
+    <body style="margin: 0;"><div class="box"><button style="width: 100%;">Cycle</button></div></body></html>
+    "#;
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+    let button = dom.els.iter().find(|e| e.tag == "button").unwrap();
+    // The unresolvable declaration is dropped, keeping the earlier width
+    assert_eq!(button.b[2], 200);
+}
+
+#[test]
+fn test_overflow_clipping() {
+    let items: String = (1..=30)
+        .map(|i| format!(r#"<a href="/item/{i}" style="display: block; height: 50px;">Item {i}</a>"#))
+        .collect();
+    let html = format!(
+        r#"<html><body>
+        <div style="height: 420px; overflow: auto;">{items}</div>
+        <p>After the panel</p>
+        <div style="height: 100px;"><a href="/spill" style="display: block; height: 300px;">Spills over</a></div>
+        </body></html>"#
+    );
+    let dom = browsy_core::parse(&html, 1920.0, 1080.0);
+    let item = |n: u32| dom.find_by_text(&format!("Item {n}")).into_iter().find(|e| e.tag == "a").unwrap();
+
+    let first = item(1);
+    assert_eq!(first.clipped, None);
+    assert_eq!(first.visible_b, None);
+    let top = first.b[1];
+
+    // Item 9 straddles the bottom edge of the 420px panel
+    let straddling = item(9);
+    assert_eq!(straddling.clipped, Some(true));
+    let visible = straddling.visible_b.expect("partially visible");
+    assert_eq!(visible[1], top + 400);
+    assert_eq!(visible[3], 20);
+    assert!(!straddling.is_fully_clipped());
+
+    // Item 12 would be above the fold, but only by scrolling the panel
+    let hidden_in_panel = item(12);
+    assert!(hidden_in_panel.b[1] < 1080);
+    assert!(hidden_in_panel.is_fully_clipped());
+    assert!(dom.above_fold().iter().all(|e| e.id != hidden_in_panel.id));
+    assert!(dom.above_fold().iter().any(|e| e.id == straddling.id));
+
+    let on_screen: Vec<u32> = dom.on_screen().iter().map(|e| e.id).collect();
+    assert!(on_screen.contains(&first.id));
+    assert!(on_screen.contains(&straddling.id));
+    assert!(!on_screen.contains(&hidden_in_panel.id));
+    assert!(dom.filter_on_screen().get(hidden_in_panel.id).is_none());
+
+    // Content after the panel sits below it and is not clipped
+    let after = dom.find_by_text("After the panel")[0];
+    assert!(after.b[1] >= top + 420);
+    assert_eq!(after.clipped, None);
+
+    // Overflow is visible by default, so nothing clips the spilling link
+    let spill = dom.find_by_text("Spills over")[0];
+    assert_eq!(spill.clipped, None);
+}
+
+#[test]
+fn test_pinned_elements_stay_above_fold() {
+    let paragraphs: String = (1..=40)
+        .map(|i| format!(r#"<p style="height: 200px;">Section {i}</p>"#))
+        .collect();
+    let html = format!(
+        r#"<html><body style="margin: 0;">
+        <nav style="position: fixed; top: 0; left: 0; width: 100%; height: 60px;"><a href="/">Home</a></nav>
+        <div style="position: sticky; top: 0;"><a href="/top">Back to top</a></div>
+        <main style="padding-top: 60px;">{paragraphs}</main>
+        </body></html>"#
+    );
+    let mut dom = browsy_core::parse(&html, 1920.0, 1080.0);
+    let home = dom.find_by_text("Home").into_iter().find(|e| e.tag == "a").unwrap().id;
+    let back = dom.find_by_text("Back to top").into_iter().find(|e| e.tag == "a").unwrap().id;
+    let first = dom.find_by_text("Section 1")[0].id;
+    let last = dom.find_by_text("Section 40")[0].id;
+
+    assert_eq!(dom.get(home).unwrap().pinned, Some(true));
+    assert_eq!(dom.get(back).unwrap().pinned, Some(true));
+    assert_eq!(dom.get(first).unwrap().pinned, None);
+    assert!(browsy_core::output::to_compact_string(&dom).contains(&format!("[^{home}:a")));
+
+    let bottom = dom.get(last).unwrap().b[1] as f32 - 500.0;
+    for scroll_y in [0.0, 2500.0, bottom] {
+        dom.scroll = [0.0, scroll_y];
+        let above: Vec<u32> = dom.above_fold().iter().map(|e| e.id).collect();
+        assert!(above.contains(&home), "nav missing at scroll {scroll_y}");
+        assert!(above.contains(&back), "sticky link missing at scroll {scroll_y}");
+        assert!(dom.filter_above_fold().get(home).is_some());
+        assert!(dom.below_fold().iter().all(|e| e.id != home));
+    }
+
+    // Scrolled to the bottom, the first section is gone and the last is in view
+    let above: Vec<u32> = dom.above_fold().iter().map(|e| e.id).collect();
+    assert!(!above.contains(&first));
+    assert!(above.contains(&last));
+}
+
+#[test]
+fn test_selector_matching() {
+    use browsy_core::css::selector::{matches_element, parse_stylesheet, Ancestor};
+    use std::collections::HashMap;
+
+    struct El {
+        tag: &'static str,
+        attrs: HashMap<String, String>,
+    }
+    fn el(tag: &'static str, attrs: &[(&str, &str)]) -> El {
+        let attrs = attrs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        El { tag, attrs }
+    }
+    fn classes(el: &El) -> Vec<String> {
+        el.attrs.get("class").map(|c| c.split_whitespace().map(String::from).collect()).unwrap_or_default()
+    }
+    // `path` runs from the root to the element being matched
+    fn matches(selector: &str, path: &[El]) -> bool {
+        let rules = parse_stylesheet(&format!("{selector} {{ color: red; }}"), 1920.0, 1080.0);
+        let Some(rule) = rules.first() else { return false };
+        let (target, parents) = path.split_last().unwrap();
+        let ancestors: Vec<Ancestor> = parents
+            .iter()
+            .map(|a| Ancestor {
+                tag: a.tag,
+                classes: classes(a),
+                id: a.attrs.get("id").map(String::as_str),
+                attrs: &a.attrs,
+            })
+            .collect();
+        let id = target.attrs.get("id").map(String::as_str);
+        matches_element(&rule.selectors[0], target.tag, &classes(target), id, &target.attrs, &ancestors)
+    }
+
+    let menu_link = || {
+        vec![
+            el("nav", &[("aria-label", "Main")]),
+            el("ul", &[("class", "menu")]),
+            el("li", &[("class", "item open")]),
+            el("a", &[("href", "/docs/guide.pdf"), ("class", "promo")]),
+        ]
+    };
+    let cases: &[(&str, bool)] = &[
+        // Descendant and child combinators
+        ("nav a", true),
+        ("nav ul li a", true),
+        ("nav > ul > li > a", true),
+        ("nav > a", false),
+        ("ul > a", false),
+        (".menu > li a", true),
+        (".menu > .item > a.promo", true),
+        ("nav > li a", false),
+        ("section a", false),
+        // A nearer match that fails further left must not hide a farther one
+        ("nav > * a", true),
+        // Attribute selectors on the element and on ancestors
+        ("a[href]", true),
+        ("a[href=\"/docs/guide.pdf\"]", true),
+        ("a[href='/docs']", false),
+        ("a[href^=\"/docs\"]", true),
+        ("a[href^=\"/blog\"]", false),
+        ("a[href$=\".pdf\"]", true),
+        ("a[href$=\".html\"]", false),
+        ("a[href*=\"guide\"]", true),
+        ("a[href*=\"manual\"]", false),
+        ("[aria-label=\"Main\"] a", true),
+        ("[aria-label=\"Footer\"] a", false),
+        ("li[class~=\"open\"] > a", true),
+        // :not() with simple inner selectors
+        ("a:not(.external)", true),
+        ("a:not(.promo)", false),
+        ("a:not([href$=\".pdf\"])", false),
+        ("a:not(.external, span)", true),
+        ("li:not(.open) a", false),
+        // Dynamic pseudo-classes and pseudo-elements never match a static render
+        ("li:hover > a", false),
+        ("a:focus", false),
+        ("a::before", false),
+        ("a:first-child", true),
+    ];
+    for (selector, expected) in cases {
+        assert_eq!(matches(selector, &menu_link()), *expected, "selector {selector:?}");
+    }
+
+    // Complex selectors inside :not() invalidate the rule
+    assert!(parse_stylesheet(":not(nav a) { display: none; }", 1920.0, 1080.0).is_empty());
+}
+
+#[test]
+fn test_selector_specificity() {
+    use browsy_core::css::selector::parse_stylesheet;
+
+    let spec = |selector: &str| parse_stylesheet(&format!("{selector} {{}}"), 1920.0, 1080.0)[0].specificity;
+    assert_eq!(spec("nav ul li a"), 4);
+    assert_eq!(spec(".menu > li a"), 12);
+    assert_eq!(spec("a[href^=\"/\"]"), 11);
+    // :not() counts as its argument
+    assert_eq!(spec("a:not(.promo)"), 11);
+    assert_eq!(spec("a:not(#main)"), 101);
+    assert_eq!(spec("a:not(span, .x)"), 11);
+
+    // More specific rules win regardless of order
+    let html = r#"<html><head><style>
+        nav ul li a { display: none; }
+        nav a:not(.hidden) { display: block; }
+    </style></head><body><nav><ul><li><a href="/">Home</a></li></ul></nav></body></html>"#;
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+    assert_ne!(dom.find_by_text("Home")[0].hidden, Some(true));
+}
+
+#[test]
+fn test_combinator_rules_hide_menus() {
+    let path = format!("{}/tests/fixtures/mega_menu.html", env!("CARGO_MANIFEST_DIR"));
+    let html = std::fs::read_to_string(path).unwrap();
+    let dom = browsy_core::parse(&html, 1920.0, 1080.0);
+
+    let visible_links: Vec<&str> = dom
+        .els
+        .iter()
+        .filter(|e| e.tag == "a" && e.hidden != Some(true))
+        .filter_map(|e| e.text.as_deref())
+        .collect();
+    assert_eq!(visible_links, ["Shop", "Help", "Sale", "Privacy"]);
+    assert!(dom.find_by_text("Shipping details").iter().any(|e| e.hidden != Some(true)));
+}
+
+#[test]
+fn test_parse_color_forms() {
+    let red = Color { r: 255, g: 0, b: 0, a: 1.0 };
+    let forms = ["red", "#f00", "#FF0000", "rgb(255, 0, 0)", "rgb(100% 0% 0%)", "hsl(0, 100%, 50%)"];
+    for value in forms.into_iter().chain(["hsl(360deg 100% 50%)"]) {
+        assert_eq!(Color::parse(value), Some(red), "{value}");
+    }
+    assert_eq!(Color::parse("rgba(0, 0, 0, .5)").map(|c| c.a), Some(0.5));
+    assert_eq!(Color::parse("rgb(0 0 0 / 25%)").map(|c| c.a), Some(0.25));
+    assert_eq!(Color::parse("#00000080").map(|c| c.a), Some(128.0 / 255.0));
+    assert_eq!(Color::parse("transparent").map(|c| c.a), Some(0.0));
+    for value in ["inherit", "currentColor", "#12", "rgb(1, 2)", "var(--red)"] {
+        assert_eq!(Color::parse(value), None, "{value}");
+    }
+}
+
+#[test]
+fn test_hsl_of_common_colors() {
+    let (h, s, l) = Color::parse("#28a745").unwrap().to_hsl();
+    assert!((130.0..140.0).contains(&h) && s > 0.5 && (0.35..0.45).contains(&l), "{h} {s} {l}");
+    let (_, s, l) = Color::parse("#6c757d").unwrap().to_hsl();
+    assert!(s < 0.15 && (0.4..0.5).contains(&l), "{s} {l}");
+}
@@ -0,0 +1,118 @@
+//! Page type, blocked info and suggested actions come out the same whether
+//! the detectors run on rayon's pool or one after another, and the same as
+//! the golden detections of the corpus and fixtures.
+
+use browsy_core::output::{self, OutputOptions, SpatialDom};
+use serde_json::{json, Value};
+
+/// What the detectors found on every page in `corpus/snapshots` and
+/// `fixtures`, keyed by path. First captured from the detectors as they were
+/// before they shared one feature index.
+const GOLDEN: &str = include_str!("fixtures/detections.json");
+
+fn parse(html: &str, parallel_min_elements: usize) -> SpatialDom {
+    let dom_tree = browsy_core::dom::parse_html(html);
+    let styled = browsy_core::css::compute_styles_with_viewport(&dom_tree, 1920.0, 1080.0);
+    let laid_out = browsy_core::layout::compute_layout(&styled, 1920.0, 1080.0);
+    let options = OutputOptions { parallel_min_elements, ..Default::default() };
+    output::generate_spatial_dom_with_options(&laid_out, 1920.0, 1080.0, &options)
+}
+
+/// Every `.html` file directly inside `dir`, sorted by name.
+fn html_files(dir: &str) -> Vec<std::path::PathBuf> {
+    let dir = format!("{}/tests/{}", env!("CARGO_MANIFEST_DIR"), dir);
+    let mut files: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("Failed to read {dir}: {e}"))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "html"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn assert_same_detections(files: &[std::path::PathBuf]) {
+    assert!(!files.is_empty());
+    for path in files {
+        let html = std::fs::read_to_string(path).unwrap();
+        let parallel = parse(&html, 0);
+        let sequential = parse(&html, usize::MAX);
+        assert_eq!(parallel.page_type, sequential.page_type, "{}", path.display());
+        assert_eq!(
+            serde_json::to_string(&parallel.blocked).unwrap(),
+            serde_json::to_string(&sequential.blocked).unwrap(),
+            "{}",
+            path.display()
+        );
+        assert_eq!(
+            serde_json::to_string(&parallel.suggested_actions).unwrap(),
+            serde_json::to_string(&sequential.suggested_actions).unwrap(),
+            "{}",
+            path.display()
+        );
+    }
+}
+
+fn detections(dom: &SpatialDom) -> Value {
+    json!({
+        "page_type": dom.page_type,
+        "blocked": dom.blocked,
+        "suggested_actions": dom.suggested_actions,
+    })
+}
+
+#[test]
+fn test_detections_match_golden() {
+    let golden: serde_json::Map<String, Value> = serde_json::from_str(GOLDEN).unwrap();
+    for dir in ["corpus/snapshots", "fixtures"] {
+        for path in html_files(dir) {
+            let name = format!("{dir}/{}", path.file_name().unwrap().to_string_lossy());
+            let expected = golden.get(&name).unwrap_or_else(|| panic!("{name} has no golden detections"));
+            let html = std::fs::read_to_string(&path).unwrap();
+            for parallel_min_elements in [0, usize::MAX] {
+                assert_eq!(&detections(&parse(&html, parallel_min_elements)), expected, "{name}");
+            }
+        }
+    }
+}
+
+#[test]
+fn test_corpus_detections_match_sequential() {
+    assert_same_detections(&html_files("corpus/snapshots"));
+}
+
+#[test]
+fn test_fixture_detections_match_sequential() {
+    assert_same_detections(&html_files("fixtures"));
+}
+
+#[test]
+fn test_parallel_detection_keeps_action_order() {
+    // A login form behind a cookie banner, with a search box and pagination
+    let html = r#"
+    <html><head><title>Sign in</title></head><body>
+        <div><p>We use cookies to improve your experience on this website.</p>
+            <button>Accept all</button><button>Reject all</button></div>
+        <input type="search" name="q" placeholder="Search"><button>Go</button>
+        <form>
+            <input type="text" name="username">
+            <input type="password" name="password">
+            <button type="submit">Sign in</button>
+        </form>
+        <a href="/page/2" rel="next">Next</a>
+    </body></html>
+    "#;
+    let parallel = parse(html, 0);
+    let kinds: Vec<String> = parallel
+        .suggested_actions
+        .iter()
+        .map(|a| serde_json::to_value(a).unwrap()["action"].as_str().unwrap_or_default().to_string())
+        .collect();
+    let sequential = parse(html, usize::MAX);
+    assert_eq!(
+        serde_json::to_string(&parallel.suggested_actions).unwrap(),
+        serde_json::to_string(&sequential.suggested_actions).unwrap()
+    );
+    let login = kinds.iter().position(|k| k == "Login").expect("login action");
+    let cookies = kinds.iter().position(|k| k == "CookieConsent").expect("cookie action");
+    assert!(login < cookies, "{kinds:?}");
+}
@@ -0,0 +1,137 @@
+//! `SessionConfig::allowed_domains` and `blocked_domains`. Pages come from a
+//! local server; off-limits hosts are never contacted, so they needn't exist.
+
+#![cfg(feature = "fetch")]
+
+mod common;
+
+use browsy_core::fetch::{FetchError, RecordingMode, Session, SessionConfig};
+use common::Response;
+
+const START_PAGE: &str = r#"<html><head><title>Start</title></head><body>
+    <a href="/redirect">Continue</a>
+    <a href="//tracker.blocked.test/pixel">Share</a>
+    <a href="/next">Next page</a>
+</body></html>"#;
+
+/// Serve `START_PAGE` at every path except `/redirect`, which redirects to
+/// `http://www.blocked.test/landing`. Returns the base URL.
+fn serve() -> String {
+    let port = common::serve(|request| {
+        if request.path == "/redirect" {
+            Response::redirect("http://www.blocked.test/landing")
+        } else {
+            Response::html(START_PAGE)
+        }
+    });
+    format!("http://127.0.0.1:{port}")
+}
+
+fn config(allowed: Option<&[&str]>, blocked: &[&str]) -> SessionConfig {
+    SessionConfig {
+        fetch_css: false,
+        allow_private_network: true,
+        allowed_domains: allowed.map(|list| list.iter().map(|d| d.to_string()).collect()),
+        blocked_domains: blocked.iter().map(|d| d.to_string()).collect(),
+        ..SessionConfig::default()
+    }
+}
+
+fn session(allowed: Option<&[&str]>, blocked: &[&str]) -> Session {
+    Session::with_config(config(allowed, blocked)).unwrap()
+}
+
+fn link(session: &Session, text: &str) -> u32 {
+    session.dom().unwrap().find_by_text(text)[0].id
+}
+
+#[test]
+fn test_goto_blocked_domain() {
+    let mut session = session(None, &["blocked.test"]);
+    match session.goto("https://shop.blocked.test/") {
+        Err(FetchError::BlockedUrl(url)) => assert_eq!(url, "https://shop.blocked.test/"),
+        other => panic!("expected BlockedUrl, got {:?}", other.map(|d| d.title)),
+    }
+}
+
+#[test]
+fn test_redirect_into_blocked_domain() {
+    let base = serve();
+    let mut session = session(None, &["blocked.test"]);
+    session.goto(&base).unwrap();
+
+    let continue_link = link(&session, "Continue");
+    match session.click(continue_link) {
+        Err(FetchError::BlockedUrl(url)) => assert_eq!(url, "http://www.blocked.test/landing"),
+        other => panic!("expected BlockedUrl, got {:?}", other.map(|d| d.title)),
+    }
+    // Still on the start page
+    assert_eq!(session.dom().unwrap().title, "Start");
+}
+
+#[test]
+fn test_relative_link_onto_blocked_host() {
+    let base = serve();
+    let mut session = session(None, &["blocked.test"]);
+    session.goto(&base).unwrap();
+
+    let share = link(&session, "Share");
+    match session.click(share) {
+        Err(FetchError::BlockedUrl(url)) => assert_eq!(url, "http://tracker.blocked.test/pixel"),
+        other => panic!("expected BlockedUrl, got {:?}", other.map(|d| d.title)),
+    }
+}
+
+#[test]
+fn test_allowed_domains_confine_navigation() {
+    let base = serve();
+    let mut session = session(Some(&["127.0.0.1"]), &[]);
+    session.goto(&base).unwrap();
+
+    // Same host: fine
+    let next = link(&session, "Next page");
+    assert_eq!(session.click(next).unwrap().title, "Start");
+    // Redirect off the allowed host
+    let continue_link = link(&session, "Continue");
+    assert!(matches!(session.click(continue_link), Err(FetchError::BlockedUrl(_))));
+
+    // New lists apply from the next navigation on
+    session.set_domain_lists(None, vec!["127.0.0.1".to_string()]).unwrap();
+    assert!(matches!(session.goto(&base), Err(FetchError::BlockedUrl(_))));
+}
+
+/// Whether a session with these lists lets `url` through. It replays from an
+/// empty fixture directory, so nothing is fetched: a URL let through fails
+/// for want of a recording instead of with `BlockedUrl`.
+fn lets_through(url: &str, allowed: Option<&[&str]>, blocked: &[&str]) -> bool {
+    let dir = std::env::temp_dir().join(format!("browsy-domains-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let mut session = Session::with_config(SessionConfig {
+        allow_non_http: true,
+        recording: Some(RecordingMode::Replay(dir)),
+        ..config(allowed, blocked)
+    })
+    .unwrap();
+    match session.goto(url) {
+        Err(FetchError::BlockedUrl(_)) => false,
+        Err(FetchError::ActionError(e)) if e.starts_with("No recorded response") => true,
+        other => panic!("{url}: expected BlockedUrl or a missing recording, got {:?}", other.map(|d| d.title)),
+    }
+}
+
+#[test]
+fn test_domain_lists_match_subdomains() {
+    assert!(lets_through("https://app.mycorp.com/", Some(&["mycorp.com"]), &[]));
+    assert!(lets_through("https://MyCorp.com./", Some(&["mycorp.com"]), &[]));
+    assert!(!lets_through("https://notmycorp.com/", Some(&["mycorp.com"]), &[]));
+    assert!(!lets_through("https://mycorp.com.evil.net/", Some(&["mycorp.com"]), &[]));
+    assert!(lets_through("https://app.mycorp.com/", Some(&["*.mycorp.com"]), &[]));
+    assert!(!lets_through("https://mycorp.com/", Some(&["*.mycorp.com"]), &[]));
+
+    assert!(!lets_through("https://m.facebook.com/", None, &["facebook.com"]));
+    assert!(lets_through("https://example.com/", None, &["facebook.com"]));
+    // Blocking wins over allowing
+    assert!(!lets_through("https://social.mycorp.com/", Some(&["mycorp.com"]), &["social.mycorp.com"]));
+    // A URL with no host matches no allowed domain
+    assert!(!lets_through("data:text/html,hi", Some(&["mycorp.com"]), &[]));
+}
@@ -0,0 +1,152 @@
+//! `Session::discover_feeds` and `Session::fetch_sitemap`, against a local
+//! server with a robots.txt, a sitemap index that nests another index, and
+//! the sitemaps in tests/fixtures/sitemaps.
+
+#![cfg(feature = "fetch")]
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use browsy_core::fetch::{parse_sitemap, FeedKind, FetchError, Session, SessionConfig, Sitemap, SitemapEntry};
+use common::Response;
+
+fn fixture(name: &str) -> String {
+    let path = format!("{}/tests/fixtures/sitemaps/{name}", env!("CARGO_MANIFEST_DIR"));
+    std::fs::read_to_string(path).unwrap()
+}
+
+const PAGE: &str = r#"<html><head><title>Blog</title>
+<link rel="alternate" type="application/rss+xml" title="Posts" href="/feed.rss">
+<link rel="alternate" type="application/atom+xml" href="https://example.com/atom.xml">
+<link rel="alternate" type="application/rss+xml" href="/feed.rss">
+<link rel="alternate" hreflang="de" href="/de/">
+<link rel="stylesheet" type="text/css" href="/site.css">
+</head><body><h1>Blog</h1></body></html>"#;
+
+/// Serve the blog page, its robots.txt and sitemaps. Returns the port and
+/// how many times robots.txt was asked for.
+fn serve() -> (u16, Arc<AtomicUsize>) {
+    let robots_requests = Arc::new(AtomicUsize::new(0));
+    let counter = robots_requests.clone();
+    let port = common::serve(move |request| {
+        let port = request.port;
+        match request.path.as_str() {
+            "/" => Response::html(PAGE),
+            "/robots.txt" => {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Response::ok(
+                    "text/plain",
+                    format!(
+                        "User-agent: *\nDisallow: /admin\n\nSitemap: http://localhost:{port}/sitemaps/sitemap_index.xml\nsitemap:/sitemaps/posts.xml\n"
+                    ),
+                )
+            }
+            "/sitemap.xml" => Response::ok("application/xml", fixture("pages.xml")),
+            "/not-a-sitemap.xml" => Response::ok("application/xml", "<rss><channel/></rss>"),
+            p => match p.strip_prefix("/sitemaps/") {
+                Some(name) => Response::ok("application/xml", fixture(name)),
+                None => Response::empty("404 Not Found"),
+            },
+        }
+    });
+    (port, robots_requests)
+}
+
+fn session() -> Session {
+    Session::with_config(SessionConfig {
+        fetch_css: false,
+        allow_private_network: true,
+        ..SessionConfig::default()
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_parse_sitemap() {
+    let Sitemap::Urls(urls) = parse_sitemap(&fixture("posts.xml")).unwrap() else {
+        panic!("expected a urlset");
+    };
+    assert_eq!(
+        urls,
+        vec![
+            SitemapEntry {
+                loc: "https://example.com/posts/first?a=1&b=2".to_string(),
+                lastmod: Some("2024-04-30T12:00:00+00:00".to_string()),
+            },
+            SitemapEntry { loc: "https://example.com/posts/second".to_string(), lastmod: None },
+        ]
+    );
+
+    let Sitemap::Index(children) = parse_sitemap(&fixture("sitemap_index.xml")).unwrap() else {
+        panic!("expected a sitemap index");
+    };
+    assert_eq!(children.len(), 3);
+    assert_eq!(children[0].lastmod.as_deref(), Some("2024-05-01"));
+
+    assert!(parse_sitemap("<rss><channel/></rss>").unwrap_err().contains("<rss>"));
+    assert!(parse_sitemap("<urlset><url><loc>a</url>").is_err());
+}
+
+#[test]
+fn test_discover_feeds() {
+    let (port, robots_requests) = serve();
+    let mut session = session();
+    assert!(matches!(session.discover_feeds(), Err(FetchError::NoPageLoaded)));
+
+    session.goto(&format!("http://localhost:{port}/")).unwrap();
+    let found = session.discover_feeds().unwrap();
+    let feeds: Vec<_> = found.feeds.iter().map(|f| (f.href.as_str(), f.kind, f.title.as_deref())).collect();
+    let feed_url = format!("http://localhost:{port}/feed.rss");
+    assert_eq!(
+        feeds,
+        vec![
+            (feed_url.as_str(), FeedKind::Rss, Some("Posts")),
+            ("https://example.com/atom.xml", FeedKind::Atom, None),
+        ]
+    );
+    assert_eq!(
+        found.sitemaps,
+        vec![
+            format!("http://localhost:{port}/sitemaps/sitemap_index.xml"),
+            format!("http://localhost:{port}/sitemaps/posts.xml"),
+            format!("http://localhost:{port}/sitemap.xml"),
+        ]
+    );
+
+    // robots.txt is read once per origin
+    assert_eq!(session.discover_feeds().unwrap(), found);
+    assert_eq!(robots_requests.load(Ordering::SeqCst), 1);
+    // and the current page is left alone
+    assert_eq!(session.dom().unwrap().title, "Blog");
+}
+
+#[test]
+fn test_fetch_sitemap_follows_nested_indexes() {
+    let (port, _) = serve();
+    let mut session = session();
+
+    let entries = session.fetch_sitemap(&format!("http://localhost:{port}/sitemaps/sitemap_index.xml")).unwrap();
+    let locs: Vec<&str> = entries.iter().map(|e| e.loc.as_str()).collect();
+    // posts.xml is listed twice but read once
+    assert_eq!(
+        locs,
+        vec![
+            "https://example.com/posts/first?a=1&b=2",
+            "https://example.com/posts/second",
+            "https://example.com/about",
+        ]
+    );
+    assert_eq!(entries[2].lastmod.as_deref(), Some("2023-11-02"));
+    assert!(session.dom().is_none());
+
+    match session.fetch_sitemap(&format!("http://localhost:{port}/not-a-sitemap.xml")) {
+        Err(FetchError::ActionError(msg)) => assert!(msg.contains("not a sitemap"), "{msg}"),
+        other => panic!("expected a parse error, got {other:?}"),
+    }
+    assert!(matches!(
+        session.fetch_sitemap(&format!("http://localhost:{port}/missing.xml")),
+        Err(FetchError::HttpError(404))
+    ));
+}
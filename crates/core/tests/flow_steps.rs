@@ -0,0 +1,261 @@
+//! Scripted flows: each step type run against a session, and whole flows
+//! stopping at their first failure.
+
+#![cfg(feature = "fetch")]
+
+mod common;
+
+use std::path::PathBuf;
+
+use browsy_core::fetch::{
+    Assertion, Extraction, FieldTarget, Flow, Session, SessionConfig, Step, StepError, Target,
+};
+use browsy_core::output::PageType;
+use common::Response;
+
+const LOGIN_PAGE: &str = r#"<html><head><title>Sign in</title></head><body>
+    <form action="/dashboard" method="get">
+        <label for="email">Email</label>
+        <input type="email" id="email" name="email">
+        <label for="password">Password</label>
+        <input type="password" id="password" name="password">
+        <select name="plan"><option value="free">Free</option><option value="pro">Pro</option></select>
+        <input type="checkbox" id="remember" name="remember">
+        <label for="remember">Remember me</label>
+        <button type="submit">Sign in</button>
+    </form>
+    <a href="/reset">Forgot your password? Sign in with a link instead</a>
+</body></html>"#;
+
+const DASHBOARD_PAGE: &str = r#"<html><head><title>Dashboard - Acme</title></head><body>
+    <h1>Orders</h1>
+    <table>
+        <tr><th>Order</th><th>Total</th></tr>
+        <tr><td>A-100</td><td>$20</td></tr>
+    </table>
+    <a href="/orders/A-100">View order A-100</a>
+    <a href="/settings">Settings</a>
+</body></html>"#;
+
+/// Serve the dashboard at `/dashboard` (whatever the query) and the sign-in
+/// page anywhere else. Returns the base URL.
+fn serve() -> String {
+    let port = common::serve(|request| {
+        Response::html(if request.path.starts_with("/dashboard") { DASHBOARD_PAGE } else { LOGIN_PAGE })
+    });
+    format!("http://127.0.0.1:{port}")
+}
+
+fn session() -> Session {
+    Session::with_config(SessionConfig { fetch_css: false, allow_private_network: true, ..SessionConfig::default() })
+        .unwrap()
+}
+
+fn session_on(html: &str) -> Session {
+    let mut session = session();
+    session.load_html(html, "https://example.com/").unwrap();
+    session
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("browsy-flow-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn text(s: &str) -> Target {
+    Target { text: Some(s.to_string()), ..Target::default() }
+}
+
+fn label(s: &str) -> FieldTarget {
+    FieldTarget { label: Some(s.to_string()), ..FieldTarget::default() }
+}
+
+#[test]
+fn test_flow_reads_every_step_type_from_json() {
+    let flow = Flow::from_json(
+        r#"{ "name": "sign in", "steps": [
+            { "goto": "https://example.com/login" },
+            { "type": { "label": "Email", "text": "ada@example.com" } },
+            { "select": { "name": "plan", "value": "pro" } },
+            { "check": { "id": 7 } },
+            { "click": { "text": "Sign in", "role": "button" } },
+            { "assert": { "page_type": "Dashboard", "title_contains": "Acme" } },
+            { "extract": { "tables": "out/tables.json" } },
+            { "snapshot": "snapshots" }
+        ] }"#,
+    )
+    .unwrap();
+    assert_eq!(flow.name.as_deref(), Some("sign in"));
+    assert_eq!(
+        flow.steps,
+        [
+            Step::Goto("https://example.com/login".into()),
+            Step::Type { field: label("Email"), text: "ada@example.com".into() },
+            Step::Select {
+                field: FieldTarget { name: Some("plan".into()), ..FieldTarget::default() },
+                value: "pro".into(),
+            },
+            Step::Check(FieldTarget { id: Some(7), ..FieldTarget::default() }),
+            Step::Click(Target { role: Some("button".into()), ..text("Sign in") }),
+            Step::Assert(Assertion {
+                page_type: Some(PageType::Dashboard),
+                title_contains: Some("Acme".into()),
+                ..Assertion::default()
+            }),
+            Step::Extract(Extraction { tables: Some("out/tables.json".into()), links: None }),
+            Step::Snapshot("snapshots".into()),
+        ]
+    );
+    assert_eq!(flow.steps[1].to_string(), "type into label=\"Email\"", "typed text stays out of reports");
+
+    // Typos in a target are caught rather than matching anything
+    let err = Flow::from_json(r#"{ "steps": [{ "click": { "txt": "Sign in" } }] }"#).unwrap_err();
+    assert!(err.to_string().contains("unknown field `txt`"), "{err}");
+}
+
+#[test]
+fn test_flow_rejects_steps_with_nothing_to_match() {
+    let rejected = |step: &str| {
+        let json = format!(r#"{{ "steps": [{{ "goto": "https://example.com" }}, {step}] }}"#);
+        Flow::from_json(&json).unwrap_err().to_string()
+    };
+    // Field targets are flattened into their step, so a typo leaves them empty
+    let err = rejected(r#"{ "type": { "lable": "Email", "text": "ada@example.com" } }"#);
+    assert!(err.contains("step 2 (type) gives nothing to match"), "{err}");
+    let err = rejected(r#"{ "select": { "value": "pro" } }"#);
+    assert!(err.contains("step 2 (select)"), "{err}");
+    let err = rejected(r#"{ "check": {} }"#);
+    assert!(err.contains("step 2 (check)"), "{err}");
+    let err = rejected(r#"{ "click": {} }"#);
+    assert!(err.contains("step 2 (click)"), "{err}");
+    // An assert with no checks, or an empty target to look for, would always pass
+    let err = rejected(r#"{ "assert": {} }"#);
+    assert!(err.contains("step 2 (assert)"), "{err}");
+    let err = rejected(r#"{ "assert": { "title_contains": "Acme", "element_exists": {} } }"#);
+    assert!(err.contains("step 2 (assert)"), "{err}");
+}
+
+#[test]
+fn test_type_select_and_check_find_fields() {
+    let mut session = session_on(LOGIN_PAGE);
+    Step::Type { field: label("email"), text: "ada@example.com".into() }.run(&mut session).unwrap();
+    let plan = FieldTarget { name: Some("plan".into()), ..FieldTarget::default() };
+    Step::Select { field: plan, value: "pro".into() }.run(&mut session).unwrap();
+    Step::Check(label("Remember")).run(&mut session).unwrap();
+
+    let dom = session.dom().unwrap();
+    let by_name = |name: &str| dom.els.iter().find(|e| e.name.as_deref() == Some(name)).unwrap();
+    assert_eq!(by_name("email").val.as_deref(), Some("ada@example.com"));
+    assert_eq!(by_name("plan").val.as_deref(), Some("pro"));
+    assert_eq!(by_name("remember").checked, Some(true));
+
+    let err = Step::Type { field: label("Phone"), text: "555".into() }.run(&mut session).unwrap_err();
+    assert_eq!(err.to_string(), "no element matches label=\"Phone\"");
+    // A field of the wrong kind is the session's error
+    let err = Step::Check(label("Email")).run(&mut session).unwrap_err();
+    assert!(matches!(err, StepError::Fetch(_)), "{err:?}");
+}
+
+#[test]
+fn test_goto_and_click_drive_the_session() {
+    let base = serve();
+    let mut session = session();
+    Step::Goto(format!("{base}/login")).run(&mut session).unwrap();
+    Step::Type { field: label("Email"), text: "ada@example.com".into() }.run(&mut session).unwrap();
+
+    // The link mentions "Sign in" too, but the button's whole text matches
+    Step::Click(text("sign in")).run(&mut session).unwrap();
+    let url = session.url().unwrap().to_string();
+    assert!(url.starts_with(&format!("{base}/dashboard?")), "{url}");
+    assert!(url.contains("email=ada%40example.com"), "{url}");
+
+    let err = Step::Click(text("Delete account")).run(&mut session).unwrap_err();
+    assert!(matches!(&err, StepError::NotFound(target) if target == "text=\"Delete account\""), "{err:?}");
+}
+
+#[test]
+fn test_assert_reports_what_the_page_had() {
+    let mut session = session_on(LOGIN_PAGE);
+    let check = |assertion: Assertion| assertion.check(session.dom_ref().unwrap()).map_err(|e| e.to_string());
+
+    let passing = Assertion {
+        page_type: Some(PageType::Login),
+        title_contains: Some("Sign".into()),
+        element_exists: Some(Target { role: Some("button".into()), ..text("Sign in") }),
+        text_contains: Some("forgot your PASSWORD".into()),
+    };
+    assert_eq!(check(passing), Ok(()));
+    assert_eq!(
+        check(Assertion { page_type: Some(PageType::Dashboard), ..Assertion::default() }),
+        Err("page_type: expected Dashboard, got Login".to_string())
+    );
+    assert_eq!(
+        check(Assertion { title_contains: Some("Dashboard".into()), ..Assertion::default() }),
+        Err("title_contains: expected \"Dashboard\", got \"Sign in\"".to_string())
+    );
+    assert_eq!(
+        check(Assertion { element_exists: Some(text("Sign out")), ..Assertion::default() }),
+        Err("element_exists: expected text=\"Sign out\", got no such element".to_string())
+    );
+    assert_eq!(
+        check(Assertion { text_contains: Some("Welcome back".into()), ..Assertion::default() }),
+        Err("text_contains: expected \"Welcome back\", got no element with that text".to_string())
+    );
+
+    session.reset();
+    let err = Step::Assert(Assertion::default()).run(&mut session).unwrap_err();
+    assert!(matches!(err, StepError::Fetch(_)), "no page loaded: {err:?}");
+}
+
+#[test]
+fn test_extract_writes_tables_and_links() {
+    let dir = temp_dir("extract");
+    let mut session = session_on(DASHBOARD_PAGE);
+    let extraction = Extraction { tables: Some(dir.join("data/tables.json")), links: Some(dir.join("links.json")) };
+    Step::Extract(extraction).run(&mut session).unwrap();
+
+    let read = |name: &str| -> serde_json::Value {
+        serde_json::from_str(&std::fs::read_to_string(dir.join(name)).unwrap()).unwrap()
+    };
+    let tables = read("data/tables.json");
+    assert_eq!(tables[0]["headers"], serde_json::json!(["Order", "Total"]));
+    assert_eq!(tables[0]["rows"], serde_json::json!([["A-100", "$20"]]));
+    let links = read("links.json");
+    let hrefs: Vec<&str> = links.as_array().unwrap().iter().map(|l| l["href"].as_str().unwrap()).collect();
+    assert_eq!(hrefs, ["https://example.com/orders/A-100", "https://example.com/settings"]);
+    assert_eq!(links[1]["text"], "Settings");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_run_stops_at_the_first_failure_and_snapshots_it() {
+    let (snapshots, failures) = (temp_dir("snapshots"), temp_dir("failures"));
+    let mut session = session_on(LOGIN_PAGE);
+    let flow = Flow {
+        name: None,
+        steps: vec![
+            Step::Snapshot(snapshots.clone()),
+            Step::Assert(Assertion { page_type: Some(PageType::Dashboard), ..Assertion::default() }),
+            Step::Type { field: label("Email"), text: "never typed".into() },
+        ],
+    };
+
+    let mut passed = Vec::new();
+    let record = |i: usize, step: &Step| passed.push((i, step.to_string()));
+    let failure = flow.run(&mut session, Some(failures.as_path()), record).unwrap_err();
+    assert_eq!(passed, [(1, format!("snapshot to {}", snapshots.display()))]);
+    assert_eq!(failure.index, 2);
+    assert_eq!(failure.step.to_string(), "assert page_type=Dashboard");
+    assert!(matches!(failure.error, StepError::Assertion { check: "page_type", .. }));
+
+    assert_eq!(std::fs::read_dir(&snapshots).unwrap().count(), 1);
+    let snapshot = failure.snapshot.expect("snapshot of the failure");
+    assert!(snapshot.starts_with(&failures) && snapshot.join("meta.json").exists());
+    assert!(session.dom().unwrap().els.iter().all(|e| e.val.as_deref() != Some("never typed")));
+
+    let flow = Flow { name: None, steps: flow.steps[..1].to_vec() };
+    assert!(flow.run(&mut session, None, |_, _| {}).is_ok());
+    let _ = std::fs::remove_dir_all(&snapshots);
+    let _ = std::fs::remove_dir_all(&failures);
+}
@@ -71,6 +71,297 @@ fn test_email_code_extraction() {
     );
 }
 
+#[test]
+fn test_email_magic_link_extraction() {
+    let dom = parse_fixture("email_magic_link.html");
+    assert_eq!(dom.page_type, PageType::EmailBody);
+
+    let links = dom.find_verification_links();
+    assert_eq!(links.len(), 1, "only the verify button is a magic link, got: {:?}", links);
+    let (id, href) = &links[0];
+    assert_eq!(href, "https://accounts.example.com/email/verify?token=c2lnbmVkLXRva2Vu&uid=42");
+    assert_eq!(dom.get(*id).unwrap().text.as_deref(), Some("Verify email"));
+
+    let hints = dom.find_verification();
+    assert_eq!(hints.codes, vec!["739214".to_string()]);
+    assert_eq!(hints.links, links);
+}
+
+#[test]
+fn test_code_email_has_no_magic_links() {
+    let dom = parse_fixture("email_with_code.html");
+    assert!(dom.find_verification_links().is_empty());
+}
+
+fn load_more_id(dom: &browsy_core::output::SpatialDom) -> Option<u32> {
+    dom.suggested_actions.iter().find_map(|a| match a {
+        SuggestedAction::LoadMore { button_id } => Some(*button_id),
+        _ => None,
+    })
+}
+
+#[test]
+fn test_load_more_button_detected() {
+    let dom = parse_fixture("load_more_button.html");
+    let id = load_more_id(&dom).expect("Should detect the load more button");
+    let button = dom.get(id).unwrap();
+    assert_eq!(button.tag, "button");
+    assert_eq!(button.text.as_deref(), Some("Load more"));
+}
+
+#[test]
+fn test_load_more_link_detected() {
+    let dom = parse_fixture("load_more_link.html");
+    let id = load_more_id(&dom).expect("Should detect the load more link");
+    let link = dom.get(id).unwrap();
+    assert_eq!(link.tag, "a");
+    assert!(link.href.as_deref().unwrap().ends_with("/news?page=2"));
+}
+
+#[test]
+fn test_per_card_read_more_is_not_load_more() {
+    // Every card has "Read more" and "Show more"; none of them loads the next batch
+    let dom = parse_fixture("read_more_cards.html");
+    assert_eq!(load_more_id(&dom), None, "{:?}", dom.suggested_actions);
+}
+
+fn crumb_texts(dom: &browsy_core::output::SpatialDom) -> Vec<String> {
+    dom.breadcrumbs().unwrap_or_default().into_iter().map(|c| c.text).collect()
+}
+
+#[test]
+fn test_breadcrumbs_from_aria_container() {
+    let dom = parse_fixture("breadcrumb_nav.html");
+    let crumbs = dom.breadcrumbs().expect("Should find the breadcrumb nav");
+    assert_eq!(crumb_texts(&dom), vec!["Docs", "Guides", "Installing"]);
+    assert_eq!(crumbs[1].href.as_deref(), Some("/docs/guides"));
+    assert_eq!(dom.get(crumbs[1].id.unwrap()).unwrap().text.as_deref(), Some("Guides"));
+    // The current page is plain text
+    assert_eq!(crumbs[2].href, None);
+}
+
+#[test]
+fn test_breadcrumbs_from_json_ld() {
+    let dom = parse_fixture("breadcrumb_jsonld.html");
+    let crumbs = dom.breadcrumbs().expect("Should read the BreadcrumbList");
+    // Ordered by position, not by array order
+    assert_eq!(crumb_texts(&dom), vec!["Home", "Kitchen", "Kettles"]);
+    assert_eq!(crumbs[1].href.as_deref(), Some("https://shop.example/kitchen"));
+    // Steps are tied to a link on the page when one points at the same URL
+    let link = dom.get(crumbs[1].id.expect("Kitchen is linked on the page")).unwrap();
+    assert_eq!(link.tag, "a");
+    assert_eq!(crumbs[2].id, None);
+    // The JSON itself never leaks into page text
+    assert!(dom.find_by_text("itemListElement").is_empty());
+}
+
+#[test]
+fn test_breadcrumbs_from_separated_link_row() {
+    let dom = parse_fixture("breadcrumb_row.html");
+    assert_eq!(
+        crumb_texts(&dom),
+        vec!["Home", "Recipes", "Bread", "Baking bread at home"]
+    );
+}
+
+#[test]
+fn test_nav_bar_is_not_breadcrumbs() {
+    let dom = parse_fixture("nav_bar.html");
+    assert_eq!(dom.breadcrumbs(), None);
+}
+
+#[test]
+fn test_product_page_us_prices() {
+    let dom = parse_fixture("product_us.html");
+    assert_eq!(dom.page_type, PageType::Product);
+
+    let primary = dom.primary_price().expect("Should find the sale price");
+    assert_eq!((primary.amount, primary.currency.as_str()), (1299.0, "USD"));
+    assert!(!primary.original);
+    assert_eq!(dom.get(primary.element_id).unwrap().tag, "strong");
+
+    let prices = dom.prices();
+    let was = prices.iter().find(|p| p.amount == 1499.0).expect("Should keep the was price");
+    assert!(was.original, "<del> price should be flagged: {was:?}");
+    // The shipping threshold sits closer to the button but is part of a sentence
+    let shipping = prices.iter().position(|p| p.amount == 35.0).unwrap();
+    assert!(shipping > 0);
+    // Each price is reported once, not again for its wrapper
+    assert_eq!(prices.iter().filter(|p| p.amount == 1299.0).count(), 1);
+}
+
+#[test]
+fn test_product_page_european_prices() {
+    let dom = parse_fixture("product_eu.html");
+    // No og:type or JSON-LD: a price beside "In den Warenkorb" is enough
+    assert_eq!(dom.page_type, PageType::Product);
+
+    let primary = dom.primary_price().expect("Should find the price");
+    assert_eq!((primary.amount, primary.currency.as_str()), (1299.0, "EUR"));
+
+    let prices = dom.prices();
+    assert!(prices.iter().any(|p| p.amount == 1499.0 && p.original), "{prices:?}");
+    let unit = prices.iter().find(|p| p.per_unit).expect("Should flag the price per kg");
+    assert_eq!(unit.amount, 18.9);
+    assert!(prices.iter().any(|p| p.amount == 18.9 && !p.per_unit));
+}
+
+#[test]
+fn test_article_meta_from_meta_tags() {
+    let dom = parse_fixture("article_meta_tags.html");
+    let meta = dom.article.expect("Should read the meta tags");
+    assert_eq!(meta.published_at.as_deref(), Some("2026-03-03T09:14:00Z"));
+    assert_eq!(meta.updated_at.as_deref(), Some("2026-03-03T15:40:00+01:00"));
+    assert_eq!(meta.author.as_deref(), Some("Priya Raman"));
+    assert_eq!(meta.relative_date, None);
+}
+
+#[test]
+fn test_article_meta_from_visible_byline() {
+    let dom = parse_fixture("article_byline.html");
+    let meta = dom.article.expect("Should read the byline and time elements");
+    assert_eq!(meta.author.as_deref(), Some("Jane Doe"));
+    assert_eq!(meta.published_at.as_deref(), Some("2026-03-03T09:14:00Z"));
+    assert_eq!(meta.updated_at.as_deref(), Some("2026-03-03T11:02:00"));
+    // Shown verbatim, not converted to a date
+    assert_eq!(meta.relative_date.as_deref(), Some("3 hours ago"));
+}
+
+#[test]
+fn test_article_meta_tags_win_over_json_ld_and_page() {
+    let dom = parse_fixture("article_conflict.html");
+    let meta = dom.article.expect("Should read article metadata");
+    assert_eq!(meta.published_at.as_deref(), Some("2026-03-01T08:00:00Z"));
+    assert_eq!(meta.author.as_deref(), Some("Ada Lovelace"));
+    // No modified meta tag, so JSON-LD fills it in
+    assert_eq!(meta.updated_at.as_deref(), Some("2026-03-05T10:30:00Z"));
+}
+
+fn article_meta(body: &str, head: &str) -> browsy_core::output::ArticleMeta {
+    let html = format!("<html><head>{head}</head><body>{body}</body></html>");
+    browsy_core::parse(&html, 1920.0, 1080.0).article.unwrap_or_default()
+}
+
+/// The publication date read from an `article:published_time` meta tag.
+fn published(content: &str) -> Option<String> {
+    let head = format!(r#"<meta property="article:published_time" content="{content}">"#);
+    article_meta("<p>Body</p>", &head).published_at
+}
+
+#[test]
+fn test_article_iso_dates_keep_time_and_offset() {
+    assert_eq!(published("2026-03-03").as_deref(), Some("2026-03-03"));
+    assert_eq!(published("2026-03-03T09:14:00Z").as_deref(), Some("2026-03-03T09:14:00Z"));
+    assert_eq!(published("2026-03-03T09:14:00.000Z").as_deref(), Some("2026-03-03T09:14:00Z"));
+    assert_eq!(published("2026-03-03 09:14").as_deref(), Some("2026-03-03T09:14:00"));
+    assert_eq!(published("2026-03-03T09:14:00+0100").as_deref(), Some("2026-03-03T09:14:00+01:00"));
+    assert_eq!(published("2026-03-03T09:14:00-05:00").as_deref(), Some("2026-03-03T09:14:00-05:00"));
+    assert_eq!(published("2026-13-03"), None);
+}
+
+#[test]
+fn test_article_written_dates() {
+    assert_eq!(published("March 3, 2026").as_deref(), Some("2026-03-03"));
+    assert_eq!(published("3rd Mar 2026").as_deref(), Some("2026-03-03"));
+    assert_eq!(published("Sept 21, 2025").as_deref(), Some("2025-09-21"));
+    assert_eq!(published("Tue, 03 Mar 2026 09:14:00 GMT").as_deref(), Some("2026-03-03"));
+    assert_eq!(published("Marketing 2026"), None);
+    assert_eq!(published("3 hours ago"), None);
+}
+
+#[test]
+fn test_article_relative_dates_are_kept_verbatim() {
+    let relative = |text: &str| article_meta(&format!("<time>{text}</time>"), "").relative_date;
+    assert_eq!(relative("Updated 3 hours ago").as_deref(), Some("3 hours ago"));
+    assert_eq!(relative("an hour ago").as_deref(), Some("an hour ago"));
+    assert_eq!(relative("Posted 5m ago").as_deref(), Some("5m ago"));
+    assert_eq!(relative("Yesterday, 14:02").as_deref(), Some("yesterday"));
+    assert_eq!(relative("Long ago and far away"), None);
+    assert_eq!(relative("March 3, 2026"), None);
+}
+
+#[test]
+fn test_article_byline_author() {
+    // A paragraph needs "By"; an element marked as the byline doesn't
+    let from_text = |text: &str| article_meta(&format!("<p>{text}</p>"), "").author;
+    let from_byline = |text: &str| article_meta(&format!(r#"<span class="byline">{text}</span>"#), "").author;
+    assert_eq!(from_text("By Jane Doe").as_deref(), Some("Jane Doe"));
+    assert_eq!(from_text("By Jane Doe · 3 hours ago").as_deref(), Some("Jane Doe"));
+    assert_eq!(from_text("by Jane Doe, Science Editor").as_deref(), Some("Jane Doe"));
+    assert_eq!(from_text("By Jane Doe and John Roe on March 3").as_deref(), Some("Jane Doe and John Roe"));
+    assert_eq!(from_byline("Ludwig van Beethoven").as_deref(), Some("Ludwig van Beethoven"));
+    assert_eq!(from_text("Jane Doe"), None);
+    assert_eq!(from_text("By the way, we moved"), None);
+    assert_eq!(from_byline("Jane Doe writes about science and the people behind it"), None);
+}
+
+#[test]
+fn test_declared_language() {
+    let dom = parse_fixture("lang_de.html");
+    assert_eq!(dom.lang.as_deref(), Some("de-DE"));
+    assert_eq!(dom.lang_confidence, Some(1.0));
+    assert!(dom.language_mismatch("en-US,en;q=0.9"));
+    assert!(!dom.language_mismatch("de"));
+}
+
+#[test]
+fn test_detected_language() {
+    let dom = parse_fixture("lang_ja.html");
+    assert_eq!(dom.lang.as_deref(), Some("ja"));
+    assert!(dom.lang_confidence.unwrap() > 0.9, "{:?}", dom.lang_confidence);
+    assert!(dom.language_mismatch("en"));
+    assert!(!dom.language_mismatch("ja-JP"));
+}
+
+/// The language guessed for a page holding `text`, with its confidence.
+fn detected_language(text: &str) -> Option<(String, f32)> {
+    let dom = browsy_core::parse(&format!("<html><body><p>{text}</p></body></html>"), 1920.0, 1080.0);
+    dom.lang.zip(dom.lang_confidence)
+}
+
+#[test]
+fn test_detects_latin_languages_from_function_words() {
+    let (lang, confidence) = detected_language("Die Stadt hat sich entschieden, die Straße für den Verkehr zu sperren, und das ist nicht das erste Mal.").unwrap();
+    assert_eq!(lang, "de");
+    assert!(confidence > 0.7, "{confidence}");
+    let en = detected_language("The council said that the road will be closed to traffic for the rest of the year.").unwrap();
+    assert_eq!(en.0, "en");
+    let fr = detected_language("Le conseil a décidé que la rue sera fermée pendant les travaux, et les commerces sont inquiets.").unwrap();
+    assert_eq!(fr.0, "fr");
+}
+
+#[test]
+fn test_detects_language_scripts() {
+    let lang = |text: &str| detected_language(text).unwrap().0;
+    assert_eq!(lang("東京の天気は明日から雨になる見込みです。傘を忘れないでください。"), "ja");
+    assert_eq!(lang("北京今天天气晴朗，气温适宜，适合外出散步和参观博物馆。"), "zh");
+    assert_eq!(lang("Сегодня в Москве ожидается солнечная погода без осадков."), "ru");
+}
+
+#[test]
+fn test_too_little_text_has_no_language() {
+    assert_eq!(detected_language("OK"), None);
+    assert_eq!(detected_language("Zyxwv qrstu lmnop ghijk"), None);
+}
+
+#[test]
+fn test_language_mismatch_compares_primary_subtags() {
+    let page = |lang: &str| browsy_core::parse(&format!(r#"<html lang="{lang}"><body><p>Hallo</p></body></html>"#), 1920.0, 1080.0);
+    assert!(!page("de-DE").language_mismatch("de-AT"));
+    assert!(page("de-DE").language_mismatch("en-US,en;q=0.9"));
+    assert!(!page("pt").language_mismatch(" PT_br "));
+}
+
+#[test]
+fn test_mixed_language_is_not_a_mismatch() {
+    let dom = parse_fixture("lang_mixed.html");
+    // German and English paragraphs side by side: some guess, but not a confident one
+    assert!(dom.lang.is_some());
+    assert!(dom.lang_confidence.unwrap() < 0.5, "{:?}", dom.lang_confidence);
+    assert!(!dom.language_mismatch("de"));
+    assert!(!dom.language_mismatch("fr"));
+}
+
 #[test]
 fn test_oauth_consent_flow() {
     let dom = parse_fixture("oauth.html");
@@ -170,3 +461,86 @@ fn test_2fa_separate_digits_flow() {
         assert_eq!(*code_length, Some(6), "Should detect 6 separate digit inputs");
     }
 }
+
+fn id_named(dom: &browsy_core::output::SpatialDom, name: &str) -> u32 {
+    dom.els
+        .iter()
+        .find(|e| e.name.as_deref() == Some(name))
+        .unwrap_or_else(|| panic!("no element named {name}"))
+        .id
+}
+
+#[test]
+fn test_autocomplete_pairs_login_fields() {
+    // The username is more than 500px above the password, and another form's
+    // text input is nearer
+    let dom = parse_fixture("github_login.html");
+
+    let login = dom.suggested_actions.iter().find(|a| matches!(a, SuggestedAction::Login { .. }));
+    let Some(SuggestedAction::Login { username_id, password_id, submit_id, .. }) = login else {
+        panic!("Should detect login action, got: {:?}", dom.suggested_actions);
+    };
+    assert_eq!(*username_id, id_named(&dom, "login"));
+    assert_eq!(*password_id, id_named(&dom, "password"));
+    assert_eq!(*submit_id, id_named(&dom, "commit"));
+}
+
+#[test]
+fn test_two_step_login_flow() {
+    // Step 1 asks only for the email, so there is nothing to log in with yet
+    let email_dom = parse_fixture("signin_email.html");
+    assert!(
+        !email_dom.suggested_actions.iter().any(|a| matches!(a, SuggestedAction::Login { .. })),
+        "Email step has no password, got: {:?}",
+        email_dom.suggested_actions
+    );
+
+    // Step 2 repeats the email at the top, far above the password
+    let dom = parse_fixture("signin_password.html");
+    let login = dom.suggested_actions.iter().find(|a| matches!(a, SuggestedAction::Login { .. }));
+    let Some(SuggestedAction::Login { username_id, password_id, .. }) = login else {
+        panic!("Should detect login action, got: {:?}", dom.suggested_actions);
+    };
+    assert_eq!(*username_id, id_named(&dom, "identifier"));
+    assert_eq!(*password_id, id_named(&dom, "password"));
+}
+
+#[test]
+fn test_new_password_is_registration() {
+    let html = r#"<html><head><title>Sign in to Acme</title></head><body>
+        <h1>Sign in to Acme</h1>
+        <form>
+          <input type="email" name="account" autocomplete="email">
+          <input type="password" name="choose" autocomplete="new-password">
+          <button type="submit">Continue</button>
+        </form>
+    </body></html>"#;
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+
+    assert!(!dom.suggested_actions.iter().any(|a| matches!(a, SuggestedAction::Login { .. })));
+    let register = dom.suggested_actions.iter().find(|a| matches!(a, SuggestedAction::Register { .. }));
+    let Some(SuggestedAction::Register { email_id, password_id, confirm_password_id, .. }) = register else {
+        panic!("Should detect register action, got: {:?}", dom.suggested_actions);
+    };
+    assert_eq!(*email_id, Some(id_named(&dom, "account")));
+    assert_eq!(*password_id, id_named(&dom, "choose"));
+    assert_eq!(*confirm_password_id, None);
+}
+
+#[test]
+fn test_one_time_code_needs_no_keywords() {
+    let html = r#"<html><head><title>Acme</title></head><body>
+        <h1>Almost there</h1>
+        <form>
+          <input type="text" name="token" inputmode="numeric" autocomplete="one-time-code">
+          <button type="submit">Continue</button>
+        </form>
+    </body></html>"#;
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+
+    let code_action = dom.suggested_actions.iter().find(|a| matches!(a, SuggestedAction::EnterCode { .. }));
+    let Some(SuggestedAction::EnterCode { input_id, .. }) = code_action else {
+        panic!("Should detect enter code action, got: {:?}", dom.suggested_actions);
+    };
+    assert_eq!(*input_id, id_named(&dom, "token"));
+}
@@ -0,0 +1,126 @@
+//! `Session::fork` and `new_tab`: forks share the state they started from, then
+//! diverge; tabs share only their cookies.
+
+#![cfg(feature = "fetch")]
+
+mod common;
+
+use browsy_core::fetch::{LoginStatus, Session, SessionConfig};
+use common::Response;
+
+const LOGIN_PAGE: &str = r#"<html><head><title>Sign in</title></head><body>
+    <form action="/session" method="post">
+        <input type="email" name="email" placeholder="Email">
+        <input type="password" name="password" placeholder="Password">
+        <button type="submit">Sign in</button>
+    </form>
+</body></html>"#;
+
+/// A site that signs in on `POST /session` with a `sid` cookie and signs out
+/// on `/logout`. Every other page says whether the request carried the cookie.
+fn serve() -> String {
+    let port = common::serve(|request| {
+        let path = request.path.as_str();
+        match (request.method == "POST", path) {
+            (true, "/session") => {
+                Response::html("<html><head><title>Account</title></head><body><h1>Welcome back, Ada</h1></body></html>")
+                    .with_header("Set-Cookie: sid=ada; Path=/")
+            }
+            (false, "/login") => Response::html(LOGIN_PAGE),
+            (false, "/logout") => {
+                Response::html("<html><head><title>Signed out</title></head><body><h1>Bye</h1></body></html>")
+                    .with_header("Set-Cookie: sid=; Path=/; Max-Age=0")
+            }
+            _ => {
+                let status = if request.has_cookie("sid=ada") { "Signed in as Ada" } else { "Not signed in" };
+                Response::html(format!("<html><head><title>{path}</title></head><body><p>{status}</p></body></html>"))
+            }
+        }
+    });
+    format!("http://127.0.0.1:{port}")
+}
+
+fn session() -> Session {
+    Session::with_config(SessionConfig {
+        fetch_css: false,
+        allow_private_network: true,
+        ..SessionConfig::default()
+    })
+    .unwrap()
+}
+
+fn signed_in(session: &mut Session, url: &str) -> bool {
+    let dom = session.goto(url).unwrap();
+    dom.els.iter().any(|e| e.text.as_deref() == Some("Signed in as Ada"))
+}
+
+#[test]
+fn test_forks_stay_signed_in_while_histories_diverge() {
+    let base = serve();
+    let mut main = session();
+    main.goto(&format!("{base}/login")).unwrap();
+    let outcome = main.login("ada@example.com", "hunter2").unwrap();
+    assert_eq!(outcome.status, LoginStatus::Success);
+
+    let mut fork = main.fork().unwrap();
+    assert_eq!(fork.url(), main.url());
+    assert_eq!(fork.history_len(), main.history_len());
+    assert_eq!(fork.dom().unwrap().title, "Account");
+
+    assert!(signed_in(&mut main, &format!("{base}/inbox")));
+    assert!(signed_in(&mut fork, &format!("{base}/settings")));
+    assert!(main.url().unwrap().ends_with("/inbox"));
+    assert!(fork.url().unwrap().ends_with("/settings"));
+
+    // Signing out of one fork leaves the other's cookies alone
+    main.goto(&format!("{base}/logout")).unwrap();
+    assert!(!signed_in(&mut main, &format!("{base}/inbox")));
+    assert!(signed_in(&mut fork, &format!("{base}/inbox")));
+
+    // Each fork goes back through its own history
+    assert_eq!(main.history_len(), fork.history_len() + 1);
+    assert!(fork.back().unwrap().url.ends_with("/settings"));
+    assert!(main.back().unwrap().url.ends_with("/logout"));
+}
+
+#[test]
+fn test_fork_keeps_typed_values() {
+    let base = serve();
+    let mut main = session();
+    main.goto(&format!("{base}/login")).unwrap();
+    let email = main
+        .dom()
+        .unwrap()
+        .els
+        .iter()
+        .find(|e| e.name.as_deref() == Some("email"))
+        .unwrap()
+        .id;
+    main.type_text(email, "ada@example.com").unwrap();
+
+    let mut fork = main.fork().unwrap();
+    fork.type_text(email, "grace@example.com").unwrap();
+
+    let value = |session: &Session| session.dom().unwrap().get(email).unwrap().val.clone();
+    assert_eq!(value(&main).as_deref(), Some("ada@example.com"));
+    assert_eq!(value(&fork).as_deref(), Some("grace@example.com"));
+}
+
+#[test]
+fn test_new_tab_shares_cookies_but_not_pages() {
+    let base = serve();
+    let mut main = session();
+    main.goto(&format!("{base}/login")).unwrap();
+    main.login("ada@example.com", "hunter2").unwrap();
+
+    let mut tab = main.new_tab().unwrap();
+    assert_eq!(tab.url(), None);
+    assert_eq!(tab.history_len(), 0);
+    assert!(tab.dom().is_none());
+    assert!(signed_in(&mut tab, &format!("{base}/settings")));
+    assert_eq!(main.dom().unwrap().title, "Account");
+
+    // Signing out in the tab signs the other session out too
+    tab.goto(&format!("{base}/logout")).unwrap();
+    assert!(!signed_in(&mut main, &format!("{base}/inbox")));
+}
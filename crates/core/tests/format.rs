@@ -0,0 +1,76 @@
+//! The header the servers and the CLI print above a page, and the formats
+//! and scopes that share it.
+
+use browsy_core::output::{
+    apply_scope, format_header, format_page, format_view, to_compact_string, HiddenReason, PageType, Scope,
+    SpatialDom, DEFAULT_HIDDEN_DROP,
+};
+
+const PAGE: &str = r#"<html lang="en"><head><title>Orders</title></head><body>
+    <h1>Orders</h1>
+    <form><label for="ref">Order number</label><input id="ref" name="ref"><button>Track</button></form>
+    <table><tr><th>Order</th><th>Total</th></tr><tr><td>A-100</td><td>$20</td></tr></table>
+    <div style="margin-top: 2000px"><a href="/help">Help</a></div>
+    <div style="display: none"><a href="/secret">Secret</a></div>
+</body></html>"#;
+
+fn page() -> SpatialDom {
+    let mut dom = browsy_core::parse(PAGE, 1280.0, 800.0);
+    dom.url = "https://example.com/orders".into();
+    dom
+}
+
+#[test]
+fn test_header_counts_what_the_page_holds() {
+    let dom = page();
+    let header = format_header(&dom);
+    let above_fold = dom.view(Scope::AboveFold).len();
+    assert!(above_fold < dom.els.len(), "{header}");
+    assert!(
+        header.starts_with(&format!(
+            "title: Orders\nurl: https://example.com/orders\nels: {}\nlang: en\n",
+            dom.els.len()
+        )),
+        "{header}"
+    );
+    assert!(header.contains(&format!("\nfold: {above_fold}/{} els\n", dom.els.len())), "{header}");
+    assert!(header.contains("\nhidden: 1\nforms: 1\ntables: 1\n"), "{header}");
+    assert!(header.ends_with("---\n"), "{header}");
+    assert!(!header.contains("page_type:"), "Other is left out: {header}");
+
+    let mut dom = dom;
+    dom.page_type = PageType::Dashboard;
+    assert!(format_header(&dom).contains("\npage_type: Dashboard\n"));
+}
+
+#[test]
+fn test_formats_share_the_header() {
+    let dom = page();
+    let compact = format_page(&dom, None);
+    let (header, body) = compact.split_once("---\n").unwrap();
+    assert_eq!(format!("{header}---\n"), format_header(&dom));
+    assert_eq!(body, to_compact_string(&dom));
+    assert!(format_page(&dom, Some("map")).starts_with(&format_header(&dom)));
+    let json: serde_json::Value = serde_json::from_str(&format_page(&dom, Some("json"))).unwrap();
+    assert_eq!(json["title"], "Orders");
+
+    // A view counts its own elements, and the breakdown stays the page's
+    let view = dom.view(Scope::AboveFold);
+    let text = format_view(&view, None);
+    assert!(text.contains(&format!("\nels: {}\n", view.len())), "{text}");
+    assert!(text.contains(&format!("\nfold: {}/{} els\n", view.len(), dom.els.len())), "{text}");
+    assert!(text.ends_with(&view.to_compact_string()));
+}
+
+#[test]
+fn test_apply_scope_narrows_in_order() {
+    let dom = page();
+    assert_eq!(apply_scope(&dom, None, false, false).els.len(), dom.els.len());
+    let visible = apply_scope(&dom, Some(&[]), false, false);
+    assert_eq!(visible.els.len(), dom.strip_hidden(DEFAULT_HIDDEN_DROP).els.len());
+    let hidden = apply_scope(&dom, Some(&[HiddenReason::Display]), false, false);
+    assert!(hidden.els.iter().all(|e| e.text.as_deref() != Some("Secret")));
+    let folded = apply_scope(&dom, None, true, false);
+    assert_eq!(folded.els.len(), dom.filter_above_fold().els.len());
+    assert!(folded.els.iter().all(|e| e.text.as_deref() != Some("Help")));
+}
@@ -54,6 +54,7 @@ mod inner {
             browsy_core::output::SuggestedAction::SelectFromList { .. } => "SelectFromList",
             browsy_core::output::SuggestedAction::CookieConsent { .. } => "CookieConsent",
             browsy_core::output::SuggestedAction::Paginate { .. } => "Paginate",
+            browsy_core::output::SuggestedAction::LoadMore { .. } => "LoadMore",
             browsy_core::output::SuggestedAction::Register { .. } => "Register",
             browsy_core::output::SuggestedAction::Contact { .. } => "Contact",
             browsy_core::output::SuggestedAction::FillForm { .. } => "FillForm",
@@ -180,3 +180,23 @@ fn test_js_tab_detection() {
         .collect();
     assert_eq!(tab_behaviors.len(), 2);
 }
+
+#[test]
+fn test_js_detect_data_url_navigation() {
+    let html = r#"
+    <html><body>
+        <button type="button" data-url="/news?page=2">Load more</button>
+        <a href="/about" data-url="/ignored">About</a>
+    </body></html>
+    "#;
+
+    let dom_tree = browsy_core::dom::parse_html(html);
+    let behaviors = js::detect_behaviors(&dom_tree);
+
+    // Links navigate through their href, so only the button gets a behavior
+    assert_eq!(behaviors.len(), 1, "{:?}", behaviors);
+    match &behaviors[0].action {
+        js::JsAction::Navigate { url } => assert_eq!(url, "/news?page=2"),
+        other => panic!("Expected Navigate, got {:?}", other),
+    }
+}
@@ -0,0 +1,280 @@
+//! `Session::login` outcomes and `Session::enter_code`. Each test serves a
+//! page and fixed responses to its form submits from a local server.
+
+#![cfg(feature = "fetch")]
+
+mod common;
+
+use std::sync::{Arc, Mutex};
+
+use browsy_core::fetch::{AuditAction, FetchError, LoginStatus, Session, SessionConfig};
+use browsy_core::output::SuggestedAction;
+use common::Response;
+
+const LOGIN_PAGE: &str = r#"<html><head><title>Sign in</title></head><body>
+    <h1>Sign in</h1>
+    <form action="/session" method="post">
+        <input type="email" name="email" placeholder="Email">
+        <input type="password" name="password" placeholder="Password">
+        <button type="submit">Sign in</button>
+    </form>
+</body></html>"#;
+
+/// Serve `page` for GET requests and `after_submit` for POSTs. Returns the
+/// base URL and the bodies of requests received.
+fn serve(page: &'static str, after_submit: &'static str) -> (String, Arc<Mutex<Vec<String>>>) {
+    serve_routes(vec![("GET ", page), ("POST ", after_submit)])
+}
+
+/// Serve the page of the first route whose prefix the request line starts
+/// with, such as `"POST /password"`. Returns the base URL and the bodies of
+/// requests received.
+fn serve_routes(routes: Vec<(&'static str, &'static str)>) -> (String, Arc<Mutex<Vec<String>>>) {
+    let bodies = Arc::new(Mutex::new(Vec::new()));
+    let received = bodies.clone();
+    let port = common::serve(move |request| {
+        if !request.body.is_empty() {
+            received.lock().unwrap().push(request.body.clone());
+        }
+        let line = format!("{} {}", request.method, request.path);
+        routes
+            .iter()
+            .find(|(prefix, _)| line.starts_with(prefix))
+            .map_or(Response::empty("404 Not Found"), |(_, page)| Response::html(*page))
+    });
+    (format!("http://127.0.0.1:{port}"), bodies)
+}
+
+fn session() -> Session {
+    Session::with_config(SessionConfig {
+        fetch_css: false,
+        allow_private_network: true,
+        ..SessionConfig::default()
+    })
+    .unwrap()
+}
+
+fn login(after_submit: &'static str) -> browsy_core::fetch::LoginOutcome {
+    let (base, _) = serve(LOGIN_PAGE, after_submit);
+    let mut session = session();
+    session.goto(&format!("{base}/login")).unwrap();
+    session.login("ada@example.com", "hunter2").unwrap()
+}
+
+#[test]
+fn test_login_success() {
+    let outcome = login(
+        r#"<html><head><title>Account</title></head><body>
+        <h1>Welcome back, Ada</h1><a href="/logout">Sign out</a>
+        </body></html>"#,
+    );
+    assert_eq!(outcome.status, LoginStatus::Success);
+    assert!(outcome.is_success());
+    // Derefs to the page
+    assert_eq!(outcome.title, "Account");
+}
+
+#[test]
+fn test_login_failed_reports_alert_text() {
+    let outcome = login(
+        r#"<html><head><title>Sign in</title></head><body>
+        <h1>Sign in</h1>
+        <p role="alert">Invalid email or password</p>
+        <form action="/session" method="post">
+            <input type="email" name="email" placeholder="Email">
+            <input type="password" name="password" placeholder="Password">
+            <button type="submit">Sign in</button>
+        </form>
+        </body></html>"#,
+    );
+    assert_eq!(
+        outcome.status,
+        LoginStatus::Failed { reason: "Invalid email or password".to_string() }
+    );
+    assert_eq!(outcome.status.to_string(), "failed (Invalid email or password)");
+}
+
+#[test]
+fn test_login_failed_when_form_comes_back() {
+    let outcome = login(LOGIN_PAGE);
+    assert!(matches!(outcome.status, LoginStatus::Failed { .. }), "{:?}", outcome.status);
+}
+
+#[test]
+fn test_login_two_factor_required() {
+    let outcome = login(
+        r#"<html><head><title>Two-factor authentication</title></head><body>
+        <h1>Enter the code from your authenticator app</h1>
+        <form action="/verify" method="post">
+            <input type="text" name="code" placeholder="6-digit code">
+            <button type="submit">Verify</button>
+        </form>
+        </body></html>"#,
+    );
+    assert_eq!(outcome.status, LoginStatus::TwoFactorRequired);
+}
+
+#[test]
+fn test_login_captcha_required() {
+    let outcome = login(
+        r#"<html><head><title>Security check</title></head><body>
+        <h1>Please verify you are human</h1>
+        <div class="g-recaptcha" data-sitekey="6Lc-test"></div>
+        </body></html>"#,
+    );
+    assert_eq!(outcome.status, LoginStatus::CaptchaRequired);
+}
+
+// ---------------------------------------------------------------------------
+// Email first, then the password on the next page
+// ---------------------------------------------------------------------------
+
+const EMAIL_STEP: &str = r#"<html><head><title>Sign in</title></head><body>
+    <h1>Sign in</h1>
+    <form action="/identifier" method="post">
+        <input type="email" name="identifier" autocomplete="username" placeholder="Email or phone">
+        <button type="submit">Next</button>
+    </form>
+</body></html>"#;
+
+const PASSWORD_STEP: &str = r#"<html><head><title>Sign in</title></head><body>
+    <h1>Welcome</h1>
+    <form action="/challenge" method="post">
+        <input type="email" name="identifier" value="ada@example.com" autocomplete="username" readonly>
+        <input type="password" name="password" autocomplete="current-password" placeholder="Enter your password">
+        <button type="submit">Next</button>
+    </form>
+</body></html>"#;
+
+const ACCOUNT_PAGE: &str = r#"<html><head><title>Account</title></head><body>
+    <h1>Welcome back, Ada</h1><a href="/logout">Sign out</a>
+</body></html>"#;
+
+fn form_pairs(body: &str) -> Vec<(String, String)> {
+    url::form_urlencoded::parse(body.as_bytes()).into_owned().collect()
+}
+
+#[test]
+fn test_login_email_then_password() {
+    let (base, bodies) = serve_routes(vec![
+        ("GET /login", EMAIL_STEP),
+        ("POST /identifier", PASSWORD_STEP),
+        ("POST /challenge", ACCOUNT_PAGE),
+    ]);
+    let mut session = session();
+    session.goto(&format!("{base}/login")).unwrap();
+
+    let outcome = session.login("ada@example.com", "hunter2").unwrap();
+    assert_eq!(outcome.status, LoginStatus::Success);
+    assert_eq!(outcome.steps, 2);
+    assert_eq!(outcome.title, "Account");
+
+    let bodies = bodies.lock().unwrap();
+    assert_eq!(bodies.len(), 2, "{bodies:?}");
+    assert_eq!(form_pairs(&bodies[0]), [("identifier".to_string(), "ada@example.com".to_string())]);
+    // The read-only email was sent as the page filled it in
+    assert_eq!(
+        form_pairs(&bodies[1]),
+        [
+            ("identifier".to_string(), "ada@example.com".to_string()),
+            ("password".to_string(), "hunter2".to_string()),
+        ]
+    );
+
+    let steps: Vec<AuditAction> = session
+        .audit_log()
+        .into_iter()
+        .map(|e| e.action)
+        .filter(|a| matches!(a, AuditAction::LoginStep { .. }))
+        .collect();
+    assert_eq!(
+        steps,
+        [
+            AuditAction::LoginStep { step: 1, fields: vec!["username".to_string()] },
+            AuditAction::LoginStep { step: 2, fields: vec!["password".to_string()] },
+        ]
+    );
+}
+
+#[test]
+fn test_login_stops_at_captcha_between_steps() {
+    let (base, bodies) = serve_routes(vec![
+        ("GET /login", EMAIL_STEP),
+        (
+            "POST /identifier",
+            r#"<html><head><title>Security check</title></head><body>
+            <h1>Please verify you are human</h1>
+            <div class="g-recaptcha" data-sitekey="6Lc-test"></div>
+            </body></html>"#,
+        ),
+    ]);
+    let mut session = session();
+    session.goto(&format!("{base}/login")).unwrap();
+
+    let outcome = session.login("ada@example.com", "hunter2").unwrap();
+    assert_eq!(outcome.status, LoginStatus::CaptchaRequired);
+    assert_eq!(outcome.steps, 1);
+    assert_eq!(bodies.lock().unwrap().len(), 1);
+}
+
+// ---------------------------------------------------------------------------
+// Verification codes
+// ---------------------------------------------------------------------------
+
+const SPLIT_CODE_PAGE: &str = include_str!("fixtures/2fa_separate.html");
+
+#[test]
+fn test_enter_code_distributes_split_digits() {
+    let (base, bodies) = serve(
+        SPLIT_CODE_PAGE,
+        "<html><head><title>Account</title></head><body><h1>Signed in</h1></body></html>",
+    );
+    let mut session = session();
+    session.goto(&format!("{base}/verify")).unwrap();
+
+    let dom = session.enter_code("123 4-56").unwrap();
+    assert_eq!(dom.title, "Account");
+
+    let bodies = bodies.lock().unwrap();
+    assert_eq!(bodies.len(), 1, "{bodies:?}");
+    let pairs: Vec<(String, String)> = url::form_urlencoded::parse(bodies[0].as_bytes())
+        .into_owned()
+        .collect();
+    let digits: Vec<(String, String)> = (1..=6)
+        .map(|i| (format!("digit{i}"), i.to_string()))
+        .collect();
+    assert_eq!(pairs, digits);
+}
+
+#[test]
+fn test_enter_code_rejects_wrong_length_for_split_digits() {
+    let mut session = session();
+    session.load_html(SPLIT_CODE_PAGE, "https://example.com/verify").unwrap();
+
+    match session.enter_code("12345") {
+        Err(FetchError::ActionError(msg)) => assert!(msg.contains("6-character"), "{msg}"),
+        other => panic!("expected a length error, got {:?}", other.map(|d| d.title)),
+    }
+}
+
+#[test]
+fn test_enter_code_stops_at_the_end_of_the_split_digits() {
+    // A narrow input in another form further down is not a seventh box
+    let page = SPLIT_CODE_PAGE.replace("</main>", r#"<form action="/resend" method="post">
+        <p>Send the code to another number</p>
+        <input type="tel" name="prefix" style="width: 40px" aria-label="Country code">
+        <button type="submit">Send</button>
+    </form></main>"#);
+    let mut session = session();
+    let dom = session.load_html(&page, "https://example.com/verify").unwrap();
+    assert!(
+        dom.suggested_actions.iter().any(|a| matches!(a, SuggestedAction::EnterCode { code_length: Some(6), .. })),
+        "{:?}",
+        dom.suggested_actions
+    );
+
+    match session.enter_code("1234567") {
+        Err(FetchError::ActionError(msg)) => assert!(msg.contains("6-character"), "{msg}"),
+        other => panic!("expected a length error, got {:?}", other.map(|d| d.title)),
+    }
+}
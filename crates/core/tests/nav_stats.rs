@@ -0,0 +1,89 @@
+//! `Session::nav_stats`: what each navigation downloaded, against a local
+//! server with stylesheets to fetch and one to block.
+
+#![cfg(feature = "fetch")]
+
+mod common;
+
+use browsy_core::fetch::{NavStats, Session, SessionConfig, Timings};
+use common::Response;
+
+const PAGE: &str = r#"<html><head><title>Shop</title>
+    <link rel="stylesheet" href="/css/base.css">
+    <link rel="stylesheet" href="/css/theme.css">
+    <link rel="stylesheet" href="/ads/banner.css">
+</head><body>
+    <h1>Shop</h1>
+    <form action="/cart" method="post">
+        <input type="text" name="qty" value="1">
+        <button type="submit">Add to cart</button>
+    </form>
+</body></html>"#;
+
+const CART: &str = "<html><head><title>Cart</title></head><body><h1>Cart</h1><p>1 item</p></body></html>";
+const BASE_CSS: &str = "h1 { font-size: 32px; }";
+const THEME_CSS: &str = "body { margin: 0; } p { color: #333; }";
+
+/// Serve the page, its stylesheets and the cart. Returns the base URL.
+fn serve() -> String {
+    let port = common::serve(|request| match request.path.as_str() {
+        "/css/base.css" => Response::ok("text/css", BASE_CSS),
+        "/css/theme.css" => Response::ok("text/css", THEME_CSS),
+        // Would be counted as fetched if it were requested
+        "/ads/banner.css" => Response::ok("text/css", "div { display: none; }"),
+        "/cart" => Response::html(CART),
+        _ => Response::html(PAGE),
+    });
+    format!("http://127.0.0.1:{port}")
+}
+
+#[test]
+fn test_nav_stats_count_pages_and_stylesheets() {
+    let base = serve();
+    let mut session = Session::with_config(SessionConfig {
+        allow_private_network: true,
+        blocked_patterns: vec!["/ads/".to_string()],
+        ..SessionConfig::default()
+    })
+    .unwrap();
+    assert!(session.last_nav_stats().is_none());
+
+    let dom = session.goto(&format!("{base}/shop")).unwrap();
+    let stats = session.last_nav_stats().unwrap().clone();
+    assert_eq!(stats.url, format!("{base}/shop"));
+    assert_eq!(stats.page_bytes, PAGE.len() as u64);
+    assert_eq!(stats.css_fetched, 2);
+    assert_eq!(stats.css_blocked, 1);
+    assert_eq!(stats.css_bytes, (BASE_CSS.len() + THEME_CSS.len()) as u64);
+    assert_eq!(stats.elements, dom.els.len());
+    assert!(stats.timings.total >= stats.timings.fetch);
+
+    let add = dom.find_by_text("Add to cart")[0].id;
+    session.click(add).unwrap();
+    let cart = session.last_nav_stats().unwrap();
+    assert_eq!(cart.url, format!("{base}/cart"));
+    assert_eq!(cart.page_bytes, CART.len() as u64);
+    assert_eq!((cart.css_fetched, cart.css_blocked, cart.css_bytes), (0, 0, 0));
+
+    // Pages loaded from HTML aren't navigations
+    session.load_html(CART, &format!("{base}/local")).unwrap();
+    assert_eq!(session.nav_stats().len(), 2);
+    assert_eq!(session.nav_stats()[0], stats);
+}
+
+#[test]
+fn test_nav_stats_summary() {
+    let stats = NavStats {
+        url: "https://example.com/".to_string(),
+        page_bytes: 100 * 1024,
+        css_fetched: 6,
+        css_blocked: 3,
+        css_bytes: 312 * 1024,
+        elements: 231,
+        timings: Timings { total: 1830.0, ..Timings::default() },
+    };
+    assert_eq!(stats.summary(), "fetched 1 page + 6 css (3 blocked), 412KB, 1.8s, 231 els");
+
+    let small = NavStats { page_bytes: 900, timings: Timings { total: 42.4, ..Timings::default() }, ..NavStats::default() };
+    assert_eq!(small.summary(), "fetched 1 page + 0 css (0 blocked), 900B, 42ms, 0 els");
+}
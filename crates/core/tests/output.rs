@@ -52,6 +52,275 @@ fn test_delta_output() {
     assert_eq!(delta2.changed[0].text.as_deref(), Some("Sign Out"));
 }
 
+#[test]
+fn test_diff_ignore_bounds() {
+    let before = r#"<html><body><h1>Status</h1><a href="/a">All good</a></body></html>"#;
+    // A banner pushes everything down; the link text also changes.
+    let after = r#"<html><body><div style="height: 40px">Maintenance tonight</div>
+        <h1>Status</h1><a href="/a">Degraded</a></body></html>"#;
+    let dom1 = browsy_core::parse(before, 1920.0, 1080.0);
+    let dom2 = browsy_core::parse(after, 1920.0, 1080.0);
+
+    let strict = output::diff(&dom1, &dom2);
+    assert!(strict.changed.iter().any(|e| e.text.as_deref() == Some("Status")));
+
+    let relaxed = output::diff_with_options(
+        &dom1,
+        &dom2,
+        &output::DiffOptions { ignore_bounds: true },
+    );
+    assert!(!relaxed.changed.iter().any(|e| e.text.as_deref() == Some("Status")));
+    assert!(relaxed.changed.iter().any(|e| e.text.as_deref() == Some("Degraded")));
+    assert_eq!(relaxed.removed.len(), 1);
+
+    let same = output::diff_with_options(&dom2, &dom2, &output::DiffOptions { ignore_bounds: true });
+    assert!(same.changed.is_empty() && same.removed.is_empty());
+}
+
+#[test]
+fn test_diff_counts_identical_elements() {
+    let before = r#"<html><body><h1>News</h1>
+        <p>First story</p><a href="/more">Read more</a>
+        <p>Second story</p><a href="/more">Read more</a></body></html>"#;
+    let after = r#"<html><body><h1>News</h1>
+        <p>First story</p><a href="/more">Read more</a></body></html>"#;
+    let dom1 = browsy_core::parse(before, 1920.0, 1080.0);
+    let dom2 = browsy_core::parse(after, 1920.0, 1080.0);
+
+    let delta = output::diff_with_options(&dom1, &dom2, &output::DiffOptions { ignore_bounds: true });
+    assert!(delta.changed.is_empty(), "{:?}", delta.changed);
+    let removed: Vec<&str> = delta
+        .removed
+        .iter()
+        .map(|id| dom1.els.iter().find(|e| e.id == *id).unwrap().text.as_deref().unwrap())
+        .collect();
+    assert_eq!(removed, ["Second story", "Read more"]);
+
+    // Adding the link back is a change too
+    let delta = output::diff_with_options(&dom2, &dom1, &output::DiffOptions { ignore_bounds: true });
+    assert_eq!(delta.changed.len(), 2);
+}
+
+#[test]
+fn test_dom_query_helpers() {
+    let html = r#"
+    <html><body>
+        <form action="/subscribe">
+            <label for="email">Email address</label>
+            <input type="email" id="email" name="email" />
+            <button>Subscribe</button>
+        </form>
+        <form action="/search">
+            <input type="search" name="q" placeholder="Search" />
+            <input type="submit" value="Go" />
+        </form>
+        <label for="note">Notes</label>
+        <textarea id="note" name="note"></textarea>
+        <div role="button">Add to cart</div>
+    </body></html>
+    "#;
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+
+    assert!(dom.find_by_text("Add to cart").iter().any(|e| e.role.as_deref() == Some("button")));
+    assert!(!dom.find_by_role("button").is_empty());
+
+    let email = dom.get_by_label("  email ADDRESS ").expect("exact label match");
+    assert_eq!(email.name.as_deref(), Some("email"));
+    assert_eq!(dom.get_by_label("email").map(|e| e.id), Some(email.id), "substring fallback");
+    assert!(dom.get_by_label("phone").is_none());
+
+    let forms = dom.forms();
+    assert_eq!(forms.len(), 3);
+    assert_eq!(forms[0].fields[0].name.as_deref(), Some("email"));
+    assert!(forms[0].submit_id.is_some());
+    assert_eq!(forms[1].fields[0].label.as_deref(), Some("Search"), "placeholder stands in for label");
+    assert!(forms[1].submit_id.is_some());
+    assert_eq!(forms[2].fields[0].name.as_deref(), Some("note"));
+    assert!(forms[2].submit_id.is_none());
+}
+
+#[test]
+fn test_forms_group_by_form_element() {
+    let html = r#"
+    <html><body>
+        <form action="/newsletter">
+            <input type="email" name="newsletter" placeholder="Email" />
+        </form>
+        <form action="/login">
+            <input type="text" name="user" />
+            <button>Sign in</button>
+            <input type="password" name="pass" />
+        </form>
+        <input type="text" name="loose" />
+        <button>Go</button>
+    </body></html>
+    "#;
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+    let names = |form: &output::FormGroup| {
+        form.fields.iter().map(|f| f.name.clone().unwrap_or_default()).collect::<Vec<_>>()
+    };
+    let button = |text: &str| dom.find_by_text_exact(text)[0].id;
+
+    let forms = dom.forms();
+    assert_eq!(forms.len(), 3);
+    // A form without a button stays on its own
+    assert_eq!(names(&forms[0]), ["newsletter"]);
+    assert!(forms[0].submit_id.is_none());
+    // Fields after the button still belong to their form
+    assert_eq!(names(&forms[1]), ["user", "pass"]);
+    assert_eq!(forms[1].submit_id, Some(button("Sign in")));
+    assert_eq!(names(&forms[2]), ["loose"]);
+    assert_eq!(forms[2].submit_id, Some(button("Go")));
+}
+
+#[test]
+fn test_forms_follow_the_form_attribute() {
+    let html = r#"
+    <html><body>
+        <input type="email" name="email" form="checkout" />
+        <form id="checkout" action="/pay">
+            <input type="text" name="card" />
+            <input type="text" name="coupon" form="promo" />
+        </form>
+        <form id="promo" action="/coupon"><button>Apply</button></form>
+        <button type="submit" form="checkout">Pay now</button>
+    </body></html>
+    "#;
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+    let form_of = |name: &str| dom.els.iter().find(|e| e.name.as_deref() == Some(name)).unwrap().form;
+    assert_eq!(form_of("email"), Some(1));
+    assert_eq!(form_of("card"), Some(1));
+    assert_eq!(form_of("coupon"), Some(2));
+
+    let forms = dom.forms();
+    assert_eq!(forms.len(), 2);
+    let names: Vec<_> = forms[0].fields.iter().map(|f| f.name.clone().unwrap_or_default()).collect();
+    assert_eq!(names, ["email", "card"]);
+    assert_eq!(forms[0].submit_id, Some(dom.find_by_text_exact("Pay now")[0].id));
+}
+
+#[test]
+fn test_find_by_text_normalizes_and_ranks() {
+    let html = "<html><body>
+        <p>Please sign in to continue</p>
+        <a href=\"/login\">Sign&nbsp;&nbsp;In</a>
+        <button>
+            Sign
+            <span>in</span>
+        </button>
+        <button>SIGN IN</button>
+    </body></html>";
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+    let tags = |found: Vec<&output::SpatialElement>| found.iter().map(|e| e.tag.clone()).collect::<Vec<_>>();
+
+    // Whole-text matches first, most prominent first within each group
+    let found = tags(dom.find_by_text("sign in"));
+    let mut whole = found[..3].to_vec();
+    whole.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    assert_eq!(whole, ["a", "button", "button"]);
+    assert_eq!(found[3], "p");
+    assert_eq!(tags(dom.find_by_text("  Sign\u{a0}IN ")), found);
+    assert_eq!(tags(dom.find_by_text("to continue")), ["p"]);
+    assert!(dom.find_by_text("sign up").is_empty());
+
+    // The exact variant keeps the old case-sensitive substring match
+    assert_eq!(tags(dom.find_by_text_exact("SIGN IN")), ["button"]);
+    assert!(dom.find_by_text_exact("sign in").len() == 1);
+}
+
+#[test]
+fn test_hero_cta_outranks_footer_link_with_the_same_text() {
+    let dom = browsy_core::parse(include_str!("fixtures/hero_download.html"), 1920.0, 1080.0);
+    fn source(e: &output::SpatialElement) -> &str {
+        e.href.as_deref().and_then(|h| h.split("src=").nth(1)).unwrap_or("")
+    }
+
+    let found = dom.find_by_text("Download");
+    assert_eq!(found.iter().map(|e| source(e)).collect::<Vec<_>>(), ["hero", "nav", "footer"]);
+    let prominence = |src: &str| found.iter().find(|e| source(e) == src).unwrap().prominence.unwrap();
+    assert!(prominence("hero") > prominence("footer"));
+    assert!((0.0..=1.0).contains(&prominence("footer")));
+
+    let cta = dom.most_prominent(|e| e.text.as_deref() == Some("Download")).unwrap();
+    assert_eq!(source(cta), "hero");
+    // Landmark markers don't count
+    assert!(dom.els.iter().filter(|e| e.tag == "footer").all(|e| e.prominence.is_none()));
+    assert!(dom.most_prominent(|e| e.text.as_deref() == Some("Upload")).is_none());
+
+    // JSON only
+    assert!(serde_json::to_string(&dom).unwrap().contains("\"prominence\""));
+    let mut plain = dom.clone();
+    plain.els.iter_mut().for_each(|e| e.prominence = None);
+    assert_eq!(output::to_compact_string(&plain), output::to_compact_string(&dom));
+}
+
+#[test]
+fn test_cookie_consent_takes_the_prominent_accept_button() {
+    let html = r#"<html><body>
+        <div class="cookie-banner">
+            <div class="preferences">
+                <p style="font-size: 11px;">Manage preferences</p>
+                <button style="font-size: 11px;">Accept all</button>
+            </div>
+            <p>We use cookies to personalise content and to analyse our traffic.</p>
+            <button style="display: block; width: 320px; height: 64px; font-size: 20px;">Accept all</button>
+            <button>Reject all</button>
+        </div>
+        <h1>Shop</h1>
+    </body></html>"#;
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+    let accept = dom
+        .suggested_actions
+        .iter()
+        .find_map(|a| match a {
+            output::SuggestedAction::CookieConsent { accept_id, .. } => Some(*accept_id),
+            _ => None,
+        })
+        .expect("cookie consent action");
+    let buttons: Vec<u32> = dom.find_by_text_exact("Accept all").iter().map(|e| e.id).collect();
+    assert_eq!(buttons.len(), 2);
+    assert_eq!(accept, buttons[1]);
+}
+
+#[test]
+fn test_find_by_role_accepts_a_list() {
+    let html = r#"<html><body>
+        <h1>Shop</h1>
+        <a href="/cart">Cart</a>
+        <button>Buy</button>
+        <input type="text" name="q">
+    </body></html>"#;
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+    let roles = |role: &str| dom.find_by_role(role).iter().map(|e| e.tag.clone()).collect::<Vec<_>>();
+
+    assert_eq!(roles("button,link"), ["a", "button"]);
+    assert_eq!(roles(" link , button "), ["a", "button"]);
+    assert_eq!(roles("link"), ["a"]);
+    assert!(roles(",").is_empty());
+}
+
+#[test]
+fn test_compact_cuts_long_text_short() {
+    let terms = "You agree to these terms. ".repeat(60);
+    let html = format!("<html><body><p>{terms}</p><p>Short note</p></body></html>");
+    let mut dom = browsy_core::parse(&html, 1920.0, 1080.0);
+    let full = dom.find_by_text("You agree")[0].text.clone().unwrap();
+    assert_eq!(full.chars().count(), 1559);
+
+    let compact = output::to_compact_string(&dom);
+    let cut = format!("\"{}\u{2026}\" (1.6k chars)]", full.chars().take(200).collect::<String>().trim_end());
+    assert!(compact.lines().next().unwrap().ends_with(&cut), "{compact}");
+    assert!(compact.contains("\"Short note\"]"), "{compact}");
+    assert!(output::format_legend().contains("(1.4k chars)"));
+
+    // JSON keeps the whole text, and the limit can be changed or lifted
+    assert!(serde_json::to_string(&dom).unwrap().contains(&full));
+    dom.text_limit = Some(19);
+    assert!(output::to_compact_string(&dom).contains("\"You agree to these\u{2026}\" (1.6k chars)"));
+    dom.text_limit = None;
+    assert!(output::to_compact_string(&dom).contains(&full));
+}
+
 #[test]
 fn test_aria_attributes() {
     let html = r#"
@@ -356,6 +625,116 @@ fn test_above_fold_compact_output() {
     assert!(!compact.contains("Hidden Below"));
 }
 
+const CHECKOUT_HTML: &str = r#"
+<html>
+<body style="margin: 0;">
+    <div style="height: 500px;"></div>
+    <h2 style="height: 40px; margin: 0;">Payment details</h2>
+    <form style="margin: 0;">
+        <label for="card" style="display: block; height: 30px;">Card number</label>
+        <input id="card" name="card" style="display: block; height: 30px; margin: 0;">
+        <button style="display: block; height: 40px; margin: 150px 0 0 0;">Pay now</button>
+        <div role="alert" style="height: 30px; margin-top: 40px;">Your card was declined.</div>
+    </form>
+    <div style="height: 2000px;"></div>
+    <p>Terms of sale</p>
+</body>
+</html>
+"#;
+
+fn has_text(dom: &output::SpatialDom, text: &str) -> bool {
+    dom.els.iter().any(|e| e.text.as_deref() == Some(text))
+}
+
+#[test]
+fn test_above_fold_keeps_alert_below_fold() {
+    let dom = browsy_core::parse(CHECKOUT_HTML, 1920.0, 800.0);
+    let button = dom.els.iter().find(|e| e.text.as_deref() == Some("Pay now")).unwrap();
+    let alert = dom.els.iter().find(|e| e.alert_type.is_some()).unwrap();
+    assert!(button.b[1] < 800 && alert.b[1] >= 800, "{:?} {:?}", button.b, alert.b);
+
+    let filtered = dom.filter_above_fold();
+    assert!(has_text(&filtered, "Pay now"));
+    assert!(has_text(&filtered, "Your card was declined."));
+    assert!(!has_text(&filtered, "Terms of sale"));
+}
+
+#[test]
+fn test_above_fold_keeps_label_and_heading_scrolled_past() {
+    let mut dom = browsy_core::parse(CHECKOUT_HTML, 1920.0, 800.0);
+    let input = dom.els.iter().find(|e| e.name.as_deref() == Some("card")).unwrap();
+    // Scroll so the input's top edge is just out of view, above its label and heading
+    dom.scroll = [0.0, (input.b[1] + 5) as f32];
+
+    let filtered = dom.filter_above_fold();
+    assert!(filtered.els.iter().any(|e| e.name.as_deref() == Some("card")));
+    assert!(filtered.els.iter().any(|e| e.tag == "label" && e.text.as_deref() == Some("Card number")));
+    assert!(has_text(&filtered, "Payment details"));
+
+    let no_headings = dom.filter_above_fold_with(&output::FoldOptions {
+        heading_window: 0.0,
+        ..output::FoldOptions::default()
+    });
+    assert!(!has_text(&no_headings, "Payment details"));
+}
+
+#[test]
+fn test_fold_plus_extends_cutoff_by_half_a_viewport() {
+    let html = r#"<html><body style="margin: 0;">
+        <button>Top</button>
+        <div style="height: 1000px;"></div>
+        <button>Just past the fold</button>
+        <div style="height: 1000px;"></div>
+        <button>Far below</button>
+    </body></html>"#;
+    let dom = browsy_core::parse(html, 1920.0, 800.0);
+
+    assert!(!has_text(&dom.filter_above_fold(), "Just past the fold"));
+    let plus = dom.filter_fold_plus();
+    assert!(has_text(&plus, "Top"));
+    assert!(has_text(&plus, "Just past the fold"));
+    assert!(!has_text(&plus, "Far below"));
+}
+
+#[test]
+fn test_main_content_follows_main_landmark() {
+    let dom = browsy_core::parse(include_str!("fixtures/news_site.html"), 1920.0, 1080.0);
+    let main = dom.els.iter().find(|e| e.tag == "main").unwrap();
+    assert_eq!(dom.main_content_region(), Some(main.b));
+
+    let filtered = dom.filter_main_content();
+    for gone in ["World", "Politics", "Sport", "Lottery winner found after six months", "Privacy policy"] {
+        assert!(!has_text(&filtered, gone), "{gone}");
+    }
+    for kept in ["Harbor bridge reopens after two years of repairs", "Council votes to extend the night bus network"] {
+        assert!(filtered.els.iter().any(|e| e.tag == "a" && e.text.as_deref() == Some(kept)), "{kept}");
+    }
+    assert!(has_text(&filtered, "Top stories"));
+    assert_eq!(filtered.outside_main + filtered.els.len(), dom.els.len());
+    let compact = output::to_compact_string(&filtered);
+    assert_eq!(
+        compact.lines().next(),
+        Some(format!("[outside main: {} els]", filtered.outside_main).as_str())
+    );
+    assert!(output::format_legend().contains("[outside main: N els]"));
+}
+
+#[test]
+fn test_main_content_falls_back_to_densest_column() {
+    let dom = browsy_core::parse(include_str!("fixtures/no_landmarks.html"), 1920.0, 1080.0);
+    let region = dom.main_content_region().unwrap();
+    assert!(region[0] >= 290 && region[0] + region[2] <= 1210, "{region:?}");
+
+    let filtered = dom.filter_main_content();
+    assert!(has_text(&filtered, "Weeknight tomato soup"));
+    assert!(has_text(&filtered, "Print this recipe"));
+    for gone in ["Soups", "Desserts"] {
+        assert!(!has_text(&filtered, gone), "{gone}");
+    }
+    assert!(!filtered.els.iter().any(|e| e.text.as_deref().is_some_and(|t| t.starts_with("Copyright"))));
+    assert!(!filtered.els.iter().any(|e| e.text.as_deref().is_some_and(|t| t.starts_with("Recipe Box,"))));
+}
+
 #[test]
 fn test_hidden_dropdown_menu() {
     // Real-world pattern: nav dropdown where items are in DOM but hidden
@@ -643,6 +1022,81 @@ fn test_alert_detection_css_classes() {
     assert_eq!(warning.alert_type.as_deref(), Some("warning"));
 }
 
+#[test]
+fn test_red_text_next_to_a_field_is_an_error() {
+    let html = r#"
+    <html><head><style>
+        body { color: #222; }
+        .sale { color: rgb(220, 53, 69); font-weight: bold; }
+        .caption { color: #6c757d; font-size: 12px; }
+    </style></head><body>
+        <p class="sale">Sale ends tonight</p>
+        <div style="height: 300px;"></div>
+        <form>
+            <label for="card">Card number</label>
+            <input id="card" name="card" type="text">
+            <p style="color: #d32f2f; font-size: 13px; margin: 4px 0;">Invalid card number</p>
+            <p class="caption">We never store your card details.</p>
+            <button type="submit" style="background: #28a745; color: white;">Pay</button>
+        </form>
+    </body></html>
+    "#;
+
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+    let el = |text: &str| dom.els.iter().find(|e| e.text.as_deref() == Some(text)).unwrap();
+
+    let error = el("Invalid card number");
+    assert_eq!(error.tone, Some(output::Tone::Danger));
+    assert_eq!(error.alert_type.as_deref(), Some("error"));
+
+    // Grey captions and red text away from any field aren't errors
+    let caption = el("We never store your card details.");
+    assert_eq!(caption.tone, Some(output::Tone::Muted));
+    assert_eq!(caption.alert_type, None);
+    let sale = el("Sale ends tonight");
+    assert_eq!(sale.tone, Some(output::Tone::Danger));
+    assert_eq!(sale.alert_type, None);
+
+    assert_eq!(el("Card number").tone, Some(output::Tone::Normal));
+    assert_eq!(el("Pay").tone, Some(output::Tone::Success));
+    assert_eq!(dom.els.iter().find(|e| e.tag == "input").unwrap().tone, Some(output::Tone::Normal));
+
+    // JSON only
+    assert!(serde_json::to_string(&dom).unwrap().contains("\"tone\":\"Danger\""));
+    let mut plain = dom.clone();
+    plain.els.iter_mut().for_each(|e| e.tone = None);
+    assert_eq!(output::to_compact_string(&plain), output::to_compact_string(&dom));
+}
+
+/// The tone of a paragraph styled with `style`.
+fn tone(style: &str) -> Option<output::Tone> {
+    let html = format!(r#"<html><body><p style="{style}">Text</p></body></html>"#);
+    browsy_core::parse(&html, 1920.0, 1080.0).els[0].tone
+}
+
+#[test]
+fn test_text_colors_give_tones() {
+    for red in ["#d32f2f", "red", "crimson", "#b00020", "rgb(220, 53, 69)"] {
+        assert_eq!(tone(&format!("color: {red}")), Some(output::Tone::Danger), "{red}");
+    }
+    assert_eq!(tone("color: #28a745"), Some(output::Tone::Success));
+    assert_eq!(tone("color: #f39c12"), Some(output::Tone::Warning));
+    for grey in ["#6c757d", "#999", "gray", "rgba(0, 0, 0, .5)"] {
+        assert_eq!(tone(&format!("color: {grey}")), Some(output::Tone::Muted), "{grey}");
+    }
+    for plain in ["#222", "black", "#0645ad", "#ffcccc"] {
+        assert_eq!(tone(&format!("color: {plain}")), Some(output::Tone::Normal), "{plain}");
+    }
+    assert_eq!(tone(""), None);
+}
+
+#[test]
+fn test_background_tone_wins_over_text() {
+    assert_eq!(tone("color: white; background: #dc3545"), Some(output::Tone::Danger));
+    assert_eq!(tone("color: #721c24; background-color: #f8d7da"), Some(output::Tone::Danger));
+    assert_eq!(tone("color: #222; background: white"), Some(output::Tone::Normal));
+}
+
 #[test]
 fn test_table_extraction() {
     let html = r#"
@@ -1074,3 +1528,1038 @@ fn test_consent_action_detection() {
         assert!(!deny_ids.is_empty(), "Should have deny buttons");
     }
 }
+
+fn parse_with_selectors(html: &str) -> output::SpatialDom {
+    let dom_tree = browsy_core::dom::parse_html(html);
+    let styled = browsy_core::css::compute_styles_with_viewport(&dom_tree, 1920.0, 1080.0);
+    let laid_out = browsy_core::layout::compute_layout(&styled, 1920.0, 1080.0);
+    let options = output::OutputOptions { emit_selectors: true, ..Default::default() };
+    output::generate_spatial_dom_with_options(&laid_out, 1920.0, 1080.0, &options)
+}
+
+/// Count elements in `html` matched by a selector of the form browsy emits:
+/// `#id`, `tag[attr="v"]`, `tag`, `tag:nth-child(n)` joined by ` > `.
+fn count_selector_matches(html: &str, selector: &str) -> usize {
+    use browsy_core::dom::{DomNode, NodeType};
+
+    fn segment_matches(seg: &str, node: &DomNode, nth: usize) -> bool {
+        if let Some(id) = seg.strip_prefix('#') {
+            return node.get_attr("id") == Some(id);
+        }
+        if let Some((tag, rest)) = seg.split_once('[') {
+            let (attr, value) = rest.trim_end_matches(']').split_once('=').unwrap();
+            let value = value.trim_matches('"').replace("\\\"", "\"").replace("\\\\", "\\");
+            return node.tag == tag && node.get_attr(attr) == Some(value.as_str());
+        }
+        if let Some((tag, n)) = seg.split_once(":nth-child(") {
+            let n: usize = n.trim_end_matches(')').parse().unwrap();
+            return node.tag == tag && nth == n;
+        }
+        node.tag == seg
+    }
+
+    fn visit<'a>(node: &'a DomNode, nth: usize, chain: &mut Vec<(&'a DomNode, usize)>, segs: &[&str], count: &mut usize) {
+        if node.node_type == NodeType::Element {
+            chain.push((node, nth));
+            if chain.len() >= segs.len()
+                && segs
+                    .iter()
+                    .rev()
+                    .zip(chain.iter().rev())
+                    .all(|(seg, (n, i))| segment_matches(seg, n, *i))
+            {
+                *count += 1;
+            }
+        }
+        let mut child_nth = 0;
+        for child in &node.children {
+            if child.node_type == NodeType::Element {
+                child_nth += 1;
+            }
+            visit(child, child_nth, chain, segs, count);
+        }
+        if node.node_type == NodeType::Element {
+            chain.pop();
+        }
+    }
+
+    let root = browsy_core::dom::parse_html(html);
+    let segs: Vec<&str> = selector.split(" > ").collect();
+    let mut count = 0;
+    visit(&root, 0, &mut Vec::new(), &segs, &mut count);
+    count
+}
+
+#[test]
+fn test_selector_hints_unique() {
+    let html = r#"
+    <html><body>
+        <div id="login">
+            <input type="email" name="email" placeholder="Email" />
+            <input type="password" name="password" placeholder="Password" />
+            <button>Sign In</button>
+        </div>
+        <ul>
+            <li><a href="/a">First</a></li>
+            <li><a href="/b">Second</a></li>
+        </ul>
+        <form><input type="text" name="q" /></form>
+        <form><input type="text" name="q" /></form>
+        <button id="2fa:submit">Verify</button>
+        <p>Footer text</p>
+    </body></html>
+    "#;
+
+    let dom = parse_with_selectors(html);
+    assert!(!dom.els.is_empty());
+    for el in &dom.els {
+        let selector = el.selector.as_deref().unwrap_or_else(|| panic!("missing selector on {:?}", el));
+        assert_eq!(
+            count_selector_matches(html, selector),
+            1,
+            "selector {:?} for element {} ({}) must match exactly once",
+            selector,
+            el.id,
+            el.tag
+        );
+    }
+
+    let email = dom.els.iter().find(|e| e.name.as_deref() == Some("email")).unwrap();
+    assert_eq!(email.selector.as_deref(), Some("input[name=\"email\"]"));
+    let verify = dom.els.iter().find(|e| e.text.as_deref() == Some("Verify")).unwrap();
+    assert_eq!(verify.selector.as_deref(), Some("button[id=\"2fa:submit\"]"));
+
+    // Off by default
+    let plain = browsy_core::parse(html, 1920.0, 1080.0);
+    assert!(plain.els.iter().all(|e| e.selector.is_none()));
+}
+
+#[test]
+fn test_form_membership() {
+    let html = r#"
+    <html><body>
+        <h1>Checkout</h1>
+        <form action="/shipping" method="POST">
+            <label for="ship-name">Full name</label>
+            <input type="text" id="ship-name" name="name" />
+            <label for="ship-addr">Address</label>
+            <input type="text" id="ship-addr" name="address" />
+            <button type="submit">Save shipping</button>
+        </form>
+        <form action="/billing" method="post">
+            <label for="card">Card number</label>
+            <input type="text" id="card" name="card" />
+            <label for="bill-addr">Billing address</label>
+            <input type="text" id="bill-addr" name="address" />
+            <button type="submit">Pay now</button>
+        </form>
+        <footer>
+            <form action="/newsletter"><input type="email" name="email" placeholder="Email" /></form>
+        </footer>
+    </body></html>
+    "#;
+    let mut dom = browsy_core::parse(html, 1920.0, 1080.0);
+    output::resolve_urls(&mut dom, "https://shop.example.com/checkout");
+
+    let forms: Vec<_> = dom.els.iter().filter(|e| e.tag == "form").collect();
+    assert_eq!(forms.len(), 3);
+    assert_eq!(forms[0].form, Some(1));
+    assert_eq!(forms[0].action.as_deref(), Some("https://shop.example.com/shipping"));
+    assert_eq!(forms[0].method.as_deref(), Some("post"));
+    assert_eq!(forms[1].form, Some(2));
+    assert_eq!(forms[2].form, Some(3));
+    assert_eq!(forms[2].method, None);
+
+    let form_of = |label: &str| dom.get_by_label(label).and_then(|e| e.form);
+    assert_eq!(form_of("Full name"), Some(1));
+    assert_eq!(form_of("Address"), Some(1));
+    assert_eq!(form_of("Card number"), Some(2));
+    assert_eq!(form_of("Billing address"), Some(2));
+    let pay = dom.find_by_text("Pay now")[0];
+    assert_eq!(pay.form, Some(2));
+
+    // Text outside controls is not stamped
+    assert!(dom.find_by_text("Checkout")[0].form.is_none());
+
+    // One FillForm per form, each submitted by its own button
+    let fill_forms: Vec<_> = dom
+        .suggested_actions
+        .iter()
+        .filter_map(|a| match a {
+            output::SuggestedAction::FillForm { fields, submit_id } => Some((fields, *submit_id)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(fill_forms.len(), 2);
+    let save = dom.find_by_text("Save shipping")[0];
+    assert_eq!(fill_forms[0].1, save.id);
+    assert!(fill_forms[0].0.iter().all(|f| dom.get(f.id).unwrap().form == Some(1)));
+    assert_eq!(fill_forms[1].1, pay.id);
+    assert!(fill_forms[1].0.iter().all(|f| dom.get(f.id).unwrap().form == Some(2)));
+
+    // Compact output marks form fields when there are several forms
+    let compact = output::to_compact_string(&dom);
+    let card_line = compact.lines().find(|l| l.contains("[card]")).unwrap();
+    assert!(card_line.contains(" f2 "), "{card_line}");
+
+    let single = browsy_core::parse(
+        r#"<form><input type="text" name="q" /><button>Go</button></form>"#,
+        1920.0,
+        1080.0,
+    );
+    assert!(single.els.iter().any(|e| e.form == Some(1)));
+    assert!(!output::to_compact_string(&single).contains(" f1"));
+}
+
+#[test]
+fn test_product_from_json_ld_offers() {
+    let html = r#"<html><head><title>Trail Runner 2</title>
+        <script type="application/ld+json">
+        {"@context": "https://schema.org", "@type": "Product", "name": "Trail Runner 2",
+         "offers": {"@type": "Offer", "price": "129.00", "priceCurrency": "GBP"}}
+        </script></head><body>
+        <h1>Trail Runner 2</h1>
+        <p>Price</p><span>£129.00</span>
+        <p>Pay in 3 instalments of £43.00 with no fees or interest charged.</p>
+    </body></html>"#;
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+    assert_eq!(dom.page_type, output::PageType::Product);
+    let primary = dom.primary_price().unwrap();
+    assert_eq!((primary.amount, primary.currency.as_str()), (129.0, "GBP"));
+    assert_eq!(primary.context, "£129.00");
+
+    // Hits survive a JSON round trip
+    let json = serde_json::to_string(&dom).unwrap();
+    let back: output::SpatialDom = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.prices(), dom.prices());
+}
+
+#[test]
+fn test_prices_without_cart_are_not_a_product_page() {
+    let html = r#"<html><head><title>Notes</title></head><body>
+        <p>Lunch cost $12 today.</p>
+        <p>Was: $20</p>
+    </body></html>"#;
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+    assert_eq!(dom.page_type, output::PageType::Other);
+    let prices = dom.prices();
+    assert_eq!(prices.len(), 2);
+    assert_eq!(dom.primary_price().map(|p| p.amount), Some(12.0));
+    assert!(prices[1].original);
+}
+
+/// The prices read from a paragraph of `text`: amount, currency, and
+/// whether each is an original or per-unit price. Current prices come first.
+fn paragraph_prices(text: &str) -> Vec<(f64, String, bool, bool)> {
+    let dom = browsy_core::parse(&format!("<html><body><p>{text}</p></body></html>"), 1920.0, 1080.0);
+    dom.prices().into_iter().map(|p| (p.amount, p.currency, p.original, p.per_unit)).collect()
+}
+
+fn amounts(text: &str) -> Vec<(f64, String)> {
+    paragraph_prices(text).into_iter().map(|(amount, currency, _, _)| (amount, currency)).collect()
+}
+
+#[test]
+fn test_price_us_and_european_formats() {
+    let one = |amount: f64, currency: &str| vec![(amount, currency.to_string())];
+    assert_eq!(amounts("$1,299.00"), one(1299.0, "USD"));
+    assert_eq!(amounts("1.299,00 €"), one(1299.0, "EUR"));
+    assert_eq!(amounts("1 299,00\u{a0}€"), one(1299.0, "EUR"));
+    assert_eq!(amounts("CHF 1'299.50"), one(1299.5, "CHF"));
+    assert_eq!(amounts("€12,50"), one(12.5, "EUR"));
+    assert_eq!(amounts("£12.5"), one(12.5, "GBP"));
+    assert_eq!(amounts("1,234,567 USD"), one(1234567.0, "USD"));
+    assert_eq!(amounts("1.234.567,89 EUR"), one(1234567.89, "EUR"));
+
+    // One group of three digits is a thousands separator; other lengths are decimals
+    assert_eq!(amounts("$1,299"), one(1299.0, "USD"));
+    assert_eq!(amounts("€1.299"), one(1299.0, "EUR"));
+    assert_eq!(amounts("$0.125"), one(0.125, "USD"));
+    assert_eq!(amounts("12,5 €"), one(12.5, "EUR"));
+    assert_eq!(amounts("$19"), one(19.0, "USD"));
+}
+
+#[test]
+fn test_price_symbols_and_codes() {
+    let one = |amount: f64, currency: &str| vec![(amount, currency.to_string())];
+    assert_eq!(amounts("US$ 45"), one(45.0, "USD"));
+    assert_eq!(amounts("C$19.99"), one(19.99, "CAD"));
+    assert_eq!(amounts("R$ 1.299,90"), one(1299.9, "BRL"));
+    assert_eq!(amounts("49,99 zł"), one(49.99, "PLN"));
+    assert_eq!(amounts("¥1,200"), one(1200.0, "JPY"));
+    assert_eq!(amounts("From $5 to €10"), vec![(5.0, "USD".to_string()), (10.0, "EUR".to_string())]);
+    assert_eq!(amounts("12 $5"), one(5.0, "USD"));
+}
+
+#[test]
+fn test_numbers_without_currency_are_not_prices() {
+    assert!(amounts("Order 1,299 items in 3 days").is_empty());
+    assert!(amounts("4.5 out of 5 stars (1,024 reviews)").is_empty());
+    // Codes inside words aren't currencies
+    assert!(amounts("GEURO 12").is_empty());
+    // Symbol and number too far apart
+    assert!(amounts("$ - 12").is_empty());
+}
+
+#[test]
+fn test_was_prices_are_original() {
+    assert_eq!(
+        paragraph_prices("Was: $1,499.00 Now $1,299.00"),
+        vec![(1299.0, "USD".to_string(), false, false), (1499.0, "USD".to_string(), true, false)]
+    );
+    assert!(paragraph_prices("RRP £40").iter().all(|p| p.2));
+    assert!(paragraph_prices("List price: 1.299,00 €")[0].2);
+    assert!(!paragraph_prices("Now $12")[0].2);
+    assert!(!paragraph_prices("Kawas $12")[0].2, "marker must start a word");
+}
+
+#[test]
+fn test_per_unit_prices() {
+    assert_eq!(
+        paragraph_prices("£3.00 (£1.50/kg)"),
+        vec![(3.0, "GBP".to_string(), false, false), (1.5, "GBP".to_string(), false, true)]
+    );
+    assert!(paragraph_prices("1,20 € per 100 g")[0].3);
+    assert!(paragraph_prices("$0.25 / oz")[0].3);
+    assert!(paragraph_prices("Unit price: €4.99")[0].3);
+    // Billing periods aren't units
+    assert!(!paragraph_prices("$9.99/month")[0].3);
+    assert!(!paragraph_prices("$9.99/mo")[0].3);
+}
+
+#[test]
+fn test_zero_size_positioned_wrapper_keeps_its_button() {
+    let html = r#"<html><head><title>Checkout</title></head><body>
+        <div style="position: absolute; width: 0; height: 0;">
+            <form action="/pay" method="post">
+                <button type="submit" style="position: absolute; left: 40px; top: 300px;">Place order</button>
+            </form>
+        </div>
+        <div style="width: 0; height: 0;"></div>
+    </body></html>"#;
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+    let button = dom.els.iter().find(|e| e.tag == "button").expect("button should be emitted");
+    assert_eq!(button.text.as_deref(), Some("Place order"));
+    assert_eq!(button.form, Some(1));
+
+    // The empty 0x0 div is left out and counted
+    assert!(dom.parse_stats.zero_size_skipped >= 1, "{:?}", dom.parse_stats);
+    let json = serde_json::to_string(&dom).unwrap();
+    assert!(json.contains("\"zero_size_skipped\""));
+    let back: output::SpatialDom = serde_json::from_str(&json).unwrap();
+    assert_eq!(back.parse_stats, dom.parse_stats);
+}
+
+#[test]
+fn test_card_links_absorb_their_text_descendants() {
+    let html = include_str!("fixtures/card_grid.html");
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+    let count = |tag: &str| dom.els.iter().filter(|e| e.tag == tag).count();
+
+    // Each card used to add its h3, p and price span too: 19 elements
+    assert_eq!(dom.els.len(), 7, "{:#?}", dom.els);
+    assert_eq!((count("a"), count("h3"), count("p")), (4, 0, 0));
+    let lamp = dom.els.iter().find(|e| e.href.as_deref() == Some("/p/1")).unwrap();
+    let lamp_text = lamp.text.as_deref().unwrap();
+    assert!(lamp_text.starts_with("Desk lamp") && lamp_text.ends_with("Price: $9.99"), "{lamp_text}");
+    assert_eq!(output::to_compact_string(&dom).matches("$9.99").count(), 1);
+
+    // A badge with its own role still comes through
+    let badge = dom.els.iter().find(|e| e.tag == "span").unwrap();
+    assert_eq!(badge.role.as_deref(), Some("status"));
+    assert_eq!(badge.text.as_deref(), Some("Sold out"));
+}
+
+#[test]
+fn test_template_content_is_inert() {
+    let dom = browsy_core::parse(include_str!("fixtures/template_cards.html"), 1920.0, 1080.0);
+    assert!(dom.find_by_text("Remove").is_empty());
+    assert!(dom.find_by_text("View item").is_empty());
+    assert!(dom.find_by_text("Item name").is_empty());
+    let buttons: Vec<_> = dom.els.iter().filter(|e| e.tag == "button").collect();
+    assert_eq!(buttons.len(), 1);
+    assert_eq!(buttons[0].text.as_deref(), Some("Load saved items"));
+}
+
+#[test]
+fn test_noscript_content_is_visible() {
+    let dom = browsy_core::parse(include_str!("fixtures/noscript_nav.html"), 1920.0, 1080.0);
+    let notice = dom.find_by_text("works best with JavaScript")[0];
+    assert_eq!(notice.hidden, None);
+    assert!(notice.b[3] > 0);
+    for text in ["Guide", "API reference"] {
+        let link = dom.els.iter().find(|e| e.tag == "a" && e.text.as_deref() == Some(text)).unwrap();
+        assert_eq!(link.hidden, None, "{text}");
+    }
+    assert!(dom.els.iter().any(|e| e.tag == "nav" && e.hidden.is_none()));
+}
+
+#[test]
+fn test_source_positions_point_at_start_tags() {
+    let html = include_str!("fixtures/source_positions.html");
+    let dom = browsy_core::parse_with_positions(html, 1920.0, 1080.0);
+    let src_of = |tag: &str| dom.els.iter().find(|e| e.tag == tag).unwrap().src;
+
+    // The `<button>` in the comment and the one written by the script don't count
+    assert_eq!(src_of("h1"), Some([9, 3]));
+    assert_eq!(src_of("input"), Some([11, 5]));
+    assert_eq!(src_of("button"), Some([12, 5]));
+    assert_eq!(src_of("a"), Some([14, 17]));
+    let [line, column] = src_of("button").unwrap();
+    let tag_line = html.lines().nth(line as usize - 1).unwrap();
+    assert!(tag_line[column as usize - 1..].starts_with("<button type=\"submit\">"));
+    assert!(output::to_compact_string(&dom).contains("(12:5)"));
+
+    // Off by default
+    let plain = browsy_core::parse(html, 1920.0, 1080.0);
+    assert!(plain.els.iter().all(|e| e.src.is_none()));
+    assert!(!serde_json::to_string(&plain).unwrap().contains("\"src\""));
+}
+
+/// The first node with `tag` in document order.
+fn find_node<'a>(node: &'a browsy_core::dom::DomNode, tag: &str) -> Option<&'a browsy_core::dom::DomNode> {
+    if node.tag == tag {
+        return Some(node);
+    }
+    node.children.iter().find_map(|c| find_node(c, tag))
+}
+
+#[test]
+fn test_parser_inserted_elements_have_no_source_position() {
+    let tree = browsy_core::dom::parse_html_with_positions("<table>\n  <tr><td>Total</td></tr>\n</table>");
+    assert_eq!(find_node(&tree, "table").unwrap().src, Some([1, 1]));
+    assert_eq!(find_node(&tree, "tbody").unwrap().src, None);
+    assert_eq!(find_node(&tree, "tr").unwrap().src, Some([2, 3]));
+    assert_eq!(find_node(&tree, "td").unwrap().src, Some([2, 7]));
+    assert_eq!(find_node(&tree, "body").unwrap().src, None);
+}
+
+#[test]
+fn test_source_positions_skip_longer_tag_names_and_comments() {
+    let tree = browsy_core::dom::parse_html_with_positions("<!-- <a> --><abbr>x</abbr> <a href=#>y</a>");
+    assert_eq!(find_node(&tree, "abbr").unwrap().src, Some([1, 13]));
+    assert_eq!(find_node(&tree, "a").unwrap().src, Some([1, 28]));
+}
+
+#[test]
+fn test_summary_counts_fixture_page() {
+    let dom = browsy_core::parse(include_str!("fixtures/summary.html"), 1920.0, 1080.0);
+    let summary = dom.summary();
+    assert_eq!(summary.link_count, 4);
+    assert_eq!(summary.hidden_count, 1);
+    assert_eq!(summary.form_count, 1);
+    assert_eq!(summary.table_count, 1);
+    // Four links, two inputs and the button
+    assert_eq!(summary.interactive_count, 7);
+    for (role, count) in [("link", 4), ("textbox", 1), ("checkbox", 1), ("button", 1), ("heading", 1), ("navigation", 1)] {
+        assert_eq!(summary.counts_by_role.get(role), Some(&count), "{role}: {:?}", summary.counts_by_role);
+    }
+    assert_eq!(summary.counts_by_role.values().sum::<usize>(), dom.els.len());
+}
+
+fn garden_pages() -> (output::SpatialDom, output::SpatialDom) {
+    let mut first = browsy_core::parse(include_str!("fixtures/site_first.html"), 1920.0, 1080.0);
+    first.url = "https://garden.example/vegetables/tomatoes".to_string();
+    let mut second = browsy_core::parse(include_str!("fixtures/site_second.html"), 1920.0, 1080.0);
+    second.url = "https://garden.example/vegetables/peppers".to_string();
+    (first, second)
+}
+
+#[test]
+fn test_suppress_boilerplate_keeps_only_new_content() {
+    let (first, second) = garden_pages();
+    let total = second.els.len();
+    let fresh = output::suppress_boilerplate(second, &first);
+
+    let texts: Vec<&str> = fresh.els.iter().filter_map(|e| e.text.as_deref()).collect();
+    assert_eq!(
+        texts,
+        [
+            "Growing peppers",
+            "Peppers like it warmer than tomatoes do.",
+            "Keep the soil moist but never soggy."
+        ]
+    );
+    assert_eq!(fresh.boilerplate_suppressed + fresh.els.len(), total);
+
+    let compact = output::to_compact_string(&fresh);
+    let mut lines = compact.lines();
+    assert_eq!(
+        lines.next(),
+        Some(format!("[boilerplate: {} els suppressed]", fresh.boilerplate_suppressed).as_str())
+    );
+    assert_eq!(lines.count(), fresh.els.len());
+    assert!(output::format_legend().contains("[boilerplate: N els suppressed]"));
+}
+
+#[test]
+fn test_suppress_boilerplate_keeps_form_fields_the_footer_lacks() {
+    let footer = r#"<footer><form action="/newsletter">
+        <input type="email" name="newsletter"><button>Subscribe</button>
+    </form></footer>"#;
+    let mut home = browsy_core::parse(&format!("<html><body><h1>Home</h1>{footer}</body></html>"), 1920.0, 1080.0);
+    home.url = "https://shop.example/".to_string();
+    let mut checkout = browsy_core::parse(
+        &format!(
+            r#"<html><body><h1>Checkout</h1><form action="/pay">
+                <label for="email">Receipt email</label><input type="email" id="email" name="email">
+                <button>Pay</button>
+            </form>{footer}</body></html>"#
+        ),
+        1920.0,
+        1080.0,
+    );
+    checkout.url = "https://shop.example/checkout".to_string();
+
+    let fresh = output::suppress_boilerplate(checkout, &home);
+    let emails: Vec<Option<&str>> =
+        fresh.els.iter().filter(|e| e.input_type.as_deref() == Some("email")).map(|e| e.name.as_deref()).collect();
+    assert_eq!(emails, [Some("email")], "only the footer's field is boilerplate");
+}
+
+#[test]
+fn test_suppress_boilerplate_needs_same_host() {
+    let (first, mut second) = garden_pages();
+    second.url = "https://other.example/peppers".to_string();
+    let total = second.els.len();
+    let kept = output::suppress_boilerplate(second, &first);
+    assert_eq!(kept.els.len(), total);
+    assert_eq!(kept.boilerplate_suppressed, 0);
+    assert!(!output::to_compact_string(&kept).contains("boilerplate"));
+}
+
+/// The kind of marker a compact token is, as the legend writes it.
+fn marker_kind(token: &str) -> String {
+    let kind = if token.starts_with("->") {
+        "->url"
+    } else if token.starts_with("[=") {
+        "[=value]"
+    } else if token == "[v]" || token == "[*]" {
+        token
+    } else if token.starts_with('[') {
+        "[name]"
+    } else if token.starts_with('(') {
+        "(line:col)"
+    } else if token.len() > 1 && token.starts_with('f') && token[1..].chars().all(|c| c.is_ascii_digit()) {
+        "fN"
+    } else {
+        // Size hints and @regions stand for themselves
+        token
+    };
+    kind.to_string()
+}
+
+#[test]
+fn test_legend_explains_every_compact_marker() {
+    let dom = browsy_core::parse_with_positions(include_str!("fixtures/compact_markers.html"), 1920.0, 1080.0);
+    let compact = output::to_compact_string(&dom);
+    let legend = output::format_legend();
+
+    let mut seen = std::collections::BTreeSet::new();
+    for line in compact.lines() {
+        let inner = &line[1..line.len() - 1];
+        // Drop quoted text, which is page content rather than markup
+        let unquoted: String = inner.split('"').step_by(2).collect::<Vec<_>>().join(" ");
+        let mut tokens = unquoted.split_whitespace();
+        let head = tokens.next().unwrap();
+        for (prefix, kind) in [("!", "!id"), ("^", "^id")] {
+            if head.contains(prefix) {
+                seen.insert(kind.to_string());
+            }
+        }
+        if head.matches(':').count() == 2 {
+            seen.insert("id:tag:type".to_string());
+        }
+        seen.extend(tokens.map(marker_kind));
+    }
+
+    for kind in [
+        "!id", "^id", "id:tag:type", "fN", "[name]", "[v]", "[*]", "[=value]", "->url", "narrow", "wide", "full",
+        "(line:col)",
+    ] {
+        assert!(seen.contains(kind), "fixture should produce {kind}: {compact}");
+    }
+    assert!(seen.iter().any(|kind| kind.starts_with('@')), "fixture should produce a region: {compact}");
+    for kind in &seen {
+        assert!(legend.contains(kind.as_str()), "legend lacks {kind}:\n{legend}");
+    }
+}
+
+#[test]
+fn test_datalist_suggestions_are_capped() {
+    let dom = browsy_core::parse(include_str!("fixtures/countries.html"), 1920.0, 1080.0);
+    let country = dom.els.iter().find(|e| e.name.as_deref() == Some("country")).unwrap();
+    let suggestions = country.suggestions.as_ref().unwrap();
+    assert_eq!(suggestions.len(), 200);
+    assert_eq!(suggestions[..2], ["Afghanistan", "Albania"]);
+    assert_eq!(country.more_suggestions, Some(180));
+    let city = dom.els.iter().find(|e| e.name.as_deref() == Some("city")).unwrap();
+    assert_eq!(city.suggestions, None);
+
+    let json: serde_json::Value = serde_json::to_value(country).unwrap();
+    assert_eq!(json["suggestions"].as_array().unwrap().len(), 20);
+    assert_eq!(json["suggestions"][19], "Belgium");
+    assert_eq!(json["more_suggestions"], 180);
+
+    let compact = output::to_compact_string(&dom);
+    let line = compact.lines().find(|l| l.contains("[country]")).unwrap();
+    assert!(line.contains("{Afghanistan|Albania|"), "{line}");
+    assert!(line.contains("|Belgium|+180}"), "{line}");
+    assert!(!line.contains("Belize"), "{line}");
+}
+
+#[test]
+fn test_form_fields_carry_help_text() {
+    let dom = browsy_core::parse(include_str!("fixtures/help_text.html"), 1920.0, 1080.0);
+    let desc = |name: &str| {
+        dom.els
+            .iter()
+            .find(|e| e.name.as_deref() == Some(name))
+            .unwrap_or_else(|| panic!("no field {name}"))
+            .desc
+            .as_deref()
+    };
+
+    // aria-describedby joins every element it names, hidden or not
+    assert_eq!(desc("password"), Some("Must be at least 12 characters. Spaces are allowed."));
+    // A short line right below the field
+    assert_eq!(desc("birthday"), Some("Use format DD/MM/YYYY"));
+    // The next field's label, by <label for> or by sitting right above it, is not help text
+    assert_eq!(desc("city"), None);
+    assert_eq!(desc("phone"), None);
+
+    let compact = output::to_compact_string(&dom);
+    let line = compact.lines().find(|l| l.contains("[birthday]")).unwrap();
+    assert!(line.ends_with("(Use format DD/MM/YYYY)]"), "{line}");
+    assert!(!compact.contains("(Country)"), "{compact}");
+}
+
+#[test]
+fn test_compact_escapes_quotes_and_keeps_one_line_per_element() {
+    let dom = browsy_core::parse(include_str!("fixtures/quotes.html"), 1920.0, 1080.0);
+    let compact = output::to_compact_string(&dom);
+    assert_eq!(compact.lines().count(), dom.els.len(), "{compact}");
+    assert!(
+        compact.contains(r#":blockquote "\"The best book on gardening I have ever read,\" she said. \"Buy it.\""]"#),
+        "{compact}"
+    );
+    assert!(compact.contains(r#""Paths look like C:\\Users\\ada and tabs are spaces""#), "{compact}");
+    assert!(compact.contains(r#""line one line two""#), "{compact}");
+
+    // Every line reads back to the element it came from
+    for (line, el) in compact.lines().zip(&dom.els) {
+        let parsed = output::parse_compact_line(line).unwrap_or_else(|| panic!("unreadable: {line}"));
+        assert_eq!(parsed.id, el.id, "{line}");
+        assert_eq!(parsed.tag, el.tag, "{line}");
+        let shown = el.text.as_ref().or(el.ph.as_ref()).map(|t| t.split_whitespace().collect::<Vec<_>>().join(" "));
+        assert_eq!(parsed.text, shown, "{line}");
+        assert_eq!(parsed.href, el.href, "{line}");
+        assert_eq!(parsed.name, el.name, "{line}");
+    }
+}
+
+#[test]
+fn test_parse_compact_line_reads_every_part() {
+    let line = r#"[!^12:input:email f2 [email] [v] [*] [=ada@example.com] {a|b|+3} "Your \"work\" email" ->/x wide @top-R #"use the \"work\" one" (Used for receipts) (4:7)]"#;
+    let el = output::parse_compact_line(line).unwrap();
+    assert_eq!(
+        el,
+        output::CompactElement {
+            id: 12,
+            tag: "input".into(),
+            input_type: Some("email".into()),
+            hidden: true,
+            pinned: true,
+            form: Some(2),
+            name: Some("email".into()),
+            checked: true,
+            required: true,
+            val: Some("ada@example.com".into()),
+            suggestions: vec!["a".into(), "b".into()],
+            more_suggestions: 3,
+            text: Some(r#"Your "work" email"#.into()),
+            href: Some("/x".into()),
+            size: Some("wide".into()),
+            region: Some("top-R".into()),
+            note: Some(r#"use the "work" one"#.into()),
+            desc: Some("Used for receipts".into()),
+            src: Some([4, 7]),
+            ..Default::default()
+        }
+    );
+
+    let cut = output::parse_compact_line("[3:p \"Terms of\u{2026}\" (1.4k chars)]").unwrap();
+    assert_eq!(cut.text.as_deref(), Some("Terms of\u{2026}"));
+    assert!(output::parse_compact_line("[+4:a \"New\" ->/new]").unwrap().changed);
+    assert_eq!(output::parse_compact_line("[boilerplate: 12 els suppressed]"), None);
+    assert_eq!(output::parse_compact_line("[5:p \"unterminated]"), None);
+}
+
+#[test]
+fn test_text_source_tells_visible_text_from_accessible_names() {
+    use browsy_core::output::TextSource;
+
+    let html = r#"<html><body>
+        <button>Save draft</button>
+        <button aria-label="Close"><span class="icon"></span></button>
+        <a href="/help" title="Help center"></a>
+        <a href="/"><img src="logo.png" alt="Acme home"></a>
+        <button><svg viewBox="0 0 24 24"><title>Search</title><path d="M0 0"/></svg></button>
+        <img src="chart.png" alt="Sales by month">
+    </body></html>"#;
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+    let source = |text: &str| {
+        dom.els
+            .iter()
+            .find(|e| e.text.as_deref() == Some(text))
+            .unwrap_or_else(|| panic!("no element with text {text}"))
+            .text_source
+    };
+    assert_eq!(source("Save draft"), Some(TextSource::Visible));
+    assert_eq!(source("Close"), Some(TextSource::AriaLabel));
+    assert_eq!(source("Help center"), Some(TextSource::Title));
+    assert_eq!(source("Acme home"), Some(TextSource::ImgAlt));
+    assert_eq!(source("Search"), Some(TextSource::SvgTitle));
+    assert_eq!(source("Sales by month"), Some(TextSource::ImgAlt));
+
+    let compact = output::to_compact_string(&dom);
+    // Buttons may be followed by their size hint
+    assert!(compact.contains(r#":button "Save draft""#), "{compact}");
+    assert!(compact.contains(r#":button ~"Close""#), "{compact}");
+    assert!(compact.contains(r#":a ~"Help center" ->/help]"#), "{compact}");
+    let close = compact.lines().find(|l| l.contains("Close")).unwrap();
+    assert!(output::parse_compact_line(close).unwrap().name_only);
+
+    // Accessible names are still found by text
+    assert_eq!(dom.find_by_text("close").len(), 1);
+
+    // JSON leaves out the source of visible text, and reading it back restores it
+    let json = serde_json::to_string(&dom).unwrap();
+    assert!(json.contains(r#""text_source":"AriaLabel""#), "{json}");
+    assert!(!json.contains(r#""Visible""#), "{json}");
+    let back = output::SpatialDom::from_json(&json).unwrap();
+    assert_eq!(back.els, dom.els);
+}
+
+#[test]
+fn test_inputs_carry_their_purpose() {
+    let dom = browsy_core::parse(include_str!("fixtures/checkout.html"), 1920.0, 1080.0);
+    let field = |name: &str| dom.els.iter().find(|e| e.name.as_deref() == Some(name)).unwrap();
+    let purpose = |name: &str| field(name).purpose.as_deref();
+
+    assert_eq!(purpose("q"), Some("search"));
+    assert_eq!(purpose("contact"), Some("email"));
+    assert_eq!(purpose("mobile"), Some("phone"));
+    assert_eq!(purpose("ship_line1"), Some("address"));
+    assert_eq!(purpose("cc"), Some("credit_card_number"));
+    assert_eq!(purpose("pw"), Some("password"));
+    assert_eq!(field("ship_line1").autocomplete.as_deref(), Some("shipping address-line1"));
+
+    // Codes that aren't verification codes, and fields nothing points at
+    for name in ["zip", "cvc", "promo", "gift_message", "newsletter"] {
+        assert_eq!(purpose(name), None, "{name}");
+    }
+
+    let compact = output::to_compact_string(&dom);
+    let line = |name: &str| compact.lines().find(|l| l.contains(&format!("[{name}]"))).unwrap().to_string();
+    assert!(line("cc").contains("[cc] (credit_card_number)"), "{compact}");
+    assert!(!line("pw").contains("(password)"), "the type already says it: {compact}");
+    assert!(!line("promo").contains('('), "{compact}");
+    let parsed = output::parse_compact_line(&line("mobile")).unwrap();
+    assert_eq!(parsed.purpose.as_deref(), Some("phone"));
+}
+
+#[test]
+fn test_auth_state_from_account_menu_and_sign_in_links() {
+    use browsy_core::output::AuthState;
+
+    // A collapsed account menu with "Sign out" next to "Sign in as a different user"
+    let dom = browsy_core::parse(include_str!("fixtures/account_menu.html"), 1920.0, 1080.0);
+    assert_eq!(
+        dom.auth_state(),
+        AuthState::LoggedIn { account_hint: Some("octocat".to_string()) }
+    );
+
+    // "Hello World" isn't a greeting and a mailto link isn't an account
+    let dom = browsy_core::parse(include_str!("fixtures/signed_out.html"), 1920.0, 1080.0);
+    assert_eq!(dom.auth_state(), AuthState::LoggedOut);
+
+    let dom = browsy_core::parse(
+        r#"<html><body><header><span>ada@example.com</span></header><main><p>Dashboard</p></main></body></html>"#,
+        1920.0,
+        1080.0,
+    );
+    assert_eq!(
+        dom.auth_state(),
+        AuthState::LoggedIn { account_hint: Some("ada@example.com".to_string()) }
+    );
+
+    let dom = browsy_core::parse("<html><body><p>Nothing to see</p></body></html>", 1920.0, 1080.0);
+    assert_eq!(dom.auth_state(), AuthState::Unknown);
+}
+
+#[test]
+fn test_ascii_map_snapshot() {
+    let dom = output::SpatialDom::from_json(include_str!("fixtures/map_page.json")).unwrap();
+    let map = output::to_ascii_map(&dom, 40, 20);
+    assert_eq!(map, include_str!("fixtures/map_page.map").trim_end());
+    // Same page, same map
+    assert_eq!(output::to_ascii_map(&dom, 40, 20), map);
+    assert_eq!(output::to_ascii_map(&dom, 0, 20), "");
+}
+
+#[test]
+fn test_hidden_reasons_per_scope() {
+    use output::{HiddenReason, DEFAULT_HIDDEN_DROP};
+
+    let dom = browsy_core::parse(include_str!("fixtures/hidden_reasons.html"), 1920.0, 1080.0);
+    let texts = |dom: &output::SpatialDom| -> Vec<String> { dom.els.iter().filter_map(|e| e.text.clone()).collect() };
+    let el = |text: &str| dom.els.iter().find(|e| e.text.as_deref() == Some(text)).unwrap();
+
+    let cases = [
+        ("Your profile", None),
+        ("Decorative banner", Some(HiddenReason::AriaHidden)),
+        ("Settings", Some(HiddenReason::Display)),
+        ("Saved", Some(HiddenReason::Visibility)),
+        ("Restore draft", Some(HiddenReason::HiddenAttr)),
+        ("Skip to main content", Some(HiddenReason::OffscreenSrOnly)),
+        ("Opens in a new window", Some(HiddenReason::OffscreenSrOnly)),
+        ("3 unread messages", Some(HiddenReason::OffscreenSrOnly)),
+        // Hidden for its own reason inside screen-reader-only text
+        ("Never shown", Some(HiddenReason::Display)),
+    ];
+    for (text, reason) in cases {
+        assert_eq!(el(text).hidden_reason, reason, "{text}");
+        let hides = reason.is_some_and(|r| r != HiddenReason::OffscreenSrOnly);
+        assert_eq!(el(text).hidden == Some(true), hides, "{text}");
+    }
+
+    // visible: aria-hidden decoration goes, the rest stays marked hidden
+    let visible = dom.strip_hidden(DEFAULT_HIDDEN_DROP);
+    let kept = texts(&visible);
+    assert!(!kept.contains(&"Decorative banner".to_string()), "{kept:?}");
+    for text in ["Settings", "Saved", "Restore draft", "Skip to main content", "Your profile"] {
+        assert!(kept.contains(&text.to_string()), "{text}: {kept:?}");
+    }
+    let compact = output::to_compact_string(&visible);
+    let settings = compact.lines().find(|l| l.contains("\"Settings\"")).unwrap();
+    assert!(settings.starts_with("[!"), "{settings}");
+    let skip = compact.lines().find(|l| l.contains("\"Skip to main content\"")).unwrap();
+    assert!(!skip.starts_with("[!"), "{skip}");
+
+    // Chosen reasons
+    let kept = texts(&dom.strip_hidden(&[HiddenReason::Display, HiddenReason::OffscreenSrOnly]));
+    for text in ["Settings", "Never shown", "Skip to main content", "3 unread messages"] {
+        assert!(!kept.contains(&text.to_string()), "{text}: {kept:?}");
+    }
+    assert!(kept.contains(&"Decorative banner".to_string()), "{kept:?}");
+
+    // on_screen: everything hidden goes, screen-reader-only text stays
+    let kept = texts(&dom.filter_on_screen());
+    for text in ["Decorative banner", "Settings", "Saved", "Restore draft", "Never shown"] {
+        assert!(!kept.contains(&text.to_string()), "{text}: {kept:?}");
+    }
+    for text in ["Your profile", "Skip to main content", "Opens in a new window", "3 unread messages"] {
+        assert!(kept.contains(&text.to_string()), "{text}: {kept:?}");
+    }
+
+    assert_eq!("aria-hidden".parse::<HiddenReason>(), Ok(HiddenReason::AriaHidden));
+    assert!("offscreen".parse::<HiddenReason>().is_err());
+}
+
+#[test]
+fn test_heading_levels_and_list_depth() {
+    let html = r#"<html><body>
+        <h1>Guide</h1>
+        <div role="heading" aria-level="3">Setup</div>
+        <div role="heading">Untitled</div>
+        <h2 aria-level="4">Details</h2>
+        <ul>
+            <li>Alpha<ol><li>Beta<ul><li>Gamma</li></ul></li></ol></li>
+            <li>Delta</li>
+        </ul>
+        <div role="list"><div role="listitem">Epsilon</div></div>
+        <p>Body</p>
+    </body></html>"#;
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+    let el = |text: &str| {
+        dom.els.iter().find(|e| e.text.as_deref().is_some_and(|t| t.starts_with(text))).unwrap()
+    };
+
+    // aria-level wins over the tag; role=heading alone is level 2
+    for (text, level) in [("Guide", 1), ("Setup", 3), ("Untitled", 2), ("Details", 4)] {
+        assert_eq!(el(text).level, Some(level), "{text}");
+    }
+    // The role="list" wrapper carries Epsilon's text too, so look for the item
+    let item = |text: &str| {
+        dom.els
+            .iter()
+            .filter(|e| e.tag == "li" || e.role.as_deref() == Some("listitem"))
+            .find(|e| e.text.as_deref().is_some_and(|t| t.starts_with(text)))
+            .unwrap()
+    };
+    for (text, level) in [("Alpha", 1), ("Beta", 2), ("Gamma", 3), ("Delta", 1), ("Epsilon", 1)] {
+        assert_eq!(item(text).level, Some(level), "{text}");
+    }
+    assert_eq!(el("Body").level, None);
+
+    let headings: Vec<(&str, Option<u8>)> =
+        dom.headings().iter().map(|e| (e.text.as_deref().unwrap(), e.level)).collect();
+    assert_eq!(
+        headings,
+        vec![("Guide", Some(1)), ("Setup", Some(3)), ("Untitled", Some(2)), ("Details", Some(4))]
+    );
+
+    let json = serde_json::to_value(el("Gamma")).unwrap();
+    assert_eq!(json["level"], 3);
+    assert!(serde_json::to_value(el("Body")).unwrap().get("level").is_none());
+
+    // Compact lines only add the level where the tag doesn't say it
+    let compact = output::to_compact_string(&dom);
+    let line = |text: &str| compact.lines().find(|l| l.contains(&format!("\"{text}"))).unwrap();
+    assert!(!line("Guide").contains(" h1"), "{}", line("Guide"));
+    assert_eq!(output::parse_compact_line(line("Setup")).unwrap().level, Some(3));
+    assert_eq!(output::parse_compact_line(line("Details")).unwrap().level, Some(4));
+    assert_eq!(output::parse_compact_line(line("Gamma")).unwrap().level, None);
+}
+
+fn action_kinds(dom: &output::SpatialDom) -> Vec<String> {
+    dom.suggested_actions
+        .iter()
+        .map(|a| serde_json::to_value(a).unwrap()["action"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn test_code_form_on_an_error_page_is_one_action() {
+    // The error makes this an Error page, where FillForm used to fire on the
+    // same form as EnterCode
+    let html = r#"<html><head><title>Confirm your account</title></head><body>
+        <h1>Verify your email</h1>
+        <p class="alert-error">That code has expired. Enter the new one we sent.</p>
+        <form>
+            <label for="email">Email</label>
+            <input id="email" name="email" type="email">
+            <label for="code">Verification code</label>
+            <input id="code" name="code" type="text">
+            <button type="submit">Verify</button>
+        </form>
+    </body></html>"#;
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+    assert_eq!(dom.page_type, output::PageType::Error);
+    assert_eq!(action_kinds(&dom), ["EnterCode"]);
+    let output::SuggestedAction::EnterCode { input_id, confidence, .. } = &dom.suggested_actions[0] else {
+        unreachable!()
+    };
+    assert_eq!(dom.get(*input_id).unwrap().name.as_deref(), Some("code"));
+    assert_eq!(*confidence, None);
+}
+
+#[test]
+fn test_guessed_code_input_ranks_after_confident_actions() {
+    // Nothing marks the input as a code field; it's the only one on a 2FA page
+    let html = r#"<html><head><title>Two-factor authentication</title></head><body>
+        <h1>Two-factor authentication</h1>
+        <form>
+            <label for="token">Token</label>
+            <input id="token" name="token" type="text">
+            <button type="submit">Continue</button>
+        </form>
+        <div style="margin-top: 400px">
+            <p>We use cookies to keep you signed in and to understand how the site is used.</p>
+            <button>Accept all</button>
+        </div>
+    </body></html>"#;
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+    assert_eq!(action_kinds(&dom), ["CookieConsent", "EnterCode"]);
+    assert_eq!(dom.suggested_actions[1].confidence(), Some(0.5));
+    let json = serde_json::to_value(&dom.suggested_actions[1]).unwrap();
+    assert_eq!(json["confidence"], 0.5);
+    assert!(serde_json::to_value(&dom.suggested_actions[0]).unwrap().get("confidence").is_none());
+}
+
+#[test]
+fn test_element_neighborhood_on_a_card_grid() {
+    let dom = browsy_core::parse(include_str!("fixtures/product_cards.html"), 1920.0, 1080.0);
+    let el = |text: &str| dom.els.iter().find(|e| e.text.as_deref() == Some(text)).unwrap();
+    let texts = |els: Vec<&output::SpatialElement>| -> Vec<String> {
+        els.into_iter().map(|e| e.text.clone().unwrap_or_default()).collect()
+    };
+
+    // The card's title, price and button, but not the next card's on the same line
+    let price = el("$89.00");
+    assert_eq!(texts(dom.row_of(price.id)), ["Bookshelf", "$89.00", "Add to cart"]);
+    assert_eq!(dom.left_of(price.id).unwrap().text.as_deref(), Some("Bookshelf"));
+    assert!(dom.left_of(el("Bookshelf").id).is_none());
+
+    let card = dom.container_of(price.id).unwrap();
+    assert_eq!((card.tag.as_str(), card.role.as_deref()), ("section", Some("region")));
+    assert!(dom.row_of(card.id).iter().all(|e| e.tag == "section"), "a card's row is other cards");
+    let near = dom.neighbors(price.id, 20);
+    assert_eq!(texts(near), ["Bookshelf", "Add to cart"]);
+    assert!(dom.neighbors(price.id, 2000).iter().all(|e| e.id != card.id));
+
+    let email = dom.els.iter().find(|e| e.name.as_deref() == Some("email")).unwrap();
+    assert_eq!(dom.above(email.id).unwrap().text.as_deref(), Some("Get the weekly deals"));
+    assert_eq!(dom.container_of(email.id).unwrap().role.as_deref(), Some("contentinfo"));
+    assert_eq!(dom.left_of(el("Subscribe").id).unwrap().id, email.id);
+
+    let context = dom.context(price.id, 20).unwrap();
+    let json = serde_json::to_value(&context).unwrap();
+    assert_eq!(json["element"]["text"], "$89.00");
+    assert_eq!(json["container"]["id"], card.id);
+    assert_eq!(json["left"]["text"], "Bookshelf");
+    assert_eq!(json["row"].as_array().unwrap().len(), 3);
+    assert!(dom.context(9999, 20).is_none());
+}
+
+#[test]
+fn test_actions_keep_the_more_specific_in_a_fixed_order() {
+    // The list doesn't follow the document, and the login form isn't a
+    // FillForm too
+    let html = r#"<html><head><title>Store</title></head><body>
+        <form action="/search" role="search">
+            <input type="search" name="q" placeholder="Search products">
+            <button type="submit">Search</button>
+        </form>
+        <div class="cookie-banner">
+            <p>We use cookies to improve your experience.</p>
+            <button>Accept all</button>
+        </div>
+        <form action="/session" method="post">
+            <input type="email" name="email" placeholder="Email">
+            <input type="password" name="password" placeholder="Password">
+            <button type="submit">Sign in</button>
+        </form>
+        <ul><li><a href="/p/1">Lamp</a></li><li><a href="/p/2">Chair</a></li></ul>
+        <button>Load more</button>
+    </body></html>"#;
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+    assert_eq!(action_kinds(&dom), ["Login", "CookieConsent", "Search", "LoadMore"]);
+}
+
+#[test]
+fn test_suggested_actions_are_capped_dropping_the_least_specific() {
+    let forms: String = (0..10)
+        .map(|i| {
+            format!(
+                r#"<form action="/form{i}" method="post">
+                    <input type="text" name="first{i}" placeholder="First">
+                    <input type="text" name="second{i}" placeholder="Second">
+                    <button type="submit">Send {i}</button>
+                </form>"#
+            )
+        })
+        .collect();
+    let html = format!(
+        r#"<html><head><title>Forms</title></head><body>{forms}
+        <div class="cookie-banner">
+            <p>We use cookies to improve your experience.</p>
+            <button>Accept all</button>
+        </div>
+        </body></html>"#
+    );
+    let dom = browsy_core::parse(&html, 1920.0, 1080.0);
+    assert_eq!(dom.suggested_actions.len(), output::MAX_SUGGESTED_ACTIONS);
+    assert_eq!(action_kinds(&dom)[0], "CookieConsent");
+}
@@ -0,0 +1,117 @@
+//! `Session::paginate_collect`: following next links across a local server's
+//! pages and merging what each one has.
+
+#![cfg(feature = "fetch")]
+
+mod common;
+
+use std::time::Instant;
+
+use browsy_core::fetch::{Extract, FetchError, Session, SessionConfig, StopReason};
+use common::Response;
+
+/// Three pages of a price table, linked by "Next"; two pages whose next
+/// links point at each other; and a page whose next link is missing.
+/// Returns the port.
+fn serve() -> u16 {
+    common::serve(|request| {
+        let path = request.path.as_str();
+        match path {
+            "/prices/1" | "/prices/2" | "/prices/3" => {
+                let n: usize = path[8..].parse().unwrap();
+                let rows: String = (1..=2)
+                    .map(|i| format!("<tr><td>Item {n}.{i}</td><td>${n}{i}</td></tr>"))
+                    .collect();
+                let next = if n < 3 { format!(r#"<a href="/prices/{}">Next</a>"#, n + 1) } else { String::new() };
+                Response::html(format!(
+                    r#"<html><head><title>Prices {n}</title></head><body>
+                    <table><tr><th>Item</th><th>Price</th></tr>{rows}</table>
+                    <nav><a href="/prices/1">1</a> <a href="/prices/2">2</a> <a href="/prices/3">3</a> {next}</nav>
+                    </body></html>"#
+                ))
+            }
+            "/a" | "/b" => {
+                let other = if path == "/a" { "/b" } else { "/a#top" };
+                Response::html(format!(
+                    r#"<html><head><title>{path}</title></head><body>
+                    <ul><li>From {path}</li></ul>
+                    <a href="/about">About</a> <a href="{other}">Next</a>
+                    </body></html>"#
+                ))
+            }
+            "/broken" => Response::html(
+                r#"<html><head><title>Broken</title></head><body><ul><li>Only</li></ul><a href="/gone">Next</a></body></html>"#,
+            ),
+            _ => Response::empty("404 Not Found"),
+        }
+    })
+}
+
+fn session(per_host_delay_ms: u64) -> Session {
+    Session::with_config(SessionConfig {
+        fetch_css: false,
+        allow_private_network: true,
+        per_host_delay_ms,
+        ..SessionConfig::default()
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_collects_table_rows_across_pages() {
+    let port = serve();
+    let mut session = session(50);
+    assert!(matches!(session.paginate_collect(5, Extract::Tables), Err(FetchError::NoPageLoaded)));
+
+    session.goto(&format!("http://localhost:{port}/prices/1")).unwrap();
+    let started = Instant::now();
+    let result = session.paginate_collect(10, Extract::Tables).unwrap();
+    // Two more pages, each waiting out the per-host delay
+    assert!(started.elapsed().as_millis() >= 100, "{:?}", started.elapsed());
+
+    assert_eq!(result.stop_reason, StopReason::Exhausted);
+    assert_eq!(result.headers, vec!["page_index", "Item", "Price"]);
+    assert_eq!(
+        result.rows,
+        vec![
+            vec!["0", "Item 1.1", "$11"],
+            vec!["0", "Item 1.2", "$12"],
+            vec!["1", "Item 2.1", "$21"],
+            vec!["1", "Item 2.2", "$22"],
+            vec!["2", "Item 3.1", "$31"],
+            vec!["2", "Item 3.2", "$32"],
+        ]
+    );
+    let expected: Vec<String> = (1..=3).map(|n| format!("http://localhost:{port}/prices/{n}")).collect();
+    assert_eq!(result.visited, expected);
+    assert_eq!(session.dom().unwrap().title, "Prices 3");
+
+    session.goto(&format!("http://localhost:{port}/prices/1")).unwrap();
+    let result = session.paginate_collect(2, Extract::Tables).unwrap();
+    assert_eq!(result.stop_reason, StopReason::MaxPages);
+    assert_eq!(result.visited.len(), 2);
+    assert_eq!(result.rows.len(), 4);
+}
+
+#[test]
+fn test_stops_at_loops_and_errors() {
+    let port = serve();
+    let mut session = session(0);
+
+    session.goto(&format!("http://localhost:{port}/a")).unwrap();
+    let result = session.paginate_collect(10, Extract::Lists).unwrap();
+    assert_eq!(result.stop_reason, StopReason::LoopDetected);
+    assert_eq!(result.headers, vec!["page_index", "text"]);
+    assert_eq!(result.rows, vec![vec!["0", "From /a"], vec!["1", "From /b"]]);
+
+    // The pager's own links aren't collected
+    session.goto(&format!("http://localhost:{port}/a")).unwrap();
+    let result = session.paginate_collect(1, Extract::Links).unwrap();
+    assert_eq!(result.rows, vec![vec!["0".to_string(), "About".to_string(), format!("http://localhost:{port}/about")]]);
+
+    session.goto(&format!("http://localhost:{port}/broken")).unwrap();
+    let result = session.paginate_collect(10, Extract::Lists).unwrap();
+    assert_eq!(result.stop_reason, StopReason::Error);
+    assert!(result.error.as_deref().unwrap().contains("404"), "{:?}", result.error);
+    assert_eq!(result.rows, vec![vec!["0", "Only"]]);
+}
@@ -0,0 +1,274 @@
+//! Responses that aren't pages: PDFs read into a Spatial DOM with the `pdf`
+//! feature, and anything else described by `FetchError::BinaryContent`.
+
+#![cfg(feature = "fetch")]
+
+mod common;
+
+use browsy_core::fetch::{Session, SessionConfig};
+use common::Response;
+
+const DATASHEET: &[u8] = include_bytes!("fixtures/datasheet.pdf");
+const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+const ZIP: &[u8] = b"PK\x03\x04\x14\0\0\0\0\0";
+
+/// Serve the datasheet at `/datasheet.pdf`, a PNG at `/logo.png`, a zip for
+/// the export form at `/export`, and a page with that form anywhere else.
+/// Returns the port.
+fn serve() -> u16 {
+    common::serve(|request| match request.path.as_str() {
+        "/datasheet.pdf" => Response::ok("application/pdf", DATASHEET),
+        "/logo.png" => Response::ok("image/png", PNG),
+        "/export" => Response::ok("application/zip", ZIP),
+        _ => Response::html(
+            r#"<html><head><title>Products</title></head><body><p>X200</p>
+            <form action="/export" method="post"><button type="submit">Export</button></form></body></html>"#,
+        ),
+    })
+}
+
+fn session() -> Session {
+    Session::with_config(SessionConfig { fetch_css: false, allow_private_network: true, ..SessionConfig::default() })
+        .unwrap()
+}
+
+#[test]
+fn test_images_are_described_not_loaded() {
+    let port = serve();
+    let mut session = session();
+    session.goto(&format!("http://127.0.0.1:{port}/")).unwrap();
+
+    let url = format!("http://127.0.0.1:{port}/logo.png");
+    let err = session.goto(&url).unwrap_err();
+    assert_eq!(err.code().as_str(), "binary_content");
+    assert_eq!(err.details().unwrap(), serde_json::json!({ "url": url, "content_type": "image/png", "bytes": 16 }));
+    assert_eq!(session.dom_ref().unwrap().title, "Products", "the current page was kept");
+}
+
+#[test]
+fn test_form_returning_a_file_keeps_the_current_page() {
+    let port = serve();
+    let mut session = session();
+    let page = format!("http://127.0.0.1:{port}/");
+    session.goto(&page).unwrap();
+    let export = session.find_by_text("Export")[0].id;
+
+    let err = session.click(export).unwrap_err();
+    assert_eq!(err.code().as_str(), "binary_content");
+    assert_eq!(session.url(), Some(page.as_str()));
+    assert_eq!(session.history_len(), 1);
+    assert_eq!(session.dom_ref().unwrap().title, "Products");
+}
+
+#[cfg(not(feature = "pdf"))]
+#[test]
+fn test_pdfs_are_described_without_the_feature() {
+    let port = serve();
+    let err = session().goto(&format!("http://127.0.0.1:{port}/datasheet.pdf")).unwrap_err();
+    assert!(
+        matches!(&err, browsy_core::fetch::FetchError::BinaryContent { content_type, bytes, .. }
+            if content_type == "application/pdf" && *bytes == DATASHEET.len() as u64),
+        "{err:?}"
+    );
+}
+
+#[cfg(feature = "pdf")]
+mod pdf {
+    use super::*;
+    use browsy_core::output::PageType;
+    use lopdf::{dictionary, Document, Object, Stream};
+
+    fn summary(dom: &browsy_core::output::SpatialDom) -> Vec<(&str, &str, u16)> {
+        dom.els.iter().map(|e| (e.tag.as_str(), e.text.as_deref().unwrap(), e.page.unwrap())).collect()
+    }
+
+    /// A one-page, 612 by 792 point PDF drawing `content` with `/F1` as
+    /// Helvetica.
+    fn one_page(content: &str) -> Vec<u8> {
+        with_font(content, |doc| {
+            doc.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Helvetica" })
+        })
+    }
+
+    /// A one-page, 612 by 792 point PDF drawing `content` with `/F1` as the
+    /// font `font` adds.
+    fn with_font(content: &str, font: impl FnOnce(&mut Document) -> lopdf::ObjectId) -> Vec<u8> {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = font(&mut doc);
+        let resources_id = doc.add_object(dictionary! { "Font" => dictionary! { "F1" => font_id } });
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.as_bytes().to_vec()));
+        let page_id = doc.add_object(dictionary! { "Type" => "Page", "Parent" => pages_id, "Contents" => content_id });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![page_id.into()],
+                "Count" => 1,
+                "Resources" => resources_id,
+                "MediaBox" => vec![0.into(), 0.into(), 612.into(), 792.into()],
+            }),
+        );
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog_id);
+        let mut bytes = Vec::new();
+        doc.save_to(&mut bytes).unwrap();
+        bytes
+    }
+
+    /// `content` read at 612px wide, so a point is a pixel.
+    fn parse_page(content: &str) -> browsy_core::output::SpatialDom {
+        browsy_core::pdf::parse_pdf(&one_page(content), "http://localhost/page.pdf", 612.0, 792.0).unwrap()
+    }
+
+    #[test]
+    fn test_text_follows_text_and_graphics_matrices() {
+        let dom = parse_page("q 2 0 0 2 0 0 cm BT /F1 10 Tf 10 20 Td (Hello) Tj 0 -20 Td [(Wor) -50 (ld) -400 (again)] TJ ET Q");
+        let lines: Vec<(&str, i32, i32)> =
+            dom.els.iter().map(|e| (e.text.as_deref().unwrap(), e.b[0], e.b[3])).collect();
+        // Scaled twice over: at x 20, 20 points tall, and 40 points apart
+        assert_eq!(lines, [("Hello", 20, 20), ("World again", 20, 20)]);
+        assert_eq!(dom.els[1].b[1] - dom.els[0].b[1], 40);
+    }
+
+    /// A subset Type0 font with Identity-H codes: glyphs 1 to 7 are
+    /// "Invoice" and 8 a space when `to_unicode` maps them, 9 to 11 the
+    /// digits 4 to 6 by a range. Glyph 8 is narrow.
+    fn type0_font(doc: &mut Document, to_unicode: bool) -> lopdf::ObjectId {
+        let descendant = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "CIDFontType2",
+            "BaseFont" => "ABCDEF+Invoice",
+            "DW" => 600,
+            "W" => vec![8.into(), vec![250.into()].into()],
+        });
+        let mut font = dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type0",
+            "BaseFont" => "ABCDEF+Invoice",
+            "Encoding" => "Identity-H",
+            "DescendantFonts" => vec![descendant.into()],
+        };
+        if to_unicode {
+            let cmap = "/CIDInit /ProcSet findresource begin 12 dict begin begincmap\n\
+                /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+                /CMapName /Adobe-Identity-UCS def /CMapType 2 def\n\
+                1 begincodespacerange <0000> <FFFF> endcodespacerange\n\
+                2 beginbfchar <0001> <0049> <0008> <0020> endbfchar\n\
+                2 beginbfrange <0002> <0007> [<006E> <0076> <006F> <0069> <0063> <0065>] <0009> <000B> <0034> endbfrange\n\
+                endcmap CMapName currentdict /CMap defineresource pop end end";
+            font.set("ToUnicode", doc.add_object(Stream::new(dictionary! {}, cmap.as_bytes().to_vec())));
+        }
+        doc.add_object(font)
+    }
+
+    #[test]
+    fn test_composite_fonts_read_through_to_unicode() {
+        let pdf = with_font("BT /F1 12 Tf 72 700 Td <000100020003000400050006000700080009000A000B> Tj ET", |doc| {
+            type0_font(doc, true)
+        });
+        let dom = browsy_core::pdf::parse_pdf(&pdf, "http://localhost/invoice.pdf", 612.0, 792.0).unwrap();
+        assert_eq!(summary(&dom), [("p", "Invoice 456", 1)]);
+        // Ten glyphs at the default 600 and the space at 250, at 12 points
+        assert_eq!(dom.els[0].b[2], 75);
+        assert!(dom.warnings.is_empty(), "{:?}", dom.warnings);
+    }
+
+    #[test]
+    fn test_unreadable_text_is_left_out_with_a_warning() {
+        let pdf = with_font("BT /F1 10 Tf 72 700 Td <00010002> Tj ET", |doc| type0_font(doc, false));
+        let dom = browsy_core::pdf::parse_pdf(&pdf, "http://localhost/invoice.pdf", 612.0, 792.0).unwrap();
+        assert!(dom.els.is_empty());
+        assert_eq!(dom.warnings, ["Characters in fonts without a text mapping were left out: 2"]);
+    }
+
+    #[test]
+    fn test_differences_rename_simple_font_codes() {
+        let pdf = with_font("BT /F1 12 Tf 72 700 Td (\\001\\002e \\003\\004 10\\005) Tj ET", |doc| {
+            doc.add_object(dictionary! {
+                "Type" => "Font",
+                "Subtype" => "Type1",
+                "BaseFont" => "ABCDEF+Body",
+                "Encoding" => dictionary! {
+                    "Type" => "Encoding",
+                    "BaseEncoding" => "WinAnsiEncoding",
+                    "Differences" => vec![1.into(), "f_i".into(), "uni0076".into(), "Euro".into(), "g42".into(), "percent.alt".into()],
+                },
+            })
+        });
+        let dom = browsy_core::pdf::parse_pdf(&pdf, "http://localhost/prices.pdf", 612.0, 792.0).unwrap();
+        assert_eq!(summary(&dom), [("p", "five € 10%", 1)]);
+        assert_eq!(dom.warnings, ["Characters in fonts without a text mapping were left out: 1"]);
+    }
+
+    #[test]
+    fn test_larger_text_becomes_headings_by_size() {
+        let dom = parse_page(
+            "BT /F1 24 Tf 72 760 Td (Report) Tj ET \
+             BT /F1 11 Tf 72 730 Td (The first line of a paragraph) Tj 0 -13 Td (and its second line.) Tj ET \
+             BT /F1 16 Tf 72 690 Td (Results) Tj ET \
+             BT /F1 11 Tf 72 670 Td (Another paragraph of body text.) Tj ET",
+        );
+        let summary: Vec<(&str, &str)> = dom.els.iter().map(|e| (e.tag.as_str(), e.text.as_deref().unwrap())).collect();
+        assert_eq!(
+            summary,
+            [
+                ("h1", "Report"),
+                ("p", "The first line of a paragraph and its second line."),
+                ("h2", "Results"),
+                ("p", "Another paragraph of body text."),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_pdf_reads_headings_and_paragraphs_in_order() {
+        let dom = browsy_core::pdf::parse_pdf(DATASHEET, "http://localhost/datasheet.pdf", 1224.0, 800.0).unwrap();
+        assert_eq!(dom.title, "X200 Datasheet");
+        assert_eq!(dom.page_type, PageType::Document);
+        assert_eq!(
+            summary(&dom),
+            [
+                ("h1", "Quarterly Datasheet", 1),
+                ("p", "The X200 sensor measures temperature from -40 to 125 degrees Celsius.", 1),
+                ("h2", "Specifications", 1),
+                ("p", "Accuracy is within half a degree across the range.", 1),
+                ("h2", "Ordering information", 2),
+                ("p", "Order code X200-A ships in packs of ten.", 2),
+            ]
+        );
+        let heading = dom.headings()[0];
+        assert_eq!(heading.level, Some(1));
+
+        // Pages are scaled to the viewport (612pt to 1224px) and stacked
+        let b: Vec<[i32; 4]> = dom.els.iter().map(|e| e.b).collect();
+        assert_eq!(b[0][0], 144);
+        assert!(b.windows(2).all(|pair| pair[0][1] < pair[1][1]), "{b:?}");
+        assert!(b[4][1] > 792 * 2, "page 2 starts below page 1: {b:?}");
+    }
+
+    #[test]
+    fn test_goto_reads_linked_pdfs() {
+        let port = serve();
+        let mut session = session();
+        let url = format!("http://127.0.0.1:{port}/datasheet.pdf");
+        let dom = session.goto(&url).unwrap();
+        assert_eq!(dom.page_type, PageType::Document);
+        assert_eq!(session.url(), Some(url.as_str()));
+        assert_eq!(session.find_by_text("packs of ten")[0].page, Some(2));
+
+        let dom = session.open_pdf(&url).unwrap();
+        assert_eq!(dom.title, "X200 Datasheet");
+    }
+
+    #[test]
+    fn test_open_pdf_refuses_pages() {
+        let port = serve();
+        let mut session = session();
+        session.open_pdf(&format!("http://127.0.0.1:{port}/datasheet.pdf")).unwrap();
+        let err = session.open_pdf(&format!("http://127.0.0.1:{port}/products")).unwrap_err();
+        assert!(err.to_string().contains("is not a PDF but text/html"), "{err}");
+        assert_eq!(session.dom_ref().unwrap().title, "X200 Datasheet");
+    }
+}
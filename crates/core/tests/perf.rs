@@ -212,3 +212,38 @@ fn env_f64(key: &str, default: f64) -> f64 {
         .and_then(|v| v.parse::<f64>().ok())
         .unwrap_or(default)
 }
+
+/// Clicks work on the tree parsed when the page loaded; this compares one
+/// against the parse every click used to start with.
+#[test]
+#[ignore]
+#[cfg(feature = "fetch")]
+fn perf_click_reuses_parsed_tree() {
+    let snapshot = format!("{}/tests/corpus/snapshots/wikipedia-rust.html", env!("CARGO_MANIFEST_DIR"));
+    let page = std::fs::read_to_string(&snapshot).expect("Failed to read snapshot");
+    let html = format!("<button type=\"button\">Noop</button>{}", page.repeat(4));
+    let config = browsy_core::fetch::SessionConfig { fetch_css: false, ..Default::default() };
+    let mut session = browsy_core::fetch::Session::with_config(config).unwrap();
+    session.load_html(&html, "http://localhost/big").unwrap();
+    let button = session.find_by_text("Noop")[0].id;
+
+    const ROUNDS: u32 = 20;
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        session.click(button).unwrap();
+    }
+    let click_ms = start.elapsed().as_secs_f64() * 1000.0 / ROUNDS as f64;
+
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        let tree = browsy_core::dom::parse_html(&html);
+        std::hint::black_box(browsy_core::js::detect_behaviors(&tree));
+    }
+    let reparse_ms = start.elapsed().as_secs_f64() * 1000.0 / ROUNDS as f64;
+
+    println!();
+    println!("CLICK LATENCY ({} KiB page)", html.len() / 1024);
+    println!("click on cached tree: {:.2} ms", click_ms);
+    println!("reparse per click:    {:.2} ms", reparse_ms);
+    assert!(click_ms < reparse_ms, "a click ({click_ms:.2} ms) should cost less than reparsing ({reparse_ms:.2} ms)");
+}
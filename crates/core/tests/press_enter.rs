@@ -0,0 +1,87 @@
+//! `Session::press_enter`: submitting forms that have no submit button,
+//! against a local server.
+
+#![cfg(feature = "fetch")]
+
+mod common;
+
+use browsy_core::fetch::{AuditAction, FetchError, Session, SessionConfig};
+use browsy_core::output::SuggestedAction;
+use common::Response;
+
+const HEADER_SEARCH: &str = include_str!("fixtures/header_search.html");
+
+/// Serve the header search page, and for `/search` a results page titled
+/// with the request target. Returns the base URL.
+fn serve() -> String {
+    let port = common::serve(|request| {
+        let target = request.path.as_str();
+        if target.starts_with("/search") {
+            Response::html(format!("<html><head><title>{target}</title></head><body><h1>Results</h1></body></html>"))
+        } else {
+            Response::html(HEADER_SEARCH)
+        }
+    });
+    format!("http://127.0.0.1:{port}")
+}
+
+fn session() -> Session {
+    Session::with_config(SessionConfig {
+        fetch_css: false,
+        allow_private_network: true,
+        ..SessionConfig::default()
+    })
+    .unwrap()
+}
+
+fn input(session: &Session, name: &str) -> u32 {
+    session.dom_ref().unwrap().els.iter().find(|e| e.name.as_deref() == Some(name)).unwrap().id
+}
+
+#[test]
+fn test_search_without_button_is_still_suggested() {
+    let dom = browsy_core::parse(HEADER_SEARCH, 1920.0, 1080.0);
+    let q = dom.els.iter().find(|e| e.name.as_deref() == Some("q")).unwrap().id;
+    let search = dom
+        .suggested_actions
+        .iter()
+        .find_map(|a| match a {
+            SuggestedAction::Search { input_id, submit_id, note } => Some((*input_id, *submit_id, note.clone())),
+            _ => None,
+        })
+        .expect("Search action without a button");
+    assert_eq!(search.0, q);
+    assert_eq!(search.1, None);
+    assert!(search.2.unwrap().contains("press Enter"));
+}
+
+#[test]
+fn test_press_enter_submits_the_inputs_form() {
+    let base = serve();
+    let mut session = session();
+    session.goto(&format!("{base}/")).unwrap();
+
+    let q = input(&session, "q");
+    session.type_text(q, "tomato blight").unwrap();
+    let dom = session.press_enter(q).unwrap();
+    assert!(session.url().unwrap().starts_with(&format!("{base}/search?")));
+    assert!(session.url().unwrap().contains("q=tomato+blight"), "{:?}", session.url());
+    assert_eq!(dom.title, "/search?q=tomato+blight");
+    assert!(session.audit_log().iter().any(|e| e.action == AuditAction::PressEnter { id: q }));
+}
+
+#[test]
+fn test_press_enter_outside_a_form_sets_the_query() {
+    let base = serve();
+    let mut session = session();
+    session.goto(&format!("{base}/?page=2&filter=old#plants")).unwrap();
+
+    let filter = input(&session, "filter");
+    session.type_text(filter, "pep").unwrap();
+    session.press_enter(filter).unwrap();
+    assert_eq!(session.url(), Some(format!("{base}/?page=2&filter=pep").as_str()));
+
+    // Only inputs take Enter
+    let link = session.find_by_text_exact("Tomatoes")[0].id;
+    assert!(matches!(session.press_enter(link), Err(FetchError::WrongElementType { .. })));
+}
@@ -0,0 +1,212 @@
+//! `Session::preview_click` and `preview_submit` describe exactly the
+//! request the real action then sends, and send nothing themselves.
+
+#![cfg(feature = "fetch")]
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use browsy_core::fetch::{ActionPreview, PreviewField, Session, SessionConfig};
+use browsy_core::js::JsAction;
+use common::Response;
+
+const PAGE: &str = r##"<html><head><title>Start</title></head><body>
+    <a href="/next?from=start">Next</a>
+    <a href="#top">Top</a>
+    <button onclick="toggle('menu')">Menu</button>
+    <div id="menu" style="display: none;"><a href="/profile">Profile</a></div>
+    <form action="/search" method="get">
+        <input type="text" name="q" value="rust" />
+        <input type="hidden" name="lang" value="en" />
+        <button type="submit">Search</button>
+    </form>
+    <form action="/login" method="post">
+        <input type="text" name="user" />
+        <input type="password" name="password" />
+        <button type="submit">Sign in</button>
+    </form>
+</body></html>"##;
+
+/// Serve `PAGE` at `/`, and for any other target a page titled with the
+/// request line, showing the request body. Returns the base URL and the
+/// number of requests served.
+fn serve() -> (String, Arc<AtomicUsize>) {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    let port = common::serve(move |request| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        let (method, target, body) = (&request.method, &request.path, &request.body);
+        if target == "/" {
+            Response::html(PAGE)
+        } else {
+            Response::html(format!("<html><head><title>{method} {target}</title></head><body><p>body:{body}</p></body></html>"))
+        }
+    });
+    (format!("http://127.0.0.1:{port}"), requests)
+}
+
+fn session(redact_sensitive: bool) -> Session {
+    Session::with_config(SessionConfig {
+        fetch_css: false,
+        allow_private_network: true,
+        redact_sensitive,
+        ..SessionConfig::default()
+    })
+    .unwrap()
+}
+
+fn find(session: &Session, f: impl Fn(&browsy_core::output::SpatialElement) -> bool) -> u32 {
+    session.dom_ref().unwrap().els.iter().find(|e| f(e)).unwrap().id
+}
+
+fn named(session: &Session, name: &str) -> u32 {
+    find(session, |e| e.name.as_deref() == Some(name))
+}
+
+fn labeled(session: &Session, text: &str) -> u32 {
+    find(session, |e| e.text.as_deref() == Some(text))
+}
+
+/// The request line the echo page was titled with, and the body it shows.
+fn echoed(session: &Session) -> (String, String) {
+    let dom = session.dom_ref().unwrap();
+    let body = dom
+        .els
+        .iter()
+        .find_map(|e| e.text.as_deref()?.strip_prefix("body:"))
+        .unwrap_or_default()
+        .to_string();
+    (dom.title.clone(), body)
+}
+
+fn encode(fields: &[PreviewField]) -> String {
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    for field in fields {
+        query.append_pair(&field.name, &field.value);
+    }
+    query.finish()
+}
+
+#[test]
+fn test_link_preview_matches_the_click() {
+    let (base, requests) = serve();
+    let mut session = session(true);
+    session.goto(&base).unwrap();
+    let served = requests.load(Ordering::SeqCst);
+
+    let next = labeled(&session, "Next");
+    let preview = session.preview_click(next).unwrap();
+    assert!(matches!(session.preview_click(labeled(&session, "Top")).unwrap(), ActionPreview::Nothing));
+    assert_eq!(requests.load(Ordering::SeqCst), served, "a preview sent a request");
+    assert_eq!(session.url(), Some(format!("{base}/").as_str()));
+
+    let ActionPreview::Navigate { url } = preview else { panic!("expected Navigate, got {preview:?}") };
+    assert_eq!(url, format!("{base}/next?from=start"));
+    session.click(next).unwrap();
+    assert_eq!(echoed(&session).0, "GET /next?from=start");
+}
+
+#[test]
+fn test_behavior_preview_changes_nothing() {
+    let (base, _requests) = serve();
+    let mut session = session(true);
+    session.goto(&base).unwrap();
+    let before = session.dom_ref().unwrap().els.len();
+
+    let preview = session.preview_click(labeled(&session, "Menu")).unwrap();
+    let ActionPreview::Behavior { action: JsAction::ToggleVisibility { target } } = preview else {
+        panic!("expected a toggle, got {preview:?}")
+    };
+    assert_eq!(target, "#menu");
+    assert_eq!(session.dom_ref().unwrap().els.len(), before, "the menu opened on a preview");
+}
+
+#[test]
+fn test_get_form_preview_matches_the_submission() {
+    let (base, requests) = serve();
+    let mut session = session(true);
+    session.goto(&base).unwrap();
+    session.type_text(named(&session, "q"), "spatial dom").unwrap();
+    let served = requests.load(Ordering::SeqCst);
+
+    let search = labeled(&session, "Search");
+    let preview = session.preview_click(search).unwrap();
+    let ActionPreview::Submit { method, url, fields } = preview else { panic!("expected Submit, got {preview:?}") };
+    assert_eq!(method, "GET");
+    assert_eq!(url, format!("{base}/search"));
+    assert_eq!(fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(), ["q", "lang"]);
+    assert_eq!(fields[0].value, "spatial dom");
+    assert_eq!(fields[1].value, "en");
+
+    // Pressing Enter in the query field submits the same form
+    let entered = session.preview_submit(named(&session, "q")).unwrap();
+    assert!(matches!(&entered, ActionPreview::Submit { fields: f, .. } if *f == fields), "{entered:?}");
+    assert_eq!(requests.load(Ordering::SeqCst), served, "a preview sent a request");
+
+    session.click(search).unwrap();
+    assert_eq!(echoed(&session).0, format!("GET /search?{}", encode(&fields)));
+}
+
+#[test]
+fn test_post_form_preview_matches_the_submission() {
+    let (base, _requests) = serve();
+    let mut session = session(false);
+    session.goto(&base).unwrap();
+    session.type_text(named(&session, "user"), "ada").unwrap();
+    session.type_text(named(&session, "password"), "hunter22").unwrap();
+
+    let sign_in = labeled(&session, "Sign in");
+    let preview = session.preview_submit(sign_in).unwrap();
+    let ActionPreview::Submit { method, url, fields } = preview else { panic!("expected Submit, got {preview:?}") };
+    assert_eq!(method, "POST");
+    assert_eq!(url, format!("{base}/login"));
+
+    session.click(sign_in).unwrap();
+    let (request, body) = echoed(&session);
+    assert_eq!(request, "POST /login");
+    assert_eq!(body, encode(&fields));
+    assert_eq!(body, "user=ada&password=hunter22");
+}
+
+#[test]
+fn test_submit_includes_controls_linked_by_form_attribute() {
+    let mut session = session(false);
+    let html = r#"<html><body>
+        <input type="email" name="email" form="checkout" />
+        <form id="checkout" action="/pay" method="post">
+            <input type="text" name="card" />
+        </form>
+        <form action="/coupon"><input type="text" name="code" /><button>Apply</button></form>
+        <input type="hidden" name="plan" value="pro" form="checkout" />
+        <button type="submit" form="checkout">Pay now</button>
+    </body></html>"#;
+    session.load_html(html, "https://shop.example/cart").unwrap();
+    session.type_text(named(&session, "email"), "ada@example.com").unwrap();
+
+    let preview = session.preview_click(labeled(&session, "Pay now")).unwrap();
+    let ActionPreview::Submit { method, url, fields } = preview else { panic!("expected Submit, got {preview:?}") };
+    assert_eq!(method, "POST");
+    assert_eq!(url, "https://shop.example/pay");
+    assert_eq!(fields.iter().map(|f| f.name.as_str()).collect::<Vec<_>>(), ["email", "card", "plan"]);
+    assert_eq!(fields[0].value, "ada@example.com");
+}
+
+#[test]
+fn test_submit_preview_redacts_sensitive_values() {
+    let (base, _requests) = serve();
+    let mut session = session(true);
+    session.goto(&base).unwrap();
+    session.type_text(named(&session, "user"), "ada").unwrap();
+    session.type_text(named(&session, "password"), "hunter22").unwrap();
+
+    let preview = session.preview_submit(labeled(&session, "Sign in")).unwrap();
+    let ActionPreview::Submit { fields, .. } = preview else { panic!("expected Submit, got {preview:?}") };
+    assert_eq!(fields[0], PreviewField { name: "user".into(), value: "ada".into() });
+    assert_eq!(fields[1].name, "password");
+    assert_eq!(fields[1].value, "•••• (8 chars)");
+
+    let err = session.preview_submit(labeled(&session, "Next")).unwrap_err();
+    assert!(err.to_string().contains("submit button or input"), "{err}");
+}
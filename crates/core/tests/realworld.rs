@@ -74,12 +74,12 @@ fn fetch_and_report(url: &str) -> SpatialDom {
                     println!("  Login: username={}, password={}, submit={}, remember_me={:?}",
                         username_id, password_id, submit_id, remember_me_id);
                 }
-                SuggestedAction::EnterCode { input_id, submit_id, code_length } => {
+                SuggestedAction::EnterCode { input_id, submit_id, code_length, .. } => {
                     println!("  EnterCode: input={}, submit={}, code_length={:?}",
                         input_id, submit_id, code_length);
                 }
-                SuggestedAction::Search { input_id, submit_id } => {
-                    println!("  Search: input={}, submit={}", input_id, submit_id);
+                SuggestedAction::Search { input_id, submit_id, .. } => {
+                    println!("  Search: input={}, submit={:?}", input_id, submit_id);
                 }
                 SuggestedAction::Consent { approve_ids, deny_ids } => {
                     println!("  Consent: approve={:?}, deny={:?}", approve_ids, deny_ids);
@@ -93,6 +93,9 @@ fn fetch_and_report(url: &str) -> SpatialDom {
                 SuggestedAction::Paginate { next_id, prev_id } => {
                     println!("  Paginate: next={:?}, prev={:?}", next_id, prev_id);
                 }
+                SuggestedAction::LoadMore { button_id } => {
+                    println!("  LoadMore: button={}", button_id);
+                }
                 SuggestedAction::Register { email_id, username_id, password_id, confirm_password_id, name_id, submit_id } => {
                     println!("  Register: email={:?}, username={:?}, password={}, confirm={:?}, name={:?}, submit={}",
                         email_id, username_id, password_id, confirm_password_id, name_id, submit_id);
@@ -389,8 +392,8 @@ fn test_realworld_action_ids_valid() {
             SuggestedAction::EnterCode { input_id, submit_id, .. } => {
                 vec![*input_id, *submit_id]
             }
-            SuggestedAction::Search { input_id, submit_id } => {
-                vec![*input_id, *submit_id]
+            SuggestedAction::Search { input_id, submit_id, .. } => {
+                std::iter::once(*input_id).chain(*submit_id).collect()
             }
             SuggestedAction::Consent { approve_ids, deny_ids } => {
                 approve_ids.iter().chain(deny_ids.iter()).copied().collect()
@@ -409,6 +412,7 @@ fn test_realworld_action_ids_valid() {
                 if let Some(id) = prev_id { v.push(*id); }
                 v
             }
+            SuggestedAction::LoadMore { button_id } => vec![*button_id],
             SuggestedAction::Register { email_id, username_id, password_id, confirm_password_id, name_id, submit_id } => {
                 let mut v = vec![*password_id, *submit_id];
                 if let Some(id) = email_id { v.push(*id); }
@@ -0,0 +1,305 @@
+//! Record-and-replay tests: flows are recorded against a local server, then
+//! replayed from the fixture directory without touching the network.
+
+#![cfg(feature = "fetch")]
+
+mod common;
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use browsy_core::fetch::{FetchError, RecordingMode, Session, SessionConfig};
+use common::{Request, Response};
+
+fn see_other(location: &str, headers: &[&str]) -> Response {
+    let mut response = Response::empty("303 See Other").with_header(format!("Location: {location}"));
+    response.headers.extend(headers.iter().map(|h| h.to_string()));
+    response
+}
+
+/// Serve `handler` on a random local port. Returns the base URL and a
+/// counter of requests served.
+fn serve(handler: fn(&Request) -> Response) -> (String, Arc<AtomicUsize>) {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let counter = hits.clone();
+    let port = common::serve(move |request| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        handler(request)
+    });
+    (format!("http://127.0.0.1:{port}"), hits)
+}
+
+fn fixture_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("browsy-recording-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn session(recording: RecordingMode) -> Session {
+    Session::with_config(SessionConfig {
+        fetch_css: false,
+        allow_private_network: true,
+        recording: Some(recording),
+        ..SessionConfig::default()
+    })
+    .unwrap()
+}
+
+fn texts(session: &Session, needle: &str) -> Vec<String> {
+    session
+        .dom_ref()
+        .unwrap()
+        .els
+        .iter()
+        .filter_map(|e| e.text.clone())
+        .filter(|t| t.contains(needle))
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Login flow
+// ---------------------------------------------------------------------------
+
+const LOGIN_PAGE: &str = r#"<html><head><title>Sign in</title></head><body>
+    <h1>Sign in</h1>
+    <form action="/session" method="post">
+        <input type="hidden" name="csrf" value="t0k3n">
+        <input type="email" name="email" placeholder="Email">
+        <input type="password" name="password" placeholder="Password">
+        <button type="submit">Sign in</button>
+    </form>
+</body></html>"#;
+
+fn login_app(request: &Request) -> Response {
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/login") => Response::html(LOGIN_PAGE),
+        ("POST", "/session") => {
+            let valid = request.body.contains("email=ada%40example.com")
+                && request.body.contains("password=hunter2")
+                && request.body.contains("csrf=t0k3n");
+            if valid {
+                see_other("/account", &["Set-Cookie: sid=abc123; Path=/"])
+            } else {
+                Response::html(LOGIN_PAGE.replace("<h1>Sign in</h1>", "<h1>Sign in</h1><p role=\"alert\">Invalid password</p>"))
+            }
+        }
+        ("GET", "/account") if request.has_cookie("sid=abc123") => Response::html(
+            "<html><head><title>Account</title></head><body>\
+             <h1>Welcome back, Ada</h1><a href=\"/logout\">Sign out</a></body></html>"
+                .to_string(),
+        ),
+        ("GET", "/account") => see_other("/login", &[]),
+        _ => Response::empty("404 Not Found"),
+    }
+}
+
+#[test]
+fn test_login_flow_replays_offline() {
+    let (base, hits) = serve(login_app);
+    let dir = fixture_dir("login");
+
+    let mut recorder = session(RecordingMode::Record(dir.clone()));
+    recorder.goto(&format!("{base}/login")).unwrap();
+    let dom = recorder.login("ada@example.com", "hunter2").unwrap();
+    assert_eq!(dom.title, "Account");
+    assert!(std::fs::read_dir(&dir).unwrap().count() >= 2, "exchanges should be written");
+    let recorded = hits.load(Ordering::SeqCst);
+
+    let mut replay = session(RecordingMode::Replay(dir.clone()));
+    replay.goto(&format!("{base}/login")).unwrap();
+    let dom = replay.login("ada@example.com", "hunter2").unwrap();
+    assert_eq!(dom.title, "Account");
+    assert_eq!(dom.url, format!("{base}/account"));
+    assert_eq!(texts(&replay, "Welcome back"), vec!["Welcome back, Ada".to_string()]);
+    assert_eq!(hits.load(Ordering::SeqCst), recorded, "replay must not hit the network");
+
+    // A different form body is a different request, and nothing was recorded for it
+    let mut replay = session(RecordingMode::Replay(dir.clone()));
+    replay.goto(&format!("{base}/login")).unwrap();
+    match replay.login("grace@example.com", "hunter2") {
+        Err(FetchError::ActionError(msg)) => {
+            assert!(msg.contains(&format!("POST {base}/session")), "{msg}");
+        }
+        other => panic!("expected a replay miss, got {:?}", other.map(|o| o.dom.title)),
+    }
+    assert_eq!(hits.load(Ordering::SeqCst), recorded);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_recorded_fixtures_omit_form_values() {
+    let (base, _) = serve(login_app);
+    let dir = fixture_dir("secrets");
+
+    let mut recorder = session(RecordingMode::Record(dir.clone()));
+    recorder.goto(&format!("{base}/login")).unwrap();
+    recorder.login("ada@example.com", "hunter2").unwrap();
+
+    for entry in std::fs::read_dir(&dir).unwrap() {
+        let json = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+        assert!(!json.contains("hunter2"), "password leaked into fixture: {json}");
+    }
+
+    // The password isn't part of the body hash either, so any password replays
+    let mut replay = session(RecordingMode::Replay(dir.clone()));
+    replay.goto(&format!("{base}/login")).unwrap();
+    let dom = replay.login("ada@example.com", "letmein").unwrap();
+    assert_eq!(dom.title, "Account");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_recorded_fixtures_omit_session_cookies() {
+    let (base, _) = serve(login_app);
+    let dir = fixture_dir("cookies");
+
+    let mut recorder = session(RecordingMode::Record(dir.clone()));
+    recorder.goto(&format!("{base}/login")).unwrap();
+    recorder.login("ada@example.com", "hunter2").unwrap();
+
+    for entry in std::fs::read_dir(&dir).unwrap() {
+        let json = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+        assert!(!json.to_lowercase().contains("set-cookie"), "cookie header leaked into fixture: {json}");
+        assert!(!json.contains("abc123"), "session cookie leaked into fixture: {json}");
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+// ---------------------------------------------------------------------------
+// Pagination flow
+// ---------------------------------------------------------------------------
+
+static CACHE_BUSTER: AtomicUsize = AtomicUsize::new(0);
+
+fn list_app(request: &Request) -> Response {
+    let page: usize = request
+        .path
+        .strip_prefix("/list?page=")
+        .and_then(|rest| rest.split('&').next())
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(0);
+    if !(1..=3).contains(&page) {
+        return Response::empty("404 Not Found");
+    }
+    let mut body = format!("<html><head><title>Page {page}</title></head><body><ul>");
+    for item in 1..=2 {
+        body.push_str(&format!("<li>Item {page}-{item}</li>"));
+    }
+    body.push_str("</ul>");
+    if page < 3 {
+        // A volatile cache-buster, different on every render
+        let buster = CACHE_BUSTER.fetch_add(1, Ordering::SeqCst);
+        body.push_str(&format!("<a href=\"/list?page={}&_={buster}\">Next</a>", page + 1));
+    }
+    body.push_str("</body></html>");
+    Response::html(body)
+}
+
+fn walk_pages(session: &mut Session, start: &str) -> Vec<String> {
+    session.goto(start).unwrap();
+    let mut items = texts(session, "Item");
+    while let Some(next) = session.find_by_text("Next").first().map(|e| e.id) {
+        session.click(next).unwrap();
+        items.extend(texts(session, "Item"));
+    }
+    items
+}
+
+#[test]
+fn test_pagination_flow_replays_offline() {
+    let (base, hits) = serve(list_app);
+    let dir = fixture_dir("pagination");
+
+    let mut recorder = session(RecordingMode::Record(dir.clone()));
+    let recorded_items = walk_pages(&mut recorder, &format!("{base}/list?page=1"));
+    assert_eq!(recorded_items.len(), 6);
+    let recorded = hits.load(Ordering::SeqCst);
+
+    // utm_* parameters are ignored when matching, so the entry URL may differ
+    let mut replay = session(RecordingMode::Replay(dir.clone()));
+    let replayed_items = walk_pages(&mut replay, &format!("{base}/list?page=1&utm_source=replay"));
+    assert_eq!(replayed_items, recorded_items);
+    assert_eq!(replay.dom_ref().unwrap().title, "Page 3");
+    assert_eq!(hits.load(Ordering::SeqCst), recorded, "replay must not hit the network");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_replay_ignores_param_order_and_fragment() {
+    let (base, hits) = serve(|request| {
+        Response::html(format!("<html><head><title>{}</title></head><body><p>ok</p></body></html>", request.path))
+    });
+    let dir = fixture_dir("params");
+
+    let mut recorder = session(RecordingMode::Record(dir.clone()));
+    let recorded = recorder.goto(&format!("{base}/list?sort=new&page=2&ts=1")).unwrap();
+    let requests = hits.load(Ordering::SeqCst);
+
+    let mut replay = session(RecordingMode::Replay(dir.clone()));
+    let replayed = replay.goto(&format!("{base}/list?page=2&sort=new&ts=2#top")).unwrap();
+    assert_eq!(replayed.title, recorded.title);
+    assert_eq!(hits.load(Ordering::SeqCst), requests, "replay must not hit the network");
+
+    // Parameters that aren't ignored still tell requests apart
+    assert!(replay.goto(&format!("{base}/list?sort=old&page=2")).is_err());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+/// A form posting `fields`, each `(name, value)`, in that order.
+fn form_page(fields: &[(&str, &str)]) -> String {
+    let inputs: String = fields
+        .iter()
+        .map(|(name, value)| format!(r#"<input type="text" name="{name}" value="{value}">"#))
+        .collect();
+    format!(r#"<html><body><form action="/save" method="post">{inputs}<button type="submit">Save</button></form></body></html>"#)
+}
+
+fn submit(session: &mut Session, base: &str, fields: &[(&str, &str)]) -> Result<String, FetchError> {
+    session.load_html(&form_page(fields), &format!("{base}/edit")).unwrap();
+    let save = session.find_by_text("Save")[0].id;
+    session.click(save).map(|dom| dom.title)
+}
+
+#[test]
+fn test_replayed_form_ignores_field_order_and_volatile_fields() {
+    let (base, hits) = serve(|request| {
+        Response::html(format!("<html><head><title>Saved {}</title></head><body><p>ok</p></body></html>", request.body))
+    });
+    let dir = fixture_dir("form");
+
+    let mut recorder = session(RecordingMode::Record(dir.clone()));
+    let recorded = submit(&mut recorder, &base, &[("user", "ada"), ("pass", "pw"), ("nonce", "1")]).unwrap();
+    let requests = hits.load(Ordering::SeqCst);
+
+    let mut replay = session(RecordingMode::Replay(dir.clone()));
+    let replayed = submit(&mut replay, &base, &[("pass", "pw"), ("nonce", "2"), ("user", "ada")]).unwrap();
+    assert_eq!(replayed, recorded);
+    assert_eq!(hits.load(Ordering::SeqCst), requests, "replay must not hit the network");
+
+    // A changed value is another request
+    assert!(submit(&mut replay, &base, &[("user", "ada"), ("pass", "other")]).is_err());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_replay_miss_names_request() {
+    let dir = fixture_dir("empty");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let mut replay = session(RecordingMode::Replay(dir.clone()));
+    match replay.goto("https://example.com/missing?q=1") {
+        Err(FetchError::ActionError(msg)) => {
+            assert!(msg.contains("GET https://example.com/missing?q=1"), "{msg}");
+        }
+        other => panic!("expected a replay miss, got {:?}", other.map(|d| d.title)),
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
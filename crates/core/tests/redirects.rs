@@ -0,0 +1,87 @@
+//! Redirect chains: loops and `max_redirects` fail with `TooManyRedirects`,
+//! and `last_redirects` lists the chain either way.
+
+#![cfg(feature = "fetch")]
+
+mod common;
+
+use browsy_core::fetch::{FetchError, Session, SessionConfig};
+use common::Response;
+
+/// A site where `/a` -> `/b` -> `/c` -> `/a` forever, `/hop/N` goes on to
+/// `/hop/N+1`, `/old` moves to `/new`, and every other page is plain HTML.
+fn serve() -> String {
+    let port = common::serve(|request| {
+        let path = request.path.as_str();
+        let location = match path {
+            "/a" => Some("/b".to_string()),
+            "/b" => Some("/c".to_string()),
+            "/c" => Some("/a".to_string()),
+            "/old" => Some("/new".to_string()),
+            _ => path
+                .strip_prefix("/hop/")
+                .and_then(|n| n.parse::<u32>().ok())
+                .map(|n| format!("/hop/{}", n + 1)),
+        };
+        match location {
+            Some(location) => Response::redirect(&location),
+            None => Response::html(format!("<html><head><title>{path}</title></head><body><h1>{path}</h1></body></html>")),
+        }
+    });
+    format!("http://127.0.0.1:{port}")
+}
+
+fn session(max_redirects: usize) -> Session {
+    Session::with_config(SessionConfig {
+        fetch_css: false,
+        allow_private_network: true,
+        max_redirects,
+        ..SessionConfig::default()
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_redirect_cycle_fails_with_its_chain() {
+    let base = serve();
+    let mut session = session(10);
+
+    let err = session.goto(&format!("{base}/a")).unwrap_err();
+    let expected: Vec<String> = ["/a", "/b", "/c", "/a"].iter().map(|p| format!("{base}{p}")).collect();
+    match &err {
+        FetchError::TooManyRedirects { chain } => assert_eq!(chain, &expected),
+        other => panic!("expected TooManyRedirects, got {other:?}"),
+    }
+    // The loop is caught when it closes, well before the limit
+    assert!(err.to_string().starts_with("Redirect loop: "), "{err}");
+    assert!(err.to_string().ends_with(&format!("{base}/c -> {base}/a")), "{err}");
+    assert_eq!(session.last_redirects(), expected.as_slice());
+}
+
+#[test]
+fn test_max_redirects_limits_the_chain() {
+    let base = serve();
+    let mut session = session(3);
+
+    let err = session.goto(&format!("{base}/hop/0")).unwrap_err();
+    let FetchError::TooManyRedirects { chain } = &err else {
+        panic!("expected TooManyRedirects, got {err:?}");
+    };
+    // The requested URL, three followed redirects, and the one refused
+    assert_eq!(chain.len(), 5);
+    assert!(chain[4].ends_with("/hop/4"));
+    assert!(err.to_string().starts_with("Too many redirects: "), "{err}");
+}
+
+#[test]
+fn test_last_redirects_lists_followed_chain() {
+    let base = serve();
+    let mut session = session(10);
+
+    let dom = session.goto(&format!("{base}/old")).unwrap();
+    assert_eq!(dom.title, "/new");
+    assert_eq!(session.last_redirects(), [format!("{base}/old"), format!("{base}/new")]);
+
+    session.goto(&format!("{base}/new")).unwrap();
+    assert!(session.last_redirects().is_empty());
+}
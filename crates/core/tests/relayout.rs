@@ -0,0 +1,158 @@
+//! Pages re-rendered after a JS toggle come out the same whether only the
+//! toggled elements are laid out again or the whole page is.
+
+#![cfg(feature = "fetch")]
+
+use browsy_core::fetch::{Session, SessionConfig};
+
+/// Whether the last re-render laid out only the restyled elements.
+fn incremental_relayout(session: &Session) -> bool {
+    session.timings().unwrap().incremental
+}
+
+fn session(incremental_relayout: bool) -> Session {
+    Session::with_config(SessionConfig {
+        incremental_relayout,
+        ..SessionConfig::default()
+    })
+    .unwrap()
+}
+
+/// Load `html` in an incremental and a full-rebuild session, click the
+/// `n`th element with each text in turn, and compare the pages after each.
+/// `incremental` is whether the first session's re-renders lay out only
+/// what the clicks restyled.
+fn assert_same_after_clicks(html: &str, clicks: &[(&str, usize)], incremental: bool) {
+    let mut first = session(true);
+    let mut full = session(false);
+    first.load_html_offline(html, "http://localhost/page").unwrap();
+    full.load_html_offline(html, "http://localhost/page").unwrap();
+
+    for (step, &(text, n)) in clicks.iter().enumerate() {
+        let id = first.find_by_text_exact(text)[n].id;
+        assert_eq!(id, full.find_by_text_exact(text)[n].id);
+        first.click(id).unwrap();
+        full.click(id).unwrap();
+
+        let a = serde_json::to_string_pretty(&first.dom().unwrap()).unwrap();
+        let b = serde_json::to_string_pretty(&full.dom().unwrap()).unwrap();
+        assert!(a == b, "pages differ after click {step} on {text:?}:\n{a}\n---\n{b}");
+        assert_eq!(incremental_relayout(&first), incremental, "click {step} on {text:?}");
+        assert!(!incremental_relayout(&full));
+    }
+}
+
+#[test]
+fn test_toggled_dropdown_matches_full_rebuild() {
+    let html = r#"
+    <html><body style="margin: 0">
+        <nav style="display: flex; gap: 12px; width: 60%">
+            <a href="/">Home</a>
+            <button onclick="toggle('menu')">Account</button>
+            <ul id="menu" style="display: none; width: 50%; padding: 8px">
+                <li><a href="/profile">Profile</a></li>
+                <li><a href="/settings">Settings</a></li>
+                <li><a href="/logout">Sign out</a></li>
+            </ul>
+        </nav>
+        <main><h1>Dashboard</h1><p>Welcome back.</p></main>
+    </body></html>
+    "#;
+    assert_same_after_clicks(html, &[("Account", 0), ("Account", 0), ("Account", 0)], true);
+}
+
+#[test]
+fn test_class_toggle_restyles_descendants_like_full_rebuild() {
+    let html = r#"
+    <html><head><style>
+        .panel { display: none; width: 400px; }
+        .panel.open { display: block; --gap: 24px; }
+        .open .item { padding: 10px; margin-top: var(--gap); }
+        .open > p { font-size: 24px; }
+    </style></head><body>
+        <button onclick="document.getElementById('panel').classList.toggle('open')">Filters</button>
+        <div id="panel" class="panel">
+            <p>Narrow the results below by brand, price or rating.</p>
+            <div class="item"><label><input type="checkbox" name="brand"> Brand</label></div>
+            <div class="item"><label><input type="checkbox" name="price"> Price</label></div>
+        </div>
+        <section><h2>Results</h2><p>24 products</p></section>
+    </body></html>
+    "#;
+    assert_same_after_clicks(html, &[("Filters", 0), ("Filters", 0)], true);
+}
+
+#[test]
+fn test_tab_switch_matches_full_rebuild() {
+    let html = r#"
+    <html><body>
+        <div role="tablist">
+            <button role="tab" aria-controls="specs">Specs</button>
+            <button role="tab" aria-controls="reviews">Reviews</button>
+        </div>
+        <div id="specs" hidden><table><tr><td>Weight</td><td>1.2 kg</td></tr></table></div>
+        <div id="reviews" hidden><p>Great value for the price.</p><p>Arrived quickly.</p></div>
+        <footer><a href="/help">Help</a></footer>
+    </body></html>
+    "#;
+    // The tab list's text contains each tab's, so the tabs are the second match
+    assert_same_after_clicks(html, &[("Specs", 1), ("Reviews", 1)], true);
+}
+
+#[test]
+fn test_collapse_in_grid_matches_full_rebuild() {
+    let html = r##"
+    <html><body>
+        <div style="display: grid; grid-template-columns: 1fr 2fr; gap: 16px">
+            <div>
+                <button data-toggle="collapse" data-target="#faq1">Shipping</button>
+                <div id="faq1" style="display:none"><p>We ship worldwide within five days.</p></div>
+            </div>
+            <div>
+                <button data-toggle="collapse" data-target="#faq2">Returns</button>
+                <div id="faq2" style="display:none"><p>Returns are free for thirty days.</p></div>
+            </div>
+        </div>
+    </body></html>
+    "##;
+    assert_same_after_clicks(html, &[("Shipping", 0), ("Returns", 0), ("Shipping", 0)], true);
+}
+
+#[test]
+fn test_read_more_cards_match_full_rebuild() {
+    let html = include_str!("fixtures/read_more_cards.html");
+    assert_same_after_clicks(html, &[("Show more", 0), ("Show more", 2), ("Show more", 0)], true);
+}
+
+#[test]
+fn test_toggles_without_a_single_target_fall_back_to_full_rebuild() {
+    // A class selector and a duplicated id can't be pinned to one element
+    let html = r#"
+    <html><body>
+        <button onclick="$('.drawer').toggle()">Drawer</button>
+        <div class="drawer" style="display: none"><a href="/a">Inside the drawer</a></div>
+        <button onclick="toggle('note')">Note</button>
+        <p id="note" style="display: none">First note</p>
+        <p id="note" style="display: none">Second note</p>
+    </body></html>
+    "#;
+    assert_same_after_clicks(html, &[("Drawer", 0), ("Note", 0), ("Note", 0)], false);
+}
+
+#[test]
+fn test_toggle_after_resize_matches_full_rebuild() {
+    let html = include_str!("fixtures/read_more_cards.html");
+    let mut incremental = session(true);
+    let mut full = session(false);
+    for session in [&mut incremental, &mut full] {
+        session.load_html_offline(html, "http://localhost/page").unwrap();
+        session.resize(390.0, 844.0).unwrap();
+        let id = session.find_by_text_exact("Show more")[1].id;
+        session.click(id).unwrap();
+    }
+    assert_eq!(
+        serde_json::to_string(&incremental.dom().unwrap()).unwrap(),
+        serde_json::to_string(&full.dom().unwrap()).unwrap()
+    );
+    assert!(incremental_relayout(&incremental));
+}
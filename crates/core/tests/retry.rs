@@ -0,0 +1,144 @@
+//! `SessionConfig::retry` against local servers that fail before they answer.
+
+#![cfg(feature = "fetch")]
+
+mod common;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use browsy_core::fetch::{
+    AuditAction, FetchError, RetryCondition, RetryPolicy, Session, SessionConfig,
+};
+use common::Response;
+
+const PAGE: &str = r#"<html><head><title>Ready</title></head><body>
+    <form action="/order" method="post"><input name="qty" value="1"><button type="submit">Order</button></form>
+</body></html>"#;
+
+/// Answer the first `failures` requests with `503`, then serve `PAGE` to
+/// GETs. POSTs always get `503`. Returns the base URL and a request counter.
+fn serve(failures: usize) -> (String, Arc<AtomicUsize>) {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    let port = common::serve(move |request| {
+        let n = counter.fetch_add(1, Ordering::SeqCst);
+        if n < failures || request.method == "POST" {
+            Response::empty("503 Service Unavailable")
+        } else {
+            Response::html(PAGE)
+        }
+    });
+    (format!("http://127.0.0.1:{port}"), requests)
+}
+
+fn session(max_attempts: usize) -> Session {
+    Session::with_config(SessionConfig {
+        fetch_css: false,
+        allow_private_network: true,
+        retry: RetryPolicy { max_attempts, base_delay_ms: 5, ..RetryPolicy::default() },
+        ..SessionConfig::default()
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_goto_retries_until_success() {
+    let (base, requests) = serve(2);
+    let mut session = session(3);
+    assert_eq!(session.goto(&base).unwrap().title, "Ready");
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+    let attempts = session.last_attempts();
+    assert_eq!(
+        attempts.iter().map(|a| (a.status, a.delay_ms)).collect::<Vec<_>>(),
+        vec![(Some(503), 5), (Some(503), 10), (Some(200), 0)]
+    );
+    let retries: Vec<u32> = session
+        .audit_log()
+        .into_iter()
+        .filter_map(|e| match e.action {
+            AuditAction::Retry { attempt, reason: RetryCondition::ServerError, .. } => Some(attempt),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(retries, vec![1, 2]);
+}
+
+#[test]
+fn test_goto_gives_up_after_max_attempts() {
+    let (base, requests) = serve(5);
+    let mut session = session(2);
+    assert!(matches!(session.goto(&base), Err(FetchError::HttpError(503))));
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_post_that_got_a_response_is_not_resent() {
+    let (base, requests) = serve(0);
+    let mut session = session(3);
+    session.goto(&base).unwrap();
+
+    let order = session.dom().unwrap().find_by_text("Order")[0].id;
+    assert!(matches!(session.click(order), Err(FetchError::HttpError(503))));
+    // One GET for the page, one POST
+    assert_eq!(requests.load(Ordering::SeqCst), 2);
+    assert_eq!(session.last_attempts().len(), 1);
+}
+
+#[test]
+fn test_retry_after_is_honored() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    let port = common::serve(move |_| {
+        if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+            Response::empty("429 Too Many Requests").with_header("Retry-After: 1")
+        } else {
+            Response::html(PAGE)
+        }
+    });
+    let mut session = session(3);
+    session.goto(&format!("http://127.0.0.1:{port}")).unwrap();
+
+    let attempts = session.last_attempts();
+    assert_eq!(
+        attempts.iter().map(|a| (a.status, a.retried_for, a.delay_ms)).collect::<Vec<_>>(),
+        vec![(Some(429), Some(RetryCondition::RateLimited), 1000), (Some(200), None, 0)]
+    );
+}
+
+#[test]
+fn test_post_without_a_response_is_resent() {
+    // Nothing listens on the form's target once this listener is dropped
+    let closed = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+    let page = PAGE.replace("action=\"/order\"", &format!("action=\"http://127.0.0.1:{closed}/order\""));
+    let port = common::serve(move |_| Response::html(page.clone()));
+    let mut session = session(3);
+    session.goto(&format!("http://127.0.0.1:{port}")).unwrap();
+
+    let order = session.dom().unwrap().find_by_text("Order")[0].id;
+    assert!(matches!(session.click(order), Err(FetchError::Network(_))));
+    let attempts = session.last_attempts();
+    assert_eq!(attempts.len(), 3);
+    assert!(attempts.iter().all(|a| a.status.is_none()));
+}
+
+#[test]
+fn test_only_listed_conditions_are_retried() {
+    let requests = Arc::new(AtomicUsize::new(0));
+    let counter = requests.clone();
+    let port = common::serve(move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Response::empty("429 Too Many Requests")
+    });
+    let mut session = Session::with_config(SessionConfig {
+        fetch_css: false,
+        allow_private_network: true,
+        retry: RetryPolicy { retry_on: vec![RetryCondition::ServerError], base_delay_ms: 5, ..RetryPolicy::default() },
+        ..SessionConfig::default()
+    })
+    .unwrap();
+    assert!(session.goto(&format!("http://127.0.0.1:{port}")).is_err());
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+    assert_eq!(session.last_attempts().len(), 1);
+}
@@ -102,6 +102,89 @@ fn test_session_type_text() {
     assert!(session.type_text(button_id, "text").is_err());
 }
 
+#[test]
+#[cfg(feature = "fetch")]
+fn test_session_redacts_sensitive_fields() {
+    let html = r#"
+    <html><body>
+        <form>
+            <input type="text" name="username" placeholder="Username" />
+            <input type="password" name="password" placeholder="Password" />
+            <input type="text" name="api_token" placeholder="Token" />
+            <button>Login</button>
+        </form>
+    </body></html>
+    "#;
+    let val = |session: &Session, name: &str| {
+        session.dom().unwrap().els.into_iter()
+            .find(|e| e.name.as_deref() == Some(name))
+            .and_then(|e| e.val)
+    };
+    let id = |session: &Session, name: &str| {
+        session.dom_ref().unwrap().els.iter()
+            .find(|e| e.name.as_deref() == Some(name))
+            .unwrap()
+            .id
+    };
+
+    let mut session = Session::new().unwrap();
+    session.load_html(html, "http://localhost").unwrap();
+    let (user, pass, token) = (id(&session, "username"), id(&session, "password"), id(&session, "api_token"));
+    session.type_text(user, "admin").unwrap();
+    session.type_text(pass, "hunter22").unwrap();
+    session.type_text(token, "tok-123").unwrap();
+
+    assert_eq!(session.typed_echo(user, "admin"), "\"admin\"");
+    assert_eq!(session.typed_echo(pass, "hunter22"), "•••• (8 chars)");
+    assert_eq!(session.typed_echo(token, "tok-123"), "•••• (7 chars)");
+    assert_eq!(val(&session, "username").as_deref(), Some("admin"));
+    assert_eq!(val(&session, "password").as_deref(), Some("•••• (8 chars)"));
+    assert_eq!(val(&session, "api_token").as_deref(), Some("•••• (7 chars)"));
+
+    let mut session = Session::with_config(fetch::SessionConfig {
+        redact_sensitive: false,
+        ..fetch::SessionConfig::default()
+    }).unwrap();
+    session.load_html(html, "http://localhost").unwrap();
+    let pass = id(&session, "password");
+    session.type_text(pass, "hunter22").unwrap();
+    assert_eq!(session.typed_echo(pass, "hunter22"), "\"hunter22\"");
+    assert_eq!(val(&session, "password").as_deref(), Some("hunter22"));
+}
+
+#[test]
+#[cfg(feature = "fetch")]
+fn test_redacted_counts_chars_not_bytes() {
+    assert_eq!(fetch::redact::redacted("hunter22"), "•••• (8 chars)");
+    assert_eq!(fetch::redact::redacted("pässwörd"), "•••• (8 chars)");
+}
+
+#[test]
+#[cfg(feature = "fetch")]
+fn test_scrub_replaces_every_occurrence() {
+    let text = "value=hunter2 again hunter2";
+    assert_eq!(fetch::redact::scrub(text, "hunter2"), "value=•••• (7 chars) again •••• (7 chars)");
+    assert_eq!(fetch::redact::scrub(text, ""), text);
+}
+
+#[test]
+#[cfg(feature = "fetch")]
+fn test_session_language_mismatch() {
+    let html = r#"<html lang="de"><body><h1>Willkommen</h1></body></html>"#;
+
+    let mut session = Session::new().unwrap();
+    session.load_html(html, "http://localhost").unwrap();
+    assert!(!session.language_mismatch(), "no accept_language configured");
+
+    let mut session = Session::with_config(fetch::SessionConfig {
+        accept_language: Some("en-US,en;q=0.9".to_string()),
+        ..fetch::SessionConfig::default()
+    }).unwrap();
+    assert!(!session.language_mismatch(), "no page loaded");
+    session.load_html(html, "http://localhost").unwrap();
+    assert!(session.language_mismatch());
+}
+
 #[test]
 #[cfg(feature = "fetch")]
 fn test_session_select() {
@@ -127,6 +210,58 @@ fn test_session_select() {
     assert!(session.select(button_id, "val").is_err());
 }
 
+#[test]
+#[cfg(feature = "fetch")]
+fn test_select_defaults_follow_option_rules() {
+    use browsy_core::fetch::ActionPreview;
+
+    let html = r#"
+    <html><body>
+        <form action="/a">
+            <select name="country">
+                <option>  United
+                    States </option>
+                <option>Canada</option>
+            </select>
+            <select name="size">
+                <optgroup label="Small">
+                    <option value="xs">Extra small</option>
+                    <option value="s" selected="">Small</option>
+                </optgroup>
+                <optgroup label="Large">
+                    <option value="l" selected>Large</option>
+                </optgroup>
+            </select>
+            <select name="plan">
+                <option value="" disabled>Choose a plan</option>
+                <optgroup label="Retired" disabled>
+                    <option value="legacy">Legacy</option>
+                </optgroup>
+                <option value="pro">Pro</option>
+            </select>
+            <select name="empty"></select>
+            <button>Send</button>
+        </form>
+    </body></html>
+    "#;
+
+    let mut session = Session::new().unwrap();
+    session.load_html(html, "https://example.com/").unwrap();
+    let send = session.find_by_role("button")[0].id;
+    let ActionPreview::Submit { fields, .. } = session.preview_submit(send).unwrap() else {
+        panic!("expected a submission");
+    };
+    let value = |name: &str| fields.iter().find(|f| f.name == name).map(|f| f.value.as_str());
+    // No value attribute: the text, whitespace collapsed
+    assert_eq!(value("country"), Some("United States"));
+    // Nested in optgroups, and the later of two selected options wins
+    assert_eq!(value("size"), Some("l"));
+    // Disabled options and groups are passed over
+    assert_eq!(value("plan"), Some("pro"));
+    // With no options to pick from, the select sends an empty value
+    assert_eq!(value("empty"), Some(""));
+}
+
 #[test]
 #[cfg(feature = "fetch")]
 fn test_session_delta() {
@@ -197,6 +332,168 @@ fn test_session_js_click_toggle() {
     assert!(dom.els.iter().any(|e| e.href.as_deref() == Some("/profile")));
 }
 
+#[test]
+#[cfg(feature = "fetch")]
+fn test_behaviors_follow_the_page_a_toggle_rendered() {
+    let mut session = Session::new().unwrap();
+    let html = r#"
+    <html><body>
+        <button onclick="toggle('menu')">Menu</button>
+        <div id="menu" style="display: none;">
+            <a href="/profile">Profile</a>
+        </div>
+    </body></html>
+    "#;
+    session.load_html(html, "http://localhost").unwrap();
+    assert_eq!(session.behaviors().len(), 1);
+    let menu = session.find_by_text("Menu")[0].id;
+    let profile_hidden = |session: &Session| {
+        let dom = session.dom().unwrap();
+        dom.els.iter().find(|e| e.text.as_deref() == Some("Profile")).unwrap().hidden
+    };
+    assert_eq!(profile_hidden(&session), Some(true));
+
+    // The second click works on the opened menu and closes it again
+    session.click(menu).unwrap();
+    assert_eq!(profile_hidden(&session), None);
+    assert_eq!(session.behaviors().len(), 1);
+    session.click(menu).unwrap();
+    assert_eq!(profile_hidden(&session), Some(true));
+
+    // A new page brings its own behaviors
+    session.load_html("<html><body><button>Plain</button></body></html>", "http://localhost/other").unwrap();
+    assert!(session.behaviors().is_empty());
+}
+
+#[test]
+#[cfg(feature = "fetch")]
+fn test_toggle_after_elements_the_output_leaves_out() {
+    let mut session = Session::new().unwrap();
+    // Link cards whose heading and blurb the links already carry, template
+    // content, a noscript notice and an empty zero-size button all come
+    // before the toggle, and only some of them are emitted
+    let html = r#"
+    <html><body>
+        <a href="/one"><h3>First card</h3><p>About the first</p></a>
+        <a href="/two"><h3>Second card</h3><p>About the second</p></a>
+        <template><button>Template button</button><p>Template text</p></template>
+        <noscript><p>Please enable JavaScript</p></noscript>
+        <button style="width: 0; height: 0; padding: 0; border: 0"></button>
+        <button onclick="toggle('menu')">Menu</button>
+        <div id="menu" style="display: none;">
+            <a href="/profile">Profile</a>
+        </div>
+    </body></html>
+    "#;
+    session.load_html(html, "http://localhost").unwrap();
+    let menu = session.find_by_text("Menu")[0].id;
+    assert_eq!(session.behaviors().len(), 1);
+    assert_eq!(session.behaviors()[0].trigger_id, menu);
+
+    session.click(menu).unwrap();
+    let dom = session.dom().unwrap();
+    let profile = dom.els.iter().find(|e| e.text.as_deref() == Some("Profile")).unwrap();
+    assert_eq!(profile.hidden, None);
+}
+
+const STABLE_LOGIN_HTML: &str = r#"
+    <html><body>
+        <button onclick="document.getElementById('menu').style.display = 'block'">Language</button>
+        <ul id="menu" style="display: none;">
+            <li><a href="/en">English</a></li>
+            <li><a href="/de">Deutsch</a></li>
+        </ul>
+        <form action="/login" method="post">
+            <input type="email" name="email" placeholder="Email" />
+            <input type="password" name="password" placeholder="Password" />
+            <button type="submit">Sign in</button>
+        </form>
+    </body></html>
+"#;
+
+#[test]
+#[cfg(feature = "fetch")]
+fn test_stable_ids_across_rerenders() {
+    let mut session = Session::with_config(fetch::SessionConfig {
+        fetch_css: false,
+        ..Default::default()
+    })
+    .unwrap();
+    session.load_html(STABLE_LOGIN_HTML, "http://localhost/login").unwrap();
+    let sign_in = session.find_by_text("Sign in")[0].id;
+    let toggle = session.find_by_text("Language")[0].id;
+
+    // Toggling the dropdown re-renders the page; the login button keeps its id
+    let dom = session.click(toggle).unwrap();
+    assert_eq!(dom.find_by_text("Sign in")[0].id, sign_in);
+    assert_eq!(dom.find_by_text("Language")[0].id, toggle);
+    assert!(dom.suggested_actions.iter().any(|a| matches!(
+        a,
+        browsy_core::output::SuggestedAction::Login { submit_id, .. } if *submit_id == sign_in
+    )));
+
+    // A failed submit that re-renders the same page with an error above the form
+    let max_id = dom.els.iter().map(|e| e.id).max().unwrap();
+    let with_error = STABLE_LOGIN_HTML.replace(
+        "<form",
+        r#"<div role="alert">Wrong password</div><form"#,
+    );
+    let dom = session.load_html(&with_error, "http://localhost/login").unwrap();
+    assert_eq!(dom.find_by_text("Sign in")[0].id, sign_in);
+    assert_eq!(session.element(sign_in).unwrap().text.as_deref(), Some("Sign in"));
+    assert!(dom.find_by_text("Wrong password")[0].id > max_id);
+
+    // A different page starts numbering afresh
+    let dom = session.load_html(STABLE_LOGIN_HTML, "http://localhost/other").unwrap();
+    assert_eq!(dom.els[0].id, 1);
+}
+
+#[test]
+#[cfg(feature = "fetch")]
+fn test_resize_relays_out_current_page() {
+    let mut session = Session::with_config(fetch::SessionConfig {
+        fetch_css: false,
+        ..Default::default()
+    })
+    .unwrap();
+    assert!(session.resize(1280.0, 720.0).is_none());
+
+    let html = r#"<html><head><meta name="viewport" content="width=device-width"></head><body>
+        <form><input type="text" name="q" style="width: 100%"><button>Search</button></form>
+    </body></html>"#;
+    let dom = session.load_html(html, "http://localhost/search").unwrap();
+    let input = dom.els.iter().find(|e| e.tag == "input").unwrap().clone();
+    session.type_text(input.id, "shoes").unwrap();
+
+    let dom = session.resize(375.0, 812.0).unwrap();
+    assert_eq!(dom.vp, [375.0, 812.0]);
+    assert_eq!(session.viewport(), (375.0, 812.0));
+    let resized = dom.els.iter().find(|e| e.tag == "input").unwrap();
+    assert_eq!(resized.id, input.id);
+    assert!(resized.b[2] < input.b[2], "{:?} vs {:?}", resized.b, input.b);
+    assert_eq!(resized.val.as_deref(), Some("shoes"));
+}
+
+#[test]
+#[cfg(feature = "fetch")]
+fn test_stable_ids_disabled() {
+    let mut session = Session::with_config(fetch::SessionConfig {
+        fetch_css: false,
+        stable_ids: false,
+        ..Default::default()
+    })
+    .unwrap();
+    session.load_html(STABLE_LOGIN_HTML, "http://localhost/login").unwrap();
+    let sign_in = session.find_by_text("Sign in")[0].id;
+
+    let with_error = STABLE_LOGIN_HTML.replace(
+        "<form",
+        r#"<div role="alert">Wrong password</div><form"#,
+    );
+    let dom = session.load_html(&with_error, "http://localhost/login").unwrap();
+    assert_eq!(dom.find_by_text("Sign in")[0].id, sign_in + 1);
+}
+
 #[test]
 #[cfg(feature = "fetch")]
 fn test_search_result_parsing() {
@@ -340,6 +637,78 @@ fn test_google_result_parsing() {
     assert!(results[1].snippet.contains("multi-paradigm"));
 }
 
+#[test]
+#[cfg(feature = "fetch")]
+fn test_ddg_result_urls_are_decoded_and_cleaned() {
+    // Captured from html.duckduckgo.com, trimmed to one result
+    let html = r#"
+    <html><body>
+    <div class="result results_links results_links_deep web-result">
+        <div class="links_main links_deep result__body">
+            <h2 class="result__title">
+                <a rel="nofollow" class="result__a" href="//duckduckgo.com/l/?uddg=https%3A%2F%2Fblog.example.com%2F2024%2Frust%2Dasync%3Futm_source%3Dddg%26utm_medium%3Dsearch%26page%3D2&amp;rut=9f2c41">Async Rust in practice</a>
+            </h2>
+            <a class="result__snippet" href="//duckduckgo.com/l/?uddg=https%3A%2F%2Fblog.example.com%2F2024%2Frust%2Dasync%3Futm_source%3Dddg%26utm_medium%3Dsearch%26page%3D2&amp;rut=9f2c41">What we learned moving a service to <b>async</b> Rust.</a>
+        </div>
+    </div>
+    </body></html>
+    "#;
+
+    let results = fetch::extract_search_results_from(&browsy_core::dom::parse_html(html));
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].url, "https://blog.example.com/2024/rust-async?page=2");
+    assert!(results[0].raw_url.starts_with("//duckduckgo.com/l/?uddg="));
+    assert!(results[0].raw_url.ends_with("&rut=9f2c41"));
+}
+
+#[test]
+#[cfg(feature = "fetch")]
+fn test_google_result_urls_drop_tracking_params() {
+    // Captured from google.com/search, trimmed to two results
+    let html = r#"
+    <html><body>
+    <div id="rso">
+        <div class="MjjYud"><div class="tF2Cxc"><div class="yuRUbf">
+            <a href="/url?q=https://docs.example.org/guide%3Fsection%3Dinstall&amp;sa=U&amp;ved=2ahUKEwi8&amp;usg=AOvVaw3">
+                <h3 class="LC20lb">Installation guide</h3>
+            </a>
+        </div></div></div>
+        <div class="MjjYud"><div class="tF2Cxc"><div class="yuRUbf">
+            <a href="https://shop.example.net/item/42?color=red&amp;gclid=Cj0KCQ&amp;fbclid=IwAR2&amp;ved=2ahUK">
+                <h3 class="LC20lb">Red item</h3>
+            </a>
+        </div></div></div>
+    </div>
+    </body></html>
+    "#;
+
+    let results = fetch::extract_google_results_from(&browsy_core::dom::parse_html(html));
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].url, "https://docs.example.org/guide?section=install");
+    assert!(results[0].raw_url.starts_with("/url?q="));
+    assert_eq!(results[1].url, "https://shop.example.net/item/42?color=red");
+    assert!(results[1].raw_url.contains("gclid="));
+}
+
+#[test]
+#[cfg(feature = "fetch")]
+fn test_normalize_result_url() {
+    let tracking = fetch::default_tracking_params();
+    let clean = |href: &str| fetch::normalize_result_url(href, &tracking);
+
+    assert_eq!(
+        clean("//duckduckgo.com/l/?uddg=https%3A%2F%2Fexample.com%2Fa%20b%3Fid%3D7%26utm_campaign%3Dx&rut=1"),
+        "https://example.com/a%20b?id=7"
+    );
+    assert_eq!(clean("/url?q=https://example.com/&sa=U&ved=abc"), "https://example.com/");
+    assert_eq!(clean("https://example.com/?ved=1&usg=2&fbclid=3"), "https://example.com/");
+    // Other parameters keep their order and encoding
+    assert_eq!(clean("https://example.com/s?q=a+b&gclid=1&lang=en"), "https://example.com/s?q=a+b&lang=en");
+    // Not a redirect to unwrap, and not a URL
+    assert_eq!(clean("https://example.com/url?q=rust"), "https://example.com/url?q=rust");
+    assert_eq!(clean("/search?q=rust"), "/search?q=rust");
+}
+
 #[test]
 #[ignore]
 #[cfg(feature = "fetch")]
@@ -641,3 +1010,273 @@ fn test_find_input_by_purpose() {
     assert!(phone.is_some());
     assert_eq!(phone.unwrap().input_type.as_deref(), Some("tel"));
 }
+
+#[test]
+#[cfg(feature = "fetch")]
+fn test_load_more_click_follows_data_url_and_href() {
+    use browsy_core::output::SuggestedAction;
+
+    let dir = std::env::temp_dir().join(format!("browsy-load-more-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let url = "https://herald.example/news?page=2";
+    let exchange = fetch::Exchange {
+        method: "GET".to_string(),
+        url: url.to_string(),
+        body_hash: None,
+        status: 200,
+        final_url: url.to_string(),
+        headers: vec![("content-type".to_string(), "text/html".to_string())],
+        body: "<html><head><title>Latest news - page 2</title></head><body><h1>Older stories</h1></body></html>".to_string(),
+    };
+    std::fs::write(dir.join("0000.json"), serde_json::to_string(&exchange).unwrap()).unwrap();
+
+    let mut session = Session::with_config(fetch::SessionConfig {
+        fetch_css: false,
+        recording: Some(fetch::RecordingMode::Replay(dir.clone())),
+        ..Default::default()
+    })
+    .unwrap();
+
+    for fixture in ["load_more_button.html", "load_more_link.html"] {
+        let html = std::fs::read_to_string(format!(
+            "{}/tests/fixtures/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            fixture
+        ))
+        .unwrap();
+        let dom = session.load_html(&html, "https://herald.example/news").unwrap();
+        let button_id = dom
+            .suggested_actions
+            .iter()
+            .find_map(|a| match a {
+                SuggestedAction::LoadMore { button_id } => Some(*button_id),
+                _ => None,
+            })
+            .unwrap_or_else(|| panic!("{fixture}: no LoadMore action"));
+
+        let next = session.click(button_id).unwrap();
+        assert_eq!(next.url, url, "{fixture}");
+        assert_eq!(next.title, "Latest news - page 2", "{fixture}");
+    }
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+#[cfg(feature = "fetch")]
+fn test_without_boilerplate_compares_with_previous_page() {
+    let mut session = Session::new().unwrap();
+    let first = session
+        .load_html(include_str!("fixtures/site_first.html"), "https://garden.example/tomatoes")
+        .unwrap();
+    // Nothing to compare the first page with
+    assert_eq!(session.without_boilerplate(first.clone()).els.len(), first.els.len());
+
+    let second = session
+        .load_html(include_str!("fixtures/site_second.html"), "https://garden.example/peppers")
+        .unwrap();
+    let fresh = session.without_boilerplate(second);
+    assert!(fresh.boilerplate_suppressed > 0);
+    assert!(fresh.els.iter().all(|e| e.href.is_none()), "nav links should be gone");
+    assert!(fresh.els.iter().any(|e| e.text.as_deref() == Some("Growing peppers")));
+}
+
+#[cfg(feature = "fetch")]
+fn disabled_form_session(force_actions: bool) -> (Session, impl Fn(&str) -> u32) {
+    let mut session = Session::with_config(fetch::SessionConfig {
+        fetch_css: false,
+        force_actions,
+        ..Default::default()
+    })
+    .unwrap();
+    let dom = session
+        .load_html(include_str!("fixtures/disabled_form.html"), "https://plans.example/plan")
+        .unwrap();
+    let id = move |key: &str| {
+        dom.els
+            .iter()
+            .find(|e| e.name.as_deref() == Some(key) || e.text.as_deref() == Some(key))
+            .unwrap_or_else(|| panic!("no element {key}"))
+            .id
+    };
+    (session, id)
+}
+
+#[test]
+#[cfg(feature = "fetch")]
+fn test_actions_refuse_disabled_and_readonly_elements() {
+    use browsy_core::fetch::{ErrorCode, FetchError};
+
+    let (mut session, id) = disabled_form_session(false);
+    let state = |err: FetchError| match err {
+        FetchError::ElementUnavailable { state, .. } => state,
+        other => panic!("expected ElementUnavailable, got {other:?}"),
+    };
+
+    let err = session.type_text(id("account"), "grace@example.com").unwrap_err();
+    assert_eq!(err.code(), ErrorCode::ActionElementUnavailable);
+    assert!(err.to_string().contains("is readonly"), "{err}");
+    assert_eq!(state(session.type_text(id("notes"), "hi").unwrap_err()), "readonly");
+    assert_eq!(state(session.select(id("region"), "us").unwrap_err()), "disabled");
+    assert_eq!(state(session.check(id("legacy")).unwrap_err()), "disabled");
+    assert_eq!(state(session.toggle(id("legacy")).unwrap_err()), "disabled");
+
+    // Nothing was stored for the refused actions
+    let dom = session.dom().unwrap();
+    assert_eq!(dom.get(id("account")).unwrap().val.as_deref(), Some("ada@example.com"));
+    assert_ne!(dom.get(id("region")).unwrap().val.as_deref(), Some("us"));
+    assert_ne!(dom.get(id("legacy")).unwrap().checked, Some(true));
+
+    // The enabled fields still take input, and aria-disabled="false" is enabled
+    session.type_text(id("name"), "Ada Lovelace").unwrap();
+    assert_eq!(session.element(id("Show help")).unwrap().disabled, Some(false));
+}
+
+#[test]
+#[cfg(feature = "fetch")]
+fn test_action_errors_carry_codes_and_details() {
+    use browsy_core::fetch::{ErrorCode, FetchError};
+
+    let mut session = Session::with_config(fetch::SessionConfig {
+        fetch_css: false,
+        ..Default::default()
+    })
+    .unwrap();
+    let err = session.click(1).unwrap_err();
+    assert_eq!(err.to_string(), "Action error: No page loaded");
+    assert_eq!(err.code(), ErrorCode::NoPageLoaded);
+
+    let dom = session
+        .load_html(r#"<a href="/docs">Docs</a><input type="text" name="q">"#, "https://example.com/")
+        .unwrap();
+    let link = dom.els.iter().find(|e| e.tag == "a").unwrap().id;
+    let last = dom.els.iter().map(|e| e.id).max().unwrap();
+
+    let missing = session.click(99).unwrap_err();
+    assert_eq!(missing.to_string(), "Action error: Element 99 not found");
+    assert_eq!(missing.code(), ErrorCode::ActionElementNotFound);
+    assert_eq!(missing.details().unwrap()["known_ids_range"], serde_json::json!([1, last]));
+
+    let wrong = session.type_text(link, "rust").unwrap_err();
+    assert_eq!(wrong.to_string(), format!("Action error: Element {link} (a) is not a text input"));
+    assert_eq!(wrong.code().as_str(), "action_wrong_element_type");
+
+    assert_eq!(FetchError::HttpError(404).details().unwrap()["status"], 404);
+    assert_eq!(FetchError::Network("refused".into()).details(), None);
+    assert_eq!(serde_json::to_value(ErrorCode::SessionExpired).unwrap(), "session_expired");
+}
+
+#[test]
+#[cfg(feature = "fetch")]
+fn test_disabled_submit_names_empty_required_fields() {
+    use browsy_core::fetch::FetchError;
+
+    let (mut session, id) = disabled_form_session(false);
+    let empty = |session: &mut Session| match session.click(id("Save plan")).unwrap_err() {
+        FetchError::ElementUnavailable { state, empty_required, .. } => {
+            assert_eq!(state, "disabled");
+            empty_required
+        }
+        other => panic!("expected ElementUnavailable, got {other:?}"),
+    };
+
+    assert_eq!(empty(&mut session), [id("name"), id("terms")]);
+    session.type_text(id("name"), "Ada Lovelace").unwrap();
+    assert_eq!(empty(&mut session), [id("terms")]);
+    session.check(id("terms")).unwrap();
+    assert!(empty(&mut session).is_empty());
+}
+
+#[test]
+#[cfg(feature = "fetch")]
+fn test_force_actions_bypasses_disabled_and_readonly() {
+    let (mut session, id) = disabled_form_session(true);
+    session.type_text(id("account"), "grace@example.com").unwrap();
+    session.select(id("region"), "us").unwrap();
+    session.check(id("legacy")).unwrap();
+
+    let dom = session.dom().unwrap();
+    assert_eq!(dom.get(id("account")).unwrap().val.as_deref(), Some("grace@example.com"));
+    assert_eq!(dom.get(id("region")).unwrap().val.as_deref(), Some("us"));
+    assert_eq!(dom.get(id("legacy")).unwrap().readonly, None);
+    assert_eq!(dom.get(id("account")).unwrap().readonly, Some(true));
+}
+
+#[test]
+#[cfg(feature = "fetch")]
+fn test_typing_outside_datalist_suggestions_warns() {
+    let mut session = Session::with_config(fetch::SessionConfig {
+        fetch_css: false,
+        ..Default::default()
+    })
+    .unwrap();
+    let dom = session
+        .load_html(include_str!("fixtures/countries.html"), "https://shop.example/ship")
+        .unwrap();
+    let id = |name: &str| dom.els.iter().find(|e| e.name.as_deref() == Some(name)).unwrap().id;
+
+    // Past the serialized 20, so the whole list is checked
+    session.type_text(id("country"), "Morocco").unwrap();
+    assert_eq!(session.suggestion_warning(id("country"), "Morocco"), None);
+
+    session.type_text(id("country"), "Atlantis").unwrap();
+    let warning = session.suggestion_warning(id("country"), "Atlantis").unwrap();
+    assert_eq!(warning, format!("\"Atlantis\" is not one of the 200 values element {} suggests", id("country")));
+    assert_eq!(session.dom().unwrap().get(id("country")).unwrap().val.as_deref(), Some("Atlantis"));
+
+    // Fields without a datalist never warn
+    session.type_text(id("city"), "Atlantis").unwrap();
+    assert_eq!(session.suggestion_warning(id("city"), "Atlantis"), None);
+}
+
+#[test]
+#[cfg(feature = "fetch")]
+fn test_prefilled_values_and_defaults() {
+    let mut session = Session::with_config(fetch::SessionConfig {
+        fetch_css: false,
+        ..Default::default()
+    })
+    .unwrap();
+    let dom = session
+        .load_html(include_str!("fixtures/edit_profile.html"), "https://example.com/profile")
+        .unwrap();
+    let id = |name: &str| dom.els.iter().find(|e| e.name.as_deref() == Some(name)).unwrap().id;
+
+    let page = session.dom().unwrap();
+    let el = |name: &str| page.get(id(name)).unwrap().clone();
+    assert_eq!(el("name").val.as_deref(), Some("Ada Lovelace"));
+    // Set but empty is not the same as absent
+    assert_eq!(el("website").val.as_deref(), Some(""));
+    assert_eq!(el("company").val, None);
+    // A textarea's value is its text, and its text isn't repeated as a name
+    let bio = el("bio");
+    assert_eq!(bio.val.as_deref(), Some("Mathematician.\nWrites about the Analytical Engine."));
+    assert_eq!(bio.text, None);
+    assert_eq!(el("notes").val, None);
+    assert!(page.els.iter().all(|e| e.default_val.is_none()));
+    assert!(browsy_core::output::to_compact_string(&page)
+        .contains("[=Mathematician. Writes about the Analytical Engine.]"));
+
+    session.type_text(id("name"), "Ada King").unwrap();
+    session.type_text(id("bio"), "Countess of Lovelace.").unwrap();
+    session.type_text(id("company"), "Analytical Engines Ltd").unwrap();
+
+    let page = session.dom().unwrap();
+    let el = |name: &str| page.get(id(name)).unwrap().clone();
+    assert_eq!(el("name").val.as_deref(), Some("Ada King"));
+    assert_eq!(el("name").default_val.as_deref(), Some("Ada Lovelace"));
+    assert_eq!(el("bio").val.as_deref(), Some("Countess of Lovelace."));
+    assert_eq!(
+        el("bio").default_val.as_deref(),
+        Some("Mathematician.\nWrites about the Analytical Engine.")
+    );
+    assert_eq!(el("company").default_val, None);
+    assert_eq!(el("website").default_val, None);
+    let compact = browsy_core::output::to_compact_string(&page);
+    assert!(compact.contains("[=Ada King]"), "{compact}");
+    assert!(!compact.contains("Ada Lovelace"), "{compact}");
+
+    // The page itself is unchanged
+    assert_eq!(session.dom_ref().unwrap().get(id("name")).unwrap().val.as_deref(), Some("Ada Lovelace"));
+}
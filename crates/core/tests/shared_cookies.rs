@@ -0,0 +1,87 @@
+//! Sessions with jars sharing some domains see each other's cookies for
+//! those domains only, and cookies move between sessions as JSON.
+
+#![cfg(feature = "fetch")]
+
+mod common;
+
+use std::sync::Arc;
+
+use browsy_core::fetch::{CookieJar, Session, SessionConfig};
+
+fn config() -> SessionConfig {
+    SessionConfig {
+        fetch_css: false,
+        allow_private_network: true,
+        ..SessionConfig::default()
+    }
+}
+
+fn signed_in(session: &mut Session, url: &str) -> bool {
+    let dom = session.goto(url).unwrap();
+    dom.els.iter().any(|e| e.text.as_deref() == Some("Signed in as Ada"))
+}
+
+#[test]
+fn test_shared_domains_share_cookies_and_others_stay_per_session() {
+    let port = common::serve_sign_in();
+    let shared = Arc::new(CookieJar::default());
+    let domains = vec!["localhost".to_string()];
+    let jar = || Arc::new(CookieJar::sharing(shared.clone(), &domains));
+    let mut a = Session::with_cookie_jar(config(), jar()).unwrap();
+    let mut b = Session::with_cookie_jar(config(), jar()).unwrap();
+
+    a.goto(&format!("http://localhost:{port}/login")).unwrap();
+    a.goto(&format!("http://127.0.0.1:{port}/login")).unwrap();
+
+    assert!(signed_in(&mut b, &format!("http://localhost:{port}/")));
+    assert!(
+        !signed_in(&mut b, &format!("http://127.0.0.1:{port}/")),
+        "a cookie of an unshared host leaked to another session"
+    );
+    assert!(signed_in(&mut a, &format!("http://127.0.0.1:{port}/")));
+
+    // Resetting one session leaves the shared login to the others
+    a.reset();
+    assert!(!signed_in(&mut a, &format!("http://127.0.0.1:{port}/")));
+    assert!(signed_in(&mut b, &format!("http://localhost:{port}/")));
+}
+
+#[test]
+fn test_parent_domain_cookies_reach_both_sides_of_shared_domains() {
+    use reqwest::cookie::CookieStore;
+    use reqwest::header::HeaderValue;
+
+    let jar = CookieJar::sharing(Arc::new(CookieJar::default()), &["app.example.com".to_string()]);
+    let url = |url: &str| url::Url::parse(url).unwrap();
+    let set = |cookie: &str, at: &str| {
+        jar.set_cookies(&mut std::iter::once(&HeaderValue::from_str(cookie).unwrap()), &url(at));
+    };
+    let sent = |to: &str| jar.cookies(&url(to)).map(|h| h.to_str().unwrap().to_string()).unwrap_or_default();
+
+    // One set by an unshared host, one by a shared host, both for the parent domain
+    set("own=1; Domain=example.com; Path=/", "https://example.com/");
+    set("shared=1; Domain=example.com; Path=/", "https://app.example.com/");
+
+    for host in ["https://example.com/", "https://app.example.com/", "https://www.example.com/"] {
+        let cookies = sent(host);
+        assert!(cookies.contains("own=1") && cookies.contains("shared=1"), "{host}: {cookies}");
+    }
+    assert_eq!(sent("https://example.org/"), "");
+}
+
+#[test]
+fn test_exported_cookies_import_into_another_session() {
+    let port = common::serve_sign_in();
+    let mut a = Session::with_config(config()).unwrap();
+    a.goto(&format!("http://127.0.0.1:{port}/login")).unwrap();
+    let json = a.export_cookies();
+    assert!(json.contains("sid"), "{json}");
+
+    let mut b = Session::with_config(config()).unwrap();
+    assert!(!signed_in(&mut b, &format!("http://127.0.0.1:{port}/")));
+    assert_eq!(b.import_cookies(&json).unwrap(), 1);
+    assert!(signed_in(&mut b, &format!("http://127.0.0.1:{port}/")));
+
+    assert!(b.import_cookies("{ not cookies").is_err());
+}
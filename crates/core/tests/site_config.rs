@@ -0,0 +1,126 @@
+//! `SessionConfig::site_overrides_path`: per-site settings from a
+//! `browsy.toml` file. The local server is reached as both `localhost` and
+//! `127.0.0.1`, two hosts with overrides of their own.
+
+#![cfg(feature = "fetch")]
+
+mod common;
+
+use std::path::PathBuf;
+
+use browsy_core::fetch::{ConsentChoice, FetchError, Session, SessionConfig, SiteOverrides};
+use common::Response;
+
+/// Serve a page whose title is the request's `User-Agent` and whose body is
+/// its `X-Site` header. Returns the port.
+fn serve() -> u16 {
+    common::serve(|request| {
+        let user_agent = request.header("user-agent").unwrap_or_default();
+        let site = request.header("x-site").unwrap_or("none");
+        Response::html(format!("<html><head><title>{user_agent}</title></head><body><p>{site}</p></body></html>"))
+    })
+}
+
+fn write_config(name: &str, toml: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("browsy-{}-{name}.toml", std::process::id()));
+    std::fs::write(&path, toml).unwrap();
+    path
+}
+
+#[test]
+fn test_most_specific_suffix_wins() {
+    let overrides = SiteOverrides::parse(
+        r#"
+        [sites."example.com"]
+        fetch_css = false
+
+        [sites."*.docs.example.com"]
+        user_agent = "docs-agent"
+        "#,
+    )
+    .unwrap();
+
+    let (suffix, site) = overrides.for_host("api.docs.example.com").unwrap();
+    assert_eq!(suffix, "docs.example.com");
+    assert_eq!(site.user_agent.as_deref(), Some("docs-agent"));
+    assert_eq!(site.fetch_css, None);
+
+    assert_eq!(overrides.for_host("WWW.Example.com").unwrap().0, "example.com");
+    assert_eq!(overrides.for_host("example.com").unwrap().0, "example.com");
+    // A suffix only matches whole labels
+    assert!(overrides.for_host("notexample.com").is_none());
+}
+
+#[test]
+fn test_parse_errors_give_the_line() {
+    let path = write_config(
+        "invalid",
+        "[sites.\"example.com\"]\nfetch_css = false\nviewport = \"mobile\"\n",
+    );
+    let result = Session::with_config(SessionConfig {
+        site_overrides_path: Some(path.clone()),
+        ..SessionConfig::default()
+    });
+    std::fs::remove_file(&path).unwrap();
+    match result {
+        Err(FetchError::ActionError(msg)) => {
+            assert!(msg.contains("line 3"), "{msg}");
+            assert!(msg.contains(&path.display().to_string()), "{msg}");
+        }
+        Err(e) => panic!("expected a parse error, got {e}"),
+        Ok(_) => panic!("expected a parse error"),
+    }
+
+    let unknown = SiteOverrides::parse("[sites.\"example.com\"]\nfetch_ccs = false\n").unwrap_err();
+    assert!(unknown.to_string().contains("fetch_ccs"), "{unknown}");
+}
+
+#[test]
+fn test_overrides_switch_with_each_host() {
+    let port = serve();
+    let path = write_config(
+        "hosts",
+        r#"
+        [sites."localhost"]
+        user_agent = "phone-agent"
+        viewport = [390, 844]
+        headers = { "X-Site" = "local" }
+        auto_consent = "reject"
+
+        [sites."127.0.0.1"]
+        per_host_delay_ms = 1
+        "#,
+    );
+    let mut session = Session::with_config(SessionConfig {
+        fetch_css: false,
+        allow_private_network: true,
+        user_agent: "desk-agent".to_string(),
+        site_overrides_path: Some(path.clone()),
+        ..SessionConfig::default()
+    })
+    .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let dom = session.goto(&format!("http://localhost:{port}/")).unwrap();
+    assert_eq!(dom.title, "phone-agent");
+    assert!(dom.find_by_text("local").iter().any(|e| e.tag == "p"));
+    assert_eq!(session.site_override(), Some("localhost"));
+    let config = session.effective_config();
+    assert_eq!((config.viewport_width, config.viewport_height), (390.0, 844.0));
+    assert_eq!(config.auto_dismiss_cookie_banners, Some(ConsentChoice::Reject));
+
+    // The next host puts back what the first one overrode
+    let dom = session.goto(&format!("http://127.0.0.1:{port}/")).unwrap();
+    assert_eq!(dom.title, "desk-agent");
+    assert!(dom.find_by_text("none").iter().any(|e| e.tag == "p"));
+    assert_eq!(session.site_override(), Some("127.0.0.1"));
+    let config = session.effective_config();
+    assert_eq!((config.viewport_width, config.viewport_height), (1920.0, 1080.0));
+    assert_eq!(config.auto_dismiss_cookie_banners, None);
+    assert_eq!(config.per_host_delay_ms, 1);
+    assert!(config.extra_headers.is_empty());
+
+    let dom = session.goto(&format!("http://localhost:{port}/again")).unwrap();
+    assert_eq!(dom.title, "phone-agent");
+    assert_eq!(session.effective_config().per_host_delay_ms, 0);
+}
@@ -0,0 +1,112 @@
+//! Session snapshots: folder contents, redaction of typed secrets, and
+//! automatic snapshots when an action fails.
+
+#![cfg(feature = "fetch")]
+
+use std::path::{Path, PathBuf};
+
+use browsy_core::fetch::{Session, SessionConfig};
+use serde_json::Value;
+
+const LOGIN_PAGE: &str = r#"<html><head><title>Sign in</title></head><body>
+    <form action="/login" method="post">
+        <input type="text" name="username" placeholder="Username">
+        <input type="password" name="password" placeholder="Password">
+        <select name="plan"><option value="free">Free</option><option value="pro">Pro</option></select>
+        <button type="submit">Sign in</button>
+    </form>
+    <a href="https://tracker.example/pixel">Partner</a>
+</body></html>"#;
+
+fn snapshot_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("browsy-snapshot-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn read_json(path: &Path) -> Value {
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+fn field_id(session: &Session, name: &str) -> u32 {
+    session.dom_ref().unwrap().els.iter().find(|e| e.name.as_deref() == Some(name)).unwrap().id
+}
+
+fn folders(dir: &Path) -> Vec<PathBuf> {
+    let mut folders: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| entries.filter_map(|e| e.ok().map(|e| e.path())).collect())
+        .unwrap_or_default();
+    folders.sort();
+    folders
+}
+
+#[test]
+fn test_save_snapshot_writes_page_and_redacted_form_state() {
+    let dir = snapshot_dir("manual");
+    let mut session = Session::with_config(SessionConfig { fetch_css: false, ..SessionConfig::default() }).unwrap();
+    session.load_html(LOGIN_PAGE, "https://example.com/login").unwrap();
+    session.type_text(field_id(&session, "username"), "alice").unwrap();
+    session.type_text(field_id(&session, "password"), "hunter22").unwrap();
+    session.select(field_id(&session, "plan"), "pro").unwrap();
+
+    let folder = session.save_snapshot(&dir).unwrap();
+    assert_eq!(folders(&dir), vec![folder.clone()]);
+    assert!(folder.file_name().unwrap().to_str().unwrap().ends_with("-example.com"));
+
+    let meta = read_json(&folder.join("meta.json"));
+    assert_eq!(meta["url"], "https://example.com/login");
+    assert_eq!(meta["title"], "Sign in");
+    assert!(meta.get("reason").is_none());
+    assert_eq!(std::fs::read_to_string(folder.join("page.html")).unwrap(), LOGIN_PAGE);
+
+    let values = read_json(&folder.join("form_state.json"))["values"].clone();
+    let value_of = |name: &str| {
+        values.as_array().unwrap().iter().find(|v| v["name"] == name).unwrap()["value"].clone()
+    };
+    assert_eq!(value_of("username"), "alice");
+    assert_eq!(value_of("password"), "•••• (8 chars)");
+    assert_eq!(value_of("plan"), "pro");
+
+    let dom = read_json(&folder.join("dom.json"));
+    let password = dom["els"].as_array().unwrap().iter().find(|e| e["name"] == "password").unwrap();
+    assert_eq!(password["val"], "•••• (8 chars)");
+
+    let audit = read_json(&folder.join("audit.json"));
+    assert_eq!(audit.as_array().unwrap().len(), 4);
+    for file in ["meta.json", "page.html", "dom.json", "form_state.json", "audit.json"] {
+        assert!(!std::fs::read_to_string(folder.join(file)).unwrap().contains("hunter22"), "{file}");
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_snapshot_on_error_saves_one_snapshot_per_failed_action() {
+    let dir = snapshot_dir("on-error");
+    let mut session = Session::with_config(SessionConfig {
+        fetch_css: false,
+        blocked_domains: vec!["tracker.example".to_string()],
+        snapshot_on_error: Some(dir.clone()),
+        ..SessionConfig::default()
+    })
+    .unwrap();
+    session.load_html(LOGIN_PAGE, "https://example.com/login").unwrap();
+    session.type_text(field_id(&session, "password"), "hunter22").unwrap();
+    assert!(folders(&dir).is_empty());
+
+    // The click fails inside the goto it triggers; only the click snapshots
+    let partner = session.find_by_text("Partner")[0].id;
+    assert!(session.click(partner).is_err());
+    let saved = folders(&dir);
+    assert_eq!(saved.len(), 1);
+    let meta = read_json(&saved[0].join("meta.json"));
+    assert!(meta["reason"].as_str().unwrap().contains("tracker.example"), "{meta}");
+    assert_eq!(meta["url"], "https://example.com/login");
+    let form_state = std::fs::read_to_string(saved[0].join("form_state.json")).unwrap();
+    assert!(form_state.contains("•••• (8 chars)") && !form_state.contains("hunter22"));
+    let audit = read_json(&saved[0].join("audit.json"));
+    assert_eq!(audit.as_array().unwrap().last().unwrap()["type"], "error");
+
+    assert!(session.goto("https://tracker.example/").is_err());
+    assert_eq!(folders(&dir).len(), 2);
+    let _ = std::fs::remove_dir_all(&dir);
+}
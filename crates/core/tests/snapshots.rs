@@ -0,0 +1,73 @@
+//! Spatial DOM JSON written by older and newer versions still loads, and
+//! reads back the same.
+
+use browsy_core::output::{self, SpatialDom, SCHEMA_VERSION};
+
+/// Frozen snapshots, each with the compact output it gave when it was written.
+const CORPUS: &[(&str, &str, &str)] = &[
+    (
+        "unversioned_login",
+        include_str!("fixtures/snapshots/unversioned_login.json"),
+        include_str!("fixtures/snapshots/unversioned_login.compact"),
+    ),
+    (
+        "unversioned_minimal",
+        include_str!("fixtures/snapshots/unversioned_minimal.json"),
+        include_str!("fixtures/snapshots/unversioned_minimal.compact"),
+    ),
+    (
+        "newer_schema",
+        include_str!("fixtures/snapshots/newer_schema.json"),
+        include_str!("fixtures/snapshots/newer_schema.compact"),
+    ),
+];
+
+#[test]
+fn test_snapshot_corpus_loads_with_identical_compact_output() {
+    for (name, json, compact) in CORPUS {
+        let dom = output::migrate(json).unwrap_or_else(|e| panic!("{name}: {e}"));
+        assert_eq!(dom.schema, SCHEMA_VERSION, "{name}");
+        assert_eq!(output::to_compact_string(&dom), compact.trim_end(), "{name}");
+        for el in &dom.els {
+            assert_eq!(dom.get(el.id), Some(el), "{name}: id index");
+        }
+
+        let from_json = SpatialDom::from_json(json).unwrap();
+        assert_eq!(output::to_compact_string(&from_json), compact.trim_end(), "{name}");
+    }
+}
+
+#[test]
+fn test_unversioned_snapshot_gets_defaults() {
+    let dom = output::migrate(include_str!("fixtures/snapshots/unversioned_minimal.json")).unwrap();
+    assert_eq!(dom.vp, [1920.0, 1080.0]);
+    assert_eq!(dom.scroll, [0.0, 0.0]);
+    assert_eq!(dom.get(3).unwrap().href.as_deref(), Some("https://www.iana.org/domains/example"));
+
+    // Even an empty object is an empty page
+    let empty = output::migrate("{}").unwrap();
+    assert!(empty.els.is_empty());
+    assert_eq!(empty.url, "");
+}
+
+#[test]
+fn test_snapshots_round_trip_at_the_current_schema() {
+    let dom = browsy_core::parse(
+        r#"<html><body><h1>Hello</h1><input name="q" placeholder="Search"></body></html>"#,
+        1920.0,
+        1080.0,
+    );
+    assert_eq!(dom.schema, SCHEMA_VERSION);
+
+    let json: serde_json::Value = serde_json::to_value(&dom).unwrap();
+    assert_eq!(json["schema"], SCHEMA_VERSION);
+    let back = SpatialDom::from_json(&json.to_string()).unwrap();
+    assert_eq!(back.els, dom.els);
+    assert_eq!(output::to_compact_string(&back), output::to_compact_string(&dom));
+}
+
+#[test]
+fn test_unreadable_snapshots_still_fail() {
+    assert!(output::migrate("not json").is_err());
+    assert!(output::migrate(r#"{"els": [{"tag": "a"}]}"#).is_err(), "elements need an id and bounds");
+}
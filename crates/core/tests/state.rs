@@ -0,0 +1,112 @@
+//! `Session::state` and `restore_state`: a new session picks up the URL,
+//! history and cookies an earlier one saved.
+
+#![cfg(feature = "fetch")]
+
+mod common;
+
+use std::path::PathBuf;
+
+use browsy_core::fetch::{Session, SessionConfig, SessionState};
+
+/// Base URL of the sign-in site (see `common::serve_sign_in`).
+fn serve() -> String {
+    format!("http://127.0.0.1:{}", common::serve_sign_in())
+}
+
+fn session() -> Session {
+    Session::with_config(SessionConfig {
+        fetch_css: false,
+        allow_private_network: true,
+        ..SessionConfig::default()
+    })
+    .unwrap()
+}
+
+fn state_path(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("browsy-state-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir.join("session.json")
+}
+
+fn says(dom: &browsy_core::output::SpatialDom, text: &str) -> bool {
+    dom.els.iter().any(|e| e.text.as_deref() == Some(text))
+}
+
+#[test]
+fn test_restored_session_keeps_url_history_and_cookies() {
+    let base = serve();
+    let path = state_path("restore");
+    let mut first = session();
+    first.goto(&format!("{base}/login")).unwrap();
+    first.goto(&format!("{base}/account")).unwrap();
+    first.state().save(&path).unwrap();
+
+    let mut second = session();
+    let state = SessionState::load(&path).unwrap().expect("saved state");
+    assert_eq!(state.cookie_count(), 1);
+    second.restore_state(state).unwrap();
+    assert_eq!(second.url(), Some(format!("{base}/account").as_str()));
+    assert_eq!(second.history_len(), 2);
+    assert_eq!(second.cookie_count(), 1);
+    assert!(second.dom().is_none(), "restoring doesn't fetch the page");
+
+    let dom = second.reload().unwrap();
+    assert!(says(&dom, "Signed in as Ada"));
+    assert_eq!(second.history_len(), 2, "reload doesn't add to the history");
+    let dom = second.back().unwrap();
+    assert_eq!(dom.title, "/login");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_state_file_is_private() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let base = serve();
+    let path = state_path("private");
+    let mut session = session();
+    session.goto(&format!("{base}/login")).unwrap();
+
+    // Replacing a readable file leaves a private one
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, "{}").unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+    session.state().save(&path).unwrap();
+    assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+    assert_eq!(SessionState::load(&path).unwrap().unwrap().cookie_count(), 1);
+}
+
+#[test]
+fn test_missing_and_corrupt_state_files() {
+    let path = state_path("corrupt");
+    assert!(SessionState::load(&path).unwrap().is_none());
+
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, "{\"url\": \"http://exa").unwrap();
+    assert!(SessionState::load(&path).is_err());
+
+    std::fs::write(&path, r#"{"url": "not a url", "history": []}"#).unwrap();
+    let state = SessionState::load(&path).unwrap().unwrap();
+    let mut session = session();
+    assert!(session.restore_state(state).is_err());
+    assert_eq!(session.url(), None);
+
+    SessionState::remove(&path).unwrap();
+    assert!(!path.exists());
+    SessionState::remove(&path).unwrap();
+}
+
+#[test]
+fn test_reset_forgets_cookies_and_history() {
+    let base = serve();
+    let mut session = session();
+    session.goto(&format!("{base}/login")).unwrap();
+    session.reset();
+    assert_eq!(session.url(), None);
+    assert_eq!(session.history_len(), 0);
+    assert!(session.dom().is_none());
+
+    let dom = session.goto(&format!("{base}/account")).unwrap();
+    assert!(says(&dom, "Not signed in"));
+}
@@ -0,0 +1,146 @@
+//! Tracing spans and per-phase timings for page loads. Pages are served from
+//! replay fixtures so nothing touches the network.
+
+#![cfg(feature = "fetch")]
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use browsy_core::fetch::{Exchange, RecordingMode, Session, SessionConfig};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+
+/// A `MakeWriter` that keeps everything written to it.
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> tracing_subscriber::fmt::MakeWriter<'a> for Captured {
+    type Writer = Captured;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        self.clone()
+    }
+}
+
+/// Run `f` with a subscriber that logs every closed span, and return its logs.
+fn capture_spans<T>(f: impl FnOnce() -> T) -> (T, String) {
+    let captured = Captured::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing_subscriber::filter::LevelFilter::DEBUG)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_ansi(false)
+        .without_time()
+        .with_writer(captured.clone())
+        .finish();
+    let guard = subscriber.set_default();
+    let value = f();
+    drop(guard);
+    let logs = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+    (value, logs)
+}
+
+/// Whether `logs` close a span named `span`. A line reads `INFO goto{url=..}:fetch{url=..}: target: close`,
+/// and the span closing is the innermost one, before the first `: `.
+fn closed(logs: &str, span: &str) -> bool {
+    logs.lines().any(|line| {
+        let Some((spans, rest)) = line.trim_start().split_once(": ") else {
+            return false;
+        };
+        let spans = spans.split_once(' ').map_or(spans, |(_, spans)| spans);
+        let mut depth = 0;
+        let names: String = spans
+            .chars()
+            .filter(|&c| {
+                match c {
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => return depth == 0,
+                }
+                false
+            })
+            .collect();
+        rest.ends_with(": close") && names.rsplit(':').next() == Some(span)
+    })
+}
+
+const PAGE: &str = r#"<html><head><title>Styled</title>
+    <link rel="stylesheet" href="/style.css">
+</head><body><h1>Hello</h1><p class="muted">World</p></body></html>"#;
+
+fn replay_fixtures() -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("browsy-timings-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let exchanges = [
+        ("https://example.com/", "text/html", PAGE),
+        ("https://example.com/style.css", "text/css", ".muted { display: none; }"),
+    ];
+    for (i, (url, content_type, body)) in exchanges.iter().enumerate() {
+        let exchange = Exchange {
+            method: "GET".to_string(),
+            url: url.to_string(),
+            body_hash: None,
+            status: 200,
+            final_url: url.to_string(),
+            headers: vec![("content-type".to_string(), content_type.to_string())],
+            body: body.to_string(),
+        };
+        let json = serde_json::to_string_pretty(&exchange).unwrap();
+        std::fs::write(dir.join(format!("{i:04}-get.json")), json).unwrap();
+    }
+    dir
+}
+
+#[test]
+fn test_goto_records_spans_for_every_phase() {
+    let dir = replay_fixtures();
+    let mut session = Session::with_config(SessionConfig {
+        recording: Some(RecordingMode::Replay(dir.clone())),
+        ..SessionConfig::default()
+    })
+    .unwrap();
+
+    let (dom, logs) = capture_spans(|| session.goto("https://example.com/").unwrap());
+    assert_eq!(dom.title, "Styled");
+
+    for span in ["goto", "fetch", "css_fetch", "parse", "style", "layout", "spatial"] {
+        assert!(closed(&logs, span), "missing {span} span in:\n{logs}");
+    }
+    assert!(logs.contains("url=https://example.com/style.css"), "{logs}");
+
+    let timings = session.timings().expect("timings after goto");
+    assert!(timings.total > 0.0);
+    let phases = timings.fetch + timings.css_fetch + timings.parse + timings.style + timings.layout + timings.spatial;
+    assert!(timings.total + 0.1 >= phases, "{timings:?}");
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_load_html_timings_skip_network_phases() {
+    let mut session = Session::new().unwrap();
+    assert!(session.timings().is_none());
+
+    let (_, logs) = capture_spans(|| session.load_html_offline(PAGE, "https://example.com/").unwrap());
+    for span in ["load_html", "parse", "style", "layout", "spatial"] {
+        assert!(closed(&logs, span), "missing {span} span in:\n{logs}");
+    }
+    assert!(!closed(&logs, "fetch"), "{logs}");
+    assert!(!closed(&logs, "css_fetch"), "{logs}");
+
+    let timings = session.timings().unwrap();
+    assert_eq!(timings.fetch, 0.0);
+    assert_eq!(timings.css_fetch, 0.0);
+}
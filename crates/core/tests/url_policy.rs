@@ -0,0 +1,147 @@
+//! `Session::check_url_allowed`: one URL policy for pages, form submissions,
+//! stylesheets and downloads. The local server is reached as both `localhost`
+//! and `127.0.0.1`, so the domain lists can tell its URLs apart.
+
+#![cfg(feature = "fetch")]
+
+mod common;
+
+use browsy_core::fetch::{FetchError, Session, SessionConfig, UrlKind};
+use url::Url;
+use common::Response;
+
+/// Serve pages whose stylesheets and form point at both hosts. Returns the port.
+fn serve() -> u16 {
+    common::serve(|request| {
+        let port = request.port;
+        match request.path.as_str() {
+            p if p.ends_with(".css") => Response::ok("text/css", "h1 { font-size: 32px; }"),
+            "/ads/landing" => {
+                Response::html("<html><head><title>Sponsored</title></head><body><h1>Sponsored</h1></body></html>")
+            }
+            "/done" => Response::html("<html><head><title>Done</title></head><body><p>Sent</p></body></html>"),
+            _ => Response::html(format!(
+                r#"<html><head><title>Home</title>
+                <link rel="stylesheet" href="/css/site.css">
+                <link rel="stylesheet" href="/ads/banner.css">
+                <link rel="stylesheet" href="http://127.0.0.1:{port}/cdn/lib.css">
+                </head><body><h1>Home</h1>
+                <form action="http://127.0.0.1:{port}/done" method="post">
+                    <input type="text" name="q" value="hi">
+                    <button type="submit">Send</button>
+                </form>
+                <form action="/ads/done" method="post">
+                    <input type="text" name="r" value="hi">
+                    <button type="submit">Track</button>
+                </form>
+                </body></html>"#
+            )),
+        }
+    })
+}
+
+fn session(fetch_css: bool) -> Session {
+    Session::with_config(SessionConfig {
+        fetch_css,
+        allow_private_network: true,
+        blocked_patterns: vec!["/ads/".to_string()],
+        ..SessionConfig::default()
+    })
+    .unwrap()
+}
+
+#[test]
+fn test_navigation_warns_on_blocked_pattern() {
+    let port = serve();
+    let mut session = session(false);
+
+    let dom = session.goto(&format!("http://localhost:{port}/")).unwrap();
+    assert!(dom.warnings.is_empty(), "{:?}", dom.warnings);
+
+    let dom = session.goto(&format!("http://localhost:{port}/ads/landing")).unwrap();
+    assert_eq!(dom.title, "Sponsored");
+    assert_eq!(dom.warnings.len(), 1);
+    assert!(dom.warnings[0].contains("\"/ads/\""), "{:?}", dom.warnings);
+    assert_eq!(session.dom().unwrap().warnings, dom.warnings);
+
+    // The domain lists still block a page outright
+    session.set_domain_lists(Some(vec!["localhost".to_string()]), Vec::new()).unwrap();
+    assert!(matches!(
+        session.goto(&format!("http://127.0.0.1:{port}/")),
+        Err(FetchError::BlockedUrl(_))
+    ));
+}
+
+#[test]
+fn test_form_submissions_follow_the_policy() {
+    let port = serve();
+    let mut session = session(false);
+    session.set_domain_lists(Some(vec!["localhost".to_string()]), Vec::new()).unwrap();
+
+    let dom = session.goto(&format!("http://localhost:{port}/")).unwrap();
+    let send = dom.find_by_text("Send")[0].id;
+    match session.click(send) {
+        Err(FetchError::BlockedUrl(url)) => assert!(url.contains("127.0.0.1"), "{url}"),
+        other => panic!("expected a blocked submission, got {:?}", other.map(|d| d.title)),
+    }
+
+    // A submission matching a blocked pattern goes through with a warning
+    let track = dom.find_by_text("Track")[0].id;
+    let dom = session.click(track).unwrap();
+    assert_eq!(dom.warnings.len(), 1, "{:?}", dom.warnings);
+    assert!(dom.warnings[0].contains("/ads/done"), "{:?}", dom.warnings);
+}
+
+#[test]
+fn test_stylesheets_follow_the_policy() {
+    let port = serve();
+    let mut session = session(true);
+    // Stylesheets aren't held to the allowed domains
+    session.set_domain_lists(Some(vec!["localhost".to_string()]), Vec::new()).unwrap();
+    session.goto(&format!("http://localhost:{port}/")).unwrap();
+    let stats = session.last_nav_stats().unwrap();
+    assert_eq!((stats.css_fetched, stats.css_blocked), (2, 1));
+
+    // but are to the blocked ones
+    session
+        .set_domain_lists(Some(vec!["localhost".to_string()]), vec!["127.0.0.1".to_string()])
+        .unwrap();
+    session.goto(&format!("http://localhost:{port}/")).unwrap();
+    let stats = session.last_nav_stats().unwrap();
+    assert_eq!((stats.css_fetched, stats.css_blocked), (1, 2));
+}
+
+#[test]
+fn test_policy_differs_by_kind() {
+    let mut session = Session::with_config(SessionConfig {
+        blocked_patterns: vec!["tracker.example".to_string()],
+        ..SessionConfig::default()
+    })
+    .unwrap();
+    session
+        .set_domain_lists(Some(vec!["example.com".to_string()]), vec!["evil.example.com".to_string()])
+        .unwrap();
+    let url = |s: &str| Url::parse(s).unwrap();
+    let all = [UrlKind::Navigation, UrlKind::Form, UrlKind::Stylesheet, UrlKind::Download];
+
+    for kind in all {
+        assert!(session.check_url_allowed(&url("https://www.example.com/a"), kind).is_ok(), "{kind:?}");
+        assert!(session.check_url_allowed(&url("http://127.0.0.1/a"), kind).is_err(), "{kind:?}");
+        assert!(session.check_url_allowed(&url("file:///etc/passwd"), kind).is_err(), "{kind:?}");
+        assert!(session.check_url_allowed(&url("https://evil.example.com/a"), kind).is_err(), "{kind:?}");
+    }
+
+    let cdn = url("https://cdn.other.net/lib.css");
+    assert!(session.check_url_allowed(&cdn, UrlKind::Stylesheet).is_ok());
+    assert!(session.check_url_allowed(&cdn, UrlKind::Download).is_err());
+    assert!(session.check_url_allowed(&cdn, UrlKind::Navigation).is_err());
+
+    let tracker = url("https://example.com/tracker.example/pixel");
+    assert!(session.check_url_allowed(&tracker, UrlKind::Navigation).is_ok());
+    assert!(session.check_url_allowed(&tracker, UrlKind::Form).is_ok());
+    assert!(matches!(
+        session.check_url_allowed(&tracker, UrlKind::Stylesheet),
+        Err(FetchError::BlockedUrl(_))
+    ));
+    assert!(session.check_url_allowed(&tracker, UrlKind::Download).is_err());
+}
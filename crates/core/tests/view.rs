@@ -0,0 +1,121 @@
+use browsy_core::output::{self, Atom, Scope, SpatialDom, DEFAULT_HIDDEN_DROP};
+
+const FIXTURES: &[&str] = &[
+    include_str!("fixtures/news_site.html"),
+    include_str!("fixtures/no_landmarks.html"),
+    include_str!("fixtures/hidden_reasons.html"),
+    include_str!("fixtures/mega_menu.html"),
+    include_str!("fixtures/checkout.html"),
+    include_str!("fixtures/card_grid.html"),
+    include_str!("fixtures/help_text.html"),
+];
+
+const SCOPES: &[Scope] = &[
+    Scope::All,
+    Scope::Visible,
+    Scope::OnScreen,
+    Scope::AboveFold,
+    Scope::VisibleAboveFold,
+    Scope::FoldPlus,
+    Scope::Main,
+];
+
+/// What the servers did before views: clone, then filter.
+fn cloned(dom: &SpatialDom, scope: Scope) -> SpatialDom {
+    match scope {
+        Scope::All => dom.clone(),
+        Scope::Visible => dom.strip_hidden(DEFAULT_HIDDEN_DROP),
+        Scope::OnScreen => dom.filter_on_screen(),
+        Scope::AboveFold => dom.filter_above_fold(),
+        Scope::VisibleAboveFold => dom.strip_hidden(DEFAULT_HIDDEN_DROP).filter_above_fold(),
+        Scope::FoldPlus => dom.filter_fold_plus(),
+        Scope::Main => dom.filter_main_content(),
+    }
+}
+
+#[test]
+fn test_view_formats_like_the_cloned_dom() {
+    for (i, html) in FIXTURES.iter().enumerate() {
+        for viewport in [[1920.0, 1080.0], [390.0, 500.0]] {
+            let dom = browsy_core::parse(html, viewport[0], viewport[1]);
+            for &scope in SCOPES {
+                let expected = cloned(&dom, scope);
+                let view = dom.view(scope);
+                let context = format!("fixture {i}, {viewport:?}, {scope:?}");
+
+                assert_eq!(view.len(), expected.els.len(), "{context}");
+                assert_eq!(view.outside_main(), expected.outside_main, "{context}");
+                assert_eq!(view.to_compact_string(), output::to_compact_string(&expected), "{context}");
+                assert_eq!(
+                    view.compact_lines().collect::<Vec<_>>(),
+                    output::compact_lines(&expected).collect::<Vec<_>>(),
+                    "{context}"
+                );
+                assert_eq!(
+                    view.to_ascii_map(output::MAP_COLS, output::MAP_ROWS),
+                    output::to_ascii_map(&expected, output::MAP_COLS, output::MAP_ROWS),
+                    "{context}"
+                );
+                let ndjson: Vec<String> = expected.els.iter().map(|e| serde_json::to_string(e).unwrap()).collect();
+                assert_eq!(view.ndjson_lines().collect::<Vec<_>>(), ndjson, "{context}");
+                assert_eq!(
+                    serde_json::to_string(&view.to_dom()).unwrap(),
+                    serde_json::to_string(&expected).unwrap(),
+                    "{context}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_view_narrowing_chains_like_the_cloning_filters() {
+    let dom = browsy_core::parse(include_str!("fixtures/news_site.html"), 1920.0, 1080.0);
+    let chained = dom.strip_hidden(DEFAULT_HIDDEN_DROP).filter_above_fold().filter_main_content();
+    let view = dom
+        .view(Scope::All)
+        .strip_hidden(DEFAULT_HIDDEN_DROP)
+        .above_fold_with(&Default::default())
+        .main_content();
+    assert_eq!(view.to_compact_string(), output::to_compact_string(&chained));
+    assert_eq!(view.els().map(|e| e.id).collect::<Vec<_>>(), chained.els.iter().map(|e| e.id).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_scope_parses_the_server_names() {
+    assert_eq!("visible_above_fold".parse::<Scope>(), Ok(Scope::VisibleAboveFold));
+    assert_eq!("main".parse::<Scope>(), Ok(Scope::Main));
+    assert!("everything".parse::<Scope>().is_err());
+    assert_eq!(Scope::default(), Scope::All);
+}
+
+#[test]
+fn test_tags_and_roles_share_their_strings() {
+    let html = r#"<html><body>
+        <a href="/a">One</a><a href="/b">Two</a>
+        <span role="x-custom">First</span>
+        <span role="x-custom">Second</span>
+    </body></html>"#;
+    let dom = browsy_core::parse(html, 1920.0, 1080.0);
+    let links: Vec<_> = dom.els.iter().filter(|e| e.tag == "a").collect();
+    assert_eq!(links.len(), 2);
+    assert_eq!(links[0].role.as_deref(), Some("link"));
+    // Known tags and roles point at the same static string
+    assert_eq!(links[0].tag.as_ptr(), links[1].tag.as_ptr());
+    assert_eq!(links[0].role.as_deref().unwrap().as_ptr(), links[1].role.as_deref().unwrap().as_ptr());
+    assert_eq!(links[0].tag.as_ptr(), Atom::new("a").as_ptr());
+
+    // Other roles are stored once per page and shared by every element with them
+    let widgets: Vec<_> = dom.els.iter().filter(|e| e.tag == "span").collect();
+    assert_eq!(widgets.len(), 2);
+    let role = |i: usize| widgets[i].role.as_deref().unwrap();
+    assert_eq!(role(0), "x-custom");
+    assert_eq!(role(0).as_ptr(), role(1).as_ptr());
+    assert_ne!(role(0).as_ptr(), Atom::new("x-custom").as_ptr());
+
+    // Atoms serialize and compare as the strings they hold
+    assert_eq!(serde_json::to_string(&Atom::new("my-widget")).unwrap(), "\"my-widget\"");
+    assert_eq!(Atom::new("button"), "button");
+    let round_trip = SpatialDom::from_json(&serde_json::to_string(&dom).unwrap()).unwrap();
+    assert_eq!(round_trip.els, dom.els);
+}
@@ -0,0 +1,98 @@
+//! `Watcher`: baselines, changes and failures between snapshots of a page,
+//! the retry backoff, and resuming from a saved snapshot.
+
+#![cfg(feature = "fetch")]
+
+use std::time::Duration;
+
+use browsy_core::fetch::{backoff_delay, WatchConfig, WatchEvent, Watcher};
+use browsy_core::output::{BlockedInfo, SpatialDom};
+
+fn page(body: &str) -> SpatialDom {
+    browsy_core::parse(&format!("<html><body>{body}</body></html>"), 1920.0, 1080.0)
+}
+
+fn blocked(signals: &[&str]) -> BlockedInfo {
+    BlockedInfo {
+        reason: "test".to_string(),
+        signals: signals.iter().map(|s| s.to_string()).collect(),
+        recommendations: Vec::new(),
+        require_human: false,
+    }
+}
+
+#[test]
+fn test_baseline_then_unchanged_then_changed() {
+    let mut watcher = Watcher::new("https://example.com", WatchConfig::default());
+    let first = watcher.observe(page("<h1>Price</h1><p>$10</p>"));
+    assert!(matches!(first, WatchEvent::Baseline { els } if els > 0));
+    assert!(!first.should_alert());
+
+    let same = watcher.observe(page("<h1>Price</h1><p>$10</p>"));
+    assert!(matches!(same, WatchEvent::Unchanged));
+    assert!(!same.should_alert());
+
+    let changed = watcher.observe(page("<h1>Price</h1><p>$12</p>"));
+    assert!(changed.should_alert());
+    match changed {
+        WatchEvent::Changed(delta) => {
+            assert!(delta.changed.iter().any(|e| e.text.as_deref() == Some("$12")));
+        }
+        other => panic!("expected change, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_blocked_page_keeps_previous_snapshot() {
+    let mut watcher = Watcher::new("https://example.com", WatchConfig::default());
+    watcher.observe(page("<p>ok</p>"));
+
+    let mut wall = page("<p>Too many requests</p>");
+    wall.blocked = Some(blocked(&["rate_limit"]));
+    let event = watcher.observe(wall);
+    assert!(matches!(event, WatchEvent::Failed { .. }));
+    assert!(!event.should_alert());
+    assert!(watcher.next_delay(&event) > WatchConfig::default().interval);
+
+    let back = watcher.observe(page("<p>ok</p>"));
+    assert!(matches!(back, WatchEvent::Unchanged));
+}
+
+#[test]
+fn test_backoff_delay() {
+    let interval = Duration::from_secs(300);
+    assert_eq!(backoff_delay(1, interval, None), Duration::from_secs(5));
+    assert_eq!(backoff_delay(3, interval, None), Duration::from_secs(20));
+    assert_eq!(backoff_delay(20, interval, None), interval);
+
+    let limited = blocked(&["rate_limit"]);
+    assert_eq!(backoff_delay(1, interval, Some(&limited)), Duration::from_secs(600));
+    assert_eq!(backoff_delay(2, interval, Some(&limited)), Duration::from_secs(1200));
+    assert_eq!(backoff_delay(20, interval, Some(&limited)), Duration::from_secs(60 * 60));
+    // A huge --interval saturates instead of overflowing.
+    let huge = Duration::from_secs(u64::MAX / 2);
+    assert_eq!(backoff_delay(20, huge, Some(&limited)), huge);
+
+    let other = blocked(&["captcha"]);
+    assert_eq!(backoff_delay(1, interval, Some(&other)), Duration::from_secs(5));
+}
+
+#[test]
+fn test_state_dir_resumes_from_last_snapshot() {
+    let dir = std::env::temp_dir().join(format!("browsy-watch-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let config = WatchConfig {
+        state_dir: Some(dir.clone()),
+        ..WatchConfig::default()
+    };
+
+    let mut watcher = Watcher::new("https://example.com", config.clone());
+    watcher.observe(page("<p>status: up</p>"));
+
+    let mut restarted = Watcher::new("https://example.com", config);
+    assert!(restarted.previous().is_some());
+    let event = restarted.observe(page("<p>status: down</p>"));
+    assert!(event.should_alert());
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
@@ -1,18 +1,34 @@
 //! MCP server for browsy — exposes browse/click/type/search tools over stdio.
 
-use std::sync::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
 
-use browsy_core::fetch::{FetchError, Session, SearchEngine};
+use browsy_core::fetch::{
+    redact, ErrorCode, Extract, FetchError, Session, SessionState, SearchEngine, DEFAULT_MAX_PAGES,
+};
 use browsy_core::output;
 
 use rmcp::{
     handler::server::tool::ToolRouter,
     handler::server::wrapper::Parameters,
-    model::{CallToolResult, Content, ServerCapabilities, ServerInfo, Implementation},
-    tool, tool_handler, tool_router, ErrorData as McpError, ServerHandler,
+    model::{
+        AnnotateAble, CallToolResult, Content, GetPromptRequestParams, GetPromptResult,
+        Implementation, ListPromptsResult, ListResourcesResult,
+        PaginatedRequestParams, RawResource, ReadResourceRequestParams, ReadResourceResult,
+        ResourceContents, ResourceUpdatedNotificationParam, ServerCapabilities, ServerInfo,
+        SubscribeRequestParams, UnsubscribeRequestParams,
+    },
+    service::{NotificationContext, RequestContext},
+    tool, tool_handler, tool_router, ErrorData as McpError, Peer, RoleServer, ServerHandler,
 };
 
 use serde::Deserialize;
+use tracing::Instrument;
+
+mod prompts;
+pub use prompts::{prompts, render_prompt, PromptSpec, PROMPTS};
 
 // --- Parameter structs ---
 
@@ -20,9 +36,21 @@ use serde::Deserialize;
 pub struct BrowseParams {
     #[schemars(description = "URL to navigate to")]
     pub url: String,
-    #[schemars(description = "Output format: 'compact' (default) or 'json'")]
+    #[schemars(description = "Output format: 'compact' (default), 'json', or 'map' (an ASCII layout map with a legend of the IDs on it)")]
+    pub format: Option<String>,
+    #[schemars(description = "Scope: 'all' (default), 'visible', 'on_screen', 'above_fold', 'visible_above_fold', 'fold_plus' (half a viewport past the fold), 'main' (the main content, without the header, nav, sidebars and footer), or 'new_content' (leave out what the previous page on the same site also had)")]
+    pub scope: Option<String>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct LoadHtmlParams {
+    #[schemars(description = "Raw HTML document to analyze")]
+    pub html: String,
+    #[schemars(description = "URL the HTML came from, used to resolve relative links (default 'about:blank', which leaves them unresolved)")]
+    pub url: Option<String>,
+    #[schemars(description = "Output format: 'compact' (default), 'json', or 'map' (an ASCII layout map with a legend of the IDs on it)")]
     pub format: Option<String>,
-    #[schemars(description = "Scope: 'all' (default), 'visible', 'above_fold', or 'visible_above_fold'")]
+    #[schemars(description = "Scope: 'all' (default), 'visible', 'on_screen', 'above_fold', 'visible_above_fold', 'fold_plus' (half a viewport past the fold), 'main' (the main content, without the header, nav, sidebars and footer), or 'new_content' (leave out what the previous page on the same site also had)")]
     pub scope: Option<String>,
 }
 
@@ -30,6 +58,44 @@ pub struct BrowseParams {
 pub struct ClickParams {
     #[schemars(description = "Element ID to click")]
     pub id: u32,
+    #[schemars(description = "The user has approved this click: take it even if the action policy would ask for confirmation")]
+    pub confirmed: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct PreviewActionParams {
+    #[schemars(description = "Element ID to preview")]
+    pub id: u32,
+    #[schemars(description = "Preview submitting the element's form (as submit/press Enter would) instead of clicking it")]
+    pub submit: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ElementTextParams {
+    #[schemars(description = "Element ID whose text to return")]
+    pub id: u32,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ElementContextParams {
+    #[schemars(description = "Element ID to describe the surroundings of")]
+    pub id: u32,
+    #[schemars(description = "How far from the element to look for neighbors, in CSS pixels (default 50)")]
+    pub radius: Option<i32>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct PressEnterParams {
+    #[schemars(description = "Element ID of the input to press Enter in")]
+    pub id: u32,
+    #[schemars(description = "The user has approved this submission: send it even if the action policy would ask for confirmation")]
+    pub confirmed: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct ConfirmActionParams {
+    #[schemars(description = "Token from a confirmation_required error")]
+    pub token: String,
 }
 
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
@@ -54,11 +120,19 @@ pub struct SelectParams {
     pub value: String,
 }
 
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct AnnotateParams {
+    #[schemars(description = "Element ID to leave the note on")]
+    pub id: u32,
+    #[schemars(description = "The note; an empty note removes the element's note")]
+    pub note: String,
+}
+
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct GetPageParams {
-    #[schemars(description = "Output format: 'compact' (default) or 'json'")]
+    #[schemars(description = "Output format: 'compact' (default), 'json', or 'map' (an ASCII layout map with a legend of the IDs on it)")]
     pub format: Option<String>,
-    #[schemars(description = "Scope: 'all' (default), 'visible', 'above_fold', or 'visible_above_fold'")]
+    #[schemars(description = "Scope: 'all' (default), 'visible', 'on_screen', 'above_fold', 'visible_above_fold', 'fold_plus' (half a viewport past the fold), 'main' (the main content, without the header, nav, sidebars and footer), or 'new_content' (leave out what the previous page on the same site also had)")]
     pub scope: Option<String>,
 }
 
@@ -70,14 +144,34 @@ pub struct SearchParams {
     pub engine: Option<String>,
 }
 
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct FetchSitemapParams {
+    #[schemars(description = "URL of a sitemap or sitemap index")]
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct CollectPagesParams {
+    #[schemars(description = "What to collect from each page: 'tables', 'lists' or 'links'")]
+    pub extract: String,
+    #[schemars(description = "Most pages to read, counting the current one (default 10)")]
+    pub max_pages: Option<usize>,
+}
+
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct FindParams {
-    #[schemars(description = "Find elements containing this text")]
+    #[schemars(description = "Find elements containing this text, ignoring case and extra whitespace; whole-text matches come first")]
     pub text: Option<String>,
-    #[schemars(description = "Find elements with this ARIA role")]
+    #[schemars(description = "Find elements with this ARIA role, or any of a comma-separated list such as button,link")]
     pub role: Option<String>,
 }
 
+#[derive(Debug, Deserialize, schemars::JsonSchema)]
+pub struct TabParams {
+    #[schemars(description = "Tab ID, as new_tab returned it or list_tabs shows it")]
+    pub id: TabId,
+}
+
 #[derive(Debug, Deserialize, schemars::JsonSchema)]
 pub struct LoginParams {
     #[schemars(description = "Username or email")]
@@ -92,38 +186,25 @@ pub struct EnterCodeParams {
     pub code: String,
 }
 
-// --- Output helpers ---
+// --- Instructions ---
 
-pub fn format_page(dom: &output::SpatialDom, format: Option<&str>) -> String {
-    match format {
-        Some("json") => serde_json::to_string_pretty(dom).unwrap_or_default(),
-        _ => {
-            let mut header = format!(
-                "title: {}\nurl: {}\nels: {}\n---\n",
-                dom.title,
-                dom.url,
-                dom.els.len()
-            );
-            header.push_str(&output::to_compact_string(dom));
-            header
-        }
-    }
-}
+/// How to read the compact page format; shared by server instructions and
+/// prompts, which follow it with `output::format_legend()`.
+pub const FORMAT_LEGEND: &str = "browsy: zero-render browser for AI agents. Use browse to navigate, \
+     then interact with elements by ID. Elements are listed one per line in [id:tag \"text\"] format, \
+     with these markers:";
 
-fn strip_hidden(mut dom: output::SpatialDom) -> output::SpatialDom {
-    dom.els.retain(|e| e.hidden != Some(true));
-    dom.rebuild_index();
-    dom
-}
+const WHEN_TO_USE: &str = "WHEN TO USE BROWSY vs WebFetch: browsy is for interactive browsing — \
+     filling forms, logging in, clicking through pages, reading page structure, \
+     extracting tables, and multi-step navigation with session/cookie persistence. \
+     Do NOT use browsy as a replacement for WebFetch. If you just need to read \
+     content from a URL (fetching docs, reading an article, checking an API response), \
+     use WebFetch instead. Use browsy when you need to interact with a page or \
+     understand its structure (form fields, buttons, page type detection).";
 
-fn apply_scope(dom: output::SpatialDom, scope: Option<&str>) -> output::SpatialDom {
-    match scope.unwrap_or("all") {
-        "visible" => strip_hidden(dom),
-        "above_fold" => dom.filter_above_fold(),
-        "visible_above_fold" => strip_hidden(dom).filter_above_fold(),
-        _ => dom,
-    }
-}
+// --- Output helpers ---
+
+pub use browsy_core::output::{format_page, format_view};
 
 fn captcha_warning(dom: &output::SpatialDom) -> Option<String> {
     if dom.page_type != output::PageType::Captcha {
@@ -162,32 +243,298 @@ fn blocked_warning(dom: &output::SpatialDom) -> Option<String> {
     Some(text)
 }
 
-fn err(msg: impl Into<String>) -> McpError {
-    McpError::new(rmcp::model::ErrorCode::INVALID_PARAMS, msg.into(), None)
+/// The settings in effect for the session's current page. Header values
+/// are left out, since they may hold credentials.
+fn effective_settings(session: &Session) -> serde_json::Value {
+    let config = session.effective_config();
+    serde_json::json!({
+        "site": session.site_override(),
+        "user_agent": config.user_agent,
+        "fetch_css": config.fetch_css,
+        "viewport": [config.viewport_width, config.viewport_height],
+        "headers": config.extra_headers.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+        "per_host_delay_ms": config.per_host_delay_ms,
+        "auto_consent": config.auto_dismiss_cookie_banners,
+    })
 }
 
+/// The error's code, and its details if it has any, go in `data`:
+/// `{"code": "action_element_not_found", "details": {"id": 9, ...}}`.
 fn map_fetch_error(e: FetchError) -> McpError {
-    match &e {
-        FetchError::InvalidUrl(_) | FetchError::ActionError(_) | FetchError::BlockedUrl(_) =>
-            McpError::new(rmcp::model::ErrorCode::INVALID_PARAMS, e.to_string(), None),
-        FetchError::Network(_) | FetchError::HttpError(_) | FetchError::ResponseTooLarge(_, _) =>
-            McpError::new(rmcp::model::ErrorCode::INTERNAL_ERROR, e.to_string(), None),
+    let kind = match &e {
+        FetchError::InvalidUrl(_)
+        | FetchError::BlockedUrl(_)
+        | FetchError::ActionError(_)
+        | FetchError::ElementNotFound { .. }
+        | FetchError::WrongElementType { .. }
+        | FetchError::ElementUnavailable { .. }
+        | FetchError::ConfirmationRequired { .. }
+        | FetchError::BinaryContent { .. }
+        | FetchError::NoPageLoaded => rmcp::model::ErrorCode::INVALID_PARAMS,
+        FetchError::Network(_)
+        | FetchError::Timeout(_)
+        | FetchError::HttpError(_)
+        | FetchError::ResponseTooLarge(_, _)
+        | FetchError::TooManyRedirects { .. } => rmcp::model::ErrorCode::INTERNAL_ERROR,
+    };
+    let mut data = serde_json::json!({ "code": e.code() });
+    if let Some(details) = e.details() {
+        data["details"] = details;
+    }
+    let mut message = e.to_string();
+    if let FetchError::ConfirmationRequired { .. } = e {
+        message.push_str(". Ask the user; if they approve, call confirm_action with the token.");
+    }
+    McpError::new(kind, message, Some(data))
+}
+
+// --- Resources ---
+
+/// Page resources exposed to clients: (uri, name, description, mime type).
+pub const PAGE_RESOURCES: &[(&str, &str, &str, &str)] = &[
+    ("page://current", "current", "Current page as compact Spatial DOM, including typed form values", "text/plain"),
+    ("page://current.json", "current.json", "Current page as full Spatial DOM JSON", "application/json"),
+    ("page://delta", "delta", "Changes between the previous and current page", "text/plain"),
+    ("page://tables", "tables", "Structured table data extracted from the current page", "application/json"),
+];
+
+/// Render a page resource from the session without mutating it.
+pub fn read_page_resource(session: &Session, uri: &str) -> Result<String, McpError> {
+    let no_page = || McpError::resource_not_found("No page loaded", None);
+    match uri {
+        "page://current" => session
+            .dom()
+            .map(|dom| format_page(&dom, None))
+            .ok_or_else(no_page),
+        "page://current.json" => session
+            .dom()
+            .map(|dom| format_page(&dom, Some("json")))
+            .ok_or_else(no_page),
+        "page://delta" => {
+            if session.dom_ref().is_none() {
+                return Err(no_page());
+            }
+            session
+                .delta()
+                .map(|delta| output::delta_to_compact_string(&delta))
+                .ok_or_else(|| McpError::resource_not_found("No previous page to diff against", None))
+        }
+        "page://tables" => {
+            let dom = session.dom_ref().ok_or_else(no_page)?;
+            Ok(serde_json::to_string_pretty(&dom.tables()).unwrap_or_default())
+        }
+        _ => Err(McpError::resource_not_found(format!("Unknown resource: {uri}"), None)),
     }
 }
 
 // --- Server ---
 
+/// The file in the state directory the session is saved to.
+pub const STATE_FILE: &str = "session.json";
+
+/// Identifies an open tab. The session a server starts with is tab 1.
+pub type TabId = u32;
+
+/// Most tabs a server keeps open unless `with_max_tabs` says otherwise.
+pub const DEFAULT_MAX_TABS: usize = 8;
+
+/// The server's sessions, one per tab. Tabs opened with `new_tab` share the
+/// first one's cookie jar.
+struct Tabs {
+    sessions: HashMap<TabId, Session>,
+    active: TabId,
+    next_id: TabId,
+    /// The tab whose restored page hasn't been fetched again yet.
+    reload: Option<TabId>,
+}
+
+impl Tabs {
+    fn active(&mut self) -> &mut Session {
+        self.sessions.get_mut(&self.active).expect("the active tab is open")
+    }
+}
+
+/// The active tab's session, for as long as the tabs stay locked.
+struct ActiveSession<'a>(MutexGuard<'a, Tabs>);
+
+impl Deref for ActiveSession<'_> {
+    type Target = Session;
+
+    fn deref(&self) -> &Session {
+        &self.0.sessions[&self.0.active]
+    }
+}
+
+impl DerefMut for ActiveSession<'_> {
+    fn deref_mut(&mut self) -> &mut Session {
+        self.0.active()
+    }
+}
+
+/// Drop closed tabs' sessions on the blocking pool: like creating one,
+/// dropping its HTTP client inside the async runtime panics.
+fn drop_sessions(sessions: Vec<Session>) {
+    if !sessions.is_empty() {
+        tokio::task::spawn_blocking(move || drop(sessions));
+    }
+}
+
+fn tab_limit(open: usize) -> McpError {
+    McpError::invalid_request(
+        format!("{open} tabs are open, the most allowed; close_tab one first"),
+        Some(serde_json::json!({ "code": ErrorCode::SessionLimit })),
+    )
+}
+
+fn tab_not_found(id: TabId) -> McpError {
+    McpError::invalid_params(
+        format!("No tab {id}; list_tabs shows the open ones"),
+        Some(serde_json::json!({ "code": ErrorCode::SessionExpired, "details": { "tab": id } })),
+    )
+}
+
+/// Sessions hold a blocking HTTP client, which panics when created or
+/// dropped inside the async runtime. Create them before starting it, keep a
+/// clone of the server until it has stopped, and the server does the rest:
+/// `new_tab` and `close_tab` create and drop sessions on the blocking pool.
 #[derive(Clone)]
 pub struct BrowsyServer {
-    session: std::sync::Arc<Mutex<Session>>,
+    tabs: std::sync::Arc<Mutex<Tabs>>,
+    max_tabs: usize,
+    peer: std::sync::Arc<Mutex<Option<Peer<RoleServer>>>>,
+    /// Page resource URIs the client has subscribed to.
+    subscriptions: std::sync::Arc<Mutex<HashSet<String>>>,
+    /// Where the active session is saved after each page change, if anywhere.
+    state_path: Option<PathBuf>,
     tool_router: ToolRouter<Self>,
 }
 
+impl BrowsyServer {
+    /// A server that saves the session's URL, history and cookies to
+    /// [`STATE_FILE`] in `dir` after every page change, and starts from what
+    /// it finds there. The saved page is fetched again on the first tool
+    /// call. An unreadable state file is logged and a fresh session used.
+    pub fn with_state_dir(mut session: Session, dir: &Path) -> Self {
+        let path = dir.join(STATE_FILE);
+        let restored = SessionState::load(&path).and_then(|state| match state {
+            Some(state) => {
+                let has_page = state.url.is_some();
+                session.restore_state(state)?;
+                Ok(has_page)
+            }
+            None => Ok(false),
+        });
+        let has_page = restored.unwrap_or_else(|e| {
+            tracing::warn!(path = %path.display(), error = %e, "ignoring saved session state; starting fresh");
+            session.reset();
+            false
+        });
+        let mut server = Self::with_session(session);
+        if has_page {
+            server.tabs.lock().unwrap().reload = Some(1);
+        }
+        server.state_path = Some(path);
+        server
+    }
+
+    /// Allow at most `max_tabs` tabs open at once (at least one).
+    pub fn with_max_tabs(mut self, max_tabs: usize) -> Self {
+        self.max_tabs = max_tabs.max(1);
+        self
+    }
+
+    /// Run `f` on the active tab's session.
+    pub fn with_active_session<R>(&self, f: impl FnOnce(&mut Session) -> R) -> R {
+        f(self.tabs.lock().unwrap().active())
+    }
+
+    /// The active tab's session, with the page a restored state left off on
+    /// fetched again if this is the first call since.
+    fn session(&self) -> ActiveSession<'_> {
+        let mut tabs = self.tabs.lock().unwrap();
+        if tabs.reload == Some(tabs.active) {
+            tabs.reload = None;
+            if let Err(e) = tabs.active().reload() {
+                tracing::warn!(error = %e, "could not fetch the restored page again");
+            }
+        }
+        ActiveSession(tabs)
+    }
+
+    /// The active tab's session, for tools that replace the current page
+    /// anyway and so needn't fetch a restored one first.
+    fn session_for_new_page(&self) -> ActiveSession<'_> {
+        let mut tabs = self.tabs.lock().unwrap();
+        if tabs.reload == Some(tabs.active) {
+            tabs.reload = None;
+        }
+        ActiveSession(tabs)
+    }
+
+    /// Save the session when there is a state directory, then tell the
+    /// client its page resources changed.
+    fn page_changed(&self, session: &Session) {
+        if let Some(path) = &self.state_path {
+            if let Err(e) = session.state().save(path) {
+                tracing::warn!(error = %e, "could not save session state");
+            }
+        }
+        self.notify_page_changed();
+    }
+
+    /// Names of all tools registered on this server.
+    pub fn tool_names(&self) -> Vec<String> {
+        self.tool_router
+            .list_all()
+            .into_iter()
+            .map(|tool| tool.name.to_string())
+            .collect()
+    }
+
+    /// Tell the connected client that the page resources it subscribed to
+    /// have changed.
+    fn notify_page_changed(&self) {
+        let Some(peer) = self.peer.lock().unwrap().clone() else {
+            return;
+        };
+        let uris: Vec<String> = {
+            let subscriptions = self.subscriptions.lock().unwrap();
+            PAGE_RESOURCES
+                .iter()
+                .map(|(uri, ..)| uri.to_string())
+                .filter(|uri| subscriptions.contains(uri))
+                .collect()
+        };
+        if uris.is_empty() {
+            return;
+        }
+        tokio::spawn(async move {
+            for uri in uris {
+                let param = ResourceUpdatedNotificationParam { uri };
+                if peer.notify_resource_updated(param).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
 #[tool_router]
 impl BrowsyServer {
-    pub fn with_session(session: std::sync::Arc<Mutex<Session>>) -> Self {
+    /// A server whose first tab is `session`.
+    pub fn with_session(session: Session) -> Self {
+        let tabs = Tabs {
+            sessions: HashMap::from([(1, session)]),
+            active: 1,
+            next_id: 2,
+            reload: None,
+        };
         Self {
-            session,
+            tabs: std::sync::Arc::new(Mutex::new(tabs)),
+            max_tabs: DEFAULT_MAX_TABS,
+            peer: std::sync::Arc::new(Mutex::new(None)),
+            subscriptions: std::sync::Arc::new(Mutex::new(HashSet::new())),
+            state_path: None,
             tool_router: Self::tool_router(),
         }
     }
@@ -197,12 +544,32 @@ impl BrowsyServer {
         &self,
         Parameters(params): Parameters<BrowseParams>,
     ) -> Result<CallToolResult, McpError> {
-        let mut session = self.session.lock().unwrap();
+        let _span = tracing::info_span!("tool", name = "browse").entered();
+        let mut session = self.session_for_new_page();
         let dom = session.goto(&params.url).map_err(map_fetch_error)?;
         let mut text = blocked_warning(&dom).unwrap_or_default();
         text.push_str(&captcha_warning(&dom).unwrap_or_default());
-        let scoped = apply_scope(dom, params.scope.as_deref());
-        text.push_str(&format_page(&scoped, params.format.as_deref()));
+        let dom = session.scoped(dom, params.scope.as_deref());
+        let view = dom.view(output::Scope::from_param(params.scope.as_deref()));
+        text.push_str(&format_view(&view, params.format.as_deref()));
+        self.page_changed(&session);
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(description = "Analyze raw HTML (saved pages, email bodies, HTML from another fetcher) without any network access and load it as the current page, so click/type_text work on it afterwards.")]
+    pub async fn load_html(
+        &self,
+        Parameters(params): Parameters<LoadHtmlParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let _span = tracing::info_span!("tool", name = "load_html").entered();
+        let url = params.url.as_deref().unwrap_or("about:blank");
+        let mut session = self.session_for_new_page();
+        let dom = session.load_html_offline(&params.html, url).map_err(map_fetch_error)?;
+        let mut text = captcha_warning(&dom).unwrap_or_default();
+        let dom = session.scoped(dom, params.scope.as_deref());
+        let view = dom.view(output::Scope::from_param(params.scope.as_deref()));
+        text.push_str(&format_view(&view, params.format.as_deref()));
+        self.page_changed(&session);
         Ok(CallToolResult::success(vec![Content::text(text)]))
     }
 
@@ -211,11 +578,37 @@ impl BrowsyServer {
         &self,
         Parameters(params): Parameters<ClickParams>,
     ) -> Result<CallToolResult, McpError> {
-        let mut session = self.session.lock().unwrap();
-        let dom = session.click(params.id).map_err(map_fetch_error)?;
+        let _span = tracing::info_span!("tool", name = "click").entered();
+        let mut session = self.session();
+        let dom = if params.confirmed == Some(true) {
+            session.confirmed(|session| session.click(params.id))
+        } else {
+            session.click(params.id)
+        }
+        .map_err(map_fetch_error)?;
         let mut text = blocked_warning(&dom).unwrap_or_default();
         text.push_str(&captcha_warning(&dom).unwrap_or_default());
         text.push_str(&format_page(&dom, None));
+        self.page_changed(&session);
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        description = "Show what clicking an element (or, with submit, submitting its form) would do without doing it: the URL a link loads, the JS behavior a button runs, or the method, URL and fields a form sends. Sends no request and changes nothing."
+    )]
+    pub async fn preview_action(
+        &self,
+        Parameters(params): Parameters<PreviewActionParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let _span = tracing::info_span!("tool", name = "preview_action").entered();
+        let session = self.session();
+        let preview = if params.submit.unwrap_or(false) {
+            session.preview_submit(params.id)
+        } else {
+            session.preview_click(params.id)
+        }
+        .map_err(map_fetch_error)?;
+        let text = serde_json::to_string_pretty(&preview).unwrap_or_default();
         Ok(CallToolResult::success(vec![Content::text(text)]))
     }
 
@@ -224,12 +617,53 @@ impl BrowsyServer {
         &self,
         Parameters(params): Parameters<TypeTextParams>,
     ) -> Result<CallToolResult, McpError> {
-        let mut session = self.session.lock().unwrap();
+        let _span = tracing::info_span!("tool", name = "type_text").entered();
+        let mut session = self.session();
         session.type_text(params.id, &params.text).map_err(map_fetch_error)?;
-        Ok(CallToolResult::success(vec![Content::text(format!(
-            "Typed {:?} into element {}",
-            params.text, params.id
-        ))]))
+        let echo = session.typed_echo(params.id, &params.text);
+        let mut text = format!("Typed {} into element {}", echo, params.id);
+        if let Some(warning) = session.suggestion_warning(params.id, &params.text) {
+            text.push_str(&format!("\nWarning: {}", warning));
+        }
+        self.page_changed(&session);
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(description = "Press Enter in an input by element ID. Submits its form with the current values, even when there is no submit button; use it for search boxes a Search action lists without a submit_id.")]
+    pub async fn press_enter(
+        &self,
+        Parameters(params): Parameters<PressEnterParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let _span = tracing::info_span!("tool", name = "press_enter").entered();
+        let mut session = self.session();
+        let dom = if params.confirmed == Some(true) {
+            session.confirmed(|session| session.press_enter(params.id))
+        } else {
+            session.press_enter(params.id)
+        }
+        .map_err(map_fetch_error)?;
+        let mut text = blocked_warning(&dom).unwrap_or_default();
+        text.push_str(&captcha_warning(&dom).unwrap_or_default());
+        text.push_str(&format_page(&dom, None));
+        self.page_changed(&session);
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(
+        description = "Take a click or form submission that was held with a confirmation_required error, by its token. Only call this once the user has approved the action described in the error."
+    )]
+    pub async fn confirm_action(
+        &self,
+        Parameters(params): Parameters<ConfirmActionParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let _span = tracing::info_span!("tool", name = "confirm_action").entered();
+        let mut session = self.session();
+        let dom = session.confirm(&params.token).map_err(map_fetch_error)?;
+        let mut text = blocked_warning(&dom).unwrap_or_default();
+        text.push_str(&captcha_warning(&dom).unwrap_or_default());
+        text.push_str(&format_page(&dom, None));
+        self.page_changed(&session);
+        Ok(CallToolResult::success(vec![Content::text(text)]))
     }
 
     #[tool(description = "Check a checkbox or radio button by element ID.")]
@@ -237,8 +671,10 @@ impl BrowsyServer {
         &self,
         Parameters(params): Parameters<CheckParams>,
     ) -> Result<CallToolResult, McpError> {
-        let mut session = self.session.lock().unwrap();
+        let _span = tracing::info_span!("tool", name = "check").entered();
+        let mut session = self.session();
         session.check(params.id).map_err(map_fetch_error)?;
+        self.page_changed(&session);
         Ok(CallToolResult::success(vec![Content::text(format!(
             "Checked element {}",
             params.id
@@ -250,8 +686,10 @@ impl BrowsyServer {
         &self,
         Parameters(params): Parameters<CheckParams>,
     ) -> Result<CallToolResult, McpError> {
-        let mut session = self.session.lock().unwrap();
+        let _span = tracing::info_span!("tool", name = "uncheck").entered();
+        let mut session = self.session();
         session.uncheck(params.id).map_err(map_fetch_error)?;
+        self.page_changed(&session);
         Ok(CallToolResult::success(vec![Content::text(format!(
             "Unchecked element {}",
             params.id
@@ -263,23 +701,45 @@ impl BrowsyServer {
         &self,
         Parameters(params): Parameters<SelectParams>,
     ) -> Result<CallToolResult, McpError> {
-        let mut session = self.session.lock().unwrap();
+        let _span = tracing::info_span!("tool", name = "select").entered();
+        let mut session = self.session();
         session.select(params.id, &params.value).map_err(map_fetch_error)?;
+        self.page_changed(&session);
         Ok(CallToolResult::success(vec![Content::text(format!(
             "Selected {:?} in element {}",
             params.value, params.id
         ))]))
     }
 
+    #[tool(description = "Leave a note on an element by ID, such as \"tried, out of stock\". The note shows after the element as #\"note\", follows it when the page re-renders, and is dropped when the element disappears or another page loads. An empty note removes it.")]
+    pub async fn annotate(
+        &self,
+        Parameters(params): Parameters<AnnotateParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let _span = tracing::info_span!("tool", name = "annotate").entered();
+        let mut session = self.session();
+        let removed = params.note.trim().is_empty();
+        session.annotate(params.id, params.note).map_err(map_fetch_error)?;
+        self.page_changed(&session);
+        let message = if removed {
+            format!("Removed the note on element {}", params.id)
+        } else {
+            format!("Annotated element {}", params.id)
+        };
+        Ok(CallToolResult::success(vec![Content::text(message)]))
+    }
+
     #[tool(description = "Get the current page DOM with form state (typed values, checked states). Use after type_text/check/select to see the updated form.")]
     pub async fn get_page(
         &self,
         Parameters(params): Parameters<GetPageParams>,
     ) -> Result<CallToolResult, McpError> {
-        let session = self.session.lock().unwrap();
-        let dom = session.dom().ok_or_else(|| err("No page loaded"))?;
-        let scoped = apply_scope(dom, params.scope.as_deref());
-        let text = format_page(&scoped, params.format.as_deref());
+        let _span = tracing::info_span!("tool", name = "get_page").entered();
+        let session = self.session();
+        let dom = session.dom().ok_or_else(|| map_fetch_error(FetchError::NoPageLoaded))?;
+        let dom = session.scoped(dom, params.scope.as_deref());
+        let view = dom.view(output::Scope::from_param(params.scope.as_deref()));
+        let text = format_view(&view, params.format.as_deref());
         Ok(CallToolResult::success(vec![Content::text(text)]))
     }
 
@@ -288,32 +748,206 @@ impl BrowsyServer {
         &self,
         Parameters(params): Parameters<SearchParams>,
     ) -> Result<CallToolResult, McpError> {
+        let _span = tracing::info_span!("tool", name = "search").entered();
         let engine = match params.engine.as_deref() {
             Some("google") => SearchEngine::Google,
             _ => SearchEngine::DuckDuckGo,
         };
-        let mut session = self.session.lock().unwrap();
+        let mut session = self.session();
         let results = session.search_with(&params.query, engine).map_err(map_fetch_error)?;
         let json = serde_json::to_string_pretty(&results).unwrap_or_default();
+        self.page_changed(&session);
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "List the RSS/Atom feeds the current page links to and the sitemaps of its site, from robots.txt and /sitemap.xml.")]
+    pub async fn discover_feeds(&self) -> Result<CallToolResult, McpError> {
+        let _span = tracing::info_span!("tool", name = "discover_feeds").entered();
+        let mut session = self.session();
+        let feeds = session.discover_feeds().map_err(map_fetch_error)?;
+        let json = serde_json::to_string_pretty(&feeds).unwrap_or_default();
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Fetch a sitemap and return the pages it lists (loc and lastmod), following a sitemap index into its sitemaps. Does not change the current page.")]
+    pub async fn fetch_sitemap(
+        &self,
+        Parameters(params): Parameters<FetchSitemapParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let _span = tracing::info_span!("tool", name = "fetch_sitemap").entered();
+        let mut session = self.session();
+        let entries = session.fetch_sitemap(&params.url).map_err(map_fetch_error)?;
+        let json = serde_json::to_string_pretty(&entries).unwrap_or_default();
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Follow the current page's next links and merge the tables, lists or links of every page into one JSON result: headers (starting with page_index), rows, the URLs visited and why collection stopped (exhausted, max_pages, loop_detected or error). Leaves the session on the last page read.")]
+    pub async fn collect_pages(
+        &self,
+        Parameters(params): Parameters<CollectPagesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let _span = tracing::info_span!("tool", name = "collect_pages").entered();
+        let extract: Extract = params.extract.parse().map_err(|e: String| McpError::invalid_params(e, None))?;
+        let mut session = self.session();
+        let result = session
+            .paginate_collect(params.max_pages.unwrap_or(DEFAULT_MAX_PAGES), extract)
+            .map_err(map_fetch_error)?;
+        let json = serde_json::to_string_pretty(&result).unwrap_or_default();
+        self.page_changed(&session);
         Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
     #[tool(description = "Go back to the previous page in browsing history.")]
     pub async fn back(&self) -> Result<CallToolResult, McpError> {
-        let mut session = self.session.lock().unwrap();
+        let _span = tracing::info_span!("tool", name = "back").entered();
+        let mut session = self.session();
         let dom = session.back().map_err(map_fetch_error)?;
         let text = format_page(&dom, None);
+        self.page_changed(&session);
         Ok(CallToolResult::success(vec![Content::text(text)]))
     }
 
-    #[tool(description = "Log in using detected login form fields. Requires a page with a login form loaded.")]
+    #[tool(description = "Start over with a fresh session: close every other tab, forget cookies, history and the current page, and delete the saved session state when the server keeps one.")]
+    pub async fn reset_session(&self) -> Result<CallToolResult, McpError> {
+        let _span = tracing::info_span!("tool", name = "reset_session").entered();
+        let closed = {
+            let mut session = self.session_for_new_page();
+            session.reset();
+            let tabs = &mut session.0;
+            let active = tabs.active;
+            let others: Vec<TabId> = tabs.sessions.keys().copied().filter(|&id| id != active).collect();
+            others.iter().filter_map(|id| tabs.sessions.remove(id)).collect()
+        };
+        drop_sessions(closed);
+        if let Some(path) = &self.state_path {
+            SessionState::remove(path).map_err(map_fetch_error)?;
+        }
+        self.notify_page_changed();
+        Ok(CallToolResult::success(vec![Content::text("Session reset".to_string())]))
+    }
+
+    #[tool(description = "Open a new tab with no page and make it the active tab; every other tool works on the active tab. Tabs share cookies, so a login carries over, but each has its own page, history and form values. Returns the new tab's ID.")]
+    pub async fn new_tab(&self) -> Result<CallToolResult, McpError> {
+        // Not entered: a held span guard would make the future !Send
+        let span = tracing::info_span!("tool", name = "new_tab");
+        let open = self.tabs.lock().unwrap().sessions.len();
+        if open >= self.max_tabs {
+            return Err(tab_limit(open));
+        }
+        let tabs = self.tabs.clone();
+        let session = tokio::task::spawn_blocking(move || tabs.lock().unwrap().active().new_tab())
+            .instrument(span)
+            .await
+            .map_err(|e| McpError::internal_error(e.to_string(), None))?
+            .map_err(map_fetch_error)?;
+        let id = {
+            let mut tabs = self.tabs.lock().unwrap();
+            // Another new_tab may have opened one while this session was made
+            let open = tabs.sessions.len();
+            if open >= self.max_tabs {
+                drop(tabs);
+                drop_sessions(vec![session]);
+                return Err(tab_limit(open));
+            }
+            let id = tabs.next_id;
+            tabs.next_id += 1;
+            tabs.sessions.insert(id, session);
+            tabs.active = id;
+            id
+        };
+        self.notify_page_changed();
+        Ok(CallToolResult::success(vec![Content::text(format!("Opened tab {id}"))]))
+    }
+
+    #[tool(description = "List the open tabs as JSON: id, title, url and whether it is the active tab.")]
+    pub async fn list_tabs(&self) -> Result<CallToolResult, McpError> {
+        let _span = tracing::info_span!("tool", name = "list_tabs").entered();
+        let tabs = self.tabs.lock().unwrap();
+        let mut ids: Vec<TabId> = tabs.sessions.keys().copied().collect();
+        ids.sort_unstable();
+        let list: Vec<serde_json::Value> = ids
+            .into_iter()
+            .map(|id| {
+                let session = &tabs.sessions[&id];
+                serde_json::json!({
+                    "id": id,
+                    "title": session.dom_ref().map(|dom| dom.title.as_str()),
+                    "url": session.url(),
+                    "active": id == tabs.active,
+                })
+            })
+            .collect();
+        let json = serde_json::to_string_pretty(&list).unwrap_or_default();
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(description = "Make another open tab the active one and return its page.")]
+    pub async fn switch_tab(
+        &self,
+        Parameters(params): Parameters<TabParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let _span = tracing::info_span!("tool", name = "switch_tab").entered();
+        {
+            let mut tabs = self.tabs.lock().unwrap();
+            if !tabs.sessions.contains_key(&params.id) {
+                return Err(tab_not_found(params.id));
+            }
+            tabs.active = params.id;
+        }
+        let session = self.session();
+        let text = match session.dom() {
+            Some(dom) => format_page(&dom, None),
+            None => format!("Switched to tab {}, which has no page loaded", params.id),
+        };
+        self.notify_page_changed();
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(description = "Close a tab. Closing the active tab makes the tab opened before it active. The last open tab can't be closed.")]
+    pub async fn close_tab(
+        &self,
+        Parameters(params): Parameters<TabParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let _span = tracing::info_span!("tool", name = "close_tab").entered();
+        let (closed, active) = {
+            let mut tabs = self.tabs.lock().unwrap();
+            if !tabs.sessions.contains_key(&params.id) {
+                return Err(tab_not_found(params.id));
+            }
+            if tabs.sessions.len() == 1 {
+                return Err(McpError::invalid_params("Can't close the last open tab", None));
+            }
+            let closed = tabs.sessions.remove(&params.id);
+            if tabs.active == params.id {
+                let ids = || tabs.sessions.keys().copied();
+                let next = ids().filter(|&id| id < params.id).max().or_else(|| ids().min());
+                tabs.active = next.expect("a tab is left open");
+            }
+            if tabs.reload == Some(params.id) {
+                tabs.reload = None;
+            }
+            (closed, tabs.active)
+        };
+        drop_sessions(closed.into_iter().collect());
+        self.notify_page_changed();
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Closed tab {}; tab {active} is active",
+            params.id
+        ))]))
+    }
+
+    #[tool(description = "Log in using detected login form fields. Requires a page with a login form loaded. The first line reports the outcome: success, failed (reason), two_factor_required or captcha_required.")]
     pub async fn login(
         &self,
         Parameters(params): Parameters<LoginParams>,
     ) -> Result<CallToolResult, McpError> {
-        let mut session = self.session.lock().unwrap();
-        let dom = session.login(&params.username, &params.password).map_err(map_fetch_error)?;
-        let text = format_page(&dom, None);
+        let _span = tracing::info_span!("tool", name = "login").entered();
+        let mut session = self.session();
+        let outcome = session.login(&params.username, &params.password).map_err(map_fetch_error)?;
+        let page = format!("login: {}\n{}", outcome.status, format_page(&outcome.dom, None));
+        // A site may echo the password back (e.g. in a re-rendered form)
+        let text = redact::scrub(&page, &params.password);
+        self.page_changed(&session);
         Ok(CallToolResult::success(vec![Content::text(text)]))
     }
 
@@ -322,9 +956,11 @@ impl BrowsyServer {
         &self,
         Parameters(params): Parameters<EnterCodeParams>,
     ) -> Result<CallToolResult, McpError> {
-        let mut session = self.session.lock().unwrap();
+        let _span = tracing::info_span!("tool", name = "enter_code").entered();
+        let mut session = self.session();
         let dom = session.enter_code(&params.code).map_err(map_fetch_error)?;
         let text = format_page(&dom, None);
+        self.page_changed(&session);
         Ok(CallToolResult::success(vec![Content::text(text)]))
     }
 
@@ -333,7 +969,8 @@ impl BrowsyServer {
         &self,
         Parameters(params): Parameters<FindParams>,
     ) -> Result<CallToolResult, McpError> {
-        let session = self.session.lock().unwrap();
+        let _span = tracing::info_span!("tool", name = "find").entered();
+        let session = self.session();
         let mut results: Vec<browsy_core::output::SpatialElement> = params
             .text
             .as_deref()
@@ -354,19 +991,55 @@ impl BrowsyServer {
         Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
+    #[tool(description = "Return an element's whole text. The compact format cuts text over 200 characters short and notes its full length, as in \"Terms…\" (1.4k chars).")]
+    pub async fn get_element_text(
+        &self,
+        Parameters(params): Parameters<ElementTextParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let _span = tracing::info_span!("tool", name = "get_element_text").entered();
+        let session = self.session();
+        let el = session.require_element(params.id).map_err(map_fetch_error)?;
+        Ok(CallToolResult::success(vec![Content::text(el.text.clone().unwrap_or_default())]))
+    }
+
+    #[tool(description = "Return an element with what surrounds it: the landmark or card holding it, the elements on its row (a product's title, price and button), the nearest elements left of and above it (usually its label), and its neighbors within radius pixels.")]
+    pub async fn element_context(
+        &self,
+        Parameters(params): Parameters<ElementContextParams>,
+    ) -> Result<CallToolResult, McpError> {
+        let _span = tracing::info_span!("tool", name = "element_context").entered();
+        let session = self.session();
+        session.require_element(params.id).map_err(map_fetch_error)?;
+        let dom = session.dom().ok_or_else(|| map_fetch_error(FetchError::NoPageLoaded))?;
+        let radius = params.radius.unwrap_or(output::DEFAULT_NEIGHBOR_RADIUS);
+        let json = serde_json::to_string_pretty(&dom.context(params.id, radius)).unwrap_or_default();
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
     #[tool(description = "Extract structured table data from the current page. Returns headers and rows.")]
     pub async fn tables(&self) -> Result<CallToolResult, McpError> {
-        let session = self.session.lock().unwrap();
-        let dom = session.dom().ok_or_else(|| err("No page loaded"))?;
+        let _span = tracing::info_span!("tool", name = "tables").entered();
+        let session = self.session();
+        let dom = session.dom().ok_or_else(|| map_fetch_error(FetchError::NoPageLoaded))?;
         let tables = dom.tables();
         let json = serde_json::to_string_pretty(&tables).unwrap_or_default();
         Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
+    #[tool(description = "Summarize the current page without its element list: element counts by role, and how many elements are hidden, links or interactive, and how many forms and tables there are.")]
+    pub async fn page_summary(&self) -> Result<CallToolResult, McpError> {
+        let _span = tracing::info_span!("tool", name = "page_summary").entered();
+        let session = self.session();
+        let dom = session.dom().ok_or_else(|| map_fetch_error(FetchError::NoPageLoaded))?;
+        let json = serde_json::to_string_pretty(&dom.summary()).unwrap_or_default();
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
     #[tool(description = "Get page metadata: page type, suggested actions (login/search/consent), alerts, pagination, title, and URL.")]
     pub async fn page_info(&self) -> Result<CallToolResult, McpError> {
-        let session = self.session.lock().unwrap();
-        let dom = session.dom().ok_or_else(|| err("No page loaded"))?;
+        let _span = tracing::info_span!("tool", name = "page_info").entered();
+        let session = self.session();
+        let dom = session.dom().ok_or_else(|| map_fetch_error(FetchError::NoPageLoaded))?;
         let mut info = serde_json::json!({
             "title": dom.title,
             "url": dom.url,
@@ -380,13 +1053,40 @@ impl BrowsyServer {
                 })
             }).collect::<Vec<_>>(),
             "pagination": dom.pagination(),
+            "load_more": dom.load_more_button(),
+            "breadcrumbs": dom.breadcrumbs(),
+            "auth_state": dom.auth_state(),
+            "price": dom.primary_price(),
+            "article": dom.article,
+            "lang": dom.lang,
+            "lang_confidence": dom.lang_confidence,
+            "language_mismatch": session.language_mismatch(),
+            "effective_config": effective_settings(&session),
         });
+        if let Some(timings) = session.timings() {
+            info.as_object_mut().unwrap().insert(
+                "timings".to_string(),
+                serde_json::to_value(timings).unwrap_or_default(),
+            );
+        }
+        if let Some(nav_stats) = session.last_nav_stats() {
+            info.as_object_mut().unwrap().insert(
+                "nav_stats".to_string(),
+                serde_json::to_value(nav_stats).unwrap_or_default(),
+            );
+        }
         if let Some(domain_memory) = session.domain_memory_for_current() {
             info.as_object_mut().unwrap().insert(
                 "domain_memory".to_string(),
                 serde_json::to_value(domain_memory).unwrap_or_default(),
             );
         }
+        if dom.page_type == output::PageType::EmailBody {
+            info.as_object_mut().unwrap().insert(
+                "verification".to_string(),
+                serde_json::to_value(dom.find_verification()).unwrap_or_default(),
+            );
+        }
         if let Some(ref captcha) = dom.captcha {
             info.as_object_mut().unwrap().insert(
                 "captcha".to_string(),
@@ -419,21 +1119,16 @@ impl BrowsyServer {
 impl ServerHandler for BrowsyServer {
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
-            instructions: Some(
-                "browsy: zero-render browser for AI agents. Use browse to navigate, \
-                 then interact with elements by ID. Elements are listed with [id:tag \"text\"] format. \
-                 Size hints (narrow/wide/full) appear on form elements. \
-                 Position (@top, @mid-L, etc.) appears only to disambiguate duplicate elements.\n\n\
-                 WHEN TO USE BROWSY vs WebFetch: browsy is for interactive browsing — \
-                 filling forms, logging in, clicking through pages, reading page structure, \
-                 extracting tables, and multi-step navigation with session/cookie persistence. \
-                 Do NOT use browsy as a replacement for WebFetch. If you just need to read \
-                 content from a URL (fetching docs, reading an article, checking an API response), \
-                 use WebFetch instead. Use browsy when you need to interact with a page or \
-                 understand its structure (form fields, buttons, page type detection)."
-                    .to_string(),
-            ),
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            instructions: Some(format!(
+                "{FORMAT_LEGEND}\n{}\n\n{WHEN_TO_USE}",
+                output::format_legend()
+            )),
+            capabilities: ServerCapabilities::builder()
+                .enable_tools()
+                .enable_resources()
+                .enable_resources_subscribe()
+                .enable_prompts()
+                .build(),
             server_info: Implementation {
                 name: "browsy-mcp".to_string(),
                 version: env!("CARGO_PKG_VERSION").to_string(),
@@ -442,4 +1137,88 @@ impl ServerHandler for BrowsyServer {
             ..Default::default()
         }
     }
+
+    async fn on_initialized(&self, context: NotificationContext<RoleServer>) {
+        *self.peer.lock().unwrap() = Some(context.peer);
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        let resources = PAGE_RESOURCES
+            .iter()
+            .map(|(uri, name, description, mime)| {
+                let mut raw = RawResource::new(*uri, *name);
+                raw.description = Some(description.to_string());
+                raw.mime_type = Some(mime.to_string());
+                raw.no_annotation()
+            })
+            .collect();
+        Ok(ListResourcesResult::with_all_items(resources))
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        let text = {
+            let session = self.session();
+            read_page_resource(&session, &request.uri)?
+        };
+        let mime = PAGE_RESOURCES
+            .iter()
+            .find(|(uri, ..)| *uri == request.uri)
+            .map(|(.., mime)| mime.to_string());
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::TextResourceContents {
+                uri: request.uri,
+                mime_type: mime,
+                text,
+                meta: None,
+            }],
+        })
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, McpError> {
+        Ok(ListPromptsResult::with_all_items(prompts()))
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, McpError> {
+        render_prompt(&request.name, request.arguments.as_ref())
+    }
+
+    async fn subscribe(
+        &self,
+        request: SubscribeRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        if !PAGE_RESOURCES.iter().any(|(uri, ..)| *uri == request.uri) {
+            return Err(McpError::resource_not_found(
+                format!("Unknown resource: {}", request.uri),
+                None,
+            ));
+        }
+        self.subscriptions.lock().unwrap().insert(request.uri);
+        Ok(())
+    }
+
+    async fn unsubscribe(
+        &self,
+        request: UnsubscribeRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<(), McpError> {
+        self.subscriptions.lock().unwrap().remove(&request.uri);
+        Ok(())
+    }
 }
@@ -1,28 +1,67 @@
-use std::sync::Mutex;
-
-use browsy_core::fetch::{Session, SessionConfig};
-use browsy_mcp::BrowsyServer;
+use browsy_core::fetch::{ActionPolicy, Session, SessionConfig};
+use browsy_mcp::{BrowsyServer, DEFAULT_MAX_TABS};
 use rmcp::ServiceExt;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
+
+const USAGE: &str = "usage: browsy-mcp [--log-level <FILTER>] [--state-dir <DIR>] [--max-tabs <N>] \
+                     [--confirm-submit <WORDS>]... [--confirm-outside <DOMAIN>]...";
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut log_level = "warn".to_string();
+    let mut state_dir = std::env::var_os("BROWSY_STATE_DIR").map(std::path::PathBuf::from);
+    let mut max_tabs = DEFAULT_MAX_TABS;
+    let mut confirm_submit = Vec::new();
+    let mut confirm_outside = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--log-level" => log_level = args.next().ok_or(USAGE)?,
+            "--state-dir" => state_dir = Some(args.next().ok_or(USAGE)?.into()),
+            "--max-tabs" => max_tabs = args.next().ok_or(USAGE)?.parse().map_err(|_| USAGE)?,
+            "--confirm-submit" => confirm_submit.push(args.next().ok_or(USAGE)?),
+            "--confirm-outside" => confirm_outside.push(args.next().ok_or(USAGE)?),
+            _ => return Err(USAGE.into()),
+        }
+    }
+    // stdout carries the MCP protocol, so logs must go to stderr
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::try_new(&log_level)?)
+        .with_span_events(FmtSpan::CLOSE)
+        .with_writer(std::io::stderr)
+        .with_ansi(false)
+        .try_init()
+        .map_err(|e| e.to_string())?;
+
     // Create Session outside tokio runtime — reqwest::blocking::Client has its own
     // internal runtime that panics if dropped inside another tokio context.
-    let config = SessionConfig::default();
+    let mut config = SessionConfig::default();
+    if !confirm_submit.is_empty() || !confirm_outside.is_empty() {
+        let mut policy = ActionPolicy { allowed_domains: confirm_outside, ..ActionPolicy::default() };
+        if !confirm_submit.is_empty() {
+            policy.submit_patterns = confirm_submit;
+        }
+        config.action_policy = Some(policy);
+    }
     let session = Session::with_config(config)?;
-    let session = std::sync::Arc::new(Mutex::new(session));
 
-    let server = BrowsyServer::with_session(session.clone());
+    let server = match state_dir {
+        Some(dir) => BrowsyServer::with_state_dir(session, &dir),
+        None => BrowsyServer::with_session(session),
+    }
+    .with_max_tabs(max_tabs);
+    let service = server.clone();
 
     tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .build()?
         .block_on(async move {
-            let service = server.serve(rmcp::transport::stdio()).await?;
+            let service = service.serve(rmcp::transport::stdio()).await?;
             service.waiting().await?;
             Ok::<(), Box<dyn std::error::Error>>(())
         })?;
 
-    // Session's Arc drops here, outside tokio — safe for reqwest::blocking
-    drop(session);
+    // The tabs' sessions drop here, outside tokio — safe for reqwest::blocking
+    drop(server);
     Ok(())
 }
@@ -0,0 +1,172 @@
+//! Task-oriented prompt templates that walk an agent through common browsy flows.
+
+use rmcp::model::{
+    GetPromptResult, JsonObject, Prompt, PromptArgument, PromptMessage, PromptMessageRole,
+};
+use browsy_core::output;
+use rmcp::ErrorData as McpError;
+
+use crate::FORMAT_LEGEND;
+
+/// A prompt template: its arguments as (name, description, required) and
+/// the tools its rendered text tells the agent to call.
+pub struct PromptSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub arguments: &'static [(&'static str, &'static str, bool)],
+    pub tools: &'static [&'static str],
+}
+
+pub const PROMPTS: &[PromptSpec] = &[
+    PromptSpec {
+        name: "login_to_site",
+        description: "Log in to a site, handling verification codes and CAPTCHAs",
+        arguments: &[("url", "URL of the site or its login page", true)],
+        tools: &["browse", "page_info", "login", "enter_code", "get_page"],
+    },
+    PromptSpec {
+        name: "search_and_summarize",
+        description: "Search the web, read the top results and summarize them",
+        arguments: &[
+            ("query", "What to search for", true),
+            ("n_results", "How many results to open (1-10, default 3)", false),
+        ],
+        tools: &["search", "browse", "tables", "back"],
+    },
+    PromptSpec {
+        name: "fill_form_from_json",
+        description: "Fill a form on a page from a JSON object of field values",
+        arguments: &[
+            ("url", "URL of the page containing the form", true),
+            ("values_json", "JSON object mapping field labels or names to values", true),
+        ],
+        tools: &["browse", "find", "type_text", "check", "select", "get_page", "click"],
+    },
+];
+
+const DEFAULT_RESULTS: usize = 3;
+const MAX_RESULTS: usize = 10;
+
+/// All prompts in the shape returned by `prompts/list`.
+pub fn prompts() -> Vec<Prompt> {
+    PROMPTS
+        .iter()
+        .map(|spec| {
+            let arguments = spec
+                .arguments
+                .iter()
+                .map(|(name, description, required)| PromptArgument {
+                    name: name.to_string(),
+                    title: None,
+                    description: Some(description.to_string()),
+                    required: Some(*required),
+                })
+                .collect();
+            Prompt::new(spec.name, Some(spec.description), Some(arguments))
+        })
+        .collect()
+}
+
+/// Render a prompt by name, validating its arguments and applying defaults.
+pub fn render_prompt(name: &str, args: Option<&JsonObject>) -> Result<GetPromptResult, McpError> {
+    let spec = PROMPTS
+        .iter()
+        .find(|spec| spec.name == name)
+        .ok_or_else(|| McpError::invalid_params(format!("Unknown prompt: {name}"), None))?;
+
+    let body = match spec.name {
+        "login_to_site" => {
+            let url = url_arg(args, "url")?;
+            format!(
+                "Log in to {url}.\n\n\
+                 1. Call `browse` with url \"{url}\".\n\
+                 2. Call `page_info` and read page_type and suggested_actions. If the page is not \
+                    a Login page, click the sign-in link it suggests and check again.\n\
+                 3. Ask the user for their credentials if you do not have them, then call `login` \
+                    with username and password.\n\
+                 4. If the next page_type is TwoFactorAuth or it asks for a verification code, ask \
+                    the user for the code and call `enter_code`.\n\
+                 5. If page_info reports a captcha or blocked page, stop and ask the user to solve it.\n\
+                 6. Confirm success with `get_page`: the login form should be gone and the page \
+                    should show account content."
+            )
+        }
+        "search_and_summarize" => {
+            let query = required_arg(args, "query")?;
+            let n = match optional_arg(args, "n_results") {
+                None => DEFAULT_RESULTS,
+                Some(raw) => match raw.parse::<usize>() {
+                    Ok(n) if (1..=MAX_RESULTS).contains(&n) => n,
+                    _ => {
+                        return Err(McpError::invalid_params(
+                            format!("n_results must be a number from 1 to {MAX_RESULTS}"),
+                            None,
+                        ))
+                    }
+                },
+            };
+            format!(
+                "Research \"{query}\" and summarize what you find.\n\n\
+                 1. Call `search` with query \"{query}\".\n\
+                 2. For each of the top {n} results, call `browse` with its URL and read the page \
+                    text. Use `tables` when the answer is in tabular data.\n\
+                 3. Call `back` or `browse` the next result URL to continue.\n\
+                 4. Summarize the findings in a few sentences, citing the URL of each source."
+            )
+        }
+        "fill_form_from_json" => {
+            let url = url_arg(args, "url")?;
+            let raw = required_arg(args, "values_json")?;
+            let values: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&raw)
+                .map_err(|e| McpError::invalid_params(format!("values_json must be a JSON object: {e}"), None))?;
+            let fields = values
+                .iter()
+                .map(|(field, value)| match value {
+                    serde_json::Value::String(s) => format!("- {field}: {s}"),
+                    other => format!("- {field}: {other}"),
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            format!(
+                "Fill the form at {url} with these values:\n{fields}\n\n\
+                 1. Call `browse` with url \"{url}\".\n\
+                 2. For each field, locate the input by its label, placeholder or name in the page \
+                    output, or call `find` with the field name as text.\n\
+                 3. Use `type_text` for text inputs and textareas, `check` for checkboxes and radio \
+                    buttons (true means checked), and `select` for dropdowns.\n\
+                 4. Call `get_page` and verify every field shows the expected value.\n\
+                 5. Only `click` the submit button if the user asked you to submit."
+            )
+        }
+        _ => unreachable!("every PromptSpec has a renderer"),
+    };
+
+    let text = format!("{body}\n\nPage format: {FORMAT_LEGEND}\n{}", output::format_legend());
+    Ok(GetPromptResult {
+        description: Some(spec.description.to_string()),
+        messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+    })
+}
+
+fn optional_arg(args: Option<&JsonObject>, name: &str) -> Option<String> {
+    match args?.get(name)? {
+        serde_json::Value::String(s) if s.trim().is_empty() => None,
+        serde_json::Value::String(s) => Some(s.trim().to_string()),
+        serde_json::Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+fn required_arg(args: Option<&JsonObject>, name: &str) -> Result<String, McpError> {
+    optional_arg(args, name)
+        .ok_or_else(|| McpError::invalid_params(format!("Missing required argument: {name}"), None))
+}
+
+fn url_arg(args: Option<&JsonObject>, name: &str) -> Result<String, McpError> {
+    let url = required_arg(args, name)?;
+    if url.starts_with("http://") || url.starts_with("https://") {
+        Ok(url)
+    } else {
+        Err(McpError::invalid_params(format!("{name} must be an http(s) URL"), None))
+    }
+}
@@ -0,0 +1,88 @@
+//! Helpers shared by the MCP server tests.
+
+// Each test crate uses a different part of this
+#![allow(dead_code)]
+
+use std::future::Future;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::task::{Context, Poll, Waker};
+
+use browsy_core::fetch::{Session, SessionConfig};
+use browsy_mcp::BrowsyServer;
+
+/// A session config that skips fetching stylesheets.
+pub fn make_config() -> SessionConfig {
+    SessionConfig {
+        fetch_css: false,
+        ..SessionConfig::default()
+    }
+}
+
+/// A server with a fresh session.
+pub fn make_server() -> BrowsyServer {
+    let session = Session::with_config(make_config()).unwrap();
+    BrowsyServer::with_session(session)
+}
+
+/// The text of a tool result's first content item.
+pub fn extract_text(result: rmcp::model::CallToolResult) -> String {
+    result
+        .content
+        .first()
+        .and_then(|c| c.raw.as_text())
+        .map(|t| t.text.clone())
+        .unwrap_or_default()
+}
+
+/// Run a tool call to completion outside any tokio runtime, for tools that
+/// may send a request: the session's blocking HTTP client panics (in debug
+/// builds) if it sends from inside one. With one tab and no client
+/// connected they never await.
+pub fn call<F: Future>(tool: F) -> F::Output {
+    let mut tool = std::pin::pin!(tool);
+    match tool.as_mut().poll(&mut Context::from_waker(Waker::noop())) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("tool call awaited"),
+    }
+}
+
+/// Serve a site on a random local port where `/login` sets a `sid` session
+/// cookie and every other page says whether the request carried it. Pages
+/// are titled with their path. Returns the site's base URL.
+pub fn serve_sign_in() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else { continue };
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            if reader.read_line(&mut line).is_err() {
+                continue;
+            }
+            let path = line.split_whitespace().nth(1).unwrap_or("/").to_string();
+            let mut signed_in = false;
+            loop {
+                let mut header = String::new();
+                if reader.read_line(&mut header).is_err() || header.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = header.split_once(':') {
+                    signed_in |= name.eq_ignore_ascii_case("cookie")
+                        && value.split(';').any(|c| c.trim() == "sid=ada");
+                }
+            }
+
+            let status = if signed_in { "Signed in as Ada" } else { "Not signed in" };
+            let page = format!("<html><head><title>{path}</title></head><body><p>{status}</p></body></html>");
+            let cookie = if path == "/login" { "Set-Cookie: sid=ada; Path=/\r\n" } else { "" };
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\n{cookie}Content-Length: {}\r\nConnection: close\r\n\r\n{page}",
+                page.len()
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+    format!("http://127.0.0.1:{port}")
+}
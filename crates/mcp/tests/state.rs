@@ -0,0 +1,97 @@
+//! A server with a state directory saves its session after every page
+//! change, and a new server started on that directory carries on from it.
+
+mod common;
+
+use std::path::{Path, PathBuf};
+
+use browsy_core::fetch::{Session, SessionConfig, SessionState};
+use browsy_mcp::*;
+use common::{call, extract_text};
+use rmcp::handler::server::wrapper::Parameters;
+
+fn state_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("browsy-mcp-state-{}-{name}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// A server on `dir`.
+fn start(dir: &Path) -> BrowsyServer {
+    let session = Session::with_config(SessionConfig {
+        fetch_css: false,
+        allow_private_network: true,
+        ..SessionConfig::default()
+    })
+    .unwrap();
+    BrowsyServer::with_state_dir(session, dir)
+}
+
+fn browse_params(url: String) -> Parameters<BrowseParams> {
+    Parameters(BrowseParams { url, format: None, scope: None })
+}
+
+#[test]
+fn test_restarted_server_restores_url_and_cookies() {
+    let base = common::serve_sign_in();
+    let dir = state_dir("restart");
+
+    let first = start(&dir);
+    let urls = [format!("{base}/login"), format!("{base}/account")];
+    for url in urls {
+        call(first.browse(browse_params(url))).unwrap();
+    }
+    drop(first);
+    assert!(dir.join(STATE_FILE).exists());
+
+    let second = start(&dir);
+    second.with_active_session(|session| {
+        assert_eq!(session.url(), Some(format!("{base}/account").as_str()));
+        assert_eq!(session.history_len(), 2);
+        assert_eq!(session.cookie_count(), 1);
+    });
+
+    // The first tool call fetches the restored page again, signed in
+    let text = extract_text(call(second.get_page(Parameters(GetPageParams { format: None, scope: None }))).unwrap());
+    assert!(text.contains("Signed in as Ada"), "{text}");
+    assert_eq!(second.with_active_session(|session| session.history_len()), 2);
+}
+
+#[test]
+fn test_corrupt_state_starts_fresh() {
+    let dir = state_dir("corrupt");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join(STATE_FILE), "{ not json").unwrap();
+
+    let server = start(&dir);
+    assert_eq!(server.with_active_session(|session| session.url().map(str::to_string)), None);
+
+    let html = "<html><head><title>Fresh</title></head><body><p>Hi</p></body></html>".to_string();
+    let params = LoadHtmlParams { html, url: None, format: None, scope: None };
+    let text = extract_text(call(server.load_html(Parameters(params))).unwrap());
+    assert!(text.contains("Fresh"));
+    // The page change replaced the corrupt file
+    assert!(SessionState::load(&dir.join(STATE_FILE)).unwrap().is_some());
+}
+
+#[test]
+fn test_reset_session_wipes_saved_state() {
+    let base = common::serve_sign_in();
+    let dir = state_dir("reset");
+    let server = start(&dir);
+
+    let url = format!("{base}/login");
+    call(server.browse(browse_params(url))).unwrap();
+    let text = extract_text(call(server.reset_session()).unwrap());
+    assert_eq!(text, "Session reset");
+    assert!(!dir.join(STATE_FILE).exists());
+    server.with_active_session(|session| {
+        assert_eq!(session.url(), None);
+        assert_eq!(session.history_len(), 0);
+    });
+    drop(server);
+
+    // A server started afterwards has nothing to restore
+    let restarted = start(&dir);
+    assert_eq!(restarted.with_active_session(|session| session.url().map(str::to_string)), None);
+}
@@ -0,0 +1,115 @@
+//! Tabs each keep their own page and form values, and the tab tools keep
+//! the number open within the server's limit.
+
+mod common;
+
+use browsy_mcp::*;
+use common::{extract_text, make_server};
+use rmcp::handler::server::wrapper::Parameters;
+
+const FORM: &str = r#"<html><head><title>Search</title></head><body>
+    <input type="text" name="q" placeholder="Query" />
+</body></html>"#;
+
+fn load_form(url: &str) -> Parameters<LoadHtmlParams> {
+    Parameters(LoadHtmlParams { html: FORM.to_string(), url: Some(url.to_string()), format: None, scope: None })
+}
+
+fn get_page() -> Parameters<GetPageParams> {
+    Parameters(GetPageParams { format: None, scope: None })
+}
+
+/// Run `f` with a tokio runtime on its own thread, returning the server so
+/// its sessions drop outside async context.
+fn run<T: Send + 'static>(
+    server: BrowsyServer,
+    f: impl FnOnce(&BrowsyServer, &tokio::runtime::Runtime) -> T + Send + 'static,
+) -> (T, BrowsyServer) {
+    std::thread::spawn(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let value = f(&server, &rt);
+        drop(rt);
+        (value, server)
+    })
+    .join()
+    .unwrap()
+}
+
+#[test]
+fn test_tabs_keep_their_own_form_values() {
+    let ((first, second, back, tabs), _server) = run(make_server(), |server, rt| {
+        rt.block_on(server.load_html(load_form("https://example.com/first"))).unwrap();
+        let id = server.with_active_session(|s| {
+            s.dom_ref().unwrap().els.iter().find(|e| e.tag == "input").unwrap().id
+        });
+        rt.block_on(server.type_text(Parameters(TypeTextParams { id, text: "browsy".into() }))).unwrap();
+        let first = extract_text(rt.block_on(server.get_page(get_page())).unwrap());
+
+        let opened = extract_text(rt.block_on(server.new_tab()).unwrap());
+        assert_eq!(opened, "Opened tab 2");
+        rt.block_on(server.load_html(load_form("https://example.com/second"))).unwrap();
+        let second = extract_text(rt.block_on(server.get_page(get_page())).unwrap());
+
+        let back = extract_text(rt.block_on(server.switch_tab(Parameters(TabParams { id: 1 }))).unwrap());
+        let tabs = extract_text(rt.block_on(server.list_tabs()).unwrap());
+        (first, second, back, tabs)
+    });
+
+    assert!(first.contains("browsy"), "{first}");
+    assert!(!second.contains("browsy"), "the new tab shouldn't see tab 1's input: {second}");
+    assert!(back.contains("browsy"), "{back}");
+
+    let tabs: serde_json::Value = serde_json::from_str(&tabs).unwrap();
+    assert_eq!(tabs[0]["id"], 1);
+    assert_eq!(tabs[0]["url"], "https://example.com/first");
+    assert_eq!(tabs[0]["active"], true);
+    assert_eq!(tabs[1]["id"], 2);
+    assert_eq!(tabs[1]["title"], "Search");
+    assert_eq!(tabs[1]["active"], false);
+}
+
+#[test]
+fn test_new_tab_stops_at_max_tabs() {
+    let server = make_server().with_max_tabs(2);
+    let ((second, third), _server) = run(server, |server, rt| {
+        (rt.block_on(server.new_tab()), rt.block_on(server.new_tab()))
+    });
+    assert!(second.is_ok());
+    let err = third.unwrap_err();
+    assert_eq!(err.data.unwrap()["code"], "session_limit");
+}
+
+#[test]
+fn test_concurrent_new_tabs_stay_within_max_tabs() {
+    let server = make_server().with_max_tabs(2);
+    let (results, server) = run(server, |server, rt| {
+        rt.block_on(async {
+            let (a, b, c) = tokio::join!(server.new_tab(), server.new_tab(), server.new_tab());
+            [a, b, c].map(|result| result.is_ok())
+        })
+    });
+    assert_eq!(results.iter().filter(|ok| **ok).count(), 1, "{results:?}");
+    let (tabs, _server) = run(server, |server, rt| extract_text(rt.block_on(server.list_tabs()).unwrap()));
+    let tabs: serde_json::Value = serde_json::from_str(&tabs).unwrap();
+    assert_eq!(tabs.as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn test_close_tab() {
+    let ((closed, active, missing, last), server) = run(make_server(), |server, rt| {
+        rt.block_on(server.new_tab()).unwrap();
+        rt.block_on(server.new_tab()).unwrap();
+        let closed = extract_text(rt.block_on(server.close_tab(Parameters(TabParams { id: 3 }))).unwrap());
+        let active = server.with_active_session(|s| s.url().map(str::to_string));
+        let missing = rt.block_on(server.switch_tab(Parameters(TabParams { id: 3 })));
+        rt.block_on(server.close_tab(Parameters(TabParams { id: 1 }))).unwrap();
+        let last = rt.block_on(server.close_tab(Parameters(TabParams { id: 2 })));
+        (closed, active, missing, last)
+    });
+
+    assert_eq!(closed, "Closed tab 3; tab 2 is active");
+    assert_eq!(active, None);
+    assert_eq!(missing.unwrap_err().data.unwrap()["code"], "session_expired");
+    assert!(last.is_err(), "the last tab can't be closed");
+    drop(server);
+}
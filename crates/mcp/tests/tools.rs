@@ -1,35 +1,17 @@
+mod common;
+
 use std::sync::{Arc, Mutex};
 
 use browsy_core::fetch::{Session, SessionConfig};
 use browsy_mcp::BrowsyServer;
 use rmcp::handler::server::wrapper::Parameters;
 use browsy_mcp::*;
-
-fn make_config() -> SessionConfig {
-    SessionConfig {
-        fetch_css: false,
-        ..SessionConfig::default()
-    }
-}
-
-fn make_server() -> BrowsyServer {
-    let session = Session::with_config(make_config()).unwrap();
-    BrowsyServer::with_session(Arc::new(Mutex::new(session)))
-}
+use common::{call, extract_text, make_config, make_server};
 
 fn make_server_with_html(html: &str, url: &str) -> BrowsyServer {
     let mut session = Session::with_config(make_config()).unwrap();
     session.load_html(html, url).unwrap();
-    BrowsyServer::with_session(Arc::new(Mutex::new(session)))
-}
-
-fn extract_text(result: rmcp::model::CallToolResult) -> String {
-    result
-        .content
-        .first()
-        .and_then(|c| c.raw.as_text())
-        .map(|t| t.text.clone())
-        .unwrap_or_default()
+    BrowsyServer::with_session(session)
 }
 
 /// Run an async closure on a fresh tokio runtime.
@@ -98,6 +80,63 @@ fn test_page_info_suggested_actions() {
     let actions = info["suggested_actions"].as_array().unwrap();
     assert!(!actions.is_empty(), "should detect login action");
     assert_eq!(actions[0]["action"], "Login");
+    assert!(info["timings"]["layout"].is_number(), "should report phase timings");
+}
+
+#[test]
+fn test_page_info_reports_verification_hints_on_emails() {
+    let html = r#"
+    <html><head><title>Confirm your email</title></head>
+    <body>
+        <p>From: Example Accounts &lt;no-reply@accounts.example.com&gt;</p>
+        <p>To: ada@example.com</p>
+        <p>Subject: Confirm your email address</p>
+        <p>Date: Tue, Mar 3, 2026</p>
+        <div style="height: 200px"></div>
+        <a href="https://accounts.example.com/email/verify?token=abc">Verify email</a>
+        <p>Or enter your verification code: 739214</p>
+    </body></html>"#;
+    let server = make_server_with_html(html, "https://mail.example.com/message/1");
+
+    let (info, _server) = run_async(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let result = rt.block_on(async { server.page_info().await.unwrap() });
+        let info: serde_json::Value = serde_json::from_str(&extract_text(result)).unwrap();
+        drop(rt);
+        (info, server)
+    });
+
+    assert_eq!(info["page_type"], "EmailBody");
+    assert_eq!(info["verification"]["codes"], serde_json::json!(["739214"]));
+    let links = info["verification"]["links"].as_array().unwrap();
+    assert_eq!(links.len(), 1);
+    assert!(links[0][1].as_str().unwrap().contains("/email/verify?token="), "{links:?}");
+}
+
+#[test]
+fn test_page_summary_and_legend_in_instructions() {
+    let html = r#"<html><body>
+        <a href="/a">A</a> <a href="/b">B</a>
+        <div hidden><button>Secret</button></div>
+    </body></html>"#;
+    let server = make_server_with_html(html, "https://example.com");
+
+    let (summary, server) = run_async(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let result = rt.block_on(async { server.page_summary().await.unwrap() });
+        let summary: serde_json::Value = serde_json::from_str(&extract_text(result)).unwrap();
+        drop(rt);
+        (summary, server)
+    });
+
+    assert_eq!(summary["link_count"], 2);
+    assert_eq!(summary["hidden_count"], 1);
+    assert_eq!(summary["interactive_count"], 3);
+    assert_eq!(summary["counts_by_role"]["button"], 1);
+
+    use rmcp::ServerHandler;
+    let instructions = server.get_info().instructions.unwrap();
+    assert!(instructions.contains(browsy_core::output::format_legend()), "{instructions}");
 }
 
 #[test]
@@ -155,6 +194,98 @@ fn test_type_text_and_get_page() {
     assert!(page_text.contains("Alice"), "typed value should appear in page");
 }
 
+#[test]
+fn test_type_text_redacts_password() {
+    let html = r#"
+    <html><head><title>Sign in</title></head>
+    <body>
+        <input type="password" name="pw" placeholder="Password" />
+    </body></html>"#;
+    let server = make_server_with_html(html, "https://example.com/login");
+
+    let (typed_text, page_text, _server) = run_async(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let (typed_text, page_text) = rt.block_on(async {
+            let result = server
+                .get_page(Parameters(GetPageParams { format: Some("json".to_string()), scope: None }))
+                .await
+                .unwrap();
+            let dom: serde_json::Value = serde_json::from_str(&extract_text(result)).unwrap();
+            let input_id = dom["els"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|e| e["tag"] == "input")
+                .unwrap()["id"]
+                .as_u64()
+                .unwrap() as u32;
+
+            let result = server
+                .type_text(Parameters(TypeTextParams {
+                    id: input_id,
+                    text: "hunter22".to_string(),
+                }))
+                .await
+                .unwrap();
+            let typed_text = extract_text(result);
+
+            let result = server
+                .get_page(Parameters(GetPageParams { format: Some("json".to_string()), scope: None }))
+                .await
+                .unwrap();
+            (typed_text, extract_text(result))
+        });
+        drop(rt);
+        (typed_text, page_text, server)
+    });
+
+    assert!(typed_text.contains("•••• (8 chars)"), "{typed_text}");
+    assert!(!typed_text.contains("hunter22"), "{typed_text}");
+    assert!(!page_text.contains("hunter22"), "{page_text}");
+}
+
+#[test]
+fn test_load_html_tool() {
+    let html = r#"
+    <html><head><title>Saved</title></head>
+    <body>
+        <form><input type="text" name="q" placeholder="Search" /></form>
+        <a href="/archive">Archive</a>
+    </body></html>"#.to_string();
+    let server = make_server();
+
+    let (text, page_text, _server) = run_async(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let (text, page_text) = rt.block_on(async {
+            let result = server
+                .load_html(Parameters(LoadHtmlParams {
+                    html,
+                    url: None,
+                    format: Some("json".to_string()),
+                    scope: None,
+                }))
+                .await
+                .unwrap();
+            let text = extract_text(result);
+            let result = server
+                .get_page(Parameters(GetPageParams { format: None, scope: None }))
+                .await
+                .unwrap();
+            (text, extract_text(result))
+        });
+        drop(rt);
+        (text, page_text, server)
+    });
+
+    let dom: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(dom["url"], "about:blank");
+    assert!(
+        dom["els"].as_array().unwrap().iter().any(|e| e["href"] == "/archive"),
+        "relative links should stay unresolved: {text}"
+    );
+    assert!(page_text.contains("title: Saved"), "loaded page should be current: {page_text}");
+}
+
 #[test]
 fn test_find_tool() {
     let html = r#"
@@ -205,21 +336,12 @@ fn test_login_tool() {
     </body></html>"#;
     let server = make_server_with_html(html, "https://example.com/login");
 
-    let (_result, _server) = run_async(move || {
-        let rt = tokio::runtime::Runtime::new().unwrap();
-        let result = rt.block_on(async {
-            server
-                .login(Parameters(LoginParams {
-                    username: "admin".to_string(),
-                    password: "secret".to_string(),
-                }))
-                .await
-        });
-        drop(rt);
-        // login() types values and clicks submit.
-        // With load_html, the submit click may fail with network error — that's expected.
-        (result, server)
-    });
+    // login() types values and clicks submit, which posts to the page's
+    // form action. That may fail with network error — that's expected.
+    let _result = call(server.login(Parameters(LoginParams {
+        username: "admin".to_string(),
+        password: "secret".to_string(),
+    })));
     // We just verify it doesn't panic — the result may be Ok or Err depending on
     // whether the form action URL is reachable.
 }
@@ -272,4 +394,380 @@ fn test_error_on_no_page() {
         err.message.contains("No page loaded"),
         "error message should say no page loaded"
     );
+    assert_eq!(err.data.unwrap()["code"], "no_page_loaded");
+}
+
+#[test]
+fn test_error_codes_for_bad_element() {
+    let html = r#"<html><body><h1>Title</h1><a href="/next">Next</a></body></html>"#;
+    let mut session = Session::with_config(make_config()).unwrap();
+    let heading = session.load_html(html, "https://example.com").unwrap().find_by_text("Title")[0].id;
+    let server = BrowsyServer::with_session(session);
+
+    let (results, _server) = run_async(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let results = rt.block_on(async {
+            let missing = server.click(Parameters(ClickParams { id: 999, confirmed: None })).await;
+            let wrong = server
+                .type_text(Parameters(TypeTextParams { id: heading, text: "x".into() }))
+                .await;
+            (missing, wrong)
+        });
+        drop(rt);
+        (results, server)
+    });
+
+    let missing = results.0.unwrap_err().data.unwrap();
+    assert_eq!(missing["code"], "action_element_not_found");
+    assert_eq!(missing["details"]["id"], 999);
+    assert!(missing["details"]["known_ids_range"].is_array(), "{missing}");
+    let wrong = results.1.unwrap_err().data.unwrap();
+    assert_eq!(wrong["code"], "action_wrong_element_type");
+    assert_eq!(wrong["details"]["expected"], "text input");
+}
+
+#[test]
+fn test_get_element_text_returns_whole_text() {
+    let paragraph = "Read these terms carefully. ".repeat(40);
+    let paragraph = paragraph.trim_end();
+    let html = format!("<html><body><h1>Terms</h1><p>{paragraph}</p></body></html>");
+    let mut session = Session::with_config(make_config()).unwrap();
+    let id = session.load_html(&html, "https://example.com").unwrap().find_by_text("Read these terms")[0].id;
+    let server = BrowsyServer::with_session(session);
+
+    let (page, text, _server) = run_async(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let (page, text) = rt.block_on(async {
+            let page = server.get_page(Parameters(GetPageParams { format: None, scope: None })).await.unwrap();
+            let text = server.get_element_text(Parameters(ElementTextParams { id })).await.unwrap();
+            (page, text)
+        });
+        drop(rt);
+        (extract_text(page), extract_text(text), server)
+    });
+
+    assert!(page.contains("\u{2026}\" (1.1k chars)"), "{page}");
+    assert_eq!(text, paragraph);
+}
+
+#[test]
+fn test_element_context_returns_the_card_row() {
+    let html = r#"<html><body><main>
+        <section style="display: flex; gap: 12px; width: 560px">
+            <h3 style="margin: 0; width: 200px">Bookshelf</h3>
+            <span style="width: 80px">$89.00</span>
+            <button>Add to cart</button>
+        </section>
+    </main></body></html>"#;
+    let mut session = Session::with_config(make_config()).unwrap();
+    let id = session.load_html(html, "https://example.com").unwrap().find_by_text_exact("$89.00")[0].id;
+    let server = BrowsyServer::with_session(session);
+
+    let (context, missing, _server) = run_async(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let results = rt.block_on(async {
+            let context = server.element_context(Parameters(ElementContextParams { id, radius: None })).await;
+            let missing = server.element_context(Parameters(ElementContextParams { id: 999, radius: None })).await;
+            (context, missing)
+        });
+        drop(rt);
+        (extract_text(results.0.unwrap()), results.1, server)
+    });
+
+    let context: serde_json::Value = serde_json::from_str(&context).unwrap();
+    let row: Vec<&str> = context["row"].as_array().unwrap().iter().map(|e| e["text"].as_str().unwrap()).collect();
+    assert_eq!(row, ["Bookshelf", "$89.00", "Add to cart"]);
+    assert_eq!(context["left"]["text"], "Bookshelf");
+    assert_eq!(context["container"]["tag"], "section");
+    assert_eq!(missing.unwrap_err().data.unwrap()["code"], "action_element_not_found");
+}
+
+// --- Resources ---
+
+/// Client that records `notifications/resources/updated` URIs.
+struct UpdateRecorder {
+    uris: Arc<Mutex<Vec<String>>>,
+    updated: Arc<tokio::sync::Notify>,
+}
+
+impl rmcp::ClientHandler for UpdateRecorder {
+    async fn on_resource_updated(
+        &self,
+        params: rmcp::model::ResourceUpdatedNotificationParam,
+        _context: rmcp::service::NotificationContext<rmcp::RoleClient>,
+    ) {
+        self.uris.lock().unwrap().push(params.uri);
+        self.updated.notify_one();
+    }
+}
+
+fn read_params(uri: &str) -> rmcp::model::ReadResourceRequestParams {
+    rmcp::model::ReadResourceRequestParams {
+        meta: None,
+        uri: uri.to_string(),
+    }
+}
+
+fn resource_text(result: &rmcp::model::ReadResourceResult) -> String {
+    match result.contents.first() {
+        Some(rmcp::model::ResourceContents::TextResourceContents { text, .. }) => text.clone(),
+        _ => String::new(),
+    }
+}
+
+#[test]
+fn test_page_resources() {
+    use rmcp::ServiceExt;
+
+    let html = r#"
+    <html><head><title>Resources</title></head>
+    <body>
+        <input type="text" id="q" name="q" placeholder="Query" />
+        <table><tr><th>Name</th></tr><tr><td>Alpha</td></tr></table>
+    </body></html>"#;
+    let mut session = Session::with_config(make_config()).unwrap();
+    session.load_html(html, "https://example.com/res").unwrap();
+    // Keep a clone so the Session is dropped outside the runtime.
+    let server = BrowsyServer::with_session(session);
+    let handle = server.clone();
+
+    let uris = Arc::new(Mutex::new(Vec::new()));
+    let recorder = UpdateRecorder {
+        uris: uris.clone(),
+        updated: Arc::new(tokio::sync::Notify::new()),
+    };
+    let updated = recorder.updated.clone();
+
+    let (listed, current, tables, delta_err, typed, after_unsubscribe) = run_async(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let out = rt.block_on(async move {
+            let (server_transport, client_transport) = tokio::io::duplex(4096);
+            let server_task = tokio::spawn(async move {
+                let running = server.serve(server_transport).await.unwrap();
+                let _ = running.waiting().await;
+            });
+            let client = recorder.serve(client_transport).await.unwrap();
+
+            let listed: Vec<String> = client
+                .list_all_resources()
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|r| r.raw.uri)
+                .collect();
+            let current = resource_text(&client.read_resource(read_params("page://current")).await.unwrap());
+            let tables = resource_text(&client.read_resource(read_params("page://tables")).await.unwrap());
+            let delta_err = client.read_resource(read_params("page://delta")).await.is_err();
+
+            let input_id = current
+                .lines()
+                .find(|l| l.contains("input"))
+                .and_then(|l| l.trim_start_matches('[').split(':').next())
+                .and_then(|id| id.parse::<u32>().ok())
+                .unwrap();
+            let type_text = |text: &str| {
+                let mut args = serde_json::Map::new();
+                args.insert("id".into(), serde_json::json!(input_id));
+                args.insert("text".into(), serde_json::json!(text));
+                client.call_tool(rmcp::model::CallToolRequestParams {
+                    meta: None,
+                    name: "type_text".into(),
+                    arguments: Some(args),
+                    task: None,
+                })
+            };
+            client
+                .subscribe(rmcp::model::SubscribeRequestParams {
+                    meta: None,
+                    uri: "page://current".into(),
+                })
+                .await
+                .unwrap();
+            type_text("browsy").await.unwrap();
+            tokio::time::timeout(std::time::Duration::from_secs(5), updated.notified())
+                .await
+                .expect("resource update notification");
+            let typed = resource_text(&client.read_resource(read_params("page://current")).await.unwrap());
+
+            client
+                .unsubscribe(rmcp::model::UnsubscribeRequestParams {
+                    meta: None,
+                    uri: "page://current".into(),
+                })
+                .await
+                .unwrap();
+            type_text("again").await.unwrap();
+            let after_unsubscribe =
+                tokio::time::timeout(std::time::Duration::from_millis(300), updated.notified())
+                    .await
+                    .is_ok();
+
+            client.cancel().await.unwrap();
+            let _ = server_task.await;
+            (listed, current, tables, delta_err, typed, after_unsubscribe)
+        });
+        drop(rt);
+        out
+    });
+
+    for uri in ["page://current", "page://current.json", "page://delta", "page://tables"] {
+        assert!(listed.iter().any(|u| u == uri), "missing resource {uri}");
+    }
+    assert!(current.contains("title: Resources"), "compact page: {current}");
+    assert!(tables.contains("Alpha"), "tables: {tables}");
+    assert!(delta_err, "delta without a previous page should error");
+    assert!(typed.contains("browsy"), "typed value should appear: {typed}");
+    // Only the subscribed resource is reported, and only while subscribed.
+    assert_eq!(*uris.lock().unwrap(), ["page://current"]);
+    assert!(!after_unsubscribe, "no updates after unsubscribing");
+    drop(handle);
+}
+
+#[test]
+fn test_resource_no_page_loaded() {
+    let session = Session::with_config(make_config()).unwrap();
+    let err = read_page_resource(&session, "page://current").unwrap_err();
+    assert!(err.message.contains("No page loaded"));
+    let err = read_page_resource(&session, "page://tables").unwrap_err();
+    assert!(err.message.contains("No page loaded"));
+    assert!(read_page_resource(&session, "page://nope").is_err());
+}
+
+// --- Prompts ---
+
+fn prompt_args(pairs: &[(&str, &str)]) -> rmcp::model::JsonObject {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), serde_json::json!(v)))
+        .collect()
+}
+
+fn prompt_text(result: &rmcp::model::GetPromptResult) -> String {
+    match &result.messages[0].content {
+        rmcp::model::PromptMessageContent::Text { text } => text.clone(),
+        _ => String::new(),
+    }
+}
+
+/// Words wrapped in backticks — prompts only backtick tool names.
+fn backticked(text: &str) -> Vec<String> {
+    text.split('`').skip(1).step_by(2).map(str::to_string).collect()
+}
+
+#[test]
+fn test_prompts_reference_existing_tools() {
+    let server = make_server();
+    let tools = server.tool_names();
+    let samples = [
+        ("login_to_site", prompt_args(&[("url", "https://example.com/login")])),
+        ("search_and_summarize", prompt_args(&[("query", "rust html parsers"), ("n_results", "2")])),
+        (
+            "fill_form_from_json",
+            prompt_args(&[("url", "https://example.com/signup"), ("values_json", r#"{"Email":"a@b.c","Terms":true}"#)]),
+        ),
+    ];
+    assert_eq!(samples.len(), PROMPTS.len());
+    assert_eq!(prompts().len(), PROMPTS.len());
+
+    for (name, args) in &samples {
+        let spec = PROMPTS.iter().find(|p| p.name == *name).expect("prompt listed");
+        let text = prompt_text(&render_prompt(name, Some(args)).unwrap());
+        assert!(text.contains(FORMAT_LEGEND), "{name} should embed the format legend");
+
+        let referenced = backticked(&text);
+        for tool in &referenced {
+            assert!(tools.contains(tool), "{name} references unknown tool {tool}");
+        }
+        for tool in spec.tools {
+            assert!(tools.iter().any(|t| t == tool), "{name} declares unknown tool {tool}");
+            assert!(referenced.iter().any(|t| t == tool), "{name} never mentions {tool}");
+        }
+    }
+    drop(server);
+}
+
+#[test]
+fn test_prompt_argument_validation() {
+    let text = prompt_text(
+        &render_prompt("search_and_summarize", Some(&prompt_args(&[("query", "browsy")]))).unwrap(),
+    );
+    assert!(text.contains("top 3 results"), "n_results should default to 3: {text}");
+
+    assert!(render_prompt("login_to_site", None).is_err());
+    assert!(render_prompt("login_to_site", Some(&prompt_args(&[("url", "example.com")]))).is_err());
+    assert!(render_prompt(
+        "search_and_summarize",
+        Some(&prompt_args(&[("query", "x"), ("n_results", "0")]))
+    )
+    .is_err());
+    assert!(render_prompt(
+        "fill_form_from_json",
+        Some(&prompt_args(&[("url", "https://example.com"), ("values_json", "[1,2]")]))
+    )
+    .is_err());
+    let err = render_prompt("nope", None).unwrap_err();
+    assert!(err.message.contains("Unknown prompt"));
+}
+
+#[test]
+fn test_held_click_returns_confirmation_token() {
+    let html = r#"<html><body><a href="https://elsewhere.org/">Elsewhere</a></body></html>"#;
+    let config = SessionConfig {
+        action_policy: Some(browsy_core::fetch::ActionPolicy {
+            allowed_domains: vec!["example.com".to_string()],
+            ..Default::default()
+        }),
+        ..make_config()
+    };
+    let mut session = Session::with_config(config).unwrap();
+    let link = session.load_html(html, "https://example.com").unwrap().find_by_text("Elsewhere")[0].id;
+    let server = BrowsyServer::with_session(session);
+
+    let ((held, unknown), _server) = run_async(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let results = rt.block_on(async {
+            let held = server.click(Parameters(ClickParams { id: link, confirmed: None })).await;
+            let unknown = server
+                .confirm_action(Parameters(ConfirmActionParams { token: "nope".into() }))
+                .await;
+            (held, unknown)
+        });
+        drop(rt);
+        (results, server)
+    });
+
+    let held = held.unwrap_err();
+    assert!(held.message.contains("call confirm_action"), "{}", held.message);
+    let data = held.data.unwrap();
+    assert_eq!(data["code"], "confirmation_required");
+    assert!(data["details"]["token"].as_str().is_some_and(|t| !t.is_empty()), "{data}");
+    assert_eq!(data["details"]["description"], "Navigate to https://elsewhere.org/, outside the allowed domains");
+    assert_eq!(unknown.unwrap_err().data.unwrap()["code"], "action_failed");
+}
+
+#[test]
+fn test_annotate_shows_note_in_page() {
+    let html = r#"<html><body><a href="/a">First result</a><a href="/b">Second result</a></body></html>"#;
+    let mut session = Session::with_config(make_config()).unwrap();
+    let first = session.load_html(html, "https://example.com").unwrap().find_by_text("First result")[0].id;
+    let server = BrowsyServer::with_session(session);
+
+    let ((annotated, page), _server) = run_async(move || {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let results = rt.block_on(async {
+            let annotated = server
+                .annotate(Parameters(AnnotateParams { id: first, note: "already read".into() }))
+                .await
+                .unwrap();
+            let page = server.get_page(Parameters(GetPageParams { format: None, scope: None })).await.unwrap();
+            (extract_text(annotated), extract_text(page))
+        });
+        drop(rt);
+        (results, server)
+    });
+
+    assert_eq!(annotated, format!("Annotated element {first}"));
+    let line = page.lines().find(|l| l.contains("First result")).unwrap();
+    assert!(line.contains(r#"#"already read""#), "{page}");
+    assert!(!page.lines().any(|l| l.contains("Second result") && l.contains('#')), "{page}");
 }
@@ -1,2 +1,2 @@
-from browsy._core import Browser, Page, Element
-__all__ = ["Browser", "Page", "Element"]
+from browsy._core import Browser, BrowsyError, Page, Element, parse
+__all__ = ["Browser", "BrowsyError", "Page", "Element", "parse"]
@@ -0,0 +1,7 @@
+"""asyncio interface to browsy: every Browser method returns an awaitable."""
+from browsy._core import aio as _aio
+
+AsyncBrowser = _aio.AsyncBrowser
+Browser = AsyncBrowser
+
+__all__ = ["AsyncBrowser", "Browser"]
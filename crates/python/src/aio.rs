@@ -0,0 +1,267 @@
+//! asyncio bindings: `AsyncBrowser` runs its `Session` on a dedicated worker
+//! thread and returns awaitables that resolve on the caller's event loop.
+
+use std::sync::mpsc;
+use std::thread;
+
+use pyo3::conversion::IntoPyObjectExt;
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+
+use browsy_core::fetch::{FetchError, Session, SessionConfig};
+
+use crate::{convert_err, json_to_py, recording_mode, Element, Page};
+
+type Job = Box<dyn FnOnce(&mut Session) + Send>;
+
+/// Complete `fut` from the event loop thread unless it was cancelled meanwhile.
+#[pyfunction]
+fn _resolve_future(fut: &Bound<'_, PyAny>, value: PyObject, is_error: bool) -> PyResult<()> {
+    if fut.call_method0("cancelled")?.is_truthy()? {
+        return Ok(());
+    }
+    let method = if is_error { "set_exception" } else { "set_result" };
+    fut.call_method1(method, (value,))?;
+    Ok(())
+}
+
+/// A browsing session whose methods return awaitables.
+///
+/// Calls on one AsyncBrowser run one at a time, in the order they were made.
+#[pyclass(module = "browsy._core.aio")]
+struct AsyncBrowser {
+    jobs: Option<mpsc::Sender<Job>>,
+}
+
+impl AsyncBrowser {
+    /// Queue `work` on the worker thread and return an asyncio future for its result.
+    fn submit<T, W, C>(&self, py: Python<'_>, work: W, convert: C) -> PyResult<PyObject>
+    where
+        T: Send + 'static,
+        W: FnOnce(&mut Session) -> Result<T, FetchError> + Send + 'static,
+        C: FnOnce(Python<'_>, T) -> PyResult<PyObject> + Send + 'static,
+    {
+        let jobs = self.jobs.as_ref().ok_or_else(closed_err)?;
+        let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+        let fut = event_loop.call_method0("create_future")?;
+        let resolve = wrap_pyfunction!(_resolve_future, py)?.unbind();
+        let (event_loop, handle) = (event_loop.unbind(), fut.clone().unbind());
+
+        let job: Job = Box::new(move |session| {
+            let result = work(session);
+            Python::with_gil(|py| {
+                let (value, is_error) = match result.map_err(convert_err).and_then(|v| convert(py, v)) {
+                    Ok(value) => (value, false),
+                    Err(e) => (e.into_value(py).into_any(), true),
+                };
+                // Fails only if the loop is already closed; nobody is waiting then.
+                let _ = event_loop.call_method1(py, "call_soon_threadsafe", (resolve, handle, value, is_error));
+            });
+        });
+        jobs.send(job).map_err(|_| closed_err())?;
+        Ok(fut.unbind())
+    }
+
+    /// Like `submit`, for results that convert to Python directly.
+    fn call<T, W>(&self, py: Python<'_>, work: W) -> PyResult<PyObject>
+    where
+        T: for<'py> IntoPyObject<'py> + Send + 'static,
+        W: FnOnce(&mut Session) -> Result<T, FetchError> + Send + 'static,
+    {
+        self.submit(py, work, |py, value| value.into_py_any(py))
+    }
+}
+
+fn closed_err() -> PyErr {
+    PyRuntimeError::new_err("AsyncBrowser is closed")
+}
+
+/// An already-completed future, for `__aenter__`/`__aexit__`.
+fn ready(py: Python<'_>, value: PyObject) -> PyResult<PyObject> {
+    let fut = py
+        .import("asyncio")?
+        .call_method0("get_running_loop")?
+        .call_method0("create_future")?;
+    fut.call_method1("set_result", (value,))?;
+    Ok(fut.unbind())
+}
+
+#[pymethods]
+impl AsyncBrowser {
+    #[new]
+    #[pyo3(signature = (viewport_width=1920, viewport_height=1080, emit_selectors=false, allow_private_network=false, record=None, replay=None, allowed_domains=None, blocked_domains=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        viewport_width: u32,
+        viewport_height: u32,
+        emit_selectors: bool,
+        allow_private_network: bool,
+        record: Option<String>,
+        replay: Option<String>,
+        allowed_domains: Option<Vec<String>>,
+        blocked_domains: Option<Vec<String>>,
+    ) -> PyResult<Self> {
+        let recording = recording_mode(record, replay)?;
+        let (jobs, queue) = mpsc::channel::<Job>();
+        let (ready_tx, ready_rx) = mpsc::sync_channel(1);
+        // The Session is created, used and dropped on the worker thread only.
+        thread::Builder::new()
+            .name("browsy-aio".to_string())
+            .spawn(move || {
+                let config = SessionConfig {
+                    viewport_width: viewport_width as f32,
+                    viewport_height: viewport_height as f32,
+                    emit_selectors,
+                    allow_private_network,
+                    recording,
+                    allowed_domains,
+                    blocked_domains: blocked_domains.unwrap_or_default(),
+                    ..SessionConfig::default()
+                };
+                let mut session = match Session::with_config(config) {
+                    Ok(session) => {
+                        let _ = ready_tx.send(Ok(()));
+                        session
+                    }
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                        return;
+                    }
+                };
+                for job in queue {
+                    job(&mut session);
+                }
+            })
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        ready_rx
+            .recv()
+            .map_err(|_| PyRuntimeError::new_err("browsy worker thread exited"))?
+            .map_err(convert_err)?;
+        Ok(AsyncBrowser { jobs: Some(jobs) })
+    }
+
+    fn goto(&self, py: Python<'_>, url: String) -> PyResult<PyObject> {
+        self.call(py, move |s| s.goto(&url).map(|dom| Page { inner: dom }))
+    }
+
+    fn click(&self, py: Python<'_>, id: u32) -> PyResult<PyObject> {
+        self.call(py, move |s| s.click(id).map(|dom| Page { inner: dom }))
+    }
+
+    fn press_enter(&self, py: Python<'_>, id: u32) -> PyResult<PyObject> {
+        self.call(py, move |s| s.press_enter(id).map(|dom| Page { inner: dom }))
+    }
+
+    fn type_text(&self, py: Python<'_>, id: u32, text: String) -> PyResult<PyObject> {
+        self.call(py, move |s| {
+            s.type_text(id, &text)?;
+            Ok(s.suggestion_warning(id, &text))
+        })
+    }
+
+    fn check(&self, py: Python<'_>, id: u32) -> PyResult<PyObject> {
+        self.call(py, move |s| s.check(id))
+    }
+
+    fn uncheck(&self, py: Python<'_>, id: u32) -> PyResult<PyObject> {
+        self.call(py, move |s| s.uncheck(id))
+    }
+
+    fn select(&self, py: Python<'_>, id: u32, value: String) -> PyResult<PyObject> {
+        self.call(py, move |s| s.select(id, &value))
+    }
+
+    fn back(&self, py: Python<'_>) -> PyResult<PyObject> {
+        self.call(py, |s| s.back().map(|dom| Page { inner: dom }))
+    }
+
+    fn dom(&self, py: Python<'_>) -> PyResult<PyObject> {
+        self.call(py, |s| Ok(s.dom().map(|dom| Page { inner: dom })))
+    }
+
+    fn load_html(&self, py: Python<'_>, html: String, url: String) -> PyResult<PyObject> {
+        self.call(py, move |s| s.load_html(&html, &url).map(|dom| Page { inner: dom }))
+    }
+
+    fn login(&self, py: Python<'_>, username: String, password: String) -> PyResult<PyObject> {
+        self.call(py, move |s| s.login(&username, &password).map(|o| Page { inner: o.dom }))
+    }
+
+    fn enter_code(&self, py: Python<'_>, code: String) -> PyResult<PyObject> {
+        self.call(py, move |s| s.enter_code(&code).map(|dom| Page { inner: dom }))
+    }
+
+    fn find_by_text(&self, py: Python<'_>, text: String) -> PyResult<PyObject> {
+        self.call(py, move |s| {
+            Ok(s.find_by_text(&text).into_iter().map(|e| Element { inner: e.clone() }).collect::<Vec<_>>())
+        })
+    }
+
+    fn find_by_role(&self, py: Python<'_>, role: String) -> PyResult<PyObject> {
+        self.call(py, move |s| {
+            Ok(s.find_by_role(&role).into_iter().map(|e| Element { inner: e.clone() }).collect::<Vec<_>>())
+        })
+    }
+
+    fn search(&self, py: Python<'_>, query: String) -> PyResult<PyObject> {
+        self.submit(py, move |s| s.search(&query), |py, results| {
+            let items = results
+                .into_iter()
+                .map(|r| {
+                    let dict = pyo3::types::PyDict::new(py);
+                    dict.set_item("title", &r.title)?;
+                    dict.set_item("url", &r.url)?;
+                    dict.set_item("snippet", &r.snippet)?;
+                    dict.set_item("raw_url", &r.raw_url)?;
+                    Ok(dict.into_any().unbind())
+                })
+                .collect::<PyResult<Vec<PyObject>>>()?;
+            items.into_py_any(py)
+        })
+    }
+
+    fn discover_feeds(&self, py: Python<'_>) -> PyResult<PyObject> {
+        self.submit(py, |s| s.discover_feeds(), |py, feeds| {
+            Ok(json_to_py(py, serde_json::to_value(feeds).unwrap()))
+        })
+    }
+
+    fn fetch_sitemap(&self, py: Python<'_>, url: String) -> PyResult<PyObject> {
+        self.submit(py, move |s| s.fetch_sitemap(&url), |py, entries| {
+            Ok(json_to_py(py, serde_json::to_value(entries).unwrap()))
+        })
+    }
+
+    /// Stop accepting calls. Calls already queued still complete.
+    fn close(&mut self) {
+        self.jobs = None;
+    }
+
+    #[getter]
+    fn closed(&self) -> bool {
+        self.jobs.is_none()
+    }
+
+    fn __aenter__(slf: Bound<'_, Self>) -> PyResult<PyObject> {
+        ready(slf.py(), slf.clone().into_any().unbind())
+    }
+
+    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
+    fn __aexit__(
+        &mut self,
+        py: Python<'_>,
+        _exc_type: Option<PyObject>,
+        _exc_value: Option<PyObject>,
+        _traceback: Option<PyObject>,
+    ) -> PyResult<PyObject> {
+        self.close();
+        ready(py, false.into_py_any(py)?)
+    }
+}
+
+/// Build the `aio` submodule.
+pub(crate) fn module(py: Python<'_>) -> PyResult<Bound<'_, PyModule>> {
+    let m = PyModule::new(py, "aio")?;
+    m.add_class::<AsyncBrowser>()?;
+    Ok(m)
+}
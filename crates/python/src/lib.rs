@@ -1,13 +1,39 @@
 //! Python bindings for browsy via PyO3.
 
 use pyo3::prelude::*;
-use pyo3::exceptions::PyRuntimeError;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
 
-use browsy_core::fetch::{InputPurpose, Session, SessionConfig};
+use browsy_core::fetch::{ActionPolicy, InputPurpose, RecordingMode, Session, SessionConfig};
 use browsy_core::output::{self, SpatialDom, SpatialElement as CoreElement};
 
+mod aio;
+
+pyo3::create_exception!(
+    _core,
+    BrowsyError,
+    PyRuntimeError,
+    "A browsing error. `code` says what kind (`no_page_loaded`, `action_element_not_found`, ...) and `details` holds its particulars, or None."
+);
+
 fn convert_err(e: browsy_core::fetch::FetchError) -> PyErr {
-    PyRuntimeError::new_err(e.to_string())
+    let err = BrowsyError::new_err(e.to_string());
+    Python::with_gil(|py| {
+        let value = err.value(py);
+        let details = e.details().map(|d| json_to_py(py, d)).unwrap_or_else(|| py.None());
+        let _ = value.setattr("code", e.code().as_str());
+        let _ = value.setattr("details", details);
+    });
+    err
+}
+
+/// The recording mode for the `record=` / `replay=` constructor arguments.
+fn recording_mode(record: Option<String>, replay: Option<String>) -> PyResult<Option<RecordingMode>> {
+    match (record, replay) {
+        (Some(_), Some(_)) => Err(PyValueError::new_err("record and replay are mutually exclusive")),
+        (Some(dir), None) => Ok(Some(RecordingMode::Record(dir.into()))),
+        (None, Some(dir)) => Ok(Some(RecordingMode::Replay(dir.into()))),
+        (None, None) => Ok(None),
+    }
 }
 
 fn json_to_py(py: Python<'_>, val: serde_json::Value) -> PyObject {
@@ -106,6 +132,45 @@ impl Element {
         self.inner.disabled
     }
 
+    #[getter]
+    fn readonly(&self) -> Option<bool> {
+        self.inner.readonly
+    }
+
+    /// Where `text` came from: `Visible`, `AriaLabel`, `Title`, `ImgAlt` or
+    /// `SvgTitle`.
+    #[getter]
+    fn level(&self) -> Option<u8> {
+        self.inner.level
+    }
+
+    #[getter]
+    fn text_source(&self) -> Option<String> {
+        self.inner.text_source.map(|source| format!("{:?}", source))
+    }
+
+    #[getter]
+    fn autocomplete(&self) -> Option<&str> {
+        self.inner.autocomplete.as_deref()
+    }
+
+    /// What the input is for, such as "email" or "credit_card_number".
+    #[getter]
+    fn purpose(&self) -> Option<&str> {
+        self.inner.purpose.as_deref()
+    }
+
+    /// The field's help text, from `aria-describedby` or the line under it.
+    #[getter]
+    fn description(&self) -> Option<String> {
+        self.inner.desc.clone()
+    }
+
+    #[getter]
+    fn suggestions(&self) -> Option<Vec<String>> {
+        self.inner.suggestions.clone()
+    }
+
     #[getter]
     fn checked(&self) -> Option<bool> {
         self.inner.checked
@@ -131,6 +196,16 @@ impl Element {
         self.inner.hidden
     }
 
+    #[getter]
+    fn hidden_reason(&self) -> Option<String> {
+        self.inner.hidden_reason.map(|reason| format!("{:?}", reason))
+    }
+
+    #[getter]
+    fn selector(&self) -> Option<&str> {
+        self.inner.selector.as_deref()
+    }
+
     #[getter]
     fn bounds(&self) -> (i32, i32, i32, i32) {
         (self.inner.b[0], self.inner.b[1], self.inner.b[2], self.inner.b[3])
@@ -195,6 +270,15 @@ impl Page {
         format!("{:?}", self.inner.page_type)
     }
 
+    /// Element counts as a dict: `counts_by_role`, `hidden_count`,
+    /// `form_count`, `table_count`, `link_count` and `interactive_count`.
+    fn summary(&self) -> PyObject {
+        Python::with_gil(|py| {
+            let val = serde_json::to_value(self.inner.summary()).unwrap();
+            json_to_py(py, val)
+        })
+    }
+
     fn pagination(&self) -> Option<PyObject> {
         let p = self.inner.pagination()?;
         Python::with_gil(|py| {
@@ -207,10 +291,102 @@ impl Page {
         })
     }
 
+    /// Breadcrumb trail as dicts with `text`, `href` and `id`, or None.
+    fn breadcrumbs(&self) -> Option<Vec<PyObject>> {
+        let crumbs = self.inner.breadcrumbs()?;
+        Python::with_gil(|py| {
+            Some(crumbs.into_iter().map(|c| {
+                let dict = pyo3::types::PyDict::new(py);
+                dict.set_item("text", &c.text).unwrap();
+                dict.set_item("href", &c.href).unwrap();
+                dict.set_item("id", c.id).unwrap();
+                dict.into_any().unbind()
+            }).collect())
+        })
+    }
+
+    /// Whether the page looks signed in, as a dict: `state` is `logged_in`,
+    /// `logged_out` or `unknown`, and a signed-in page may add the
+    /// `account_hint` it shows (a name or email address).
+    fn auth_state(&self) -> PyObject {
+        Python::with_gil(|py| {
+            let val = serde_json::to_value(self.inner.auth_state()).unwrap();
+            json_to_py(py, val)
+        })
+    }
+
+    /// Article metadata as a dict with `published_at`, `updated_at`,
+    /// `author` and `relative_date`, or None.
+    fn meta(&self) -> Option<PyObject> {
+        let meta = self.inner.article.as_ref()?;
+        Python::with_gil(|py| {
+            let dict = pyo3::types::PyDict::new(py);
+            dict.set_item("published_at", &meta.published_at).unwrap();
+            dict.set_item("updated_at", &meta.updated_at).unwrap();
+            dict.set_item("author", &meta.author).unwrap();
+            dict.set_item("relative_date", &meta.relative_date).unwrap();
+            Some(dict.into_any().unbind())
+        })
+    }
+
     fn alerts(&self) -> Vec<Element> {
         self.inner.alerts().into_iter().map(|e| Element { inner: e.clone() }).collect()
     }
 
+    fn find_by_text(&self, text: &str) -> Vec<Element> {
+        self.inner.find_by_text(text).into_iter().map(|e| Element { inner: e.clone() }).collect()
+    }
+
+    fn find_by_role(&self, role: &str) -> Vec<Element> {
+        self.inner.find_by_role(role).into_iter().map(|e| Element { inner: e.clone() }).collect()
+    }
+
+    fn get_by_label(&self, label: &str) -> Option<Element> {
+        self.inner.get_by_label(label).map(|e| Element { inner: e.clone() })
+    }
+
+    /// Visible elements within `radius` pixels of element `id`, nearest first.
+    /// Elements holding it, like its card, are left out.
+    #[pyo3(signature = (id, radius=50))]
+    fn neighbors(&self, id: u32, radius: i32) -> Vec<Element> {
+        self.inner.neighbors(id, radius).into_iter().map(|e| Element { inner: e.clone() }).collect()
+    }
+
+    /// The elements on element `id`'s row within its container, left to right.
+    fn row_of(&self, id: u32) -> Vec<Element> {
+        self.inner.row_of(id).into_iter().map(|e| Element { inner: e.clone() }).collect()
+    }
+
+    /// The nearest element left of element `id` on its row, or None.
+    fn left_of(&self, id: u32) -> Option<Element> {
+        self.inner.left_of(id).map(|e| Element { inner: e.clone() })
+    }
+
+    /// The nearest element above element `id` that overlaps it horizontally, or None.
+    fn above(&self, id: u32) -> Option<Element> {
+        self.inner.above(id).map(|e| Element { inner: e.clone() })
+    }
+
+    /// The landmark or card holding element `id`, or None.
+    fn container_of(&self, id: u32) -> Option<Element> {
+        self.inner.container_of(id).map(|e| Element { inner: e.clone() })
+    }
+
+    fn forms(&self) -> Vec<PyObject> {
+        Python::with_gil(|py| {
+            self.inner.forms().into_iter().map(|f| {
+                let val = serde_json::to_value(f).unwrap();
+                json_to_py(py, val)
+            }).collect()
+        })
+    }
+
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Page> {
+        let dom = SpatialDom::from_json(json).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(Page { inner: dom })
+    }
+
     fn to_json(&self) -> PyResult<String> {
         serde_json::to_string(&self.inner)
             .map_err(|e| PyRuntimeError::new_err(e.to_string()))
@@ -240,105 +416,197 @@ impl Page {
 
 // --- Browser ---
 
+/// A browsing session. The inner `Session` is dropped by `close()` (or when the
+/// Browser is garbage-collected); after that every method raises RuntimeError.
 #[pyclass]
 struct Browser {
-    session: Session,
+    session: Option<Session>,
+}
+
+impl Browser {
+    fn session(&self) -> PyResult<&Session> {
+        self.session.as_ref().ok_or_else(closed_err)
+    }
+
+    fn session_mut(&mut self) -> PyResult<&mut Session> {
+        self.session.as_mut().ok_or_else(closed_err)
+    }
+}
+
+fn closed_err() -> PyErr {
+    PyRuntimeError::new_err("Browser is closed")
 }
 
 #[pymethods]
 impl Browser {
     #[new]
-    #[pyo3(signature = (viewport_width=1920, viewport_height=1080))]
-    fn new(viewport_width: u32, viewport_height: u32) -> PyResult<Self> {
+    /// `confirm_submit` (words in a submit button's text) and
+    /// `confirm_outside` (domains) turn on the action policy: matching clicks
+    /// and submissions raise `confirmation_required` until `confirm(token)`.
+    #[pyo3(signature = (viewport_width=1920, viewport_height=1080, emit_selectors=false, record=None, replay=None, allowed_domains=None, blocked_domains=None, confirm_submit=None, confirm_outside=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        viewport_width: u32,
+        viewport_height: u32,
+        emit_selectors: bool,
+        record: Option<String>,
+        replay: Option<String>,
+        allowed_domains: Option<Vec<String>>,
+        blocked_domains: Option<Vec<String>>,
+        confirm_submit: Option<Vec<String>>,
+        confirm_outside: Option<Vec<String>>,
+    ) -> PyResult<Self> {
+        let action_policy = (confirm_submit.is_some() || confirm_outside.is_some()).then(|| {
+            let defaults = ActionPolicy::default();
+            ActionPolicy {
+                submit_patterns: confirm_submit.unwrap_or(defaults.submit_patterns),
+                allowed_domains: confirm_outside.unwrap_or_default(),
+                ..defaults
+            }
+        });
         let config = SessionConfig {
             viewport_width: viewport_width as f32,
             viewport_height: viewport_height as f32,
+            emit_selectors,
+            recording: recording_mode(record, replay)?,
+            allowed_domains,
+            blocked_domains: blocked_domains.unwrap_or_default(),
+            action_policy,
             ..SessionConfig::default()
         };
         let session = Session::with_config(config).map_err(convert_err)?;
-        Ok(Browser { session })
+        Ok(Browser { session: Some(session) })
     }
 
     fn goto(&mut self, url: &str) -> PyResult<Page> {
-        let dom = self.session.goto(url).map_err(convert_err)?;
+        let dom = self.session_mut()?.goto(url).map_err(convert_err)?;
         Ok(Page { inner: dom })
     }
 
-    fn click(&mut self, id: u32) -> PyResult<Page> {
-        let dom = self.session.click(id).map_err(convert_err)?;
+    /// With `confirmed=True` the action policy lets the click through.
+    #[pyo3(signature = (id, confirmed=false))]
+    fn click(&mut self, id: u32, confirmed: bool) -> PyResult<Page> {
+        let session = self.session_mut()?;
+        let dom = if confirmed { session.confirmed(|s| s.click(id)) } else { session.click(id) };
+        Ok(Page { inner: dom.map_err(convert_err)? })
+    }
+
+    /// Submits the input's form, even when it has no submit button.
+    #[pyo3(signature = (id, confirmed=false))]
+    fn press_enter(&mut self, id: u32, confirmed: bool) -> PyResult<Page> {
+        let session = self.session_mut()?;
+        let dom = if confirmed { session.confirmed(|s| s.press_enter(id)) } else { session.press_enter(id) };
+        Ok(Page { inner: dom.map_err(convert_err)? })
+    }
+
+    /// Take the action held with `token`, from the `details` of a
+    /// `confirmation_required` error.
+    fn confirm(&mut self, token: &str) -> PyResult<Page> {
+        let dom = self.session_mut()?.confirm(token).map_err(convert_err)?;
         Ok(Page { inner: dom })
     }
 
-    fn type_text(&mut self, id: u32, text: &str) -> PyResult<()> {
-        self.session.type_text(id, text).map_err(convert_err)
+    /// What `click(id)` would do, or with `submit=True` what `press_enter`
+    /// or a submit button would send, as a dict with a `kind` of `navigate`,
+    /// `behavior`, `submit` or `nothing`. Nothing is sent.
+    #[pyo3(signature = (id, submit=false))]
+    fn preview(&self, id: u32, submit: bool) -> PyResult<PyObject> {
+        let session = self.session()?;
+        let preview = if submit { session.preview_submit(id) } else { session.preview_click(id) };
+        let preview = preview.map_err(convert_err)?;
+        Python::with_gil(|py| Ok(json_to_py(py, serde_json::to_value(preview).unwrap())))
+    }
+
+    /// Returns a warning when `text` isn't one of the field's suggestions.
+    fn type_text(&mut self, id: u32, text: &str) -> PyResult<Option<String>> {
+        let session = self.session_mut()?;
+        session.type_text(id, text).map_err(convert_err)?;
+        Ok(session.suggestion_warning(id, text))
     }
 
     fn check(&mut self, id: u32) -> PyResult<()> {
-        self.session.check(id).map_err(convert_err)
+        self.session_mut()?.check(id).map_err(convert_err)
     }
 
     fn uncheck(&mut self, id: u32) -> PyResult<()> {
-        self.session.uncheck(id).map_err(convert_err)
+        self.session_mut()?.uncheck(id).map_err(convert_err)
     }
 
     fn select(&mut self, id: u32, value: &str) -> PyResult<()> {
-        self.session.select(id, value).map_err(convert_err)
+        self.session_mut()?.select(id, value).map_err(convert_err)
     }
 
     fn back(&mut self) -> PyResult<Page> {
-        let dom = self.session.back().map_err(convert_err)?;
+        let dom = self.session_mut()?.back().map_err(convert_err)?;
         Ok(Page { inner: dom })
     }
 
-    fn dom(&self) -> Option<Page> {
-        self.session.dom().map(|d| Page { inner: d })
+    fn dom(&self) -> PyResult<Option<Page>> {
+        Ok(self.session()?.dom().map(|d| Page { inner: d }))
     }
 
     fn search(&mut self, query: &str) -> PyResult<Vec<PyObject>> {
-        let results = self.session.search(query).map_err(convert_err)?;
+        let results = self.session_mut()?.search(query).map_err(convert_err)?;
         Python::with_gil(|py| {
             Ok(results.into_iter().map(|r| {
                 let dict = pyo3::types::PyDict::new(py);
                 dict.set_item("title", &r.title).unwrap();
                 dict.set_item("url", &r.url).unwrap();
                 dict.set_item("snippet", &r.snippet).unwrap();
+                dict.set_item("raw_url", &r.raw_url).unwrap();
                 dict.into_any().unbind()
             }).collect())
         })
     }
 
-    fn find_by_text(&self, text: &str) -> Vec<Element> {
-        self.session.find_by_text(text)
+    /// Feeds and sitemaps of the current page's site, as a dict with
+    /// `sitemaps` (URLs) and `feeds` (dicts with `href`, `kind`, `title`).
+    fn discover_feeds(&mut self) -> PyResult<PyObject> {
+        let feeds = self.session_mut()?.discover_feeds().map_err(convert_err)?;
+        Python::with_gil(|py| Ok(json_to_py(py, serde_json::to_value(feeds).unwrap())))
+    }
+
+    /// Pages a sitemap lists, as dicts with `loc` and `lastmod`, following a
+    /// sitemap index into its sitemaps.
+    fn fetch_sitemap(&mut self, url: &str) -> PyResult<Vec<PyObject>> {
+        let entries = self.session_mut()?.fetch_sitemap(url).map_err(convert_err)?;
+        Python::with_gil(|py| {
+            Ok(entries.into_iter().map(|e| json_to_py(py, serde_json::to_value(e).unwrap())).collect())
+        })
+    }
+
+    fn find_by_text(&self, text: &str) -> PyResult<Vec<Element>> {
+        Ok(self.session()?.find_by_text(text)
             .into_iter()
             .map(|e| Element { inner: e.clone() })
-            .collect()
+            .collect())
     }
 
-    fn find_by_role(&self, role: &str) -> Vec<Element> {
-        self.session.find_by_role(role)
+    fn find_by_role(&self, role: &str) -> PyResult<Vec<Element>> {
+        Ok(self.session()?.find_by_role(role)
             .into_iter()
             .map(|e| Element { inner: e.clone() })
-            .collect()
+            .collect())
     }
 
     fn login(&mut self, username: &str, password: &str) -> PyResult<Page> {
-        let dom = self.session.login(username, password).map_err(convert_err)?;
-        Ok(Page { inner: dom })
+        let outcome = self.session_mut()?.login(username, password).map_err(convert_err)?;
+        Ok(Page { inner: outcome.dom })
     }
 
     fn enter_code(&mut self, code: &str) -> PyResult<Page> {
-        let dom = self.session.enter_code(code).map_err(convert_err)?;
+        let dom = self.session_mut()?.enter_code(code).map_err(convert_err)?;
         Ok(Page { inner: dom })
     }
 
-    fn find_by_text_fuzzy(&self, text: &str) -> Vec<Element> {
-        self.session.find_by_text_fuzzy(text)
+    fn find_by_text_fuzzy(&self, text: &str) -> PyResult<Vec<Element>> {
+        Ok(self.session()?.find_by_text_fuzzy(text)
             .into_iter()
             .map(|e| Element { inner: e.clone() })
-            .collect()
+            .collect())
     }
 
-    fn find_input_by_purpose(&self, purpose: &str) -> Option<Element> {
+    fn find_input_by_purpose(&self, purpose: &str) -> PyResult<Option<Element>> {
         let p = match purpose.to_lowercase().as_str() {
             "password" => InputPurpose::Password,
             "email" => InputPurpose::Email,
@@ -346,27 +614,109 @@ impl Browser {
             "verification_code" | "code" | "otp" => InputPurpose::VerificationCode,
             "search" => InputPurpose::Search,
             "phone" | "tel" => InputPurpose::Phone,
-            _ => return None,
+            "address" => InputPurpose::Address,
+            "credit_card_number" | "card" => InputPurpose::CreditCardNumber,
+            _ => return Ok(None),
         };
-        self.session.find_input_by_purpose(p).map(|e| Element { inner: e.clone() })
+        Ok(self.session()?.find_input_by_purpose(p).map(|e| Element { inner: e.clone() }))
     }
 
-    fn find_verification_code(&self) -> Option<String> {
-        self.session.find_verification_code()
+    fn find_verification_code(&self) -> PyResult<Option<String>> {
+        Ok(self.session()?.find_verification_code())
     }
 
     fn load_html(&mut self, html: &str, url: &str) -> PyResult<Page> {
-        let dom = self.session.load_html(html, url).map_err(convert_err)?;
+        let dom = self.session_mut()?.load_html(html, url).map_err(convert_err)?;
         Ok(Page { inner: dom })
     }
+
+    /// A new Browser starting from this one's cookies, page, history and
+    /// form values, that can then diverge from it.
+    fn fork(&self) -> PyResult<Browser> {
+        let session = self.session()?.fork().map_err(convert_err)?;
+        Ok(Browser { session: Some(session) })
+    }
+
+    /// A new Browser with this one's settings and cookie jar but no page or
+    /// history, like another tab: cookies either one receives, both send.
+    fn new_tab(&self) -> PyResult<Browser> {
+        let session = self.session()?.new_tab().map_err(convert_err)?;
+        Ok(Browser { session: Some(session) })
+    }
+
+    /// Current URL, history length and cookie count, for debugging.
+    fn session_info(&self) -> PyResult<PyObject> {
+        let session = self.session()?;
+        Python::with_gil(|py| {
+            let dict = pyo3::types::PyDict::new(py);
+            dict.set_item("url", session.url())?;
+            dict.set_item("history_len", session.history_len())?;
+            dict.set_item("cookie_count", session.cookie_count())?;
+            Ok(dict.into_any().unbind())
+        })
+    }
+
+    /// Actions taken in this session as dicts with `seq`, `ts_ms`, `type`
+    /// and the action's fields, oldest first.
+    #[pyo3(signature = (since_seq=None))]
+    fn audit_log(&self, since_seq: Option<u64>) -> PyResult<Vec<PyObject>> {
+        let session = self.session()?;
+        let events = match since_seq {
+            Some(seq) => session.audit_log_since(seq),
+            None => session.audit_log(),
+        };
+        Python::with_gil(|py| {
+            Ok(events.into_iter().map(|e| {
+                let val = serde_json::to_value(e).unwrap();
+                json_to_py(py, val)
+            }).collect())
+        })
+    }
+
+    /// Drop the session and its connection pool. Calling it again is a no-op.
+    fn close(&mut self) {
+        self.session = None;
+    }
+
+    #[getter]
+    fn closed(&self) -> bool {
+        self.session.is_none()
+    }
+
+    fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
+    fn __exit__(
+        &mut self,
+        _exc_type: Option<PyObject>,
+        _exc_value: Option<PyObject>,
+        _traceback: Option<PyObject>,
+    ) -> bool {
+        self.close();
+        false
+    }
+}
+
+// --- Module functions ---
+
+/// Parse an HTML string into a Page without fetching anything.
+#[pyfunction]
+#[pyo3(signature = (html, viewport=(1920, 1080)))]
+fn parse(html: &str, viewport: (u32, u32)) -> Page {
+    Page { inner: browsy_core::parse(html, viewport.0 as f32, viewport.1 as f32) }
 }
 
 // --- Module ---
 
 #[pymodule]
 fn _core(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(parse, m)?)?;
     m.add_class::<Browser>()?;
     m.add_class::<Page>()?;
     m.add_class::<Element>()?;
+    m.add("BrowsyError", m.py().get_type::<BrowsyError>())?;
+    m.add_submodule(&aio::module(m.py())?)?;
     Ok(())
 }
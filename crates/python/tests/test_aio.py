@@ -0,0 +1,103 @@
+import asyncio
+import threading
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+
+import pytest
+
+from browsy.aio import AsyncBrowser, Browser
+
+
+PAGES = {
+    "/": "<html><head><title>Home</title></head><body>"
+         "<h1>Home</h1><a href=\"/next\">Next page</a></body></html>",
+    "/next": "<html><head><title>Next</title></head><body>"
+             "<h1>Next</h1><p>Arrived</p></body></html>",
+}
+
+
+class _Handler(BaseHTTPRequestHandler):
+    def do_GET(self):
+        body = PAGES.get(self.path)
+        if body is None:
+            self.send_error(404)
+            return
+        data = body.encode()
+        self.send_response(200)
+        self.send_header("Content-Type", "text/html; charset=utf-8")
+        self.send_header("Content-Length", str(len(data)))
+        self.end_headers()
+        self.wfile.write(data)
+
+    def log_message(self, *args):
+        pass
+
+
+@pytest.fixture(scope="module")
+def server_url():
+    server = ThreadingHTTPServer(("127.0.0.1", 0), _Handler)
+    thread = threading.Thread(target=server.serve_forever, daemon=True)
+    thread.start()
+    yield f"http://127.0.0.1:{server.server_address[1]}"
+    server.shutdown()
+
+
+def test_browser_alias():
+    assert Browser is AsyncBrowser
+
+
+async def _goto_and_click(browser, url):
+    page = await browser.goto(url + "/")
+    link = page.find_by_text("Next page")[0]
+    return await browser.click(link.id)
+
+
+@pytest.mark.asyncio
+async def test_concurrent_browsers(server_url):
+    async with AsyncBrowser(allow_private_network=True) as a, \
+            AsyncBrowser(allow_private_network=True) as b:
+        page_a, page_b = await asyncio.gather(
+            _goto_and_click(a, server_url),
+            _goto_and_click(b, server_url),
+        )
+        assert page_a.title == "Next"
+        assert page_b.title == "Next"
+        assert page_a.url.endswith("/next")
+    assert a.closed and b.closed
+
+
+@pytest.mark.asyncio
+async def test_calls_run_in_order(server_url):
+    async with AsyncBrowser(allow_private_network=True) as browser:
+        # Both calls are queued before either is awaited.
+        home, nxt = await asyncio.gather(
+            browser.goto(server_url + "/"),
+            browser.goto(server_url + "/next"),
+        )
+        assert (home.title, nxt.title) == ("Home", "Next")
+        current = await browser.dom()
+        assert current.title == "Next"
+        previous = await browser.back()
+        assert previous.title == "Home"
+
+
+@pytest.mark.asyncio
+async def test_errors_raise_runtime_error():
+    browser = AsyncBrowser()
+    with pytest.raises(RuntimeError):
+        await browser.click(999)
+    browser.close()
+    with pytest.raises(RuntimeError, match="closed"):
+        browser.dom()
+
+
+@pytest.mark.asyncio
+async def test_form_state_round_trip():
+    async with AsyncBrowser() as browser:
+        page = await browser.load_html(
+            '<html><body><input type="text" name="q" placeholder="Search"/></body></html>',
+            "https://example.com/",
+        )
+        field = next(e for e in page.elements if e.tag == "input")
+        await browser.type_text(field.id, "browsy")
+        current = await browser.dom()
+        assert current.get(field.id).value == "browsy"
@@ -1,6 +1,6 @@
 import json
 import pytest
-from browsy import Browser
+from browsy import Browser, BrowsyError, Page, parse
 
 
 @pytest.fixture
@@ -93,6 +93,20 @@ HIDDEN_HTML = """
 """
 
 
+def replay_dir(tmp_path, url, body):
+    """Record `body` as the page served at `url` and return the directory to replay it from."""
+    exchange = {
+        "method": "GET",
+        "url": url,
+        "status": 200,
+        "final_url": url,
+        "headers": [["content-type", "text/html"]],
+        "body": body,
+    }
+    (tmp_path / "0000-get-example.com.json").write_text(json.dumps(exchange))
+    return str(tmp_path)
+
+
 def test_load_html(browser):
     page = browser.load_html(SIMPLE_HTML, "https://example.com")
     assert page.title == "Test Page"
@@ -230,6 +244,14 @@ def test_tables(browser):
     assert any("Alice" in row for row in table["rows"])
 
 
+def test_summary(browser):
+    summary = browser.load_html(HIDDEN_HTML, "https://example.com").summary()
+    assert summary["link_count"] == 1
+    assert summary["hidden_count"] == 1
+    assert summary["counts_by_role"]["link"] == 1
+    assert browser.load_html(TABLE_HTML, "https://example.com/table").summary()["table_count"] == 1
+
+
 def test_visible(browser):
     page = browser.load_html(HIDDEN_HTML, "https://example.com")
     all_els = page.elements
@@ -254,3 +276,281 @@ def test_repr(browser):
     if elements:
         el_repr = repr(elements[0])
         assert "Element" in el_repr
+
+
+def test_selectors_opt_in(browser):
+    page = browser.load_html(FORM_HTML, "https://example.com/form")
+    assert all(e.selector is None for e in page.elements)
+
+    b = Browser(emit_selectors=True)
+    page = b.load_html(FORM_HTML, "https://example.com/form")
+    email = [e for e in page.elements if e.input_type == "email"][0]
+    assert email.selector is not None
+
+
+def test_page_find_by_text():
+    page = parse(SIMPLE_HTML)
+    found = page.find_by_text("About")
+    assert len(found) == 1
+    assert found[0].href == "/about"
+    assert page.find_by_text("Nope") == []
+
+
+def test_page_find_by_role(browser):
+    page = browser.load_html(FORM_HTML, "https://example.com/form")
+    checkboxes = page.find_by_role("checkbox")
+    assert checkboxes
+    assert all(e.role == "checkbox" for e in checkboxes)
+
+
+def test_page_get_by_label(browser):
+    page = browser.load_html(FORM_HTML, "https://example.com/form")
+    email = page.get_by_label("email")
+    assert email is not None
+    assert email.name == "email"
+    assert page.get_by_label("  PASSWORD ").name == "password"
+    assert page.get_by_label("Phone number") is None
+
+
+CARD_HTML = """<html><body><main>
+    <section style="display: flex; gap: 12px; width: 560px">
+        <h3 style="margin: 0; width: 200px">Bookshelf</h3>
+        <span style="width: 80px">$89.00</span>
+        <button>Add to cart</button>
+    </section>
+</main></body></html>"""
+
+
+def test_page_element_neighborhood():
+    page = parse(CARD_HTML)
+    price = [e for e in page.elements if e.text == "$89.00"][0]
+    assert [e.text for e in page.row_of(price.id)] == ["Bookshelf", "$89.00", "Add to cart"]
+    assert page.left_of(price.id).text == "Bookshelf"
+    assert page.container_of(price.id).tag == "section"
+    assert {e.text for e in page.neighbors(price.id, 20)} == {"Bookshelf", "Add to cart"}
+    assert page.above(price.id) is None
+    assert page.row_of(9999) == []
+
+
+def test_page_forms(browser):
+    page = browser.load_html(FORM_HTML, "https://example.com/form")
+    forms = page.forms()
+    assert len(forms) == 1
+    names = [f.get("name") for f in forms[0]["fields"]]
+    assert names == ["email", "password", "remember"]
+    sign_in = page.find_by_text("Sign In")[0]
+    assert forms[0]["submit_id"] == sign_in.id
+
+
+def test_helpers_work_on_from_json_pages(browser):
+    original = browser.load_html(FORM_HTML, "https://example.com/form")
+    page = Page.from_json(original.to_json())
+    assert page.get_by_label("Email").id == original.get_by_label("Email").id
+    assert len(page.forms()) == 1
+    with pytest.raises(ValueError):
+        Page.from_json("not json")
+
+
+BREADCRUMB_HTML = """
+<html>
+<head><title>Installing - Docs</title></head>
+<body>
+    <nav aria-label="Breadcrumb">
+        <ol style="display: flex">
+            <li><a href="/docs">Docs</a></li>
+            <li aria-current="page">Installing</li>
+        </ol>
+    </nav>
+    <h1>Installing</h1>
+</body>
+</html>
+"""
+
+
+def test_breadcrumbs(browser):
+    page = browser.load_html(BREADCRUMB_HTML, "https://example.com/docs/install")
+    crumbs = page.breadcrumbs()
+    assert [c["text"] for c in crumbs] == ["Docs", "Installing"]
+    assert crumbs[0]["href"] == "https://example.com/docs"
+    assert crumbs[1]["href"] is None
+    assert Page.from_json(page.to_json()).breadcrumbs() == crumbs
+    assert parse(SIMPLE_HTML).breadcrumbs() is None
+
+
+ARTICLE_HTML = """
+<html>
+<head>
+    <title>Bike lanes approved</title>
+    <meta property="article:published_time" content="2026-03-03T09:14:00Z">
+</head>
+<body>
+    <h1>Bike lanes approved</h1>
+    <p>By Priya Raman</p>
+</body>
+</html>
+"""
+
+
+def test_meta(browser):
+    page = browser.load_html(ARTICLE_HTML, "https://example.com/news/bike-lanes")
+    meta = page.meta()
+    assert meta["published_at"] == "2026-03-03T09:14:00Z"
+    assert meta["author"] == "Priya Raman"
+    assert meta["updated_at"] is None
+    assert parse(SIMPLE_HTML).meta() is None
+
+
+def test_module_parse():
+    page = parse(SIMPLE_HTML, viewport=(375, 812))
+    assert isinstance(page, Page)
+    assert page.title == "Test Page"
+    assert json.loads(page.to_json())["vp"] == [375.0, 812.0]
+
+
+def test_closed_browser_raises():
+    b = Browser()
+    b.load_html(SIMPLE_HTML, "https://example.com")
+    assert not b.closed
+    b.close()
+    assert b.closed
+    with pytest.raises(RuntimeError, match="closed"):
+        b.load_html(SIMPLE_HTML, "https://example.com")
+    with pytest.raises(RuntimeError, match="closed"):
+        b.find_by_text("About")
+
+
+def test_context_manager_closes():
+    with Browser() as b:
+        page = b.load_html(SIMPLE_HTML, "https://example.com")
+        assert page.title == "Test Page"
+    assert b.closed
+    with pytest.raises(RuntimeError):
+        b.dom()
+
+
+def test_double_close_is_noop():
+    b = Browser()
+    b.close()
+    b.close()
+    assert b.closed
+
+
+def test_session_info(browser):
+    info = browser.session_info()
+    assert info == {"url": None, "history_len": 0, "cookie_count": 0}
+    browser.load_html(SIMPLE_HTML, "https://example.com/")
+    # load_html doesn't navigate, so history is untouched.
+    assert browser.session_info()["history_len"] == 0
+
+
+def test_fork(browser):
+    browser.load_html(SIMPLE_HTML, "https://example.com")
+    fork = browser.fork()
+    assert fork.dom().title == "Test Page"
+    fork.load_html(TABLE_HTML, "https://example.com/table")
+    assert fork.dom().title == "Table"
+    assert browser.dom().title == "Test Page"
+
+
+def test_new_tab(browser):
+    browser.load_html(SIMPLE_HTML, "https://example.com")
+    tab = browser.new_tab()
+    assert tab.dom() is None
+    tab.load_html(TABLE_HTML, "https://example.com/table")
+    assert tab.dom().title == "Table"
+    assert browser.dom().title == "Test Page"
+
+
+def test_replay_from_fixtures(tmp_path):
+    replay = replay_dir(tmp_path, "https://example.com/", SIMPLE_HTML)
+    with Browser(replay=replay) as b:
+        page = b.goto("https://example.com/?utm_source=test")
+        assert page.title == "Test Page"
+        with pytest.raises(RuntimeError, match="No recorded response for GET https://example.com/other"):
+            b.goto("https://example.com/other")
+
+
+def test_preview_submit_sends_nothing(tmp_path):
+    replay = replay_dir(tmp_path, "https://example.com/form", FORM_HTML)
+    with Browser(replay=replay) as b:
+        page = b.goto("https://example.com/form")
+        email = [e for e in page.elements if e.input_type == "email"][0]
+        password = [e for e in page.elements if e.input_type == "password"][0]
+        button = b.find_by_text("Sign In")[0]
+        b.type_text(email.id, "ada@example.com")
+        b.type_text(password.id, "hunter22")
+
+        preview = b.preview(button.id)
+        assert preview["kind"] == "submit"
+        assert preview["method"] == "POST"
+        assert preview["url"] == "https://example.com/submit"
+        fields = {f["name"]: f["value"] for f in preview["fields"]}
+        assert fields["email"] == "ada@example.com"
+        assert fields["password"] == "•••• (8 chars)"
+        assert b.preview(email.id, submit=True) == preview
+        # Nothing was sent: the replay has no response for the form
+        assert b.dom().title == "Form Page"
+
+
+def test_confirmation_required_until_confirmed(tmp_path):
+    replay = replay_dir(tmp_path, "https://example.com/form", FORM_HTML)
+    with Browser(replay=replay, confirm_submit=["sign in"]) as b:
+        b.goto("https://example.com/form")
+        button = b.find_by_text("Sign In")[0]
+        with pytest.raises(BrowsyError) as info:
+            b.click(button.id)
+        assert info.value.code == "confirmation_required"
+        token = info.value.details["token"]
+        assert 'Submit "Sign In"' in info.value.details["description"]
+        assert b.dom().title == "Form Page"
+
+        # Confirming sends the form, which the replay has no response for
+        with pytest.raises(BrowsyError, match="No recorded response for POST https://example.com/submit"):
+            b.confirm(token)
+        with pytest.raises(BrowsyError) as info:
+            b.confirm(token)
+        assert info.value.code == "action_failed"
+
+
+def test_record_and_replay_are_exclusive(tmp_path):
+    with pytest.raises(ValueError):
+        Browser(record=str(tmp_path), replay=str(tmp_path))
+
+
+def test_error_codes(browser):
+    browser.load_html(SIMPLE_HTML, "https://example.com")
+    with pytest.raises(BrowsyError) as info:
+        browser.click(9999)
+    assert isinstance(info.value, RuntimeError)
+    assert info.value.code == "action_element_not_found"
+    assert info.value.details["id"] == 9999
+    assert len(info.value.details["known_ids_range"]) == 2
+
+    heading = browser.find_by_text("Welcome")[0].id
+    with pytest.raises(BrowsyError) as info:
+        browser.type_text(heading, "hello")
+    assert info.value.code == "action_wrong_element_type"
+
+
+def test_domain_lists():
+    with Browser(blocked_domains=["facebook.com"]) as b:
+        with pytest.raises(RuntimeError, match="Blocked URL: https://m.facebook.com/"):
+            b.goto("https://m.facebook.com/")
+    with Browser(allowed_domains=["mycorp.com"]) as b:
+        with pytest.raises(RuntimeError, match="Blocked URL"):
+            b.goto("https://example.org/")
+
+
+def test_audit_log(browser):
+    page = browser.load_html(FORM_HTML, "https://example.com/signin")
+    password = next(el.id for el in page.elements if el.name == "password")
+    remember = next(el.id for el in page.elements if el.name == "remember")
+    browser.type_text(password, "hunter22")
+    browser.check(remember)
+
+    log = browser.audit_log()
+    assert [e["type"] for e in log] == ["load_html", "type", "check"]
+    assert [e["seq"] for e in log] == [1, 2, 3]
+    assert log[0]["url"] == "https://example.com/signin"
+    assert log[1] == {"seq": 2, "ts_ms": log[1]["ts_ms"], "type": "type", "id": password, "value": "•••• (8 chars)"}
+    assert [e["type"] for e in browser.audit_log(since_seq=2)] == ["check"]
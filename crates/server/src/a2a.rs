@@ -4,23 +4,33 @@
 //! A2A protocol specification. Browsy acts as an autonomous skill provider:
 //! orchestrating agents describe a browsing goal, browsy handles multi-step
 //! navigation using page intelligence, and streams progress via SSE.
+//! Tasks are tracked in `AppState` so their events can be replayed and
+//! followed from `GET /a2a/tasks/{id}/events`, and cancelled. A task that
+//! stops on a CAPTCHA or 2FA page waits for `POST /a2a/tasks/{id}/input`
+//! and then carries on in the same browser session.
 
 use std::convert::Infallible;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::RecvTimeoutError;
 use std::sync::Arc;
+use std::time::Instant;
 
 use axum::{
-    extract::State,
+    extract::{Path, State},
     http::StatusCode,
     response::{
         sse::{Event, KeepAlive, Sse},
-        IntoResponse,
+        IntoResponse, Response,
     },
     routing::{get, post},
     Json, Router,
 };
-use browsy_core::fetch::{Session, SessionConfig};
-use browsy_core::output::{self, PageType, SuggestedAction};
+use browsy_core::fetch::{LoginStatus, Session, SessionConfig};
+use browsy_core::output::{self, PageType, SpatialDom, SuggestedAction};
 use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_stream::StreamExt;
 use uuid::Uuid;
 
 use crate::AppState;
@@ -31,6 +41,9 @@ pub fn a2a_routes() -> Router<Arc<AppState>> {
         .route("/.well-known/agent.json", get(agent_card))
         .route("/a2a/tasks", post(create_task))
         .route("/a2a/tasks/{task_id}", get(get_task))
+        .route("/a2a/tasks/{task_id}/events", get(task_events))
+        .route("/a2a/tasks/{task_id}/cancel", post(cancel_task))
+        .route("/a2a/tasks/{task_id}/input", post(send_input))
 }
 
 // ---------------------------------------------------------------------------
@@ -38,6 +51,37 @@ pub fn a2a_routes() -> Router<Arc<AppState>> {
 // ---------------------------------------------------------------------------
 
 async fn agent_card() -> impl IntoResponse {
+    let mut skills = vec![serde_json::json!({
+        "id": "web-browse",
+        "name": "Browse & Extract",
+        "description": "Navigate to a URL, interact with the page, and extract content. Handles multi-step flows like login, search, and form submission autonomously.",
+        "tags": ["web", "browsing", "scraping", "forms"],
+        "examples": [
+            "Go to example.com and extract the pricing table",
+            "Search for 'browsy' on DuckDuckGo and return the top 5 results",
+            "Log in to this dashboard with these credentials and download the report"
+        ]
+    })];
+
+    // One skill per REST route, generated from the same registry as the router
+    for capability in crate::capabilities() {
+        let mut skill = serde_json::json!({
+            "id": capability.name,
+            "name": capability.name,
+            "description": capability.description,
+            "tags": ["rest"],
+            "examples": [serde_json::to_string(&capability.example).unwrap_or_default()],
+            "endpoint": {
+                "method": capability.method,
+                "path": capability.path,
+            },
+        });
+        if let Some(schema) = capability.input_schema {
+            skill["inputSchema"] = schema;
+        }
+        skills.push(skill);
+    }
+
     Json(serde_json::json!({
         "name": "browsy",
         "description": "Zero-render browser for AI agents. Navigates websites, fills forms, extracts structured data without rendering pixels.",
@@ -47,19 +91,12 @@ async fn agent_card() -> impl IntoResponse {
             "streaming": true,
             "pushNotifications": false
         },
-        "skills": [
-            {
-                "id": "web-browse",
-                "name": "Browse & Extract",
-                "description": "Navigate to a URL, interact with the page, and extract content. Handles multi-step flows like login, search, and form submission autonomously.",
-                "tags": ["web", "browsing", "scraping", "forms"],
-                "examples": [
-                    "Go to example.com and extract the pricing table",
-                    "Search for 'browsy' on DuckDuckGo and return the top 5 results",
-                    "Log in to this dashboard with these credentials and download the report"
-                ]
-            }
-        ],
+        "skills": skills,
+        "session": {
+            "header": "X-Browsy-Session",
+            "description": "REST calls share browser state through this header. The first response returns a new token; send it back on later requests to reuse the session."
+        },
+        "outputFormats": ["compact", "json", "map"],
         "defaultInputModes": ["text/plain"],
         "defaultOutputModes": ["text/plain", "application/json"]
     }))
@@ -91,15 +128,52 @@ struct Credentials {
     password: String,
 }
 
+/// What the caller sends to a task waiting in `input-required`.
+#[derive(Debug, Default, Deserialize)]
+struct TaskInput {
+    /// The verification code for a `two_factor` challenge. Without one, a
+    /// `captcha` challenge is retried by loading its page again.
+    code: Option<String>,
+}
+
+/// A2A task lifecycle states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum TaskState {
+    Submitted,
+    Working,
+    /// Paused on a CAPTCHA or 2FA page until the caller sends input;
+    /// `challenge` describes what is needed.
+    InputRequired,
+    Completed,
+    Failed,
+    Canceled,
+}
+
+impl TaskState {
+    /// Whether the worker has stopped and no further events will be sent.
+    fn is_final(self) -> bool {
+        matches!(self, TaskState::Completed | TaskState::Failed | TaskState::Canceled)
+    }
+
+    /// Whether event streams end at this state: the task is over, or it waits
+    /// for input, which starts a new stream.
+    fn ends_stream(self) -> bool {
+        self.is_final() || self == TaskState::InputRequired
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 struct TaskStatus {
     id: String,
-    status: String,
+    status: TaskState,
     steps: Vec<TaskStep>,
     #[serde(skip_serializing_if = "Option::is_none")]
     result: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    challenge: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -108,6 +182,237 @@ struct TaskStep {
     detail: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     page_type: Option<String>,
+    /// Summary of the page reached once this step's session action completed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page: Option<PageSummary>,
+}
+
+impl TaskStep {
+    fn new(action: &str, detail: String) -> Self {
+        Self {
+            action: action.to_string(),
+            detail,
+            page_type: None,
+            page: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PageSummary {
+    title: String,
+    url: String,
+    page_type: String,
+    els: usize,
+}
+
+impl PageSummary {
+    fn of(dom: &SpatialDom) -> Self {
+        Self {
+            title: dom.title.clone(),
+            url: dom.url.clone(),
+            page_type: format!("{:?}", dom.page_type),
+            els: dom.els.len(),
+        }
+    }
+}
+
+/// A task tracked in `AppState`: its latest status, the full event history
+/// (replayed to late subscribers), and the live SSE subscribers.
+pub(crate) struct TaskRecord {
+    status: TaskStatus,
+    events: Vec<TaskStatus>,
+    subscribers: Vec<mpsc::UnboundedSender<TaskStatus>>,
+    cancel: Arc<AtomicBool>,
+    /// Hands input to the worker while the task is `input-required`.
+    input: Option<std::sync::mpsc::Sender<TaskInput>>,
+    updated: Instant,
+}
+
+/// Record a status transition and fan it out to subscribers.
+/// Transitions after a final state (e.g. a worker finishing after cancel) are dropped.
+fn publish_status(state: &AppState, payload: TaskStatus) {
+    let mut tasks = state.tasks.lock().unwrap();
+    let record = match tasks.get_mut(&payload.id) {
+        Some(r) => r,
+        None => return,
+    };
+    if record.status.status.is_final() {
+        return;
+    }
+    record.subscribers.retain(|tx| tx.send(payload.clone()).is_ok());
+    record.events.push(payload.clone());
+    let status = payload.status;
+    record.status = payload;
+    record.updated = Instant::now();
+    if status.ends_stream() {
+        // Dropping the senders ends every open event stream.
+        record.subscribers.clear();
+    }
+    if status.is_final() {
+        // Wakes a worker still waiting for input
+        record.input = None;
+    }
+}
+
+/// Subscribe to a task: replays its history, then follows live transitions.
+fn subscribe(state: &AppState, task_id: &str) -> Option<mpsc::UnboundedReceiver<TaskStatus>> {
+    let mut tasks = state.tasks.lock().unwrap();
+    let record = tasks.get_mut(task_id)?;
+    let (tx, rx) = mpsc::unbounded_channel();
+    for event in &record.events {
+        let _ = tx.send(event.clone());
+    }
+    if !record.status.status.ends_stream() {
+        record.subscribers.push(tx);
+    }
+    Some(rx)
+}
+
+fn status_stream(
+    rx: mpsc::UnboundedReceiver<TaskStatus>,
+) -> Sse<impl futures::Stream<Item = Result<Event, Infallible>>> {
+    let stream = UnboundedReceiverStream::new(rx).map(|status| {
+        Ok(Event::default()
+            .event("status")
+            .data(serde_json::to_string(&status).unwrap_or_default()))
+    });
+    Sse::new(stream).keep_alive(KeepAlive::new().interval(std::time::Duration::from_secs(15)))
+}
+
+/// Publishes a running task's transitions to the shared task store.
+struct TaskReporter {
+    state: Arc<AppState>,
+    task_id: String,
+    cancel: Arc<AtomicBool>,
+}
+
+impl TaskReporter {
+    fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+
+    fn send(
+        &self,
+        status: TaskState,
+        steps: &[TaskStep],
+        result: Option<serde_json::Value>,
+        error: Option<String>,
+    ) {
+        publish_status(
+            &self.state,
+            TaskStatus {
+                id: self.task_id.clone(),
+                status,
+                steps: steps.to_vec(),
+                result,
+                error,
+                challenge: None,
+            },
+        );
+    }
+
+    /// Pause on a challenge until the caller sends input for it. `None` once
+    /// the task was cancelled or failed for want of input within the session
+    /// timeout.
+    fn input_required(&self, steps: &[TaskStep], challenge: serde_json::Value) -> Option<TaskInput> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        if let Some(record) = self.state.tasks.lock().unwrap().get_mut(&self.task_id) {
+            // A task cancelled before now would never drop the sender
+            if record.status.status.is_final() {
+                return None;
+            }
+            record.input = Some(tx);
+        }
+        publish_status(
+            &self.state,
+            TaskStatus {
+                id: self.task_id.clone(),
+                status: TaskState::InputRequired,
+                steps: steps.to_vec(),
+                result: None,
+                error: None,
+                challenge: Some(challenge),
+            },
+        );
+        match rx.recv_timeout(self.state.config.session_timeout) {
+            Ok(input) => Some(input),
+            Err(RecvTimeoutError::Timeout) => {
+                self.send(TaskState::Failed, steps, None, Some("No input arrived within the session timeout".into()));
+                None
+            }
+            // Cancelled
+            Err(RecvTimeoutError::Disconnected) => None,
+        }
+    }
+}
+
+/// Attach the page reached by the last step to it.
+fn record_page(steps: &mut [TaskStep], dom: &SpatialDom) {
+    if let Some(last) = steps.last_mut() {
+        last.page_type = Some(format!("{:?}", dom.page_type));
+        last.page = Some(PageSummary::of(dom));
+    }
+}
+
+/// Structured challenge info when the page needs a human (CAPTCHA) or a code (2FA).
+fn detect_challenge(dom: &SpatialDom) -> Option<serde_json::Value> {
+    match dom.page_type {
+        PageType::Captcha => Some(serde_json::json!({
+            "kind": "captcha",
+            "url": dom.url,
+            "captcha": dom.captcha,
+        })),
+        PageType::TwoFactorAuth => {
+            let enter_code = dom
+                .suggested_actions
+                .iter()
+                .find(|a| matches!(a, SuggestedAction::EnterCode { .. }));
+            Some(serde_json::json!({
+                "kind": "two_factor",
+                "url": dom.url,
+                "enter_code": enter_code,
+            }))
+        }
+        _ => None,
+    }
+}
+
+/// Get past every CAPTCHA or 2FA page from `dom` on, with the caller's input
+/// for each, and return the page after them. `None` once the task has ended.
+fn pass_challenges(
+    session: &mut Session,
+    mut dom: SpatialDom,
+    steps: &mut Vec<TaskStep>,
+    reporter: &TaskReporter,
+) -> Option<SpatialDom> {
+    while let Some(challenge) = detect_challenge(&dom) {
+        let input = reporter.input_required(steps, challenge)?;
+        let resumed = match input.code {
+            Some(code) if dom.page_type == PageType::TwoFactorAuth => {
+                steps.push(TaskStep::new("enter_code", "Entering the verification code".into()));
+                session.enter_code(&code)
+            }
+            _ => {
+                // The CAPTCHA may have cleared by now
+                steps.push(TaskStep::new("browse", format!("Reloading {}", dom.url)));
+                session.goto(&dom.url)
+            }
+        };
+        if reporter.is_cancelled() {
+            return None;
+        }
+        dom = match resumed {
+            Ok(d) => d,
+            Err(e) => {
+                reporter.send(TaskState::Failed, steps, None, Some(e.to_string()));
+                return None;
+            }
+        };
+        record_page(steps, &dom);
+        reporter.send(TaskState::Working, steps, None, None);
+    }
+    Some(dom)
 }
 
 // ---------------------------------------------------------------------------
@@ -119,18 +424,49 @@ async fn create_task(
     Json(req): Json<CreateTaskRequest>,
 ) -> Sse<impl futures::Stream<Item = Result<Event, Infallible>>> {
     let task_id = Uuid::new_v4().to_string();
-    let (tx, rx) = tokio::sync::mpsc::channel::<Result<Event, Infallible>>(32);
+    let cancel = Arc::new(AtomicBool::new(false));
+
+    let submitted = TaskStatus {
+        id: task_id.clone(),
+        status: TaskState::Submitted,
+        steps: Vec::new(),
+        result: None,
+        error: None,
+        challenge: None,
+    };
+    let (tx, rx) = mpsc::unbounded_channel();
+    let _ = tx.send(submitted.clone());
+    {
+        let mut tasks = state.tasks.lock().unwrap();
+        // Purge finished tasks that have been idle past the session timeout
+        let timeout = state.config.session_timeout;
+        tasks.retain(|_, t| !(t.status.status.is_final() && t.updated.elapsed() >= timeout));
+        tasks.insert(
+            task_id.clone(),
+            TaskRecord {
+                status: submitted.clone(),
+                events: vec![submitted],
+                subscribers: vec![tx],
+                cancel: cancel.clone(),
+                input: None,
+                updated: Instant::now(),
+            },
+        );
+    }
 
     let allow_private = state.config.allow_private_network;
+    let reporter = TaskReporter {
+        state: state.clone(),
+        task_id,
+        cancel,
+    };
 
     // Spawn the task execution on a blocking thread (reqwest::blocking)
-    let task_id_clone = task_id.clone();
     tokio::task::spawn_blocking(move || {
-        execute_task(task_id_clone, req, allow_private, tx);
+        execute_task(req, allow_private, &reporter);
     });
 
-    let stream = tokio_stream::wrappers::ReceiverStream::new(rx);
-    Sse::new(stream).keep_alive(KeepAlive::new().interval(std::time::Duration::from_secs(15)))
+    status_stream(rx)
 }
 
 /// Parse a URL from the goal text or params.
@@ -185,30 +521,11 @@ enum TaskIntent {
     FillForm,
 }
 
-/// Execute a browsing task, sending SSE events along the way.
-fn execute_task(
-    task_id: String,
-    req: CreateTaskRequest,
-    allow_private: bool,
-    tx: tokio::sync::mpsc::Sender<Result<Event, Infallible>>,
-) {
+/// Execute a browsing task, publishing status transitions along the way.
+/// Cancellation is checked after every session action.
+fn execute_task(req: CreateTaskRequest, allow_private: bool, reporter: &TaskReporter) {
     let mut steps: Vec<TaskStep> = Vec::new();
 
-    let send_status = |status: &str, steps: &[TaskStep], result: Option<serde_json::Value>, error: Option<String>| {
-        let payload = TaskStatus {
-            id: task_id.clone(),
-            status: status.to_string(),
-            steps: steps.to_vec(),
-            result,
-            error,
-        };
-        let _ = tx.blocking_send(Ok(
-            Event::default()
-                .event("status")
-                .data(serde_json::to_string(&payload).unwrap_or_default()),
-        ));
-    };
-
     // 1. Parse the goal
     let intent = detect_intent(&req.goal);
     let url = extract_url(&req.goal, &req.params);
@@ -235,7 +552,7 @@ fn execute_task(
         None
     });
 
-    send_status("working", &steps, None, None);
+    reporter.send(TaskState::Working, &steps, None, None);
 
     // 2. Create session
     let mut config = SessionConfig::default();
@@ -243,7 +560,7 @@ fn execute_task(
     let mut session = match Session::with_config(config) {
         Ok(s) => s,
         Err(e) => {
-            send_status("failed", &steps, None, Some(format!("Session creation failed: {e}")));
+            reporter.send(TaskState::Failed, &steps, None, Some(format!("Session creation failed: {e}")));
             return;
         }
     };
@@ -254,30 +571,31 @@ fn execute_task(
             let query = match search_query {
                 Some(q) => q,
                 None => {
-                    send_status("failed", &steps, None, Some("No search query found in goal".into()));
+                    reporter.send(TaskState::Failed, &steps, None, Some("No search query found in goal".into()));
                     return;
                 }
             };
 
-            steps.push(TaskStep {
-                action: "search".into(),
-                detail: format!("Searching for: {query}"),
-                page_type: None,
-            });
-            send_status("working", &steps, None, None);
+            steps.push(TaskStep::new("search", format!("Searching for: {query}")));
+            reporter.send(TaskState::Working, &steps, None, None);
 
-            match session.search(&query) {
+            let searched = session.search(&query);
+            if reporter.is_cancelled() {
+                return;
+            }
+            match searched {
                 Ok(results) => {
+                    if let Some(dom) = session.dom_ref() {
+                        record_page(&mut steps, dom);
+                    }
                     let result = serde_json::to_value(&results).unwrap_or_default();
-                    steps.push(TaskStep {
-                        action: "complete".into(),
-                        detail: format!("Found {} results", results.len()),
-                        page_type: Some("SearchResults".into()),
-                    });
-                    send_status("completed", &steps, Some(result), None);
+                    let mut step = TaskStep::new("complete", format!("Found {} results", results.len()));
+                    step.page_type = Some("SearchResults".into());
+                    steps.push(step);
+                    reporter.send(TaskState::Completed, &steps, Some(result), None);
                 }
                 Err(e) => {
-                    send_status("failed", &steps, None, Some(e.to_string()));
+                    reporter.send(TaskState::Failed, &steps, None, Some(e.to_string()));
                 }
             }
         }
@@ -286,68 +604,78 @@ fn execute_task(
             let url = match url {
                 Some(u) => u,
                 None => {
-                    send_status("failed", &steps, None, Some("No URL found for login".into()));
+                    reporter.send(TaskState::Failed, &steps, None, Some("No URL found for login".into()));
                     return;
                 }
             };
             let creds = match req.params.credentials {
                 Some(c) => c,
                 None => {
-                    send_status("failed", &steps, None, Some("No credentials provided for login".into()));
+                    reporter.send(TaskState::Failed, &steps, None, Some("No credentials provided for login".into()));
                     return;
                 }
             };
 
             // Navigate to URL
-            steps.push(TaskStep {
-                action: "browse".into(),
-                detail: format!("Navigating to {url}"),
-                page_type: None,
-            });
-            send_status("working", &steps, None, None);
+            steps.push(TaskStep::new("browse", format!("Navigating to {url}")));
+            reporter.send(TaskState::Working, &steps, None, None);
 
-            let dom = match session.goto(&url) {
+            let navigated = session.goto(&url);
+            if reporter.is_cancelled() {
+                return;
+            }
+            let dom = match navigated {
                 Ok(d) => d,
                 Err(e) => {
-                    send_status("failed", &steps, None, Some(e.to_string()));
+                    reporter.send(TaskState::Failed, &steps, None, Some(e.to_string()));
                     return;
                 }
             };
 
-            steps.last_mut().unwrap().page_type = Some(format!("{:?}", dom.page_type));
-            send_status("working", &steps, None, None);
+            record_page(&mut steps, &dom);
+            if pass_challenges(&mut session, dom, &mut steps, reporter).is_none() {
+                return;
+            }
+            reporter.send(TaskState::Working, &steps, None, None);
 
             // Login
-            steps.push(TaskStep {
-                action: "login".into(),
-                detail: format!("Logging in as {}", creds.username),
-                page_type: None,
-            });
-            send_status("working", &steps, None, None);
+            steps.push(TaskStep::new("login", format!("Logging in as {}", creds.username)));
+            reporter.send(TaskState::Working, &steps, None, None);
+
+            let logged_in = session.login(&creds.username, &creds.password);
+            if reporter.is_cancelled() {
+                return;
+            }
+            match logged_in {
+                Ok(outcome) => {
+                    record_page(&mut steps, &outcome.dom);
+                    let challenged = detect_challenge(&outcome.dom).is_some();
+                    let Some(dom) = pass_challenges(&mut session, outcome.dom, &mut steps, reporter) else {
+                        return;
+                    };
+                    // The status of the login form's own submit no longer holds
+                    let status = if challenged { LoginStatus::from_page(&dom) } else { outcome.status };
 
-            match session.login(&creds.username, &creds.password) {
-                Ok(dom) => {
                     let page_type = format!("{:?}", dom.page_type);
                     let compact = output::to_compact_string(&dom);
-                    steps.push(TaskStep {
-                        action: "complete".into(),
-                        detail: format!("Login result: {page_type}"),
-                        page_type: Some(page_type),
-                    });
-                    send_status(
-                        "completed",
+                    let mut step = TaskStep::new("complete", format!("Login result: {status}"));
+                    step.page_type = Some(page_type);
+                    steps.push(step);
+                    reporter.send(
+                        TaskState::Completed,
                         &steps,
                         Some(serde_json::json!({
                             "title": dom.title,
                             "url": dom.url,
                             "page_type": format!("{:?}", dom.page_type),
+                            "login": status,
                             "content": compact,
                         })),
                         None,
                     );
                 }
                 Err(e) => {
-                    send_status("failed", &steps, None, Some(e.to_string()));
+                    reporter.send(TaskState::Failed, &steps, None, Some(e.to_string()));
                 }
             }
         }
@@ -358,75 +686,69 @@ fn execute_task(
                 None => {
                     // For search-like intent without a URL, try search
                     if let Some(query) = search_query {
-                        steps.push(TaskStep {
-                            action: "search".into(),
-                            detail: format!("No URL found, searching: {query}"),
-                            page_type: None,
-                        });
-                        send_status("working", &steps, None, None);
-                        match session.search(&query) {
+                        steps.push(TaskStep::new("search", format!("No URL found, searching: {query}")));
+                        reporter.send(TaskState::Working, &steps, None, None);
+                        let searched = session.search(&query);
+                        if reporter.is_cancelled() {
+                            return;
+                        }
+                        match searched {
                             Ok(results) => {
                                 let result = serde_json::to_value(&results).unwrap_or_default();
-                                send_status("completed", &steps, Some(result), None);
+                                reporter.send(TaskState::Completed, &steps, Some(result), None);
                             }
                             Err(e) => {
-                                send_status("failed", &steps, None, Some(e.to_string()));
+                                reporter.send(TaskState::Failed, &steps, None, Some(e.to_string()));
                             }
                         }
                         return;
                     }
-                    send_status("failed", &steps, None, Some("No URL or search query found in goal".into()));
+                    reporter.send(TaskState::Failed, &steps, None, Some("No URL or search query found in goal".into()));
                     return;
                 }
             };
 
             // Navigate
-            steps.push(TaskStep {
-                action: "browse".into(),
-                detail: format!("Navigating to {url}"),
-                page_type: None,
-            });
-            send_status("working", &steps, None, None);
+            steps.push(TaskStep::new("browse", format!("Navigating to {url}")));
+            reporter.send(TaskState::Working, &steps, None, None);
 
-            let dom = match session.goto(&url) {
+            let navigated = session.goto(&url);
+            if reporter.is_cancelled() {
+                return;
+            }
+            let dom = match navigated {
                 Ok(d) => d,
                 Err(e) => {
-                    send_status("failed", &steps, None, Some(e.to_string()));
+                    reporter.send(TaskState::Failed, &steps, None, Some(e.to_string()));
                     return;
                 }
             };
 
-            let page_type = format!("{:?}", dom.page_type);
-            steps.last_mut().unwrap().page_type = Some(page_type.clone());
-            send_status("working", &steps, None, None);
+            record_page(&mut steps, &dom);
+            reporter.send(TaskState::Working, &steps, None, None);
 
             // Handle cookie consent if detected
             for action in &dom.suggested_actions {
                 if let SuggestedAction::CookieConsent { accept_id, .. } = action {
-                    steps.push(TaskStep {
-                        action: "click".into(),
-                        detail: "Accepting cookie consent".into(),
-                        page_type: None,
-                    });
-                    send_status("working", &steps, None, None);
-                    let _ = session.click(*accept_id);
+                    steps.push(TaskStep::new("click", "Accepting cookie consent".into()));
+                    reporter.send(TaskState::Working, &steps, None, None);
+                    if let Ok(after) = session.click(*accept_id) {
+                        record_page(&mut steps, &after);
+                    }
+                    if reporter.is_cancelled() {
+                        return;
+                    }
                     break;
                 }
             }
 
             // Handle CAPTCHA / blocked
-            if dom.page_type == PageType::Captcha {
-                send_status(
-                    "failed",
-                    &steps,
-                    None,
-                    Some("CAPTCHA detected — requires human verification".into()),
-                );
+            let Some(dom) = pass_challenges(&mut session, dom, &mut steps, reporter) else {
                 return;
-            }
+            };
             if dom.page_type == PageType::Blocked {
-                send_status(
-                    "failed",
+                reporter.send(
+                    TaskState::Failed,
                     &steps,
                     None,
                     Some("Access blocked by target site".into()),
@@ -467,29 +789,29 @@ fn execute_task(
                 );
             }
 
-            steps.push(TaskStep {
-                action: "complete".into(),
-                detail: format!(
+            let mut step = TaskStep::new(
+                "complete",
+                format!(
                     "Extracted content ({} elements, {} tables)",
                     final_dom.els.len(),
                     tables.len()
                 ),
-                page_type: Some(format!("{:?}", final_dom.page_type)),
-            });
-            send_status("completed", &steps, Some(result), None);
+            );
+            step.page_type = Some(format!("{:?}", final_dom.page_type));
+            steps.push(step);
+            reporter.send(TaskState::Completed, &steps, Some(result), None);
         }
     }
 }
 
 // ---------------------------------------------------------------------------
-// Get Task — GET /a2a/tasks/:task_id (status polling stub)
+// Get Task — GET /a2a/tasks/:task_id
 // ---------------------------------------------------------------------------
 
 async fn get_task(
-    axum::extract::Path(task_id): axum::extract::Path<String>,
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<String>,
 ) -> impl IntoResponse {
-    // Task results are streamed via SSE. This endpoint provides a minimal
-    // acknowledgment that the task ID format is valid.
     if Uuid::parse_str(&task_id).is_err() {
         return (
             StatusCode::BAD_REQUEST,
@@ -497,12 +819,135 @@ async fn get_task(
         );
     }
 
+    let tasks = state.tasks.lock().unwrap();
+    if let Some(record) = tasks.get(&task_id) {
+        return (
+            StatusCode::OK,
+            Json(serde_json::to_value(&record.status).unwrap_or_default()),
+        );
+    }
+
     (
         StatusCode::OK,
         Json(serde_json::json!({
             "id": task_id,
             "status": "unknown",
-            "message": "Task results are delivered via SSE stream on POST /a2a/tasks. Use the SSE event stream for real-time status."
+            "message": "No task with this ID is tracked (it may have expired). Use GET /a2a/tasks/{id}/events to follow a running task."
         })),
     )
 }
+
+// ---------------------------------------------------------------------------
+// Task Events — GET /a2a/tasks/:task_id/events
+// ---------------------------------------------------------------------------
+
+async fn task_events(
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<String>,
+) -> Response {
+    match subscribe(&state, &task_id) {
+        Some(rx) => status_stream(rx).into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": "Unknown task ID" })),
+        )
+            .into_response(),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Cancel Task — POST /a2a/tasks/:task_id/cancel
+// ---------------------------------------------------------------------------
+
+async fn cancel_task(
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<String>,
+) -> Response {
+    let current = {
+        let tasks = state.tasks.lock().unwrap();
+        match tasks.get(&task_id) {
+            Some(record) => {
+                record.cancel.store(true, Ordering::SeqCst);
+                record.status.clone()
+            }
+            None => {
+                return (
+                    StatusCode::NOT_FOUND,
+                    Json(serde_json::json!({ "error": "Unknown task ID" })),
+                )
+                    .into_response();
+            }
+        }
+    };
+
+    if current.status.is_final() {
+        return (StatusCode::CONFLICT, Json(current)).into_response();
+    }
+
+    let canceled = TaskStatus {
+        status: TaskState::Canceled,
+        result: None,
+        error: None,
+        challenge: None,
+        ..current
+    };
+    publish_status(&state, canceled.clone());
+    (StatusCode::OK, Json(canceled)).into_response()
+}
+
+// ---------------------------------------------------------------------------
+// Send Input — POST /a2a/tasks/:task_id/input
+// ---------------------------------------------------------------------------
+
+async fn send_input(
+    State(state): State<Arc<AppState>>,
+    Path(task_id): Path<String>,
+    Json(input): Json<TaskInput>,
+) -> Response {
+    let (sender, working, rx) = {
+        let mut tasks = state.tasks.lock().unwrap();
+        let Some(record) = tasks.get_mut(&task_id) else {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": "Unknown task ID" })),
+            )
+                .into_response();
+        };
+        if record.status.status != TaskState::InputRequired {
+            return (StatusCode::CONFLICT, Json(record.status.clone())).into_response();
+        }
+        let two_factor = record.status.challenge.as_ref().is_some_and(|c| c["kind"] == "two_factor");
+        if two_factor && input.code.is_none() {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(serde_json::json!({ "error": "A two_factor challenge needs a code" })),
+            )
+                .into_response();
+        }
+        let Some(sender) = record.input.take() else {
+            return (StatusCode::CONFLICT, Json(record.status.clone())).into_response();
+        };
+        // Follow the task from here on, like the stream that created it
+        let (tx, rx) = mpsc::unbounded_channel();
+        record.subscribers.push(tx);
+        let working = TaskStatus {
+            status: TaskState::Working,
+            challenge: None,
+            ..record.status.clone()
+        };
+        (sender, working, rx)
+    };
+
+    publish_status(&state, working.clone());
+    if sender.send(input).is_err() {
+        publish_status(
+            &state,
+            TaskStatus {
+                status: TaskState::Failed,
+                error: Some("The task stopped waiting for input".into()),
+                ..working
+            },
+        );
+    }
+    status_stream(rx).into_response()
+}
@@ -0,0 +1,135 @@
+//! API key authentication and per-key rate limiting.
+//!
+//! Both are off by default. With `ServerConfig::api_keys` set, every route
+//! except the public ones below needs a key in `Authorization: Bearer <key>`
+//! or `X-Api-Key: <key>`. With `ServerConfig::rate_limit_per_minute` set,
+//! each key gets a token bucket of that size, refilled continuously.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::IntoResponse,
+    Json,
+};
+use browsy_core::fetch::ErrorCode;
+
+use crate::{AppState, ErrorResponse};
+
+/// Routes reachable without a key: liveness probes and A2A discovery.
+pub(crate) const PUBLIC_ROUTES: &[&str] = &["/health", "/.well-known/agent.json", "/openapi.json"];
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets keyed by API key.
+pub(crate) struct RateLimiter {
+    per_minute: u32,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take one token for `key`, or return how many seconds until one is free.
+    pub fn acquire(&self, key: &str) -> Result<(), u64> {
+        let capacity = self.per_minute as f64;
+        let per_second = capacity / 60.0;
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+        let refill = now.duration_since(bucket.updated).as_secs_f64() * per_second;
+        bucket.tokens = (bucket.tokens + refill).min(capacity);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else if per_second > 0.0 {
+            Err(((1.0 - bucket.tokens) / per_second).ceil().max(1.0) as u64)
+        } else {
+            Err(60)
+        }
+    }
+}
+
+/// The key presented by the client, from either supported header.
+fn presented_key(headers: &HeaderMap) -> Option<&str> {
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim);
+    bearer.or_else(|| headers.get("X-Api-Key").and_then(|v| v.to_str().ok()).map(str::trim))
+}
+
+/// Compare without short-circuiting, so response timing doesn't reveal how
+/// much of a key matched.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn reject(status: StatusCode, error: &str) -> axum::response::Response {
+    let code = match status {
+        StatusCode::TOO_MANY_REQUESTS => ErrorCode::RateLimited,
+        _ => ErrorCode::Unauthorized,
+    };
+    (status, Json(ErrorResponse::new(code, error))).into_response()
+}
+
+fn unauthorized(error: &str) -> axum::response::Response {
+    let mut response = reject(StatusCode::UNAUTHORIZED, error);
+    response
+        .headers_mut()
+        .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+    response
+}
+
+/// Middleware: check the API key and the key's rate limit.
+pub(crate) async fn authorize(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> axum::response::Response {
+    let keys = &state.config.api_keys;
+    let public = request
+        .extensions()
+        .get::<MatchedPath>()
+        .is_some_and(|path| PUBLIC_ROUTES.contains(&path.as_str()));
+    if keys.is_empty() || public {
+        return next.run(request).await;
+    }
+
+    let presented = match presented_key(request.headers()) {
+        Some(key) if !key.is_empty() => key,
+        _ => return unauthorized("Missing API key"),
+    };
+    let Some(key) = keys.iter().find(|k| constant_time_eq(k.as_bytes(), presented.as_bytes())) else {
+        return unauthorized("Invalid API key");
+    };
+
+    if let Some(limiter) = &state.rate_limiter {
+        if let Err(retry_after) = limiter.acquire(key) {
+            let mut response = reject(StatusCode::TOO_MANY_REQUESTS, "Rate limit exceeded");
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            return response;
+        }
+    }
+
+    next.run(request).await
+}
@@ -0,0 +1,496 @@
+//! Capability registry shared by the REST router and the A2A agent card.
+//!
+//! Every REST route is declared once here, together with its handler, so the
+//! routes mounted by `build_router` and the skills advertised on the agent
+//! card always match.
+
+use std::sync::Arc;
+
+use axum::extract::DefaultBodyLimit;
+use axum::routing::{get, post, MethodRouter};
+use schemars::JsonSchema;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    AnnotateParams, AppState, AuditQuery, BrowseParams, BrowseStreamParams, CheckParams, ClickParams, ConfirmParams, CrawlParams,
+    ElementContextQuery, EnterCodeParams, FindParams, GetPageQuery, ImportCookiesParams, LoginParams, PaginateParams,
+    ParseParams, PressEnterParams, PreviewParams, SearchParams, SelectParams, SessionConfigParams, SitemapParams,
+    TypeTextParams, MAX_PARSE_HTML_BYTES,
+};
+
+/// A REST endpoint as advertised to agents.
+#[derive(Debug, Clone, Serialize)]
+pub struct Capability {
+    /// Skill name (matches the MCP tool name where one exists).
+    pub name: &'static str,
+    /// HTTP method: `GET` or `POST`.
+    pub method: &'static str,
+    pub path: &'static str,
+    pub description: &'static str,
+    /// JSON Schema of the request body (POST) or query string (GET).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input_schema: Option<serde_json::Value>,
+    /// Example request body or query parameters.
+    pub example: serde_json::Value,
+}
+
+fn schema<T: JsonSchema>() -> Option<serde_json::Value> {
+    serde_json::to_value(schemars::schema_for!(T)).ok()
+}
+
+fn capability(
+    name: &'static str,
+    method: &'static str,
+    path: &'static str,
+    description: &'static str,
+    input_schema: Option<serde_json::Value>,
+    example: serde_json::Value,
+) -> Capability {
+    Capability {
+        name,
+        method,
+        path,
+        description,
+        input_schema,
+        example,
+    }
+}
+
+/// All REST routes paired with their handlers.
+pub(crate) fn registry() -> Vec<(Capability, MethodRouter<Arc<AppState>>)> {
+    vec![
+        (
+            capability("health", "GET", "/health", "Liveness check. Returns 'ok'.", None, json!({})),
+            get(crate::health),
+        ),
+        (
+            capability(
+                "openapi",
+                "GET",
+                "/openapi.json",
+                "OpenAPI description of every route, including the session header.",
+                None,
+                json!({}),
+            ),
+            get(crate::openapi_spec),
+        ),
+        (
+            capability(
+                "browse",
+                "POST",
+                "/api/browse",
+                "Navigate to a URL and return the page as a Spatial DOM.",
+                schema::<BrowseParams>(),
+                json!({ "url": "https://example.com", "format": "compact", "scope": "visible" }),
+            ),
+            post(crate::browse),
+        ),
+        (
+            capability(
+                "browse_get",
+                "GET",
+                "/api/browse",
+                "Same as browse, with the parameters in the query string so responses can be cached.",
+                schema::<BrowseParams>(),
+                json!({ "url": "https://example.com", "format": "compact", "scope": "visible" }),
+            ),
+            get(crate::browse_get),
+        ),
+        (
+            capability(
+                "browse_stream",
+                "POST",
+                "/api/browse/stream",
+                "Same as browse, with the page sent as Server-Sent Events: a meta event, batches of elements, then done.",
+                schema::<BrowseStreamParams>(),
+                json!({ "url": "https://example.com", "format": "compact", "batch_size": 500 }),
+            ),
+            post(crate::browse_stream),
+        ),
+        (
+            capability(
+                "load_html",
+                "POST",
+                "/api/parse",
+                "Analyze raw HTML without fetching anything and load it into the session.",
+                schema::<ParseParams>(),
+                json!({
+                    "html": "<form><input name=\"q\"><button>Search</button></form>",
+                    "url": "https://example.com/",
+                    "viewport": [1280, 800]
+                }),
+            ),
+            // JSON escaping can double the size of the HTML on the wire
+            post(crate::parse).layer(DefaultBodyLimit::max(2 * MAX_PARSE_HTML_BYTES)),
+        ),
+        (
+            capability(
+                "click",
+                "POST",
+                "/api/click",
+                "Click an element by ID. Links navigate, buttons submit forms.",
+                schema::<ClickParams>(),
+                json!({ "id": 5 }),
+            ),
+            post(crate::click),
+        ),
+        (
+            capability(
+                "preview_action",
+                "POST",
+                "/api/preview",
+                "Show what clicking an element, or submitting its form, would do without doing it: the URL a link loads, the JS behavior it triggers, or a form's method, URL and fields (sensitive values redacted).",
+                schema::<PreviewParams>(),
+                json!({ "id": 5, "submit": false }),
+            ),
+            post(crate::preview),
+        ),
+        (
+            capability(
+                "type_text",
+                "POST",
+                "/api/type",
+                "Type text into an input field by element ID.",
+                schema::<TypeTextParams>(),
+                json!({ "id": 3, "text": "user@example.com" }),
+            ),
+            post(crate::type_text),
+        ),
+        (
+            capability(
+                "press_enter",
+                "POST",
+                "/api/press-enter",
+                "Press Enter in an input by element ID, submitting its form even when it has no submit button.",
+                schema::<PressEnterParams>(),
+                json!({ "id": 3 }),
+            ),
+            post(crate::press_enter),
+        ),
+        (
+            capability(
+                "confirm_action",
+                "POST",
+                "/api/confirm",
+                "Take a click or form submission the action policy held, by the token of its confirmation_required (409) error.",
+                schema::<ConfirmParams>(),
+                json!({ "token": "5f0c2e9a41b7d3c8e6a1f4b2d9c07e35" }),
+            ),
+            post(crate::confirm),
+        ),
+        (
+            capability(
+                "check",
+                "POST",
+                "/api/check",
+                "Check a checkbox or radio button by element ID.",
+                schema::<CheckParams>(),
+                json!({ "id": 7 }),
+            ),
+            post(crate::check),
+        ),
+        (
+            capability(
+                "uncheck",
+                "POST",
+                "/api/uncheck",
+                "Uncheck a checkbox by element ID.",
+                schema::<CheckParams>(),
+                json!({ "id": 7 }),
+            ),
+            post(crate::uncheck),
+        ),
+        (
+            capability(
+                "select",
+                "POST",
+                "/api/select",
+                "Select an option in a dropdown by element ID.",
+                schema::<SelectParams>(),
+                json!({ "id": 9, "value": "us" }),
+            ),
+            post(crate::select),
+        ),
+        (
+            capability(
+                "annotate",
+                "POST",
+                "/api/annotate",
+                "Leave a note on an element by ID. It shows with the element, follows it across re-renders of the page and is dropped when the element disappears; an empty note removes it.",
+                schema::<AnnotateParams>(),
+                json!({ "id": 12, "note": "out of stock, try the next one" }),
+            ),
+            post(crate::annotate),
+        ),
+        (
+            capability(
+                "search",
+                "POST",
+                "/api/search",
+                "Search the web and return structured results.",
+                schema::<SearchParams>(),
+                json!({ "query": "rust web framework", "engine": "duckduckgo" }),
+            ),
+            post(crate::search),
+        ),
+        (
+            capability(
+                "discover_feeds",
+                "GET",
+                "/api/feeds",
+                "List the RSS/Atom feeds the current page links to and the sitemaps of its site (from robots.txt and /sitemap.xml).",
+                None,
+                json!({}),
+            ),
+            get(crate::feeds),
+        ),
+        (
+            capability(
+                "fetch_sitemap",
+                "POST",
+                "/api/sitemap",
+                "Fetch a sitemap and return the pages it lists, following a sitemap index into its sitemaps.",
+                schema::<SitemapParams>(),
+                json!({ "url": "https://example.com/sitemap.xml" }),
+            ),
+            post(crate::sitemap),
+        ),
+        (
+            capability(
+                "collect_pages",
+                "POST",
+                "/api/paginate",
+                "Follow the current page's next links and merge the tables, lists or links of each page, with the page each row came from.",
+                schema::<PaginateParams>(),
+                json!({ "extract": "tables", "max_pages": 5 }),
+            ),
+            post(crate::paginate),
+        ),
+        (
+            capability(
+                "crawl",
+                "POST",
+                "/api/crawl",
+                "Crawl a site breadth first from a URL and return one inventory entry per page: URL, depth, title, page type, element count and outlinks. At most 50 pages.",
+                schema::<CrawlParams>(),
+                json!({ "url": "https://docs.example.com/", "max_pages": 20, "max_depth": 2 }),
+            ),
+            post(crate::crawl),
+        ),
+        (
+            capability(
+                "login",
+                "POST",
+                "/api/login",
+                "Fill and submit the login form detected on the current page.",
+                schema::<LoginParams>(),
+                json!({ "username": "user@example.com", "password": "secret" }),
+            ),
+            post(crate::login),
+        ),
+        (
+            capability(
+                "enter_code",
+                "POST",
+                "/api/enter-code",
+                "Enter a verification or 2FA code on the current page.",
+                schema::<EnterCodeParams>(),
+                json!({ "code": "482901" }),
+            ),
+            post(crate::enter_code),
+        ),
+        (
+            capability(
+                "find",
+                "POST",
+                "/api/find",
+                "Find elements on the current page by text or ARIA role.",
+                schema::<FindParams>(),
+                json!({ "text": "Sign In" }),
+            ),
+            post(crate::find),
+        ),
+        (
+            capability(
+                "find_get",
+                "GET",
+                "/api/find",
+                "Same as find, with the parameters in the query string.",
+                schema::<FindParams>(),
+                json!({ "text": "Sign In" }),
+            ),
+            get(crate::find_get),
+        ),
+        (
+            capability(
+                "get_page",
+                "GET",
+                "/api/page",
+                "Return the current page with typed form values overlaid.",
+                schema::<GetPageQuery>(),
+                json!({ "format": "json", "scope": "visible" }),
+            ),
+            get(crate::get_page),
+        ),
+        (
+            capability(
+                "ws",
+                "GET",
+                "/api/ws",
+                "Open a WebSocket bound to one session. Send browse, click and page commands as JSON; each gets a response with the same 'req', and the server pushes auto_action and session_expiring events.",
+                None,
+                json!({ "op": "browse", "url": "https://example.com", "req": 1 }),
+            ),
+            get(crate::ws::upgrade),
+        ),
+        (
+            capability(
+                "page_info",
+                "GET",
+                "/api/page-info",
+                "Return page type, suggested actions, alerts and pagination.",
+                None,
+                json!({}),
+            ),
+            get(crate::page_info),
+        ),
+        (
+            capability(
+                "tables",
+                "GET",
+                "/api/tables",
+                "Extract the tables on the current page as headers and rows.",
+                None,
+                json!({}),
+            ),
+            get(crate::tables),
+        ),
+        (
+            capability(
+                "get_element_text",
+                "GET",
+                "/api/element/{id}/text",
+                "Return an element's whole text, which the compact format cuts short after 200 characters.",
+                None,
+                json!({}),
+            ),
+            get(crate::element_text),
+        ),
+        (
+            capability(
+                "element_context",
+                "GET",
+                "/api/element/{id}/context",
+                "Return an element with its container, its row, the elements left of and above it, and its neighbors.",
+                schema::<ElementContextQuery>(),
+                json!({ "radius": 50 }),
+            ),
+            get(crate::element_context),
+        ),
+        (
+            capability(
+                "summary",
+                "GET",
+                "/api/summary",
+                "Count the current page's elements by role, and its hidden elements, links, forms and tables.",
+                None,
+                json!({}),
+            ),
+            get(crate::summary),
+        ),
+        (
+            capability(
+                "back",
+                "POST",
+                "/api/back",
+                "Go back to the previous page in the session history.",
+                None,
+                json!({}),
+            ),
+            post(crate::back),
+        ),
+        (
+            capability(
+                "session_config",
+                "POST",
+                "/api/session-config",
+                "Limit which domains this session may navigate to, and change its viewport, stylesheet fetching and User-Agent.",
+                schema::<SessionConfigParams>(),
+                json!({ "allowed_domains": ["example.com"], "blocked_domains": ["facebook.com"] }),
+            ),
+            post(crate::session_config),
+        ),
+        (
+            capability(
+                "fork_session",
+                "POST",
+                "/api/sessions/{token}/fork",
+                "Copy a session, with its cookies, page, history and form values, into a new one that can diverge from it. Returns the new session's token.",
+                None,
+                json!({}),
+            ),
+            post(crate::fork_session),
+        ),
+        (
+            capability(
+                "export_cookies",
+                "POST",
+                "/api/cookies/export",
+                "Return the session's cookies as JSON, session cookies included, for importing into another session.",
+                None,
+                json!({}),
+            ),
+            post(crate::export_cookies),
+        ),
+        (
+            capability(
+                "import_cookies",
+                "POST",
+                "/api/cookies/import",
+                "Add cookies exported from a session to this one. Cookies of the server's shared domains reach every session.",
+                schema::<ImportCookiesParams>(),
+                json!({ "cookies": [] }),
+            ),
+            post(crate::import_cookies),
+        ),
+        (
+            capability(
+                "audit",
+                "GET",
+                "/api/audit",
+                "Return the session's audit log of actions, oldest first.",
+                schema::<AuditQuery>(),
+                json!({ "since_seq": 10 }),
+            ),
+            get(crate::audit),
+        ),
+        (
+            capability(
+                "nav_stats",
+                "GET",
+                "/api/nav-stats",
+                "Return what the session's last navigations cost: bytes, stylesheets fetched and blocked, phase timings and element counts.",
+                None,
+                json!({}),
+            ),
+            get(crate::nav_stats),
+        ),
+        (
+            capability(
+                "snapshot",
+                "POST",
+                "/api/snapshot",
+                "Save the current page's HTML, Spatial DOM, form state (sensitive values redacted) and recent audit events to a folder on the server, and return its path.",
+                None,
+                json!({}),
+            ),
+            post(crate::snapshot),
+        ),
+    ]
+}
+
+/// The capabilities advertised on the agent card, one per REST route.
+pub fn capabilities() -> Vec<Capability> {
+    registry().into_iter().map(|(capability, _)| capability).collect()
+}
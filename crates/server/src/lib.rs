@@ -1,23 +1,47 @@
 //! REST API + A2A server for browsy.
 
 use std::collections::HashMap;
+use std::convert::Infallible;
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
 use axum::{
-    extract::{Query, State},
-    http::{HeaderMap, HeaderValue, StatusCode},
-    response::IntoResponse,
-    routing::{get, post},
+    extract::{MatchedPath, Path, Query, Request, State},
+    http::{HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::{self, Next},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+    routing::get,
     Json, Router,
 };
-use browsy_core::fetch::{FetchError, SearchEngine, Session, SessionConfig};
-use browsy_core::output;
+use browsy_core::fetch::{
+    redact, ActionPolicy, ConsentChoice, CookieJar, CrawlOpts, ErrorCode, Extract, FetchError, LoginStatus,
+    SearchEngine, Session, SessionConfig, Timings, DEFAULT_MAX_PAGES,
+};
+use browsy_core::output::{self, format_page, format_view};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use tower_http::cors::{self, CorsLayer};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use tracing::Instrument;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::EnvFilter;
 use uuid::Uuid;
 
 mod a2a;
+mod auth;
+mod capabilities;
+mod metrics;
+mod openapi;
+mod ws;
+
+pub use capabilities::{capabilities, Capability};
+pub use openapi::openapi;
 
 // ---------------------------------------------------------------------------
 // Session management
@@ -29,9 +53,23 @@ struct SessionEntry {
     last_access: Instant,
 }
 
+/// A page loaded by a session action with its timings, the action's
+/// error, or the status for a missing session.
+type PageResult = Result<Result<(output::SpatialDom, Option<Timings>), FetchError>, StatusCode>;
+
 /// Shared server state.
 pub struct AppState {
     sessions: Mutex<HashMap<String, SessionEntry>>,
+    /// A2A tasks by ID, with their event history and live subscribers.
+    tasks: Mutex<HashMap<String, a2a::TaskRecord>>,
+    /// Present when `ServerConfig::enable_metrics` is set.
+    metrics: Option<metrics::Metrics>,
+    /// Present when `ServerConfig::rate_limit_per_minute` is set.
+    rate_limiter: Option<auth::RateLimiter>,
+    /// Served at `GET /openapi.json`; built once, as routes can't change.
+    openapi: serde_json::Value,
+    /// Cookies of `ServerConfig::shared_cookie_domains`, used by every session.
+    shared_cookies: Arc<CookieJar>,
     config: ServerConfig,
 }
 
@@ -41,6 +79,36 @@ pub struct ServerConfig {
     pub session_timeout: Duration,
     pub max_sessions: usize,
     pub allow_private_network: bool,
+    /// Log filter for `init_logging`, e.g. `info` or `browsy_core=debug`.
+    pub log_level: String,
+    pub log_format: LogFormat,
+    /// Serve Prometheus metrics at `GET /metrics`.
+    pub enable_metrics: bool,
+    /// Keys accepted in `Authorization: Bearer` or `X-Api-Key`. Empty
+    /// disables authentication.
+    pub api_keys: Vec<String>,
+    /// Requests allowed per API key per minute, with bursts up to the same
+    /// number. Only applies when `api_keys` is set.
+    pub rate_limit_per_minute: Option<u32>,
+    /// Origins allowed to call the API from a browser: exact origins such as
+    /// `https://app.example.com`, or `*` for any. Empty disables CORS.
+    pub cors_origins: Vec<String>,
+    /// Domains new sessions may navigate to (with subdomains). `None` allows
+    /// every domain. Sessions can change this with `POST /api/session-config`.
+    pub allowed_domains: Option<Vec<String>>,
+    /// Domains new sessions never navigate to.
+    pub blocked_domains: Vec<String>,
+    /// Where `POST /api/snapshot` writes session snapshots.
+    pub snapshot_dir: PathBuf,
+    /// A `browsy.toml` file of per-site overrides every session applies.
+    pub site_overrides_path: Option<PathBuf>,
+    /// Domains (with subdomains) whose cookies every session shares, so a
+    /// login in one session serves all of them. Other cookies stay with the
+    /// session that received them.
+    pub shared_cookie_domains: Vec<String>,
+    /// Clicks and form submissions every session holds until they're
+    /// confirmed with `POST /api/confirm` or sent again with `confirmed`.
+    pub action_policy: Option<ActionPolicy>,
 }
 
 impl Default for ServerConfig {
@@ -50,6 +118,57 @@ impl Default for ServerConfig {
             session_timeout: Duration::from_secs(30 * 60),
             max_sessions: 100,
             allow_private_network: false,
+            log_level: "info".to_string(),
+            log_format: LogFormat::Text,
+            enable_metrics: true,
+            api_keys: Vec::new(),
+            rate_limit_per_minute: None,
+            cors_origins: vec!["*".to_string()],
+            allowed_domains: None,
+            blocked_domains: Vec::new(),
+            snapshot_dir: std::env::temp_dir().join("browsy-snapshots"),
+            site_overrides_path: None,
+            shared_cookie_domains: Vec::new(),
+            action_policy: None,
+        }
+    }
+}
+
+/// How logs are written to stderr.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Human-readable lines.
+    #[default]
+    Text,
+    /// One JSON object per line, for log collectors.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            other => Err(format!("unknown log format {other:?} (expected text or json)")),
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Install a global tracing subscriber writing to stderr with this
+    /// config's level and format. Closed spans are logged with their
+    /// duration, so slow requests can be broken down by phase.
+    pub fn init_logging(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let filter = EnvFilter::try_new(&self.log_level)?;
+        let builder = tracing_subscriber::fmt()
+            .with_env_filter(filter)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_writer(std::io::stderr);
+        match self.log_format {
+            LogFormat::Text => builder.try_init(),
+            LogFormat::Json => builder.json().try_init(),
         }
     }
 }
@@ -58,6 +177,11 @@ impl AppState {
     pub fn new(config: ServerConfig) -> Self {
         Self {
             sessions: Mutex::new(HashMap::new()),
+            tasks: Mutex::new(HashMap::new()),
+            metrics: config.enable_metrics.then(metrics::Metrics::default),
+            rate_limiter: config.rate_limit_per_minute.map(auth::RateLimiter::new),
+            openapi: openapi::openapi(),
+            shared_cookies: Arc::new(CookieJar::default()),
             config,
         }
     }
@@ -91,8 +215,17 @@ impl AppState {
         // Create new session
         let mut session_config = SessionConfig::default();
         session_config.allow_private_network = self.config.allow_private_network;
-        let session = Session::with_config(session_config)
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        session_config.allowed_domains = self.config.allowed_domains.clone();
+        session_config.blocked_domains = self.config.blocked_domains.clone();
+        session_config.site_overrides_path = self.config.site_overrides_path.clone();
+        session_config.action_policy = self.config.action_policy.clone();
+        let session = if self.config.shared_cookie_domains.is_empty() {
+            Session::with_config(session_config)
+        } else {
+            let jar = CookieJar::sharing(self.shared_cookies.clone(), &self.config.shared_cookie_domains);
+            Session::with_cookie_jar(session_config, Arc::new(jar))
+        }
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
         let new_token = Uuid::new_v4().to_string();
         sessions.insert(
             new_token.clone(),
@@ -109,10 +242,90 @@ impl AppState {
     where
         F: FnOnce(&mut Session) -> R,
     {
+        tracing::Span::current().record("session", token);
         let mut sessions = self.sessions.lock().unwrap();
         let entry = sessions.get_mut(token).ok_or(StatusCode::BAD_REQUEST)?;
         entry.last_access = Instant::now();
-        Ok(f(&mut entry.session))
+        let started = Instant::now();
+        let result = f(&mut entry.session);
+        if let Some(metrics) = &self.metrics {
+            metrics.record_session_call(started.elapsed());
+        }
+        Ok(result)
+    }
+
+    /// Like `with_session`, for actions that load a page: the session's
+    /// timings are returned along with the page.
+    fn with_page<F>(&self, token: &str, f: F) -> PageResult
+    where
+        F: FnOnce(&mut Session) -> Result<output::SpatialDom, FetchError>,
+    {
+        let result = self.with_session(token, |session| {
+            f(session).map(|dom| (dom, session.timings().cloned()))
+        });
+        if let (Some(metrics), Ok(Ok((dom, timings)))) = (&self.metrics, &result) {
+            metrics.record_page(dom, timings.as_ref());
+        }
+        result
+    }
+
+    /// Count a failed session action and map it to an error response.
+    fn fetch_error(&self, e: FetchError) -> (StatusCode, Json<ErrorResponse>) {
+        if let Some(metrics) = &self.metrics {
+            metrics.record_fetch_error(&e);
+        }
+        map_fetch_error(e)
+    }
+
+    /// Copy the session for `token` into a new session and return the new
+    /// token. `BAD_REQUEST`, like `with_session`, if there is no such session.
+    fn fork_session(&self, token: &str) -> Result<String, StatusCode> {
+        tracing::Span::current().record("session", token);
+        let mut sessions = self.sessions.lock().unwrap();
+        let timeout = self.config.session_timeout;
+        sessions.retain(|_, entry| entry.last_access.elapsed() < timeout);
+
+        let entry = sessions.get_mut(token).ok_or(StatusCode::BAD_REQUEST)?;
+        entry.last_access = Instant::now();
+        let session = entry
+            .session
+            .fork()
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if sessions.len() >= self.config.max_sessions {
+            return Err(StatusCode::SERVICE_UNAVAILABLE);
+        }
+        let new_token = Uuid::new_v4().to_string();
+        sessions.insert(
+            new_token.clone(),
+            SessionEntry {
+                session,
+                last_access: Instant::now(),
+            },
+        );
+        Ok(new_token)
+    }
+
+    /// Time left before the session expires, or `None` once it has (in
+    /// which case it is dropped).
+    fn session_time_left(&self, token: &str) -> Option<Duration> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let idle = sessions.get(token)?.last_access.elapsed();
+        let left = self.config.session_timeout.checked_sub(idle).filter(|d| !d.is_zero());
+        if left.is_none() {
+            sessions.remove(token);
+        }
+        left
+    }
+
+    /// Sessions that have not expired yet.
+    fn active_sessions(&self) -> usize {
+        let timeout = self.config.session_timeout;
+        self.sessions
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|entry| entry.last_access.elapsed() < timeout)
+            .count()
     }
 }
 
@@ -120,102 +333,334 @@ impl AppState {
 // Request / response types
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct BrowseParams {
+    #[schemars(description = "URL to navigate to")]
     pub url: String,
+    #[schemars(description = "Output format: 'compact' (default), 'json', or 'map' (an ASCII layout map with a legend of the IDs on it)")]
+    pub format: Option<String>,
+    #[schemars(description = "Scope: 'all' (default), 'visible', 'on_screen', 'above_fold', 'visible_above_fold', 'fold_plus' (half a viewport past the fold), 'main' (the main content, without the header, nav, sidebars and footer), or 'new_content' (leave out what the previous page on the same site also had)")]
+    pub scope: Option<String>,
+    #[schemars(description = "Include a CSS selector hint on each element (JSON format only); computing them is kept on or off for later requests in the session")]
+    pub selectors: Option<bool>,
+    #[schemars(description = "Dismiss a cookie consent banner on the loaded page: 'accept' or 'reject' (default: leave it)")]
+    pub auto_consent: Option<String>,
+    #[schemars(description = "Revalidate a page this session fetched before with its ETag/Last-Modified; if unchanged, return it again with an X-Browsy-Not-Modified header")]
+    pub if_modified: Option<bool>,
+    #[schemars(description = "Viewport as WIDTHxHEIGHT in CSS pixels, like '375x812'; kept for later requests in the session")]
+    pub viewport: Option<String>,
+    #[schemars(description = "Fetch external stylesheets (default: the server's setting); kept for later requests in the session")]
+    pub fetch_css: Option<bool>,
+    #[schemars(description = "User-Agent header to send; kept for later requests in the session")]
+    pub user_agent: Option<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct BrowseStreamParams {
+    #[serde(flatten)]
+    pub browse: BrowseParams,
+    #[schemars(description = "Elements per 'elements' event (default 500). Streams are 'compact' lines or, with format 'json', one JSON element per line")]
+    pub batch_size: Option<usize>,
+}
+
+/// Elements per event of `POST /api/browse/stream` when the request doesn't
+/// say.
+pub const DEFAULT_STREAM_BATCH: usize = 500;
+
+/// Events `POST /api/browse/stream` holds while the client catches up.
+const STREAM_BUFFER: usize = 4;
+
+/// Largest HTML document accepted by `POST /api/parse`, matching the
+/// response limit for fetched pages.
+pub const MAX_PARSE_HTML_BYTES: usize = 5 * 1024 * 1024;
+
+/// Most pages `POST /api/crawl` visits, so a crawl finishes within one request.
+pub const MAX_CRAWL_PAGES: usize = 50;
+
+/// Largest viewport dimension a request may ask for.
+const MAX_VIEWPORT_PX: f32 = 10_000.0;
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ParseParams {
+    #[schemars(description = "Raw HTML document to analyze")]
+    pub html: String,
+    #[schemars(description = "URL the HTML came from, used to resolve relative links (default 'about:blank', which leaves them unresolved)")]
+    pub url: Option<String>,
+    #[schemars(description = "Viewport as [width, height] in CSS pixels; kept for later requests in the session")]
+    pub viewport: Option<[f32; 2]>,
+    #[schemars(description = "Output format: 'compact' (default), 'json', or 'map' (an ASCII layout map with a legend of the IDs on it)")]
     pub format: Option<String>,
+    #[schemars(description = "Scope: 'all' (default), 'visible', 'on_screen', 'above_fold', 'visible_above_fold', 'fold_plus' (half a viewport past the fold), 'main' (the main content, without the header, nav, sidebars and footer), or 'new_content' (leave out what the previous page on the same site also had)")]
     pub scope: Option<String>,
+    #[schemars(description = "Include a CSS selector hint on each element (JSON format only); computing them is kept on or off for later requests in the session")]
+    pub selectors: Option<bool>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct ClickParams {
+    #[schemars(description = "Element ID to click")]
+    pub id: u32,
+    #[schemars(description = "Take the click even if the server's action policy would hold it for confirmation")]
+    pub confirmed: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PreviewParams {
+    #[schemars(description = "Element ID to click, or with submit, a submit button or input")]
     pub id: u32,
+    #[schemars(description = "Preview submitting the element's form, as press-enter does for an input, instead of clicking it")]
+    pub submit: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PressEnterParams {
+    #[schemars(description = "Element ID of the input to press Enter in")]
+    pub id: u32,
+    #[schemars(description = "Submit even if the server's action policy would hold it for confirmation")]
+    pub confirmed: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ConfirmParams {
+    #[schemars(description = "Token from a confirmation_required error")]
+    pub token: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct TypeTextParams {
+    #[schemars(description = "Element ID of the text input")]
     pub id: u32,
+    #[schemars(description = "Text to type into the input")]
     pub text: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct CheckParams {
+    #[schemars(description = "Element ID of the checkbox or radio button")]
     pub id: u32,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct SelectParams {
+    #[schemars(description = "Element ID of the select element")]
     pub id: u32,
+    #[schemars(description = "Value to select")]
     pub value: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AnnotateParams {
+    #[schemars(description = "Element ID to leave the note on")]
+    pub id: u32,
+    #[schemars(description = "The note; an empty note removes the element's note")]
+    pub note: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct SearchParams {
+    #[schemars(description = "Search query")]
     pub query: String,
+    #[schemars(description = "Search engine: 'duckduckgo' (default) or 'google'")]
     pub engine: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SitemapParams {
+    #[schemars(description = "URL of a sitemap or sitemap index")]
+    pub url: String,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct PaginateParams {
+    #[schemars(description = "What to collect from each page: 'tables', 'lists' or 'links'")]
+    pub extract: String,
+    #[schemars(description = "Most pages to read, counting the current one (default 10)")]
+    pub max_pages: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CrawlParams {
+    #[schemars(description = "URL to start crawling from")]
+    pub url: String,
+    #[schemars(description = "Most pages to visit (default 50, at most 50)")]
+    pub max_pages: Option<usize>,
+    #[schemars(description = "Most links to follow from the start page (default 3)")]
+    pub max_depth: Option<usize>,
+    #[schemars(description = "Only follow links to the start page's host (default true)")]
+    pub same_host_only: Option<bool>,
+    #[schemars(description = "Only follow links whose URL contains one of these")]
+    #[serde(default)]
+    pub include_patterns: Vec<String>,
+    #[schemars(description = "Never follow links whose URL contains one of these")]
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>,
+    #[schemars(description = "Skip pages robots.txt disallows (default true)")]
+    pub respect_robots: Option<bool>,
+}
+
+impl CrawlParams {
+    fn opts(self) -> Result<(String, CrawlOpts), String> {
+        let defaults = CrawlOpts::default();
+        let max_pages = self.max_pages.unwrap_or(MAX_CRAWL_PAGES);
+        if max_pages > MAX_CRAWL_PAGES {
+            return Err(format!("max_pages {max_pages} is over the limit of {MAX_CRAWL_PAGES}"));
+        }
+        let opts = CrawlOpts {
+            max_pages,
+            max_depth: self.max_depth.unwrap_or(defaults.max_depth),
+            same_host_only: self.same_host_only.unwrap_or(defaults.same_host_only),
+            include_patterns: self.include_patterns,
+            exclude_patterns: self.exclude_patterns,
+            respect_robots: self.respect_robots.unwrap_or(defaults.respect_robots),
+        };
+        Ok((self.url, opts))
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct LoginParams {
+    #[schemars(description = "Username or email")]
     pub username: String,
+    #[schemars(description = "Password")]
     pub password: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct EnterCodeParams {
+    #[schemars(description = "Verification or 2FA code")]
     pub code: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct FindParams {
+    #[schemars(description = "Find elements containing this text, ignoring case and extra whitespace; whole-text matches come first")]
     pub text: Option<String>,
+    #[schemars(description = "Find elements with this ARIA role, or any of a comma-separated list such as button,link")]
     pub role: Option<String>,
+    #[schemars(description = "Include a CSS selector hint on each element, if the page was loaded with selectors on")]
+    pub selectors: Option<bool>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct GetPageQuery {
+    #[schemars(description = "Output format: 'compact' (default), 'json', or 'map' (an ASCII layout map with a legend of the IDs on it)")]
     pub format: Option<String>,
+    #[schemars(description = "Scope: 'all' (default), 'visible', 'on_screen', 'above_fold', 'visible_above_fold', 'fold_plus' (half a viewport past the fold), 'main' (the main content, without the header, nav, sidebars and footer), or 'new_content' (leave out what the previous page on the same site also had)")]
     pub scope: Option<String>,
+    #[schemars(description = "Include a CSS selector hint on each element, if the page was loaded with selectors on (JSON format only)")]
+    pub selectors: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SessionConfigParams {
+    #[schemars(description = "Only navigate to these domains and their subdomains ('*.example.com' for subdomains only). An empty list allows every domain")]
+    pub allowed_domains: Option<Vec<String>>,
+    #[schemars(description = "Never navigate to these domains or their subdomains")]
+    pub blocked_domains: Option<Vec<String>>,
+    #[schemars(description = "Viewport as WIDTHxHEIGHT in CSS pixels, like '375x812'. The current page is laid out again at the new size without being fetched")]
+    pub viewport: Option<String>,
+    #[schemars(description = "Fetch external stylesheets on later page loads")]
+    pub fetch_css: Option<bool>,
+    #[schemars(description = "User-Agent header to send on later requests")]
+    pub user_agent: Option<String>,
+    #[schemars(description = "Compute a CSS selector hint for each element on later page loads (default off), for requests with selectors=true to show")]
+    pub selectors: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ImportCookiesParams {
+    /// Cookies as `POST /api/cookies/export` returns them.
+    pub cookies: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct AuditQuery {
+    #[schemars(description = "Only return events with a sequence number greater than this")]
+    pub since_seq: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ElementContextQuery {
+    #[schemars(description = "How far from the element to look for neighbors, in CSS pixels (default 50)")]
+    pub radius: Option<i32>,
 }
 
-#[derive(Debug, Serialize)]
+/// Body of every error response: `{"error": {"code", "message", "details"}}`.
+#[derive(Debug, Serialize, JsonSchema)]
 struct ErrorResponse {
-    error: String,
+    error: ErrorBody,
 }
 
-// ---------------------------------------------------------------------------
-// Output helpers (mirroring browsy-mcp)
-// ---------------------------------------------------------------------------
+#[derive(Debug, Serialize, JsonSchema)]
+struct ErrorBody {
+    /// What went wrong, for clients to branch on (`no_page_loaded`).
+    code: String,
+    message: String,
+    /// The error's particulars, such as the range of element IDs the page
+    /// has when an ID wasn't found.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    details: Option<serde_json::Value>,
+}
 
-fn format_page(dom: &output::SpatialDom, format: Option<&str>) -> String {
-    match format {
-        Some("json") => serde_json::to_string_pretty(dom).unwrap_or_default(),
-        _ => {
-            let mut header = format!(
-                "title: {}\nurl: {}\nels: {}\n---\n",
-                dom.title,
-                dom.url,
-                dom.els.len()
-            );
-            header.push_str(&output::to_compact_string(dom));
-            header
+impl ErrorResponse {
+    fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            error: ErrorBody {
+                code: code.to_string(),
+                message: message.into(),
+                details: None,
+            },
         }
     }
 }
 
-fn strip_hidden(mut dom: output::SpatialDom) -> output::SpatialDom {
-    dom.els.retain(|e| e.hidden != Some(true));
-    dom.rebuild_index();
+impl From<&FetchError> for ErrorResponse {
+    fn from(e: &FetchError) -> Self {
+        let mut response = Self::new(e.code(), e.to_string());
+        response.error.details = e.details();
+        response
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Output helpers
+// ---------------------------------------------------------------------------
+
+/// Drop selector hints unless the caller explicitly asked for them.
+fn apply_selectors(mut dom: output::SpatialDom, selectors: Option<bool>) -> output::SpatialDom {
+    if selectors != Some(true) {
+        for el in &mut dom.els {
+            el.selector = None;
+        }
+    }
     dom
 }
 
-fn apply_scope(dom: output::SpatialDom, scope: Option<&str>) -> output::SpatialDom {
-    match scope.unwrap_or("all") {
-        "visible" => strip_hidden(dom),
-        "above_fold" => dom.filter_above_fold(),
-        "visible_above_fold" => strip_hidden(dom).filter_above_fold(),
-        _ => dom,
+/// A page in the requested scope and format.
+fn render_page(
+    dom: output::SpatialDom,
+    scope: Option<&str>,
+    selectors: Option<bool>,
+    format: Option<&str>,
+) -> String {
+    let view = dom.view(output::Scope::from_param(scope));
+    if format == Some("json") {
+        return format_page(&apply_selectors(view.to_dom(), selectors), format);
     }
+    format_view(&view, format)
+}
+
+/// A page an action just loaded: `render_page` behind the CAPTCHA warning,
+/// if there is one.
+fn render_loaded_page(
+    dom: output::SpatialDom,
+    scope: Option<&str>,
+    selectors: Option<bool>,
+    format: Option<&str>,
+) -> String {
+    let mut text = captcha_warning(&dom).unwrap_or_default();
+    text.push_str(&render_page(dom, scope, selectors, format));
+    text
 }
 
 fn captcha_warning(dom: &output::SpatialDom) -> Option<String> {
@@ -237,19 +682,33 @@ fn captcha_warning(dom: &output::SpatialDom) -> Option<String> {
 
 fn map_fetch_error(e: FetchError) -> (StatusCode, Json<ErrorResponse>) {
     let status = match &e {
-        FetchError::InvalidUrl(_) | FetchError::ActionError(_) | FetchError::BlockedUrl(_) => {
-            StatusCode::BAD_REQUEST
-        }
+        FetchError::InvalidUrl(_)
+        | FetchError::BlockedUrl(_)
+        | FetchError::ActionError(_)
+        | FetchError::ElementNotFound { .. }
+        | FetchError::WrongElementType { .. }
+        | FetchError::ElementUnavailable { .. }
+        | FetchError::NoPageLoaded => StatusCode::BAD_REQUEST,
+        FetchError::ConfirmationRequired { .. } => StatusCode::CONFLICT,
+        FetchError::BinaryContent { .. } => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+        // The site failed or misbehaved, not browsy
         FetchError::Network(_)
         | FetchError::HttpError(_)
-        | FetchError::ResponseTooLarge(_, _) => StatusCode::INTERNAL_SERVER_ERROR,
+        | FetchError::ResponseTooLarge(_, _)
+        | FetchError::TooManyRedirects { .. } => StatusCode::BAD_GATEWAY,
+        FetchError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
     };
-    (
-        status,
-        Json(ErrorResponse {
-            error: e.to_string(),
-        }),
-    )
+    (status, Json(ErrorResponse::from(&e)))
+}
+
+/// The response when a request's session can't be created or has gone.
+fn session_failure(status: StatusCode) -> axum::response::Response {
+    let (code, message) = match status {
+        StatusCode::SERVICE_UNAVAILABLE => (ErrorCode::SessionLimit, "Too many sessions open"),
+        StatusCode::INTERNAL_SERVER_ERROR => (ErrorCode::Internal, "Session creation failed"),
+        _ => (ErrorCode::SessionExpired, "Session expired"),
+    };
+    (status, Json(ErrorResponse::new(code, message))).into_response()
 }
 
 // ---------------------------------------------------------------------------
@@ -277,6 +736,18 @@ fn session_text_response(token: &str, status: StatusCode, text: String) -> impl
     (status, headers, text)
 }
 
+/// A page response, with per-phase timings of the load in `X-Browsy-Timing`.
+fn page_response(token: &str, text: String, timings: Option<&Timings>) -> axum::response::Response {
+    let mut response = session_text_response(token, StatusCode::OK, text).into_response();
+    let header = timings
+        .and_then(|t| serde_json::to_string(t).ok())
+        .and_then(|json| HeaderValue::from_str(&json).ok());
+    if let Some(value) = header {
+        response.headers_mut().insert("X-Browsy-Timing", value);
+    }
+    response
+}
+
 // ---------------------------------------------------------------------------
 // Blocking helper
 // ---------------------------------------------------------------------------
@@ -286,12 +757,13 @@ fn session_text_response(token: &str, status: StatusCode, text: String) -> impl
 /// `browsy_core::Session` uses `reqwest::blocking::Client` which has its own
 /// internal tokio Runtime. This Runtime cannot be created or dropped inside
 /// another async context. All session operations must therefore run on a
-/// dedicated blocking thread.
+/// dedicated blocking thread. The closure runs inside the request's span.
 async fn run_blocking<F>(f: F) -> axum::response::Response
 where
     F: FnOnce() -> axum::response::Response + Send + 'static,
 {
-    match tokio::task::spawn_blocking(f).await {
+    let span = tracing::Span::current();
+    match tokio::task::spawn_blocking(move || span.in_scope(f)).await {
         Ok(response) => response,
         Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
     }
@@ -301,33 +773,87 @@ where
 // Router
 // ---------------------------------------------------------------------------
 
+/// CORS for browser-based clients. Preflights are answered here, before
+/// routing and authentication. The session token travels in a custom header,
+/// so it must be both allowed on requests and exposed on responses.
+fn cors_layer(origins: &[String]) -> Option<CorsLayer> {
+    if origins.is_empty() {
+        return None;
+    }
+    let allow_origin = if origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(origins.iter().filter_map(|o| HeaderValue::from_str(o).ok()))
+    };
+    let header = axum::http::HeaderName::from_static;
+    Some(
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([Method::GET, Method::POST])
+            .allow_headers([
+                axum::http::header::CONTENT_TYPE,
+                axum::http::header::AUTHORIZATION,
+                header("x-api-key"),
+                header("x-browsy-session"),
+            ])
+            .expose_headers([
+                header("x-browsy-session"),
+                header("x-browsy-timing"),
+                header("x-browsy-login-status"),
+                axum::http::header::RETRY_AFTER,
+            ])
+            .max_age(Duration::from_secs(600)),
+    )
+}
+
 /// Build the axum router.
 pub fn build_router(state: Arc<AppState>) -> Router {
-    let cors = CorsLayer::new()
-        .allow_origin(cors::Any)
-        .allow_methods(cors::Any)
-        .allow_headers(cors::Any)
-        .expose_headers([axum::http::HeaderName::from_static("x-browsy-session")]);
-
-    Router::new()
-        .route("/health", get(health))
-        .route("/api/browse", post(browse))
-        .route("/api/click", post(click))
-        .route("/api/type", post(type_text))
-        .route("/api/check", post(check))
-        .route("/api/uncheck", post(uncheck))
-        .route("/api/select", post(select))
-        .route("/api/search", post(search))
-        .route("/api/login", post(login))
-        .route("/api/enter-code", post(enter_code))
-        .route("/api/find", post(find))
-        .route("/api/page", get(get_page))
-        .route("/api/page-info", get(page_info))
-        .route("/api/tables", get(tables))
-        .route("/api/back", post(back))
+    // REST routes come from the capability registry so the agent card can't drift
+    let mut router = Router::new();
+    for (capability, handler) in capabilities::registry() {
+        router = router.route(capability.path, handler);
+    }
+
+    router = router
         .merge(a2a::a2a_routes())
-        .layer(cors)
-        .with_state(state)
+        .route_layer(middleware::from_fn_with_state(state.clone(), auth::authorize))
+        .route_layer(middleware::from_fn_with_state(state.clone(), observe_request));
+    if state.config.enable_metrics {
+        router = router.route("/metrics", get(prometheus_metrics));
+    }
+
+    if let Some(cors) = cors_layer(&state.config.cors_origins) {
+        router = router.layer(cors);
+    }
+    router.with_state(state)
+}
+
+/// Wrap each request in a span carrying its route, and the session token
+/// once the handler has resolved one. Also counts and times the request.
+async fn observe_request(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> axum::response::Response {
+    let started = Instant::now();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| request.uri().path().to_string());
+    let span = tracing::info_span!(
+        "request",
+        method = %request.method(),
+        route = %route,
+        session = tracing::field::Empty,
+        status = tracing::field::Empty,
+    );
+    let response = next.run(request).instrument(span.clone()).await;
+    span.record("status", response.status().as_u16());
+    if let Some(metrics) = &state.metrics {
+        metrics.record_request(&route, started.elapsed());
+    }
+    response
 }
 
 // ---------------------------------------------------------------------------
@@ -338,169 +864,605 @@ async fn health() -> &'static str {
     "ok"
 }
 
+/// GET /openapi.json
+async fn openapi_spec(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    Json(state.openapi.clone())
+}
+
+/// GET /metrics  (Prometheus text format)
+async fn prometheus_metrics(State(state): State<Arc<AppState>>) -> axum::response::Response {
+    let Some(metrics) = &state.metrics else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let body = metrics.render(state.active_sessions());
+    (
+        [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        body,
+    )
+        .into_response()
+}
+
 /// POST /api/browse  { url, format?, scope? }
 async fn browse(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
     Json(params): Json<BrowseParams>,
 ) -> axum::response::Response {
+    browse_page(state, headers, params).await
+}
+
+/// GET /api/browse  ?url=&format=&scope=
+async fn browse_get(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<BrowseParams>,
+) -> axum::response::Response {
+    browse_page(state, headers, params).await
+}
+
+/// Navigate and render the page for both forms of `/api/browse`, so the
+/// cacheable GET can't drift from the POST.
+async fn browse_page(
+    state: Arc<AppState>,
+    headers: HeaderMap,
+    params: BrowseParams,
+) -> axum::response::Response {
+    let options = match BrowseOptions::from_params(&params) {
+        Ok(options) => options,
+        Err(error) => {
+            return (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(ErrorCode::InvalidRequest, error))).into_response();
+        }
+    };
+
     run_blocking(move || {
         let token = match state.get_or_create_session(&headers) {
             Ok(t) => t,
-            Err(s) => {
-                return session_text_response("", s, "session creation failed".into())
-                    .into_response()
-            }
+            Err(s) => return session_failure(s),
         };
 
-        let result = state.with_session(&token, |session| session.goto(&params.url));
-        match result {
-            Ok(Ok(dom)) => {
-                let mut text = captcha_warning(&dom).unwrap_or_default();
-                let scoped = apply_scope(dom, params.scope.as_deref());
-                text.push_str(&format_page(&scoped, params.format.as_deref()));
-                session_text_response(&token, StatusCode::OK, text).into_response()
+        match browse_session(&state, &token, &params, &options) {
+            Ok(Ok((dom, timings))) => {
+                let not_modified = dom.not_modified;
+                let text = render_loaded_page(
+                    dom,
+                    params.scope.as_deref(),
+                    params.selectors,
+                    params.format.as_deref(),
+                );
+                let mut response = page_response(&token, text, timings.as_ref());
+                if not_modified {
+                    response
+                        .headers_mut()
+                        .insert("X-Browsy-Not-Modified", HeaderValue::from_static("true"));
+                }
+                response
             }
             Ok(Err(e)) => {
-                let (status, body) = map_fetch_error(e);
+                let (status, body) = state.fetch_error(e);
                 session_response(&token, status, body.0).into_response()
             }
-            Err(s) => session_text_response("", s, "session error".into()).into_response(),
+            Err(s) => session_failure(s),
         }
     })
     .await
 }
 
-/// POST /api/click  { id }
-async fn click(
+/// POST /api/browse/stream  { url, format?, scope?, batch_size? }
+///
+/// Loads the page like `/api/browse`, then sends it as Server-Sent Events:
+/// `meta` with the page header, `elements` with up to `batch_size` lines
+/// each, and `done` with the totals. The session is free again before the
+/// first event, so a client that hangs up mid-stream leaves it usable.
+async fn browse_stream(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
-    Json(params): Json<ClickParams>,
+    Json(params): Json<BrowseStreamParams>,
 ) -> axum::response::Response {
+    let invalid = |error: String| {
+        (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(ErrorCode::InvalidRequest, error))).into_response()
+    };
+    let ndjson = match params.browse.format.as_deref() {
+        None | Some("compact") => false,
+        Some("json") => true,
+        Some(other) => return invalid(format!("Invalid format: {other:?} can't be streamed (use compact or json)")),
+    };
+    let batch_size = params.batch_size.unwrap_or(DEFAULT_STREAM_BATCH);
+    if batch_size == 0 {
+        return invalid("Invalid batch_size: must be at least 1".to_string());
+    }
+    let options = match BrowseOptions::from_params(&params.browse) {
+        Ok(options) => options,
+        Err(error) => return invalid(error),
+    };
+
     run_blocking(move || {
         let token = match state.get_or_create_session(&headers) {
             Ok(t) => t,
-            Err(s) => {
-                return session_text_response("", s, "session creation failed".into())
-                    .into_response()
-            }
+            Err(s) => return session_failure(s),
         };
 
-        let result = state.with_session(&token, |session| session.click(params.id));
-        match result {
-            Ok(Ok(dom)) => {
-                let mut text = captcha_warning(&dom).unwrap_or_default();
-                text.push_str(&format_page(&dom, None));
-                session_text_response(&token, StatusCode::OK, text).into_response()
+        match browse_session(&state, &token, &params.browse, &options) {
+            Ok(Ok((dom, _))) => {
+                let dom = apply_selectors(dom, params.browse.selectors);
+                let scope = output::Scope::from_param(params.browse.scope.as_deref());
+                let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+                tokio::task::spawn_blocking(move || stream_page(&dom.view(scope), ndjson, batch_size, &tx));
+                let sse = Sse::new(ReceiverStream::new(rx)).keep_alive(KeepAlive::default());
+                let mut response = sse.into_response();
+                if let Ok(val) = HeaderValue::from_str(&token) {
+                    response.headers_mut().insert("X-Browsy-Session", val);
+                }
+                response
             }
             Ok(Err(e)) => {
-                let (status, body) = map_fetch_error(e);
+                let (status, body) = state.fetch_error(e);
                 session_response(&token, status, body.0).into_response()
             }
-            Err(s) => session_text_response("", s, "session error".into()).into_response(),
+            Err(s) => session_failure(s),
         }
     })
     .await
 }
 
-/// POST /api/type  { id, text }
-async fn type_text(
+/// Send a page as the events of `/api/browse/stream`. Lines are made as
+/// they are sent, and sending stops as soon as the client is gone.
+fn stream_page(
+    view: &output::DomView,
+    ndjson: bool,
+    batch_size: usize,
+    tx: &mpsc::Sender<Result<Event, Infallible>>,
+) {
+    let send = |name: &str, data: String| tx.blocking_send(Ok(Event::default().event(name).data(data))).is_ok();
+    let dom = view.dom();
+
+    let meta = serde_json::json!({
+        "title": dom.title,
+        "url": dom.url,
+        "lang": dom.lang,
+        "page_type": dom.page_type,
+        "els": view.len(),
+        "suggested_actions": dom.suggested_actions,
+        "auto_actions": dom.auto_actions,
+        "warnings": dom.warnings,
+        "boilerplate_suppressed": dom.boilerplate_suppressed,
+        "outside_main": view.outside_main(),
+    });
+    if !send("meta", meta.to_string()) {
+        return;
+    }
+
+    let mut lines: Box<dyn Iterator<Item = String> + '_> = if ndjson {
+        Box::new(view.ndjson_lines())
+    } else {
+        Box::new(view.compact_lines())
+    };
+    let mut batches = 0;
+    loop {
+        let batch: Vec<String> = lines.by_ref().take(batch_size).collect();
+        if batch.is_empty() {
+            break;
+        }
+        if !send("elements", batch.join("\n")) {
+            return;
+        }
+        batches += 1;
+    }
+    send("done", serde_json::json!({ "elements": view.len(), "batches": batches }).to_string());
+}
+
+/// The parts of `BrowseParams` checked before the session is touched.
+struct BrowseOptions {
+    auto_consent: Option<ConsentChoice>,
+    settings: SessionSettings,
+}
+
+impl BrowseOptions {
+    fn from_params(params: &BrowseParams) -> Result<Self, String> {
+        let auto_consent = params.auto_consent.as_deref().map(str::parse).transpose()?;
+        let settings = SessionSettings::new(
+            params.viewport.as_deref(),
+            params.fetch_css,
+            params.user_agent.as_deref(),
+            params.selectors,
+        )?;
+        Ok(Self { auto_consent, settings })
+    }
+}
+
+/// Session settings a request may change. They stay with the session for
+/// its later requests.
+#[derive(Debug, Default)]
+struct SessionSettings {
+    viewport: Option<[f32; 2]>,
+    fetch_css: Option<bool>,
+    user_agent: Option<String>,
+    selectors: Option<bool>,
+}
+
+impl SessionSettings {
+    fn new(
+        viewport: Option<&str>,
+        fetch_css: Option<bool>,
+        user_agent: Option<&str>,
+        selectors: Option<bool>,
+    ) -> Result<Self, String> {
+        let viewport = viewport.map(parse_viewport).transpose()?;
+        if let Some(agent) = user_agent {
+            if agent.trim().is_empty() || HeaderValue::from_str(agent).is_err() {
+                return Err(format!("Invalid user_agent: {agent:?} is not a usable header value"));
+            }
+        }
+        Ok(Self {
+            viewport,
+            fetch_css,
+            user_agent: user_agent.map(str::to_string),
+            selectors,
+        })
+    }
+
+    /// Everything but the viewport, which callers either set for the next
+    /// page load or resize the current page to.
+    fn apply_fetch_settings(&self, session: &mut Session) -> Result<(), FetchError> {
+        if let Some(fetch_css) = self.fetch_css {
+            session.set_fetch_css(fetch_css);
+        }
+        if let Some(agent) = &self.user_agent {
+            session.set_user_agent(agent)?;
+        }
+        if let Some(selectors) = self.selectors {
+            session.set_emit_selectors(selectors);
+        }
+        Ok(())
+    }
+}
+
+fn viewport_in_range(width: f32, height: f32) -> bool {
+    let valid = |v: f32| v.is_finite() && (1.0..=MAX_VIEWPORT_PX).contains(&v);
+    valid(width) && valid(height)
+}
+
+/// Parse a `WIDTHxHEIGHT` viewport such as `375x812`.
+fn parse_viewport(value: &str) -> Result<[f32; 2], String> {
+    let [width, height] = value
+        .split_once(['x', 'X'])
+        .and_then(|(w, h)| Some([w.trim().parse::<f32>().ok()?, h.trim().parse::<f32>().ok()?]))
+        .ok_or_else(|| format!("Invalid viewport: {value:?} (expected WIDTHxHEIGHT, like \"375x812\")"))?;
+    if !viewport_in_range(width, height) {
+        return Err(format!(
+            "Invalid viewport: {value:?} (width and height must be between 1 and {MAX_VIEWPORT_PX})"
+        ));
+    }
+    Ok([width, height])
+}
+
+/// Navigate the session as `/api/browse` asks. Shared with the WebSocket
+/// `browse` command.
+fn browse_session(
+    state: &AppState,
+    token: &str,
+    params: &BrowseParams,
+    options: &BrowseOptions,
+) -> PageResult {
+    let if_modified = params.if_modified.unwrap_or(false);
+    let auto_consent = options.auto_consent;
+    state.with_page(token, |session| {
+        if let Some([width, height]) = options.settings.viewport {
+            session.set_viewport(width, height);
+        }
+        options.settings.apply_fetch_settings(session)?;
+        let goto = |session: &mut Session| {
+            if if_modified {
+                session.goto_if_modified(&params.url)
+            } else {
+                session.goto(&params.url)
+            }
+        };
+        let result = if auto_consent.is_none() {
+            goto(session)
+        } else {
+            // Applies to this navigation only
+            let previous = session.auto_dismiss_cookie_banners();
+            session.set_auto_dismiss_cookie_banners(auto_consent);
+            let result = goto(session);
+            session.set_auto_dismiss_cookie_banners(previous);
+            result
+        };
+        result.map(|dom| session.scoped(dom, params.scope.as_deref()))
+    })
+}
+
+/// POST /api/parse  { html, url?, viewport?, format?, scope? }
+async fn parse(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
-    Json(params): Json<TypeTextParams>,
+    Json(params): Json<ParseParams>,
 ) -> axum::response::Response {
+    if params.html.len() > MAX_PARSE_HTML_BYTES {
+        let body = ErrorResponse::new(
+            ErrorCode::InvalidRequest,
+            format!("HTML too large: {} bytes (max {})", params.html.len(), MAX_PARSE_HTML_BYTES),
+        );
+        return (StatusCode::PAYLOAD_TOO_LARGE, Json(body)).into_response();
+    }
+    if let Some([width, height]) = params.viewport {
+        if !viewport_in_range(width, height) {
+            let body = ErrorResponse::new(
+                ErrorCode::InvalidRequest,
+                format!("Invalid viewport: [{width}, {height}]"),
+            );
+            return (StatusCode::BAD_REQUEST, Json(body)).into_response();
+        }
+    }
+
     run_blocking(move || {
         let token = match state.get_or_create_session(&headers) {
             Ok(t) => t,
-            Err(s) => {
-                return session_text_response("", s, "session creation failed".into())
-                    .into_response()
-            }
+            Err(s) => return session_failure(s),
         };
 
-        let result =
-            state.with_session(&token, |session| session.type_text(params.id, &params.text));
+        let url = params.url.as_deref().unwrap_or("about:blank");
+        let result = state.with_page(&token, |session| {
+            if let Some([width, height]) = params.viewport {
+                session.set_viewport(width, height);
+            }
+            if let Some(selectors) = params.selectors {
+                session.set_emit_selectors(selectors);
+            }
+            session
+                .load_html_offline(&params.html, url)
+                .map(|dom| session.scoped(dom, params.scope.as_deref()))
+        });
         match result {
-            Ok(Ok(())) => {
-                let body = serde_json::json!({
-                    "ok": true,
-                    "message": format!("Typed {:?} into element {}", params.text, params.id)
-                });
-                session_response(&token, StatusCode::OK, body).into_response()
+            Ok(Ok((dom, timings))) => {
+                let text = render_loaded_page(
+                    dom,
+                    params.scope.as_deref(),
+                    params.selectors,
+                    params.format.as_deref(),
+                );
+                page_response(&token, text, timings.as_ref())
             }
             Ok(Err(e)) => {
-                let (status, body) = map_fetch_error(e);
+                let (status, body) = state.fetch_error(e);
                 session_response(&token, status, body.0).into_response()
             }
-            Err(s) => session_text_response("", s, "session error".into()).into_response(),
+            Err(s) => session_failure(s),
         }
     })
     .await
 }
 
-/// POST /api/check  { id }
-async fn check(
+/// POST /api/click  { id, confirmed? }
+async fn click(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
-    Json(params): Json<CheckParams>,
+    Json(params): Json<ClickParams>,
 ) -> axum::response::Response {
     run_blocking(move || {
         let token = match state.get_or_create_session(&headers) {
             Ok(t) => t,
-            Err(s) => {
-                return session_text_response("", s, "session creation failed".into())
-                    .into_response()
-            }
+            Err(s) => return session_failure(s),
         };
 
-        let result = state.with_session(&token, |session| session.check(params.id));
+        let result = state.with_page(&token, |session| {
+            if params.confirmed == Some(true) {
+                session.confirmed(|session| session.click(params.id))
+            } else {
+                session.click(params.id)
+            }
+        });
         match result {
-            Ok(Ok(())) => {
-                let body = serde_json::json!({
-                    "ok": true,
-                    "message": format!("Checked element {}", params.id)
-                });
-                session_response(&token, StatusCode::OK, body).into_response()
+            Ok(Ok((dom, timings))) => {
+                let text = render_loaded_page(dom, None, None, None);
+                page_response(&token, text, timings.as_ref())
             }
             Ok(Err(e)) => {
-                let (status, body) = map_fetch_error(e);
+                let (status, body) = state.fetch_error(e);
                 session_response(&token, status, body.0).into_response()
             }
-            Err(s) => session_text_response("", s, "session error".into()).into_response(),
+            Err(s) => session_failure(s),
         }
     })
     .await
 }
 
-/// POST /api/uncheck  { id }
-async fn uncheck(
+/// POST /api/preview  { id, submit? }
+///
+/// Responds with what clicking or submitting would do, as JSON, without
+/// sending anything or changing the session.
+async fn preview(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
-    Json(params): Json<CheckParams>,
+    Json(params): Json<PreviewParams>,
 ) -> axum::response::Response {
     run_blocking(move || {
         let token = match state.get_or_create_session(&headers) {
             Ok(t) => t,
-            Err(s) => {
-                return session_text_response("", s, "session creation failed".into())
-                    .into_response()
-            }
+            Err(s) => return session_failure(s),
         };
 
-        let result = state.with_session(&token, |session| session.uncheck(params.id));
+        let result = state.with_session(&token, |session| {
+            if params.submit == Some(true) {
+                session.preview_submit(params.id)
+            } else {
+                session.preview_click(params.id)
+            }
+        });
         match result {
-            Ok(Ok(())) => {
-                let body = serde_json::json!({
+            Ok(Ok(preview)) => session_response(&token, StatusCode::OK, preview).into_response(),
+            Ok(Err(e)) => {
+                let (status, body) = state.fetch_error(e);
+                session_response(&token, status, body.0).into_response()
+            }
+            Err(s) => session_failure(s),
+        }
+    })
+    .await
+}
+
+/// POST /api/press-enter  { id, confirmed? }
+async fn press_enter(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(params): Json<PressEnterParams>,
+) -> axum::response::Response {
+    run_blocking(move || {
+        let token = match state.get_or_create_session(&headers) {
+            Ok(t) => t,
+            Err(s) => return session_failure(s),
+        };
+
+        let result = state.with_page(&token, |session| {
+            if params.confirmed == Some(true) {
+                session.confirmed(|session| session.press_enter(params.id))
+            } else {
+                session.press_enter(params.id)
+            }
+        });
+        match result {
+            Ok(Ok((dom, timings))) => {
+                let text = render_loaded_page(dom, None, None, None);
+                page_response(&token, text, timings.as_ref())
+            }
+            Ok(Err(e)) => {
+                let (status, body) = state.fetch_error(e);
+                session_response(&token, status, body.0).into_response()
+            }
+            Err(s) => session_failure(s),
+        }
+    })
+    .await
+}
+
+/// POST /api/confirm  { token }
+///
+/// Takes an action the action policy held, answering as the click or
+/// submission would have.
+async fn confirm(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(params): Json<ConfirmParams>,
+) -> axum::response::Response {
+    run_blocking(move || {
+        let token = match state.get_or_create_session(&headers) {
+            Ok(t) => t,
+            Err(s) => return session_failure(s),
+        };
+
+        let result = state.with_page(&token, |session| session.confirm(&params.token));
+        match result {
+            Ok(Ok((dom, timings))) => {
+                let text = render_loaded_page(dom, None, None, None);
+                page_response(&token, text, timings.as_ref())
+            }
+            Ok(Err(e)) => {
+                let (status, body) = state.fetch_error(e);
+                session_response(&token, status, body.0).into_response()
+            }
+            Err(s) => session_failure(s),
+        }
+    })
+    .await
+}
+
+/// POST /api/type  { id, text }
+async fn type_text(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(params): Json<TypeTextParams>,
+) -> axum::response::Response {
+    run_blocking(move || {
+        let token = match state.get_or_create_session(&headers) {
+            Ok(t) => t,
+            Err(s) => return session_failure(s),
+        };
+
+        let result = state.with_session(&token, |session| {
+            session
+                .type_text(params.id, &params.text)
+                .map(|()| {
+                    let echo = session.typed_echo(params.id, &params.text);
+                    (echo, session.suggestion_warning(params.id, &params.text))
+                })
+        });
+        match result {
+            Ok(Ok((echo, warning))) => {
+                let mut body = serde_json::json!({
+                    "ok": true,
+                    "message": format!("Typed {} into element {}", echo, params.id)
+                });
+                if let Some(warning) = warning {
+                    body["warning"] = serde_json::json!(warning);
+                }
+                session_response(&token, StatusCode::OK, body).into_response()
+            }
+            Ok(Err(e)) => {
+                let (status, body) = state.fetch_error(e);
+                session_response(&token, status, body.0).into_response()
+            }
+            Err(s) => session_failure(s),
+        }
+    })
+    .await
+}
+
+/// POST /api/check  { id }
+async fn check(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(params): Json<CheckParams>,
+) -> axum::response::Response {
+    run_blocking(move || {
+        let token = match state.get_or_create_session(&headers) {
+            Ok(t) => t,
+            Err(s) => return session_failure(s),
+        };
+
+        let result = state.with_session(&token, |session| session.check(params.id));
+        match result {
+            Ok(Ok(())) => {
+                let body = serde_json::json!({
+                    "ok": true,
+                    "message": format!("Checked element {}", params.id)
+                });
+                session_response(&token, StatusCode::OK, body).into_response()
+            }
+            Ok(Err(e)) => {
+                let (status, body) = state.fetch_error(e);
+                session_response(&token, status, body.0).into_response()
+            }
+            Err(s) => session_failure(s),
+        }
+    })
+    .await
+}
+
+/// POST /api/uncheck  { id }
+async fn uncheck(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(params): Json<CheckParams>,
+) -> axum::response::Response {
+    run_blocking(move || {
+        let token = match state.get_or_create_session(&headers) {
+            Ok(t) => t,
+            Err(s) => return session_failure(s),
+        };
+
+        let result = state.with_session(&token, |session| session.uncheck(params.id));
+        match result {
+            Ok(Ok(())) => {
+                let body = serde_json::json!({
                     "ok": true,
                     "message": format!("Unchecked element {}", params.id)
                 });
                 session_response(&token, StatusCode::OK, body).into_response()
             }
             Ok(Err(e)) => {
-                let (status, body) = map_fetch_error(e);
+                let (status, body) = state.fetch_error(e);
                 session_response(&token, status, body.0).into_response()
             }
-            Err(s) => session_text_response("", s, "session error".into()).into_response(),
+            Err(s) => session_failure(s),
         }
     })
     .await
@@ -515,10 +1477,7 @@ async fn select(
     run_blocking(move || {
         let token = match state.get_or_create_session(&headers) {
             Ok(t) => t,
-            Err(s) => {
-                return session_text_response("", s, "session creation failed".into())
-                    .into_response()
-            }
+            Err(s) => return session_failure(s),
         };
 
         let result =
@@ -532,10 +1491,44 @@ async fn select(
                 session_response(&token, StatusCode::OK, body).into_response()
             }
             Ok(Err(e)) => {
-                let (status, body) = map_fetch_error(e);
+                let (status, body) = state.fetch_error(e);
                 session_response(&token, status, body.0).into_response()
             }
-            Err(s) => session_text_response("", s, "session error".into()).into_response(),
+            Err(s) => session_failure(s),
+        }
+    })
+    .await
+}
+
+/// POST /api/annotate  { id, note }
+async fn annotate(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(params): Json<AnnotateParams>,
+) -> axum::response::Response {
+    run_blocking(move || {
+        let token = match state.get_or_create_session(&headers) {
+            Ok(t) => t,
+            Err(s) => return session_failure(s),
+        };
+
+        let removed = params.note.trim().is_empty();
+        let result = state.with_session(&token, |session| session.annotate(params.id, params.note));
+        match result {
+            Ok(Ok(())) => {
+                let message = if removed {
+                    format!("Removed the note on element {}", params.id)
+                } else {
+                    format!("Annotated element {}", params.id)
+                };
+                let body = serde_json::json!({ "ok": true, "message": message });
+                session_response(&token, StatusCode::OK, body).into_response()
+            }
+            Ok(Err(e)) => {
+                let (status, body) = state.fetch_error(e);
+                session_response(&token, status, body.0).into_response()
+            }
+            Err(s) => session_failure(s),
         }
     })
     .await
@@ -550,10 +1543,7 @@ async fn search(
     run_blocking(move || {
         let token = match state.get_or_create_session(&headers) {
             Ok(t) => t,
-            Err(s) => {
-                return session_text_response("", s, "session creation failed".into())
-                    .into_response()
-            }
+            Err(s) => return session_failure(s),
         };
 
         let engine = match params.engine.as_deref() {
@@ -568,10 +1558,126 @@ async fn search(
                 session_response(&token, StatusCode::OK, results).into_response()
             }
             Ok(Err(e)) => {
-                let (status, body) = map_fetch_error(e);
+                let (status, body) = state.fetch_error(e);
+                session_response(&token, status, body.0).into_response()
+            }
+            Err(s) => session_failure(s),
+        }
+    })
+    .await
+}
+
+/// GET /api/feeds
+///
+/// The feeds and sitemaps of the current page's site.
+async fn feeds(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> axum::response::Response {
+    run_blocking(move || {
+        let token = match state.get_or_create_session(&headers) {
+            Ok(t) => t,
+            Err(s) => return session_failure(s),
+        };
+
+        match state.with_session(&token, |session| session.discover_feeds()) {
+            Ok(Ok(feeds)) => session_response(&token, StatusCode::OK, feeds).into_response(),
+            Ok(Err(e)) => {
+                let (status, body) = state.fetch_error(e);
+                session_response(&token, status, body.0).into_response()
+            }
+            Err(s) => session_failure(s),
+        }
+    })
+    .await
+}
+
+/// POST /api/sitemap  { url }
+async fn sitemap(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(params): Json<SitemapParams>,
+) -> axum::response::Response {
+    run_blocking(move || {
+        let token = match state.get_or_create_session(&headers) {
+            Ok(t) => t,
+            Err(s) => return session_failure(s),
+        };
+
+        match state.with_session(&token, |session| session.fetch_sitemap(&params.url)) {
+            Ok(Ok(entries)) => session_response(&token, StatusCode::OK, entries).into_response(),
+            Ok(Err(e)) => {
+                let (status, body) = state.fetch_error(e);
                 session_response(&token, status, body.0).into_response()
             }
-            Err(s) => session_text_response("", s, "session error".into()).into_response(),
+            Err(s) => session_failure(s),
+        }
+    })
+    .await
+}
+
+/// POST /api/paginate  { extract, max_pages? }
+///
+/// Follow the current page's next links and merge what each page has.
+async fn paginate(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(params): Json<PaginateParams>,
+) -> axum::response::Response {
+    let extract: Extract = match params.extract.parse() {
+        Ok(extract) => extract,
+        Err(error) => {
+            return (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(ErrorCode::InvalidRequest, error))).into_response();
+        }
+    };
+    let max_pages = params.max_pages.unwrap_or(DEFAULT_MAX_PAGES);
+
+    run_blocking(move || {
+        let token = match state.get_or_create_session(&headers) {
+            Ok(t) => t,
+            Err(s) => return session_failure(s),
+        };
+
+        match state.with_session(&token, |session| session.paginate_collect(max_pages, extract)) {
+            Ok(Ok(result)) => session_response(&token, StatusCode::OK, result).into_response(),
+            Ok(Err(e)) => {
+                let (status, body) = state.fetch_error(e);
+                session_response(&token, status, body.0).into_response()
+            }
+            Err(s) => session_failure(s),
+        }
+    })
+    .await
+}
+
+/// POST /api/crawl  { url, max_pages?, max_depth?, same_host_only?, include_patterns?, exclude_patterns?, respect_robots? }
+///
+/// A breadth-first crawl from `url`, answered when it finishes.
+async fn crawl(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(params): Json<CrawlParams>,
+) -> axum::response::Response {
+    let (url, opts) = match params.opts() {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            return (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(ErrorCode::InvalidRequest, error))).into_response();
+        }
+    };
+
+    run_blocking(move || {
+        let token = match state.get_or_create_session(&headers) {
+            Ok(t) => t,
+            Err(s) => return session_failure(s),
+        };
+
+        match state.with_session(&token, |session| session.crawl(&url, opts)) {
+            Ok(Ok(entries)) => session_response(&token, StatusCode::OK, entries).into_response(),
+            Ok(Err(e)) => {
+                let (status, body) = state.fetch_error(e);
+                session_response(&token, status, body.0).into_response()
+            }
+            Err(s) => session_failure(s),
         }
     })
     .await
@@ -586,24 +1692,39 @@ async fn login(
     run_blocking(move || {
         let token = match state.get_or_create_session(&headers) {
             Ok(t) => t,
-            Err(s) => {
-                return session_text_response("", s, "session creation failed".into())
-                    .into_response()
-            }
+            Err(s) => return session_failure(s),
         };
 
-        let result = state
-            .with_session(&token, |session| session.login(&params.username, &params.password));
+        let mut status = None;
+        let result = state.with_page(&token, |session| {
+            session.login(&params.username, &params.password).map(|outcome| {
+                status = Some(outcome.status);
+                outcome.dom
+            })
+        });
         match result {
-            Ok(Ok(dom)) => {
-                let text = format_page(&dom, None);
-                session_text_response(&token, StatusCode::OK, text).into_response()
+            Ok(Ok((dom, timings))) => {
+                let status = status.unwrap_or(LoginStatus::Success);
+                let page = format!("login: {status}\n{}", format_page(&dom, None));
+                // A site may echo the password back (e.g. in a re-rendered form)
+                let text = redact::scrub(&page, &params.password);
+                let mut response = page_response(&token, text, timings.as_ref());
+                let kind = match status {
+                    LoginStatus::Success => "success",
+                    LoginStatus::Failed { .. } => "failed",
+                    LoginStatus::TwoFactorRequired => "two_factor_required",
+                    LoginStatus::CaptchaRequired => "captcha_required",
+                };
+                response
+                    .headers_mut()
+                    .insert("X-Browsy-Login-Status", HeaderValue::from_static(kind));
+                response
             }
             Ok(Err(e)) => {
-                let (status, body) = map_fetch_error(e);
+                let (status, body) = state.fetch_error(e);
                 session_response(&token, status, body.0).into_response()
             }
-            Err(s) => session_text_response("", s, "session error".into()).into_response(),
+            Err(s) => session_failure(s),
         }
     })
     .await
@@ -618,23 +1739,20 @@ async fn enter_code(
     run_blocking(move || {
         let token = match state.get_or_create_session(&headers) {
             Ok(t) => t,
-            Err(s) => {
-                return session_text_response("", s, "session creation failed".into())
-                    .into_response()
-            }
+            Err(s) => return session_failure(s),
         };
 
-        let result = state.with_session(&token, |session| session.enter_code(&params.code));
+        let result = state.with_page(&token, |session| session.enter_code(&params.code));
         match result {
-            Ok(Ok(dom)) => {
+            Ok(Ok((dom, timings))) => {
                 let text = format_page(&dom, None);
-                session_text_response(&token, StatusCode::OK, text).into_response()
+                page_response(&token, text, timings.as_ref())
             }
             Ok(Err(e)) => {
-                let (status, body) = map_fetch_error(e);
+                let (status, body) = state.fetch_error(e);
                 session_response(&token, status, body.0).into_response()
             }
-            Err(s) => session_text_response("", s, "session error".into()).into_response(),
+            Err(s) => session_failure(s),
         }
     })
     .await
@@ -645,14 +1763,29 @@ async fn find(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
     Json(params): Json<FindParams>,
+) -> axum::response::Response {
+    find_elements(state, headers, params).await
+}
+
+/// GET /api/find  ?text=&role=
+async fn find_get(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<FindParams>,
+) -> axum::response::Response {
+    find_elements(state, headers, params).await
+}
+
+/// Search the session's page for both forms of `/api/find`.
+async fn find_elements(
+    state: Arc<AppState>,
+    headers: HeaderMap,
+    params: FindParams,
 ) -> axum::response::Response {
     run_blocking(move || {
         let token = match state.get_or_create_session(&headers) {
             Ok(t) => t,
-            Err(s) => {
-                return session_text_response("", s, "session creation failed".into())
-                    .into_response()
-            }
+            Err(s) => return session_failure(s),
         };
 
         let result = state.with_session(&token, |session| {
@@ -672,6 +1805,12 @@ async fn find(
                 results.extend(role_results);
             }
 
+            if params.selectors != Some(true) {
+                for el in &mut results {
+                    el.selector = None;
+                }
+            }
+
             results
         });
 
@@ -679,7 +1818,7 @@ async fn find(
             Ok(results) => {
                 session_response(&token, StatusCode::OK, results).into_response()
             }
-            Err(s) => session_text_response("", s, "session error".into()).into_response(),
+            Err(s) => session_failure(s),
         }
     })
     .await
@@ -694,26 +1833,29 @@ async fn get_page(
     run_blocking(move || {
         let token = match state.get_or_create_session(&headers) {
             Ok(t) => t,
-            Err(s) => {
-                return session_text_response("", s, "session creation failed".into())
-                    .into_response()
-            }
+            Err(s) => return session_failure(s),
         };
 
-        let result = state.with_session(&token, |session| session.dom());
+        let result = state.with_session(&token, |session| {
+            session
+                .dom()
+                .map(|dom| session.scoped(dom, params.scope.as_deref()))
+        });
         match result {
             Ok(Some(dom)) => {
-                let scoped = apply_scope(dom, params.scope.as_deref());
-                let text = format_page(&scoped, params.format.as_deref());
+                let text = render_page(
+                    dom,
+                    params.scope.as_deref(),
+                    params.selectors,
+                    params.format.as_deref(),
+                );
                 session_text_response(&token, StatusCode::OK, text).into_response()
             }
             Ok(None) => {
-                let body = ErrorResponse {
-                    error: "No page loaded".into(),
-                };
+                let body = ErrorResponse::new(ErrorCode::NoPageLoaded, "No page loaded");
                 session_response(&token, StatusCode::BAD_REQUEST, body).into_response()
             }
-            Err(s) => session_text_response("", s, "session error".into()).into_response(),
+            Err(s) => session_failure(s),
         }
     })
     .await
@@ -727,15 +1869,18 @@ async fn page_info(
     run_blocking(move || {
         let token = match state.get_or_create_session(&headers) {
             Ok(t) => t,
-            Err(s) => {
-                return session_text_response("", s, "session creation failed".into())
-                    .into_response()
-            }
+            Err(s) => return session_failure(s),
         };
 
-        let result = state.with_session(&token, |session| session.dom());
+        let result = state.with_session(&token, |session| {
+            session.dom().map(|dom| {
+                let settings = effective_settings(session);
+                let nav_stats = session.last_nav_stats().cloned();
+                (dom, session.timings().cloned(), nav_stats, session.language_mismatch(), settings)
+            })
+        });
         match result {
-            Ok(Some(dom)) => {
+            Ok(Some((dom, timings, nav_stats, language_mismatch, settings))) => {
                 let mut info = serde_json::json!({
                     "title": dom.title,
                     "url": dom.url,
@@ -749,7 +1894,24 @@ async fn page_info(
                         })
                     }).collect::<Vec<_>>(),
                     "pagination": dom.pagination(),
+                    "load_more": dom.load_more_button(),
+                    "breadcrumbs": dom.breadcrumbs(),
+                    "auth_state": dom.auth_state(),
+                    "price": dom.primary_price(),
+                    "article": dom.article,
+                    "lang": dom.lang,
+                    "lang_confidence": dom.lang_confidence,
+                    "language_mismatch": language_mismatch,
+                    "effective_config": settings,
+                    "timings": timings,
+                    "nav_stats": nav_stats,
                 });
+                if dom.page_type == output::PageType::EmailBody {
+                    info.as_object_mut().unwrap().insert(
+                        "verification".to_string(),
+                        serde_json::to_value(dom.find_verification()).unwrap_or_default(),
+                    );
+                }
                 if let Some(ref captcha) = dom.captcha {
                     info.as_object_mut().unwrap().insert(
                         "captcha".to_string(),
@@ -759,17 +1921,30 @@ async fn page_info(
                 session_response(&token, StatusCode::OK, info).into_response()
             }
             Ok(None) => {
-                let body = ErrorResponse {
-                    error: "No page loaded".into(),
-                };
+                let body = ErrorResponse::new(ErrorCode::NoPageLoaded, "No page loaded");
                 session_response(&token, StatusCode::BAD_REQUEST, body).into_response()
             }
-            Err(s) => session_text_response("", s, "session error".into()).into_response(),
+            Err(s) => session_failure(s),
         }
     })
     .await
 }
 
+/// The settings in effect for the session's current page, for `page_info`.
+/// Header values are left out, since they may hold credentials.
+fn effective_settings(session: &Session) -> serde_json::Value {
+    let config = session.effective_config();
+    serde_json::json!({
+        "site": session.site_override(),
+        "user_agent": config.user_agent,
+        "fetch_css": config.fetch_css,
+        "viewport": [config.viewport_width, config.viewport_height],
+        "headers": config.extra_headers.iter().map(|(name, _)| name).collect::<Vec<_>>(),
+        "per_host_delay_ms": config.per_host_delay_ms,
+        "auto_consent": config.auto_dismiss_cookie_banners,
+    })
+}
+
 /// GET /api/tables
 async fn tables(
     State(state): State<Arc<AppState>>,
@@ -778,10 +1953,7 @@ async fn tables(
     run_blocking(move || {
         let token = match state.get_or_create_session(&headers) {
             Ok(t) => t,
-            Err(s) => {
-                return session_text_response("", s, "session creation failed".into())
-                    .into_response()
-            }
+            Err(s) => return session_failure(s),
         };
 
         let result = state.with_session(&token, |session| session.dom());
@@ -791,12 +1963,100 @@ async fn tables(
                 session_response(&token, StatusCode::OK, table_data).into_response()
             }
             Ok(None) => {
-                let body = ErrorResponse {
-                    error: "No page loaded".into(),
-                };
+                let body = ErrorResponse::new(ErrorCode::NoPageLoaded, "No page loaded");
+                session_response(&token, StatusCode::BAD_REQUEST, body).into_response()
+            }
+            Err(s) => session_failure(s),
+        }
+    })
+    .await
+}
+
+/// GET /api/element/{id}/text
+///
+/// An element's whole text, which the compact format cuts short when long.
+async fn element_text(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<u32>,
+) -> axum::response::Response {
+    run_blocking(move || {
+        let token = match state.get_or_create_session(&headers) {
+            Ok(t) => t,
+            Err(s) => return session_failure(s),
+        };
+
+        let result = state.with_session(&token, |session| {
+            session.require_element(id).map(|el| el.text.clone().unwrap_or_default())
+        });
+        match result {
+            Ok(Ok(text)) => {
+                let body = serde_json::json!({ "id": id, "text": text });
+                session_response(&token, StatusCode::OK, body).into_response()
+            }
+            Ok(Err(e)) => {
+                let (status, body) = state.fetch_error(e);
+                session_response(&token, status, body.0).into_response()
+            }
+            Err(s) => session_failure(s),
+        }
+    })
+    .await
+}
+
+/// GET /api/element/{id}/context  ?radius=
+///
+/// An element with its container, row, the elements left of and above it,
+/// and its neighbors within `radius` pixels.
+async fn element_context(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<u32>,
+    Query(params): Query<ElementContextQuery>,
+) -> axum::response::Response {
+    run_blocking(move || {
+        let token = match state.get_or_create_session(&headers) {
+            Ok(t) => t,
+            Err(s) => return session_failure(s),
+        };
+
+        let radius = params.radius.unwrap_or(output::DEFAULT_NEIGHBOR_RADIUS);
+        let result = state.with_session(&token, |session| {
+            session.require_element(id)?;
+            let dom = session.dom().ok_or(FetchError::NoPageLoaded)?;
+            Ok::<_, FetchError>(serde_json::to_value(dom.context(id, radius)).unwrap_or_default())
+        });
+        match result {
+            Ok(Ok(body)) => session_response(&token, StatusCode::OK, body).into_response(),
+            Ok(Err(e)) => {
+                let (status, body) = state.fetch_error(e);
+                session_response(&token, status, body.0).into_response()
+            }
+            Err(s) => session_failure(s),
+        }
+    })
+    .await
+}
+
+/// GET /api/summary
+async fn summary(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> axum::response::Response {
+    run_blocking(move || {
+        let token = match state.get_or_create_session(&headers) {
+            Ok(t) => t,
+            Err(s) => return session_failure(s),
+        };
+
+        let result = state.with_session(&token, |session| session.dom());
+        match result {
+            Ok(Some(dom)) => session_response(&token, StatusCode::OK, dom.summary()).into_response(),
+            Ok(None) => {
+                let body = ErrorResponse::new(ErrorCode::NoPageLoaded, "No page loaded");
                 session_response(&token, StatusCode::BAD_REQUEST, body).into_response()
             }
-            Err(s) => session_text_response("", s, "session error".into()).into_response(),
+            Err(s) => session_failure(s),
         }
     })
     .await
@@ -810,23 +2070,235 @@ async fn back(
     run_blocking(move || {
         let token = match state.get_or_create_session(&headers) {
             Ok(t) => t,
-            Err(s) => {
-                return session_text_response("", s, "session creation failed".into())
-                    .into_response()
-            }
+            Err(s) => return session_failure(s),
         };
 
-        let result = state.with_session(&token, |session| session.back());
+        let result = state.with_page(&token, |session| session.back());
         match result {
-            Ok(Ok(dom)) => {
+            Ok(Ok((dom, timings))) => {
                 let text = format_page(&dom, None);
-                session_text_response(&token, StatusCode::OK, text).into_response()
+                page_response(&token, text, timings.as_ref())
+            }
+            Ok(Err(e)) => {
+                let (status, body) = state.fetch_error(e);
+                session_response(&token, status, body.0).into_response()
+            }
+            Err(s) => session_failure(s),
+        }
+    })
+    .await
+}
+
+/// GET /api/nav-stats
+///
+/// What the session's last navigations cost, oldest first.
+async fn nav_stats(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> axum::response::Response {
+    run_blocking(move || {
+        let token = match state.get_or_create_session(&headers) {
+            Ok(t) => t,
+            Err(s) => return session_failure(s),
+        };
+
+        match state.with_session(&token, |session| session.nav_stats().to_vec()) {
+            Ok(stats) => session_response(&token, StatusCode::OK, stats).into_response(),
+            Err(s) => session_failure(s),
+        }
+    })
+    .await
+}
+
+/// GET /api/audit  ?since_seq=
+async fn audit(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<AuditQuery>,
+) -> axum::response::Response {
+    run_blocking(move || {
+        let token = match state.get_or_create_session(&headers) {
+            Ok(t) => t,
+            Err(s) => return session_failure(s),
+        };
+
+        let result = state.with_session(&token, |session| match params.since_seq {
+            Some(seq) => session.audit_log_since(seq),
+            None => session.audit_log(),
+        });
+        match result {
+            Ok(events) => session_response(&token, StatusCode::OK, events).into_response(),
+            Err(s) => session_failure(s),
+        }
+    })
+    .await
+}
+
+/// POST /api/snapshot
+///
+/// Saves the session's page, form state and recent audit events into a new
+/// folder under `ServerConfig::snapshot_dir` and returns its path.
+async fn snapshot(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> axum::response::Response {
+    run_blocking(move || {
+        let token = match state.get_or_create_session(&headers) {
+            Ok(t) => t,
+            Err(s) => return session_failure(s),
+        };
+
+        let dir = state.config.snapshot_dir.clone();
+        let result = state.with_session(&token, |session| session.save_snapshot(&dir));
+        match result {
+            Ok(Ok(path)) => session_response(
+                &token,
+                StatusCode::OK,
+                serde_json::json!({ "path": path.display().to_string() }),
+            )
+            .into_response(),
+            Ok(Err(e)) => session_response(
+                &token,
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ErrorResponse::from(&e),
+            )
+            .into_response(),
+            Err(s) => session_failure(s),
+        }
+    })
+    .await
+}
+
+/// POST /api/sessions/{token}/fork
+///
+/// Responds with `{ "session": <new token> }`; the `X-Browsy-Session` header
+/// stays the forked session's.
+async fn fork_session(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> axum::response::Response {
+    run_blocking(move || match state.fork_session(&token) {
+        Ok(fork) => {
+            session_response(&token, StatusCode::OK, serde_json::json!({ "session": fork })).into_response()
+        }
+        Err(s) => session_failure(s),
+    })
+    .await
+}
+
+/// POST /api/session-config  { allowed_domains?, blocked_domains?, viewport?, fetch_css?, user_agent?, selectors? }
+///
+/// Fields left out keep their current value.
+async fn session_config(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(params): Json<SessionConfigParams>,
+) -> axum::response::Response {
+    let settings = match SessionSettings::new(
+        params.viewport.as_deref(),
+        params.fetch_css,
+        params.user_agent.as_deref(),
+        params.selectors,
+    ) {
+        Ok(settings) => settings,
+        Err(error) => {
+            return (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(ErrorCode::InvalidRequest, error))).into_response();
+        }
+    };
+
+    run_blocking(move || {
+        let token = match state.get_or_create_session(&headers) {
+            Ok(t) => t,
+            Err(s) => return session_failure(s),
+        };
+
+        let result = state.with_session(&token, |session| {
+            let allowed = match params.allowed_domains {
+                Some(list) if list.is_empty() => None,
+                Some(list) => Some(list),
+                None => session.allowed_domains().map(<[String]>::to_vec),
+            };
+            let blocked = params
+                .blocked_domains
+                .unwrap_or_else(|| session.blocked_domains().to_vec());
+            session.set_domain_lists(allowed, blocked)?;
+            settings.apply_fetch_settings(session)?;
+            if let Some([width, height]) = settings.viewport {
+                session.resize(width, height);
+            }
+            let (width, height) = session.viewport();
+            Ok::<_, FetchError>(serde_json::json!({
+                "allowed_domains": session.allowed_domains(),
+                "blocked_domains": session.blocked_domains(),
+                "viewport": format!("{width}x{height}"),
+                "fetch_css": session.fetch_css(),
+                "user_agent": session.user_agent(),
+                "selectors": session.emit_selectors(),
+            }))
+        });
+        match result {
+            Ok(Ok(body)) => session_response(&token, StatusCode::OK, body).into_response(),
+            Ok(Err(e)) => {
+                let (status, body) = state.fetch_error(e);
+                session_response(&token, status, body.0).into_response()
+            }
+            Err(s) => session_failure(s),
+        }
+    })
+    .await
+}
+
+/// POST /api/cookies/export
+///
+/// Responds with `{ "cookies": [...] }`: the session's unexpired cookies,
+/// session cookies and those of shared domains included.
+async fn export_cookies(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> axum::response::Response {
+    run_blocking(move || {
+        let token = match state.get_or_create_session(&headers) {
+            Ok(t) => t,
+            Err(s) => return session_failure(s),
+        };
+
+        match state.with_session(&token, |session| session.export_cookies()) {
+            Ok(json) => {
+                let cookies: serde_json::Value = serde_json::from_str(&json).unwrap_or_default();
+                session_response(&token, StatusCode::OK, serde_json::json!({ "cookies": cookies })).into_response()
+            }
+            Err(s) => session_failure(s),
+        }
+    })
+    .await
+}
+
+/// POST /api/cookies/import  { cookies: [...] }
+///
+/// Adds the cookies to the session, replacing any with the same domain,
+/// path and name. Cookies of shared domains go to the shared jar, for
+/// every session. Responds with `{ "imported": <count> }`.
+async fn import_cookies(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(params): Json<ImportCookiesParams>,
+) -> axum::response::Response {
+    run_blocking(move || {
+        let token = match state.get_or_create_session(&headers) {
+            Ok(t) => t,
+            Err(s) => return session_failure(s),
+        };
+
+        let json = serde_json::Value::Array(params.cookies).to_string();
+        match state.with_session(&token, |session| session.import_cookies(&json)) {
+            Ok(Ok(imported)) => {
+                session_response(&token, StatusCode::OK, serde_json::json!({ "imported": imported })).into_response()
             }
             Ok(Err(e)) => {
-                let (status, body) = map_fetch_error(e);
+                let (status, body) = state.fetch_error(e);
                 session_response(&token, status, body.0).into_response()
             }
-            Err(s) => session_text_response("", s, "session error".into()).into_response(),
+            Err(s) => session_failure(s),
         }
     })
     .await
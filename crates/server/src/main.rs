@@ -1,8 +1,20 @@
 use std::sync::Arc;
 use browsy_server::{AppState, ServerConfig, build_router};
 
+const USAGE: &str = "usage: browsy-server [--log-level <FILTER>] [--log-format text|json]";
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let config = ServerConfig::default();
+    let mut config = ServerConfig::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--log-level" => config.log_level = args.next().ok_or(USAGE)?,
+            "--log-format" => config.log_format = args.next().ok_or(USAGE)?.parse()?,
+            _ => return Err(USAGE.into()),
+        }
+    }
+    config.init_logging().map_err(|e| e.to_string())?;
+
     let port = config.port;
     let state = Arc::new(AppState::new(config));
     let app = build_router(state);
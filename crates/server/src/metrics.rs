@@ -0,0 +1,185 @@
+//! Prometheus metrics for the REST server, rendered in the text exposition
+//! format at `GET /metrics`.
+//!
+//! Labels are limited to matched route templates, error variants and
+//! detection kinds, so cardinality stays bounded no matter which URLs
+//! clients browse.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use browsy_core::fetch::{FetchError, Timings};
+use browsy_core::output::SpatialDom;
+
+/// Histogram bucket upper bounds, in seconds.
+const BUCKETS: [f64; 12] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// Non-cumulative count per bucket; observations above the last bound
+    /// only show up in `count`.
+    buckets: [u64; BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        if let Some(i) = BUCKETS.iter().position(|bound| seconds <= *bound) {
+            self.buckets[i] += 1;
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let sep = if labels.is_empty() { "" } else { "," };
+        let mut cumulative = 0;
+        for (bound, count) in BUCKETS.iter().zip(self.buckets) {
+            cumulative += count;
+            let _ = writeln!(out, "{name}_bucket{{{labels}{sep}le=\"{bound}\"}} {cumulative}");
+        }
+        let _ = writeln!(out, "{name}_bucket{{{labels}{sep}le=\"+Inf\"}} {}", self.count);
+        let braces = if labels.is_empty() { String::new() } else { format!("{{{labels}}}") };
+        let _ = writeln!(out, "{name}_sum{braces} {}", self.sum);
+        let _ = writeln!(out, "{name}_count{braces} {}", self.count);
+    }
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    requests: BTreeMap<String, u64>,
+    request_duration: BTreeMap<String, Histogram>,
+    session_call_duration: Histogram,
+    fetch_duration: Histogram,
+    page_load_duration: Histogram,
+    fetch_errors: BTreeMap<&'static str, u64>,
+    detections: BTreeMap<&'static str, u64>,
+}
+
+/// Counters and histograms shared by all handlers.
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+    registry: Mutex<Registry>,
+}
+
+impl Metrics {
+    /// A request finished on `route`, the matched path template.
+    pub fn record_request(&self, route: &str, elapsed: Duration) {
+        let mut registry = self.registry.lock().unwrap();
+        *registry.requests.entry(route.to_string()).or_default() += 1;
+        registry
+            .request_duration
+            .entry(route.to_string())
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Time a closure spent holding a session.
+    pub fn record_session_call(&self, elapsed: Duration) {
+        self.registry
+            .lock()
+            .unwrap()
+            .session_call_duration
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// A page was loaded: record its timings and what was detected on it.
+    pub fn record_page(&self, dom: &SpatialDom, timings: Option<&Timings>) {
+        let mut registry = self.registry.lock().unwrap();
+        if let Some(timings) = timings {
+            if timings.fetch > 0.0 {
+                registry.fetch_duration.observe(timings.fetch / 1000.0);
+            }
+            registry.page_load_duration.observe(timings.total / 1000.0);
+        }
+        if dom.captcha.is_some() {
+            *registry.detections.entry("captcha").or_default() += 1;
+        }
+        if dom.blocked.is_some() {
+            *registry.detections.entry("blocked").or_default() += 1;
+        }
+    }
+
+    pub fn record_fetch_error(&self, error: &FetchError) {
+        *self
+            .registry
+            .lock()
+            .unwrap()
+            .fetch_errors
+            .entry(error_variant(error))
+            .or_default() += 1;
+    }
+
+    /// Render every metric in the Prometheus text format.
+    pub fn render(&self, active_sessions: usize) -> String {
+        let registry = self.registry.lock().unwrap();
+        let mut out = String::new();
+
+        header(&mut out, "browsy_http_requests_total", "counter", "REST requests handled, by route.");
+        for (route, count) in &registry.requests {
+            let _ = writeln!(out, "browsy_http_requests_total{{route=\"{}\"}} {count}", escape(route));
+        }
+
+        header(&mut out, "browsy_http_request_duration_seconds", "histogram", "REST request latency, by route.");
+        for (route, histogram) in &registry.request_duration {
+            let labels = format!("route=\"{}\"", escape(route));
+            histogram.render(&mut out, "browsy_http_request_duration_seconds", &labels);
+        }
+
+        header(&mut out, "browsy_session_call_duration_seconds", "histogram", "Time spent holding a session per request.");
+        registry.session_call_duration.render(&mut out, "browsy_session_call_duration_seconds", "");
+
+        header(&mut out, "browsy_fetch_duration_seconds", "histogram", "Network time to fetch a page, excluding stylesheets.");
+        registry.fetch_duration.render(&mut out, "browsy_fetch_duration_seconds", "");
+
+        header(&mut out, "browsy_page_load_duration_seconds", "histogram", "Total time to fetch and render a page.");
+        registry.page_load_duration.render(&mut out, "browsy_page_load_duration_seconds", "");
+
+        header(&mut out, "browsy_fetch_errors_total", "counter", "Failed session actions, by FetchError variant.");
+        for (variant, count) in &registry.fetch_errors {
+            let _ = writeln!(out, "browsy_fetch_errors_total{{variant=\"{variant}\"}} {count}");
+        }
+
+        header(&mut out, "browsy_detections_total", "counter", "Loaded pages flagged as CAPTCHA or blocked.");
+        for (kind, count) in &registry.detections {
+            let _ = writeln!(out, "browsy_detections_total{{kind=\"{kind}\"}} {count}");
+        }
+
+        header(&mut out, "browsy_active_sessions", "gauge", "Sessions currently held by the server.");
+        let _ = writeln!(out, "browsy_active_sessions {active_sessions}");
+
+        out
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+}
+
+/// Escape a label value per the exposition format.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn error_variant(error: &FetchError) -> &'static str {
+    match error {
+        FetchError::InvalidUrl(_) => "invalid_url",
+        FetchError::BlockedUrl(_) => "blocked_url",
+        FetchError::Network(_) => "network",
+        FetchError::Timeout(_) => "timeout",
+        FetchError::HttpError(_) => "http_error",
+        FetchError::ActionError(_) => "action_error",
+        FetchError::ElementNotFound { .. } => "element_not_found",
+        FetchError::WrongElementType { .. } => "wrong_element_type",
+        FetchError::ElementUnavailable { .. } => "element_unavailable",
+        FetchError::NoPageLoaded => "no_page_loaded",
+        FetchError::ResponseTooLarge(_, _) => "response_too_large",
+        FetchError::TooManyRedirects { .. } => "too_many_redirects",
+        FetchError::ConfirmationRequired { .. } => "confirmation_required",
+        FetchError::BinaryContent { .. } => "binary_content",
+    }
+}
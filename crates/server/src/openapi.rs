@@ -0,0 +1,223 @@
+//! OpenAPI description of the REST API.
+//!
+//! Built from the capability registry, the same list `build_router` mounts,
+//! so every route is described by construction. Request bodies and query
+//! parameters come from the parameter structs' JSON Schemas.
+
+use serde_json::{json, Map, Value};
+
+use crate::capabilities::{capabilities, Capability};
+use crate::ErrorResponse;
+
+/// Values of the `format` parameter.
+const FORMATS: &[&str] = &["compact", "json", "map"];
+
+/// Values of the `scope` parameter, as understood by `Scope::from_param`.
+const SCOPES: &[&str] = &["all", "visible", "on_screen", "above_fold", "visible_above_fold", "fold_plus", "main", "new_content"];
+
+/// The OpenAPI 3.1 document for every registered route.
+pub fn openapi() -> Value {
+    let mut schemas = Map::new();
+    let error = hoist_defs(schema_value::<ErrorResponse>(), &mut schemas);
+    schemas.insert("ErrorResponse".to_string(), error);
+
+    let mut paths = Map::new();
+    for capability in capabilities() {
+        let operation = operation(&capability, &mut schemas);
+        let item = paths.entry(capability.path).or_insert_with(|| json!({}));
+        item[capability.method.to_ascii_lowercase()] = operation;
+    }
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "browsy",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Browse pages as a Spatial DOM. Every /api route runs in a session: \
+                the first response carries an X-Browsy-Session header, and sending it back \
+                continues that session. Requests without it (or with an expired token) start \
+                a new one.",
+        },
+        "paths": paths,
+        "components": {
+            "schemas": schemas,
+            "parameters": {
+                "Session": {
+                    "name": "X-Browsy-Session",
+                    "in": "header",
+                    "required": false,
+                    "description": "Session token from a previous response. Omit it to start a new session.",
+                    "schema": { "type": "string" },
+                },
+            },
+            "headers": {
+                "Session": {
+                    "description": "Token of the session that handled the request; send it back to continue it.",
+                    "schema": { "type": "string" },
+                },
+            },
+            "responses": {
+                "Error": {
+                    "description": "The request failed: `error.code` says how, for clients to branch on, and `error.message` says why.",
+                    "content": {
+                        "application/json": { "schema": { "$ref": "#/components/schemas/ErrorResponse" } },
+                    },
+                },
+                "SessionLimit": {
+                    "description": "The server's session limit is reached (`session_limit`).",
+                    "content": {
+                        "application/json": { "schema": { "$ref": "#/components/schemas/ErrorResponse" } },
+                    },
+                },
+            },
+            "securitySchemes": {
+                "bearer": { "type": "http", "scheme": "bearer" },
+                "apiKey": { "type": "apiKey", "in": "header", "name": "X-Api-Key" },
+            },
+        },
+        // Keys are only required when the server is started with some
+        "security": [{}, { "bearer": [] }, { "apiKey": [] }],
+    })
+}
+
+fn operation(capability: &Capability, schemas: &mut Map<String, Value>) -> Value {
+    let in_session = capability.path.starts_with("/api/");
+    let mut operation = json!({
+        "operationId": capability.name,
+        "summary": capability.description,
+        "responses": responses(in_session),
+    });
+    if crate::auth::PUBLIC_ROUTES.contains(&capability.path) {
+        operation["security"] = json!([]);
+    }
+
+    let mut parameters: Vec<Value> = path_parameters(capability.path).collect();
+    if in_session {
+        parameters.push(json!({ "$ref": "#/components/parameters/Session" }));
+    }
+    if let Some(schema) = capability.input_schema.clone() {
+        let mut schema = hoist_defs(schema, schemas);
+        add_enums(&mut schema);
+        if capability.method == "GET" {
+            parameters.extend(query_parameters(&schema));
+        } else {
+            operation["requestBody"] = json!({
+                "required": true,
+                "content": {
+                    "application/json": { "schema": schema, "example": capability.example },
+                },
+            });
+        }
+    }
+    if !parameters.is_empty() {
+        operation["parameters"] = Value::Array(parameters);
+    }
+    operation
+}
+
+fn responses(in_session: bool) -> Value {
+    let mut ok = json!({
+        "description": "Pages come back as compact text, as JSON with `format=json`, or as an ASCII layout map with `format=map`. Other results are JSON.",
+        "content": {
+            "text/plain": { "schema": { "type": "string" } },
+            "application/json": { "schema": {} },
+        },
+    });
+    if in_session {
+        ok["headers"] = json!({ "X-Browsy-Session": { "$ref": "#/components/headers/Session" } });
+    }
+
+    let mut responses = json!({ "200": ok });
+    if in_session {
+        for status in ["400", "401", "429", "500", "502", "504"] {
+            responses[status] = json!({ "$ref": "#/components/responses/Error" });
+        }
+        responses["503"] = json!({ "$ref": "#/components/responses/SessionLimit" });
+    }
+    responses
+}
+
+/// The `{name}` segments of a route's path.
+fn path_parameters(path: &str) -> impl Iterator<Item = Value> + '_ {
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| json!({ "name": name, "in": "path", "required": true, "schema": { "type": "string" } }))
+}
+
+/// One query parameter per property of a GET route's schema.
+fn query_parameters(schema: &Value) -> Vec<Value> {
+    let required: Vec<&str> = schema["required"]
+        .as_array()
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let Some(properties) = schema["properties"].as_object() else {
+        return Vec::new();
+    };
+    properties
+        .iter()
+        .map(|(name, property)| {
+            let mut property = property.clone();
+            let description = property.as_object_mut().and_then(|p| p.remove("description"));
+            let mut parameter = json!({
+                "name": name,
+                "in": "query",
+                "required": required.contains(&name.as_str()),
+                "schema": property,
+            });
+            if let Some(description) = description {
+                parameter["description"] = description;
+            }
+            parameter
+        })
+        .collect()
+}
+
+/// Spell out the values `format` and `scope` accept, which the parameter
+/// structs only describe in prose.
+fn add_enums(schema: &mut Value) {
+    for (name, values) in [("format", FORMATS), ("scope", SCOPES)] {
+        if let Some(property) = schema.get_mut("properties").and_then(|p| p.get_mut(name)) {
+            property["type"] = json!("string");
+            property["enum"] = json!(values);
+        }
+    }
+}
+
+/// Move a schema's `$defs` into `components/schemas` and point its
+/// references there.
+fn hoist_defs(mut schema: Value, schemas: &mut Map<String, Value>) -> Value {
+    if let Some(object) = schema.as_object_mut() {
+        object.remove("$schema");
+        if let Some(Value::Object(defs)) = object.remove("$defs") {
+            for (name, mut def) in defs {
+                rewrite_refs(&mut def);
+                schemas.insert(name, def);
+            }
+        }
+    }
+    rewrite_refs(&mut schema);
+    schema
+}
+
+fn rewrite_refs(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            for (key, child) in object.iter_mut() {
+                match child {
+                    Value::String(target) if key == "$ref" => {
+                        if let Some(name) = target.strip_prefix("#/$defs/") {
+                            *target = format!("#/components/schemas/{name}");
+                        }
+                    }
+                    _ => rewrite_refs(child),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(rewrite_refs),
+        _ => {}
+    }
+}
+
+fn schema_value<T: schemars::JsonSchema>() -> Value {
+    serde_json::to_value(schemars::schema_for!(T)).unwrap_or_default()
+}
@@ -0,0 +1,267 @@
+//! `GET /api/ws`: one session driven over a WebSocket.
+//!
+//! The socket is bound to a single session for its lifetime: the one named by
+//! the upgrade request's `X-Browsy-Session` header, or a new one. Clients send
+//! JSON commands and get a `response` for each, in order, echoing the
+//! command's `req` value if it had one:
+//!
+//! ```text
+//! -> {"op": "browse", "url": "https://example.com", "req": 1}
+//! <- {"type": "response", "req": 1, "status": 200, "page": "title: ..."}
+//! -> {"op": "click", "id": 4, "req": 2}
+//! -> {"op": "page", "format": "json", "req": 3}
+//! ```
+//!
+//! The server also pushes events of its own:
+//!
+//! - `session` first, with the session's token
+//! - `auto_action` before a response, for each thing the session did on its
+//!   own while loading the page (such as dismissing a cookie banner)
+//! - `session_expiring` when the session has been idle for most of its timeout
+//!
+//! Once the session expires the socket is closed with code 1000.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::IntoResponse;
+use browsy_core::output;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{AppState, BrowseParams, GetPageQuery, PageResult};
+
+/// Longest warning `session_expiring` gives before the session expires.
+const EXPIRY_WARNING: Duration = Duration::from_secs(60);
+
+/// Longest wait between checks of the session's expiry.
+const EXPIRY_CHECK: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum Command {
+    Browse(BrowseParams),
+    Click {
+        id: u32,
+        format: Option<String>,
+        scope: Option<String>,
+    },
+    Page(GetPageQuery),
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage {
+    Session {
+        session: String,
+    },
+    Response {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        req: Option<Value>,
+        status: u16,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        page: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    AutoAction {
+        action: String,
+    },
+    SessionExpiring {
+        expires_in_secs: u64,
+    },
+}
+
+/// A command's rendered page and the session's automatic actions, or its
+/// error status and message.
+type Outcome = Result<(String, Vec<String>), (StatusCode, String)>;
+
+/// GET /api/ws  (WebSocket upgrade)
+pub(crate) async fn upgrade(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> axum::response::Response {
+    // Creating a session builds a blocking HTTP client, which must stay off the runtime
+    let lookup = {
+        let state = state.clone();
+        tokio::task::spawn_blocking(move || state.get_or_create_session(&headers)).await
+    };
+    let token = match lookup {
+        Ok(Ok(token)) => token,
+        Ok(Err(s)) => return crate::session_failure(s),
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+    tracing::Span::current().record("session", token.as_str());
+
+    let header = HeaderValue::from_str(&token).ok();
+    let mut response = ws.on_upgrade(move |socket| serve(socket, state, token));
+    if let Some(value) = header {
+        response.headers_mut().insert("X-Browsy-Session", value);
+    }
+    response
+}
+
+async fn serve(mut socket: WebSocket, state: Arc<AppState>, token: String) {
+    if send(&mut socket, &ServerMessage::Session { session: token.clone() }).await.is_err() {
+        return;
+    }
+
+    let timeout = state.config.session_timeout;
+    let warn_within = (timeout / 2).min(EXPIRY_WARNING);
+    let mut expiry = tokio::time::interval((timeout / 4).min(EXPIRY_CHECK));
+    let mut warned = false;
+    loop {
+        tokio::select! {
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    for reply in run(&state, &token, text.as_str()).await {
+                        if send(&mut socket, &reply).await.is_err() {
+                            return;
+                        }
+                    }
+                    warned = false;
+                }
+                // Pings are answered by axum; binary frames carry no commands
+                Some(Ok(Message::Binary(_) | Message::Ping(_) | Message::Pong(_))) => {}
+                Some(Ok(Message::Close(_)) | Err(_)) | None => return,
+            },
+            _ = expiry.tick() => match state.session_time_left(&token) {
+                None => {
+                    let frame = CloseFrame {
+                        code: close_code::NORMAL,
+                        reason: "session expired".into(),
+                    };
+                    let _ = socket.send(Message::Close(Some(frame))).await;
+                    return;
+                }
+                Some(left) if left <= warn_within && !warned => {
+                    warned = true;
+                    let expires_in_secs = left.as_secs_f64().ceil() as u64;
+                    if send(&mut socket, &ServerMessage::SessionExpiring { expires_in_secs }).await.is_err() {
+                        return;
+                    }
+                }
+                Some(_) => {}
+            },
+        }
+    }
+}
+
+async fn send(socket: &mut WebSocket, message: &ServerMessage) -> Result<(), axum::Error> {
+    let json = serde_json::to_string(message).unwrap_or_default();
+    socket.send(Message::Text(json.into())).await
+}
+
+/// Run one command on the blocking pool and return the messages it produces:
+/// its events, then its response.
+async fn run(state: &Arc<AppState>, token: &str, text: &str) -> Vec<ServerMessage> {
+    let mut value: Value = match serde_json::from_str(text) {
+        Ok(value) => value,
+        Err(e) => return vec![response(None, Err((StatusCode::BAD_REQUEST, format!("Invalid JSON: {e}"))))],
+    };
+    let req = value.as_object_mut().and_then(|command| command.remove("req"));
+    let command = match serde_json::from_value::<Command>(value) {
+        Ok(command) => command,
+        Err(e) => return vec![response(req, Err((StatusCode::BAD_REQUEST, format!("Invalid command: {e}"))))],
+    };
+
+    let (state, token) = (state.clone(), token.to_string());
+    let span = tracing::Span::current();
+    let outcome = tokio::task::spawn_blocking(move || span.in_scope(|| execute(&state, &token, command)))
+        .await
+        .unwrap_or_else(|_| Err((StatusCode::INTERNAL_SERVER_ERROR, "Command failed".to_string())));
+
+    let mut messages: Vec<ServerMessage> = match &outcome {
+        Ok((_, auto_actions)) => auto_actions
+            .iter()
+            .map(|action| ServerMessage::AutoAction { action: action.clone() })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    messages.push(response(req, outcome.map(|(page, _)| page)));
+    messages
+}
+
+fn response(req: Option<Value>, outcome: Result<String, (StatusCode, String)>) -> ServerMessage {
+    match outcome {
+        Ok(page) => ServerMessage::Response {
+            req,
+            status: StatusCode::OK.as_u16(),
+            page: Some(page),
+            error: None,
+        },
+        Err((status, error)) => ServerMessage::Response {
+            req,
+            status: status.as_u16(),
+            page: None,
+            error: Some(error),
+        },
+    }
+}
+
+/// The same session work and rendering as the REST routes.
+fn execute(state: &AppState, token: &str, command: Command) -> Outcome {
+    match command {
+        Command::Browse(params) => {
+            let options = crate::BrowseOptions::from_params(&params)
+                .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            let dom = loaded(state, crate::browse_session(state, token, &params, &options))?;
+            let auto_actions = dom.auto_actions.clone();
+            let page = crate::render_loaded_page(
+                dom,
+                params.scope.as_deref(),
+                params.selectors,
+                params.format.as_deref(),
+            );
+            Ok((page, auto_actions))
+        }
+        Command::Click { id, format, scope } => {
+            let clicked = state.with_page(token, |session| {
+                session
+                    .click(id)
+                    .map(|dom| session.scoped(dom, scope.as_deref()))
+            });
+            let dom = loaded(state, clicked)?;
+            let auto_actions = dom.auto_actions.clone();
+            let page = crate::render_loaded_page(dom, scope.as_deref(), None, format.as_deref());
+            Ok((page, auto_actions))
+        }
+        Command::Page(query) => {
+            let dom = state
+                .with_session(token, |session| {
+                    session
+                        .dom()
+                        .map(|dom| session.scoped(dom, query.scope.as_deref()))
+                })
+                .map_err(session_expired)?
+                .ok_or_else(|| (StatusCode::BAD_REQUEST, "No page loaded".to_string()))?;
+            let page = crate::render_page(
+                dom,
+                query.scope.as_deref(),
+                query.selectors,
+                query.format.as_deref(),
+            );
+            Ok((page, Vec::new()))
+        }
+    }
+}
+
+fn loaded(state: &AppState, result: PageResult) -> Result<output::SpatialDom, (StatusCode, String)> {
+    match result {
+        Ok(Ok((dom, _))) => Ok(dom),
+        Ok(Err(e)) => {
+            let (status, body) = state.fetch_error(e);
+            Err((status, body.0.error.message))
+        }
+        Err(status) => Err(session_expired(status)),
+    }
+}
+
+/// The socket's session is gone, so it can only have expired.
+fn session_expired(status: StatusCode) -> (StatusCode, String) {
+    (status, "Session expired".to_string())
+}
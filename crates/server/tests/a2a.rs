@@ -1,5 +1,7 @@
 //! Integration tests for A2A agent card discovery and task endpoints.
 
+mod common;
+
 use std::sync::Arc;
 
 use axum_test::TestServer;
@@ -101,6 +103,51 @@ async fn agent_card_skill_content() {
     assert_eq!(examples.len(), 3);
 }
 
+#[tokio::test]
+async fn agent_card_advertises_every_capability() {
+    let server = test_server();
+    let body: Value = server.get("/.well-known/agent.json").await.json();
+    let skills = body["skills"].as_array().unwrap();
+
+    for capability in browsy_server::capabilities() {
+        let skill = skills
+            .iter()
+            .find(|s| s["id"] == capability.name)
+            .unwrap_or_else(|| panic!("no card entry for {}", capability.name));
+        assert_eq!(skill["endpoint"]["path"], capability.path);
+        assert_eq!(skill["endpoint"]["method"], capability.method);
+        assert_eq!(skill["examples"].as_array().unwrap().len(), 1);
+        if capability.input_schema.is_some() {
+            assert!(skill["inputSchema"]["properties"].is_object(), "{} schema", capability.name);
+        }
+    }
+
+    assert_eq!(body["session"]["header"], "X-Browsy-Session");
+    let formats: Vec<&str> = body["outputFormats"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v.as_str().unwrap())
+        .collect();
+    assert_eq!(formats, vec!["compact", "json", "map"]);
+}
+
+#[tokio::test]
+async fn every_capability_is_routed() {
+    let server = test_server();
+
+    for capability in browsy_server::capabilities() {
+        let response = match capability.method {
+            "GET" => server.get(capability.path).await,
+            "POST" => server.post(capability.path).json(&serde_json::json!({})).await,
+            other => panic!("unexpected method {other}"),
+        };
+        let status = response.status_code();
+        assert_ne!(status, StatusCode::NOT_FOUND, "{} {} is not routed", capability.method, capability.path);
+        assert_ne!(status, StatusCode::METHOD_NOT_ALLOWED, "{} {} has the wrong method", capability.method, capability.path);
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Task endpoint tests
 // ---------------------------------------------------------------------------
@@ -131,3 +178,212 @@ async fn task_valid_uuid_returns_unknown_status() {
     assert_eq!(body["id"], uuid);
     assert_eq!(body["status"], "unknown");
 }
+
+// ---------------------------------------------------------------------------
+// Task lifecycle tests (against a local fixture site)
+// ---------------------------------------------------------------------------
+
+const LOGIN_HTML: &str = r#"<html><head><title>Sign in</title></head><body>
+    <h1>Sign in</h1>
+    <form action="/dashboard" method="post">
+        <input type="text" name="username" placeholder="Username" />
+        <input type="password" name="password" placeholder="Password" />
+        <button type="submit">Sign in</button>
+    </form>
+</body></html>"#;
+
+const DASHBOARD_HTML: &str = r#"<html><head><title>Dashboard</title></head><body>
+    <h1>Dashboard</h1>
+    <p>Welcome back</p>
+    <a href="/logout">Log out</a>
+</body></html>"#;
+
+const VERIFY_HTML: &str = r#"<html><head><title>Verify Your Identity</title></head><body>
+    <h1>Enter verification code</h1>
+    <form action="/done" method="post">
+        <input type="text" name="code" placeholder="Enter code" />
+        <button>Verify</button>
+    </form>
+</body></html>"#;
+
+/// Serve the fixture pages on an ephemeral local port. `after_login` is
+/// returned from the login form's POST target; the verification form leads
+/// to the dashboard.
+async fn fixture_site(after_login: &'static str) -> String {
+    use axum::response::Html;
+    use axum::routing::{get, post};
+
+    let app = axum::Router::new()
+        .route("/login", get(|| async { Html(LOGIN_HTML) }))
+        .route("/dashboard", post(move || async move { Html(after_login) }))
+        .route("/done", post(|| async { Html(DASHBOARD_HTML) }));
+    format!("http://{}", common::serve(app).await)
+}
+
+fn private_server() -> TestServer {
+    let config = ServerConfig {
+        allow_private_network: true,
+        ..Default::default()
+    };
+    let state = Arc::new(AppState::new(config));
+    TestServer::new(build_router(state)).unwrap()
+}
+
+/// Parse the `data:` payloads of an SSE body into JSON values.
+fn sse_statuses(body: &str) -> Vec<Value> {
+    body.lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(|data| serde_json::from_str(data.trim_start()).unwrap())
+        .collect()
+}
+
+fn states(events: &[Value]) -> Vec<&str> {
+    events.iter().map(|e| e["status"].as_str().unwrap()).collect()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn task_login_streams_state_transitions() {
+    let site = fixture_site(DASHBOARD_HTML).await;
+    let server = private_server();
+
+    let response = server
+        .post("/a2a/tasks")
+        .json(&serde_json::json!({
+            "goal": format!("Log in to {site}/login"),
+            "params": { "credentials": { "username": "alice", "password": "hunter2" } }
+        }))
+        .await;
+    response.assert_status_ok();
+
+    let events = sse_statuses(&response.text());
+    assert_eq!(
+        states(&events),
+        vec!["submitted", "working", "working", "working", "working", "completed"]
+    );
+
+    let task_id = events[0]["id"].as_str().unwrap().to_string();
+    assert!(events.iter().all(|e| e["id"] == task_id.as_str()));
+
+    let last = events.last().unwrap();
+    let steps = last["steps"].as_array().unwrap();
+    assert_eq!(steps[0]["action"], "browse");
+    assert_eq!(steps[0]["page"]["page_type"], "Login");
+    assert_eq!(steps[0]["page"]["title"], "Sign in");
+    assert_eq!(steps[1]["action"], "login");
+    assert_eq!(steps[1]["page"]["title"], "Dashboard");
+    assert_eq!(last["result"]["title"], "Dashboard");
+
+    // The task is persisted: polling and replaying the event stream both work
+    let polled: Value = server.get(&format!("/a2a/tasks/{task_id}")).await.json();
+    assert_eq!(polled["status"], "completed");
+
+    let replay = server.get(&format!("/a2a/tasks/{task_id}/events")).await;
+    replay.assert_status_ok();
+    assert_eq!(states(&sse_statuses(&replay.text())), states(&events));
+
+    // Finished tasks can't be cancelled
+    let cancel = server.post(&format!("/a2a/tasks/{task_id}/cancel")).await;
+    cancel.assert_status(StatusCode::CONFLICT);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn task_two_factor_requires_input() {
+    let site = fixture_site(VERIFY_HTML).await;
+    let server = private_server();
+
+    let response = server
+        .post("/a2a/tasks")
+        .json(&serde_json::json!({
+            "goal": format!("Log in to {site}/login"),
+            "params": { "credentials": { "username": "alice", "password": "hunter2" } }
+        }))
+        .await;
+
+    let events = sse_statuses(&response.text());
+    let last = events.last().unwrap();
+    assert_eq!(last["status"], "input-required");
+    assert_eq!(last["challenge"]["kind"], "two_factor");
+    assert_eq!(last["challenge"]["enter_code"]["action"], "EnterCode");
+    assert!(last.get("result").is_none());
+
+    // The task waits for the code rather than ending
+    let task_id = last["id"].as_str().unwrap();
+    let polled: Value = server.get(&format!("/a2a/tasks/{task_id}")).await.json();
+    assert_eq!(polled["status"], "input-required");
+    let cancel = server.post(&format!("/a2a/tasks/{task_id}/cancel")).await;
+    cancel.assert_status_ok();
+    assert_eq!(cancel.json::<Value>()["status"], "canceled");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn task_resumes_with_the_verification_code() {
+    let site = fixture_site(VERIFY_HTML).await;
+    let server = private_server();
+
+    let response = server
+        .post("/a2a/tasks")
+        .json(&serde_json::json!({
+            "goal": format!("Log in to {site}/login"),
+            "params": { "credentials": { "username": "alice", "password": "hunter2" } }
+        }))
+        .await;
+    let events = sse_statuses(&response.text());
+    let task_id = events[0]["id"].as_str().unwrap().to_string();
+    assert_eq!(events.last().unwrap()["status"], "input-required");
+
+    // A two-factor challenge can't go on without a code
+    server
+        .post(&format!("/a2a/tasks/{task_id}/input"))
+        .json(&serde_json::json!({}))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    let resumed = server
+        .post(&format!("/a2a/tasks/{task_id}/input"))
+        .json(&serde_json::json!({ "code": "123456" }))
+        .await;
+    resumed.assert_status_ok();
+    let events = sse_statuses(&resumed.text());
+    assert_eq!(states(&events), vec!["working", "working", "completed"]);
+
+    let last = events.last().unwrap();
+    assert!(last.get("challenge").is_none());
+    let steps = last["steps"].as_array().unwrap();
+    let enter_code = steps.iter().find(|s| s["action"] == "enter_code").unwrap();
+    assert_eq!(enter_code["page"]["title"], "Dashboard");
+    assert_eq!(last["result"]["title"], "Dashboard");
+    assert_eq!(last["result"]["login"]["status"], "success");
+
+    // The replay holds both streams
+    let replay = server.get(&format!("/a2a/tasks/{task_id}/events")).await;
+    let replayed = sse_statuses(&replay.text());
+    assert_eq!(replayed[replayed.len() - 4]["status"], "input-required");
+    assert_eq!(replayed.last().unwrap()["status"], "completed");
+
+    // Only a task waiting for input takes it
+    server
+        .post(&format!("/a2a/tasks/{task_id}/input"))
+        .json(&serde_json::json!({ "code": "123456" }))
+        .await
+        .assert_status(StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn task_unknown_id_events_and_cancel_return_404() {
+    let server = test_server();
+    let uuid = "550e8400-e29b-41d4-a716-446655440000";
+
+    server
+        .get(&format!("/a2a/tasks/{uuid}/events"))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    server
+        .post(&format!("/a2a/tasks/{uuid}/cancel"))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    server
+        .post(&format!("/a2a/tasks/{uuid}/input"))
+        .json(&serde_json::json!({ "code": "123456" }))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+}
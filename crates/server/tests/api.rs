@@ -1,8 +1,11 @@
 //! Integration tests for the browsy REST API.
 
+mod common;
+
 use std::sync::Arc;
 
 use axum_test::TestServer;
+use browsy_core::fetch::ActionPolicy;
 use browsy_server::{AppState, ServerConfig, build_router};
 use http::StatusCode;
 use serde_json::json;
@@ -77,6 +80,115 @@ async fn tables_without_browse_returns_400() {
     res.assert_status(StatusCode::BAD_REQUEST);
 }
 
+#[tokio::test]
+async fn forked_session_diverges_from_original() {
+    let server = test_server();
+    let session = http::header::HeaderName::from_static("x-browsy-session");
+    let html = r#"<form><input name="q"><button>Search</button></form>"#;
+    let res = server.post("/api/parse").json(&json!({ "html": html, "format": "json" })).await;
+    let original = session_header(&res);
+    let field = element_id(&res.json(), |el| el["name"] == "q");
+    server
+        .post("/api/type")
+        .add_header(session.clone(), original.clone())
+        .json(&json!({ "id": field, "text": "shoes" }))
+        .await
+        .assert_status_ok();
+
+    let res = server
+        .post(&format!("/api/sessions/{}/fork", original.to_str().unwrap()))
+        .await;
+    res.assert_status_ok();
+    let body: serde_json::Value = res.json();
+    let fork = http::HeaderValue::from_str(body["session"].as_str().unwrap()).unwrap();
+    assert_ne!(fork, original);
+
+    server
+        .post("/api/type")
+        .add_header(session.clone(), fork.clone())
+        .json(&json!({ "id": field, "text": "boots" }))
+        .await
+        .assert_status_ok();
+    let value = |res: axum_test::TestResponse| {
+        let page: serde_json::Value = res.json();
+        page["els"].as_array().unwrap().iter().find(|el| el["id"] == field).unwrap()["val"].clone()
+    };
+    let page = |token: http::HeaderValue| {
+        server
+            .get("/api/page")
+            .add_header(session.clone(), token)
+            .add_query_param("format", "json")
+    };
+    assert_eq!(value(page(original).await), "shoes");
+    assert_eq!(value(page(fork).await), "boots");
+
+    let res = server.post("/api/sessions/no-such-session/fork").await;
+    res.assert_status(StatusCode::BAD_REQUEST);
+    assert_eq!(res.json::<serde_json::Value>()["error"]["code"], "session_expired");
+}
+
+#[tokio::test]
+async fn new_content_scope_drops_shared_nav() {
+    let server = test_server();
+    let session = http::header::HeaderName::from_static("x-browsy-session");
+    let page = |body: &str| {
+        format!(r#"<html><body><nav><a href="/">Home</a><a href="/docs">Docs</a></nav>{body}</body></html>"#)
+    };
+
+    let res = server
+        .post("/api/parse")
+        .json(&json!({ "html": page("<h1>Install</h1>"), "url": "https://docs.example/install" }))
+        .await;
+    let token = session_header(&res);
+    let res = server
+        .post("/api/parse")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({
+            "html": page("<h1>Configure</h1><p>Edit the config file.</p>"),
+            "url": "https://docs.example/configure",
+            "scope": "new_content",
+        }))
+        .await;
+    res.assert_status_ok();
+    let text = res.text();
+    assert!(text.contains("[boilerplate: ") && text.contains(" els suppressed]"), "{text}");
+    assert!(text.contains("Configure") && text.contains("Edit the config file."), "{text}");
+    assert!(!text.contains("Docs"), "{text}");
+
+    // Without the scope the whole page comes back
+    let res = server
+        .get("/api/page")
+        .add_header(session, token)
+        .await;
+    assert!(res.text().contains("Docs"));
+}
+
+#[tokio::test]
+async fn summary_counts_parsed_page() {
+    let server = test_server();
+    server.get("/api/summary").await.assert_status(StatusCode::BAD_REQUEST);
+
+    let html = r#"<html><body>
+        <form><input name="q"><button>Search</button></form>
+        <a href="/a">A</a><a href="/b">B</a>
+        <div style="display:none"><a href="/c">C</a></div>
+    </body></html>"#;
+    let res = server.post("/api/parse").json(&json!({ "html": html })).await;
+    res.assert_status_ok();
+
+    let res = server
+        .get("/api/summary")
+        .add_header(http::header::HeaderName::from_static("x-browsy-session"), session_header(&res))
+        .await;
+    res.assert_status_ok();
+    let summary: serde_json::Value = res.json();
+    assert_eq!(summary["link_count"], 3);
+    assert_eq!(summary["hidden_count"], 1);
+    assert_eq!(summary["form_count"], 1);
+    assert_eq!(summary["table_count"], 0);
+    assert_eq!(summary["counts_by_role"]["link"], 3);
+}
+
 // ---------------------------------------------------------------------------
 // CORS headers
 // ---------------------------------------------------------------------------
@@ -93,6 +205,68 @@ async fn cors_headers_present() {
     assert_eq!(header.to_str().unwrap(), "*");
 }
 
+fn test_server_with_cors(origins: &[&str]) -> TestServer {
+    let config = ServerConfig {
+        cors_origins: origins.iter().map(|o| o.to_string()).collect(),
+        ..Default::default()
+    };
+    TestServer::new(build_router(Arc::new(AppState::new(config)))).unwrap()
+}
+
+fn header_str<'a>(res: &'a axum_test::TestResponse, name: &str) -> &'a str {
+    res.headers()
+        .get(name)
+        .unwrap_or_else(|| panic!("{name} header should be present"))
+        .to_str()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn cors_preflight_allows_session_header() {
+    let server = test_server_with_cors(&["https://ui.example"]);
+    let res = server
+        .method(http::Method::OPTIONS, "/api/parse")
+        .add_header(http::header::ORIGIN, http::HeaderValue::from_static("https://ui.example"))
+        .add_header(
+            http::header::ACCESS_CONTROL_REQUEST_METHOD,
+            http::HeaderValue::from_static("POST"),
+        )
+        .add_header(
+            http::header::ACCESS_CONTROL_REQUEST_HEADERS,
+            http::HeaderValue::from_static("content-type,x-browsy-session"),
+        )
+        .await;
+    res.assert_status_ok();
+    assert_eq!(header_str(&res, "access-control-allow-origin"), "https://ui.example");
+    assert!(header_str(&res, "access-control-allow-methods").contains("POST"));
+    let allowed = header_str(&res, "access-control-allow-headers").to_ascii_lowercase();
+    assert!(allowed.contains("x-browsy-session"), "{allowed}");
+    assert!(allowed.contains("content-type"), "{allowed}");
+}
+
+#[tokio::test]
+async fn cors_post_exposes_session_header() {
+    let server = test_server_with_cors(&["https://ui.example"]);
+    let res = server
+        .post("/api/parse")
+        .add_header(http::header::ORIGIN, http::HeaderValue::from_static("https://ui.example"))
+        .json(&json!({ "html": "<p>Hello</p>" }))
+        .await;
+    res.assert_status_ok();
+    assert_eq!(header_str(&res, "access-control-allow-origin"), "https://ui.example");
+    let exposed = header_str(&res, "access-control-expose-headers").to_ascii_lowercase();
+    assert!(exposed.contains("x-browsy-session"), "{exposed}");
+    session_header(&res);
+
+    // Other origins get no CORS grant
+    let res = server
+        .post("/api/parse")
+        .add_header(http::header::ORIGIN, http::HeaderValue::from_static("https://evil.example"))
+        .json(&json!({ "html": "<p>Hello</p>" }))
+        .await;
+    assert!(res.headers().get("access-control-allow-origin").is_none());
+}
+
 // ---------------------------------------------------------------------------
 // Session limit enforcement
 // ---------------------------------------------------------------------------
@@ -265,3 +439,1217 @@ async fn select_without_browse_returns_error() {
         res.status_code()
     );
 }
+
+#[tokio::test]
+async fn errors_carry_codes_and_details() {
+    let server = test_server();
+    let session = http::header::HeaderName::from_static("x-browsy-session");
+
+    let res = server.get("/api/page").await;
+    res.assert_status(StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["error"]["code"], "no_page_loaded");
+    assert_eq!(body["error"]["message"], "No page loaded");
+
+    let res = server
+        .post("/api/parse")
+        .json(&json!({ "html": "<h1>Hi</h1><input name='q'><a href='/a'>A</a>", "format": "json" }))
+        .await;
+    res.assert_status_ok();
+    let token = session_header(&res);
+    let page: serde_json::Value = res.json();
+    let ids: Vec<u64> = page["els"].as_array().unwrap().iter().map(|e| e["id"].as_u64().unwrap()).collect();
+
+    let res = server
+        .post("/api/click")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({ "id": 999 }))
+        .await;
+    res.assert_status(StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["error"]["code"], "action_element_not_found");
+    assert_eq!(body["error"]["message"], "Action error: Element 999 not found");
+    let range = &body["error"]["details"]["known_ids_range"];
+    assert_eq!(range, &json!([ids.iter().min(), ids.iter().max()]));
+
+    let heading = element_id(&page, |e| e["tag"] == "h1");
+    let res = server
+        .post("/api/type")
+        .add_header(session, token)
+        .json(&json!({ "id": heading, "text": "hello" }))
+        .await;
+    res.assert_status(StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["error"]["code"], "action_wrong_element_type");
+    assert_eq!(body["error"]["details"]["expected"], "text input");
+}
+
+// ---------------------------------------------------------------------------
+// POST /api/parse
+// ---------------------------------------------------------------------------
+
+const SIGNUP_HTML: &str = include_str!("fixtures/signup.html");
+
+fn session_header(res: &axum_test::TestResponse) -> http::header::HeaderValue {
+    res.headers()
+        .get("x-browsy-session")
+        .expect("should return session token")
+        .clone()
+}
+
+fn element_id(page: &serde_json::Value, matches: impl Fn(&serde_json::Value) -> bool) -> u64 {
+    page["els"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|el| matches(el))
+        .and_then(|el| el["id"].as_u64())
+        .expect("element should be present")
+}
+
+#[tokio::test]
+async fn parse_fixture_form_then_interact() {
+    let server = test_server();
+    let session = http::header::HeaderName::from_static("x-browsy-session");
+
+    let res = server
+        .post("/api/parse")
+        .json(&json!({
+            "html": SIGNUP_HTML,
+            "url": "https://shop.example/signup",
+            "format": "json",
+        }))
+        .await;
+    res.assert_status_ok();
+    let token = session_header(&res);
+    let page: serde_json::Value = res.json();
+    assert_eq!(page["title"], "Create account");
+    assert_eq!(page["url"], "https://shop.example/signup");
+    let email = element_id(&page, |el| el["name"] == "email");
+    let rules = element_id(&page, |el| el["text"] == "Password rules");
+    assert!(
+        page["els"].as_array().unwrap().iter().any(|el| el["href"] == "https://shop.example/terms"),
+        "relative links should resolve against the given URL"
+    );
+
+    let res = server
+        .post("/api/type")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({ "id": email, "text": "ada@example.com" }))
+        .await;
+    res.assert_status_ok();
+
+    let res = server
+        .get("/api/page")
+        .add_header(session.clone(), token.clone())
+        .add_query_param("format", "json")
+        .await;
+    res.assert_status_ok();
+    let page: serde_json::Value = res.json();
+    assert!(page["els"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|el| el["id"] == email && el["val"] == "ada@example.com"));
+
+    // Same-page JS behaviors run against the parsed HTML
+    let res = server
+        .post("/api/click")
+        .add_header(session, token)
+        .json(&json!({ "id": rules }))
+        .await;
+    res.assert_status_ok();
+    let text = res.text();
+    assert!(text.contains("Passwords need at least 12 characters"), "{text}");
+    assert!(text.contains("url: https://shop.example/signup"), "{text}");
+}
+
+/// Serve one page with `ETag: "v1"`, answering 304 when the client has it.
+async fn etag_site() -> String {
+    use axum::http::{header, HeaderMap};
+    use axum::response::IntoResponse;
+
+    let app = axum::Router::new().route(
+        "/",
+        axum::routing::get(|headers: HeaderMap| async move {
+            let etag = [(header::ETAG, "\"v1\"")];
+            if headers.get(header::IF_NONE_MATCH).is_some_and(|v| v == "\"v1\"") {
+                (StatusCode::NOT_MODIFIED, etag).into_response()
+            } else {
+                (etag, axum::response::Html("<title>Cached</title><h1>Hello</h1>")).into_response()
+            }
+        }),
+    );
+    format!("http://{}/", common::serve(app).await)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn browse_if_modified_flags_unchanged_page() {
+    let site = etag_site().await;
+    let server = test_server();
+    let session = http::header::HeaderName::from_static("x-browsy-session");
+
+    let res = server
+        .post("/api/browse")
+        .json(&json!({ "url": site, "if_modified": true }))
+        .await;
+    res.assert_status_ok();
+    assert!(res.headers().get("x-browsy-not-modified").is_none());
+    let token = session_header(&res);
+
+    let res = server
+        .post("/api/browse")
+        .add_header(session, token)
+        .json(&json!({ "url": site, "if_modified": true }))
+        .await;
+    res.assert_status_ok();
+    assert_eq!(res.headers().get("x-browsy-not-modified").unwrap(), "true");
+    assert!(res.text().contains("Cached"));
+}
+
+/// Serve `/a` -> `/b` -> `/c` -> `/a`, redirecting forever.
+async fn redirect_loop_site() -> String {
+    use axum::response::Redirect;
+
+    let app = axum::Router::new()
+        .route("/a", axum::routing::get(|| async { Redirect::to("/b") }))
+        .route("/b", axum::routing::get(|| async { Redirect::to("/c") }))
+        .route("/c", axum::routing::get(|| async { Redirect::to("/a") }));
+    format!("http://{}", common::serve(app).await)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn redirect_loop_returns_bad_gateway_with_chain() {
+    let site = redirect_loop_site().await;
+    let server = test_server();
+
+    let res = server
+        .post("/api/browse")
+        .json(&json!({ "url": format!("{site}/a") }))
+        .await;
+    res.assert_status(StatusCode::BAD_GATEWAY);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["error"]["code"], "too_many_redirects");
+    let message = body["error"]["message"].as_str().unwrap();
+    assert_eq!(message, format!("Redirect loop: {site}/a -> {site}/b -> {site}/c -> {site}/a"));
+    assert_eq!(body["error"]["details"]["redirect_chain"].as_array().unwrap().len(), 4);
+}
+
+/// Serve `/echo`, whose heading is its raw query string, and a CAPTCHA page
+/// at `/check`.
+async fn echo_site() -> String {
+    use axum::extract::RawQuery;
+    use axum::response::Html;
+
+    let app = axum::Router::new()
+        .route(
+            "/echo",
+            axum::routing::get(|RawQuery(query): RawQuery| async move {
+                Html(format!(
+                    "<title>Echo</title><h1>{}</h1><button>Go</button><p style=\"margin-top: 2000px\">Footer</p>",
+                    query.unwrap_or_default()
+                ))
+            }),
+        )
+        .route(
+            "/check",
+            axum::routing::get(|| async {
+                Html("<title>Security check</title><h1>Are you human?</h1><button>Verify</button>")
+            }),
+        );
+    format!("http://{}", common::serve(app).await)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn browse_get_matches_post() {
+    let site = echo_site().await;
+    let server = test_server();
+    // `%2B` and `+` in the target URL must come through the outer query string intact
+    let target = format!("{site}/echo?q=c%2B%2B+rust");
+
+    for (format, scope) in [("compact", "all"), ("json", "visible"), ("compact", "above_fold")] {
+        let post = server
+            .post("/api/browse")
+            .json(&json!({ "url": target, "format": format, "scope": scope }))
+            .await;
+        let get = server
+            .get("/api/browse")
+            .add_query_param("url", &target)
+            .add_query_param("format", format)
+            .add_query_param("scope", scope)
+            .await;
+        post.assert_status_ok();
+        get.assert_status_ok();
+        assert_eq!(get.text(), post.text(), "{format} {scope}");
+        assert!(get.text().contains("q=c%2B%2B+rust"), "{}", get.text());
+        assert_eq!(get.text().contains("Footer"), scope != "above_fold", "{}", get.text());
+    }
+
+    // Encoded by hand: a literal `+` in the outer query would mean a space
+    let encoded = target
+        .replace('%', "%25")
+        .replace('+', "%2B")
+        .replace(':', "%3A")
+        .replace('/', "%2F")
+        .replace('?', "%3F")
+        .replace('=', "%3D");
+    let get = server.get(&format!("/api/browse?url={encoded}&format=json")).await;
+    get.assert_status_ok();
+    let page: serde_json::Value = get.json();
+    assert_eq!(page["url"], target);
+
+    // The CAPTCHA warning is prefixed the same way
+    let captcha = format!("{site}/check");
+    let post = server.post("/api/browse").json(&json!({ "url": captcha })).await;
+    let get = server.get("/api/browse").add_query_param("url", &captcha).await;
+    assert!(get.text().starts_with("\u{26a0} CAPTCHA detected"), "{}", get.text());
+    assert_eq!(get.text(), post.text());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn find_get_matches_post_within_session() {
+    let site = echo_site().await;
+    let server = test_server();
+    let session = http::header::HeaderName::from_static("x-browsy-session");
+
+    let res = server.get("/api/browse").add_query_param("url", format!("{site}/echo?q=1")).await;
+    res.assert_status_ok();
+    let token = session_header(&res);
+
+    for (query, body) in [
+        (vec![("text", "Go")], json!({ "text": "Go" })),
+        (vec![("role", "button")], json!({ "role": "button" })),
+        (vec![("text", "Footer"), ("role", "button")], json!({ "text": "Footer", "role": "button" })),
+    ] {
+        let post = server.post("/api/find").add_header(session.clone(), token.clone()).json(&body).await;
+        let mut get = server.get("/api/find").add_header(session.clone(), token.clone());
+        for (key, value) in query {
+            get = get.add_query_param(key, value);
+        }
+        let get = get.await;
+        post.assert_status_ok();
+        get.assert_status_ok();
+        let (post, get): (serde_json::Value, serde_json::Value) = (post.json(), get.json());
+        assert!(!get.as_array().unwrap().is_empty(), "{body}");
+        assert_eq!(get, post, "{body}");
+    }
+}
+
+#[tokio::test]
+async fn long_text_is_cut_short_and_served_whole() {
+    let server = test_server();
+    let session = http::header::HeaderName::from_static("x-browsy-session");
+    let html = include_str!("fixtures/terms.html");
+    let paragraph = html.split("<p>").nth(1).unwrap().split("</p>").next().unwrap();
+    assert!(paragraph.len() >= 5000);
+
+    let res = server
+        .post("/api/parse")
+        .json(&json!({ "html": html, "url": "https://shop.example/terms" }))
+        .await;
+    res.assert_status_ok();
+    let token = session_header(&res);
+    let compact = res.text();
+    let line = compact.lines().find(|l| l.contains("By using this service")).unwrap();
+    assert!(line.ends_with("\u{2026}\" (5k chars)]"), "{line}");
+    assert!(line.len() < 300, "{line}");
+
+    let id = line[1..].split(':').next().unwrap();
+    let res = server
+        .get(&format!("/api/element/{id}/text"))
+        .add_header(session.clone(), token.clone())
+        .await;
+    res.assert_status_ok();
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["text"], paragraph);
+
+    let res = server.get("/api/element/999/text").add_header(session, token).await;
+    res.assert_status(StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["error"]["code"], "action_element_not_found");
+}
+
+#[tokio::test]
+async fn element_context_groups_a_card() {
+    let server = test_server();
+    let session = http::header::HeaderName::from_static("x-browsy-session");
+    let res = server
+        .post("/api/parse")
+        .json(&json!({
+            "html": include_str!("../../core/tests/fixtures/product_cards.html"),
+            "url": "https://shop.example/deals",
+        }))
+        .await;
+    res.assert_status_ok();
+    let token = session_header(&res);
+    let compact = res.text();
+    let line = compact.lines().find(|l| l.contains("\"$89.00\"")).unwrap();
+    let id = line[1..].split(':').next().unwrap();
+
+    let res = server
+        .get(&format!("/api/element/{id}/context"))
+        .add_query_param("radius", 20)
+        .add_header(session.clone(), token.clone())
+        .await;
+    res.assert_status_ok();
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["element"]["text"], "$89.00");
+    let row: Vec<&str> = body["row"].as_array().unwrap().iter().map(|e| e["text"].as_str().unwrap()).collect();
+    assert_eq!(row, ["Bookshelf", "$89.00", "Add to cart"]);
+    assert_eq!(body["container"]["tag"], "section");
+    assert_eq!(body["left"]["text"], "Bookshelf");
+    assert_eq!(body["neighbors"].as_array().unwrap().len(), 2);
+
+    let res = server.get("/api/element/999/context").add_header(session, token).await;
+    res.assert_status(StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["error"]["code"], "action_element_not_found");
+}
+
+#[tokio::test]
+async fn domain_lists_from_config_and_per_session() {
+    let config = ServerConfig {
+        blocked_domains: vec!["facebook.com".to_string()],
+        ..Default::default()
+    };
+    let server = TestServer::new(build_router(Arc::new(AppState::new(config)))).unwrap();
+    let session = http::header::HeaderName::from_static("x-browsy-session");
+
+    let res = server
+        .post("/api/browse")
+        .json(&json!({ "url": "https://m.facebook.com/groups" }))
+        .await;
+    res.assert_status(StatusCode::BAD_REQUEST);
+    let token = session_header(&res);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["error"]["code"], "blocked_url");
+    assert_eq!(body["error"]["message"], "Blocked URL: https://m.facebook.com/groups");
+
+    // Narrow the session to one domain; the server-wide block list stays
+    let res = server
+        .post("/api/session-config")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({ "allowed_domains": ["mycorp.com"] }))
+        .await;
+    res.assert_status_ok();
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["allowed_domains"], json!(["mycorp.com"]));
+    assert_eq!(body["blocked_domains"], json!(["facebook.com"]));
+
+    let res = server
+        .post("/api/browse")
+        .add_header(session, token)
+        .json(&json!({ "url": "https://example.org/" }))
+        .await;
+    res.assert_status(StatusCode::BAD_REQUEST);
+}
+
+/// Serve a search form whose 300px box is most of a phone screen but a
+/// sliver of a desktop one.
+async fn search_site() -> String {
+    let app = axum::Router::new().route(
+        "/",
+        axum::routing::get(|| async {
+            axum::response::Html(
+                r#"<html><head><title>Search</title><meta name="viewport" content="width=device-width"></head>
+                <body><form><input name="q" placeholder="Search" style="width: 300px"><button>Go</button></form></body></html>"#,
+            )
+        }),
+    );
+    format!("http://{}/", common::serve(app).await)
+}
+
+fn search_box_line(page: &str) -> &str {
+    page.lines().find(|line| line.contains("\"Search\"")).unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn sessions_keep_their_own_viewports() {
+    let site = search_site().await;
+    let server = test_server();
+    let session = http::header::HeaderName::from_static("x-browsy-session");
+
+    let phone = json!({ "url": site, "viewport": "375x812", "fetch_css": false, "user_agent": "browsy-phone" });
+    let desktop = json!({ "url": site, "viewport": "1920x1080" });
+    let (phone, desktop) = tokio::join!(
+        async { server.post("/api/browse").json(&phone).await },
+        async { server.post("/api/browse").json(&desktop).await },
+    );
+    phone.assert_status_ok();
+    desktop.assert_status_ok();
+    assert!(search_box_line(&phone.text()).ends_with(" wide]"), "{}", phone.text());
+    assert!(!search_box_line(&desktop.text()).contains("wide"), "{}", desktop.text());
+
+    // The phone session keeps its settings for later requests
+    let token = session_header(&phone);
+    let res = server
+        .get("/api/page")
+        .add_header(session.clone(), token.clone())
+        .add_query_param("format", "json")
+        .await;
+    let page: serde_json::Value = res.json();
+    assert_eq!(page["vp"], json!([375.0, 812.0]));
+    let res = server
+        .post("/api/browse")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({ "url": site, "format": "json" }))
+        .await;
+    let page: serde_json::Value = res.json();
+    assert_eq!(page["vp"], json!([375.0, 812.0]));
+
+    let res = server
+        .post("/api/session-config")
+        .add_header(session, session_header(&desktop))
+        .json(&json!({}))
+        .await;
+    let settings: serde_json::Value = res.json();
+    assert_eq!(settings["viewport"], "1920x1080");
+    assert_eq!(settings["fetch_css"], true);
+    assert_ne!(settings["user_agent"], "browsy-phone");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn session_config_viewport_relays_out_current_page() {
+    let site = search_site().await;
+    let server = test_server();
+    let session = http::header::HeaderName::from_static("x-browsy-session");
+
+    let res = server.post("/api/browse").json(&json!({ "url": site })).await;
+    res.assert_status_ok();
+    let token = session_header(&res);
+    let page = res.text();
+    let search_id = search_box_line(&page)
+        .trim_start_matches('[')
+        .split(':')
+        .next()
+        .unwrap()
+        .to_string();
+    server
+        .post("/api/type")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({ "id": search_id.parse::<u32>().unwrap(), "text": "boots" }))
+        .await
+        .assert_status_ok();
+
+    let res = server
+        .post("/api/session-config")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({ "viewport": "375x812" }))
+        .await;
+    res.assert_status_ok();
+    let settings: serde_json::Value = res.json();
+    assert_eq!(settings["viewport"], "375x812");
+
+    let res = server.get("/api/page").add_header(session.clone(), token.clone()).await;
+    let page = res.text();
+    let line = search_box_line(&page);
+    assert!(line.starts_with(&format!("[{search_id}:")), "{page}");
+    assert!(line.contains("[=boots]") && line.ends_with(" wide]"), "{page}");
+
+    for bad in [json!({ "viewport": "wide" }), json!({ "viewport": "375x0" }), json!({ "user_agent": "a\nb" })] {
+        let res = server
+            .post("/api/session-config")
+            .add_header(session.clone(), token.clone())
+            .json(&bad)
+            .await;
+        res.assert_status(StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = res.json();
+        assert!(body["error"]["message"].as_str().unwrap().starts_with("Invalid"), "{body}");
+    }
+    let res = server
+        .post("/api/browse")
+        .json(&json!({ "url": site, "viewport": "375 by 812" }))
+        .await;
+    res.assert_status(StatusCode::BAD_REQUEST);
+    let body: serde_json::Value = res.json();
+    assert!(body["error"]["message"].as_str().unwrap().contains("WIDTHxHEIGHT"), "{body}");
+}
+
+#[tokio::test]
+async fn selectors_are_computed_only_once_asked_for() {
+    let server = test_server();
+    let session = http::header::HeaderName::from_static("x-browsy-session");
+    let selectors = |page: &serde_json::Value| {
+        page["els"].as_array().unwrap().iter().filter(|el| el.get("selector").is_some()).count()
+    };
+
+    let res = server
+        .post("/api/parse")
+        .json(&json!({ "html": SIGNUP_HTML, "format": "json" }))
+        .await;
+    let token = session_header(&res);
+    assert_eq!(selectors(&res.json()), 0);
+    // Asking on a page loaded without them finds none
+    let res = server
+        .get("/api/page")
+        .add_header(session.clone(), token.clone())
+        .add_query_param("format", "json")
+        .add_query_param("selectors", "true")
+        .await;
+    assert_eq!(selectors(&res.json()), 0);
+    let res = server
+        .post("/api/session-config")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({}))
+        .await;
+    assert_eq!(res.json::<serde_json::Value>()["selectors"], false);
+
+    let res = server
+        .post("/api/parse")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({ "html": SIGNUP_HTML, "format": "json", "selectors": true }))
+        .await;
+    assert!(selectors(&res.json()) > 0);
+    // The session keeps computing them, but only shows them when asked
+    let res = server
+        .post("/api/parse")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({ "html": SIGNUP_HTML, "format": "json" }))
+        .await;
+    assert_eq!(selectors(&res.json()), 0);
+    let res = server
+        .get("/api/page")
+        .add_header(session.clone(), token.clone())
+        .add_query_param("format", "json")
+        .add_query_param("selectors", "true")
+        .await;
+    assert!(selectors(&res.json()) > 0);
+
+    let res = server
+        .post("/api/session-config")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({ "selectors": false }))
+        .await;
+    assert_eq!(res.json::<serde_json::Value>()["selectors"], false);
+    server
+        .post("/api/parse")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({ "html": SIGNUP_HTML }))
+        .await
+        .assert_status_ok();
+    let res = server
+        .get("/api/page")
+        .add_header(session, token)
+        .add_query_param("format", "json")
+        .add_query_param("selectors", "true")
+        .await;
+    assert_eq!(selectors(&res.json()), 0);
+}
+
+#[tokio::test]
+async fn typed_password_is_redacted() {
+    let server = test_server();
+    let session = http::header::HeaderName::from_static("x-browsy-session");
+    let res = server
+        .post("/api/parse")
+        .json(&json!({ "html": SIGNUP_HTML, "format": "json" }))
+        .await;
+    let token = session_header(&res);
+    let page: serde_json::Value = res.json();
+    let email = element_id(&page, |el| el["name"] == "email");
+    let password = element_id(&page, |el| el["name"] == "password");
+
+    let res = server
+        .post("/api/type")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({ "id": password, "text": "hunter22" }))
+        .await;
+    res.assert_status_ok();
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["message"], format!("Typed •••• (8 chars) into element {password}"));
+
+    let res = server
+        .post("/api/type")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({ "id": email, "text": "ada@example.com" }))
+        .await;
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["message"], format!("Typed \"ada@example.com\" into element {email}"));
+
+    let res = server
+        .get("/api/page")
+        .add_header(session, token)
+        .add_query_param("format", "json")
+        .await;
+    let text = res.text();
+    assert!(!text.contains("hunter22"), "password leaked: {text}");
+    assert!(text.contains("ada@example.com"), "{text}");
+}
+
+#[tokio::test]
+async fn audit_log_lists_session_actions() {
+    let server = test_server();
+    let session = http::header::HeaderName::from_static("x-browsy-session");
+    let res = server
+        .post("/api/parse")
+        .json(&json!({ "html": SIGNUP_HTML, "url": "https://example.com/signup", "format": "json" }))
+        .await;
+    let token = session_header(&res);
+    let page: serde_json::Value = res.json();
+    let password = element_id(&page, |el| el["name"] == "password");
+    server
+        .post("/api/type")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({ "id": password, "text": "hunter22" }))
+        .await
+        .assert_status_ok();
+
+    let res = server.get("/api/audit").add_header(session.clone(), token.clone()).await;
+    res.assert_status_ok();
+    let events: serde_json::Value = res.json();
+    let events = events.as_array().unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0]["seq"], 1);
+    assert_eq!(events[0]["type"], "load_html");
+    assert_eq!(events[0]["url"], "https://example.com/signup");
+    assert_eq!(events[1]["type"], "type");
+    assert_eq!(events[1]["id"], password);
+    assert_eq!(events[1]["value"], "•••• (8 chars)");
+    assert!(events[1]["ts_ms"].as_u64().unwrap() >= events[0]["ts_ms"].as_u64().unwrap());
+
+    let res = server
+        .get("/api/audit")
+        .add_header(session, token)
+        .add_query_param("since_seq", 1)
+        .await;
+    let events: serde_json::Value = res.json();
+    assert_eq!(events.as_array().unwrap().len(), 1);
+    assert_eq!(events[0]["seq"], 2);
+}
+
+#[tokio::test]
+async fn snapshot_saves_page_with_redacted_form_state() {
+    let dir = std::env::temp_dir().join(format!("browsy-api-snapshot-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let config = ServerConfig { snapshot_dir: dir.clone(), ..Default::default() };
+    let server = TestServer::new(build_router(Arc::new(AppState::new(config)))).unwrap();
+    let session = http::header::HeaderName::from_static("x-browsy-session");
+    let res = server
+        .post("/api/parse")
+        .json(&json!({ "html": SIGNUP_HTML, "url": "https://example.com/signup", "format": "json" }))
+        .await;
+    let token = session_header(&res);
+    let page: serde_json::Value = res.json();
+    let password = element_id(&page, |el| el["name"] == "password");
+    server
+        .post("/api/type")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({ "id": password, "text": "hunter22" }))
+        .await
+        .assert_status_ok();
+
+    let res = server.post("/api/snapshot").add_header(session, token).await;
+    res.assert_status_ok();
+    let body: serde_json::Value = res.json();
+    let folder = std::path::PathBuf::from(body["path"].as_str().unwrap());
+    assert!(folder.starts_with(&dir));
+    assert_eq!(std::fs::read_to_string(folder.join("page.html")).unwrap(), SIGNUP_HTML);
+    let form_state = std::fs::read_to_string(folder.join("form_state.json")).unwrap();
+    assert!(form_state.contains("•••• (8 chars)"), "{form_state}");
+    assert!(!form_state.contains("hunter22"), "{form_state}");
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+#[tokio::test]
+async fn parse_defaults_to_about_blank() {
+    let server = test_server();
+    let res = server
+        .post("/api/parse")
+        .json(&json!({ "html": SIGNUP_HTML, "format": "json" }))
+        .await;
+    res.assert_status_ok();
+    let page: serde_json::Value = res.json();
+    assert_eq!(page["url"], "about:blank");
+    assert!(
+        page["els"].as_array().unwrap().iter().any(|el| el["href"] == "/terms"),
+        "relative links should stay unresolved"
+    );
+}
+
+#[tokio::test]
+async fn parse_applies_viewport() {
+    let server = test_server();
+    let res = server
+        .post("/api/parse")
+        .json(&json!({ "html": SIGNUP_HTML, "viewport": [1280, 720], "format": "json" }))
+        .await;
+    res.assert_status_ok();
+    let page: serde_json::Value = res.json();
+    assert_eq!(page["vp"], json!([1280.0, 720.0]));
+
+    let res = server
+        .post("/api/parse")
+        .json(&json!({ "html": SIGNUP_HTML, "viewport": [0, 720] }))
+        .await;
+    res.assert_status(StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn parse_reports_phase_timings() {
+    let server = test_server();
+    let res = server
+        .post("/api/parse")
+        .json(&json!({ "html": SIGNUP_HTML }))
+        .await;
+    res.assert_status_ok();
+    let token = session_header(&res);
+    let header = res
+        .headers()
+        .get("x-browsy-timing")
+        .expect("should return phase timings")
+        .to_str()
+        .unwrap()
+        .to_string();
+    let timings: serde_json::Value = serde_json::from_str(&header).unwrap();
+    for phase in ["fetch", "css_fetch", "parse", "style", "layout", "spatial", "total"] {
+        assert!(timings[phase].is_number(), "missing {phase} in {header}");
+    }
+    assert_eq!(timings["fetch"], 0.0);
+
+    let res = server
+        .get("/api/page-info")
+        .add_header(http::header::HeaderName::from_static("x-browsy-session"), token)
+        .await;
+    res.assert_status_ok();
+    let info: serde_json::Value = res.json();
+    assert_eq!(info["timings"], timings);
+}
+
+#[tokio::test]
+async fn parse_rejects_oversized_html() {
+    let server = test_server();
+    let html = "a".repeat(browsy_server::MAX_PARSE_HTML_BYTES + 1);
+    let res = server
+        .post("/api/parse")
+        .json(&json!({ "html": html }))
+        .await;
+    res.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+    assert!(res.headers().get("x-browsy-session").is_none());
+}
+
+#[tokio::test]
+async fn parse_missing_html_returns_422() {
+    let server = test_server();
+    let res = server.post("/api/parse").json(&json!({})).await;
+    res.assert_status(StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+// ---------------------------------------------------------------------------
+// Metrics
+// ---------------------------------------------------------------------------
+
+/// Value of the sample whose name and labels are exactly `series`.
+fn sample(metrics: &str, series: &str) -> Option<f64> {
+    metrics
+        .lines()
+        .find_map(|line| line.strip_prefix(series)?.strip_prefix(' ')?.parse().ok())
+}
+
+#[tokio::test]
+async fn metrics_count_requests_errors_and_sessions() {
+    let server = test_server();
+    let session = http::header::HeaderName::from_static("x-browsy-session");
+    let mut token = None;
+    for _ in 0..2 {
+        let res = server
+            .post("/api/parse")
+            .json(&json!({ "html": SIGNUP_HTML, "url": "https://private.example/account/42" }))
+            .await;
+        res.assert_status_ok();
+        token = Some(session_header(&res));
+    }
+    // An unknown element on a loaded page
+    server
+        .post("/api/click")
+        .add_header(session, token.unwrap())
+        .json(&json!({ "id": 999 }))
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+
+    let res = server.get("/metrics").await;
+    res.assert_status_ok();
+    let text = res.text();
+    assert_eq!(sample(&text, r#"browsy_http_requests_total{route="/api/parse"}"#), Some(2.0), "{text}");
+    assert_eq!(sample(&text, r#"browsy_http_requests_total{route="/api/click"}"#), Some(1.0), "{text}");
+    assert_eq!(sample(&text, r#"browsy_fetch_errors_total{variant="element_not_found"}"#), Some(1.0), "{text}");
+    assert_eq!(sample(&text, "browsy_page_load_duration_seconds_count"), Some(2.0), "{text}");
+    assert_eq!(sample(&text, "browsy_session_call_duration_seconds_count"), Some(3.0), "{text}");
+    assert_eq!(sample(&text, "browsy_active_sessions"), Some(2.0), "{text}");
+    assert!(!text.contains("private.example"), "URLs must never become labels");
+}
+
+#[tokio::test]
+async fn metrics_can_be_disabled() {
+    let config = ServerConfig {
+        enable_metrics: false,
+        ..Default::default()
+    };
+    let server = TestServer::new(build_router(Arc::new(AppState::new(config)))).unwrap();
+    server.get("/metrics").await.assert_status(StatusCode::NOT_FOUND);
+}
+
+// ---------------------------------------------------------------------------
+// Authentication and rate limiting
+// ---------------------------------------------------------------------------
+
+fn test_server_with_keys(rate_limit_per_minute: Option<u32>) -> TestServer {
+    let config = ServerConfig {
+        api_keys: vec!["key-one".to_string(), "key-two".to_string()],
+        rate_limit_per_minute,
+        ..Default::default()
+    };
+    TestServer::new(build_router(Arc::new(AppState::new(config)))).unwrap()
+}
+
+fn api_key(value: &'static str) -> (http::header::HeaderName, http::header::HeaderValue) {
+    (
+        http::header::HeaderName::from_static("x-api-key"),
+        http::header::HeaderValue::from_static(value),
+    )
+}
+
+#[tokio::test]
+async fn missing_api_key_returns_401() {
+    let server = test_server_with_keys(None);
+    let res = server.post("/api/parse").json(&json!({ "html": SIGNUP_HTML })).await;
+    res.assert_status(StatusCode::UNAUTHORIZED);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["error"]["code"], "unauthorized");
+    assert_eq!(body["error"]["message"], "Missing API key");
+    assert!(res.headers().get("x-browsy-session").is_none(), "no session before auth");
+
+    let res = server.post("/a2a/tasks").json(&json!({ "goal": "anything" })).await;
+    res.assert_status(StatusCode::UNAUTHORIZED);
+
+    // Liveness probes and the agent card stay public
+    server.get("/health").await.assert_status_ok();
+    server.get("/.well-known/agent.json").await.assert_status_ok();
+}
+
+#[tokio::test]
+async fn wrong_api_key_returns_401() {
+    let server = test_server_with_keys(None);
+    let (name, value) = api_key("key-three");
+    let res = server
+        .post("/api/parse")
+        .add_header(name, value)
+        .json(&json!({ "html": SIGNUP_HTML }))
+        .await;
+    res.assert_status(StatusCode::UNAUTHORIZED);
+    let body: serde_json::Value = res.json();
+    assert_eq!(body["error"]["message"], "Invalid API key");
+}
+
+#[tokio::test]
+async fn valid_api_key_in_either_header() {
+    let server = test_server_with_keys(None);
+    let (name, value) = api_key("key-one");
+    server
+        .post("/api/parse")
+        .add_header(name, value)
+        .json(&json!({ "html": SIGNUP_HTML }))
+        .await
+        .assert_status_ok();
+
+    server
+        .post("/api/parse")
+        .add_header(
+            http::header::AUTHORIZATION,
+            http::header::HeaderValue::from_static("Bearer key-two"),
+        )
+        .json(&json!({ "html": SIGNUP_HTML }))
+        .await
+        .assert_status_ok();
+}
+
+#[tokio::test]
+async fn exceeding_rate_limit_returns_429() {
+    let server = test_server_with_keys(Some(2));
+    for _ in 0..2 {
+        let (name, value) = api_key("key-one");
+        server
+            .post("/api/parse")
+            .add_header(name, value)
+            .json(&json!({ "html": SIGNUP_HTML }))
+            .await
+            .assert_status_ok();
+    }
+
+    let (name, value) = api_key("key-one");
+    let res = server
+        .post("/api/parse")
+        .add_header(name, value)
+        .json(&json!({ "html": SIGNUP_HTML }))
+        .await;
+    res.assert_status(StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = res
+        .headers()
+        .get("retry-after")
+        .expect("should say when to retry")
+        .to_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!((1..=30).contains(&retry_after), "{retry_after}");
+
+    // Limits are per key
+    let (name, value) = api_key("key-two");
+    server
+        .post("/api/parse")
+        .add_header(name, value)
+        .json(&json!({ "html": SIGNUP_HTML }))
+        .await
+        .assert_status_ok();
+}
+
+#[tokio::test]
+async fn parse_renders_an_ascii_map() {
+    let server = test_server();
+    let res = server
+        .post("/api/parse")
+        .json(&json!({
+            "html": "<html><body><h1>Sign up</h1><a href=\"/login\">Log in</a></body></html>",
+            "format": "map",
+        }))
+        .await;
+    res.assert_status_ok();
+    let text = res.text();
+    let (header, map) = text.split_once("---\n").unwrap();
+    assert!(header.contains("els: "), "{text}");
+    assert!(map.contains('.') && map.contains('#'), "{text}");
+    assert!(map.contains(":a \"Log in\"]"), "{text}");
+}
+
+/// Serve `/login`, which sets a `sid` cookie, and `/`, which says whether the
+/// request carried it. Returns the port, so the site can be reached as both
+/// `localhost` and `127.0.0.1`.
+async fn login_site() -> u16 {
+    use axum::http::{header, HeaderMap};
+
+    let app = axum::Router::new()
+        .route(
+            "/login",
+            axum::routing::get(|| async { ([(header::SET_COOKIE, "sid=ada; Path=/")], axum::response::Html("<p>Welcome</p>")) }),
+        )
+        .route(
+            "/",
+            axum::routing::get(|headers: HeaderMap| async move {
+                let signed_in = common::has_cookie(&headers, "sid=ada");
+                axum::response::Html(if signed_in { "<p>Signed in as Ada</p>" } else { "<p>Not signed in</p>" })
+            }),
+        );
+    common::serve(app).await.port()
+}
+
+/// Browse to `url` in the session `token` (a new one when `None`) and
+/// return the page text and the session's token.
+async fn browse_text(server: &TestServer, token: Option<&http::HeaderValue>, url: String) -> (String, http::HeaderValue) {
+    let mut request = server.post("/api/browse").json(&json!({ "url": url }));
+    if let Some(token) = token {
+        request = request.add_header(http::header::HeaderName::from_static("x-browsy-session"), token.clone());
+    }
+    let res = request.await;
+    res.assert_status_ok();
+    (res.text(), session_header(&res))
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn shared_cookie_domains_share_only_those_cookies() {
+    let port = login_site().await;
+    let config = ServerConfig {
+        allow_private_network: true,
+        shared_cookie_domains: vec!["localhost".to_string()],
+        ..Default::default()
+    };
+    let server = TestServer::new(build_router(Arc::new(AppState::new(config)))).unwrap();
+
+    let (_, a) = browse_text(&server, None, format!("http://localhost:{port}/login")).await;
+    browse_text(&server, Some(&a), format!("http://127.0.0.1:{port}/login")).await;
+
+    let (text, b) = browse_text(&server, None, format!("http://localhost:{port}/")).await;
+    assert_ne!(a, b);
+    assert!(text.contains("Signed in as Ada"), "shared domain cookie should reach session B: {text}");
+    let (text, _) = browse_text(&server, Some(&b), format!("http://127.0.0.1:{port}/")).await;
+    assert!(text.contains("Not signed in"), "session A's unshared cookie leaked to B: {text}");
+    let (text, _) = browse_text(&server, Some(&a), format!("http://127.0.0.1:{port}/")).await;
+    assert!(text.contains("Signed in as Ada"), "{text}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn exported_cookies_import_into_another_session() {
+    let port = login_site().await;
+    let server = test_server();
+    let session = http::header::HeaderName::from_static("x-browsy-session");
+
+    let (_, a) = browse_text(&server, None, format!("http://127.0.0.1:{port}/login")).await;
+    let res = server.post("/api/cookies/export").add_header(session.clone(), a).await;
+    res.assert_status_ok();
+    let exported: serde_json::Value = res.json();
+    assert_eq!(exported["cookies"].as_array().unwrap().len(), 1);
+
+    let (text, b) = browse_text(&server, None, format!("http://127.0.0.1:{port}/")).await;
+    assert!(text.contains("Not signed in"), "{text}");
+    let res = server
+        .post("/api/cookies/import")
+        .add_header(session.clone(), b.clone())
+        .json(&exported)
+        .await;
+    res.assert_status_ok();
+    assert_eq!(res.json::<serde_json::Value>()["imported"], 1);
+    let (text, _) = browse_text(&server, Some(&b), format!("http://127.0.0.1:{port}/")).await;
+    assert!(text.contains("Signed in as Ada"), "{text}");
+
+    let res = server
+        .post("/api/cookies/import")
+        .add_header(session, b)
+        .json(&json!({ "cookies": [{ "name": "sid" }] }))
+        .await;
+    res.assert_status(StatusCode::BAD_REQUEST);
+    assert_eq!(res.json::<serde_json::Value>()["error"]["code"], "action_failed");
+}
+
+#[tokio::test]
+async fn preview_describes_actions_without_taking_them() {
+    let server = test_server();
+    let session = http::header::HeaderName::from_static("x-browsy-session");
+
+    let res = server
+        .post("/api/parse")
+        .json(&json!({ "html": SIGNUP_HTML, "url": "https://shop.example/signup", "format": "json" }))
+        .await;
+    res.assert_status_ok();
+    let token = session_header(&res);
+    let page: serde_json::Value = res.json();
+    let terms = element_id(&page, |el| el["text"] == "Terms of service");
+    let rules = element_id(&page, |el| el["text"] == "Password rules");
+
+    let res = server
+        .post("/api/preview")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({ "id": terms }))
+        .await;
+    res.assert_status_ok();
+    assert_eq!(res.json::<serde_json::Value>(), json!({ "kind": "navigate", "url": "https://shop.example/terms" }));
+
+    let res = server
+        .post("/api/preview")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({ "id": rules }))
+        .await;
+    res.assert_status_ok();
+    let preview: serde_json::Value = res.json();
+    assert_eq!(preview["kind"], "behavior");
+    assert_eq!(preview["action"]["ToggleVisibility"]["target"], "#help");
+
+    let res = server
+        .post("/api/preview")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({ "id": terms, "submit": true }))
+        .await;
+    res.assert_status(StatusCode::BAD_REQUEST);
+    assert_eq!(res.json::<serde_json::Value>()["error"]["code"], "action_wrong_element_type");
+
+    // Neither preview changed the page
+    let res = server.get("/api/page").add_header(session, token).add_query_param("format", "json").await;
+    res.assert_status_ok();
+    let after: serde_json::Value = res.json();
+    assert_eq!(after["url"], "https://shop.example/signup");
+    let help = after["els"].as_array().unwrap().iter().find(|el| el["text"] == "Passwords need at least 12 characters");
+    assert_eq!(help.unwrap()["hidden"], true);
+}
+
+#[tokio::test]
+async fn annotate_shows_notes_in_the_page() {
+    let server = test_server();
+    let session = http::header::HeaderName::from_static("x-browsy-session");
+
+    let res = server
+        .post("/api/parse")
+        .json(&json!({ "html": SIGNUP_HTML, "url": "https://shop.example/signup", "format": "json" }))
+        .await;
+    let token = session_header(&res);
+    let terms = element_id(&res.json::<serde_json::Value>(), |el| el["text"] == "Terms of service");
+
+    let res = server
+        .post("/api/annotate")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({ "id": terms, "note": "read before signing up" }))
+        .await;
+    res.assert_status_ok();
+
+    let res = server.get("/api/page").add_header(session.clone(), token.clone()).add_query_param("format", "json").await;
+    let page: serde_json::Value = res.json();
+    let el = page["els"].as_array().unwrap().iter().find(|el| el["id"] == terms).unwrap();
+    assert_eq!(el["note"], "read before signing up");
+    let res = server.get("/api/page").add_header(session.clone(), token.clone()).await;
+    assert!(res.text().contains(r#"#"read before signing up""#), "{}", res.text());
+
+    let res = server
+        .post("/api/annotate")
+        .add_header(session, token)
+        .json(&json!({ "id": 9999, "note": "missing" }))
+        .await;
+    res.assert_status(StatusCode::BAD_REQUEST);
+    assert_eq!(res.json::<serde_json::Value>()["error"]["code"], "action_element_not_found");
+}
+
+/// A page with a "Delete account" form, and the page it posts to.
+async fn account_site() -> String {
+    let app = axum::Router::new()
+        .route(
+            "/",
+            axum::routing::get(|| async {
+                axum::response::Html(
+                    r#"<form action="/delete" method="post"><button type="submit">Delete account</button></form>"#,
+                )
+            }),
+        )
+        .route("/delete", axum::routing::post(|| async { axum::response::Html("<p>Account deleted</p>") }));
+    format!("http://{}/", common::serve(app).await)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn held_action_returns_409_until_confirmed() {
+    let url = account_site().await;
+    let config = ServerConfig {
+        allow_private_network: true,
+        action_policy: Some(ActionPolicy::default()),
+        ..Default::default()
+    };
+    let server = TestServer::new(build_router(Arc::new(AppState::new(config)))).unwrap();
+    let session = http::header::HeaderName::from_static("x-browsy-session");
+
+    let res = server.post("/api/browse").json(&json!({ "url": url, "format": "json" })).await;
+    res.assert_status_ok();
+    let token = session_header(&res);
+    let delete = element_id(&res.json(), |el| el["text"] == "Delete account");
+
+    let res = server
+        .post("/api/click")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({ "id": delete }))
+        .await;
+    res.assert_status(StatusCode::CONFLICT);
+    let error: serde_json::Value = res.json();
+    assert_eq!(error["error"]["code"], "confirmation_required");
+    assert!(error["error"]["details"]["description"].as_str().unwrap().contains("Delete account"));
+    let confirmation = error["error"]["details"]["token"].as_str().unwrap().to_string();
+
+    let res = server
+        .post("/api/confirm")
+        .add_header(session.clone(), token.clone())
+        .json(&json!({ "token": confirmation }))
+        .await;
+    res.assert_status_ok();
+    assert!(res.text().contains("Account deleted"), "{}", res.text());
+
+    // Pre-authorized with `confirmed`
+    server.post("/api/browse").add_header(session.clone(), token.clone()).json(&json!({ "url": url })).await;
+    let res = server
+        .post("/api/click")
+        .add_header(session, token)
+        .json(&json!({ "id": delete, "confirmed": true }))
+        .await;
+    res.assert_status_ok();
+    assert!(res.text().contains("Account deleted"), "{}", res.text());
+}
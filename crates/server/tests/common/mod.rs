@@ -0,0 +1,24 @@
+//! A local server for the tests that fetch pages from a site of their own.
+
+// Each test crate uses a different part of this
+#![allow(dead_code)]
+
+use std::net::SocketAddr;
+
+/// Serve `app` on a random local port and return its address.
+pub async fn serve(app: axum::Router) -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    addr
+}
+
+/// Whether the request's `headers` carried `cookie`, such as `"sid=ada"`.
+pub fn has_cookie(headers: &axum::http::HeaderMap, cookie: &str) -> bool {
+    headers
+        .get(axum::http::header::COOKIE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(';').any(|c| c.trim() == cookie))
+}
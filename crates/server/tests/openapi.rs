@@ -0,0 +1,100 @@
+//! Integration tests for the OpenAPI description at `/openapi.json`.
+
+use std::sync::Arc;
+
+use axum_test::TestServer;
+use browsy_server::{AppState, ServerConfig, build_router};
+use serde_json::Value;
+
+fn test_server(api_keys: Vec<String>) -> TestServer {
+    let config = ServerConfig {
+        api_keys,
+        ..Default::default()
+    };
+    let state = Arc::new(AppState::new(config));
+    TestServer::new(build_router(state)).unwrap()
+}
+
+/// Follow a local `$ref` such as `#/components/parameters/Session`.
+fn resolve<'a>(spec: &'a Value, value: &'a Value) -> &'a Value {
+    match value["$ref"].as_str() {
+        Some(target) => target
+            .trim_start_matches("#/")
+            .split('/')
+            .fold(spec, |node, key| &node[key]),
+        None => value,
+    }
+}
+
+#[tokio::test]
+async fn spec_describes_every_registered_route() {
+    let server = test_server(Vec::new());
+    let response = server.get("/openapi.json").await;
+    response.assert_status_ok();
+    let spec: Value = response.json();
+    assert_eq!(spec, browsy_server::openapi());
+    assert_eq!(spec["openapi"], "3.1.0");
+
+    for capability in browsy_server::capabilities() {
+        let operation = &spec["paths"][capability.path][capability.method.to_ascii_lowercase()];
+        assert!(operation.is_object(), "{} {} is missing", capability.method, capability.path);
+        assert_eq!(operation["operationId"], capability.name);
+        if !capability.path.starts_with("/api/") {
+            continue;
+        }
+
+        assert!(resolve(&spec, &operation["responses"]["400"])["content"]["application/json"].is_object());
+        let parameters = operation["parameters"].as_array().unwrap();
+        assert!(
+            parameters.iter().any(|p| resolve(&spec, p)["name"] == "X-Browsy-Session"),
+            "{} {} lacks the session header",
+            capability.method,
+            capability.path
+        );
+        assert!(
+            resolve(&spec, &operation["responses"]["200"]["headers"]["X-Browsy-Session"]).is_object(),
+            "{} {} lacks the session response header",
+            capability.method,
+            capability.path
+        );
+    }
+}
+
+#[tokio::test]
+async fn spec_lists_bodies_query_parameters_and_enums() {
+    let spec = browsy_server::openapi();
+    let browse = &spec["paths"]["/api/browse"];
+
+    let body = &browse["post"]["requestBody"]["content"]["application/json"]["schema"];
+    assert_eq!(body["required"], serde_json::json!(["url"]));
+    assert_eq!(body["properties"]["format"]["enum"], serde_json::json!(["compact", "json", "map"]));
+    assert!(body["properties"]["scope"]["enum"].as_array().unwrap().contains(&"above_fold".into()));
+
+    let query = browse["get"]["parameters"].as_array().unwrap();
+    let url = query.iter().find(|p| p["name"] == "url").unwrap();
+    assert_eq!((url["in"].as_str(), url["required"].as_bool()), (Some("query"), Some(true)));
+    let scope = query.iter().find(|p| p["name"] == "scope").unwrap();
+    assert_eq!(scope["required"], false);
+    assert_eq!(scope["schema"]["enum"][0], "all");
+
+    let fork = spec["paths"]["/api/sessions/{token}/fork"]["post"]["parameters"].as_array().unwrap();
+    let token = fork.iter().find(|p| p["name"] == "token").unwrap();
+    assert_eq!((token["in"].as_str(), token["required"].as_bool()), (Some("path"), Some(true)));
+
+    let error = &spec["components"]["schemas"]["ErrorResponse"];
+    assert_eq!(error["properties"]["error"]["$ref"], "#/components/schemas/ErrorBody");
+    let error_body = &spec["components"]["schemas"]["ErrorBody"];
+    assert_eq!(error_body["properties"]["code"]["type"], "string");
+    assert!(error_body["required"].as_array().unwrap().contains(&"message".into()));
+}
+
+#[tokio::test]
+async fn spec_is_public_when_api_keys_are_required() {
+    let server = test_server(vec!["secret".to_string()]);
+    server.get("/openapi.json").await.assert_status_ok();
+    server.get("/api/page").await.assert_status(http::StatusCode::UNAUTHORIZED);
+
+    let spec = browsy_server::openapi();
+    assert_eq!(spec["paths"]["/openapi.json"]["get"]["security"], serde_json::json!([]));
+    assert!(spec["paths"]["/api/page"]["get"].get("security").is_none());
+}
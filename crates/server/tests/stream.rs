@@ -0,0 +1,181 @@
+//! Integration tests for `POST /api/browse/stream`, over real connections so
+//! a client can read part of the stream and hang up.
+
+mod common;
+
+use std::sync::Arc;
+
+use browsy_server::{AppState, ServerConfig, build_router};
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// Paragraphs on the big page, one element each.
+const ITEMS: usize = 5000;
+
+/// A site whose `/big` page has a heading and `ITEMS` paragraphs.
+async fn big_site() -> String {
+    let items: String = (0..ITEMS).map(|i| format!("<p>Item {i}</p>")).collect();
+    let page = format!("<html><head><title>Big</title></head><body><h1>Inventory</h1>{items}</body></html>");
+    let app = axum::Router::new().route(
+        "/big",
+        axum::routing::get(move || {
+            let page = page.clone();
+            async move { axum::response::Html(page) }
+        }),
+    );
+    format!("http://{}/big", common::serve(app).await)
+}
+
+async fn browsy() -> String {
+    let config = ServerConfig { allow_private_network: true, ..Default::default() };
+    common::serve(build_router(Arc::new(AppState::new(config)))).await.to_string()
+}
+
+/// Send a request over a new connection and return it, ready to read.
+async fn request(addr: &str, method: &str, path: &str, session: Option<&str>, body: Option<Value>) -> TcpStream {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let body = body.map(|b| b.to_string()).unwrap_or_default();
+    let session = session.map(|s| format!("X-Browsy-Session: {s}\r\n")).unwrap_or_default();
+    let head = format!(
+        "{method} {path} HTTP/1.1\r\nHost: {addr}\r\n{session}Content-Type: application/json\r\n\
+         Content-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await.unwrap();
+    stream.write_all(body.as_bytes()).await.unwrap();
+    stream
+}
+
+/// Read into `text` until `done` holds or the server closes the connection.
+async fn read_until(stream: &mut TcpStream, text: &mut String, done: impl Fn(&str) -> bool) {
+    let mut buf = [0u8; 16 * 1024];
+    while !done(text) {
+        let n = stream.read(&mut buf).await.unwrap();
+        if n == 0 {
+            break;
+        }
+        text.push_str(&String::from_utf8_lossy(&buf[..n]));
+    }
+}
+
+fn header<'a>(response: &'a str, name: &str) -> Option<&'a str> {
+    let head = response.split("\r\n\r\n").next()?;
+    head.lines()
+        .find_map(|line| line.split_once(':').filter(|(n, _)| n.eq_ignore_ascii_case(name)))
+        .map(|(_, value)| value.trim())
+}
+
+/// The `(event, data)` pairs of an SSE body, with multi-line data joined.
+/// Chunk sizes between the events are skipped.
+fn parse_events(response: &str) -> Vec<(String, String)> {
+    let body = response.split_once("\r\n\r\n").map(|(_, body)| body).unwrap_or_default();
+    let mut events = Vec::new();
+    let mut event: Option<(String, Vec<&str>)> = None;
+    for line in body.lines().map(|l| l.trim_end_matches('\r')) {
+        if let Some(name) = line.strip_prefix("event: ") {
+            event = Some((name.to_string(), Vec::new()));
+        } else if let (Some(data), Some((_, lines))) = (line.strip_prefix("data: "), event.as_mut()) {
+            lines.push(data);
+        } else if line.is_empty() {
+            if let Some((name, lines)) = event.take() {
+                events.push((name, lines.join("\n")));
+            }
+        }
+    }
+    events
+}
+
+fn count_events(response: &str, name: &str) -> usize {
+    response.matches(&format!("event: {name}\n")).count() + response.matches(&format!("event: {name}\r\n")).count()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn stream_sends_meta_batches_and_done() {
+    let site = big_site().await;
+    let addr = browsy().await;
+
+    let body = json!({ "url": site, "batch_size": 1000 });
+    let mut stream = request(&addr, "POST", "/api/browse/stream", None, Some(body)).await;
+    let mut response = String::new();
+    read_until(&mut stream, &mut response, |_| false).await;
+
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", &response[..200.min(response.len())]);
+    assert!(header(&response, "content-type").unwrap().starts_with("text/event-stream"));
+    assert!(header(&response, "x-browsy-session").is_some());
+
+    let events = parse_events(&response);
+    let (first, meta) = &events[0];
+    assert_eq!(first, "meta");
+    let meta: Value = serde_json::from_str(meta).unwrap();
+    assert_eq!(meta["title"], "Big");
+    let total = meta["els"].as_u64().unwrap() as usize;
+    assert!(total > ITEMS, "{total}");
+
+    let batches: Vec<&String> = events.iter().filter(|(name, _)| name == "elements").map(|(_, d)| d).collect();
+    assert_eq!(batches.len(), total.div_ceil(1000));
+    assert!(batches.iter().all(|b| b.lines().count() <= 1000));
+    let lines: Vec<&str> = batches.iter().flat_map(|b| b.lines()).collect();
+    assert_eq!(lines.len(), total);
+    assert!(lines[0].contains("\"Inventory\""), "{}", lines[0]);
+    assert!(lines.last().unwrap().contains(&format!("\"Item {}\"", ITEMS - 1)));
+
+    let (last, done) = events.last().unwrap();
+    assert_eq!(last, "done");
+    let done: Value = serde_json::from_str(done).unwrap();
+    assert_eq!(done["elements"], total);
+    assert_eq!(done["batches"], batches.len());
+
+    // JSON streams one element per line
+    let body = json!({ "url": site, "format": "json", "batch_size": 2000 });
+    let mut stream = request(&addr, "POST", "/api/browse/stream", None, Some(body)).await;
+    let mut response = String::new();
+    read_until(&mut stream, &mut response, |_| false).await;
+    let events = parse_events(&response);
+    let first = events.iter().find(|(name, _)| name == "elements").unwrap();
+    let el: Value = serde_json::from_str(first.1.lines().next().unwrap()).unwrap();
+    assert_eq!(el["tag"], "h1");
+    assert_eq!(count_events(&response, "elements"), total.div_ceil(2000));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn client_can_hang_up_mid_stream() {
+    let site = big_site().await;
+    let addr = browsy().await;
+
+    let body = json!({ "url": site, "batch_size": 100 });
+    let mut stream = request(&addr, "POST", "/api/browse/stream", None, Some(body)).await;
+    let mut response = String::new();
+    read_until(&mut stream, &mut response, |text| count_events(text, "elements") >= 2).await;
+    assert_eq!(count_events(&response, "done"), 0, "the stream should still be going");
+    let session = header(&response, "x-browsy-session").unwrap().to_string();
+    drop(stream);
+
+    // The session still answers, with the page it loaded
+    let mut stream = request(&addr, "GET", "/api/page", Some(&session), None).await;
+    let mut page = String::new();
+    read_until(&mut stream, &mut page, |_| false).await;
+    assert!(page.starts_with("HTTP/1.1 200"), "{page}");
+    assert!(page.contains("title: Big"), "{page}");
+
+    let mut stream = request(&addr, "POST", "/api/browse/stream", Some(&session), Some(json!({ "url": site }))).await;
+    let mut response = String::new();
+    read_until(&mut stream, &mut response, |_| false).await;
+    assert_eq!(header(&response, "x-browsy-session"), Some(session.as_str()));
+    assert_eq!(count_events(&response, "done"), 1);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn stream_rejects_map_and_empty_batches() {
+    let addr = browsy().await;
+    for body in [
+        json!({ "url": "https://example.com", "format": "map" }),
+        json!({ "url": "https://example.com", "batch_size": 0 }),
+    ] {
+        let mut stream = request(&addr, "POST", "/api/browse/stream", None, Some(body.clone())).await;
+        let mut response = String::new();
+        read_until(&mut stream, &mut response, |_| false).await;
+        assert!(response.starts_with("HTTP/1.1 400"), "{body}: {response}");
+        assert!(response.contains("invalid_request"), "{response}");
+    }
+}
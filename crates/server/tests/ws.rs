@@ -0,0 +1,133 @@
+//! Integration tests for the WebSocket endpoint at `/api/ws`.
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use browsy_server::{AppState, ServerConfig, build_router};
+use futures::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+const HOME: &str = r#"<html><head><title>News</title></head><body>
+    <div id="cookie-banner">
+        <p>We use cookies to improve your experience and to measure traffic.</p>
+        <button onclick="document.getElementById('cookie-banner').style.display='none'">Accept all</button>
+        <button onclick="document.getElementById('cookie-banner').style.display='none'">Reject all</button>
+    </div>
+    <h1>Today's headlines</h1>
+    <a href="/story">Read the story</a>
+</body></html>"#;
+
+const STORY: &str = "<html><head><title>Story</title></head><body><h1>The whole story</h1></body></html>";
+
+/// A site with a cookie banner on its home page linking to a story.
+async fn news_site() -> String {
+    let app = axum::Router::new()
+        .route("/", axum::routing::get(|| async { axum::response::Html(HOME) }))
+        .route("/story", axum::routing::get(|| async { axum::response::Html(STORY) }));
+    format!("http://{}/", common::serve(app).await)
+}
+
+async fn browsy(session_timeout: Duration) -> String {
+    let config = ServerConfig {
+        allow_private_network: true,
+        session_timeout,
+        ..Default::default()
+    };
+    common::serve(build_router(Arc::new(AppState::new(config)))).await.to_string()
+}
+
+async fn connect(addr: &str) -> Socket {
+    let (socket, response) = tokio_tungstenite::connect_async(format!("ws://{addr}/api/ws")).await.unwrap();
+    assert!(response.headers().get("x-browsy-session").is_some());
+    socket
+}
+
+/// The next message, as JSON; `None` once the server closes the socket.
+async fn next(socket: &mut Socket) -> Option<Value> {
+    loop {
+        let message = tokio::time::timeout(Duration::from_secs(30), socket.next())
+            .await
+            .expect("no message from the server")?
+            .unwrap();
+        match message {
+            Message::Text(text) => return Some(serde_json::from_str(text.as_str()).unwrap()),
+            Message::Close(_) => return None,
+            _ => {}
+        }
+    }
+}
+
+async fn command(socket: &mut Socket, command: Value) {
+    socket.send(Message::Text(command.to_string().into())).await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn browse_click_and_page_over_one_session() {
+    let site = news_site().await;
+    let addr = browsy(Duration::from_secs(600)).await;
+    let mut socket = connect(&addr).await;
+
+    let hello = next(&mut socket).await.unwrap();
+    assert_eq!(hello["type"], "session");
+    assert!(hello["session"].as_str().is_some_and(|s| !s.is_empty()));
+
+    // The banner is dismissed while loading, which is pushed ahead of the response
+    command(&mut socket, json!({ "op": "browse", "url": site, "auto_consent": "accept", "req": 1 })).await;
+    let event = next(&mut socket).await.unwrap();
+    assert_eq!(event["type"], "auto_action");
+    assert!(event["action"].as_str().unwrap().contains("Accept all"), "{event}");
+    let browsed = next(&mut socket).await.unwrap();
+    assert_eq!((browsed["type"].as_str(), browsed["req"].as_i64()), (Some("response"), Some(1)));
+    assert_eq!(browsed["status"], 200);
+    assert!(browsed["page"].as_str().unwrap().contains("Today's headlines"), "{browsed}");
+
+    command(&mut socket, json!({ "op": "page", "format": "json", "req": "page" })).await;
+    let page = next(&mut socket).await.unwrap();
+    assert_eq!(page["req"], "page");
+    let dom: Value = serde_json::from_str(page["page"].as_str().unwrap()).unwrap();
+    let link = dom["els"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["text"] == "Read the story")
+        .unwrap()["id"]
+        .clone();
+
+    command(&mut socket, json!({ "op": "click", "id": link, "req": 2 })).await;
+    let clicked = next(&mut socket).await.unwrap();
+    assert_eq!((clicked["req"].as_i64(), clicked["status"].as_i64()), (Some(2), Some(200)));
+    assert!(clicked["page"].as_str().unwrap().contains("The whole story"), "{clicked}");
+
+    // Errors answer the command that caused them and leave the socket open
+    command(&mut socket, json!({ "op": "click", "id": 9999, "req": 3 })).await;
+    let failed = next(&mut socket).await.unwrap();
+    assert_eq!((failed["req"].as_i64(), failed["status"].as_i64()), (Some(3), Some(400)));
+    assert!(failed["error"].is_string());
+    command(&mut socket, json!({ "op": "fly", "req": 4 })).await;
+    let unknown = next(&mut socket).await.unwrap();
+    assert_eq!((unknown["req"].as_i64(), unknown["status"].as_i64()), (Some(4), Some(400)));
+
+    command(&mut socket, json!({ "op": "page" })).await;
+    let page = next(&mut socket).await.unwrap();
+    assert!(page.get("req").is_none());
+    assert!(page["page"].as_str().unwrap().contains("title: Story"), "{page}");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn socket_warns_then_closes_when_session_expires() {
+    let addr = browsy(Duration::from_secs(2)).await;
+    let mut socket = connect(&addr).await;
+    assert_eq!(next(&mut socket).await.unwrap()["type"], "session");
+
+    let warning = next(&mut socket).await.unwrap();
+    assert_eq!(warning["type"], "session_expiring");
+    assert!(warning["expires_in_secs"].as_u64().unwrap() <= 1, "{warning}");
+    assert!(next(&mut socket).await.is_none(), "socket should close once the session expires");
+}